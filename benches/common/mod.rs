@@ -0,0 +1,16 @@
+//! Shared input for the benchmarks.
+
+use bitalino_rs::decode::encode_frame;
+use bitalino_rs::Frame;
+
+/// `frames` back-to-back frames with consecutive sequence numbers, varying
+/// values and valid CRCs, as a device streams them.
+pub fn stream(n_channels: usize, frames: usize) -> Vec<u8> {
+    (0..frames)
+        .flat_map(|i| {
+            let analog: Vec<u16> = (0..n_channels).map(|j| (i * 31 + j * 17) as u16).collect();
+            let digital = [0, 1, 2, 3].map(|j| (i >> j) as u8 & 1);
+            encode_frame(&Frame::new((i % 16) as u8, digital, &analog))
+        })
+        .collect()
+}
@@ -0,0 +1,70 @@
+//! CRC verification and frame decoding: the per-frame decoder against the
+//! batch decoder used by the read path, on one second of frames at 1000 Hz
+//! for every channel count.
+
+use std::hint::black_box;
+
+use bitalino_rs::decode::{check_crc, decode_frame, decode_frames, frame_size};
+use bitalino_rs::MAX_ANALOG_CHANNELS;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+mod common;
+
+const FRAMES: usize = 1000;
+
+fn crc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crc");
+    group.throughput(Throughput::Elements(FRAMES as u64));
+    for n_channels in 1..=MAX_ANALOG_CHANNELS {
+        let bytes = common::stream(n_channels, FRAMES);
+        let size = frame_size(n_channels);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(n_channels),
+            &bytes,
+            |b, bytes| {
+                b.iter(|| {
+                    black_box(bytes)
+                        .chunks_exact(size)
+                        .filter(|chunk| check_crc(chunk))
+                        .count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn decoders(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(FRAMES as u64));
+    for n_channels in 1..=MAX_ANALOG_CHANNELS {
+        let bytes = common::stream(n_channels, FRAMES);
+        let size = frame_size(n_channels);
+        group.bench_with_input(
+            BenchmarkId::new("per_frame", n_channels),
+            &bytes,
+            |b, bytes| {
+                let mut out = Vec::with_capacity(FRAMES);
+                b.iter(|| {
+                    out.clear();
+                    for chunk in bytes.chunks_exact(size) {
+                        out.push((decode_frame(chunk, n_channels), check_crc(chunk)));
+                    }
+                    black_box(&out);
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("batch", n_channels), &bytes, |b, bytes| {
+            let mut out = Vec::with_capacity(FRAMES);
+            b.iter(|| {
+                out.clear();
+                decode_frames(black_box(bytes), n_channels, &mut out);
+                black_box(&out);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, crc, decoders);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+"""Time the Rust-to-Python conversion of frames for every channel count.
+
+Criterion cannot load the extension module, so this covers what the Rust
+benchmarks leave out: ``read_timed()`` turning decoded frames into ``Frame``
+objects, ``batch.frames`` building the Python list, and ``batch.arrays()``
+when NumPy is installed. Frames are replayed from a generated capture.
+
+Run against a release build:
+
+    uv run maturin develop --release
+    uv run python benches/python_frames.py
+"""
+
+from __future__ import annotations
+
+import argparse
+import tempfile
+import time
+from collections.abc import Callable
+from pathlib import Path
+
+from bitalino_rs import Bitalino
+
+FRAMES = 1000
+
+
+def frame_size(n_channels: int) -> int:
+    bits = 12 + 10 * n_channels if n_channels <= 4 else 52 + 6 * (n_channels - 4)
+    return (bits + 7) // 8
+
+
+def crc(frame: bytes) -> int:
+    """4-bit CRC of a frame with its own CRC nibble zeroed."""
+    value = 0
+    for i, byte in enumerate(frame):
+        if i == len(frame) - 1:
+            byte &= 0xF0
+        for bit in range(7, -1, -1):
+            value <<= 1
+            if value & 0x10:
+                value ^= 0x03
+            value ^= (byte >> bit) & 1
+    return value & 0x0F
+
+
+def capture(n_channels: int, frames: int) -> bytes:
+    """Back-to-back frames with consecutive sequence numbers and valid CRCs."""
+    size = frame_size(n_channels)
+    out = bytearray()
+    for i in range(frames):
+        frame = bytearray((i * 31 + j * 17) & 0xFF for j in range(size))
+        frame[-1] = (i % 16) << 4
+        frame[-1] |= crc(bytes(frame))
+        out += frame
+    return bytes(out)
+
+
+def best_of(repeat: int, setup: Callable[[], object], run: Callable[[object], object]) -> float:
+    """Fastest of ``repeat`` timed runs, in seconds; ``setup`` is not timed."""
+    best = float("inf")
+    for _ in range(repeat):
+        arg = setup()
+        started = time.perf_counter()
+        run(arg)
+        best = min(best, time.perf_counter() - started)
+    return best
+
+
+def parse_args() -> argparse.Namespace:
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("--repeat", type=int, default=50, help="Runs per measurement")
+    return parser.parse_args()
+
+
+def main() -> int:
+    args = parse_args()
+    try:
+        import numpy  # noqa: F401
+
+        has_numpy = True
+    except ImportError:
+        has_numpy = False
+
+    print(
+        f"{'channels':>8} {'read_timed':>12} {'.frames':>12} {'.arrays()':>12}"
+        "  (us per 1000 frames)"
+    )
+    with tempfile.TemporaryDirectory() as tmp:
+        for n_channels in range(1, 7):
+            path = Path(tmp) / f"{n_channels}ch.bin"
+            path.write_bytes(capture(n_channels, FRAMES))
+
+            def started(n_channels: int = n_channels, path: Path = path) -> Bitalino:
+                dev = Bitalino.replay(str(path))
+                dev.start(rate=1000, channels=list(range(n_channels)))
+                return dev
+
+            def batch(n_channels: int = n_channels, path: Path = path) -> object:
+                return started(n_channels, path).read_timed(FRAMES)
+
+            read = best_of(args.repeat, started, lambda dev: dev.read_timed(FRAMES))
+            frames = best_of(args.repeat, batch, lambda b: b.frames)
+            arrays = best_of(args.repeat, batch, lambda b: b.arrays()) if has_numpy else None
+            print(
+                f"{n_channels:>8} {read * 1e6:>12.1f} {frames * 1e6:>12.1f} "
+                + (f"{arrays * 1e6:>12.1f}" if arrays is not None else f"{'-':>12}")
+            )
+    return 0
+
+
+if __name__ == "__main__":
+    raise SystemExit(main())
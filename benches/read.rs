@@ -0,0 +1,62 @@
+//! The driver's read path end to end, from transport bytes to a `FrameBatch`,
+//! and the columnar transpose behind `FrameBatch.arrays()` in Python.
+//!
+//! Frames come from a `ReplayTransport` holding one second at 1000 Hz, so the
+//! numbers cover buffering, decoding, sequence tracking and statistics but no
+//! radio. Python-side conversion is measured by `benches/python_frames.py`.
+
+use std::hint::black_box;
+
+use bitalino_rs::{Bitalino, ReplayTransport, MAX_ANALOG_CHANNELS};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+mod common;
+
+const FRAMES: usize = 1000;
+
+/// A started device replaying `bytes`.
+fn replaying(bytes: &[u8], channels: &[u8]) -> Bitalino {
+    let transport = ReplayTransport::from_bytes(bytes.to_vec());
+    let mut dev = Bitalino::from_transport(Box::new(transport));
+    dev.start(1000, channels.to_vec())
+        .expect("replay accepts start");
+    dev
+}
+
+fn read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read");
+    group.throughput(Throughput::Elements(FRAMES as u64));
+    for n_channels in 1..=MAX_ANALOG_CHANNELS {
+        let bytes = common::stream(n_channels, FRAMES);
+        let channels: Vec<u8> = (0..n_channels as u8).collect();
+        group.bench_function(BenchmarkId::from_parameter(n_channels), |b| {
+            b.iter_batched_ref(
+                || replaying(&bytes, &channels),
+                |dev| black_box(dev.read_frames_timed(FRAMES).expect("whole capture")),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn columns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("to_columns");
+    group.throughput(Throughput::Elements(FRAMES as u64));
+    for n_channels in 1..=MAX_ANALOG_CHANNELS {
+        let bytes = common::stream(n_channels, FRAMES);
+        let channels: Vec<u8> = (0..n_channels as u8).collect();
+        let batch = replaying(&bytes, &channels)
+            .read_frames_timed(FRAMES)
+            .expect("whole capture");
+        group.bench_with_input(
+            BenchmarkId::from_parameter(n_channels),
+            &batch,
+            |b, batch| b.iter(|| black_box(batch.to_columns())),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read, columns);
+criterion_main!(benches);
@@ -9,9 +9,11 @@ The Python package is a thin, well-typed façade over the Rust core compiled as
     stateful operations you call from Python.
 * ``models``: Immutable data carriers (frames, batches, device state) and the
     allowed sampling-rate literals used across the API surface.
-* ``logging``: Opt-in helpers that bridge Rust logs into Python's ``logging``
-    ecosystem so you can watch the driver internals during debugging or capture
-    them alongside your application logs.
+* ``exceptions``: ``BitalinoError`` and its subclasses, so callers can write
+    targeted ``except`` blocks (timeouts, CRC problems, Bluetooth failures).
+* ``logging``: Control of the bridge carrying Rust logs into Python's
+    ``logging`` ecosystem (installed on import), so you can watch the driver
+    internals during debugging or capture them alongside your application logs.
 
 What happens under the hood
 ---------------------------
@@ -28,33 +30,75 @@ The Rust layer (pyo3/abi3) owns the heavy lifting:
 
 Typical usage
 -------------
->>> from bitalino_rs import Bitalino, enable_rust_logs
->>> enable_rust_logs("info")  # bridge Rust logs into Python's logging
+>>> from bitalino_rs import Bitalino, set_log_level
+>>> set_log_level("info")  # Rust records forwarded to Python's logging
 >>> dev = Bitalino.connect("12:D3:51:FE:6F:A3")
 >>> dev.start(rate=1000, channels=[0, 1, 2])
 >>> frames = dev.read(100)
 >>> dev.stop()
 """
 
-from bitalino_rs.device import Bitalino
-from bitalino_rs.logging import enable_rust_logs, reset_log_cache
+from bitalino_rs.device import AsyncBitalino, Bitalino, FrameIterator, PwmSchedule
+from bitalino_rs.exceptions import (
+    BitalinoError,
+    BluetoothError,
+    CrcError,
+    NotInAcquisitionError,
+    PairingError,
+    TimeoutError,
+)
+from bitalino_rs.logging import (
+    enable_rust_logs,
+    init_logging,
+    reset_log_cache,
+    reset_logging_cache,
+    set_log_level,
+)
 from bitalino_rs.models import (
     DEFAULT_SAMPLING_RATE,
     VALID_SAMPLING_RATES,
+    AcquisitionStats,
+    Annotation,
+    ChannelStats,
+    DeviceInfo,
     DeviceState,
+    Filter,
     Frame,
     FrameBatch,
+    OutputEvent,
     SamplingRate,
+    SerialPort,
+    Session,
 )
 
 __all__ = [
     "DEFAULT_SAMPLING_RATE",
     "VALID_SAMPLING_RATES",
+    "AcquisitionStats",
+    "Annotation",
+    "AsyncBitalino",
     "Bitalino",
+    "BitalinoError",
+    "BluetoothError",
+    "ChannelStats",
+    "CrcError",
+    "DeviceInfo",
     "DeviceState",
+    "Filter",
     "Frame",
     "FrameBatch",
+    "FrameIterator",
+    "NotInAcquisitionError",
+    "OutputEvent",
+    "PairingError",
+    "PwmSchedule",
     "SamplingRate",
+    "SerialPort",
+    "Session",
+    "TimeoutError",
     "enable_rust_logs",
+    "init_logging",
     "reset_log_cache",
+    "reset_logging_cache",
+    "set_log_level",
 ]
@@ -1,22 +1,64 @@
-from .device import Bitalino
-from .logging import enable_rust_logs, reset_log_cache
+from .device import AsyncBitalino, Bitalino, FrameIterator, PwmSchedule
+from .exceptions import (
+    BitalinoError,
+    BluetoothError,
+    CrcError,
+    NotInAcquisitionError,
+    PairingError,
+    TimeoutError,
+)
+from .logging import (
+    enable_rust_logs,
+    init_logging,
+    reset_log_cache,
+    reset_logging_cache,
+    set_log_level,
+)
 from .models import (
     DEFAULT_SAMPLING_RATE,
     VALID_SAMPLING_RATES,
+    AcquisitionStats,
+    Annotation,
+    ChannelStats,
+    DeviceInfo,
     DeviceState,
+    Filter,
     Frame,
     FrameBatch,
+    OutputEvent,
     SamplingRate,
+    SerialPort,
+    Session,
 )
 
 __all__ = [
     "DEFAULT_SAMPLING_RATE",
     "VALID_SAMPLING_RATES",
+    "AcquisitionStats",
+    "Annotation",
+    "AsyncBitalino",
     "Bitalino",
+    "BitalinoError",
+    "BluetoothError",
+    "ChannelStats",
+    "CrcError",
+    "DeviceInfo",
     "DeviceState",
+    "Filter",
     "Frame",
     "FrameBatch",
+    "FrameIterator",
+    "NotInAcquisitionError",
+    "OutputEvent",
+    "PairingError",
+    "PwmSchedule",
     "SamplingRate",
+    "SerialPort",
+    "Session",
+    "TimeoutError",
     "enable_rust_logs",
+    "init_logging",
     "reset_log_cache",
+    "reset_logging_cache",
+    "set_log_level",
 ]
@@ -1,68 +1,2242 @@
 """Typing stubs for the compiled `_bitalino_core` extension.
 
-These stubs mirror the Rust implementation shipped as ``_bitalino_core``. The
-extension is built via PyO3/abi3 and exported under ``bitalino_rs._bitalino_core``.
+Generated by build.rs from the PyO3 bindings in src/python.rs and src/python/;
+edit the Rust signatures and docstrings instead of this file.
 """
 
+from collections.abc import Awaitable, Callable, Iterator
+from datetime import datetime
+from typing import Any, Literal, overload
+
 DEFAULT_SAMPLING_RATE: int
 VALID_SAMPLING_RATES: list[int]
 
+class BitalinoError(OSError):
+    """
+    Base class for all errors raised by the BITalino driver.
+
+    Attributes:
+        kind (str): Error category, e.g. "timeout" or "bluetooth".
+        code (str): Stable error code, e.g. "io.timed_out".
+    """
+
+    kind: str
+    code: str
+
+class BluetoothError(BitalinoError):
+    """Bluetooth discovery or connection failed."""
+
+class PairingError(BluetoothError):
+    """Pairing with the device failed."""
+
+class CrcError(BitalinoError):
+    """Frames arrived but failed CRC validation."""
+
+class TimeoutError(BitalinoError):
+    """The device did not answer within the allotted time."""
+
+class NotInAcquisitionError(BitalinoError):
+    """The operation requires a running acquisition; call start() first."""
+
 class Frame:
-    sequence: int
-    digital: list[int]
-    analog: list[int]
-    def __init__(self, sequence: int, digital: list[int], analog: list[int]): ...
+    """
+    A single BITalino data frame (dataclass-like).
+
+    Attributes:
+        sequence: Frame sequence number (0-15, wrapping). Use to detect dropped frames.
+        digital (list[int]): Digital input values [I1, I2, O1, O2] as list of 0/1.
+        analog: Analog channel values for configured channels: 10-bit (0-1023),
+            except the fifth and sixth values of 5- and 6-channel frames, which
+            are 6-bit (0-63). See ``resolution_bits``.
+        sample_index: Absolute sample index since ``start()``, counting frames
+            lost to CRC errors or sequence gaps. 0 for frames built by hand.
+        filled: True for a placeholder inserted by ``read_timed(gap_fill=...)``
+            where a frame was lost.
+    """
+
+    @property
+    def sequence(self) -> int:
+        """Frame sequence number (0-15, wrapping). Use to detect dropped frames."""
+
+    @property
+    def analog(self) -> list[int]:
+        """
+        Analog channel values for configured channels: 10-bit (0-1023),
+        except the fifth and sixth values of 5- and 6-channel frames, which
+        are 6-bit (0-63). See ``resolution_bits``.
+        """
+
+    @property
+    def sample_index(self) -> int:
+        """
+        Absolute sample index since ``start()``, counting frames
+        lost to CRC errors or sequence gaps. 0 for frames built by hand.
+        """
+
+    @property
+    def filled(self) -> bool:
+        """
+        True for a placeholder inserted by ``read_timed(gap_fill=...)``
+        where a frame was lost.
+        """
+
+    def __init__(
+        self,
+        sequence: int,
+        digital: list[int],
+        analog: list[int],
+        sample_index: int = 0,
+        filled: bool = False,
+    ) -> None:
+        """
+        Raises:
+            ValueError: If ``digital`` does not hold 4 values or ``analog`` more
+                than 6.
+        """
+
+    def __reduce__(self) -> tuple[type[Frame], tuple[Any, ...]]:
+        """Pickle support: rebuild through the constructor."""
+
+    def __copy__(self) -> Frame:
+        """Frames are immutable, so copies share the original."""
+
+    def __deepcopy__(self, _memo: Any) -> Frame: ...
+
     def __repr__(self) -> str: ...
+
     def __str__(self) -> str: ...
+
+    @property
+    def digital(self) -> list[int]:
+        """Digital channel states as a Python list [I1, I2, O1, O2]."""
+
     def __hash__(self) -> int: ...
-    def to_dict(self) -> dict: ...
+
+    def to_dict(self) -> dict[str, Any]:
+        """Convert to dictionary for easy serialization."""
+
+    @property
+    def n_channels(self) -> int:
+        """Number of analog channels in this frame."""
+
     @property
-    def n_channels(self) -> int: ...
+    def resolution_bits(self) -> list[int]:
+        """
+        list[int]: ADC resolution in bits of each analog value, e.g.
+        ``[10, 10, 10, 10, 6]``.
+        """
+
+    def __eq__(self, other: object) -> bool: ...
 
 class FrameBatch:
-    frames: list[Frame]
-    timestamp_us: int
-    crc_errors: int
-    sequence_gaps: int
+    """
+    Result from reading a batch of frames, includes timing info.
+
+    Behaves as a sequence of Frame objects: ``len(batch)``, ``batch[5]``,
+    ``for frame in batch``. Slicing returns a new FrameBatch whose counters and
+    timestamps still describe the read it came from.
+
+    Attributes:
+        frames: List of Frame objects.
+        first_sample_index: ``sample_index`` of the first frame, or of the next
+            frame to be read when the batch is empty.
+        timestamp_us: Microseconds since acquisition started when batch was read.
+        crc_errors: Number of frames discarded due to CRC errors.
+        sequence_gaps: Number of detected dropped frames (from sequence discontinuities).
+        partial: True if a read timeout elapsed before all requested frames arrived.
+        rssi: RSSI of the Bluetooth link around this batch (dB relative to the
+            adapter's golden receive range), or None if unavailable.
+        link_quality: Adapter-reported link quality (0-255), or None if unavailable.
+        channels (list[int]): Acquired analog channels (0-5), in frame order.
+        sampling_rate: Acquisition rate in Hz, at which ``sample_index`` advances.
+    """
+
+    @property
+    def frames(self) -> list[Frame]:
+        """List of Frame objects."""
+
+    @property
+    def first_sample_index(self) -> int:
+        """
+        ``sample_index`` of the first frame, or of the next
+        frame to be read when the batch is empty.
+        """
+
+    @property
+    def timestamp_us(self) -> int:
+        """Microseconds since acquisition started when batch was read."""
+
+    @property
+    def crc_errors(self) -> int:
+        """Number of frames discarded due to CRC errors."""
+
+    @property
+    def sequence_gaps(self) -> int:
+        """Number of detected dropped frames (from sequence discontinuities)."""
+
+    @property
+    def partial(self) -> bool:
+        """True if a read timeout elapsed before all requested frames arrived."""
+
+    @property
+    def rssi(self) -> int | None:
+        """
+        RSSI of the Bluetooth link around this batch (dB relative to the
+        adapter's golden receive range), or None if unavailable.
+        """
+
+    @property
+    def link_quality(self) -> int | None:
+        """Adapter-reported link quality (0-255), or None if unavailable."""
+
+    @property
+    def sampling_rate(self) -> int:
+        """Acquisition rate in Hz, at which ``sample_index`` advances."""
+
+    def __init__(
+        self,
+        frames: list[Frame],
+        first_sample_index: int | None = None,
+        timestamp_us: int = 0,
+        crc_errors: int = 0,
+        sequence_gaps: int = 0,
+        partial: bool = False,
+        rssi: int | None = None,
+        link_quality: int | None = None,
+        gap_fill: str = "off",
+        channels: list[int] | None = None,
+        sampling_rate: int = 1000,
+    ) -> None:
+        """
+        Build a batch from frames, e.g. to replay or test processing code.
+
+        ``first_sample_index`` defaults to the first frame's ``sample_index``
+        and ``channels`` to the first ``n_channels`` channels.
+
+        Raises:
+            ValueError: If gap_fill is not "off", "hold" or "nan", or the
+                sampling rate is not 1, 10, 100 or 1000.
+        """
+
+    def __reduce__(self) -> tuple[type[FrameBatch], tuple[Any, ...]]:
+        """Pickle support: rebuild through the constructor."""
+
+    def __copy__(self) -> FrameBatch:
+        """Batches are immutable, so copies share the original."""
+
+    def __deepcopy__(self, _memo: Any) -> FrameBatch: ...
+
     def __repr__(self) -> str: ...
+
     def __len__(self) -> int: ...
+
+    @overload
+    def __getitem__(self, index: int) -> Frame: ...
+    @overload
+    def __getitem__(self, index: slice) -> FrameBatch: ...
+
+    def __iter__(self) -> Iterator[Frame]:
+        """
+        Returns:
+            Iterator[Frame]: The frames in order.
+        """
+
+    def analog_channel(self, index: int) -> list[int]:
+        """
+        Values of one analog channel across the batch.
+
+        Args:
+            index: Position of the channel among the acquired channels, e.g. 1
+                for A3 when acquiring ``channels=[0, 2]``.
+
+        Returns:
+            List with one value per frame.
+
+        Raises:
+            IndexError: If the frames carry fewer channels.
+        """
+
     @property
-    def has_errors(self) -> bool: ...
+    def channels(self) -> list[int]:
+        """Acquired analog channels (0-5) as a Python list, in frame order."""
+
+    @property
+    def resolution_bits(self) -> list[int]:
+        """
+        list[int]: ADC resolution in bits of each acquired channel, in frame
+        order: 6 for the fifth and sixth channels of 5- and 6-channel
+        acquisitions, else 10.
+        """
+
+    @property
+    def has_errors(self) -> bool:
+        """Check if any errors occurred during reading."""
+
+    def arrays(self) -> dict[str, Any]:
+        """
+        Get the batch as column-major NumPy arrays (requires ``numpy``).
+
+        Returns:
+            Dict with ``analog`` (uint16, shape ``(n_channels, n_frames)``, one
+            contiguous row per channel), ``digital`` (uint8, one packed byte per
+            frame: bit 0 = I1, 1 = I2, 2 = O1, 3 = O2), ``sequence`` (uint8),
+            ``sample_index`` (uint64) and ``filled`` (bool, gap-fill
+            placeholders). For batches read with
+            ``gap_fill="nan"``, ``analog`` is float64 with NaN at placeholders.
+
+        Raises:
+            ImportError: If NumPy is not installed.
+        """
+
+    def to_pandas(self, start: datetime | None = None) -> Any:
+        """
+        Get the batch as a pandas DataFrame (requires ``pandas``).
+
+        Built from the ``arrays()`` columns: one column per analog channel named
+        after the acquired channel (``A1`` to ``A6``), the digital lines ``I1``,
+        ``I2``, ``O1`` and ``O2`` as 0/1, then ``sequence`` and ``filled``. The
+        ``time`` index is each frame's ``sample_index`` over the sampling rate,
+        as a ``TimedeltaIndex`` since ``start()``.
+
+        Args:
+            start (datetime | None): Acquisition start, e.g.
+                ``dev.acquisition_start_utc()``, to
+                index by wall-clock timestamps instead.
+
+        Raises:
+            ImportError: If pandas or NumPy is not installed.
+        """
+
+    def to_mne(self, sensors: list[str] | None = None, start: datetime | None = None) -> Any:
+        """
+        Get the batch as an MNE-Python ``RawArray`` (requires ``mne``).
+
+        Analog channels are named after the acquired channel (``A1`` to
+        ``A6``) and converted with the transfer function of their sensor to
+        the SI units MNE expects: volts for ECG, EMG, EEG and EOG (channel
+        types ``ecg``, ``emg``, ``eeg``, ``eog``) and siemens for EDA
+        (``gsr``). Light and unlabelled channels become unitless ``misc``
+        channels in percent and ADC counts. The digital lines ``I1``, ``I2``,
+        ``O1`` and ``O2`` follow as ``stim`` channels, so ``mne.find_events``
+        sees trigger edges. ``first_samp`` is the first frame's
+        ``sample_index``; gap-fill placeholders of ``gap_fill="nan"`` batches
+        are NaN.
+
+        Args:
+            sensors (list[str] | None): Sensor per acquired channel (``"ECG"``,
+                ``"EDA"``, ...), as in ``Session.sensors``; all ``"RAW"`` by
+                default.
+            start (datetime | None): Acquisition start as a timezone-aware
+                ``datetime``, e.g. ``dev.acquisition_start_utc()``, stored as
+                the measurement date.
+
+        Raises:
+            ValueError: If ``sensors`` does not name one sensor per channel.
+            ImportError: If MNE or NumPy is not installed.
+        """
+
+class Annotation:
+    """
+    Event marker recorded with Bitalino.annotate().
+
+    Attributes:
+        label: Free-form marker label.
+        sample_index: Sample (counted since start()) being acquired when the
+            marker was placed.
+        timestamp_us: Host time of the marker in microseconds since start(), on
+            the same clock as FrameBatch.timestamp_us.
+    """
+
+    @property
+    def label(self) -> str:
+        """Free-form marker label."""
+
+    @property
+    def sample_index(self) -> int:
+        """
+        Sample (counted since start()) being acquired when the
+        marker was placed.
+        """
+
+    @property
+    def timestamp_us(self) -> int:
+        """
+        Host time of the marker in microseconds since start(), on
+        the same clock as FrameBatch.timestamp_us.
+        """
 
-class DeviceState:
-    analog: list[int]
-    battery: int
-    battery_threshold: int
-    digital: list[int]
     def __repr__(self) -> str: ...
-    def to_dict(self) -> dict: ...
+
+class OutputEvent:
+    """
+    Output change recorded during acquisition with Bitalino.trigger(),
+    trigger_pulse() or pwm().
+
+    Attributes:
+        kind (Literal["digital", "pwm"]): ``"digital"`` or ``"pwm"``.
+        outputs: Digital output levels [O1, O2, O3, O4] set, for ``"digital"``.
+        pwm: Duty cycle (0-255) set, for ``"pwm"``.
+        sample_index: Sample (counted since start()) being acquired when the
+            command was written.
+        timestamp_us: Host time of the write in microseconds since start(), on
+            the same clock as FrameBatch.timestamp_us.
+    """
+
     @property
-    def battery_voltage(self) -> float: ...
+    def kind(self) -> Literal["digital", "pwm"]:
+        """``"digital"`` or ``"pwm"``."""
+
     @property
-    def is_battery_low(self) -> bool: ...
+    def outputs(self) -> list[int] | None:
+        """Digital output levels [O1, O2, O3, O4] set, for ``"digital"``."""
+
+    @property
+    def pwm(self) -> int | None:
+        """Duty cycle (0-255) set, for ``"pwm"``."""
+
+    @property
+    def sample_index(self) -> int:
+        """
+        Sample (counted since start()) being acquired when the
+        command was written.
+        """
+
+    @property
+    def timestamp_us(self) -> int:
+        """
+        Host time of the write in microseconds since start(), on
+        the same clock as FrameBatch.timestamp_us.
+        """
+
+    def __repr__(self) -> str: ...
+
+class SerialPort:
+    """
+    Serial port present on the host, as listed by Bitalino.list_serial_ports().
+
+    Attributes:
+        path: Path to pass to ``Bitalino(path)``, e.g. ``"/dev/rfcomm0"``.
+        kind (Literal["usb", "bluetooth", "pci", "other"]): ``"usb"``,
+            ``"bluetooth"``, ``"pci"`` or ``"other"``.
+        vid: USB vendor ID, for USB ports.
+        pid: USB product ID, for USB ports.
+        manufacturer: USB manufacturer string, if reported.
+        product: USB product string, if reported.
+        serial_number: USB serial number, if reported.
+        is_bitalino: Whether the port likely leads to a BITalino: a Bluetooth
+            port, a name or USB descriptor mentioning BITalino or PLUX, or a
+            USB-UART bridge usually wired to its UART.
+    """
+
+    @property
+    def path(self) -> str:
+        """Path to pass to ``Bitalino(path)``, e.g. ``"/dev/rfcomm0"``."""
+
+    @property
+    def kind(self) -> Literal["usb", "bluetooth", "pci", "other"]:
+        """
+        ``"usb"``,
+        ``"bluetooth"``, ``"pci"`` or ``"other"``.
+        """
+
+    @property
+    def vid(self) -> int | None:
+        """USB vendor ID, for USB ports."""
+
+    @property
+    def pid(self) -> int | None:
+        """USB product ID, for USB ports."""
+
+    @property
+    def manufacturer(self) -> str | None:
+        """USB manufacturer string, if reported."""
+
+    @property
+    def product(self) -> str | None:
+        """USB product string, if reported."""
+
+    @property
+    def serial_number(self) -> str | None:
+        """USB serial number, if reported."""
+
+    @property
+    def is_bitalino(self) -> bool:
+        """
+        Whether the port likely leads to a BITalino: a Bluetooth
+        port, a name or USB descriptor mentioning BITalino or PLUX, or a
+        USB-UART bridge usually wired to its UART.
+        """
+
+    def __repr__(self) -> str: ...
+
+class Session:
+    """
+    One recorded acquisition, from Bitalino.begin_session() to end_session().
+
+    Attributes:
+        mac: Device MAC address (or bridge address / label on other links).
+        sampling_rate: Sampling rate in Hz.
+        channels: Acquired analog channels (0-5), in frame order.
+        sensors: Sensor name per acquired channel (``"RAW"`` when unlabelled).
+        firmware_version: Firmware string, or None if never queried.
+        is_bitalino2: Whether the device is a BITalino 2.0+.
+        connection_kind (Literal["bluetooth", "serial", "tcp", "replay", "custom"]):
+            How the device is connected.
+        started (datetime): Acquisition start as a timezone-aware UTC
+            ``datetime``.
+        ended (datetime | None): When the session ended, as a UTC ``datetime``.
+        annotations: Markers placed with annotate(), oldest first.
+        output_events: Output changes made during the acquisition.
+        stats: Totals for the acquisition as of end_session().
+    """
+
+    @property
+    def mac(self) -> str: ...
+
+    @property
+    def sampling_rate(self) -> int: ...
+
+    @property
+    def channels(self) -> list[int]: ...
+
+    @property
+    def sensors(self) -> list[str]: ...
+
+    @property
+    def firmware_version(self) -> str | None: ...
 
-class Bitalino:
-    sampling_rate: int
-    def __init__(self, path: str): ...
-    @staticmethod
-    def connect(mac: str, pin: str = "1234") -> Bitalino: ...
-    def version(self) -> str: ...
-    def start(self, rate: int = 1000, channels: list[int] | None = None) -> None: ...
-    def stop(self) -> None: ...
-    def read(self, n_frames: int = 100) -> list[Frame]: ...
-    def read_timed(self, n_frames: int = 100) -> FrameBatch: ...
-    def wait_until_streaming(self, timeout: float = 2.0) -> None: ...
-    def set_battery_threshold(self, threshold: int = 30) -> None: ...
-    def battery(self, value: int = 30) -> None: ...
-    def state(self) -> DeviceState: ...
-    def trigger(self, outputs: list[int] | None = None) -> None: ...
-    def pwm(self, value: int = 100) -> None: ...
     @property
     def is_bitalino2(self) -> bool: ...
+
+    @property
+    def connection_kind(self) -> Literal["bluetooth", "serial", "tcp", "replay", "custom"]: ...
+
+    @property
+    def started(self) -> datetime: ...
+
+    @property
+    def ended(self) -> datetime | None: ...
+
+    @property
+    def annotations(self) -> list[Annotation]: ...
+
+    @property
+    def output_events(self) -> list[OutputEvent]: ...
+
+    @property
+    def stats(self) -> AcquisitionStats | None: ...
+
+    def to_json(self) -> str:
+        """The whole session as a JSON string, as recordings append it."""
+
+    def to_mne(self, batch: FrameBatch) -> Any:
+        """
+        Convert ``batch``, read during this session, to an MNE-Python
+        ``RawArray`` (requires ``mne``).
+
+        Like ``FrameBatch.to_mne()`` with the session's sensors and start time,
+        plus the annotations placed within the batch as zero-length MNE
+        annotations.
+
+        Raises:
+            ValueError: If the batch's channels or sampling rate differ from the
+                session's.
+            ImportError: If MNE or NumPy is not installed.
+        """
+
+    def __repr__(self) -> str: ...
+
+class DeviceState:
+    """
+    Device state information (BITalino 2.0+ only).
+
+    Contains current values of all analog/digital channels and battery status.
+    Obtained by calling Bitalino.state() when not in acquisition mode.
+
+    Attributes:
+        analog: All 6 analog channel values (10-bit, 0-1023).
+        battery: Battery ADC value (10-bit, 0-1023).
+        battery_threshold: Current battery threshold setting (0-63).
+        digital (list[int]): Digital channel states [I1, I2, O1, O2].
+    """
+
+    @property
+    def analog(self) -> list[int]:
+        """All 6 analog channel values (10-bit, 0-1023)."""
+
+    @property
+    def battery(self) -> int:
+        """Battery ADC value (10-bit, 0-1023)."""
+
+    @property
+    def battery_threshold(self) -> int:
+        """Current battery threshold setting (0-63)."""
+
+    def __init__(
+        self,
+        analog: list[int],
+        battery: int,
+        battery_threshold: int,
+        digital: list[int],
+    ) -> None: ...
+
+    def __reduce__(self) -> tuple[type[DeviceState], tuple[Any, ...]]:
+        """Pickle support: rebuild through the constructor."""
+
+    def __copy__(self) -> DeviceState:
+        """States are immutable, so copies share the original."""
+
+    def __deepcopy__(self, _memo: Any) -> DeviceState: ...
+
+    def __repr__(self) -> str: ...
+
+    @property
+    def battery_voltage(self) -> float:
+        """
+        Get the approximate battery voltage.
+
+        Returns:
+            Approximate battery voltage in Volts (typically 3.2V - 4.2V).
+        """
+
+    @property
+    def is_battery_low(self) -> bool:
+        """
+        Check if battery is low based on threshold setting.
+
+        Returns:
+            True if battery voltage is below the threshold.
+        """
+
+    @property
+    def digital(self) -> list[int]:
+        """Digital channel states as a Python list [I1, I2, O1, O2]."""
+
+    def to_dict(self) -> dict[str, Any]:
+        """Convert to dictionary for easy serialization."""
+
+class DeviceInfo:
+    """
+    Identity and capabilities of a connected device.
+
+    Returned by ``Bitalino.device_info()``. Firmware fields are only known
+    once ``version()`` has succeeded; the connect helpers call it for you.
+
+    Attributes:
+        mac: Bluetooth address, or None when not connected by MAC.
+        firmware_version: Firmware string (e.g. "BITalino_v5.2"), or None.
+        is_bitalino2: Whether the device is a BITalino 2.0+.
+        supported_rates: Accepted sampling rates in Hz, slowest first.
+        n_analog_channels: Number of analog inputs.
+        connection_kind (Literal["bluetooth", "serial", "tcp", "replay", "custom"]):
+            How the device is connected.
+        was_streaming: Whether the device was already acquiring when first
+            contacted (e.g. left running by a crashed process); the handshake
+            stopped it.
+    """
+
+    @property
+    def mac(self) -> str | None:
+        """Bluetooth address, or None when not connected by MAC."""
+
+    @property
+    def firmware_version(self) -> str | None:
+        """Firmware string (e.g. "BITalino_v5.2"), or None."""
+
+    @property
+    def is_bitalino2(self) -> bool:
+        """Whether the device is a BITalino 2.0+."""
+
+    @property
+    def supported_rates(self) -> list[int]:
+        """Accepted sampling rates in Hz, slowest first."""
+
     @property
-    def elapsed_us(self) -> int | None: ...
+    def n_analog_channels(self) -> int:
+        """Number of analog inputs."""
+
+    @property
+    def connection_kind(self) -> Literal["bluetooth", "serial", "tcp", "replay", "custom"]:
+        """How the device is connected."""
+
+    @property
+    def was_streaming(self) -> bool:
+        """
+        Whether the device was already acquiring when first
+        contacted (e.g. left running by a crashed process); the handshake
+        stopped it.
+        """
+
+    def __init__(
+        self,
+        mac: str | None,
+        firmware_version: str | None,
+        is_bitalino2: bool,
+        supported_rates: list[int],
+        n_analog_channels: int,
+        connection_kind: str,
+        was_streaming: bool = False,
+    ) -> None: ...
+
+    def __reduce__(self) -> tuple[type[DeviceInfo], tuple[Any, ...]]:
+        """Pickle support: rebuild through the constructor."""
+
+    def __copy__(self) -> DeviceInfo:
+        """Device info is immutable, so copies share the original."""
+
+    def __deepcopy__(self, _memo: Any) -> DeviceInfo: ...
+
+    def __repr__(self) -> str: ...
+
+    def to_dict(self) -> dict[str, Any]:
+        """Convert to dictionary for easy serialization."""
+
+    def __eq__(self, other: object) -> bool: ...
+
+class AcquisitionStats:
+    """
+    Cumulative acquisition statistics since ``start()``.
+
+    Returned by the ``Bitalino.stats`` property. Counters include frames from
+    reads that raised, so they stay accurate when batches are lost to errors.
+
+    Attributes:
+        frames: CRC-valid frames received.
+        bytes_read: Raw bytes read from the link, including discarded frames.
+        crc_errors: Frames rejected because their CRC did not match.
+        sequence_gaps: Frames missing from the sequence counter.
+        dropped_samples: Frames the device produced that never reached the
+            caller (lost on the link or rejected by CRC).
+        elapsed: Wall-clock seconds since ``start()``.
+        throughput_hz: Valid frames per second of wall-clock time.
+        clock_skew: Wall-clock time minus the time implied by the sample count
+            at the nominal rate, in seconds.
+    """
+
+    @property
+    def frames(self) -> int:
+        """CRC-valid frames received."""
+
+    @property
+    def bytes_read(self) -> int:
+        """Raw bytes read from the link, including discarded frames."""
+
+    @property
+    def crc_errors(self) -> int:
+        """Frames rejected because their CRC did not match."""
+
+    @property
+    def sequence_gaps(self) -> int:
+        """Frames missing from the sequence counter."""
+
+    @property
+    def dropped_samples(self) -> int:
+        """
+        Frames the device produced that never reached the
+        caller (lost on the link or rejected by CRC).
+        """
+
+    @property
+    def elapsed(self) -> float:
+        """Wall-clock seconds since ``start()``."""
+
+    @property
+    def throughput_hz(self) -> float:
+        """Valid frames per second of wall-clock time."""
+
+    @property
+    def clock_skew(self) -> float:
+        """
+        Wall-clock time minus the time implied by the sample count
+        at the nominal rate, in seconds.
+        """
+
+    def __repr__(self) -> str: ...
+
+    def to_dict(self) -> dict[str, Any]:
+        """Convert to dictionary for easy serialization."""
+
+class ChannelStats:
+    """
+    Windowed statistics of one analog channel, in ADC counts.
+
+    Attributes:
+        channel: Analog channel (0-5 for A1-A6).
+        samples: Samples currently in the window.
+        mean: Mean over the window.
+        rms: Root mean square around the window mean (AC amplitude).
+        min: Smallest value in the window.
+        max: Largest value in the window.
+        envelope: Rectified signal low-passed at 5 Hz; an EMG activation level.
+    """
+
+    @property
+    def channel(self) -> int:
+        """Analog channel (0-5 for A1-A6)."""
+
+    @property
+    def samples(self) -> int:
+        """Samples currently in the window."""
+
+    @property
+    def mean(self) -> float:
+        """Mean over the window."""
+
+    @property
+    def rms(self) -> float:
+        """Root mean square around the window mean (AC amplitude)."""
+
+    @property
+    def min(self) -> int:
+        """Smallest value in the window."""
+
+    @property
+    def max(self) -> int:
+        """Largest value in the window."""
+
+    @property
+    def envelope(self) -> float:
+        """Rectified signal low-passed at 5 Hz; an EMG activation level."""
+
+    def __repr__(self) -> str: ...
+
+    def to_dict(self) -> dict[str, Any]:
+        """Convert to dictionary for easy serialization."""
+
+class Filter:
+    """
+    A filter to apply to analog channels while streaming.
+
+    Build one with the static constructors and pass a list of them to
+    ``Bitalino.start_streaming(filters=...)``. Filters are designed for the
+    acquisition rate and applied in order to every channel, with the output
+    re-centred on the ADC mid-scale (512 for A1-A4).
+
+    Example:
+        >>> device.start_streaming(
+        ...     on_batch,
+        ...     filters=[Filter.notch(50), Filter.bandpass(0.5, 40)],
+        ... )
+    """
+
+    @staticmethod
+    def notch(freq: float = 50.0) -> Filter:
+        """Narrow notch removing mains interference at ``freq`` Hz (50 or 60)."""
+
+    @staticmethod
+    def lowpass(cutoff: float, order: int = 2) -> Filter:
+        """Butterworth low-pass at ``cutoff`` Hz."""
+
+    @staticmethod
+    def highpass(cutoff: float, order: int = 2) -> Filter:
+        """Butterworth high-pass at ``cutoff`` Hz, e.g. to remove baseline wander."""
+
+    @staticmethod
+    def bandpass(low: float, high: float, order: int = 2) -> Filter:
+        """Butterworth band-pass keeping ``low`` to ``high`` Hz."""
+
+    def __repr__(self) -> str: ...
+
+class Bitalino:
+    """
+    BITalino device driver.
+    Python-facing BITalino driver wrapper for connection and acquisition.
+    Provides methods to connect, configure, and read biosignal data from
+    BITalino devices via Bluetooth. No root privileges required. The backend
+    uses a raw RFCOMM socket and assumes the device is already paired/trusted
+    (e.g., via `bluetoothctl`).
+
+    Use it as a context manager (or call ``close()``) to stop acquisition and
+    release the Bluetooth connection deterministically. If the object is garbage
+    collected while acquiring, the device is stopped automatically; call
+    ``leak()`` to opt out.
+
+    Instances may be handed to other threads (e.g. a ``ThreadPoolExecutor``);
+    reads release the GIL while waiting for frames. Calls on one instance must
+    not overlap: a concurrent call raises ``RuntimeError``.
+
+    Example:
+        >>> with Bitalino.connect("7E:91:2B:C4:AF:08") as device:
+        ...     print(f"Firmware: {device.version()}")
+        ...     device.start(rate=1000, channels=[0, 1, 2])
+        ...     frames = device.read(100)
+    """
+
+    def __init__(
+        self,
+        path: str,
+        *,
+        baud_rate: int = 115200,
+        flow_control: Literal["none", "software", "hardware"] = "none",
+        dtr: bool | None = None,
+        rts: bool | None = None,
+        reset_pulse: float | None = None,
+        probe_baud_rates: bool = False,
+    ) -> None:
+        """
+        Connect to a BITalino device via serial port path (e.g., `/dev/rfcomm0`).
+
+        Use this if you've already paired and bound the device manually, or for
+        USB-serial adapters. The defaults (115200 baud, no flow control) suit
+        the BITalino UART; some adapters need other settings.
+
+        Example:
+            >>> dev = Bitalino("/dev/ttyUSB0", dtr=True, reset_pulse=0.1,
+            ...                probe_baud_rates=True)
+
+        Args:
+            path: Serial device path.
+            baud_rate: Baud rate, tried first when probing. Default: 115200.
+            flow_control (Literal["none", "software", "hardware"]): "none",
+                "software" (XON/XOFF) or "hardware" (RTS/CTS).
+            dtr: Level to drive DTR to after opening. Default: leave as is.
+            rts: Level to drive RTS to after opening. Default: leave as is.
+            reset_pulse: Seconds to hold DTR and RTS low after opening before
+                raising them, for adapters that reset the module on the edge.
+            probe_baud_rates: Query version() and, until the reply names a
+                BITalino, retry at 230400, 57600, 38400, 19200 and 9600 baud.
+                This stops an acquisition left running on the link.
+
+        Raises:
+            ValueError: If flow_control is unknown or reset_pulse is negative.
+            BitalinoError: If the port cannot be opened.
+            TimeoutError: If probing finds no rate the device answers at.
+        """
+
+    @staticmethod
+    def connect(
+        mac: str,
+        pin: str = "1234",
+        *,
+        channel: int | None = None,
+        timeout: float = 5.0,
+        retries: int = 3,
+        retry_delay: float = 0.5,
+        adapter: str | None = None,
+        known_device: bool = True,
+        scan_timeout: float = 10.0,
+        registry: bool = False,
+        pairing_agent: Callable[[str, str, int | None], str | int | bool] | None = None,
+        on_progress: Callable[[str, str, int | None], None] | None = None,
+    ) -> Bitalino:
+        """
+        Connect to a BITalino device via Bluetooth.
+
+        Uses a raw RFCOMM socket and expects the device to already be
+        paired/trusted; just pass the MAC. Builds with the `sync-bluetooth`
+        feature pair an unpaired device through BlueZ first, answering with
+        ``pin`` or, if given, ``pairing_agent``; elsewhere both are ignored.
+
+        A ``pairing_agent`` is called as ``pairing_agent(mac, request,
+        passkey)`` where ``request`` is ``"pin_code"``, ``"passkey"``,
+        ``"confirmation"`` (``passkey`` is the code to compare) or
+        ``"authorization"``. It returns the PIN as a ``str``, the passkey as an
+        ``int``, or ``True``/``False`` to accept or reject; exceptions reject.
+
+        ``on_progress(mac, stage, attempt)`` is called at each step, with
+        ``stage`` one of ``"scanning"``, ``"found"``, ``"pairing"``,
+        ``"paired"``, ``"connecting"`` (``attempt`` counts from 1; ``None``
+        otherwise) and ``"verified"``, so a UI can show what it is waiting
+        for. It runs on a helper thread; exceptions are logged and ignored.
+
+        Ctrl-C aborts the scan, pairing and retries within a fraction of a
+        second and raises ``KeyboardInterrupt``.
+
+        Example:
+            >>> def agent(mac, request, passkey):
+            ...     if request == "confirmation":
+            ...         return input(f"Does {mac} show {passkey:06}? ") == "y"
+            ...     return "9876"
+            >>> device = Bitalino.connect("7E:91:2B:C4:AF:08", pairing_agent=agent)
+
+        Args:
+            mac: The MAC address of the device (e.g., "7E:91:2B:C4:AF:08")
+            pin: The PIN code used when pairing (default "1234")
+            channel: RFCOMM channel (default: the registry's, else 1)
+            timeout: Socket I/O timeout in seconds
+            retries: Maximum connection attempts
+            retry_delay: Base delay between attempts in seconds (doubled per retry)
+            adapter: Local adapter by name ("hci1") or address; Linux only
+            known_device: Connect without scanning first (scan only if that fails)
+            scan_timeout: Upper bound on scans for the device, in seconds
+            registry: Use the on-disk registry of known devices: a device
+                connected to before is reached without scanning on its last
+                channel, a changed firmware version is logged as a warning, and
+                the connection is recorded. Managed with ``bitalino devices``.
+            pairing_agent (Callable[[str, str, int | None], str | int | bool] | None):
+                Answers pairing requests instead of ``pin``.
+            on_progress (Callable[[str, str, int | None], None] | None):
+                Called with each connection step.
+
+        Returns:
+            A connected Bitalino instance
+
+        Raises:
+            BluetoothError: If pairing or connection fails after retries
+            ValueError: If a timeout or delay is negative or not finite
+            KeyboardInterrupt: If interrupted while connecting
+        """
+
+    @staticmethod
+    def connect_tcp(address: str) -> Bitalino:
+        """
+        Connect to a BITalino proxied over TCP.
+
+        Use this when the device is attached to another machine and exposed as a
+        raw TCP byte stream (e.g. ``ser2net`` on a Raspberry Pi). Dropped
+        connections are re-established automatically.
+
+        Args:
+            address: Bridge address as ``"host:port"``.
+
+        Returns:
+            A connected Bitalino instance
+
+        Raises:
+            BitalinoError: If the bridge cannot be reached
+        """
+
+    @staticmethod
+    def replay(path: str, realtime: bool = False) -> Bitalino:
+        """
+        Open a recorded raw capture for offline playback.
+
+        The returned device behaves like a connected one: call ``start()`` with
+        the rate and channels used during the recording, then read as usual.
+        Reading past the end of the capture raises IOError.
+
+        Args:
+            path: File holding the raw bytes received during acquisition.
+            realtime: Pace playback at the sampling rate instead of replaying as
+                fast as possible. Default: False.
+
+        Returns:
+            A Bitalino instance backed by the recording
+
+        Raises:
+            IOError: If the file cannot be read
+        """
+
+    @staticmethod
+    def list_serial_ports() -> list[SerialPort]:
+        """
+        List the serial ports present on this host, likely BITalinos first.
+
+        Example:
+            >>> ports = [p for p in Bitalino.list_serial_ports() if p.is_bitalino]
+            >>> device = Bitalino(ports[0].path)
+
+        Returns:
+            A list of SerialPort
+
+        Raises:
+            BitalinoError: If the ports cannot be enumerated
+        """
+
+    def version(self) -> str:
+        """
+        Get the device firmware version.
+
+        Returns:
+            Firmware version string (e.g., "BITalino_v5.2")
+        """
+
+    def start(
+        self,
+        rate: int | None = None,
+        channels: list[int] | None = None,
+        preset: Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None = None,
+    ) -> None:
+        """
+        Start data acquisition.
+
+        A ``preset`` names a sensor kit and supplies the channels, the
+        recommended rate and the default filters of ``start_streaming()``:
+        ``"ecg"`` (A2), ``"emg"`` (A1), ``"eda"`` (A3, 100 Hz), ``"eeg"`` (A4),
+        ``"eda_ecg"`` (A2, A3) or ``"psychophysiology"`` (A1-A3). Explicit
+        ``rate`` and ``channels`` take precedence over it.
+
+        Example:
+            >>> device.start(preset="ecg")
+
+        Args:
+            rate: Sampling rate in Hz. Must be 1, 10, 100, or 1000. Default:
+                the preset's, or 1000.
+            channels: List of analog channels to acquire (0-5). Default: the
+                preset's, or all channels.
+            preset (Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None):
+                Name of a sensor kit preset. Default: none.
+
+        Raises:
+            ValueError: If the preset is unknown.
+            BitalinoError: If starting acquisition fails
+        """
+
+    def start_at(
+        self,
+        at: datetime,
+        rate: int = 1000,
+        channels: list[int] | None = None,
+    ) -> None:
+        """
+        Start data acquisition at a scheduled wall-clock time.
+
+        Sets up the acquisition right away, then blocks (without holding the
+        GIL) until ``at`` and sends only the start command, for coordinating
+        with other equipment scheduled by clock. ``start_offset`` then tells
+        how late the command went out.
+
+        Example:
+            >>> at = datetime.now(timezone.utc).replace(second=0, microsecond=0) + timedelta(minutes=1)
+            >>> device.start_at(at, rate=1000, channels=[0, 1])
+
+        Args:
+            at (datetime): When to start; a naive ``datetime`` is local time.
+            rate: Sampling rate in Hz. Must be 1, 10, 100, or 1000. Default: 1000.
+            channels: List of analog channels to acquire (0-5). Default: all.
+
+        Raises:
+            BitalinoError: If ``at`` has already passed or starting acquisition
+                fails.
+        """
+
+    @classmethod
+    def validate_config(cls, rate: int, channels: list[int]) -> list[str]:
+        """
+        Check a ``start()`` configuration without touching a device.
+
+        Raises where ``start()`` would, and otherwise lists what it would
+        quietly change or what is likely to cost data: out-of-range or repeated
+        channels, the 6-bit values of a fifth and sixth channel, and data rates
+        close to what the Bluetooth link carries.
+
+        Example:
+            >>> Bitalino.validate_config(1000, [0, 1, 2, 3, 4, 5])
+            ['With 6 channels, A5 and A6 are sent with 6-bit resolution (0-63)',
+             '6 channels at 1000 Hz is near RFCOMM limits (8000 of 11520 bytes/s); ...']
+
+        Args:
+            rate: Sampling rate in Hz.
+            channels: Analog channels to acquire (0-5).
+
+        Returns:
+            Warnings, empty if the configuration is fine.
+
+        Raises:
+            ValueError: If the rate is not 1, 10, 100 or 1000, or no channel is in 0-5.
+        """
+
+    @property
+    def preset(
+        self,
+    ) -> Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None:
+        """
+        Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None:
+        Name of the preset passed to the last ``start()``, or None.
+        """
+
+    def stop(self) -> None:
+        """Stop data acquisition."""
+
+    def force_idle(self) -> None:
+        """
+        Bring the device back to idle from any state, e.g. after a crashed
+        process left it streaming. BITalino 2.0 devices (and devices not yet
+        identified by ``version()``) also get the go-to-idle command; pending
+        input is discarded.
+        """
+
+    def read(self, n_frames: int = 100, timeout_ms: int | None = None) -> list[Frame]:
+        """
+        Read frames from the device.
+
+        Convenience wrapper that discards the batch's timing and integrity counters.
+        Use ``read_timed()`` if you need to detect CRC errors or dropped frames.
+
+        Args:
+            n_frames: Number of frames to read. Default: 100.
+            timeout_ms: Overall time budget in milliseconds. If it elapses, the
+                frames read so far are returned. Default: wait for all frames.
+
+        Returns:
+            List of Frame objects with sequence, digital, and analog attributes.
+
+        Raises:
+            BitalinoError: If reading fails
+        """
+
+    def read_timed(
+        self,
+        n_frames: int = 100,
+        timeout_ms: int | None = None,
+        allow_partial: bool = True,
+        gap_fill: Literal["off", "hold", "nan"] = "off",
+    ) -> FrameBatch:
+        """
+        Read frames with timing and error information.
+
+        This is the recommended method for high-quality acquisition as it provides
+        timing information for sample reconstruction and error statistics.
+
+        Args:
+            n_frames: Number of frames to read.
+            timeout_ms: Overall time budget in milliseconds. Default: wait for all frames.
+            allow_partial: On timeout, return the frames read so far with
+                ``partial=True`` instead of raising. Default: True.
+            gap_fill (Literal["off", "hold", "nan"]): Insert placeholder frames
+                (``Frame.filled``) where sequence
+                gaps show lost frames, so the batch has one frame per sample
+                period: ``"hold"`` repeats the last analog values, ``"nan"``
+                marks them NaN in ``arrays()``. Default: ``"off"``.
+
+        Returns:
+            FrameBatch with frames, timestamp_us, crc_errors, sequence_gaps, and partial.
+
+        Raises:
+            ValueError: If gap_fill is not "off", "hold" or "nan".
+            TimeoutError: If the timeout elapses and allow_partial is False.
+            BitalinoError: If reading fails
+        """
+
+    def read_seconds(self, seconds: float) -> FrameBatch:
+        """
+        Read ``seconds`` of data at the acquisition rate as one batch.
+
+        Computes the frame count from the sampling rate and keeps reading until
+        the batch spans that many samples, so frames discarded for CRC errors
+        are made up for. Frames lost on the link count towards the duration and
+        show in ``sequence_gaps``.
+
+        Example:
+            >>> device.start(rate=1000, channels=[0, 1])
+            >>> batch = device.read_seconds(5.0)  # about 5000 frames
+
+        Args:
+            seconds: Duration to cover, in seconds.
+
+        Returns:
+            FrameBatch with frames, timestamp_us, crc_errors, and sequence_gaps.
+
+        Raises:
+            ValueError: If seconds is negative or not finite.
+            NotInAcquisitionError: If acquisition is not started.
+            BitalinoError: If reading fails
+        """
+
+    def read_available(self, max_frames: int = 1000) -> FrameBatch:
+        """
+        Read only the frames already buffered, without blocking.
+
+        Returns immediately with whatever whole frames have arrived (up to
+        ``max_frames``), so it is safe to poll from a GUI or game loop. The batch
+        is empty if no complete frame is buffered yet.
+
+        Args:
+            max_frames: Upper bound on frames returned. Default: 1000.
+
+        Returns:
+            FrameBatch with frames, timestamp_us, crc_errors, and sequence_gaps.
+
+        Raises:
+            BitalinoError: If the transport cannot report buffered bytes or reading fails
+        """
+
+    def frames(self, batch_size: int = 100) -> FrameIterator:
+        """
+        Iterate over timed batches until acquisition stops.
+
+        Replaces a manual ``read_timed()`` loop. Iteration ends cleanly when
+        ``stop()`` or ``close()`` is called on the device, or when the iterator's
+        own ``close()`` is called.
+
+        Example:
+            >>> for batch in device.frames(batch_size=100):
+            ...     process(batch)
+
+        Args:
+            batch_size: Number of frames per yielded batch. Default: 100.
+
+        Returns:
+            FrameIterator yielding FrameBatch objects.
+
+        Raises:
+            ValueError: If batch_size is 0.
+            NotInAcquisitionError: If acquisition is not started.
+        """
+
+    def start_streaming(
+        self,
+        callback: Callable[[FrameBatch], object],
+        batch_size: int = 100,
+        filters: list[Filter] | None = None,
+        queue_size: int = 16,
+        overflow: Literal["block", "drop_oldest", "drop_newest", "error"] = "block",
+    ) -> None:
+        """
+        Stream batches to ``callback`` from a background thread.
+
+        A Rust thread reads continuously and queues each ``FrameBatch`` of
+        ``batch_size`` frames for a delivery thread, which calls
+        ``callback(batch)`` holding the GIL only for the call. Until
+        ``stop_streaming()``, other device methods raise ``RuntimeError``.
+        Exceptions raised by the callback are reported as unraisable and
+        streaming continues.
+
+        When the callback falls ``queue_size`` batches behind, ``overflow``
+        decides what gives:
+
+        - ``"block"``: pause reading until it catches up. No batch is skipped,
+          but a long pause overflows the device's buffer and frames are lost on
+          the link (visible as ``sequence_gaps``).
+        - ``"drop_oldest"``: discard the oldest queued batch, staying close to
+          live.
+        - ``"drop_newest"``: discard the batch just read.
+        - ``"error"``: end streaming; ``stop_streaming()`` raises a
+          ``BitalinoError`` with ``kind == "overflow"``.
+
+        Discarded batches are counted in ``dropped_batches``, and each batch
+        keeps its ``first_sample_index``, so gaps in time stay detectable.
+
+        ``filters`` are applied on the reader thread before the callback sees
+        each batch, so no per-batch round trip through scipy is needed.
+
+        Example:
+            >>> device.start(rate=1000, channels=[0, 1])
+            >>> device.start_streaming(lambda batch: plot(batch.arrays()))
+            >>> ...
+            >>> device.stop_streaming()
+
+        Args:
+            callback (Callable[[FrameBatch], object]): Called with each
+                FrameBatch, on the delivery thread.
+            batch_size: Frames per batch. Default: 100.
+            filters: Filters applied in order to every analog channel.
+                Default: the preset's filters if started with one, else none.
+            queue_size: Batches the callback may fall behind by. Default: 16.
+            overflow (Literal["block", "drop_oldest", "drop_newest", "error"]):
+                "block", "drop_oldest", "drop_newest" or "error".
+                Default: "block".
+
+        Raises:
+            ValueError: If batch_size or queue_size is 0, overflow is unknown,
+                or a filter is invalid at the sampling rate.
+            NotInAcquisitionError: If acquisition is not started.
+        """
+
+    def stop_streaming(self) -> None:
+        """
+        Stop the background reader started by ``start_streaming()``.
+
+        Waits for the reader thread, delivering the frames collected so far as a
+        last (shorter) batch. Acquisition keeps running; call ``stop()`` to end
+        it. No-op if not streaming.
+
+        Raises:
+            BitalinoError: If streaming ended early because a read failed (e.g.
+                the link dropped).
+        """
+
+    @property
+    def streaming(self) -> bool:
+        """
+        Whether the background reader is running.
+
+        False after ``stop_streaming()``, and also once a read error has ended
+        streaming early (``stop_streaming()`` then raises that error).
+        """
+
+    @property
+    def dropped_batches(self) -> int:
+        """
+        Batches discarded because the streaming callback fell behind.
+
+        Counts the current ``start_streaming()`` session, or the last one once
+        stopped. Always 0 with ``overflow="block"``.
+        """
+
+    def wait_until_streaming(self, timeout: float = 2.0) -> None:
+        """
+        Block until the device is reliably streaming valid frames.
+
+        After ``start()`` returns, the Bluetooth link may still be warming up: the
+        device is emitting frames but early bytes can be lost or corrupted. This
+        method discards warm-up frames and returns as soon as one CRC-valid frame
+        arrives. The valid frame's sequence number is stashed so the next
+        ``read_timed()`` call does not flag a spurious sequence gap.
+
+        Args:
+            timeout: Maximum time to wait, in seconds. Default: 2.0.
+                Must be finite and in (0, 3600].
+
+        Raises:
+            ValueError: If timeout is not a finite number in (0, 3600] seconds.
+            TimeoutError: If no CRC-valid frame arrives before the deadline.
+            CrcError: If frames arrive but none passes the CRC check.
+            BitalinoError: If the underlying transport fails (e.g. link dropped).
+            NotInAcquisitionError: If acquisition is not started.
+        """
+
+    @property
+    def sampling_rate(self) -> int:
+        """Get the current sampling rate."""
+
+    @property
+    def io_timeout(self) -> float | None:
+        """
+        Read/write timeout of the connection in seconds (5.0 by default).
+
+        Lower it during acquisition to detect a stalled link quickly; raise it
+        for slow command exchanges. Reads given their own timeout still use the
+        shorter of the two.
+
+        Raises:
+            ValueError: If set to zero, a negative or a non-finite value.
+        """
+
+    @io_timeout.setter
+    def io_timeout(self, timeout: float) -> None: ...
+
+    @property
+    def elapsed_us(self) -> int | None:
+        """Get microseconds elapsed since acquisition started."""
+
+    def acquisition_start_utc(self) -> datetime | None:
+        """
+        Wall-clock start of the current (or most recent) acquisition.
+
+        Recorded at ``start()`` alongside the monotonic clock behind
+        ``elapsed_us``, for aligning recordings with external logs or video.
+
+        Returns:
+            datetime | None: Timezone-aware UTC ``datetime``, or None before the
+            first ``start()``.
+
+        Raises:
+            RuntimeError: If the device is closed or streaming.
+        """
+
+    @property
+    def start_offset(self) -> float | None:
+        """
+        How late the start command of the last ``start_at()`` went out.
+
+        Returns:
+            float | None: Seconds between the scheduled and the actual start
+            command, or None if acquisition was started without a schedule.
+
+        Raises:
+            RuntimeError: If the device is closed or streaming.
+        """
+
+    @property
+    def stats(self) -> AcquisitionStats:
+        """
+        Cumulative statistics for the current (or most recent) acquisition.
+
+        Replaces summing ``FrameBatch`` counters by hand: totals reset on
+        ``start()``, include reads that raised, and are kept after ``stop()``.
+
+        Raises:
+            RuntimeError: If the device is closed or streaming.
+        """
+
+    def channel_stats(self) -> list[ChannelStats]:
+        """
+        Windowed statistics of each acquired channel.
+
+        Computed from the most recent ``stats_window`` seconds of frames as they
+        are read, so biofeedback code can poll a smoothed level instead of
+        processing raw frames. Empty until the first frame after ``start()``.
+
+        Returns:
+            List of ChannelStats, in acquisition channel order.
+
+        Raises:
+            RuntimeError: If the device is closed or streaming.
+        """
+
+    @property
+    def crc_policy(self) -> Literal["lenient", "strict"] | int | None:
+        """
+        Literal["lenient", "strict"] | int | None: What to do with data failing
+        its CRC check, for ``state()`` and reads.
+
+        ``"lenient"`` (default) logs and carries on: ``state()`` returns the
+        reply as decoded and reads drop corrupt frames. ``"strict"`` raises
+        ``CrcError`` on the first mismatch. An integer ``n`` re-issues the
+        state command up to ``n`` times and lets a read drop up to ``n``
+        corrupt frames before raising.
+
+        Raises:
+            ValueError: If set to another string.
+        """
+
+    @crc_policy.setter
+    def crc_policy(self, policy: Literal["lenient", "strict"] | int) -> None: ...
+
+    @property
+    def stats_window(self) -> float | None:
+        """
+        Window behind ``channel_stats()``, in seconds (1.0 by default).
+
+        Raises:
+            ValueError: If set to zero, a negative or a non-finite value.
+        """
+
+    @stats_window.setter
+    def stats_window(self, window: float) -> None: ...
+
+    @property
+    def drift_ppm(self) -> float | None:
+        """
+        Estimated drift of the device crystal in parts per million.
+
+        Compares the number of frames produced against host monotonic time.
+        Positive values mean the device samples faster than nominal. ``None``
+        until roughly 10 seconds of data have been read with ``read_timed()``.
+        """
+
+    def sample_time_us(self, sample_index: int) -> float:
+        """
+        Drift-corrected time of a sample, in microseconds since acquisition start.
+
+        Uses the estimated true sampling rate once available (see ``drift_ppm``),
+        and the nominal rate before that.
+
+        Args:
+            sample_index: Zero-based index of the sample since ``start()``.
+
+        Returns:
+            Timestamp in microseconds as a float.
+        """
+
+    def link_quality(self) -> tuple[int | None, int | None]:
+        """
+        Query the adapter for the radio conditions of the active connection.
+
+        Batches already carry a sample taken at most once per second
+        (``FrameBatch.rssi`` / ``FrameBatch.link_quality``); use this for an
+        immediate reading, e.g. while repositioning the device.
+
+        Returns:
+            ``(rssi, link_quality)``; either may be None if the adapter rejects
+            that query.
+
+        Raises:
+            BitalinoError: If the link cannot report radio metrics (serial, TCP,
+                replay, or a platform other than Linux).
+        """
+
+    @property
+    def is_bitalino2(self) -> bool:
+        """
+        Check if this is a BITalino 2.0+ device.
+
+        BITalino 2.0+ supports additional features like state(), pwm(), and
+        trigger() in idle mode. Call version() first to detect device type.
+
+        Returns:
+            True if device is BITalino 2.0+
+        """
+
+    def device_info(self) -> DeviceInfo:
+        """
+        Identity and capabilities of the connected device.
+
+        Returns:
+            DeviceInfo with the MAC, firmware version, revision, supported
+            sampling rates, analog channel count and connection kind.
+
+        Raises:
+            RuntimeError: If the device is closed or streaming.
+        """
+
+    def set_battery_threshold(self, threshold: int = 30) -> None:
+        """
+        Set the battery threshold level.
+
+        When battery voltage drops below this threshold, the device LED will blink.
+        Must be called when not in acquisition mode.
+
+        Args:
+            threshold: Threshold value (0-63).
+                0 = 3.4V (minimum), 63 = 3.8V (maximum)
+
+        Raises:
+            BitalinoError: If device is currently in acquisition mode
+        """
+
+    def battery(self, value: int = 30) -> None:
+        """Alias for set_battery_threshold for compatibility with official library."""
+
+    def state(self) -> DeviceState:
+        """
+        Get the current device state (BITalino 2.0+ only).
+
+        Returns the current values of all analog channels, digital channels,
+        battery level, and battery threshold. Must be called when not in acquisition.
+
+        Returns:
+            DeviceState object with analog, battery, battery_threshold, digital,
+            battery_voltage, and is_battery_low properties.
+
+        Raises:
+            BitalinoError: If device is not BITalino 2.0+, is in acquisition mode,
+                or communication fails
+        """
+
+    def state_during_acquisition(self) -> tuple[DeviceState, int]:
+        """
+        Get the device state without ending an ongoing acquisition (BITalino 2.0+ only).
+
+        Pauses streaming, queries ``state()``, and restarts with the same rate and
+        channels. ``elapsed_us`` and drift estimation continue across the pause.
+        Equivalent to ``state()`` when not acquiring.
+
+        Returns:
+            Tuple of (DeviceState, samples_lost), where samples_lost estimates how
+            many samples were not recorded during the pause.
+
+        Raises:
+            BitalinoError: If device is not BITalino 2.0+ or the query/restart fails
+        """
+
+    def monitor_battery(
+        self,
+        callback: Callable[[float, bool], object],
+        interval: float = 60.0,
+        threshold: float = 3.5,
+    ) -> None:
+        """
+        Install a low-battery hook: ``callback`` runs when a check finds the
+        battery below ``threshold`` Volts.
+
+        Checks run inside calls made on the device, not on a background task.
+        While acquiring, the first read after each ``interval`` takes a real
+        reading with ``state_during_acquisition()`` (BITalino 2.0+), pausing the
+        stream for a few hundred milliseconds; if that query fails, the voltage
+        is extrapolated from earlier readings instead. While idle, call
+        ``poll_battery()``, which queries the device at most every ``interval``
+        seconds. Prefer intervals of minutes to keep the pauses rare.
+
+        Args:
+            callback (Callable[[float, bool], object]): Called as
+                ``callback(voltage, estimated)`` on every check
+                below the threshold. Exceptions are reported as unraisable.
+            interval: Minimum seconds between checks. Default: 60.0.
+            threshold: Voltage below which the callback fires. Default: 3.5.
+
+        Raises:
+            ValueError: If interval is not a finite positive number.
+        """
+
+    def stop_battery_monitor(self) -> None:
+        """Remove the battery monitor installed by ``monitor_battery()``."""
+
+    def poll_battery(self) -> tuple[float, bool] | None:
+        """
+        Run the battery monitor now if its interval has elapsed. Reads already
+        do this while acquiring; see ``monitor_battery()``.
+
+        Returns:
+            ``(voltage, estimated)`` if a reading was taken, else None.
+
+        Raises:
+            BitalinoError: If the state query fails
+        """
+
+    def monitor_throughput(
+        self,
+        callback: Callable[[float, float, float], object] | None,
+        window: float = 5.0,
+        max_deficit: float = 0.1,
+    ) -> None:
+        """
+        Call ``callback`` when the frame rate falls short of the sampling rate.
+
+        Each timed read counts the valid frames it returned; when more than
+        ``max_deficit`` of the nominal frames are missing over the last
+        ``window`` seconds, the callback runs on the reading thread and a
+        warning is logged, at most once per window while the shortfall lasts.
+        The window restarts with every ``start()``. Replaces any previous
+        monitor; pass None to stop monitoring.
+
+        Example:
+            >>> device.monitor_throughput(lambda got, hz, deficit: print(f"{got:.0f}/{hz:.0f} Hz"))
+
+        Args:
+            callback (Callable[[float, float, float], object] | None): Called as
+                ``callback(received_hz, expected_hz, deficit)``, where deficit is
+                the missing fraction ``1 - received_hz / expected_hz``.
+                Exceptions are reported as unraisable.
+            window: Seconds the frame rate is measured over. Default: 5.0.
+            max_deficit: Missing fraction tolerated before warning. Default: 0.1.
+
+        Raises:
+            ValueError: If window is not a finite positive number.
+        """
+
+    def on_digital_edge(self, callback: Callable[[int, bool, int], object] | None) -> None:
+        """
+        Call ``callback`` for every digital-channel transition in incoming frames.
+
+        Edges are detected while reading (``read()``, ``read_timed()``,
+        ``frames()``, ...), so the callback runs on the reading thread. Replaces
+        any previous handler; pass None to stop reporting edges.
+
+        Args:
+            callback (Callable[[int, bool, int], object] | None): Called as
+                ``callback(channel, rising, sample_index)``, where
+                channel indexes ``Frame.digital`` (0 = I1, 1 = I2, 2 = O1, 3 = O2)
+                and sample_index counts samples since ``start()``. Exceptions are
+                reported as unraisable.
+        """
+
+    def on_heartbeat(
+        self,
+        channel: int,
+        callback: Callable[[int, float | None, float | None], object] | None,
+    ) -> None:
+        """
+        Detect heartbeats on an ECG channel and call ``callback`` for each.
+
+        A Pan-Tompkins QRS detector runs while reading, like digital-edge
+        detection. It restarts with every ``start()``, needs two seconds of
+        signal to set its thresholds, and requires a rate of at least 100 Hz.
+        Replaces any previous handler; pass None to stop detection.
+
+        Example:
+            >>> device.on_heartbeat(1, lambda i, rr, bpm: print(f"{bpm} BPM"))
+
+        Args:
+            channel: Analog channel carrying the ECG (0-5).
+            callback (Callable[[int, float | None, float | None], object] | None):
+                Called as ``callback(sample_index, rr, bpm)``, where
+                sample_index is the R peak counted since ``start()`` and rr the
+                interval to the previous beat in seconds (rr and bpm are None for
+                the first beat). Exceptions are reported as unraisable.
+
+        Raises:
+            BitalinoError: If channel is not 0-5.
+        """
+
+    def on_scr(
+        self,
+        channel: int,
+        callback: Callable[[int, int, float, float], object] | None,
+    ) -> None:
+        """
+        Decompose an EDA channel and call ``callback`` for each skin-conductance
+        response (SCR).
+
+        Samples are converted to microsiemens, smoothed below 1 Hz and split into
+        a tonic level and a phasic component (see ``eda_level``) while reading.
+        Responses rising at least 0.01 uS are reported shortly after their peak.
+        Replaces any previous handler; pass None to stop decomposition.
+
+        Args:
+            channel: Analog channel carrying the EDA sensor (0-5).
+            callback (Callable[[int, int, float, float], object] | None): Called
+                as ``callback(onset_index, peak_index, amplitude, latency)``, with sample indices counted since ``start()``, the
+                amplitude in uS and the onset-to-peak latency in seconds.
+                Exceptions are reported as unraisable.
+
+        Raises:
+            BitalinoError: If channel is not 0-5.
+        """
+
+    @property
+    def eda_level(self) -> tuple[float, float, float] | None:
+        """
+        Latest decomposed skin conductance as ``(conductance, tonic, phasic)``
+        in uS, or None without an ``on_scr()`` handler or before its first
+        sample.
+        """
+
+    def annotate(self, label: str) -> Annotation:
+        """
+        Record an event marker at the current sample.
+
+        Use from stimulus-presentation code to mark events on the signal's sample
+        axis. The sample index is estimated from the host clock, so it does not
+        lag behind by the Bluetooth buffering delay.
+
+        Args:
+            label: Free-form marker label.
+
+        Returns:
+            The recorded Annotation.
+
+        Raises:
+            NotInAcquisitionError: If acquisition is not running.
+        """
+
+    @property
+    def annotations(self) -> list[Annotation]:
+        """Markers recorded since start(), oldest first."""
+
+    def take_annotations(self) -> list[Annotation]:
+        """Remove and return the recorded markers (e.g. after writing them out)."""
+
+    @property
+    def output_events(self) -> list[OutputEvent]:
+        """
+        Output changes sent with trigger(), trigger_pulse() and pwm() since
+        start(), oldest first.
+
+        Commands sent while idle are not recorded, and neither are PWM
+        patterns or ramps.
+        """
+
+    def take_output_events(self) -> list[OutputEvent]:
+        """
+        Remove and return the recorded output changes (e.g. after writing them
+        out with the annotations).
+        """
+
+    def begin_session(self) -> Session:
+        """
+        Start collecting the running acquisition into a Session.
+
+        The session records the start time, device identity and channels now,
+        and picks up annotations, output events and statistics when ended. An
+        open session is replaced.
+
+        Raises:
+            NotInAcquisitionError: If acquisition is not running.
+        """
+
+    def end_session(self) -> Session:
+        """
+        Close the session opened with begin_session() and return it, filled
+        with the annotations, output events and statistics so far. Can be
+        called after stop().
+
+        Raises:
+            BitalinoError: If no session is open.
+        """
+
+    def enable_raw_capture(self, path: str) -> None:
+        """
+        Tee the raw acquisition stream into a file for debugging or replay.
+
+        Every byte read while decoding frames is written exactly as received,
+        including frames that fail CRC checks. The dump can be played back with
+        ``Bitalino.replay()``.
+
+        Args:
+            path: Output file (created or truncated).
+
+        Raises:
+            BitalinoError: If the file cannot be created
+        """
+
+    def disable_raw_capture(self) -> None:
+        """Stop the raw capture started with ``enable_raw_capture()`` and flush it."""
+
+    def trigger(self, outputs: list[int] | None = None) -> None:
+        """
+        Set digital output pins.
+
+        Controls the digital output pins for external circuits or LED control.
+
+        Args:
+            outputs: List of output values (0 or 1).
+                BITalino 2.0: [O1, O2] - works in both idle and acquisition modes
+                BITalino 1.0: [O1, O2, O3, O4] - requires acquisition mode
+
+        Raises:
+            NotInAcquisitionError: If BITalino 1.0 and not in acquisition mode
+        """
+
+    def trigger_pulse(self, outputs: list[int], width: float) -> None:
+        """
+        Raise digital outputs for ``width`` seconds, then bring them back down.
+
+        Outputs set to 1 go high at once; the others keep their level. The
+        call returns immediately and the outputs fall ``width`` seconds after
+        they rose, so frames can be read during the pulse, e.g. to send sync
+        pulses to an EEG amplifier or a camera. Connections that cannot be
+        written from another thread (Windows Bluetooth, replay) block for the
+        pulse instead. A new pulse, ``trigger()`` or ``stop()`` ends a pending
+        pulse early.
+
+        Example:
+            >>> device.trigger_pulse([1, 0], 0.01)  # 10 ms on O1
+
+        Args:
+            outputs: Outputs to pulse (0 or 1), same layout as ``trigger()``.
+            width: Pulse width in seconds.
+
+        Raises:
+            ValueError: If width is negative or not finite.
+            NotInAcquisitionError: If BITalino 1.0 and not in acquisition mode
+        """
+
+    def raw(self, data: bytes = b"", n_bytes: int = 0, timeout: float = 1.0) -> bytes:
+        """
+        Send raw bytes to the device and read back its reply.
+
+        An escape hatch for experimenting with undocumented firmware commands:
+        the bytes are written as given (one command byte at a time) and the
+        driver's own view of the device is not updated, so a raw start or stop
+        confuses later reads. Prefer the typed methods for anything they
+        cover.
+
+        Example:
+            >>> device.raw(b"\\x07", n_bytes=64, timeout=0.5)  # version request
+            b'BITalino_v5.2\\n'
+
+        Args:
+            data (bytes): Bytes to send; may be empty to only read.
+            n_bytes: Most bytes to read back. Default: 0 (send only).
+            timeout: Seconds to wait for ``n_bytes``. Default: 1.0.
+
+        Returns:
+            The bytes that arrived within the timeout, possibly fewer than
+            ``n_bytes``.
+
+        Raises:
+            ValueError: If timeout is negative or not finite.
+            BitalinoError: If writing or reading fails
+        """
+
+    def pwm(self, value: int = 100) -> None:
+        """
+        Set PWM output value (BITalino 2.0+ only).
+
+        Controls the pulse-width modulation output for LED brightness
+        or other PWM-driven devices.
+
+        Args:
+            value: PWM duty cycle (0-255).
+                0 = 0% duty cycle (always off)
+                255 = 100% duty cycle (always on)
+
+        Raises:
+            BitalinoError: If device is not BITalino 2.0+
+        """
+
+    def pwm_pattern(self, steps: list[tuple[int, float]]) -> PwmSchedule:
+        """
+        Play PWM ``steps`` on a background thread (BITalino 2.0+ only).
+
+        Each ``(value, seconds)`` step is held for its duration, timed from the
+        start of the pattern, and the last value stays set. Reads and other
+        calls go on meanwhile, so an LED can flash for photic stimulation or a
+        haptic driver pulse during an acquisition. Sending a value takes about
+        0.1 s, so shorter steps are stretched. A new pattern or ``pwm()``
+        replaces a running one.
+
+        Example:
+            >>> flash = [(255, 0.5), (0, 0.5)] * 10  # 1 Hz for 10 s
+            >>> device.pwm_pattern(flash)
+
+        Args:
+            steps: List of ``(value, seconds)`` pairs, value 0-255.
+
+        Returns:
+            PwmSchedule to wait for or cancel.
+
+        Raises:
+            ValueError: If a duration is negative or not finite.
+            BitalinoError: If device is not BITalino 2.0+, or the connection
+                cannot be written from another thread (Windows Bluetooth, replay).
+        """
+
+    def pwm_ramp(self, start: int, end: int, seconds: float) -> PwmSchedule:
+        """
+        Move the PWM output linearly from ``start`` to ``end`` over
+        ``seconds`` on a background thread (BITalino 2.0+ only).
+
+        The value changes at most every 0.1 s; see ``pwm_pattern()``.
+
+        Example:
+            >>> device.pwm_ramp(0, 255, 2.0).wait()  # fade an LED in
+
+        Args:
+            start: First value (0-255).
+            end: Final value (0-255), left set when the ramp ends.
+            seconds: Ramp duration.
+
+        Returns:
+            PwmSchedule to wait for or cancel.
+
+        Raises:
+            ValueError: If seconds is negative or not finite.
+            BitalinoError: Same as ``pwm_pattern()``.
+        """
+
+    def leak(self) -> None:
+        """
+        Keep the device streaming when this object is garbage collected.
+
+        By default the driver sends the stop command on cleanup if acquisition is
+        still running. After ``leak()`` the connection is released without
+        stopping the device.
+        """
+
+    def close(self) -> None:
+        """
+        Stop acquisition (if running) and close the connection.
+
+        Idempotent: calling ``close()`` on an already-closed device is a no-op.
+        Any other method called afterwards raises ``RuntimeError``.
+
+        Raises:
+            BitalinoError: If the stop command fails (the connection is still released)
+        """
+
+    @property
+    def closed(self) -> bool:
+        """Whether ``close()`` has released the connection."""
+
+    def __enter__(self) -> Bitalino: ...
+
+    def __exit__(
+        self,
+        exc_type: Any = None,
+        _exc_value: Any = None,
+        _traceback: Any = None,
+    ) -> bool:
+        """
+        Close the device. If the block raised, a failure to stop is logged so
+        the original exception propagates; on a clean exit it is raised.
+        """
+
+    def __repr__(self) -> str: ...
+
+class FrameIterator:
+    """
+    Iterator over timed batches, returned by ``Bitalino.frames()``.
+
+    Each step reads one ``FrameBatch`` of ``batch_size`` frames. Iteration ends
+    (``StopIteration``) once the iterator is closed, the device is closed, or
+    acquisition is stopped.
+    """
+
+    def __iter__(self) -> FrameIterator: ...
+
+    def __next__(self) -> FrameBatch: ...
+
+    def close(self) -> None:
+        """
+        Stop iterating; the next ``__next__`` raises ``StopIteration``.
+
+        The device itself is left untouched (acquisition keeps running).
+        """
+
+    def __repr__(self) -> str: ...
+
+class PwmSchedule:
+    """
+    PWM schedule running on a background thread, returned by
+    ``Bitalino.pwm_ramp()`` and ``Bitalino.pwm_pattern()``.
+
+    Dropping it leaves the schedule running until its last step.
+    """
+
+    @property
+    def running(self) -> bool:
+        """Whether steps are still being sent."""
+
+    def wait(self) -> None:
+        """
+        Block until the last step has been sent.
+
+        Raises:
+            BitalinoError: If writing to the device failed.
+        """
+
+    def cancel(self) -> None:
+        """
+        Stop after the value being sent, leaving the output at that value.
+
+        Raises:
+            BitalinoError: If writing to the device had failed.
+        """
+
+    def __repr__(self) -> str: ...
+
+class AsyncBitalino:
+    """
+    asyncio wrapper around a connected ``Bitalino``.
+
+    Every device call runs on a background Rust thread and returns an awaitable,
+    so acquisition never blocks the event loop. Calls are executed in the order
+    they are made. Cancelling an awaitable does not interrupt the device call
+    already queued; frames it reads are discarded.
+
+    Example:
+        >>> device = await asyncio.to_thread(Bitalino.connect, "7E:91:2B:C4:AF:08")
+        >>> async with AsyncBitalino(device) as dev:
+        ...     await dev.start(rate=1000, channels=[0, 1])
+        ...     batch = await dev.read(100)
+    """
+
+    def __init__(self, device: Bitalino) -> None:
+        """
+        Take over a connected ``Bitalino``.
+
+        The synchronous object is closed by this call; use the returned
+        ``AsyncBitalino`` from then on.
+
+        Args:
+            device: A connected (not closed) Bitalino instance.
+
+        Raises:
+            RuntimeError: If ``device`` is already closed.
+        """
+
+    def version(self) -> Awaitable[str]:
+        """
+        Await the firmware version string.
+
+        Returns:
+            Awaitable[str]: Resolves to the version, e.g. "BITalino_v5.2".
+        """
+
+    def start(
+        self,
+        rate: int | None = None,
+        channels: list[int] | None = None,
+        preset: Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None = None,
+    ) -> Awaitable[None]:
+        """
+        Await the start of acquisition; same arguments as ``Bitalino.start()``.
+
+        Args:
+            rate: Sampling rate in Hz (1, 10, 100 or 1000).
+            channels: Analog channels to acquire (0-5).
+            preset (Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None):
+                Name of a sensor kit preset.
+
+        Returns:
+            Awaitable[None]: Resolves once the device is acquiring.
+        """
+
+    def start_at(
+        self,
+        at: datetime,
+        rate: int = 1000,
+        channels: list[int] | None = None,
+    ) -> Awaitable[None]:
+        """
+        Await acquisition started at a scheduled wall-clock time; see
+        ``Bitalino.start_at()``. The event loop keeps running while waiting.
+
+        Args:
+            at (datetime): When to start; a naive ``datetime`` is local time.
+            rate: Sampling rate in Hz (1, 10, 100 or 1000).
+            channels: Analog channels to acquire (0-5). Default: all.
+
+        Returns:
+            Awaitable[None]: Resolves once the device is acquiring, just after
+            ``at``.
+        """
+
+    def stop(self) -> Awaitable[None]:
+        """
+        Await the end of acquisition.
+
+        Returns:
+            Awaitable[None]: Resolves once the device is idle.
+        """
+
+    def read(
+        self,
+        n_frames: int = 100,
+        timeout_ms: int | None = None,
+        allow_partial: bool = True,
+    ) -> Awaitable[FrameBatch]:
+        """
+        Await a batch of frames; same arguments as ``Bitalino.read_timed()``.
+
+        Returns:
+            Awaitable[FrameBatch]: Resolves to the batch.
+        """
+
+    def state(self) -> Awaitable[DeviceState]:
+        """
+        Await the device state (BITalino 2.0+ only, idle mode).
+
+        Returns:
+            Awaitable[DeviceState]: Resolves to the state.
+        """
+
+    def close(self) -> Awaitable[None]:
+        """
+        Stop acquisition (if running) and release the connection.
+
+        Calls made before ``close()`` still complete. Awaiting ``close()`` on an
+        already-closed device returns immediately.
+
+        Returns:
+            Awaitable[None]: Resolves once the connection is released.
+        """
+
+    @property
+    def closed(self) -> bool:
+        """Whether ``close()`` has been called."""
+
+    def __aenter__(self) -> Awaitable[AsyncBitalino]:
+        """
+        Returns:
+            Awaitable[AsyncBitalino]: Resolves to this object.
+        """
+
+    def __aexit__(
+        self,
+        _exc_type: Any = None,
+        _exc_value: Any = None,
+        _traceback: Any = None,
+    ) -> Awaitable[None]:
+        """
+        Close the device; exceptions are not suppressed.
+
+        Returns:
+            Awaitable[None]: Resolves like ``close()``.
+        """
+
+    def __repr__(self) -> str: ...
+
+def enable_rust_logs(level: str | None = None) -> None:
+    """Enable Rust-to-Python logging bridge at the given level (or env default)."""
+
+def reset_log_cache() -> None:
+    """Clear cached Python logger handles; call after reconfiguring Python logging."""
+
+def init_logging() -> bool:
+    """
+    Install the bridge carrying Rust logs into Python's ``logging``.
+
+    Done automatically on import, so this only matters to check the result.
+    Records go to loggers named after the Rust module (``bitalino_rs.bitalino``,
+    ...), at the level set by ``BITALINO_LOG`` or ``RUST_LOG`` (default info).
+
+    Returns:
+        True if Rust logs reach Python, False if another Rust logger was
+        installed in this process first.
+    """
+
+def set_log_level(
+    level: Literal["off", "critical", "error", "warning", "warn", "info", "debug", "trace"],
+) -> None:
+    """
+    Set the most verbose Rust level forwarded to Python's ``logging``.
+
+    Python loggers still filter what they receive, so also lower their level
+    (e.g. ``logging.getLogger("bitalino_rs").setLevel("DEBUG")``) to see
+    debug records.
+
+    Args:
+        level (Literal["off", "critical", "error", "warning", "warn", "info", "debug", "trace"]):
+            Level name, case-insensitive. ``"critical"`` means error, as Rust
+            has no more severe level.
 
-# Logging helpers
+    Raises:
+        ValueError: If the level name is unknown.
+    """
 
-def enable_rust_logs(level: str | None = None) -> None: ...
-def reset_log_cache() -> None: ...
+def reset_logging_cache() -> None:
+    """Clear cached Python logger levels; call after reconfiguring Python logging."""
@@ -4,6 +4,6 @@ Thin wrapper around the compiled `_bitalino_core` extension so downstream users
 import from a stable, Pythonic module path.
 """
 
-from bitalino_rs._bitalino_core import Bitalino
+from bitalino_rs._bitalino_core import AsyncBitalino, Bitalino, FrameIterator, PwmSchedule
 
-__all__ = ["Bitalino"]
+__all__ = ["AsyncBitalino", "Bitalino", "FrameIterator", "PwmSchedule"]
@@ -1,5 +1,5 @@
 """Public driver entry point for BITalino hardware."""
 
-from bitalino_rs._bitalino_core import Bitalino
+from bitalino_rs._bitalino_core import AsyncBitalino, Bitalino, FrameIterator, PwmSchedule
 
-__all__ = ["Bitalino"]
+__all__ = ["AsyncBitalino", "Bitalino", "FrameIterator", "PwmSchedule"]
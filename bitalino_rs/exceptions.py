@@ -0,0 +1,38 @@
+"""Exception hierarchy raised by the BITalino driver.
+
+Every driver error derives from ``BitalinoError`` (itself an ``OSError``), so
+``except OSError`` keeps catching everything while targeted handlers can pick
+out specific failures::
+
+    OSError
+    └── BitalinoError
+        ├── BluetoothError
+        │   └── PairingError
+        ├── CrcError
+        ├── TimeoutError
+        └── NotInAcquisitionError
+
+Note that ``bitalino_rs.TimeoutError`` is not the built-in ``TimeoutError``.
+
+Every raised exception also carries machine-readable ``kind`` (``"timeout"``,
+``"crc"``, ``"bluetooth"``, ``"not_acquiring"``, ...) and ``code`` (e.g.
+``"io.timed_out"``, ``"bluetooth.pairing"``) attributes for retry logic.
+"""
+
+from bitalino_rs._bitalino_core import (
+    BitalinoError,
+    BluetoothError,
+    CrcError,
+    NotInAcquisitionError,
+    PairingError,
+    TimeoutError,
+)
+
+__all__ = [
+    "BitalinoError",
+    "BluetoothError",
+    "CrcError",
+    "NotInAcquisitionError",
+    "PairingError",
+    "TimeoutError",
+]
@@ -0,0 +1,19 @@
+"""Exception hierarchy raised by the BITalino driver."""
+
+from bitalino_rs._bitalino_core import (
+    BitalinoError,
+    BluetoothError,
+    CrcError,
+    NotInAcquisitionError,
+    PairingError,
+    TimeoutError,
+)
+
+__all__ = [
+    "BitalinoError",
+    "BluetoothError",
+    "CrcError",
+    "NotInAcquisitionError",
+    "PairingError",
+    "TimeoutError",
+]
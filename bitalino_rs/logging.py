@@ -1,9 +1,22 @@
 """Logging helpers for the BITalino Rust core.
 
-These functions bridge Rust logging into Python's ``logging`` module. They are
-safe to call multiple times.
+These functions bridge Rust logging into Python's ``logging`` module. The
+bridge is installed when the extension is imported; they are safe to call
+multiple times.
 """
 
-from bitalino_rs._bitalino_core import enable_rust_logs, reset_log_cache
+from bitalino_rs._bitalino_core import (
+    enable_rust_logs,
+    init_logging,
+    reset_log_cache,
+    reset_logging_cache,
+    set_log_level,
+)
 
-__all__ = ["enable_rust_logs", "reset_log_cache"]
+__all__ = [
+    "enable_rust_logs",
+    "init_logging",
+    "reset_log_cache",
+    "reset_logging_cache",
+    "set_log_level",
+]
@@ -1,9 +1,28 @@
 """Helpers to bridge Rust logging into Python's ``logging`` module."""
 
-def enable_rust_logs(level: str | None = ...) -> None:
+from typing import Literal
+
+def init_logging() -> bool:
+    """Install the bridge (done on import); False if another Rust logger won."""
+
+def set_log_level(
+    level: Literal["off", "critical", "error", "warning", "warn", "info", "debug", "trace"],
+) -> None:
+    """Set the most verbose Rust level forwarded to Python's ``logging``."""
+
+def reset_logging_cache() -> None:
+    """Clear cached Python logger levels after reconfiguring logging."""
+
+def enable_rust_logs(level: str | None = None) -> None:
     """Enable Rust-side logging bridged into Python's ``logging`` module."""
 
 def reset_log_cache() -> None:
     """Clear cached Python logger lookups after reconfiguring logging."""
 
-__all__ = ["enable_rust_logs", "reset_log_cache"]
+__all__ = [
+    "enable_rust_logs",
+    "init_logging",
+    "reset_log_cache",
+    "reset_logging_cache",
+    "set_log_level",
+]
@@ -1,9 +1,9 @@
 """Shared data structures for BITalino interactions.
 
 These Python-visible types mirror what the Rust core returns: raw frames,
-timed batches with integrity counters, and device state snapshots. The
-``SamplingRate`` literal restricts configuration to values supported by the
-hardware crystal (1, 10, 100, 1000 Hz).
+timed batches with integrity counters, device identity, and device state
+snapshots. The ``SamplingRate`` literal restricts configuration to values
+supported by the hardware crystal (1, 10, 100, 1000 Hz).
 """
 
 from typing import Literal
@@ -11,9 +11,17 @@ from typing import Literal
 from bitalino_rs._bitalino_core import (
     DEFAULT_SAMPLING_RATE,
     VALID_SAMPLING_RATES,
+    AcquisitionStats,
+    Annotation,
+    ChannelStats,
+    DeviceInfo,
     DeviceState,
+    Filter,
     Frame,
     FrameBatch,
+    OutputEvent,
+    SerialPort,
+    Session,
 )
 
 SamplingRate = Literal[1, 10, 100, 1000]
@@ -21,8 +29,16 @@ SamplingRate = Literal[1, 10, 100, 1000]
 __all__ = [
     "DEFAULT_SAMPLING_RATE",
     "VALID_SAMPLING_RATES",
+    "AcquisitionStats",
+    "Annotation",
+    "ChannelStats",
+    "DeviceInfo",
     "DeviceState",
+    "Filter",
     "Frame",
     "FrameBatch",
+    "OutputEvent",
     "SamplingRate",
+    "SerialPort",
+    "Session",
 ]
@@ -10,9 +10,17 @@ from typing import Literal
 from bitalino_rs._bitalino_core import (
     DEFAULT_SAMPLING_RATE,
     VALID_SAMPLING_RATES,
+    AcquisitionStats,
+    Annotation,
+    ChannelStats,
+    DeviceInfo,
     DeviceState,
+    Filter,
     Frame,
     FrameBatch,
+    OutputEvent,
+    SerialPort,
+    Session,
 )
 
 SamplingRate = Literal[1, 10, 100, 1000]
@@ -20,8 +28,16 @@ SamplingRate = Literal[1, 10, 100, 1000]
 __all__ = [
     "DEFAULT_SAMPLING_RATE",
     "VALID_SAMPLING_RATES",
+    "AcquisitionStats",
+    "Annotation",
+    "ChannelStats",
+    "DeviceInfo",
     "DeviceState",
+    "Filter",
     "Frame",
     "FrameBatch",
+    "OutputEvent",
     "SamplingRate",
+    "SerialPort",
+    "Session",
 ]
@@ -0,0 +1,1024 @@
+//! Build script.
+//!
+//! With the `python` feature it renders `_bitalino_core.pyi`, the typing stub
+//! of the extension module, from the PyO3 bindings in `src/python.rs` and
+//! `src/python/` into `OUT_DIR`. maturin ships the committed copy,
+//! `bitalino_rs/_bitalino_core.pyi`, next to the compiled module together
+//! with the `py.typed` marker, so mypy and pyright see the full API of
+//! `_bitalino_core`.
+//!
+//! A build never touches the source tree: `tests/stub.rs` fails when the
+//! committed stub differs from the generated one, and
+//! `BITALINO_REGEN_STUBS=1 cargo build` overwrites it.
+//!
+//! Types are derived from the Rust signatures (`u16` is `int`, `Option<T>` is
+//! `T | None`, `Vec<T>` is `list[T]`, pyclasses by their Python name). Where
+//! the Rust type is looser than the Python contract (`Py<PyAny>`, a `&str`
+//! that only takes a few values) the docstring carries the type, the way
+//! Google-style docstrings spell it:
+//!
+//! ```text
+//! Args:
+//!     callback (Callable[[FrameBatch], object]): Called with each batch.
+//! Returns:
+//!     datetime | None: Acquisition start, or None.
+//! Attributes:
+//!     kind (Literal["digital", "pwm"]): Which output changed.
+//! ```
+//!
+//! A getter may instead start its docstring with `type: description`, and an
+//! `Overloads:` section lists `(params) -> type` variants of one method.
+
+fn main() {
+    #[cfg(feature = "python")]
+    stubs::generate();
+}
+
+#[cfg(feature = "python")]
+mod stubs {
+    use std::collections::{BTreeSet, HashMap};
+    use std::env;
+    use std::fmt::Write as _;
+    use std::fs;
+    use std::path::Path;
+
+    use syn::parse::ParseStream;
+    use syn::{Attribute, Expr, FnArg, ImplItem, Item, Lit, Pat, Type};
+
+    /// Binding sources, in the order their classes appear in the stub.
+    const SOURCES: &[&str] = &[
+        "src/python/exceptions.rs",
+        "src/python.rs",
+        "src/python/aio.rs",
+    ];
+    /// Committed stub, only written when [`REGEN_ENV`] is `1`.
+    const STUB: &str = "bitalino_rs/_bitalino_core.pyi";
+    /// Environment variable asking the build to update [`STUB`].
+    const REGEN_ENV: &str = "BITALINO_REGEN_STUBS";
+
+    /// Names a docstring type may use besides the module's own classes.
+    const KNOWN_NAMES: &[&str] = &[
+        "Any",
+        "Awaitable",
+        "BaseException",
+        "Callable",
+        "Iterator",
+        "Literal",
+        "None",
+        "TracebackType",
+        "bool",
+        "bytes",
+        "datetime",
+        "dict",
+        "float",
+        "int",
+        "list",
+        "object",
+        "slice",
+        "str",
+        "tuple",
+        "type",
+    ];
+
+    /// Where each name used in the stub is imported from.
+    const IMPORTS: &[(&str, &str)] = &[
+        ("collections.abc", "Awaitable"),
+        ("collections.abc", "Callable"),
+        ("collections.abc", "Iterator"),
+        ("datetime", "datetime"),
+        ("types", "TracebackType"),
+        ("typing", "Any"),
+        ("typing", "Literal"),
+        ("typing", "overload"),
+    ];
+
+    /// Longest single-line `def` before parameters go one per line (the ruff
+    /// line length of `pyproject.toml`).
+    const LINE_WIDTH: usize = 100;
+
+    struct Class {
+        rust: String,
+        name: String,
+        base: Option<String>,
+        doc: String,
+        eq: bool,
+        fields: Vec<(String, Type)>,
+        methods: Vec<Method>,
+    }
+
+    #[derive(PartialEq)]
+    enum Kind {
+        Instance,
+        Static,
+        Class,
+        New,
+        Getter,
+        Setter,
+    }
+
+    struct Method {
+        name: String,
+        kind: Kind,
+        doc: String,
+        /// Python parameters, including `*` and `/` markers.
+        params: Vec<Param>,
+        ret: Option<Type>,
+    }
+
+    struct Param {
+        name: String,
+        ty: Option<Type>,
+        default: Option<String>,
+    }
+
+    struct Module {
+        classes: Vec<Class>,
+        functions: Vec<Method>,
+        constants: Vec<(String, String)>,
+        /// Python names of all classes and exceptions, by Rust name.
+        names: HashMap<String, String>,
+    }
+
+    pub fn generate() {
+        let mut module = Module {
+            classes: Vec::new(),
+            functions: Vec::new(),
+            constants: Vec::new(),
+            names: HashMap::new(),
+        };
+        let files: Vec<syn::File> = SOURCES
+            .iter()
+            .map(|path| {
+                println!("cargo:rerun-if-changed={path}");
+                let source = fs::read_to_string(path).unwrap_or_else(|e| panic!("{path}: {e}"));
+                syn::parse_file(&source).unwrap_or_else(|e| panic!("{path}: {e}"))
+            })
+            .collect();
+        for file in &files {
+            collect_classes(&mut module, &file.items);
+        }
+        for file in &files {
+            collect_members(&mut module, &file.items);
+        }
+        for class in &module.classes {
+            module.names.insert(class.rust.clone(), class.name.clone());
+        }
+
+        let stub = render(&module);
+        let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo");
+        write_if_changed(&Path::new(&out_dir).join("_bitalino_core.pyi"), &stub);
+        println!("cargo:rerun-if-env-changed={REGEN_ENV}");
+        if env::var_os(REGEN_ENV).is_some_and(|v| v == "1") {
+            write_if_changed(Path::new(STUB), &stub);
+        }
+    }
+
+    fn write_if_changed(path: &Path, contents: &str) {
+        if fs::read_to_string(path).ok().as_deref() != Some(contents) {
+            fs::write(path, contents).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        }
+    }
+
+    // --- Collecting the bindings ---------------------------------------------------------
+
+    fn collect_classes(module: &mut Module, items: &[Item]) {
+        for item in items {
+            match item {
+                Item::Struct(s) => {
+                    let Some(attr) = find_attr(&s.attrs, "pyclass") else {
+                        continue;
+                    };
+                    let mut name = s.ident.to_string();
+                    let mut eq = false;
+                    if let syn::Meta::List(_) = attr.meta {
+                        attr.parse_nested_meta(|meta| {
+                            if meta.path.is_ident("name") {
+                                name = meta.value()?.parse::<syn::LitStr>()?.value();
+                            } else if meta.path.is_ident("eq") {
+                                eq = true;
+                            } else if meta.input.peek(syn::Token![=]) {
+                                meta.value()?.parse::<Expr>()?;
+                            }
+                            Ok(())
+                        })
+                        .expect("pyclass arguments");
+                    }
+                    let fields = s
+                        .fields
+                        .iter()
+                        .filter(|f| pyo3_flag(&f.attrs, "get"))
+                        .map(|f| (f.ident.as_ref().unwrap().to_string(), f.ty.clone()))
+                        .collect();
+                    module.classes.push(Class {
+                        rust: s.ident.to_string(),
+                        name,
+                        base: None,
+                        doc: doc_of(&s.attrs),
+                        eq,
+                        fields,
+                        methods: Vec::new(),
+                    });
+                }
+                Item::Macro(m) if m.mac.path.is_ident("create_exception") => {
+                    let args = m
+                        .mac
+                        .parse_body_with(
+                            syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated,
+                        )
+                        .expect("create_exception! arguments");
+                    let args: Vec<&Expr> = args.iter().collect();
+                    let name = expr_name(args[1]);
+                    let base = match expr_name(args[2]).as_str() {
+                        "PyOSError" => "OSError".to_string(),
+                        "PyException" => "Exception".to_string(),
+                        other => other.to_string(),
+                    };
+                    let doc = match args.get(3) {
+                        Some(Expr::Lit(syn::ExprLit {
+                            lit: Lit::Str(s), ..
+                        })) => s.value(),
+                        _ => String::new(),
+                    };
+                    module.classes.push(Class {
+                        rust: name.clone(),
+                        name,
+                        base: Some(base),
+                        doc,
+                        eq: false,
+                        fields: Vec::new(),
+                        methods: Vec::new(),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn collect_members(module: &mut Module, items: &[Item]) {
+        for item in items {
+            match item {
+                Item::Impl(imp) if find_attr(&imp.attrs, "pymethods").is_some() => {
+                    let Type::Path(self_ty) = &*imp.self_ty else {
+                        continue;
+                    };
+                    let rust = self_ty.path.segments.last().unwrap().ident.to_string();
+                    let methods: Vec<Method> = imp
+                        .items
+                        .iter()
+                        .filter_map(|item| match item {
+                            ImplItem::Fn(f) => Some(method(&f.attrs, &f.sig, false)),
+                            _ => None,
+                        })
+                        .collect();
+                    let class = module
+                        .classes
+                        .iter_mut()
+                        .find(|c| c.rust == rust)
+                        .unwrap_or_else(|| panic!("#[pymethods] for unknown class {rust}"));
+                    class.methods.extend(methods);
+                }
+                Item::Fn(f) if find_attr(&f.attrs, "pyfunction").is_some() => {
+                    module.functions.push(method(&f.attrs, &f.sig, true));
+                }
+                Item::Fn(f) if find_attr(&f.attrs, "pymodule").is_some() => {
+                    for stmt in &f.block.stmts {
+                        if let syn::Stmt::Expr(expr, _) = stmt {
+                            collect_constant(module, expr);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `m.add("NAME", literal)?;` in the module initializer.
+    fn collect_constant(module: &mut Module, expr: &Expr) {
+        let expr = match expr {
+            Expr::Try(t) => &*t.expr,
+            other => other,
+        };
+        let Expr::MethodCall(call) = expr else {
+            return;
+        };
+        if call.method != "add" || call.args.len() != 2 {
+            return;
+        }
+        let (Expr::Lit(name), value) = (&call.args[0], &call.args[1]) else {
+            return;
+        };
+        let Lit::Str(name) = &name.lit else {
+            return;
+        };
+        let ty = match value {
+            Expr::Lit(lit) => literal_type(&lit.lit),
+            Expr::Macro(m) if m.mac.path.is_ident("vec") => {
+                let items = m
+                    .mac
+                    .parse_body_with(
+                        syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated,
+                    )
+                    .expect("vec! items");
+                match items.first() {
+                    Some(Expr::Lit(lit)) => literal_type(&lit.lit).map(|t| format!("list[{t}]")),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(ty) = ty {
+            module.constants.push((name.value(), ty));
+        }
+    }
+
+    fn literal_type(lit: &Lit) -> Option<String> {
+        Some(
+            match lit {
+                Lit::Int(_) => "int",
+                Lit::Float(_) => "float",
+                Lit::Str(_) => "str",
+                Lit::Bool(_) => "bool",
+                Lit::ByteStr(_) => "bytes",
+                _ => return None,
+            }
+            .to_string(),
+        )
+    }
+
+    fn method(attrs: &[Attribute], sig: &syn::Signature, function: bool) -> Method {
+        let mut kind = if function {
+            Kind::Static
+        } else {
+            Kind::Instance
+        };
+        let mut name = sig.ident.to_string();
+        let mut signature = None;
+        for attr in attrs {
+            let path = attr.path();
+            if path.is_ident("new") {
+                kind = Kind::New;
+            } else if path.is_ident("staticmethod") {
+                kind = Kind::Static;
+            } else if path.is_ident("classmethod") {
+                kind = Kind::Class;
+            } else if path.is_ident("getter") || path.is_ident("setter") {
+                let getter = path.is_ident("getter");
+                kind = if getter { Kind::Getter } else { Kind::Setter };
+                let prefix = if getter { "get_" } else { "set_" };
+                name = name.strip_prefix(prefix).unwrap_or(&name).to_string();
+                if let syn::Meta::List(list) = &attr.meta {
+                    name = list.tokens.to_string();
+                }
+            } else if path.is_ident("pyo3") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        name = meta.value()?.parse::<syn::LitStr>()?.value();
+                    } else if meta.path.is_ident("signature") {
+                        signature = Some(parse_signature(meta.value()?)?);
+                    } else if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<Expr>()?;
+                    }
+                    Ok(())
+                })
+                .unwrap_or_else(|e| panic!("#[pyo3] on {}: {e}", sig.ident));
+            }
+        }
+
+        // Arguments Python passes, by name: no receiver, `Python` token or class.
+        let mut args: Vec<(String, Type)> = Vec::new();
+        for (i, arg) in sig.inputs.iter().enumerate() {
+            let FnArg::Typed(arg) = arg else {
+                continue;
+            };
+            let Pat::Ident(ident) = &*arg.pat else {
+                continue;
+            };
+            let arg_name = ident.ident.to_string();
+            let receiver = i == 0 && (arg_name == "slf" || kind == Kind::Class);
+            if receiver || type_name(&arg.ty).as_deref() == Some("Python") {
+                continue;
+            }
+            args.push((arg_name, (*arg.ty).clone()));
+        }
+        let params = match signature {
+            Some(items) => items
+                .into_iter()
+                .map(|(name, default)| {
+                    let ty = args
+                        .iter()
+                        .find(|(n, _)| *n == name)
+                        .map(|(_, t)| t.clone());
+                    Param { name, ty, default }
+                })
+                .collect(),
+            None => args
+                .into_iter()
+                .map(|(name, ty)| Param {
+                    name,
+                    ty: Some(ty),
+                    default: None,
+                })
+                .collect(),
+        };
+        let ret = match &sig.output {
+            syn::ReturnType::Default => None,
+            syn::ReturnType::Type(_, ty) => Some((**ty).clone()),
+        };
+        Method {
+            name,
+            kind,
+            doc: doc_of(attrs),
+            params,
+            ret,
+        }
+    }
+
+    /// Items of `signature = (a, b=1, *, c=None)`: names with their defaults
+    /// rendered as Python, and the bare `*` / `/` markers.
+    fn parse_signature(input: ParseStream) -> syn::Result<Vec<(String, Option<String>)>> {
+        let content;
+        syn::parenthesized!(content in input);
+        let mut items = Vec::new();
+        while !content.is_empty() {
+            if content.peek(syn::Token![*]) {
+                content.parse::<syn::Token![*]>()?;
+                let mut name = "*".to_string();
+                if content.peek(syn::Token![*]) {
+                    content.parse::<syn::Token![*]>()?;
+                    name.push('*');
+                }
+                if content.peek(syn::Ident) {
+                    name.push_str(&content.parse::<syn::Ident>()?.to_string());
+                }
+                items.push((name, None));
+            } else if content.peek(syn::Token![/]) {
+                content.parse::<syn::Token![/]>()?;
+                items.push(("/".to_string(), None));
+            } else {
+                let name = content.parse::<syn::Ident>()?.to_string();
+                let default = if content.peek(syn::Token![=]) {
+                    content.parse::<syn::Token![=]>()?;
+                    Some(python_default(&content.parse::<Expr>()?))
+                } else {
+                    None
+                };
+                items.push((name, default));
+            }
+            if !content.is_empty() {
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(items)
+    }
+
+    /// A signature default as Python source, or `...` when it is not a literal.
+    fn python_default(expr: &Expr) -> String {
+        match expr {
+            Expr::Lit(lit) => match &lit.lit {
+                Lit::Str(s) => format!("{:?}", s.value()),
+                Lit::ByteStr(s) => format!("b{:?}", String::from_utf8_lossy(&s.value())),
+                Lit::Int(i) => i.base10_digits().to_string(),
+                Lit::Float(f) => f.base10_digits().to_string(),
+                Lit::Bool(b) => if b.value { "True" } else { "False" }.to_string(),
+                _ => "...".to_string(),
+            },
+            Expr::Path(p) if p.path.is_ident("None") => "None".to_string(),
+            Expr::Unary(u) if matches!(u.op, syn::UnOp::Neg(_)) => {
+                format!("-{}", python_default(&u.expr))
+            }
+            // `b"".to_vec()`, `"x".to_string()`: the literal is what Python sees.
+            Expr::MethodCall(call) => python_default(&call.receiver),
+            _ => "...".to_string(),
+        }
+    }
+
+    fn find_attr<'a>(attrs: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+        attrs.iter().find(|a| a.path().is_ident(name))
+    }
+
+    /// Whether `#[pyo3(...)]` among `attrs` lists `flag`.
+    fn pyo3_flag(attrs: &[Attribute], flag: &str) -> bool {
+        let mut found = false;
+        for attr in attrs.iter().filter(|a| a.path().is_ident("pyo3")) {
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= meta.path.is_ident(flag);
+                if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<Expr>()?;
+                }
+                Ok(())
+            });
+        }
+        found
+    }
+
+    /// The `///` comments of an item, as the docstring Python sees.
+    fn doc_of(attrs: &[Attribute]) -> String {
+        let lines: Vec<String> = attrs
+            .iter()
+            .filter(|a| a.path().is_ident("doc"))
+            .filter_map(|a| match &a.meta {
+                syn::Meta::NameValue(nv) => match &nv.value {
+                    Expr::Lit(syn::ExprLit {
+                        lit: Lit::Str(s), ..
+                    }) => Some(s.value()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .map(|line| line.strip_prefix(' ').unwrap_or(&line).to_string())
+            .collect();
+        lines.join("\n").trim().to_string()
+    }
+
+    fn expr_name(expr: &Expr) -> String {
+        match expr {
+            Expr::Path(p) => p.path.segments.last().unwrap().ident.to_string(),
+            _ => panic!("expected a name in create_exception!"),
+        }
+    }
+
+    /// Last path segment of a (possibly borrowed) type.
+    fn type_name(ty: &Type) -> Option<String> {
+        match ty {
+            Type::Path(p) => Some(p.path.segments.last()?.ident.to_string()),
+            Type::Reference(r) => type_name(&r.elem),
+            _ => None,
+        }
+    }
+
+    // --- Rust types as Python types --------------------------------------------------------
+
+    fn type_args(ty: &Type) -> Vec<&Type> {
+        let Type::Path(p) = ty else {
+            return Vec::new();
+        };
+        match &p.path.segments.last().unwrap().arguments {
+            syn::PathArguments::AngleBracketed(args) => args
+                .args
+                .iter()
+                .filter_map(|a| match a {
+                    syn::GenericArgument::Type(t) => Some(t),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn python_type(module: &Module, class: Option<&str>, ty: &Type) -> String {
+        let py = |t: &Type| python_type(module, class, t);
+        match ty {
+            Type::Reference(r) => py(&r.elem),
+            Type::Array(a) => format!("list[{}]", py(&a.elem)),
+            Type::Slice(s) => format!("list[{}]", py(&s.elem)),
+            Type::Tuple(t) if t.elems.is_empty() => "None".to_string(),
+            Type::Tuple(t) => format!(
+                "tuple[{}]",
+                t.elems.iter().map(py).collect::<Vec<_>>().join(", ")
+            ),
+            Type::Path(_) => {
+                let name = type_name(ty).unwrap();
+                let args = type_args(ty);
+                match name.as_str() {
+                    "String" | "str" | "char" | "PathBuf" | "PyString" => "str".to_string(),
+                    "bool" | "PyBool" => "bool".to_string(),
+                    "f32" | "f64" | "PyFloat" => "float".to_string(),
+                    "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32"
+                    | "i64" | "i128" | "isize" | "PyInt" => "int".to_string(),
+                    "PyBytes" => "bytes".to_string(),
+                    "PyByteArray" => "bytearray".to_string(),
+                    "PySlice" => "slice".to_string(),
+                    "PyType" => class.map_or("type".to_string(), |c| format!("type[{c}]")),
+                    "PyAny" => "Any".to_string(),
+                    "PyList" => "list[Any]".to_string(),
+                    "PyIterator" => "Iterator[Any]".to_string(),
+                    "PyTuple" => "tuple[Any, ...]".to_string(),
+                    "PyDict" => "dict[str, Any]".to_string(),
+                    "Option" => {
+                        let inner = py(args[0]);
+                        if inner.ends_with("| None") || inner == "Any" {
+                            inner
+                        } else {
+                            format!("{inner} | None")
+                        }
+                    }
+                    "Vec" | "VecDeque" => format!("list[{}]", py(args[0])),
+                    "HashMap" | "BTreeMap" => format!("dict[{}, {}]", py(args[0]), py(args[1])),
+                    "PyResult" | "Result" | "Py" | "Bound" | "Borrowed" | "PyRef" | "PyRefMut"
+                    | "Box" => py(args[0]),
+                    "Self" => class.expect("Self outside a class").to_string(),
+                    other => module
+                        .names
+                        .get(other)
+                        .cloned()
+                        .unwrap_or_else(|| panic!("no Python type for Rust type {other}")),
+                }
+            }
+            _ => "Any".to_string(),
+        }
+    }
+
+    // --- Types from docstrings -------------------------------------------------------------
+
+    /// Entries of a Google-style docstring section, with their continuation lines.
+    fn section(doc: &str, title: &str) -> Vec<String> {
+        let mut entries: Vec<String> = Vec::new();
+        for line in doc.lines().skip_while(|l| l.trim_end() != title).skip(1) {
+            if !line.is_empty() && !line.starts_with(' ') {
+                break;
+            }
+            let text = line.trim();
+            match entries.last_mut() {
+                Some(entry) if line.starts_with("     ") => {
+                    entry.push('\n');
+                    entry.push_str(text);
+                }
+                _ if !text.is_empty() => entries.push(text.to_string()),
+                _ => {}
+            }
+        }
+        entries
+    }
+
+    /// `name (type): description` entries of an `Args:` or `Attributes:`
+    /// section, as (name, type if given, description).
+    fn typed_entries(doc: &str, title: &str) -> Vec<(String, Option<String>, String)> {
+        section(doc, title)
+            .into_iter()
+            .filter_map(|entry| {
+                let (head, desc) = split_outside_brackets(&entry, ":")?;
+                let desc = desc.trim().to_string();
+                Some(match head.split_once(' ') {
+                    Some((name, ty)) if ty.starts_with('(') && ty.ends_with(')') => {
+                        let ty = ty[1..ty.len() - 1].to_string();
+                        (name.to_string(), Some(ty), desc)
+                    }
+                    _ => (head.to_string(), None, desc),
+                })
+            })
+            .collect()
+    }
+
+    /// The type of a `type: description` line, if it starts with one.
+    fn leading_type<'a>(module: &Module, line: &'a str) -> Option<&'a str> {
+        let (ty, rest) = split_outside_brackets(line, ":")?;
+        let described = rest.is_empty() || rest.starts_with(char::is_whitespace);
+        (described && is_type(module, ty)).then_some(ty)
+    }
+
+    fn split_outside_brackets<'a>(text: &'a str, sep: &str) -> Option<(&'a str, &'a str)> {
+        let mut depth = 0i32;
+        let mut quoted = false;
+        for (i, c) in text.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                _ if quoted => {}
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ if depth == 0 && text[i..].starts_with(sep) => {
+                    return Some((&text[..i], &text[i + sep.len()..]));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Whether `text` is a type built from names the stub knows.
+    fn is_type(module: &Module, text: &str) -> bool {
+        let mut quoted = false;
+        let mut word = String::new();
+        let mut names = Vec::new();
+        for c in text.chars().chain([' ']) {
+            if c == '"' {
+                quoted = !quoted;
+            } else if quoted {
+            } else if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+            } else {
+                if !word.is_empty() {
+                    names.push(std::mem::take(&mut word));
+                }
+                if !" []|,.".contains(c) {
+                    return false;
+                }
+            }
+        }
+        !names.is_empty()
+            && names
+                .iter()
+                .all(|n| KNOWN_NAMES.contains(&n.as_str()) || module.names.values().any(|c| c == n))
+    }
+
+    fn checked_type(module: &Module, ty: &str, context: &str) -> String {
+        assert!(is_type(module, ty), "{context}: unknown type {ty:?}");
+        ty.to_string()
+    }
+
+    // --- Rendering the stub ------------------------------------------------------------------
+
+    fn render(module: &Module) -> String {
+        let mut body = String::new();
+        for (name, ty) in &module.constants {
+            writeln!(body, "{name}: {ty}").unwrap();
+        }
+        for class in &module.classes {
+            body.push('\n');
+            render_class(module, class, &mut body);
+        }
+        for function in &module.functions {
+            body.push('\n');
+            render_method(module, None, function, "", &mut body);
+        }
+
+        // Import what the signatures use; docstrings do not count.
+        let code: String = body
+            .split("\"\"\"")
+            .step_by(2)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let words: BTreeSet<&str> = code
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .collect();
+        let mut out = String::from(
+            "\"\"\"Typing stubs for the compiled `_bitalino_core` extension.\n\n\
+             Generated by build.rs from the PyO3 bindings in src/python.rs and src/python/;\n\
+             edit the Rust signatures and docstrings instead of this file.\n\"\"\"\n\n",
+        );
+        let mut from = "";
+        let mut names: Vec<&str> = Vec::new();
+        for (module_name, name) in IMPORTS.iter().chain([&("", "")]) {
+            if *module_name != from {
+                if !names.is_empty() {
+                    writeln!(out, "from {from} import {}", names.join(", ")).unwrap();
+                }
+                from = module_name;
+                names.clear();
+            }
+            if words.contains(name) {
+                names.push(name);
+            }
+        }
+        out.push('\n');
+        out.push_str(&body);
+        out
+    }
+
+    fn render_class(module: &Module, class: &Class, out: &mut String) {
+        let base = class
+            .base
+            .as_ref()
+            .map(|b| format!("({})", module.names.get(b).unwrap_or(b)))
+            .unwrap_or_default();
+        writeln!(out, "class {}{}:", class.name, base).unwrap();
+        let attributes = typed_entries(&class.doc, "Attributes:");
+        if !class.doc.is_empty() {
+            docstring(&class.doc, "    ", out);
+        }
+
+        // Each member renders on its own, separated by blank lines.
+        let mut members: Vec<String> = Vec::new();
+        // Exceptions have no Rust fields: their attributes are documented only.
+        if class.base.is_some() && !attributes.is_empty() {
+            let mut lines = String::new();
+            for (name, ty, _) in &attributes {
+                let ty = ty
+                    .as_deref()
+                    .unwrap_or_else(|| panic!("{}.{name} needs a type", class.name));
+                writeln!(
+                    lines,
+                    "    {name}: {}",
+                    checked_type(module, ty, &class.name)
+                )
+                .unwrap();
+            }
+            members.push(lines);
+        }
+        let attribute = |name: &str| attributes.iter().find(|(n, _, _)| *n == name);
+        for (name, ty) in &class.fields {
+            let (ty, doc) = match attribute(name) {
+                Some((_, Some(ty), desc)) => (checked_type(module, ty, name), desc.clone()),
+                Some((_, None, desc)) => (python_type(module, Some(&class.name), ty), desc.clone()),
+                None => (python_type(module, Some(&class.name), ty), String::new()),
+            };
+            let getter = Method {
+                name: name.clone(),
+                kind: Kind::Getter,
+                doc,
+                params: Vec::new(),
+                ret: None,
+            };
+            let mut member = String::new();
+            render_property(&getter, &ty, &mut member);
+            members.push(member);
+        }
+        for method in &class.methods {
+            let mut member = String::new();
+            if method.kind == Kind::Getter {
+                let ty = getter_type(module, class, method, attribute(&method.name));
+                render_property(method, &ty, &mut member);
+            } else {
+                render_method(module, Some(class), method, "    ", &mut member);
+            }
+            members.push(member);
+        }
+        if class.eq {
+            members.push("    def __eq__(self, other: object) -> bool: ...\n".to_string());
+        }
+        if members.is_empty() && class.doc.is_empty() {
+            members.push("    ...\n".to_string());
+        }
+        if !class.doc.is_empty() && !members.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&members.join("\n"));
+    }
+
+    /// Property type: the getter's leading `type:`, else the class's
+    /// `Attributes` entry, else the Rust return type.
+    fn getter_type(
+        module: &Module,
+        class: &Class,
+        getter: &Method,
+        attribute: Option<&(String, Option<String>, String)>,
+    ) -> String {
+        let first = getter.doc.lines().next().unwrap_or_default();
+        if let Some(ty) = leading_type(module, first) {
+            return ty.to_string();
+        }
+        if let Some((_, Some(ty), _)) = attribute {
+            return checked_type(module, ty, &getter.name);
+        }
+        let ret = getter.ret.as_ref().expect("getter returns a value");
+        python_type(module, Some(&class.name), ret)
+    }
+
+    fn render_property(getter: &Method, ty: &str, out: &mut String) {
+        writeln!(out, "    @property").unwrap();
+        def(
+            &getter.name,
+            &["self".to_string()],
+            ty,
+            "    ",
+            &getter.doc,
+            out,
+        );
+    }
+
+    fn render_method(
+        module: &Module,
+        class: Option<&Class>,
+        method: &Method,
+        indent: &str,
+        out: &mut String,
+    ) {
+        let class_name = class.map(|c| c.name.as_str());
+        let context = format!("{}.{}", class_name.unwrap_or("<module>"), method.name);
+        let args = typed_entries(&method.doc, "Args:");
+        let mut params: Vec<String> = match method.kind {
+            Kind::Static => Vec::new(),
+            Kind::Class => vec!["cls".to_string()],
+            _ => vec!["self".to_string()],
+        };
+        for param in &method.params {
+            if param.name.starts_with('*') || param.name == "/" {
+                params.push(param.name.clone());
+                continue;
+            }
+            let documented = args.iter().find(|(n, _, _)| *n == param.name);
+            let ty = match (documented, &param.ty) {
+                (Some((_, Some(ty), _)), _) => checked_type(module, ty, &context),
+                // A setter takes what its getter returns, except None.
+                (_, Some(ty)) if method.kind == Kind::Setter => {
+                    match python_type(module, class_name, ty).as_str() {
+                        "Any" => class
+                            .and_then(|c| {
+                                c.methods
+                                    .iter()
+                                    .find(|m| m.kind == Kind::Getter && m.name == method.name)
+                            })
+                            .map(|getter| {
+                                let ty = getter_type(module, class.unwrap(), getter, None);
+                                ty.trim_end_matches(" | None").to_string()
+                            })
+                            .unwrap_or_else(|| "Any".to_string()),
+                        ty => ty.to_string(),
+                    }
+                }
+                (_, Some(ty)) => python_type(module, class_name, ty),
+                (_, None) => panic!("{context}: no argument for signature entry {}", param.name),
+            };
+            params.push(match &param.default {
+                Some(default) => format!("{}: {} = {}", param.name, ty, default),
+                None => format!("{}: {}", param.name, ty),
+            });
+        }
+
+        // A `Returns:` entry reading `type: description` gives the type.
+        let returns = section(&method.doc, "Returns:");
+        let ret = match method.kind {
+            Kind::New | Kind::Setter => "None".to_string(),
+            _ => match returns.first().and_then(|r| leading_type(module, r)) {
+                Some(ty) => ty.to_string(),
+                None => match &method.ret {
+                    Some(ty) => {
+                        let ty = python_type(module, class_name, ty);
+                        // Returning `None` from these ends the iteration.
+                        if matches!(method.name.as_str(), "__next__" | "__anext__") {
+                            ty.trim_end_matches(" | None").to_string()
+                        } else {
+                            ty
+                        }
+                    }
+                    None => "None".to_string(),
+                },
+            },
+        };
+
+        let name = match method.kind {
+            Kind::New => "__init__",
+            _ => &method.name,
+        };
+        let decorator = match method.kind {
+            Kind::Static if class.is_some() => Some("@staticmethod".to_string()),
+            Kind::Class => Some("@classmethod".to_string()),
+            Kind::Setter => Some(format!("@{name}.setter")),
+            _ => None,
+        };
+        let overloads = overloads(&method.doc);
+        for (variant, variant_ret) in &overloads {
+            writeln!(out, "{indent}@overload").unwrap();
+            let mut params = params[..1].to_vec();
+            params.extend(variant.iter().map(|p| {
+                let (_, ty) = p
+                    .split_once(": ")
+                    .unwrap_or_else(|| panic!("{context}: {p:?}"));
+                checked_type(module, ty.split(" = ").next().unwrap(), &context);
+                p.clone()
+            }));
+            let ret = checked_type(module, variant_ret, &context);
+            def(name, &params, &ret, indent, "", out);
+        }
+        if overloads.is_empty() {
+            if let Some(decorator) = decorator {
+                writeln!(out, "{indent}{decorator}").unwrap();
+            }
+            def(name, &params, &ret, indent, &method.doc, out);
+        }
+    }
+
+    /// `Overloads:` entries as (parameters, return type).
+    fn overloads(doc: &str) -> Vec<(Vec<String>, String)> {
+        section(doc, "Overloads:")
+            .into_iter()
+            .map(|line| {
+                let (params, ret) = line
+                    .split_once(" -> ")
+                    .unwrap_or_else(|| panic!("overload {line:?} needs a return type"));
+                let params = params.trim_start_matches('(').trim_end_matches(')');
+                let params = params
+                    .split(", ")
+                    .filter(|p| !p.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                (params, ret.trim().to_string())
+            })
+            .collect()
+    }
+
+    fn def(name: &str, params: &[String], ret: &str, indent: &str, doc: &str, out: &mut String) {
+        let one_line = format!("{indent}def {name}({}) -> {ret}:", params.join(", "));
+        if one_line.len() + 4 <= LINE_WIDTH {
+            out.push_str(&one_line);
+        } else {
+            writeln!(out, "{indent}def {name}(").unwrap();
+            for param in params {
+                writeln!(out, "{indent}    {param},").unwrap();
+            }
+            write!(out, "{indent}) -> {ret}:").unwrap();
+        }
+        if doc.is_empty() {
+            out.push_str(" ...\n");
+        } else {
+            out.push('\n');
+            docstring(doc, &format!("{indent}    "), out);
+        }
+    }
+
+    fn docstring(doc: &str, indent: &str, out: &mut String) {
+        let doc = doc.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"");
+        if !doc.contains('\n') {
+            writeln!(out, "{indent}\"\"\"{doc}\"\"\"").unwrap();
+            return;
+        }
+        writeln!(out, "{indent}\"\"\"").unwrap();
+        for line in doc.lines() {
+            if line.is_empty() {
+                out.push('\n');
+            } else {
+                writeln!(out, "{indent}{line}").unwrap();
+            }
+        }
+        writeln!(out, "{indent}\"\"\"").unwrap();
+    }
+}
@@ -6,7 +6,7 @@ import argparse
 import random
 import time
 
-from bitalino_rs import Bitalino
+from bitalino_rs import Bitalino, BluetoothError
 
 
 def parse_args() -> argparse.Namespace:
@@ -30,7 +30,7 @@ def connect_with_retries(mac: str, retries: int, base: float, cap: float) -> Bit
     for attempt in range(retries):
         try:
             return Bitalino.connect(mac)
-        except Exception as exc:  # pragma: no cover - runtime behavior
+        except BluetoothError as exc:  # pragma: no cover - runtime behavior
             last_error = exc
             wait = backoff(base, cap, attempt)
             print(f"connect failed ({exc}); retrying in {wait:.2f}s ({attempt + 1}/{retries})")
@@ -1,4 +1,11 @@
-"""Stream timed batches to CSV with timestamps and integrity columns."""
+"""Stream timed batches to CSV with timestamps and integrity columns.
+
+The first line is a ``# acquisition_start_utc: ...`` comment giving the wall-clock
+start that ``batch_timestamp_us`` counts from, followed by comments identifying
+the device. A marker is placed with
+``annotate()`` at the start of every batch; the markers are written next to the
+stream as ``<out>.annotations.csv``.
+"""
 
 from __future__ import annotations
 
@@ -38,16 +45,30 @@ def write_header(writer: csv.writer) -> None:
     )
 
 
+def write_annotations(path: Path, annotations: list) -> None:
+    with path.open("w", newline="") as f:
+        writer = csv.writer(f)
+        writer.writerow(["sample_index", "timestamp_us", "label"])
+        for a in annotations:
+            writer.writerow([a.sample_index, a.timestamp_us, a.label])
+
+
 def main() -> int:
     args = parse_args()
     dev = Bitalino.connect(args.mac)
     dev.start(rate=args.rate, channels=[0, 1, 2, 3])
 
     with args.out.open("w", newline="") as f:
+        f.write(f"# acquisition_start_utc: {dev.acquisition_start_utc().isoformat()}\n")
+        info = dev.device_info()
+        f.write(f"# device: {info.mac}\n")
+        f.write(f"# firmware: {info.firmware_version}\n")
+        f.write(f"# connection: {info.connection_kind}\n")
         writer = csv.writer(f)
         write_header(writer)
 
         for batch_idx in range(args.batches):
+            dev.annotate(f"batch {batch_idx + 1}")
             batch = dev.read_timed(args.frames)
             for i, frame in enumerate(batch.frames):
                 writer.writerow(
@@ -67,8 +88,10 @@ def main() -> int:
             )
             time.sleep(0.05)
 
+    annotations_path = args.out.with_suffix(".annotations.csv")
+    write_annotations(annotations_path, dev.take_annotations())
     dev.stop()
-    print(f"Wrote {args.out}")
+    print(f"Wrote {args.out} and {annotations_path}")
     return 0
 
 
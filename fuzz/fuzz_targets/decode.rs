@@ -0,0 +1,41 @@
+//! Arbitrary bytes through the frame decoders and the CRC check.
+//!
+//! The first byte picks the channel count, including invalid ones; the rest
+//! is the stream. Besides not panicking, the batch decoder must agree with the
+//! per-frame decoder and the CRC check frame by frame.
+
+#![no_main]
+
+use bitalino_rs::decode::{check_crc, decode_frame, decode_frames, frame_size};
+use bitalino_rs::MAX_ANALOG_CHANNELS;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, bytes)) = data.split_first() else {
+        return;
+    };
+    let n_channels = selector as usize % (MAX_ANALOG_CHANNELS + 2);
+
+    // Any slice, not only whole frames, gets an answer.
+    check_crc(bytes);
+    for len in 0..bytes.len().min(16) {
+        check_crc(&bytes[..len]);
+    }
+
+    let mut batch = Vec::new();
+    decode_frames(bytes, n_channels, &mut batch);
+    if n_channels == 0 || n_channels > MAX_ANALOG_CHANNELS {
+        assert!(batch.is_empty());
+        return;
+    }
+
+    let size = frame_size(n_channels);
+    assert_eq!(batch.len(), bytes.len() / size);
+    for (chunk, &(frame, crc_ok)) in bytes.chunks_exact(size).zip(&batch) {
+        assert_eq!(frame, decode_frame(chunk, n_channels));
+        assert_eq!(crc_ok, check_crc(chunk));
+        assert_eq!(frame.analog_values().len(), n_channels);
+        assert!(frame.seq < 16);
+        assert!(frame.digital.iter().all(|&bit| bit <= 1));
+    }
+});
@@ -0,0 +1,41 @@
+//! Arbitrary bytes received during acquisition, through the driver's read
+//! path: buffering, CRC checks, sequence tracking and gap filling.
+//!
+//! The first two bytes pick the channels and the read options; the rest is
+//! replayed as if the device had sent it. Reads go on until the stream runs
+//! out, which the driver reports as a closed link.
+
+#![no_main]
+
+use bitalino_rs::{Bitalino, GapFill, ReadOptions, ReplayTransport};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let [channels, settings, stream @ ..] = data else {
+        return;
+    };
+    // Bit i of the first byte selects channel i; at least one is active.
+    let channels: Vec<u8> = (0..6).filter(|i| channels & (1 << i) != 0).collect();
+    let channels = if channels.is_empty() {
+        vec![0]
+    } else {
+        channels
+    };
+    let options = ReadOptions {
+        gap_fill: match settings % 3 {
+            0 => GapFill::Off,
+            1 => GapFill::HoldLast,
+            _ => GapFill::Nan,
+        },
+        ..ReadOptions::default()
+    };
+    let batch_size = 1 + (*settings as usize >> 2);
+
+    let transport = ReplayTransport::from_bytes(stream.to_vec());
+    let mut dev = Bitalino::from_transport(Box::new(transport));
+    dev.start(1000, channels).expect("replay accepts start");
+    while let Ok(batch) = dev.read_frames_timed_with(batch_size, &options) {
+        // Corrupt frames are dropped and counted; gap filling only adds frames.
+        assert!(batch.frames.len() + batch.crc_errors >= batch_size || batch.partial);
+    }
+});
@@ -0,0 +1,130 @@
+//! Periodic battery monitoring with a low-battery callback.
+//!
+//! The BITalino only reports battery voltage through `state()`, which is not
+//! answered during acquisition, so checks while streaming briefly pause it
+//! (`Bitalino::state_during_acquisition()`). [`BatteryMonitor`] also keeps the
+//! last real measurements (taken whenever `state()` runs) and extrapolates the
+//! discharge curve from them when a check cannot reach the device, so a
+//! recording can be wound down before the device dies.
+
+use std::time::{Duration, Instant};
+
+/// A battery voltage sample delivered to the monitor callback.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryReading {
+    /// Battery voltage in Volts.
+    pub voltage: f32,
+    /// `true` when extrapolated from earlier measurements because the device
+    /// could not be queried.
+    pub estimated: bool,
+}
+
+/// Low-battery watchdog driven by `Bitalino::poll_battery()` while idle and
+/// by reads during acquisition; it never runs on its own.
+///
+/// The callback fires on every poll (at most once per `interval`) whose reading
+/// is below `threshold_volts`.
+pub struct BatteryMonitor {
+    interval: Duration,
+    threshold_volts: f32,
+    callback: Box<dyn FnMut(BatteryReading) + Send>,
+    last_poll: Option<Instant>,
+    /// The two most recent real measurements, oldest first.
+    measurements: [Option<(Instant, f32)>; 2],
+}
+
+impl BatteryMonitor {
+    /// Create a monitor that checks every `interval` and calls `callback` when the
+    /// voltage is below `threshold_volts`.
+    pub fn new<F>(interval: Duration, threshold_volts: f32, callback: F) -> Self
+    where
+        F: FnMut(BatteryReading) + Send + 'static,
+    {
+        Self {
+            interval,
+            threshold_volts,
+            callback: Box::new(callback),
+            last_poll: None,
+            measurements: [None, None],
+        }
+    }
+
+    /// Whether `interval` has elapsed since the previous poll.
+    pub(crate) fn is_due(&self, now: Instant) -> bool {
+        self.last_poll
+            .map_or(true, |last| now.duration_since(last) >= self.interval)
+    }
+
+    /// Record a voltage read from the device.
+    pub(crate) fn record_measurement(&mut self, at: Instant, voltage: f32) {
+        self.measurements = [self.measurements[1], Some((at, voltage))];
+    }
+
+    /// Extrapolate the voltage at `at` from the last two measurements (or return
+    /// the last one if only one exists).
+    pub(crate) fn estimate(&self, at: Instant) -> Option<f32> {
+        match self.measurements {
+            [Some((t0, v0)), Some((t1, v1))] if t1 > t0 => {
+                let slope = (v1 - v0) / t1.duration_since(t0).as_secs_f32();
+                // Batteries do not recharge while streaming; ignore upward noise.
+                let slope = slope.min(0.0);
+                Some(v1 + slope * at.saturating_duration_since(t1).as_secs_f32())
+            }
+            [_, Some((_, v))] => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Mark a poll at `now` and fire the callback if `reading` is below threshold.
+    pub(crate) fn report(&mut self, now: Instant, reading: Option<BatteryReading>) {
+        self.last_poll = Some(now);
+        if let Some(reading) = reading {
+            if reading.voltage < self.threshold_volts {
+                (self.callback)(reading);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn estimate_extrapolates_discharge() {
+        let mut monitor = BatteryMonitor::new(Duration::from_secs(60), 3.5, |_| {});
+        let t0 = Instant::now();
+        assert_eq!(monitor.estimate(t0), None);
+
+        monitor.record_measurement(t0, 3.9);
+        assert_eq!(monitor.estimate(t0 + Duration::from_secs(10)), Some(3.9));
+
+        monitor.record_measurement(t0 + Duration::from_secs(100), 3.8);
+        let v = monitor.estimate(t0 + Duration::from_secs(300)).unwrap();
+        assert!((v - 3.6).abs() < 1e-4, "got {v}");
+    }
+
+    #[test]
+    fn callback_fires_only_below_threshold_and_when_due() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let mut monitor = BatteryMonitor::new(Duration::from_secs(60), 3.5, move |r| {
+            sink.lock().unwrap().push(r.voltage)
+        });
+        let now = Instant::now();
+        assert!(monitor.is_due(now));
+
+        let reading = |voltage| {
+            Some(BatteryReading {
+                voltage,
+                estimated: false,
+            })
+        };
+        monitor.report(now, reading(3.7));
+        assert!(!monitor.is_due(now + Duration::from_secs(30)));
+        monitor.report(now + Duration::from_secs(60), reading(3.4));
+
+        assert_eq!(*seen.lock().unwrap(), vec![3.4]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Software BITalino for developing acquisition code without a device.
+//!
+//! Serves the device protocol on a TCP port (connect with
+//! `Bitalino::connect_tcp`, `Bitalino.connect_tcp()` in Python) or on a
+//! pseudo-terminal (open the path it prints with `Bitalino::connect_serial`,
+//! `Bitalino(path)` in Python).
+
+use std::process::exit;
+
+use anyhow::{Context, Result};
+use bitalino_rs::emulator::{DigitalSignal, Emulator, Signal};
+use bitalino_rs::Preset;
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "bitalino-emulator",
+    about = "Emulate a BITalino over TCP or a pseudo-terminal"
+)]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8500", conflicts_with = "pty")]
+    tcp: String,
+    /// Serve on a pseudo-terminal instead and print its path (Unix only)
+    #[arg(long)]
+    pty: bool,
+    /// Firmware version string to report
+    #[arg(long, default_value = "BITalino_v5.2")]
+    firmware: String,
+    /// Drive the inputs of a sensor preset (ecg, emg, eda, eeg, eda_ecg,
+    /// psychophysiology) with typical signals of their sensors
+    #[arg(long)]
+    preset: Option<String>,
+    /// Signal on an analog channel, as CHANNEL=SPEC with CHANNEL 0-5 and SPEC
+    /// one of sine[:HZ[:AMPLITUDE]], ecg[:BPM[:AMPLITUDE]],
+    /// eda[:LEVEL_US[:SCR_INTERVAL[:SCR_AMPLITUDE_US[:DRIFT_US_PER_MIN]]]],
+    /// noise[:AMPLITUDE] or const:VALUE; repeat for several channels
+    #[arg(long = "signal", value_name = "CHANNEL=SPEC", value_parser = parse_signal)]
+    signals: Vec<(usize, Signal)>,
+    /// Level of a digital input, as INPUT=SPEC with INPUT 1 (I1) or 2 (I2) and
+    /// SPEC one of low, high or square[:HZ[:DUTY]]
+    #[arg(long = "input", value_name = "INPUT=SPEC", value_parser = parse_input)]
+    inputs: Vec<(usize, DigitalSignal)>,
+    /// Raw battery reading in the state reply (0-1023)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(0..=1023))]
+    battery: Option<u16>,
+}
+
+fn parse_signal(arg: &str) -> Result<(usize, Signal), String> {
+    let (channel, spec) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected CHANNEL=SPEC, got {arg:?}"))?;
+    let channel = channel
+        .parse::<usize>()
+        .ok()
+        .filter(|&ch| ch < bitalino_rs::MAX_ANALOG_CHANNELS)
+        .ok_or_else(|| format!("channel must be 0-5, got {channel:?}"))?;
+    Ok((channel, spec.parse()?))
+}
+
+fn parse_input(arg: &str) -> Result<(usize, DigitalSignal), String> {
+    let (input, spec) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected INPUT=SPEC, got {arg:?}"))?;
+    let input = match input {
+        "1" => 0,
+        "2" => 1,
+        _ => return Err(format!("input must be 1 or 2, got {input:?}")),
+    };
+    Ok((input, spec.parse()?))
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {e:#}");
+        exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let cli = Cli::parse();
+    bitalino_rs::init_rust_logging();
+
+    let mut emulator = Emulator::new().version(cli.firmware);
+    if let Some(name) = &cli.preset {
+        emulator = emulator.preset(&Preset::from_name(name)?);
+    }
+    for (channel, signal) in cli.signals {
+        emulator = emulator.signal(channel, signal);
+    }
+    for (input, signal) in cli.inputs {
+        emulator = emulator.input(input, signal);
+    }
+    if let Some(raw) = cli.battery {
+        emulator = emulator.battery(raw);
+    }
+
+    if cli.pty {
+        #[cfg(unix)]
+        {
+            let pty = bitalino_rs::emulator::EmulatorPty::open()
+                .context("Failed to allocate a pseudo-terminal")?;
+            println!("{}", pty.path().display());
+            return emulator.serve_pty(&pty).context("Emulator stopped");
+        }
+        #[cfg(not(unix))]
+        anyhow::bail!("--pty is only available on Unix");
+    }
+    println!("Listening on {}", cli.tcp);
+    emulator
+        .serve_tcp(&cli.tcp)
+        .with_context(|| format!("Failed to serve on {}", cli.tcp))
+}
@@ -15,47 +15,122 @@
 //! - Data may arrive in bursts due to Bluetooth buffering
 //! - The 4-bit sequence number (0-15) allows detection of dropped frames
 
-use std::io::{ErrorKind, Read, Write};
-use std::time::{Duration, Instant};
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{Context, Result};
-use log::{debug, warn};
-
-use crate::bluetooth::RfcommStream;
+use log::debug;
+
+use crate::battery::{BatteryMonitor, BatteryReading};
+use crate::bluetooth::{LinkQuality, RfcommStream};
+use crate::config::Profile;
+use crate::decode;
+use crate::dsp::{Beat, EdaDecomposer, EdaLevel, QrsDetector, Scr};
+use crate::errors::DriverError;
+use crate::events::{Annotation, DigitalEdge, EdgeDetector, OutputCommand, OutputEvent};
+use crate::firmware::{Capabilities, FirmwareVersion};
+#[cfg(feature = "metrics")]
+use crate::health;
+use crate::presets::Preset;
+use crate::pwm::{self, PwmSchedule};
+use crate::recording::RecordingInfo;
+use crate::registry::DeviceRegistry;
+use crate::sensors::{resolution_bits, Sensor, RESOLUTION_BITS};
+use crate::serial::{self, SerialConfig, SerialPortEntry};
+use crate::session::Session;
+use crate::stats::{AcquisitionStats, ChannelStats, Counters, MovingStats, DEFAULT_STATS_WINDOW};
+use crate::streaming::{Fanout, Stage};
+use crate::tcp::TcpTransport;
+use crate::throughput::{ThroughputMonitor, ThroughputWarning};
+use crate::timing::TimingModel;
 
 // ============================================================================
 // Constants
 // ============================================================================
 
 /// Default serial baud rate for BITalino devices
-#[allow(dead_code)]
-const BAUD_RATE: u32 = 115200;
+pub(crate) const BAUD_RATE: u32 = 115200;
 
 /// Default timeout for serial/RFCOMM operations
-#[allow(dead_code)]
-const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
-
-/// Command delay to ensure device processes the command
-const COMMAND_DELAY: Duration = Duration::from_millis(50);
-
-/// Delay after stop command before device is ready for new commands
-const STOP_DELAY: Duration = Duration::from_millis(200);
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Command delay to ensure device processes the command; none in fuzzing
+/// builds, which have no device behind the transport
+pub(crate) const COMMAND_DELAY: Duration = if cfg!(fuzzing) {
+    Duration::ZERO
+} else {
+    Duration::from_millis(50)
+};
+
+/// Minimum spacing between link quality samples taken by the read path
+const LINK_QUALITY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay after stop command before device is ready for new commands; none in
+/// fuzzing builds
+const STOP_DELAY: Duration = if cfg!(fuzzing) {
+    Duration::ZERO
+} else {
+    Duration::from_millis(200)
+};
+
+/// How long the first `version()` listens for frames from an acquisition
+/// left running; from 10 Hz up a streaming device sends one this often
+const STREAM_PROBE: Duration = if cfg!(fuzzing) {
+    Duration::ZERO
+} else {
+    Duration::from_millis(100)
+};
+
+/// How long before a scheduled start `sleep_until` stops sleeping and spins,
+/// since a sleep can overshoot by a scheduler tick
+const START_SPIN: Duration = Duration::from_millis(2);
+
+/// Bytes per second the device's 115200 baud Bluetooth bridge carries (8N1:
+/// 10 bits on the wire per byte)
+const LINK_BYTES_PER_SEC: usize = BAUD_RATE as usize / 10;
+
+/// Bytes requested from the transport by each read of the frame path: half a
+/// second of frames at 1000 Hz with every channel active
+const READ_CHUNK: usize = 4096;
 
 /// Maximum time to wait for version string
 const VERSION_TIMEOUT: Duration = Duration::from_secs(2);
 
+/// Device label for logs, metrics and traces until a MAC or address is known
+const DEFAULT_DEVICE_LABEL: &str = "bitalino";
+
+/// `log` record prefixed with a device label (`[20:16:10:00:3D:4F] message`),
+/// so lines from drivers running side by side in one process can be told apart.
+macro_rules! device_log {
+    ($level:ident, $label:expr, $($arg:tt)+) => {
+        log::$level!("[{}] {}", $label, format_args!($($arg)+))
+    };
+}
+pub(crate) use device_log;
+
+/// Number of analog inputs on a BITalino (A1-A6)
+pub const MAX_ANALOG_CHANNELS: usize = 6;
+
+/// Largest frame size in bytes (all 6 analog channels active)
+const MAX_FRAME_SIZE: usize = 8;
+
 // BITalino protocol commands
 const CMD_STOP: u8 = 0x00;
 const CMD_VERSION: u8 = 0x07;
 #[allow(dead_code)]
 const CMD_STATE: u8 = 0x0B; // BITalino 2.0+ only
 #[allow(dead_code)]
-const CMD_PWM_PREFIX: u8 = 0xA3; // BITalino 2.0+ only, followed by PWM value
+pub(crate) const CMD_PWM_PREFIX: u8 = 0xA3; // BITalino 2.0+ only, followed by PWM value
 #[allow(dead_code)]
 const CMD_TRIGGER_2: u8 = 0xB3; // BITalino 2.0 digital outputs base command
 #[allow(dead_code)]
 const CMD_TRIGGER_1: u8 = 0x03; // BITalino 1.0 digital outputs base command
-#[allow(dead_code)]
 const CMD_IDLE: u8 = 0xFF; // BITalino 2.0+ go to idle from any state
 
 // ============================================================================
@@ -80,6 +155,14 @@ pub enum SamplingRate {
 }
 
 impl SamplingRate {
+    /// Every rate the device supports, slowest first.
+    pub const ALL: [SamplingRate; 4] = [
+        SamplingRate::Hz1,
+        SamplingRate::Hz10,
+        SamplingRate::Hz100,
+        SamplingRate::Hz1000,
+    ];
+
     /// Convert sampling rate to protocol bits for the set-rate command.
     /// Used internally when setting device sampling rate.
     pub fn to_bits(self) -> u8 {
@@ -107,13 +190,19 @@ impl SamplingRate {
     pub fn period_us(self) -> u64 {
         1_000_000 / (self as u64)
     }
+
+    /// Number of samples needed to cover `duration`, rounded up.
+    pub fn frames_in(self, duration: Duration) -> usize {
+        (duration.as_nanos() * self as u128).div_ceil(1_000_000_000) as usize
+    }
 }
 
 /// A single data frame from the BITalino device.
 ///
 /// Each frame contains one sample from all active channels, plus metadata.
-/// Frames arrive at the configured sampling rate.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Frames arrive at the configured sampling rate. Analog values are stored
+/// inline, so decoding a frame never allocates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Frame {
     /// Sequence number (0-15, wraps around).
     /// Use this to detect dropped frames: if `(new_seq - old_seq) % 16 != 1`, frames were lost.
@@ -121,20 +210,78 @@ pub struct Frame {
     /// Digital input channels (4 channels: I1, I2, O1, O2).
     /// Each value is 0 or 1.
     pub digital: [u8; 4],
-    /// Analog channel values (10-bit resolution, 0-1023).
-    /// The number of values matches the channels configured in `start()`.
-    pub analog: Vec<u16>,
+    /// Analog channel values, in the order of the channels configured in
+    /// `start()`: 10-bit (0-1023), except the fifth and sixth values of 5- and
+    /// 6-channel frames, which are 6-bit (0-63); see
+    /// [`resolution_bits`](Self::resolution_bits). Only the first `n_channels`
+    /// entries are meaningful; the rest are zero. Prefer
+    /// [`analog_values`](Self::analog_values).
+    pub analog: [u16; MAX_ANALOG_CHANNELS],
+    /// Number of analog channels carried by this frame.
+    pub n_channels: u8,
+    /// Absolute sample index since `start()`, counting frames lost to CRC
+    /// errors or sequence gaps, so it advances with the device's sample clock.
+    /// Zero for frames not produced by a read.
+    pub sample_index: u64,
+    /// `true` for a placeholder standing in for a frame lost on the link,
+    /// inserted by [`ReadOptions::gap_fill`]; see [`GapFill`] for its values.
+    pub filled: bool,
 }
 
 impl Frame {
     /// Create a new frame with the given values.
+    ///
+    /// # Panics
+    /// Panics if `analog` holds more than [`MAX_ANALOG_CHANNELS`] values.
     #[inline]
-    pub fn new(seq: u8, digital: [u8; 4], analog: Vec<u16>) -> Self {
+    #[allow(dead_code)]
+    pub fn new(seq: u8, digital: [u8; 4], analog: &[u16]) -> Self {
+        let mut values = [0u16; MAX_ANALOG_CHANNELS];
+        values[..analog.len()].copy_from_slice(analog);
         Self {
             seq,
             digital,
-            analog,
+            analog: values,
+            n_channels: analog.len() as u8,
+            sample_index: 0,
+            filled: false,
+        }
+    }
+
+    /// Placeholder for the lost frame with sequence number `seq`.
+    ///
+    /// Keeps this frame's digital levels, and its analog values for
+    /// [`GapFill::HoldLast`] or zeros for [`GapFill::Nan`].
+    fn placeholder(&self, seq: u8, sample_index: u64, mode: GapFill) -> Self {
+        let mut frame = *self;
+        frame.seq = seq;
+        frame.sample_index = sample_index;
+        frame.filled = true;
+        if mode == GapFill::Nan {
+            frame.analog = [0; MAX_ANALOG_CHANNELS];
         }
+        frame
+    }
+
+    /// Analog values of the active channels.
+    #[inline]
+    pub fn analog_values(&self) -> &[u16] {
+        &self.analog[..self.n_channels as usize]
+    }
+
+    /// ADC resolution of each of [`analog_values`](Self::analog_values), in bits.
+    #[inline]
+    #[allow(dead_code)]
+    pub fn resolution_bits(&self) -> &'static [u8] {
+        &RESOLUTION_BITS[..self.n_channels as usize]
+    }
+
+    /// Analog value at `position` converted by `sensor`'s transfer function at
+    /// that value's resolution; `None` past the active channels.
+    #[allow(dead_code)]
+    pub fn physical(&self, position: usize, sensor: Sensor) -> Option<f64> {
+        let raw = *self.analog_values().get(position)?;
+        Some(sensor.to_physical(raw, resolution_bits(position)))
     }
 }
 
@@ -143,6 +290,16 @@ impl Frame {
 pub struct FrameBatch {
     /// The frames that were successfully read.
     pub frames: Vec<Frame>,
+    /// [`Frame::sample_index`] of the first frame, or of the next frame to be
+    /// read when the batch is empty.
+    #[allow(dead_code)]
+    pub first_sample_index: u64,
+    /// Acquired analog channels (0-5), in frame order.
+    #[allow(dead_code)]
+    pub channels: Vec<u8>,
+    /// Acquisition rate, at which [`Frame::sample_index`] advances.
+    #[allow(dead_code)]
+    pub sampling_rate: SamplingRate,
     /// Timestamp when the batch read started (for timing reconstruction).
     #[allow(dead_code)]
     pub timestamp_us: u64,
@@ -152,6 +309,176 @@ pub struct FrameBatch {
     /// Number of sequence discontinuities detected (potential dropped frames).
     #[allow(dead_code)]
     pub sequence_gaps: usize,
+    /// Whether the read stopped early because a [`ReadOptions`] timeout elapsed.
+    /// `frames` then holds fewer than the requested number of frames.
+    #[allow(dead_code)]
+    pub partial: bool,
+    /// Radio conditions sampled around this batch (at most once per second),
+    /// or `None` when the transport cannot report them.
+    #[allow(dead_code)]
+    pub link_quality: Option<LinkQuality>,
+    /// Policy the batch was read with; frames it inserted have `filled` set.
+    #[allow(dead_code)]
+    pub gap_fill: GapFill,
+}
+
+impl FrameBatch {
+    /// ADC resolution of each acquired channel, in bits, in frame order.
+    #[allow(dead_code)]
+    pub fn resolution_bits(&self) -> &'static [u8] {
+        &RESOLUTION_BITS[..self.channels.len().min(MAX_ANALOG_CHANNELS)]
+    }
+
+    /// Append the frames and counters of `next`, keeping this batch's
+    /// timestamp and the latest link quality sample.
+    pub(crate) fn append(&mut self, next: FrameBatch) {
+        if self.frames.is_empty() {
+            self.first_sample_index = next.first_sample_index;
+        }
+        self.frames.extend(next.frames);
+        self.crc_errors += next.crc_errors;
+        self.sequence_gaps += next.sequence_gaps;
+        self.partial = next.partial;
+        self.link_quality = next.link_quality.or(self.link_quality);
+    }
+
+    /// Transpose the batch into column-major arrays for bulk processing.
+    #[allow(dead_code)]
+    pub fn to_columns(&self) -> FrameColumns {
+        let n_channels = self.frames.first().map_or(0, |f| f.n_channels as usize);
+        let mut analog = vec![Vec::with_capacity(self.frames.len()); n_channels];
+        let mut digital = Vec::with_capacity(self.frames.len());
+        let mut sequence = Vec::with_capacity(self.frames.len());
+
+        for frame in &self.frames {
+            for (column, &value) in analog.iter_mut().zip(frame.analog_values()) {
+                column.push(value);
+            }
+            digital.push(
+                frame
+                    .digital
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |bits, (i, &d)| bits | ((d & 0x01) << i)),
+            );
+            sequence.push(frame.seq);
+        }
+
+        FrameColumns {
+            analog,
+            digital,
+            sequence,
+            sample_index: self.frames.iter().map(|f| f.sample_index).collect(),
+            filled: self.frames.iter().map(|f| f.filled).collect(),
+        }
+    }
+}
+
+/// Column-major view of a [`FrameBatch`], as produced by [`FrameBatch::to_columns`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameColumns {
+    /// One contiguous vector per active analog channel, in channel order.
+    pub analog: Vec<Vec<u16>>,
+    /// Digital channels packed per frame: bit 0 = I1, 1 = I2, 2 = O1, 3 = O2.
+    pub digital: Vec<u8>,
+    /// Sequence number of each frame.
+    pub sequence: Vec<u8>,
+    /// Absolute sample index of each frame.
+    pub sample_index: Vec<u64>,
+    /// Whether each frame is a gap-fill placeholder.
+    pub filled: Vec<bool>,
+}
+
+/// Options bounding how long a frame read may block.
+///
+/// The default blocks until every requested frame arrives (subject only to the
+/// transport's 5 s socket timeout), matching `read_frames_timed()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Overall budget for the whole read; `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+    /// Return the frames read so far (with `FrameBatch::partial` set) instead of
+    /// an error when a timeout elapses.
+    pub allow_partial: bool,
+    /// Longest time to wait for any single frame; `None` disables the limit.
+    pub max_wait_per_frame: Option<Duration>,
+    /// Insert placeholders for frames lost to sequence gaps.
+    pub gap_fill: GapFill,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            allow_partial: true,
+            max_wait_per_frame: None,
+            gap_fill: GapFill::Off,
+        }
+    }
+}
+
+/// How reads fill sequence gaps left by frames lost on the link.
+///
+/// With filling enabled a batch holds one frame per sample period, so its
+/// length stays consistent with elapsed time and fixed-rate processing does
+/// not drift when frames drop. Placeholders are marked with
+/// [`Frame::filled`] and carry the expected sequence number and the previous
+/// frame's digital levels. Gaps of up to 15 lost frames are filled; a lost
+/// 16th frame brings the 4-bit sequence number back to where it was, so it
+/// cannot be told apart from wrap-around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GapFill {
+    /// Return only the frames received.
+    #[default]
+    Off,
+    /// Repeat the last received frame's analog values.
+    #[allow(dead_code)]
+    HoldLast,
+    /// Zero the analog values; columnar views (such as the Python `arrays()`)
+    /// turn them into NaN.
+    Nan,
+}
+
+/// What the driver does with data failing its CRC check.
+///
+/// Applies to `state()` replies and to frames in the read path
+/// (`read_frames_timed()` and friends); `wait_until_streaming()` always skips
+/// corrupt frames while it looks for the first valid one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrcPolicy {
+    /// Log and carry on: `state()` returns the reply as decoded, and reads
+    /// drop corrupt frames and count them in `crc_errors`.
+    #[default]
+    Lenient,
+    /// Fail with [`DriverError::Crc`] on the first mismatch. A read that
+    /// fails this way discards the frames it had already decoded.
+    Strict,
+    /// `state()` re-issues the command up to `n` times; reads drop up to `n`
+    /// corrupt frames per call. One more mismatch fails as with `Strict`.
+    #[allow(dead_code)]
+    Retry(u32),
+}
+
+impl CrcPolicy {
+    /// Parse the Python spelling (`"lenient"`, `"strict"`).
+    #[allow(dead_code)]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lenient" => Some(CrcPolicy::Lenient),
+            "strict" => Some(CrcPolicy::Strict),
+            _ => None,
+        }
+    }
+
+    /// Corrupt frames a single read may drop before failing; `None` for no
+    /// limit.
+    fn frame_limit(self) -> Option<usize> {
+        match self {
+            CrcPolicy::Lenient => None,
+            CrcPolicy::Strict => Some(0),
+            CrcPolicy::Retry(retries) => Some(retries as usize),
+        }
+    }
 }
 
 /// Device state information (BITalino 2.0+ only).
@@ -192,37 +519,246 @@ impl DeviceState {
     }
 }
 
+/// Kind of link a driver talks over, as reported by its [`Transport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionKind {
+    /// Direct RFCOMM socket.
+    Bluetooth,
+    /// Serial port, such as a bound `/dev/rfcomm0` or a USB adapter.
+    Serial,
+    /// TCP bridge to a serial link (e.g. `ser2net`).
+    Tcp,
+    /// Recorded session played back from a capture file.
+    Replay,
+    /// Any other [`Transport`] implementation.
+    #[default]
+    Custom,
+}
+
+impl ConnectionKind {
+    /// Lowercase name used in file headers and the Python API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConnectionKind::Bluetooth => "bluetooth",
+            ConnectionKind::Serial => "serial",
+            ConnectionKind::Tcp => "tcp",
+            ConnectionKind::Replay => "replay",
+            ConnectionKind::Custom => "custom",
+        }
+    }
+}
+
+/// Identity and capabilities of a connected device.
+///
+/// Returned by [`Bitalino::device_info`]. Fields that come from the device
+/// itself are filled in by `version()`, which every connect helper calls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    /// Bluetooth address, when connected by MAC.
+    pub mac: Option<String>,
+    /// Firmware version string (e.g. `"BITalino_v5.2"`); `None` until
+    /// `version()` succeeds.
+    pub firmware_version: Option<String>,
+    /// Whether the device is a BITalino 2.0+ (state, PWM and idle triggers).
+    pub is_bitalino2: bool,
+    /// Sampling rates the device accepts.
+    pub supported_rates: Vec<SamplingRate>,
+    /// Number of analog inputs (A1-A6).
+    pub n_analog_channels: usize,
+    /// Link the driver talks over.
+    pub connection_kind: ConnectionKind,
+    /// Whether the device was already acquiring when the driver first
+    /// talked to it (e.g. left running by a crashed process); the first
+    /// `version()` stopped it.
+    pub was_streaming: bool,
+}
+
 // ============================================================================
 // Transport Abstraction
 // ============================================================================
 
-/// Trait for Read + Write + Send, allowing different transport backends.
+/// Byte stream the driver talks to: Read + Write + Send, plus optional hooks.
+///
+/// Implemented for [`RfcommStream`] and serial ports. Implement it for your own
+/// type to plug in other links (a TCP bridge, a pseudo-terminal, a recorded
+/// session) and hand it to [`Bitalino::from_transport`].
 ///
-/// `set_read_timeout` defaults to a no-op so test transports (e.g. `Cursor`) and
-/// any future backend that does not support per-call timeout adjustment continue
-/// to compile without ceremony.
-trait Transport: Read + Write + Send {
+/// `set_read_timeout` and `set_write_timeout` default to no-ops so transports
+/// that do not support per-call timeout adjustment (e.g. `Cursor`) work without
+/// ceremony; I/O then blocks as long as the underlying stream does. `bytes_available` defaults to
+/// `Unsupported`, which makes `read_available()` fail loudly instead of blocking.
+/// `link_quality` defaults to `Unsupported` as well; batches then carry no
+/// radio metrics. `connection_kind` defaults to [`ConnectionKind::Custom`].
+///
+/// # Example
+/// ```ignore
+/// struct Tcp(std::net::TcpStream);
+/// // Read/Write impls forwarding to the socket ...
+/// impl Transport for Tcp {
+///     fn set_read_timeout(&mut self, timeout: Duration) -> std::io::Result<()> {
+///         self.0.set_read_timeout(Some(timeout))
+///     }
+/// }
+///
+/// let mut device = Bitalino::from_transport(Box::new(Tcp(stream)));
+/// ```
+pub trait Transport: Read + Write + Send {
+    /// Bound how long a single `read()` may block. Reads that time out should
+    /// fail with `WouldBlock` or `TimedOut`.
     fn set_read_timeout(&mut self, _timeout: Duration) -> std::io::Result<()> {
         Ok(())
     }
+
+    /// Bound how long a single `write()` may block.
+    fn set_write_timeout(&mut self, _timeout: Duration) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Number of bytes that can be read without blocking.
+    fn bytes_available(&mut self) -> std::io::Result<usize> {
+        Err(std::io::Error::from(ErrorKind::Unsupported))
+    }
+
+    /// RSSI and link quality of the underlying radio link.
+    fn link_quality(&mut self) -> std::io::Result<LinkQuality> {
+        Err(std::io::Error::from(ErrorKind::Unsupported))
+    }
+
+    /// Kind of link, reported by [`Bitalino::device_info`].
+    fn connection_kind(&self) -> ConnectionKind {
+        ConnectionKind::Custom
+    }
+
+    /// Second handle writing to the same link, used by
+    /// [`Bitalino::pwm_pattern`] to send commands while frames are read.
+    fn try_clone_writer(&self) -> std::io::Result<Box<dyn Write + Send>> {
+        Err(std::io::Error::from(ErrorKind::Unsupported))
+    }
 }
 
 impl Transport for RfcommStream {
     fn set_read_timeout(&mut self, timeout: Duration) -> std::io::Result<()> {
         RfcommStream::set_read_timeout(self, timeout)
     }
+
+    fn set_write_timeout(&mut self, timeout: Duration) -> std::io::Result<()> {
+        RfcommStream::set_write_timeout(self, timeout)
+    }
+
+    fn bytes_available(&mut self) -> std::io::Result<usize> {
+        RfcommStream::bytes_available(self)
+    }
+
+    fn link_quality(&mut self) -> std::io::Result<LinkQuality> {
+        RfcommStream::link_quality(self)
+    }
+
+    fn connection_kind(&self) -> ConnectionKind {
+        ConnectionKind::Bluetooth
+    }
+
+    fn try_clone_writer(&self) -> std::io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(RfcommStream::try_clone(self)?))
+    }
 }
 
 impl Transport for Box<dyn serialport::SerialPort> {
     fn set_read_timeout(&mut self, timeout: Duration) -> std::io::Result<()> {
         (**self).set_timeout(timeout).map_err(std::io::Error::other)
     }
+
+    fn bytes_available(&mut self) -> std::io::Result<usize> {
+        (**self)
+            .bytes_to_read()
+            .map(|n| n as usize)
+            .map_err(std::io::Error::other)
+    }
+
+    fn connection_kind(&self) -> ConnectionKind {
+        ConnectionKind::Serial
+    }
+
+    fn try_clone_writer(&self) -> std::io::Result<Box<dyn Write + Send>> {
+        (**self)
+            .try_clone()
+            .map(|port| Box::new(port) as Box<dyn Write + Send>)
+            .map_err(std::io::Error::other)
+    }
 }
 
 // ============================================================================
 // Bitalino Driver
 // ============================================================================
 
+/// Heart-rate detection state installed by `on_heartbeat()`.
+struct HeartbeatHook {
+    /// Analog channel carrying the ECG.
+    channel: u8,
+    detector: QrsDetector,
+    callback: Box<dyn FnMut(Beat) + Send>,
+}
+
+/// EDA decomposition state installed by `on_scr()`.
+struct EdaHook {
+    /// Analog channel carrying the EDA sensor.
+    channel: u8,
+    /// Created from the first frame, once the channel's resolution is known.
+    decomposer: Option<EdaDecomposer>,
+    level: Option<EdaLevel>,
+    callback: Box<dyn FnMut(Scr) + Send>,
+}
+
+/// Bytes read from the transport but not consumed yet.
+///
+/// The frame path fills it with one large `read()` at a time and decodes as
+/// many frames out of it as are complete, instead of a syscall per frame.
+#[derive(Default)]
+struct ReadBuffer {
+    bytes: Vec<u8>,
+    /// Start of the unconsumed bytes
+    start: usize,
+}
+
+impl ReadBuffer {
+    fn len(&self) -> usize {
+        self.bytes.len() - self.start
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.bytes[self.start..]
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.start += n;
+        if self.start >= self.bytes.len() {
+            self.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.bytes.clear();
+        self.start = 0;
+    }
+
+    /// Append what one `read()` of up to `max` bytes returns, and return the
+    /// new bytes.
+    fn fill_from<R: Read + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        max: usize,
+    ) -> std::io::Result<&[u8]> {
+        if self.start > 0 {
+            self.bytes.drain(..self.start);
+            self.start = 0;
+        }
+        let old = self.bytes.len();
+        self.bytes.resize(old + max, 0);
+        let result = reader.read(&mut self.bytes[old..]);
+        self.bytes.truncate(old + *result.as_ref().unwrap_or(&0));
+        result.map(|n| &self.bytes[old..old + n])
+    }
+}
+
 /// BITalino device driver.
 ///
 /// Provides methods to connect, configure, and read data from a BITalino device.
@@ -246,11 +782,185 @@ pub struct Bitalino {
     frame_size: usize,
     sampling_rate: SamplingRate,
     start_time: Option<Instant>,
+    /// Wall-clock time of the same instant as `start_time`, kept after `stop()`
+    start_utc: Option<SystemTime>,
+    /// When the start command was written, before the post-command delay
+    start_sent: Option<Instant>,
+    /// How long after the requested time a `start_at()` start command was
+    /// written; `None` for an unscheduled start
+    start_offset: Option<Duration>,
     last_seq: Option<u8>,
-    /// Whether device is BITalino 2.0+ (supports state(), pwm(), trigger in idle)
-    is_bitalino2: bool,
-    /// Whether device is BITalino firmware >= 5.2 (state reply length differs)
-    is_bitalino52: bool,
+    /// Bytes read ahead of the frames decoded so far, including a frame cut
+    /// short by a read deadline
+    read_buffer: ReadBuffer,
+    /// Frames produced by the device since `start()`, including dropped/corrupt ones
+    samples_received: u64,
+    /// Host-vs-device clock drift estimate, fed after every timed read
+    timing: TimingModel,
+    /// Totals behind `stats()`, updated frame by frame so failed reads still count
+    counters: Counters,
+    /// Sliding windows behind `channel_stats()`
+    moving_stats: MovingStats,
+    /// Last valid frame, repeated by `GapFill::HoldLast` across batches
+    last_frame: Option<Frame>,
+    /// What to do with replies and frames failing their CRC check
+    crc_policy: CrcPolicy,
+    /// Firmware release parsed from the last successful `version()`
+    firmware: Option<FirmwareVersion>,
+    /// Features of that firmware (BITalino 1.0 ones until it is known)
+    capabilities: Capabilities,
+    /// Firmware string from the last successful `version()`
+    firmware_version: Option<String>,
+    /// Whether frames arrived unsolicited before the first `version()`;
+    /// `None` until it has listened
+    was_streaming: Option<bool>,
+    /// Bluetooth address, when connected by MAC
+    mac: Option<String>,
+    /// Whether dropping the driver sends the stop command (cleared by `leak()`)
+    stop_on_drop: bool,
+    /// Low-battery watchdog installed by `monitor_battery()`
+    battery_monitor: Option<BatteryMonitor>,
+    /// Frame-rate watchdog installed by `monitor_throughput()`
+    throughput_monitor: Option<ThroughputMonitor>,
+    /// Tracks digital levels between frames to report edges
+    edge_detector: EdgeDetector,
+    /// Receives digital edges; detection is skipped while unset
+    edge_callback: Option<Box<dyn FnMut(DigitalEdge) + Send>>,
+    /// QRS detection on one channel; skipped while unset
+    heartbeat: Option<HeartbeatHook>,
+    /// EDA decomposition on one channel; skipped while unset
+    eda: Option<EdaHook>,
+    /// Markers placed with `annotate()` during the current acquisition
+    annotations: Vec<Annotation>,
+    /// Output changes sent during the current acquisition, shared with the
+    /// thread ending a trigger pulse
+    output_events: Arc<Mutex<Vec<OutputEvent>>>,
+    /// Recording opened with `begin_session()`
+    session: Option<Session>,
+    /// Sink receiving a copy of the raw acquisition stream (`enable_raw_capture()`)
+    raw_capture: Option<BufWriter<File>>,
+    /// Last link quality sample attached to batches and when it was taken;
+    /// `None` once the transport reported it cannot measure the link
+    link_sampler: Option<LinkSampler>,
+    /// Baseline read/write timeout, restored after reads with a tighter budget
+    io_timeout: Duration,
+    /// Consumers registered with `subscribe()`
+    subscribers: Fanout,
+    /// Identifies the device in metrics and traces (MAC or bridge address)
+    device_label: String,
+    /// Held while a command is written, so a PWM schedule writing on its own
+    /// handle never splits one
+    command_lock: Arc<Mutex<()>>,
+    /// Cancels the PWM schedule started last, if any
+    pwm_cancel: Option<Arc<AtomicBool>>,
+    /// Digital output levels last set with `trigger()`, `[O1, O2, O3, O4]`
+    digital_outputs: [u8; 4],
+    /// Trigger pulse whose falling edge is still to be sent
+    pulse: Option<PendingPulse>,
+}
+
+/// Falling edge of a trigger pulse, written by its own thread.
+#[derive(Debug)]
+struct PendingPulse {
+    /// Sending (or dropping) it makes the thread write the edge at once
+    end_now: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl PendingPulse {
+    /// Write `cmd` on `writer` at `at`, or as soon as the pulse is finished,
+    /// then call `on_written` with the time of the write.
+    fn spawn(
+        mut writer: Box<dyn Write + Send>,
+        lock: Arc<Mutex<()>>,
+        at: Instant,
+        cmd: u8,
+        label: String,
+        on_written: impl FnOnce(Instant) + Send + 'static,
+    ) -> std::io::Result<Self> {
+        let (end_now, ended) = mpsc::channel();
+        let handle = std::thread::Builder::new()
+            .name("bitalino-trigger".into())
+            .spawn(move || {
+                let _ = ended.recv_timeout(at.saturating_duration_since(Instant::now()));
+                let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+                let written = Instant::now();
+                match writer.write_all(&[cmd]).and_then(|()| writer.flush()) {
+                    Ok(()) => on_written(written),
+                    Err(e) => device_log!(warn, label, "Failed to end trigger pulse: {}", e),
+                }
+            })?;
+        Ok(Self { end_now, handle })
+    }
+
+    /// Write the falling edge now unless already written, and wait for it.
+    fn finish(self) {
+        let _ = self.end_now.send(());
+        let _ = self.handle.join();
+    }
+}
+
+/// Block until the wall clock reaches `at`. The remaining time is re-read
+/// from the clock after every sleep, so a clock step while waiting moves
+/// the wake-up with it.
+fn sleep_until(at: SystemTime) {
+    while let Ok(left) = at.duration_since(SystemTime::now()) {
+        if left > START_SPIN {
+            std::thread::sleep((left - START_SPIN).min(Duration::from_secs(1)));
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Output levels `[O1, O2, O3, O4]` from a slice of 0/1 values; missing
+/// outputs are low.
+fn output_levels(outputs: &[u8]) -> [u8; 4] {
+    let mut levels = [0; 4];
+    for (level, &value) in levels.iter_mut().zip(outputs) {
+        *level = value & 0x01;
+    }
+    levels
+}
+
+/// CRC nibble of a state reply as `(received, calculated)`.
+///
+/// Computed exactly as in the official Python code:
+///
+/// ```text
+/// crc = decodedData[-1] & 0x0F
+/// decodedData[-1] = decodedData[-1] & 0xF0
+/// x = 0
+/// for i in range(number_bytes):
+///     for bit in range(7, -1, -1):
+///         x = x << 1
+///         if (x & 0x10):
+///             x = x ^ 0x03
+///         x = x ^ ((decodedData[i] >> bit) & 0x01)
+/// ```
+fn state_crc(data: &[u8]) -> (u8, u8) {
+    let last = data.len() - 1;
+    let received_crc = data[last] & 0x0F;
+    let mut x: u8 = 0;
+    for (i, &byte) in data.iter().enumerate() {
+        // The CRC bits of the last byte are cleared before the calculation.
+        let byte = if i == last { byte & 0xF0 } else { byte };
+        for bit in (0..8).rev() {
+            x <<= 1;
+            if (x & 0x10) != 0 {
+                x ^= 0x03;
+            }
+            x ^= (byte >> bit) & 0x01;
+        }
+    }
+    (received_crc, x & 0x0F)
+}
+
+/// Rate-limited link quality polling for the read path.
+#[derive(Debug, Default)]
+struct LinkSampler {
+    polled: Option<Instant>,
+    last: Option<LinkQuality>,
 }
 
 impl Bitalino {
@@ -258,41 +968,190 @@ impl Bitalino {
     // Constructors
     // ------------------------------------------------------------------------
 
+    /// Serial ports present on the host, likely BITalinos first.
+    ///
+    /// See [`SerialPortEntry::is_bitalino`] for what marks a candidate; pass
+    /// a candidate's path to [`connect_serial`](Self::connect_serial).
+    ///
+    /// # Errors
+    /// Fails if the operating system cannot enumerate its serial ports.
+    pub fn list_serial_ports() -> Result<Vec<SerialPortEntry>> {
+        serial::list_ports()
+    }
+
     /// Connect to a BITalino via serial port (e.g., `/dev/rfcomm0`).
     ///
     /// This is useful when you've already bound the device using `rfcomm bind`.
+    /// Opens the port at 115200 baud without flow control; see
+    /// [`connect_serial_with`](Self::connect_serial_with) for adapters that
+    /// need other settings.
     #[allow(dead_code)]
     pub fn connect_serial(path: &str) -> Result<Self> {
-        let port = serialport::new(path, BAUD_RATE)
-            .timeout(DEFAULT_TIMEOUT)
-            .open()
-            .with_context(|| format!("Failed to open serial port at {}", path))?;
+        Self::connect_serial_with(path, &SerialConfig::default())
+    }
 
-        Ok(Self {
-            transport: Box::new(port),
-            active_channels: Vec::new(),
-            frame_size: 0,
-            sampling_rate: SamplingRate::Hz1000,
-            start_time: None,
-            last_seq: None,
-            is_bitalino2: false,  // Will be detected on first version() call
-            is_bitalino52: false, // Will be detected on first version() call
-        })
+    /// Connect to a BITalino via serial port with explicit line settings.
+    ///
+    /// With [`SerialConfig::probe_baud_rates`] set, the port is reopened at
+    /// each candidate rate until `version()` returns a BITalino version
+    /// string, which leaves the device idle and its revision known. Each
+    /// failed rate costs about 3 s.
+    ///
+    /// # Errors
+    /// Fails if the port cannot be opened or configured, or if probing finds
+    /// no rate at which the device answers.
+    #[allow(dead_code)]
+    pub fn connect_serial_with(path: &str, config: &SerialConfig) -> Result<Self> {
+        if !config.probe_baud_rates {
+            let port = config.open(path, config.baud_rate)?;
+            let mut device = Self::from_transport(Box::new(port));
+            device.set_io_timeout(config.timeout)?;
+            return Ok(device);
+        }
+        let rates = config.candidate_rates();
+        for &baud_rate in &rates {
+            let port = config.open(path, baud_rate)?;
+            let mut device = Self::from_transport(Box::new(port));
+            device.set_io_timeout(serial::PROBE_IO_TIMEOUT)?;
+            match device.version() {
+                Ok(version) if serial::is_bitalino_version(&version) => {
+                    debug!("{} answers at {} baud: {}", path, baud_rate, version);
+                    device.set_io_timeout(config.timeout)?;
+                    return Ok(device);
+                }
+                Ok(reply) => debug!(
+                    "{} baud on {}: unexpected reply {:?}",
+                    baud_rate, path, reply
+                ),
+                Err(e) => debug!("{} baud on {}: {:#}", baud_rate, path, e),
+            }
+        }
+        Err(DriverError::Timeout(format!(
+            "No BITalino answered on {} at {:?} baud",
+            path, rates
+        ))
+        .into())
+    }
+
+    /// Connect to a BITalino proxied over TCP (e.g. `ser2net` on a Raspberry Pi).
+    ///
+    /// `addr` is `"host:port"`. Uses the same 5 s I/O timeout as the RFCOMM path
+    /// and reconnects transparently if the bridge drops the connection.
+    #[allow(dead_code)]
+    pub fn connect_tcp(addr: &str) -> Result<Self> {
+        let transport = TcpTransport::connect(addr)
+            .with_context(|| format!("Failed to connect to TCP bridge at {}", addr))?;
+        let label = transport.peer_addr().to_string();
+        let mut device = Self::from_transport(Box::new(transport));
+        device.set_device_label(label);
+        Ok(device)
+    }
+
+    /// Connect over RFCOMM to the device described by `profile`.
+    ///
+    /// Uses the profile's RFCOMM channel and retry policy, then queries
+    /// `version()` so the device is idle and its revision known. Acquisition
+    /// settings are not applied: pass `profile.rate` and `profile.channels` to
+    /// `start()`.
+    ///
+    /// With `profile.remember` set, devices already in the
+    /// [`DeviceRegistry`] are connected to without scanning, on their
+    /// remembered RFCOMM channel unless the profile sets one (and a known
+    /// BITalino 2.0 is forced idle before the handshake, see
+    /// [`force_idle`](Self::force_idle)), and the connection is recorded
+    /// there. Registry errors are logged, not returned.
+    #[allow(dead_code)]
+    pub fn from_profile(profile: &Profile) -> Result<Self> {
+        let mac = profile
+            .mac
+            .as_deref()
+            .context("Profile has no MAC address")?;
+        let mut registry = profile
+            .remember
+            .then(DeviceRegistry::load_or_warn)
+            .flatten();
+        let known = registry.as_ref().and_then(|r| r.get(mac));
+        let mut connector = profile.connector();
+        connector.known_device |= known.is_some();
+        if let Some(channel) = profile.channel.or(known.map(|k| k.channel)) {
+            connector.channel = channel;
+        }
+        let firmware = known.and_then(|k| k.firmware.clone());
+        let stream = connector.pair_and_connect(mac, &profile.pin)?;
+        let mut device = Self::from_rfcomm(stream);
+        device.set_mac(mac);
+        if let Some(firmware) = firmware {
+            // Lets the handshake force a BITalino 2.0 out of any state.
+            device.set_firmware(&firmware);
+        }
+        device.set_io_timeout(Duration::from_millis(profile.retry.io_timeout_ms))?;
+        if let Err(e) = device.version() {
+            device_log!(
+                warn,
+                device.device_label,
+                "Initial version() handshake failed after connect: {}",
+                e
+            );
+        }
+        if let Some(registry) = registry.as_mut() {
+            registry.note_connection(&device, connector.channel);
+            registry.save_or_warn();
+        }
+        Ok(device)
     }
 
     /// Create a Bitalino driver from an already-connected RFCOMM stream.
     ///
     /// This is the preferred method when using `BluetoothConnector::pair_and_connect()`.
     pub fn from_rfcomm(stream: RfcommStream) -> Self {
+        Self::from_transport(Box::new(stream))
+    }
+
+    /// Create a Bitalino instance over a custom [`Transport`].
+    ///
+    /// The transport must already be connected; the driver starts idle.
+    pub fn from_transport(transport: Box<dyn Transport>) -> Self {
         Self {
-            transport: Box::new(stream),
+            transport,
             active_channels: Vec::new(),
             frame_size: 0,
             sampling_rate: SamplingRate::Hz1000,
             start_time: None,
+            start_utc: None,
+            start_sent: None,
+            start_offset: None,
             last_seq: None,
-            is_bitalino2: false,  // Will be detected on first version() call
-            is_bitalino52: false, // Will be detected on first version() call
+            read_buffer: ReadBuffer::default(),
+            samples_received: 0,
+            timing: TimingModel::new(SamplingRate::Hz1000),
+            counters: Counters::default(),
+            moving_stats: MovingStats::new(SamplingRate::Hz1000, DEFAULT_STATS_WINDOW),
+            last_frame: None,
+            crc_policy: CrcPolicy::default(),
+            firmware: None, // Detected on the first version() call
+            capabilities: Capabilities::for_firmware(None),
+            firmware_version: None,
+            was_streaming: None,
+            mac: None,
+            stop_on_drop: true,
+            battery_monitor: None,
+            throughput_monitor: None,
+            edge_detector: EdgeDetector::default(),
+            edge_callback: None,
+            heartbeat: None,
+            eda: None,
+            annotations: Vec::new(),
+            output_events: Arc::default(),
+            session: None,
+            raw_capture: None,
+            link_sampler: Some(LinkSampler::default()),
+            io_timeout: DEFAULT_TIMEOUT,
+            subscribers: Fanout::default(),
+            device_label: DEFAULT_DEVICE_LABEL.to_string(),
+            command_lock: Arc::default(),
+            pwm_cancel: None,
+            digital_outputs: [0; 4],
+            pulse: None,
         }
     }
 
@@ -306,12 +1165,28 @@ impl Bitalino {
     /// This method properly handles the asynchronous nature of Bluetooth
     /// by reading until a delimiter or timeout.
     pub fn version(&mut self) -> Result<String> {
-        // Ensure device is in idle state
-        let _ = self.stop();
-        std::thread::sleep(STOP_DELAY);
+        if self.was_streaming.is_none() && self.frame_size == 0 {
+            let streaming = self.probe_streaming()?;
+            if streaming {
+                device_log!(
+                    warn,
+                    self.device_label,
+                    "Device was already acquiring on connect; stopping it"
+                );
+            }
+            self.was_streaming = Some(streaming);
+        }
 
-        // Clear any pending data in the buffer
-        self.flush_input()?;
+        // Ensure device is in idle state; a BITalino 2.0 may also be halfway
+        // through a command left by a crashed host.
+        if self.is_bitalino2() {
+            self.force_idle()?;
+        } else {
+            let _ = self.stop();
+            std::thread::sleep(STOP_DELAY);
+            // Clear any pending data in the buffer
+            self.flush_input()?;
+        }
 
         // Send version command
         self.send_command(CMD_VERSION)?;
@@ -366,48 +1241,81 @@ impl Bitalino {
                 if !response.is_empty() {
                     break;
                 }
-                anyhow::bail!("Timeout waiting for version response");
+                return Err(
+                    DriverError::Timeout("Timeout waiting for version response".into()).into(),
+                );
             }
         }
 
         let version = String::from_utf8_lossy(&response).trim().to_string();
-        debug!("Device version: {}", version);
+        device_log!(debug, self.device_label, "Device version: {}", version);
 
         // Drain any remaining delimiters from the response (e.g., LF after CR).
         // If left unread, these bytes can desynchronize the next framed read and
         // trigger a spurious CRC error.
         let _ = self.flush_input();
 
-        // Detect BITalino 2.0+/5.2 based on version string
-        // Format: "BITalino_v5.2" or "BITalino V5.2"
-        if let Some(num) = self.parse_version_number(&version) {
-            self.is_bitalino2 = num >= 4.2;
-            self.is_bitalino52 = num >= 5.2;
-            if self.is_bitalino2 {
-                debug!("Detected BITalino 2.0+ device");
-            }
-            if self.is_bitalino52 {
-                debug!("Detected BITalino firmware >=5.2 (17-byte state response)");
-            }
-        }
+        self.set_firmware(&version);
 
         Ok(version)
     }
 
-    /// Parse version number from firmware string.
-    /// Returns Some(major.minor) as f32, or None if it cannot be parsed.
-    fn parse_version_number(&self, version: &str) -> Option<f32> {
-        let version_lower = version.to_lowercase();
+    /// Listen for [`STREAM_PROBE`] without sending anything: bytes arriving
+    /// unsolicited mean the device is streaming.
+    fn probe_streaming(&mut self) -> Result<bool> {
+        let mut byte = [0u8; 1];
+        let _ = self.transport.set_read_timeout(STREAM_PROBE);
+        let result = self.transport.read(&mut byte);
+        let _ = self.transport.set_read_timeout(self.io_timeout);
+        match result {
+            Ok(n) => Ok(n > 0),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        let num_str = if let Some(pos) = version_lower.find("_v") {
-            version_lower[pos + 2..].chars().take(3).collect::<String>()
-        } else if let Some(pos) = version_lower.find('v') {
-            version_lower[pos + 1..].chars().take(3).collect::<String>()
-        } else {
-            return None;
-        };
+    /// Record the firmware `version()` reported and look up its features.
+    pub(crate) fn set_firmware(&mut self, version: &str) {
+        self.firmware = FirmwareVersion::parse(version);
+        self.capabilities = Capabilities::for_firmware(self.firmware);
+        device_log!(
+            debug,
+            self.device_label,
+            "Firmware {:?}: {:?}",
+            self.firmware.map(|v| v.to_string()),
+            self.capabilities
+        );
+        self.firmware_version = Some(version.to_string());
+    }
+
+    /// Fail with [`DriverError::Unsupported`] unless the firmware has
+    /// `feature`, naming the firmware and the first release that has it.
+    fn require(&self, what: &str, feature: impl Fn(&Capabilities) -> bool) -> Result<()> {
+        if feature(&self.capabilities) {
+            return Ok(());
+        }
+        let needs = Capabilities::first_with(&feature)
+            .map_or_else(String::new, |v| format!(" (needs firmware {v}+)"));
+        Err(DriverError::Unsupported(format!(
+            "{what} is not supported on {}{needs}",
+            self.firmware_label()
+        ))
+        .into())
+    }
 
-        num_str.parse::<f32>().ok()
+    /// Firmware named in error messages.
+    fn firmware_label(&self) -> String {
+        match &self.firmware_version {
+            Some(firmware) => format!("firmware {firmware}"),
+            None => "unidentified firmware (call version() first)".to_string(),
+        }
     }
 
     /// Start data acquisition at the specified sampling rate.
@@ -423,16 +1331,61 @@ impl Bitalino {
         self.start_with_rate(rate, channels)
     }
 
+    /// Start data acquisition on the channels and at the rate of `preset`.
+    ///
+    /// The preset's sensors and filters are not applied to the frames; use
+    /// [`Preset::filter_bank`] for a filtered view.
+    #[allow(dead_code)]
+    pub fn start_preset(&mut self, preset: Preset) -> Result<SamplingRate> {
+        self.start_with_rate(preset.rate, preset.channels())
+    }
+
+    /// Start data acquisition at the wall-clock time `at`, for coordinating
+    /// with other equipment scheduled by clock.
+    ///
+    /// Stops any running acquisition and sets the rate right away, then
+    /// blocks until `at` and writes only the start command, so the device
+    /// starts as close to `at` as the host clock allows. How late the command
+    /// went out is kept in [`start_offset`](Self::start_offset).
+    ///
+    /// # Errors
+    /// Fails if `at` has already passed, or where [`start`](Self::start) does.
+    #[allow(dead_code)]
+    pub fn start_at(
+        &mut self,
+        at: SystemTime,
+        sampling_rate: u16,
+        channels: Vec<u8>,
+    ) -> Result<SamplingRate> {
+        let rate = SamplingRate::from_u16_checked(sampling_rate)?;
+        anyhow::ensure!(
+            at > SystemTime::now(),
+            "Scheduled start time {} has already passed",
+            crate::recording::utc_iso8601(at)
+        );
+        self.start_scheduled(rate, channels, Some(at))
+    }
+
     /// Start data acquisition with a specific SamplingRate enum value.
     pub fn start_with_rate(
         &mut self,
         rate: SamplingRate,
         channels: Vec<u8>,
     ) -> Result<SamplingRate> {
-        // Ensure we're in a clean state
-        match self.stop() {
-            Ok(_) => {}
-            Err(e) => {
+        self.start_scheduled(rate, channels, None)
+    }
+
+    /// Start acquisition, writing the start command no earlier than `at`.
+    fn start_scheduled(
+        &mut self,
+        rate: SamplingRate,
+        channels: Vec<u8>,
+        at: Option<SystemTime>,
+    ) -> Result<SamplingRate> {
+        // Ensure we're in a clean state
+        match self.stop() {
+            Ok(_) => {}
+            Err(e) => {
                 let recoverable = e
                     .downcast_ref::<std::io::Error>()
                     .map(|ioe| {
@@ -448,7 +1401,12 @@ impl Bitalino {
                     .unwrap_or(false);
 
                 if recoverable {
-                    warn!("stop() before start failed (ignored as link idle): {}", e);
+                    device_log!(
+                        warn,
+                        self.device_label,
+                        "stop() before start failed (ignored as link idle): {}",
+                        e
+                    );
                 } else {
                     anyhow::bail!("Failed to stop before starting acquisition: {e}");
                 }
@@ -459,7 +1417,13 @@ impl Bitalino {
         self.active_channels.clear();
         self.frame_size = 0;
         self.start_time = None;
+        self.start_utc = None;
+        self.start_sent = None;
+        self.start_offset = None;
         self.last_seq = None;
+        self.last_frame = None;
+        self.read_buffer.clear();
+        self.edge_detector.reset();
 
         std::thread::sleep(COMMAND_DELAY);
         let _ = self.flush_input();
@@ -487,6 +1451,11 @@ impl Bitalino {
 
         // Start command: channel_bits | 0x01 (LSB=1 for live mode)
         let cmd = channel_bits | 0x01;
+        if let Some(at) = at {
+            sleep_until(at);
+        }
+        let sent = Instant::now();
+        let sent_utc = SystemTime::now();
         self.send_command(cmd)?;
 
         // Store active configuration
@@ -494,26 +1463,162 @@ impl Bitalino {
         self.frame_size = self.calculate_frame_size();
         self.sampling_rate = rate;
         self.start_time = Some(Instant::now());
+        self.start_utc = Some(SystemTime::now());
+        self.start_sent = Some(sent);
+        self.start_offset = at.map(|at| sent_utc.duration_since(at).unwrap_or_default());
         self.last_seq = None;
+        self.last_frame = None;
+        self.samples_received = 0;
+        self.timing = TimingModel::new(rate);
+        self.counters = Counters::default();
+        self.moving_stats.reset(rate);
+        if let Some(hook) = self.heartbeat.as_mut() {
+            hook.detector = QrsDetector::new(rate);
+            if !hook.detector.is_active() {
+                device_log!(
+                    warn,
+                    self.device_label,
+                    "Heart-rate detection needs at least 100 Hz; no beats at {} Hz",
+                    rate as u16
+                );
+            }
+        }
+        if let Some(hook) = self.eda.as_mut() {
+            hook.decomposer = None;
+            hook.level = None;
+        }
+        if let Some(monitor) = self.throughput_monitor.as_mut() {
+            monitor.reset(rate);
+        }
+        self.annotations.clear();
+        self.take_output_events();
 
-        debug!(
+        device_log!(
+            debug,
+            self.device_label,
             "Started acquisition: rate={}Hz, channels={:?}, frame_size={}",
-            rate as u16, self.active_channels, self.frame_size
+            rate as u16,
+            self.active_channels,
+            self.frame_size
         );
 
         Ok(rate)
     }
 
+    /// Check an acquisition configuration without touching a device.
+    ///
+    /// Fails where [`start`](Self::start) would: a rate other than 1, 10, 100
+    /// or 1000 Hz (every firmware supports all four), or no channel in 0-5.
+    /// Otherwise returns a warning for each setting `start` would quietly
+    /// change or that is likely to cost data: out-of-range or repeated
+    /// channels, the 6-bit values of the fifth and sixth channels, and a data
+    /// rate close to what the Bluetooth link carries.
+    #[allow(dead_code)]
+    pub fn validate_config(sampling_rate: u16, channels: &[u8]) -> Result<Vec<String>> {
+        let rate = SamplingRate::from_u16_checked(sampling_rate)?;
+        let mut warnings = Vec::new();
+        let mut valid: Vec<u8> = Vec::new();
+        for &ch in channels {
+            if usize::from(ch) >= MAX_ANALOG_CHANNELS {
+                warnings.push(format!("Channel {ch} is out of range (0-5) and is ignored"));
+            } else if valid.contains(&ch) {
+                warnings.push(format!("Channel {ch} is listed more than once"));
+            } else {
+                valid.push(ch);
+            }
+        }
+        if valid.is_empty() {
+            anyhow::bail!("No valid channels specified (must be 0-5)");
+        }
+        valid.sort_unstable();
+
+        if valid.len() > 4 {
+            let names: Vec<String> = valid[4..].iter().map(|ch| format!("A{}", ch + 1)).collect();
+            warnings.push(format!(
+                "With {} channels, {} {} sent with 6-bit resolution (0-63)",
+                valid.len(),
+                names.join(" and "),
+                if names.len() == 1 { "is" } else { "are" }
+            ));
+        }
+        let throughput = decode::frame_size(valid.len()) * rate as usize;
+        if throughput * 3 > LINK_BYTES_PER_SEC * 2 {
+            warnings.push(format!(
+                "{} channels at {} Hz is near RFCOMM limits ({} of {} bytes/s); \
+                 expect dropped frames on a weak link",
+                valid.len(),
+                rate as u16,
+                throughput,
+                LINK_BYTES_PER_SEC
+            ));
+        }
+        Ok(warnings)
+    }
+
     /// Stop data acquisition.
     pub fn stop(&mut self) -> Result<()> {
+        if let Some(capture) = self.raw_capture.as_mut() {
+            if let Err(e) = capture.flush() {
+                device_log!(
+                    warn,
+                    self.device_label,
+                    "Failed to flush raw capture: {}",
+                    e
+                );
+            }
+        }
+        self.finish_pulse();
         self.send_command(CMD_STOP)?;
         self.active_channels.clear();
         self.frame_size = 0;
-        self.start_time = None;
+        if let Some(started) = self.start_time.take() {
+            self.counters.stopped_after = Some(started.elapsed());
+        }
         self.last_seq = None;
+        self.last_frame = None;
+        self.read_buffer.clear();
+        self.edge_detector.reset();
         Ok(())
     }
 
+    /// Bring the device back to idle from any state, e.g. after a host crash
+    /// left it streaming or waiting for the argument of a PWM command.
+    ///
+    /// BITalino 2.0 devices, and devices whose firmware is not known yet, get
+    /// the go-to-idle command (`0xFF`) before the stop command; older
+    /// firmware only gets the stop command. Pending input is then discarded.
+    /// `version()` calls this by itself once the device is known to be a
+    /// BITalino 2.0.
+    #[allow(dead_code)]
+    pub fn force_idle(&mut self) -> Result<()> {
+        if self.firmware.is_none() || self.is_bitalino2() {
+            self.send_command(CMD_IDLE)?;
+        }
+        self.stop()?;
+        std::thread::sleep(STOP_DELAY);
+        self.flush_input()
+    }
+
+    /// Release the driver without stopping acquisition.
+    ///
+    /// By default, dropping a `Bitalino` that is still acquiring sends the stop
+    /// command and drains pending input so the device is not left streaming.
+    /// `leak()` disables that guard: the transport is closed but no command is
+    /// sent, so a device reachable over a persistent link (e.g. a bound
+    /// `/dev/rfcomm0`) keeps streaming for the next consumer.
+    #[allow(dead_code)]
+    pub fn leak(mut self) {
+        self.stop_on_drop = false;
+    }
+
+    /// Enable or disable stopping acquisition when the driver is dropped.
+    ///
+    /// Enabled by default. See [`leak`](Self::leak) for the consuming variant.
+    #[allow(dead_code)]
+    pub fn set_stop_on_drop(&mut self, enabled: bool) {
+        self.stop_on_drop = enabled;
+    }
+
     /// Set the battery threshold level.
     ///
     /// When battery voltage drops below this threshold, the device LED will blink.
@@ -535,7 +1640,9 @@ impl Bitalino {
                                            // Command format: <threshold (6 bits)> 0 0
         let cmd = threshold << 2;
         self.send_command(cmd)?;
-        debug!(
+        device_log!(
+            debug,
+            self.device_label,
             "Battery threshold set to {} (≈{:.2}V)",
             threshold,
             3.4 + (threshold as f32 / 63.0) * 0.4
@@ -543,6 +1650,39 @@ impl Bitalino {
         Ok(())
     }
 
+    /// Measure one command round trip (BITalino 2.0+ only, while idle).
+    ///
+    /// Times a state request from the moment the command byte is written until
+    /// the whole response has arrived, skipping the settling delay `state()`
+    /// adds after commands. The device receives a command somewhere within the
+    /// returned interval.
+    ///
+    /// # Errors
+    /// Fails on devices without the state command, during acquisition, or if
+    /// the response does not arrive within the I/O timeout.
+    #[allow(dead_code)]
+    pub fn ping(&mut self) -> Result<Duration> {
+        self.require("ping()", |c| c.state)?;
+        if self.frame_size > 0 {
+            anyhow::bail!("Cannot ping during acquisition. Call stop() first.");
+        }
+        self.flush_input()?;
+
+        let mut response = [0u8; 17];
+        let n_bytes = self.capabilities.state_reply_len;
+        let sent = Instant::now();
+        {
+            let _guard = self
+                .command_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.transport.write_all(&[CMD_STATE])?;
+            self.transport.flush()?;
+        }
+        self.transport.read_exact(&mut response[..n_bytes])?;
+        Ok(sent.elapsed())
+    }
+
     /// Get the current device state (BITalino 2.0+ only).
     ///
     /// Returns the current values of all analog channels, digital channels,
@@ -551,84 +1691,83 @@ impl Bitalino {
     /// # Errors
     /// - Returns error if device is not BITalino 2.0+
     /// - Returns error if device is currently in acquisition mode
-    /// - Returns error if CRC check fails
+    /// - Returns error if the CRC check fails under [`CrcPolicy::Strict`], or
+    ///   keeps failing under [`CrcPolicy::Retry`]
     #[allow(dead_code)]
     pub fn state(&mut self) -> Result<DeviceState> {
-        if !self.is_bitalino2 {
-            anyhow::bail!("state() is only available on BITalino 2.0+ devices. Call version() first to detect device type.");
-        }
+        self.require("state()", |c| c.state)?;
 
         if self.frame_size > 0 {
             anyhow::bail!("Cannot read state during acquisition. Call stop() first.");
         }
 
-        // Flush any pending data in the buffer before sending command
-        self.flush_input()?;
-
-        // Send state command
-        self.send_command(CMD_STATE)?;
-
         // Read state response (length depends on firmware)
         // BITalino <=5.1: 16 bytes
         // BITalino >=5.2: 17 bytes (one extra reserved byte before battery threshold)
-        let (n_bytes, offset): (usize, isize) = if self.is_bitalino52 {
-            (17, -1)
-        } else {
-            (16, 0)
-        };
-
-        let mut data = vec![0u8; n_bytes];
-        self.transport.read_exact(&mut data)?;
-
-        // Flush any extra data the device might have sent
-        self.flush_input()?;
-
-        // Print raw data for debugging
-        debug!("State response raw data: {:02X?}", data);
-
-        // Verify CRC (last 4 bits of last byte)
-        // From official Python code:
-        // crc = decodedData[-1] & 0x0F
-        // decodedData[-1] = decodedData[-1] & 0xF0
-        // x = 0
-        // for i in range(number_bytes):
-        //     for bit in range(7, -1, -1):
-        //         x = x << 1
-        //         if (x & 0x10):
-        //             x = x ^ 0x03
-        //         x = x ^ ((decodedData[i] >> bit) & 0x01)
-
-        let last = data.len() - 1;
-        let received_crc = data[last] & 0x0F;
-
-        // Clear CRC bits in last byte before calculation
-        let mut data_for_crc = data.clone();
-        data_for_crc[last] &= 0xF0;
+        let n_bytes = self.capabilities.state_reply_len;
+        let offset: isize = if n_bytes == 17 { -1 } else { 0 };
+
+        let mut attempt = 0;
+        let data = loop {
+            // Flush any pending data in the buffer before sending command
+            self.flush_input()?;
+            self.send_command(CMD_STATE)?;
+
+            let mut data = vec![0u8; n_bytes];
+            self.transport.read_exact(&mut data)?;
+
+            // Flush any extra data the device might have sent
+            self.flush_input()?;
+            device_log!(
+                debug,
+                self.device_label,
+                "State response raw data: {:02X?}",
+                data
+            );
 
-        // Calculate CRC exactly as in official Python code
-        let mut x: u8 = 0;
-        for &byte in &data_for_crc {
-            for bit in (0..8).rev() {
-                x <<= 1;
-                if (x & 0x10) != 0 {
-                    x ^= 0x03;
+            let (received_crc, calculated_crc) = state_crc(&data);
+            device_log!(
+                debug,
+                self.device_label,
+                "State CRC: received={:#X}, calculated={:#X}",
+                received_crc,
+                calculated_crc
+            );
+            if received_crc == calculated_crc {
+                break data;
+            }
+            attempt += 1;
+            match self.crc_policy {
+                // Some firmware versions may not send a proper CRC for the
+                // state command while the data itself is valid.
+                CrcPolicy::Lenient => {
+                    device_log!(
+                        warn,
+                        self.device_label,
+                        "CRC mismatch in state response (received: {:#X}, calculated: {:#X}), continuing anyway",
+                        received_crc,
+                        calculated_crc
+                    );
+                    break data;
+                }
+                CrcPolicy::Retry(retries) if attempt <= retries => {
+                    device_log!(
+                        warn,
+                        self.device_label,
+                        "CRC mismatch in state response (received: {:#X}, calculated: {:#X}), retrying ({}/{})",
+                        received_crc, calculated_crc, attempt, retries
+                    );
+                }
+                _ => {
+                    return Err(DriverError::Crc(format!(
+                    "state response failed CRC in {} attempt(s) (received {:#X}, calculated {:#X})",
+                    attempt, received_crc, calculated_crc
+                ))
+                    .into())
                 }
-                x ^= (byte >> bit) & 0x01;
             }
-        }
-        let calculated_crc = x & 0x0F;
-
-        debug!(
-            "State CRC: received={:#X}, calculated={:#X}",
-            received_crc, calculated_crc
-        );
-
-        if received_crc != calculated_crc {
-            // Note: Some BITalino firmware versions may not send proper CRC for state command.
-            // The data appears valid even when CRC doesn't match, so we log a warning but continue.
-            warn!("CRC mismatch in state response (received: {:#X}, calculated: {:#X}), continuing anyway",
-                          received_crc, calculated_crc);
-        }
+        };
+        let last = data.len() - 1;
 
         // Decode response - official Python code reads from the end using negative indices
         // decodedData[-1] = data[15] (digital + CRC)
@@ -661,23 +1800,297 @@ impl Bitalino {
 
         let analog = [a1, a2, a3, a4, a5, a6];
 
-        debug!(
+        device_log!(
+            debug,
+            self.device_label,
             "Device state: analog={:?}, battery={}, threshold={}, digital={:?}",
-            analog, battery, battery_threshold, digital
+            analog,
+            battery,
+            battery_threshold,
+            digital
         );
 
-        Ok(DeviceState {
+        let state = DeviceState {
             analog,
             battery,
             battery_threshold,
             digital,
-        })
+        };
+        if let Some(monitor) = self.battery_monitor.as_mut() {
+            monitor.record_measurement(Instant::now(), state.battery_voltage());
+        }
+        #[cfg(feature = "metrics")]
+        health::record_battery(&self.device_label, state.battery_voltage());
+        Ok(state)
+    }
+
+    /// Install a low-battery hook: `callback` runs when a check finds the
+    /// battery below `threshold_volts`.
+    ///
+    /// The driver owns the transport exclusively, so checks happen inside calls
+    /// made on it rather than on a background task:
+    /// - While acquiring, the first read after each `interval` takes a real
+    ///   reading through [`state_during_acquisition`](Self::state_during_acquisition)
+    ///   (BITalino 2.0+), which pauses the stream for a few hundred milliseconds;
+    ///   the lost samples count as dropped. Should the query fail, the voltage is
+    ///   extrapolated from earlier readings instead and a warning is logged.
+    /// - While idle, call [`poll_battery`](Self::poll_battery), which queries
+    ///   `state()` at most once per `interval`.
+    ///
+    /// Pick an `interval` of minutes rather than seconds to keep the pauses rare.
+    /// The callback fires on every check below the threshold, so recordings can be
+    /// stopped gracefully before the device dies. Replaces any previous monitor.
+    #[allow(dead_code)]
+    pub fn monitor_battery<F>(&mut self, interval: Duration, threshold_volts: f32, callback: F)
+    where
+        F: FnMut(BatteryReading) + Send + 'static,
+    {
+        self.battery_monitor = Some(BatteryMonitor::new(interval, threshold_volts, callback));
+    }
+
+    /// Remove the battery monitor installed by [`monitor_battery`](Self::monitor_battery).
+    #[allow(dead_code)]
+    pub fn stop_battery_monitor(&mut self) {
+        self.battery_monitor = None;
+    }
+
+    /// Run the battery monitor if its interval has elapsed. Reads already do
+    /// this while acquiring; see [`monitor_battery`](Self::monitor_battery).
+    ///
+    /// Returns the reading taken, or `None` when no monitor is installed, the
+    /// interval has not elapsed, or the device cannot report its battery
+    /// (BITalino 1.0).
+    ///
+    /// # Errors
+    /// Returns error if the state query fails.
+    #[allow(dead_code)]
+    pub fn poll_battery(&mut self) -> Result<Option<BatteryReading>> {
+        let now = Instant::now();
+        let due = self
+            .battery_monitor
+            .as_ref()
+            .is_some_and(|monitor| monitor.is_due(now));
+        if !due {
+            return Ok(None);
+        }
+
+        let reading = if self.capabilities.state {
+            let (state, _) = self.state_during_acquisition()?;
+            Some(BatteryReading {
+                voltage: state.battery_voltage(),
+                estimated: false,
+            })
+        } else {
+            None
+        };
+
+        if let Some(monitor) = self.battery_monitor.as_mut() {
+            monitor.report(now, reading);
+        }
+        Ok(reading)
+    }
+
+    /// Watch the frame rate during acquisition and call `callback` when more
+    /// than `max_deficit` (a fraction, e.g. `0.1`) of the nominal frames fail
+    /// to arrive over a sliding `window`.
+    ///
+    /// Every timed read feeds the monitor the number of valid frames it
+    /// returned, so saturation or interference is reported while streaming
+    /// rather than found as gaps afterwards. The callback fires at most once
+    /// per `window` while the shortfall lasts, and each warning is also logged.
+    /// The window restarts with every `start()`. Replaces any previous monitor,
+    /// including a [`throughput_warnings`](Self::throughput_warnings) channel.
+    #[allow(dead_code)]
+    pub fn monitor_throughput<F>(&mut self, window: Duration, max_deficit: f64, callback: F)
+    where
+        F: FnMut(ThroughputWarning) + Send + 'static,
+    {
+        let mut monitor = ThroughputMonitor::new(window, max_deficit, callback);
+        monitor.reset(self.sampling_rate);
+        self.throughput_monitor = Some(monitor);
+    }
+
+    /// Receive throughput warnings through a channel.
+    ///
+    /// Convenience over [`monitor_throughput`](Self::monitor_throughput) for
+    /// consumers on another thread. Warnings stop once the receiver is dropped.
+    #[allow(dead_code)]
+    pub fn throughput_warnings(
+        &mut self,
+        window: Duration,
+        max_deficit: f64,
+    ) -> Receiver<ThroughputWarning> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.monitor_throughput(window, max_deficit, move |warning| {
+            let _ = tx.send(warning);
+        });
+        rx
+    }
+
+    /// Remove the monitor installed by [`monitor_throughput`](Self::monitor_throughput).
+    #[allow(dead_code)]
+    pub fn stop_throughput_monitor(&mut self) {
+        self.throughput_monitor = None;
+    }
+
+    /// Feed the throughput monitor the valid frames of a finished read.
+    fn check_throughput(&mut self, frames: usize) {
+        let Some(monitor) = self.throughput_monitor.as_mut() else {
+            return;
+        };
+        if let Some(warning) = monitor.record(Instant::now(), frames) {
+            device_log!(
+                warn,
+                self.device_label,
+                "Receiving {:.1} frames/s of {} Hz over the last {:.1} s ({:.0}% short)",
+                warning.received_hz,
+                warning.expected_hz,
+                warning.window.as_secs_f64(),
+                warning.deficit * 100.0
+            );
+        }
+    }
+
+    /// Battery check for the read path: a real reading once the interval has
+    /// elapsed, or an extrapolated one if the state query fails.
+    fn check_battery(&mut self) {
+        let Err(e) = self.poll_battery() else {
+            return;
+        };
+        device_log!(
+            warn,
+            self.device_label,
+            "Battery check failed, extrapolating: {}",
+            e
+        );
+        let now = Instant::now();
+        let reading = self.estimate_battery(now);
+        if let Some(monitor) = self.battery_monitor.as_mut() {
+            monitor.report(now, reading);
+            #[cfg(feature = "metrics")]
+            if let Some(reading) = reading {
+                health::record_battery(&self.device_label, reading.voltage);
+            }
+        }
+    }
+
+    /// Query the adapter for the RSSI and link quality of the active connection.
+    ///
+    /// Correlate these with `FrameBatch::sequence_gaps` to tell radio problems
+    /// from host-side stalls. Batches already carry a sample taken at most once
+    /// per second; call this directly for an immediate reading.
+    ///
+    /// # Errors
+    /// Returns an `Unsupported` I/O error for transports without a radio link
+    /// (serial ports, TCP, replays) and on platforms whose Bluetooth stack does
+    /// not expose the metrics (currently everything but Linux/BlueZ).
+    #[allow(dead_code)]
+    pub fn link_quality(&mut self) -> Result<LinkQuality> {
+        Ok(self.transport.link_quality()?)
+    }
+
+    /// Link quality for the read path: re-sampled at most every
+    /// `LINK_QUALITY_INTERVAL`, and never again once the transport reports it
+    /// cannot measure the link.
+    fn sample_link_quality(&mut self) -> Option<LinkQuality> {
+        let sampler = self.link_sampler.as_mut()?;
+        let now = Instant::now();
+        if sampler
+            .polled
+            .is_some_and(|at| now.duration_since(at) < LINK_QUALITY_INTERVAL)
+        {
+            return sampler.last;
+        }
+        // Failed queries also wait a full interval, keeping the previous sample.
+        sampler.polled = Some(now);
+        match self.transport.link_quality() {
+            Ok(quality) => sampler.last = Some(quality),
+            Err(e) if e.kind() == ErrorKind::Unsupported => {
+                self.link_sampler = None;
+                return None;
+            }
+            Err(e) => device_log!(debug, self.device_label, "link quality query failed: {e}"),
+        }
+        sampler.last
+    }
+
+    fn estimate_battery(&self, now: Instant) -> Option<BatteryReading> {
+        self.battery_monitor
+            .as_ref()
+            .and_then(|monitor| monitor.estimate(now))
+            .map(|voltage| BatteryReading {
+                voltage,
+                estimated: true,
+            })
+    }
+
+    /// Query the device state while acquisition is running (BITalino 2.0+ only).
+    ///
+    /// `state()` is only answered in idle mode, so this pauses streaming, drains
+    /// in-flight frames, queries the state, and restarts with the previous sampling
+    /// rate and channels. The acquisition clock (`elapsed_us()`), sample counter,
+    /// statistics and drift model carry over the pause. When acquisition is not running this
+    /// is equivalent to `state()`.
+    ///
+    /// # Returns
+    /// The device state and the estimated number of samples lost during the pause
+    /// (pause duration × sampling rate).
+    ///
+    /// # Errors
+    /// - Returns error if device is not BITalino 2.0+ (checked before pausing)
+    /// - Returns error if the state query fails; acquisition is restarted first
+    #[allow(dead_code)]
+    pub fn state_during_acquisition(&mut self) -> Result<(DeviceState, u64)> {
+        if !self.is_acquiring() {
+            return self.state().map(|state| (state, 0));
+        }
+        self.require("state()", |c| c.state)?;
+
+        let channels = self.active_channels.clone();
+        let rate = self.sampling_rate;
+        let start_time = self.start_time;
+        let start_utc = self.start_utc;
+        let start_sent = self.start_sent;
+        let start_offset = self.start_offset;
+        let samples_received = self.samples_received;
+        let counters = self.counters;
+        let timing = self.timing.clone();
+        let annotations = std::mem::take(&mut self.annotations);
+
+        let paused_at = Instant::now();
+        self.stop()?;
+        let output_events = self.take_output_events();
+        std::thread::sleep(STOP_DELAY);
+        let _ = self.flush_input();
+
+        let state = self.state();
+        self.start_with_rate(rate, channels)?;
+
+        let samples_lost = (paused_at.elapsed().as_secs_f64() * rate as u16 as f64).round() as u64;
+        self.start_time = start_time;
+        self.start_utc = start_utc;
+        self.start_sent = start_sent;
+        self.start_offset = start_offset;
+        self.samples_received = samples_received + samples_lost;
+        self.counters = counters;
+        self.timing = timing;
+        self.annotations = annotations;
+        *self.lock_output_events() = output_events;
+        device_log!(
+            debug,
+            self.device_label,
+            "Resumed acquisition after state query (~{} samples lost)",
+            samples_lost
+        );
+
+        Ok((state?, samples_lost))
     }
 
     /// Set digital output pins.
     ///
     /// Controls the digital output pins on the BITalino device. These can be used
-    /// to control external circuits or the device's LED.
+    /// to control external circuits or the device's LED. Ends a pulse started
+    /// with [`trigger_pulse`](Self::trigger_pulse) first.
     ///
     /// # Arguments
     /// * `outputs` - Array of output values:
@@ -688,35 +2101,180 @@ impl Bitalino {
     /// - BITalino 1.0: Returns error if not in acquisition mode
     #[allow(dead_code)]
     pub fn trigger(&mut self, outputs: &[u8]) -> Result<()> {
-        if self.is_bitalino2 {
+        let levels = output_levels(outputs);
+        let cmd = self.trigger_command(levels, "trigger()")?;
+        self.finish_pulse();
+        let sent = Instant::now();
+        self.send_command(cmd)?;
+        self.digital_outputs = levels;
+        self.record_output(OutputCommand::Digital(levels), sent);
+        Ok(())
+    }
+
+    /// Raise digital outputs for `width`, then bring them back down.
+    ///
+    /// Outputs set in `outputs` go high at once; the others keep the level
+    /// last set with [`trigger`](Self::trigger). The falling edge is written
+    /// by a short-lived thread timed from the rising edge, so the call returns
+    /// immediately and frames can be read during the pulse, e.g. to send sync
+    /// pulses to an EEG amplifier or a camera. Transports that cannot be
+    /// written from another thread (Windows RFCOMM, replay) block for `width`
+    /// instead.
+    ///
+    /// A new pulse, `trigger()` or `stop()` ends a pending pulse early: on
+    /// BITalino 1.0 the trigger command is only valid during acquisition, so
+    /// the falling edge is always sent before the stop command.
+    ///
+    /// # Errors
+    /// - BITalino 1.0: Returns error if not in acquisition mode
+    /// - Fails if the rising edge cannot be written
+    #[allow(dead_code)]
+    pub fn trigger_pulse(&mut self, outputs: &[u8], width: Duration) -> Result<()> {
+        let low = self.digital_outputs;
+        let mut high = low;
+        for (level, pulsed) in high.iter_mut().zip(output_levels(outputs)) {
+            *level |= pulsed;
+        }
+        let high_cmd = self.trigger_command(high, "trigger_pulse()")?;
+        let low_cmd = self.trigger_command(low, "trigger_pulse()")?;
+        self.finish_pulse();
+
+        let writer = self.transport.try_clone_writer().ok();
+        let rose = {
+            let _guard = self
+                .command_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.transport.write_all(&[high_cmd])?;
+            self.transport.flush()?;
+            Instant::now()
+        };
+        self.record_output(OutputCommand::Digital(high), rose);
+        let fall_at = rose + width;
+
+        if let Some(writer) = writer {
+            let recorder = self.output_recorder();
+            let on_fall = move |fell| {
+                if let Some(record) = recorder {
+                    record(OutputCommand::Digital(low), fell);
+                }
+            };
+            let lock = Arc::clone(&self.command_lock);
+            match PendingPulse::spawn(
+                writer,
+                lock,
+                fall_at,
+                low_cmd,
+                self.device_label.clone(),
+                on_fall,
+            ) {
+                Ok(pulse) => {
+                    self.pulse = Some(pulse);
+                    return Ok(());
+                }
+                Err(e) => device_log!(
+                    warn,
+                    self.device_label,
+                    "Failed to schedule the end of the trigger pulse: {}",
+                    e
+                ),
+            }
+        }
+        std::thread::sleep(fall_at.saturating_duration_since(Instant::now()));
+        let fell = Instant::now();
+        self.send_command(low_cmd)?;
+        self.record_output(OutputCommand::Digital(low), fell);
+        Ok(())
+    }
+
+    /// Trigger command setting the outputs to `levels`, checking that the
+    /// device accepts it now.
+    fn trigger_command(&self, levels: [u8; 4], caller: &str) -> Result<u8> {
+        let outputs = self.capabilities.digital_outputs as usize;
+        if let Some(extra) = levels[outputs..].iter().position(|&level| level != 0) {
+            return Err(DriverError::Unsupported(format!(
+                "{caller} cannot raise O{}: {} has {} digital outputs",
+                outputs + extra + 1,
+                self.firmware_label(),
+                outputs
+            ))
+            .into());
+        }
+        let [o1, o2, o3, o4] = levels;
+        if self.capabilities.idle_trigger {
             // BITalino 2.0: Works in both idle and acquisition modes
             // Command format: 1 0 1 1 O2 O1 1 1
-            let o1 = outputs.first().copied().unwrap_or(0) & 0x01;
-            let o2 = outputs.get(1).copied().unwrap_or(0) & 0x01;
-            let cmd = CMD_TRIGGER_2 | (o2 << 3) | (o1 << 2);
-            self.send_command(cmd)?;
+            Ok(CMD_TRIGGER_2 | (o2 << 3) | (o1 << 2))
         } else {
             // BITalino 1.0: Only works during acquisition
             if self.frame_size == 0 {
-                anyhow::bail!(
-                    "BITalino 1.0 trigger() requires active acquisition. Call start() first."
-                );
+                return Err(DriverError::NotAcquiring(format!(
+                    "{caller} requires active acquisition on {} (idle triggers need firmware {}+). Call start() first.",
+                    self.firmware_label(),
+                    FirmwareVersion::BITALINO_2
+                ))
+                .into());
             }
             // Command format: 1 0 O4 O3 O2 O1 1 1
-            let o1 = outputs.first().copied().unwrap_or(0) & 0x01;
-            let o2 = outputs.get(1).copied().unwrap_or(0) & 0x01;
-            let o3 = outputs.get(2).copied().unwrap_or(0) & 0x01;
-            let o4 = outputs.get(3).copied().unwrap_or(0) & 0x01;
-            let cmd = CMD_TRIGGER_1 | (o4 << 5) | (o3 << 4) | (o2 << 3) | (o1 << 2);
-            self.send_command(cmd)?;
+            Ok(CMD_TRIGGER_1 | (o4 << 5) | (o3 << 4) | (o2 << 3) | (o1 << 2))
+        }
+    }
+
+    /// Function recording output commands written at a given instant on the
+    /// current acquisition's sample axis; `None` when not acquiring.
+    fn output_recorder(&self) -> Option<impl Fn(OutputCommand, Instant) + Send + 'static> {
+        let start = self.start_time?;
+        let timing = self.timing.clone();
+        let events = Arc::clone(&self.output_events);
+        let label = self.device_label.clone();
+        Some(move |command, sent: Instant| {
+            let timestamp_us = sent.saturating_duration_since(start).as_micros() as u64;
+            let event = OutputEvent {
+                command,
+                sample_index: timing.sample_index_at(timestamp_us),
+                timestamp_us,
+            };
+            device_log!(
+                debug,
+                label,
+                "Output {:?} at sample {}",
+                command,
+                event.sample_index
+            );
+            events
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(event);
+        })
+    }
+
+    /// Record `command`, written at `sent`, if acquiring.
+    fn record_output(&self, command: OutputCommand, sent: Instant) {
+        if let Some(record) = self.output_recorder() {
+            record(command, sent);
+        }
+    }
+
+    fn lock_output_events(&self) -> std::sync::MutexGuard<'_, Vec<OutputEvent>> {
+        self.output_events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Send the falling edge of a pending trigger pulse now, if it has not
+    /// been sent yet.
+    fn finish_pulse(&mut self) {
+        if let Some(pulse) = self.pulse.take() {
+            pulse.finish();
         }
-        Ok(())
     }
 
     /// Set PWM output value (BITalino 2.0+ only).
     ///
     /// Controls the pulse-width modulation output on the BITalino device.
     /// Can be used for controlling LED brightness or other PWM-driven devices.
+    /// Cancels a schedule started with [`pwm_pattern`](Self::pwm_pattern) or
+    /// [`pwm_ramp`](Self::pwm_ramp).
     ///
     /// # Arguments
     /// * `value` - PWM duty cycle (0-255)
@@ -727,19 +2285,26 @@ impl Bitalino {
     /// Returns error if device is not BITalino 2.0+
     #[allow(dead_code)]
     pub fn pwm(&mut self, value: u8) -> Result<()> {
-        if !self.is_bitalino2 {
-            anyhow::bail!("pwm() is only available on BITalino 2.0+ devices");
-        }
+        self.require("pwm()", |c| c.pwm)?;
+        self.cancel_pwm_schedule();
 
         // Two-byte command: 0xA3 followed by PWM value
-        self.transport.write_all(&[CMD_PWM_PREFIX])?;
-        self.transport.flush()?;
-        std::thread::sleep(COMMAND_DELAY);
-        self.transport.write_all(&[value])?;
-        self.transport.flush()?;
-        std::thread::sleep(COMMAND_DELAY);
-
-        debug!(
+        {
+            let _guard = self
+                .command_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            pwm::write_pwm(&mut *self.transport, value)?;
+        }
+        // The value byte went out one command delay before write_pwm returned.
+        let sent = Instant::now()
+            .checked_sub(COMMAND_DELAY)
+            .unwrap_or_else(Instant::now);
+        self.record_output(OutputCommand::Pwm(value), sent);
+
+        device_log!(
+            debug,
+            self.device_label,
             "PWM output set to {} ({:.1}%)",
             value,
             value as f32 / 255.0 * 100.0
@@ -747,369 +2312,1917 @@ impl Bitalino {
         Ok(())
     }
 
-    /// Check if this is a BITalino 2.0+ device.
+    /// Play PWM `steps` of `(value, duration)` on a scheduler thread
+    /// (BITalino 2.0+ only).
     ///
-    /// Returns true if the device supports extended features like `state()`, `pwm()`,
-    /// and `trigger()` in idle mode.
+    /// Each value is held for its duration, timed from the start of the
+    /// schedule, and the last one stays set. Reading frames, or any other
+    /// command, can go on meanwhile, e.g. to flash an LED for photic
+    /// stimulation during an acquisition. Sending a value takes
+    /// [`pwm::MIN_STEP`], so shorter steps are stretched to it.
     ///
-    /// Note: You must call `version()` first to detect the device type.
+    /// Replaces a schedule already running; `pwm()` cancels it.
+    ///
+    /// # Errors
+    /// Fails if the device is not BITalino 2.0+ or the transport cannot be
+    /// written from another thread (Windows RFCOMM, replay).
     #[allow(dead_code)]
-    pub fn is_bitalino2(&self) -> bool {
-        self.is_bitalino2
+    pub fn pwm_pattern(&mut self, steps: &[(u8, Duration)]) -> Result<PwmSchedule> {
+        self.require("pwm_pattern()", |c| c.pwm)?;
+        let writer = self.transport.try_clone_writer().map_err(|e| {
+            if e.kind() == ErrorKind::Unsupported {
+                anyhow::anyhow!("This transport cannot send PWM schedules")
+            } else {
+                anyhow::Error::new(e).context("Failed to open a second handle to the device")
+            }
+        })?;
+        self.cancel_pwm_schedule();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let schedule = PwmSchedule::spawn(
+            writer,
+            Arc::clone(&self.command_lock),
+            Arc::clone(&cancel),
+            steps.to_vec(),
+            self.device_label.clone(),
+        )?;
+        self.pwm_cancel = Some(cancel);
+        Ok(schedule)
     }
 
-    /// Get the current sampling rate.
+    /// Move the PWM output linearly from `from` to `to` over `duration` on a
+    /// scheduler thread (BITalino 2.0+ only).
+    ///
+    /// The ramp changes value at most once per [`pwm::MIN_STEP`]; see
+    /// [`pwm_pattern`](Self::pwm_pattern).
+    ///
+    /// # Errors
+    /// Same as [`pwm_pattern`](Self::pwm_pattern).
     #[allow(dead_code)]
-    pub fn sampling_rate(&self) -> SamplingRate {
-        self.sampling_rate
+    pub fn pwm_ramp(&mut self, from: u8, to: u8, duration: Duration) -> Result<PwmSchedule> {
+        self.pwm_pattern(&pwm::ramp_steps(from, to, duration))
     }
 
-    /// Get the time since acquisition started, in microseconds.
-    pub fn elapsed_us(&self) -> Option<u64> {
-        self.start_time.map(|t| t.elapsed().as_micros() as u64)
+    /// Stop the PWM schedule started last, if still running.
+    fn cancel_pwm_schedule(&mut self) {
+        if let Some(cancel) = self.pwm_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
     }
 
-    /// Read multiple frames from the device.
-    ///
-    /// Convenience wrapper around [`read_frames_timed`](Self::read_frames_timed) that
-    /// discards the batch's `timestamp_us`, `crc_errors`, and `sequence_gaps`. Prefer
-    /// `read_frames_timed` when you need to detect dropped or corrupted frames.
+    /// Write `bytes` to the device as they are, one command byte at a time.
     ///
-    /// # Arguments
-    /// * `n_frames` - Number of frames to read
+    /// **Escape hatch** for experimenting with undocumented firmware
+    /// commands. The driver does not interpret the bytes, so its own view of
+    /// the device (acquiring or idle, rate, channels, output levels) is not
+    /// updated: a raw start or stop command leaves the read path expecting
+    /// the previous frame layout. Prefer the typed methods for anything they
+    /// cover. Each byte is followed by the usual command delay, and a running
+    /// PWM schedule is never interleaved with them.
     ///
-    /// # Returns
-    /// Vector of frames. May contain fewer frames than requested if CRC errors occur.
-    pub fn read_frames(&mut self, n_frames: usize) -> Result<Vec<Frame>> {
-        let batch = self.read_frames_timed(n_frames)?;
-        Ok(batch.frames)
+    /// # Errors
+    /// Fails if the write fails.
+    #[allow(dead_code)]
+    pub fn send_raw(&mut self, bytes: &[u8]) -> Result<()> {
+        device_log!(
+            debug,
+            self.device_label,
+            "Sending raw bytes: {:02X?}",
+            bytes
+        );
+        for &byte in bytes {
+            self.send_command(byte)?;
+        }
+        Ok(())
     }
 
-    /// Block until the device is reliably streaming valid frames.
-    ///
-    /// After [`start`](Self::start) returns, the device begins emitting frames but the
-    /// Bluetooth link may still be warming up — early bytes can be lost or corrupted,
-    /// so the first reads may fail CRC. This method discards those warm-up frames and
-    /// returns as soon as one CRC-valid frame arrives. The valid frame is consumed
-    /// (not surfaced to the caller), and `last_seq` is updated so subsequent calls to
-    /// [`read_frames_timed`](Self::read_frames_timed) don't flag a spurious gap.
+    /// Read up to `n` bytes from the device as they arrive, waiting at most
+    /// `timeout`; fewer bytes (possibly none) are returned if the timeout
+    /// elapses or the link closes first.
     ///
-    /// # Arguments
-    /// * `timeout` - Maximum time to wait for a valid frame. Transient I/O errors
-    ///   (`WouldBlock` / `TimedOut` / `Interrupted`) are retried while bytes already
-    ///   read are kept; their wait time counts against the deadline.
+    /// **Escape hatch** to go with [`send_raw`](Self::send_raw): the bytes
+    /// are taken from the same stream frames are decoded from, so reading
+    /// during acquisition misaligns the next frames until
+    /// [`wait_until_streaming`](Self::wait_until_streaming) finds the frame
+    /// boundary again.
     ///
     /// # Errors
-    /// - Returns an error if acquisition is not started.
-    /// - Returns an error starting with `"Timeout"` if no CRC-valid frame arrives
-    ///   before `timeout` elapses.
-    pub fn wait_until_streaming(&mut self, timeout: Duration) -> Result<()> {
-        if self.frame_size == 0 {
-            anyhow::bail!("Acquisition not started. Call start() first.");
-        }
-
+    /// Fails on transport errors other than timeouts.
+    #[allow(dead_code)]
+    pub fn read_raw(&mut self, n: usize, timeout: Duration) -> Result<Vec<u8>> {
         let deadline = Instant::now()
             .checked_add(timeout)
             .ok_or_else(|| anyhow::anyhow!("timeout too large: deadline would overflow"))?;
+        let take = self.read_buffer.len().min(n);
+        let mut bytes = self.read_buffer.as_slice()[..take].to_vec();
+        self.read_buffer.consume(take);
 
-        // Tighten the kernel-level read timeout so a fully-silent peer cannot
-        // overshoot the caller's deadline by up to the default 5 s SO_RCVTIMEO.
-        // Best-effort: backends that do not support per-call tuning (the trait
-        // default no-op) keep their existing socket-level timeout.
         let _ = self
             .transport
-            .set_read_timeout(timeout.min(DEFAULT_TIMEOUT));
+            .set_read_timeout(timeout.min(self.io_timeout).max(Duration::from_millis(1)));
+        let mut chunk = [0u8; 256];
+        let result = loop {
+            if bytes.len() >= n || Instant::now() >= deadline {
+                break Ok(());
+            }
+            let want = (n - bytes.len()).min(chunk.len());
+            match self.transport.read(&mut chunk[..want]) {
+                Ok(0) => break Ok(()),
+                Ok(got) => {
+                    self.capture_raw(&chunk[..got]);
+                    self.counters.bytes_read += got as u64;
+                    bytes.extend_from_slice(&chunk[..got]);
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = self.transport.set_read_timeout(self.io_timeout);
+        result?;
+        device_log!(debug, self.device_label, "Read raw bytes: {:02X?}", bytes);
+        Ok(bytes)
+    }
 
-        let result = self.wait_until_streaming_inner(timeout, deadline);
+    /// Check if this is a BITalino 2.0+ device.
+    ///
+    /// Returns true if the device supports extended features like `state()`, `pwm()`,
+    /// and `trigger()` in idle mode.
+    ///
+    /// Note: You must call `version()` first to detect the device type.
+    #[allow(dead_code)]
+    pub fn is_bitalino2(&self) -> bool {
+        self.firmware
+            .is_some_and(|v| v >= FirmwareVersion::BITALINO_2)
+    }
 
-        // Restore the connector's default timeout regardless of outcome.
-        let _ = self.transport.set_read_timeout(DEFAULT_TIMEOUT);
+    /// Firmware release parsed from the last successful `version()`.
+    #[allow(dead_code)]
+    pub fn firmware(&self) -> Option<FirmwareVersion> {
+        self.firmware
+    }
 
-        result
+    /// Features of the device's firmware; those of BITalino 1.0 until
+    /// `version()` has succeeded, since every firmware accepts them.
+    #[allow(dead_code)]
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
     }
 
-    fn wait_until_streaming_inner(&mut self, timeout: Duration, deadline: Instant) -> Result<()> {
-        let mut buffer = vec![0u8; self.frame_size];
-        let mut discarded = 0usize;
-        let mut crc_failures = 0usize;
+    /// Whether acquisition is currently running (between `start()` and `stop()`).
+    #[allow(dead_code)]
+    pub fn is_acquiring(&self) -> bool {
+        self.frame_size > 0
+    }
 
-        loop {
-            if !self.fill_buffer(&mut buffer, Some(deadline))? {
-                anyhow::bail!(
-                    "Timeout waiting for valid frames after {:?} ({} discarded, {} CRC failures)",
-                    timeout,
-                    discarded,
-                    crc_failures
-                );
-            }
-            discarded += 1;
-            if self.verify_crc(&buffer) {
-                let frame = self.decode_frame(&buffer);
-                self.last_seq = Some(frame.seq);
-                debug!(
-                    "Streaming ready after {} frames ({} CRC failures)",
-                    discarded, crc_failures
+    /// Get the current sampling rate.
+    #[allow(dead_code)]
+    pub fn sampling_rate(&self) -> SamplingRate {
+        self.sampling_rate
+    }
+
+    /// Channels being acquired, sorted and without duplicates as `start()`
+    /// sent them (empty while idle).
+    pub fn active_channels(&self) -> &[u8] {
+        &self.active_channels
+    }
+
+    /// Baseline read/write timeout of the transport (5 s unless changed).
+    #[allow(dead_code)]
+    pub fn io_timeout(&self) -> Duration {
+        self.io_timeout
+    }
+
+    /// Change the transport's read and write timeouts at runtime.
+    ///
+    /// Tighten it during acquisition to notice a stalled link quickly, and relax
+    /// it again for command exchanges such as `version()` or `state()`. Reads
+    /// with their own budget (`read_frames_timed_with`, `wait_until_streaming`)
+    /// still shorten the read timeout temporarily and restore this value after.
+    ///
+    /// # Errors
+    /// Fails for a zero timeout, or if the transport rejects the new value.
+    #[allow(dead_code)]
+    pub fn set_io_timeout(&mut self, timeout: Duration) -> Result<()> {
+        anyhow::ensure!(!timeout.is_zero(), "I/O timeout must be greater than zero");
+        self.transport.set_read_timeout(timeout)?;
+        self.transport.set_write_timeout(timeout)?;
+        self.io_timeout = timeout;
+        Ok(())
+    }
+
+    /// Get the time since acquisition started, in microseconds.
+    pub fn elapsed_us(&self) -> Option<u64> {
+        self.start_time.map(|t| t.elapsed().as_micros() as u64)
+    }
+
+    /// Wall-clock time at which the current (or most recent) acquisition
+    /// started, for aligning recordings with external logs or video.
+    ///
+    /// Taken together with the monotonic clock behind
+    /// [`elapsed_us`](Self::elapsed_us), so `start + elapsed_us` is the host
+    /// time of a read. `None` before the first `start()`.
+    #[allow(dead_code)]
+    pub fn acquisition_start_utc(&self) -> Option<SystemTime> {
+        self.start_utc
+    }
+
+    /// When the start command of the current (or most recent) acquisition was
+    /// written to the transport. Unlike the `elapsed_us` origin, this excludes
+    /// the delay `start()` waits after sending commands.
+    #[allow(dead_code)]
+    pub fn start_command_sent(&self) -> Option<Instant> {
+        self.start_sent
+    }
+
+    /// How long after the requested time the start command of the current
+    /// (or most recent) [`start_at`](Self::start_at) was written; `None` if
+    /// acquisition was started without a schedule.
+    #[allow(dead_code)]
+    pub fn start_offset(&self) -> Option<Duration> {
+        self.start_offset
+    }
+
+    /// Clock-drift model for the current (or most recent) acquisition.
+    #[allow(dead_code)]
+    pub fn timing(&self) -> &TimingModel {
+        &self.timing
+    }
+
+    /// Estimated device crystal drift in ppm, once enough data has been read.
+    ///
+    /// See [`TimingModel::drift_ppm`].
+    #[allow(dead_code)]
+    pub fn drift_ppm(&self) -> Option<f64> {
+        self.timing.drift_ppm()
+    }
+
+    /// Number of frames the device has produced since `start()`.
+    ///
+    /// Counts delivered frames plus those lost to CRC errors or sequence gaps
+    /// (as revealed by the next valid frame's sequence number), so it tracks the
+    /// device's sample clock rather than what was decoded.
+    #[allow(dead_code)]
+    pub fn samples_received(&self) -> u64 {
+        self.samples_received
+    }
+
+    /// Name identifying this device in log lines, exported metrics and tracing
+    /// spans.
+    ///
+    /// Drivers opened with [`from_profile`](Self::from_profile) or
+    /// [`connect_tcp`](Self::connect_tcp) use the MAC or bridge address;
+    /// others default to `"bitalino"`.
+    #[allow(dead_code)]
+    pub fn device_label(&self) -> &str {
+        &self.device_label
+    }
+
+    /// Override [`device_label`](Self::device_label).
+    pub fn set_device_label(&mut self, label: impl Into<String>) {
+        self.device_label = label.into();
+    }
+
+    /// Record the Bluetooth address of the device for
+    /// [`device_info`](Self::device_info), and use it as the device label.
+    ///
+    /// Drivers built with [`from_rfcomm`](Self::from_rfcomm) cannot read the
+    /// address back from the stream; [`from_profile`](Self::from_profile)
+    /// calls this for you.
+    pub fn set_mac(&mut self, mac: impl Into<String>) {
+        let mac = mac.into();
+        self.device_label = mac.clone();
+        self.mac = Some(mac);
+    }
+
+    /// Identity and capabilities of the connected device.
+    ///
+    /// Firmware-derived fields stay at their defaults until `version()` has
+    /// succeeded once.
+    pub fn device_info(&self) -> DeviceInfo {
+        DeviceInfo {
+            mac: self.mac.clone(),
+            firmware_version: self.firmware_version.clone(),
+            is_bitalino2: self.is_bitalino2(),
+            supported_rates: SamplingRate::ALL.to_vec(),
+            n_analog_channels: MAX_ANALOG_CHANNELS,
+            connection_kind: self.transport.connection_kind(),
+            was_streaming: self.was_streaming.unwrap_or(false),
+        }
+    }
+
+    /// Cumulative statistics for the current (or most recent) acquisition.
+    ///
+    /// Counters are updated as each frame is decoded, so frames from a read that
+    /// later fails are included. Everything resets on `start()`; after `stop()`
+    /// the snapshot keeps the totals and the final acquisition length.
+    pub fn stats(&self) -> AcquisitionStats {
+        let elapsed = match self.start_time {
+            Some(started) => started.elapsed(),
+            None => self.counters.stopped_after.unwrap_or_default(),
+        };
+        self.counters
+            .snapshot(self.samples_received, self.sampling_rate, elapsed)
+    }
+
+    /// Windowed mean, RMS, min/max and envelope of each acquired channel.
+    ///
+    /// Updated by every read from the most recent [`stats_window`](Self::stats_window)
+    /// of frames, in ADC counts and in acquisition channel order. Empty before
+    /// the first frame after `start()`.
+    #[allow(dead_code)]
+    pub fn channel_stats(&self) -> Vec<ChannelStats> {
+        self.moving_stats.snapshot(&self.active_channels)
+    }
+
+    /// What the driver does with data failing its CRC check.
+    #[allow(dead_code)]
+    pub fn crc_policy(&self) -> CrcPolicy {
+        self.crc_policy
+    }
+
+    /// Change how CRC mismatches are handled (default
+    /// [`CrcPolicy::Lenient`]).
+    #[allow(dead_code)]
+    pub fn set_crc_policy(&mut self, policy: CrcPolicy) {
+        self.crc_policy = policy;
+    }
+
+    /// Length of the window behind [`channel_stats`](Self::channel_stats).
+    #[allow(dead_code)]
+    pub fn stats_window(&self) -> Duration {
+        self.moving_stats.window()
+    }
+
+    /// Change the window behind [`channel_stats`](Self::channel_stats)
+    /// (default 1 s). Shrinking it discards the oldest samples immediately.
+    #[allow(dead_code)]
+    pub fn set_stats_window(&mut self, window: Duration) {
+        self.moving_stats.set_window(window, self.sampling_rate);
+    }
+
+    /// Call `callback` for every digital-channel transition in incoming frames.
+    ///
+    /// Edges are detected in the read path (`read_frames_timed()` and friends)
+    /// and tagged with the absolute sample index, which accounts for frames lost
+    /// to sequence gaps. The first frame after `start()` only sets the baseline.
+    /// Replaces any previous handler, including a [`digital_edges`](Self::digital_edges)
+    /// channel.
+    #[allow(dead_code)]
+    pub fn on_digital_edge<F>(&mut self, callback: F)
+    where
+        F: FnMut(DigitalEdge) + Send + 'static,
+    {
+        self.edge_callback = Some(Box::new(callback));
+    }
+
+    /// Subscribe to digital edges through a channel.
+    ///
+    /// Convenience over [`on_digital_edge`](Self::on_digital_edge) for consumers
+    /// on another thread. Events stop once the receiver is dropped.
+    #[allow(dead_code)]
+    pub fn digital_edges(&mut self) -> std::sync::mpsc::Receiver<DigitalEdge> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.on_digital_edge(move |edge| {
+            let _ = tx.send(edge);
+        });
+        rx
+    }
+
+    /// Stop reporting digital edges.
+    #[allow(dead_code)]
+    pub fn clear_digital_edge_handler(&mut self) {
+        self.edge_callback = None;
+    }
+
+    /// Detect heartbeats on the ECG attached to analog `channel` (0-5) and call
+    /// `callback` for each one.
+    ///
+    /// Runs a Pan-Tompkins QRS detector (see [`QrsDetector`]) in the read path,
+    /// so beats carry the absolute sample index of their R peak. Detection
+    /// restarts with every `start()`, trains for two seconds, and needs a
+    /// sampling rate of at least 100 Hz. Replaces any previous handler,
+    /// including a [`heartbeats`](Self::heartbeats) channel.
+    ///
+    /// # Errors
+    /// Returns an error if `channel` is not 0-5.
+    #[allow(dead_code)]
+    pub fn on_heartbeat<F>(&mut self, channel: u8, callback: F) -> Result<()>
+    where
+        F: FnMut(Beat) + Send + 'static,
+    {
+        if channel as usize >= MAX_ANALOG_CHANNELS {
+            anyhow::bail!("Invalid ECG channel {} (must be 0-5)", channel);
+        }
+        self.heartbeat = Some(HeartbeatHook {
+            channel,
+            detector: QrsDetector::new(self.sampling_rate),
+            callback: Box::new(callback),
+        });
+        Ok(())
+    }
+
+    /// Receive heartbeats on analog `channel` through a channel.
+    ///
+    /// Convenience over [`on_heartbeat`](Self::on_heartbeat) for consumers on
+    /// another thread. Events stop once the receiver is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if `channel` is not 0-5.
+    #[allow(dead_code)]
+    pub fn heartbeats(&mut self, channel: u8) -> Result<Receiver<Beat>> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.on_heartbeat(channel, move |beat| {
+            let _ = tx.send(beat);
+        })?;
+        Ok(rx)
+    }
+
+    /// Stop detecting heartbeats.
+    #[allow(dead_code)]
+    pub fn clear_heartbeat_handler(&mut self) {
+        self.heartbeat = None;
+    }
+
+    /// Decompose the EDA sensor on analog `channel` (0-5) and call `callback`
+    /// for each skin-conductance response.
+    ///
+    /// Runs an [`EdaDecomposer`] in the read path: samples are converted to
+    /// microsiemens, smoothed, and split into tonic and phasic components (see
+    /// [`eda_level`](Self::eda_level)). Responses are reported shortly after
+    /// their peak. Decomposition restarts with every `start()`. Replaces any
+    /// previous handler, including an [`scrs`](Self::scrs) channel.
+    ///
+    /// # Errors
+    /// Returns an error if `channel` is not 0-5.
+    #[allow(dead_code)]
+    pub fn on_scr<F>(&mut self, channel: u8, callback: F) -> Result<()>
+    where
+        F: FnMut(Scr) + Send + 'static,
+    {
+        if channel as usize >= MAX_ANALOG_CHANNELS {
+            anyhow::bail!("Invalid EDA channel {} (must be 0-5)", channel);
+        }
+        self.eda = Some(EdaHook {
+            channel,
+            decomposer: None,
+            level: None,
+            callback: Box::new(callback),
+        });
+        Ok(())
+    }
+
+    /// Receive skin-conductance responses on analog `channel` through a channel.
+    ///
+    /// Convenience over [`on_scr`](Self::on_scr) for consumers on another
+    /// thread. Events stop once the receiver is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if `channel` is not 0-5.
+    #[allow(dead_code)]
+    pub fn scrs(&mut self, channel: u8) -> Result<Receiver<Scr>> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.on_scr(channel, move |scr| {
+            let _ = tx.send(scr);
+        })?;
+        Ok(rx)
+    }
+
+    /// Tonic and phasic skin conductance at the last sample read, or `None`
+    /// without an [`on_scr`](Self::on_scr) handler or before its first sample.
+    #[allow(dead_code)]
+    pub fn eda_level(&self) -> Option<EdaLevel> {
+        self.eda.as_ref().and_then(|hook| hook.level)
+    }
+
+    /// Stop EDA decomposition.
+    #[allow(dead_code)]
+    pub fn clear_scr_handler(&mut self) {
+        self.eda = None;
+    }
+
+    /// Record an event marker at the current sample.
+    ///
+    /// The sample index is derived from the host clock and the drift model, so it
+    /// points at the sample the device is acquiring now rather than the last one
+    /// read (which lags by the Bluetooth buffering delay). Markers are kept until
+    /// the next `start()` or [`take_annotations`](Self::take_annotations).
+    ///
+    /// # Errors
+    /// Returns error if acquisition is not running.
+    #[allow(dead_code)]
+    pub fn annotate(&mut self, label: impl Into<String>) -> Result<Annotation> {
+        let Some(timestamp_us) = self.elapsed_us() else {
+            return Err(DriverError::NotAcquiring(
+                "Acquisition not started. Call start() first.".into(),
+            )
+            .into());
+        };
+        let annotation = Annotation {
+            label: label.into(),
+            sample_index: self.timing.sample_index_at(timestamp_us),
+            timestamp_us,
+        };
+        device_log!(
+            debug,
+            self.device_label,
+            "Annotation '{}' at sample {}",
+            annotation.label,
+            annotation.sample_index
+        );
+        self.annotations.push(annotation.clone());
+        Ok(annotation)
+    }
+
+    /// Markers recorded with [`annotate`](Self::annotate), oldest first.
+    #[allow(dead_code)]
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// Remove and return the recorded markers (e.g. after writing them out).
+    #[allow(dead_code)]
+    pub fn take_annotations(&mut self) -> Vec<Annotation> {
+        std::mem::take(&mut self.annotations)
+    }
+
+    /// Output changes sent with `trigger()`, `trigger_pulse()` and `pwm()`
+    /// during the current acquisition, oldest first.
+    ///
+    /// Like annotations, they are kept until the next `start()` or
+    /// [`take_output_events`](Self::take_output_events). Commands sent while
+    /// idle are not recorded, and neither are PWM schedules.
+    #[allow(dead_code)]
+    pub fn output_events(&self) -> Vec<OutputEvent> {
+        self.lock_output_events().clone()
+    }
+
+    /// Remove and return the recorded output changes.
+    #[allow(dead_code)]
+    pub fn take_output_events(&mut self) -> Vec<OutputEvent> {
+        std::mem::take(&mut *self.lock_output_events())
+    }
+
+    /// Open a [`Session`] describing the running acquisition: its start time,
+    /// the device identity and the acquired channels (sensors `RAW` until
+    /// labelled, e.g. with [`Session::apply_profile`]).
+    ///
+    /// An open session is replaced with a warning.
+    ///
+    /// # Errors
+    /// Fails if acquisition has not been started.
+    pub fn begin_session(&mut self) -> Result<&mut Session> {
+        let Some(started) = self.start_utc.filter(|_| self.start_time.is_some()) else {
+            return Err(DriverError::NotAcquiring(
+                "Acquisition not started. Call start() first.".into(),
+            )
+            .into());
+        };
+        if self.session.is_some() {
+            device_log!(
+                warn,
+                self.device_label,
+                "begin_session() replaces a session that was never ended"
+            );
+        }
+        let device = self.device_info();
+        let info = RecordingInfo {
+            mac: device.mac.unwrap_or_else(|| self.device_label.clone()),
+            sampling_rate: self.sampling_rate as u16,
+            channels: self.active_channels.clone(),
+            sensors: vec!["RAW".to_string(); self.active_channels.len()],
+            ports: Vec::new(),
+            is_bitalino2: device.is_bitalino2,
+            firmware_version: device.firmware_version,
+            connection: device.connection_kind,
+            started,
+        };
+        Ok(self.session.insert(Session::new(info)))
+    }
+
+    /// The session opened with [`begin_session`](Self::begin_session), if it
+    /// has not ended yet.
+    #[allow(dead_code)]
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    /// Close the open session, filling in the annotations, output events and
+    /// statistics gathered so far. Works after `stop()` too, since those are
+    /// kept until the next `start()`.
+    ///
+    /// # Errors
+    /// Fails if no session is open.
+    pub fn end_session(&mut self) -> Result<Session> {
+        let mut session = self
+            .session
+            .take()
+            .context("No open session. Call begin_session() first.")?;
+        session.annotations = self.annotations.clone();
+        session.output_events = self.output_events();
+        session.stats = Some(self.stats());
+        session.ended = Some(SystemTime::now());
+        Ok(session)
+    }
+
+    /// Tee the raw acquisition stream into `path` (created or truncated).
+    ///
+    /// Every byte read while decoding frames is written to the file exactly as
+    /// received, including frames that fail CRC checks. Command responses (e.g.
+    /// `version()`, `state()`) are not captured, so the dump can be played back
+    /// with [`ReplayTransport`](crate::replay::ReplayTransport). Write failures
+    /// disable the capture with a warning instead of interrupting acquisition.
+    #[allow(dead_code)]
+    pub fn enable_raw_capture(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create raw capture at {}", path.display()))?;
+        self.raw_capture = Some(BufWriter::new(file));
+        device_log!(
+            debug,
+            self.device_label,
+            "Raw capture enabled: {}",
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Stop capturing the raw stream and flush the file.
+    #[allow(dead_code)]
+    pub fn disable_raw_capture(&mut self) -> Result<()> {
+        if let Some(mut capture) = self.raw_capture.take() {
+            capture.flush().context("Failed to flush raw capture")?;
+        }
+        Ok(())
+    }
+
+    fn capture_raw(&mut self, bytes: &[u8]) {
+        if let Some(capture) = self.raw_capture.as_mut() {
+            if let Err(e) = capture.write_all(bytes) {
+                device_log!(
+                    warn,
+                    self.device_label,
+                    "Raw capture write failed, disabling capture: {}",
+                    e
                 );
-                return Ok(());
+                self.raw_capture = None;
             }
-            crc_failures += 1;
         }
     }
 
-    /// Read exactly `buf.len()` bytes from the transport, optionally honoring a
-    /// wall-clock deadline.
+    /// Read multiple frames from the device.
     ///
-    /// With `Some(deadline)`, returns `Ok(true)` when the buffer is filled and
-    /// `Ok(false)` when the deadline elapses first. With `None`, blocks (modulo
-    /// kernel-level socket timeouts) until the buffer is filled. Bytes already read
-    /// are kept across transient `WouldBlock` / `TimedOut` / `Interrupted` errors so
-    /// the BITalino frame cursor stays aligned; `read_exact` cannot offer this
-    /// guarantee on its own.
-    fn fill_buffer(&mut self, buf: &mut [u8], deadline: Option<Instant>) -> Result<bool> {
-        let mut filled = 0usize;
-        while filled < buf.len() {
-            if let Some(d) = deadline {
-                if Instant::now() >= d {
-                    return Ok(false);
-                }
-            }
-            match self.transport.read(&mut buf[filled..]) {
-                Ok(0) => anyhow::bail!("transport closed during read"),
-                Ok(n) => filled += n,
-                Err(e)
-                    if matches!(
-                        e.kind(),
-                        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
-                    ) => {}
-                Err(e) => return Err(e.into()),
+    /// Convenience wrapper around [`read_frames_timed`](Self::read_frames_timed) that
+    /// discards the batch's `timestamp_us`, `crc_errors`, and `sequence_gaps`. Prefer
+    /// `read_frames_timed` when you need to detect dropped or corrupted frames.
+    ///
+    /// # Arguments
+    /// * `n_frames` - Number of frames to read
+    ///
+    /// # Returns
+    /// Vector of frames. May contain fewer frames than requested if CRC errors occur.
+    pub fn read_frames(&mut self, n_frames: usize) -> Result<Vec<Frame>> {
+        let batch = self.read_frames_timed(n_frames)?;
+        Ok(batch.frames)
+    }
+
+    /// Read frames covering `duration` at the acquisition rate, as one batch.
+    ///
+    /// Reads repeat until the batch spans `duration` worth of sample indices,
+    /// so frames discarded for CRC errors are made up for by later reads.
+    /// Frames lost on the link still count towards the duration; they show in
+    /// [`FrameBatch::sequence_gaps`].
+    ///
+    /// # Errors
+    /// Fails if acquisition is not running or a read fails.
+    #[allow(dead_code)]
+    pub fn read_for(&mut self, duration: Duration) -> Result<FrameBatch> {
+        let wanted = self.sampling_rate.frames_in(duration) as u64;
+        let mut batch = self.read_frames_timed(wanted as usize)?;
+        let start = batch.first_sample_index;
+        loop {
+            let covered = batch
+                .frames
+                .last()
+                .map_or(0, |f| f.sample_index + 1 - start);
+            if covered >= wanted {
+                return Ok(batch);
             }
+            let next = self.read_frames_timed((wanted - covered) as usize)?;
+            batch.append(next);
         }
-        Ok(true)
     }
 
-    /// Read multiple frames with timing and error statistics.
+    /// Block until the device is reliably streaming valid frames.
     ///
-    /// This is the recommended method for high-quality acquisition as it provides:
-    /// - Timestamp for timing reconstruction
-    /// - CRC error count
-    /// - Sequence gap detection for dropped frames
+    /// After [`start`](Self::start) returns, the device begins emitting frames but the
+    /// Bluetooth link may still be warming up — early bytes can be lost or corrupted,
+    /// so the first reads may fail CRC. This method discards those warm-up frames and
+    /// returns as soon as one CRC-valid frame arrives. The valid frame is consumed
+    /// (not surfaced to the caller), and `last_seq` is updated so subsequent calls to
+    /// [`read_frames_timed`](Self::read_frames_timed) don't flag a spurious gap.
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum time to wait for a valid frame. Transient I/O errors
+    ///   (`WouldBlock` / `TimedOut` / `Interrupted`) are retried while bytes already
+    ///   read are kept; their wait time counts against the deadline.
+    ///
+    /// # Errors
+    /// - Returns an error if acquisition is not started.
+    /// - Returns an error starting with `"Timeout"` if no CRC-valid frame arrives
+    ///   before `timeout` elapses.
+    pub fn wait_until_streaming(&mut self, timeout: Duration) -> Result<()> {
+        if self.frame_size == 0 {
+            return Err(DriverError::NotAcquiring(
+                "Acquisition not started. Call start() first.".into(),
+            )
+            .into());
+        }
+
+        let deadline = Instant::now()
+            .checked_add(timeout)
+            .ok_or_else(|| anyhow::anyhow!("timeout too large: deadline would overflow"))?;
+
+        // Tighten the kernel-level read timeout so a fully-silent peer cannot
+        // overshoot the caller's deadline by up to the baseline SO_RCVTIMEO.
+        // Best-effort: backends that do not support per-call tuning (the trait
+        // default no-op) keep their existing socket-level timeout.
+        let _ = self
+            .transport
+            .set_read_timeout(timeout.min(self.io_timeout));
+
+        let result = self.wait_until_streaming_inner(timeout, deadline);
+
+        // Restore the baseline timeout regardless of outcome.
+        let _ = self.transport.set_read_timeout(self.io_timeout);
+
+        result
+    }
+
+    fn wait_until_streaming_inner(&mut self, timeout: Duration, deadline: Instant) -> Result<()> {
+        let mut storage = [0u8; MAX_FRAME_SIZE];
+        let buffer = &mut storage[..self.frame_size];
+        let mut discarded = 0usize;
+        let mut crc_failures = 0usize;
+
+        loop {
+            if !self.fill_buffer(buffer, Some(deadline))? {
+                if discarded > 0 && crc_failures == discarded {
+                    // Bytes are flowing but never validate: corruption, not silence.
+                    return Err(DriverError::Crc(format!(
+                        "no valid frame within {:?} ({} frames failed CRC)",
+                        timeout, crc_failures
+                    ))
+                    .into());
+                }
+                return Err(DriverError::Timeout(format!(
+                    "Timeout waiting for valid frames after {:?} ({} discarded, {} CRC failures)",
+                    timeout, discarded, crc_failures
+                ))
+                .into());
+            }
+            discarded += 1;
+            if self.verify_crc(buffer) {
+                let mut frame = self.decode_frame(buffer);
+                frame.sample_index = self.samples_received;
+                self.last_seq = Some(frame.seq);
+                self.last_frame = Some(frame);
+                self.samples_received += 1;
+                self.counters.frames += 1;
+                device_log!(
+                    debug,
+                    self.device_label,
+                    "Streaming ready after {} frames ({} CRC failures)",
+                    discarded,
+                    crc_failures
+                );
+                return Ok(());
+            }
+            crc_failures += 1;
+            self.counters.crc_errors += 1;
+        }
+    }
+
+    /// Take exactly `buf.len()` bytes of the stream, optionally honoring a
+    /// wall-clock deadline.
+    ///
+    /// Bytes come from the read buffer, refilled by [`read_ahead`](Self::read_ahead)
+    /// when it runs short. With `Some(deadline)`, returns `Ok(true)` when the
+    /// buffer is filled and `Ok(false)` when the deadline elapses first. With
+    /// `None`, blocks (modulo kernel-level socket timeouts) until the buffer is
+    /// filled. Bytes already read stay buffered across transient `WouldBlock` /
+    /// `TimedOut` / `Interrupted` errors and elapsed deadlines, so the BITalino
+    /// frame cursor stays aligned; `read_exact` cannot offer this guarantee on
+    /// its own.
+    fn fill_buffer(&mut self, buf: &mut [u8], deadline: Option<Instant>) -> Result<bool> {
+        if !self.buffer_at_least(buf.len(), deadline)? {
+            return Ok(false);
+        }
+        buf.copy_from_slice(&self.read_buffer.as_slice()[..buf.len()]);
+        self.read_buffer.consume(buf.len());
+        Ok(true)
+    }
+
+    /// Read ahead until the read buffer holds `n` bytes; `Ok(false)` if the
+    /// deadline elapses first.
+    fn buffer_at_least(&mut self, n: usize, deadline: Option<Instant>) -> Result<bool> {
+        while self.read_buffer.len() < n {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                return Ok(false);
+            }
+            self.read_ahead()?;
+        }
+        Ok(true)
+    }
+
+    /// Append one transport `read()` of up to [`READ_CHUNK`] bytes to the read
+    /// buffer. Transient errors leave it unchanged.
+    fn read_ahead(&mut self) -> Result<()> {
+        let mut buffer = std::mem::take(&mut self.read_buffer);
+        let result = buffer
+            .fill_from(&mut *self.transport, READ_CHUNK)
+            .map(|bytes| {
+                self.capture_raw(bytes);
+                self.counters.bytes_read += bytes.len() as u64;
+                bytes.len()
+            });
+        self.read_buffer = buffer;
+        match result {
+            Ok(0) => anyhow::bail!("transport closed during read"),
+            Ok(_) => Ok(()),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                ) =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read multiple frames with timing and error statistics.
+    ///
+    /// This is the recommended method for high-quality acquisition as it provides:
+    /// - Timestamp for timing reconstruction
+    /// - CRC error count
+    /// - Sequence gap detection for dropped frames
+    ///
+    /// Blocks until all `n_frames` are read. Use
+    /// [`read_frames_timed_with`](Self::read_frames_timed_with) to bound the wait.
     pub fn read_frames_timed(&mut self, n_frames: usize) -> Result<FrameBatch> {
+        self.read_frames_timed_with(n_frames, &ReadOptions::default())
+    }
+
+    /// Read multiple frames with timing and error statistics, bounded by `options`.
+    ///
+    /// When `options.timeout` or `options.max_wait_per_frame` elapses before all
+    /// `n_frames` arrive, the frames read so far are returned with
+    /// [`FrameBatch::partial`] set if `options.allow_partial` is true. Otherwise an
+    /// error starting with `"Timeout"` is returned.
+    pub fn read_frames_timed_with(
+        &mut self,
+        n_frames: usize,
+        options: &ReadOptions,
+    ) -> Result<FrameBatch> {
         if self.frame_size == 0 {
-            anyhow::bail!("Acquisition not started. Call start() first.");
+            return Err(DriverError::NotAcquiring(
+                "Acquisition not started. Call start() first.".into(),
+            )
+            .into());
+        }
+
+        let deadline =
+            match options.timeout {
+                Some(t) => Some(Instant::now().checked_add(t).ok_or_else(|| {
+                    anyhow::anyhow!("timeout too large: deadline would overflow")
+                })?),
+                None => None,
+            };
+
+        // Tighten the kernel-level read timeout so a silent peer cannot overshoot
+        // the caller's budget by the baseline SO_RCVTIMEO (5 s by default).
+        let read_timeout = [options.timeout, options.max_wait_per_frame]
+            .into_iter()
+            .flatten()
+            .min();
+        if let Some(t) = read_timeout {
+            let _ = self.transport.set_read_timeout(t.min(self.io_timeout));
+        }
+
+        let result = self.read_frames_inner(n_frames, deadline, options);
+
+        if read_timeout.is_some() {
+            let _ = self.transport.set_read_timeout(self.io_timeout);
         }
 
-        let timestamp_us = self.elapsed_us().unwrap_or(0);
-        let mut frames = Vec::with_capacity(n_frames);
-        let mut buffer = vec![0u8; self.frame_size];
-        let mut crc_errors = 0usize;
-        let mut sequence_gaps = 0usize;
+        result
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "read_batch",
+            level = "debug",
+            skip_all,
+            fields(
+                device = %self.device_label,
+                n_frames = n_frames,
+                frames = tracing::field::Empty,
+                crc_errors = tracing::field::Empty,
+                sequence_gaps = tracing::field::Empty,
+                first_seq = tracing::field::Empty,
+                last_seq = tracing::field::Empty,
+                partial = tracing::field::Empty,
+            )
+        )
+    )]
+    fn read_frames_inner(
+        &mut self,
+        n_frames: usize,
+        deadline: Option<Instant>,
+        options: &ReadOptions,
+    ) -> Result<FrameBatch> {
+        let timestamp_us = self.elapsed_us().unwrap_or(0);
+        let mut frames = Vec::with_capacity(n_frames);
+        let mut decoded = Vec::new();
+        let mut taken = 0usize;
+        let mut crc_errors = 0usize;
+        let mut sequence_gaps = 0usize;
+        let mut partial = false;
+
+        while taken < n_frames {
+            let frame_deadline = match (deadline, options.max_wait_per_frame) {
+                (Some(d), Some(w)) => Some(d.min(Instant::now() + w)),
+                (Some(d), None) => Some(d),
+                (None, Some(w)) => Some(Instant::now() + w),
+                (None, None) => None,
+            };
+            if !self.buffer_at_least(self.frame_size, frame_deadline)? {
+                partial = true;
+                break;
+            }
+            // Decode every buffered frame this batch still needs in one pass.
+            let run = (self.read_buffer.len() / self.frame_size).min(n_frames - taken);
+            decoded.clear();
+            decode::decode_frames(
+                &self.read_buffer.as_slice()[..run * self.frame_size],
+                self.active_channels.len(),
+                &mut decoded,
+            );
+            taken += run;
+
+            for (mut frame, crc_ok) in decoded.drain(..) {
+                // Consumed one by one, so frames after an error stay buffered.
+                self.read_buffer.consume(self.frame_size);
+                if crc_ok {
+                    // Check for sequence gaps
+                    let mut gap = 0usize;
+                    if let Some(last) = self.last_seq {
+                        let expected = (last + 1) & 0x0F;
+                        if frame.seq != expected {
+                            // Up to 15 dropped frames; 16 would look like no gap at all.
+                            gap = ((frame.seq as i16 - expected as i16 + 16) % 16) as usize;
+                            sequence_gaps += gap;
+                            self.counters.sequence_gaps += gap as u64;
+                        }
+                    }
+                    self.last_seq = Some(frame.seq);
+
+                    // Frames lost to CRC errors or drops show up as a sequence gap on
+                    // the next valid frame, so only valid frames advance the counter.
+                    let sample_index = self.samples_received + gap as u64;
+                    if gap > 0 && options.gap_fill != GapFill::Off {
+                        let held = self.last_frame.unwrap_or(frame);
+                        frames.extend((self.samples_received..sample_index).map(|index| {
+                            let seq = (frame.seq as u64 + 16 - (sample_index - index)) % 16;
+                            held.placeholder(seq as u8, index, options.gap_fill)
+                        }));
+                    }
+                    self.samples_received = sample_index + 1;
+                    frame.sample_index = sample_index;
+                    self.last_frame = Some(frame);
+                    self.counters.frames += 1;
+                    self.moving_stats.push(&frame);
+                    if let Some(callback) = self.edge_callback.as_mut() {
+                        self.edge_detector
+                            .feed(frame.digital, sample_index, callback);
+                    }
+                    if let Some(hook) = self.heartbeat.as_mut() {
+                        let position = self.active_channels.iter().position(|&c| c == hook.channel);
+                        if let Some(&value) = position.and_then(|p| frame.analog_values().get(p)) {
+                            hook.detector.feed(value, sample_index, &mut hook.callback);
+                        }
+                    }
+                    if let Some(hook) = self.eda.as_mut() {
+                        let position = self.active_channels.iter().position(|&c| c == hook.channel);
+                        if let Some(p) = position.filter(|&p| p < frame.analog_values().len()) {
+                            let rate = self.sampling_rate;
+                            let decomposer = hook.decomposer.get_or_insert_with(|| {
+                                EdaDecomposer::new(rate, resolution_bits(p))
+                            });
+                            hook.level = Some(decomposer.feed(
+                                frame.analog[p],
+                                sample_index,
+                                &mut hook.callback,
+                            ));
+                        }
+                    }
+
+                    frames.push(frame);
+                } else {
+                    crc_errors += 1;
+                    self.counters.crc_errors += 1;
+                    if self
+                        .crc_policy
+                        .frame_limit()
+                        .is_some_and(|limit| crc_errors > limit)
+                    {
+                        return Err(DriverError::Crc(format!(
+                            "{} frame(s) failed CRC in one read ({:?})",
+                            crc_errors, self.crc_policy
+                        ))
+                        .into());
+                    }
+                }
+            }
+        }
+
+        if let Some(now_us) = self.elapsed_us() {
+            self.timing.observe(self.samples_received, now_us);
+        }
+
+        if crc_errors > 0 {
+            device_log!(
+                warn,
+                self.device_label,
+                "CRC errors in batch: {} (suppressing per-frame logs)",
+                crc_errors
+            );
+        }
+        if sequence_gaps > 0 {
+            device_log!(
+                warn,
+                self.device_label,
+                "Sequence gaps detected in batch: {} (suppressing per-frame logs)",
+                sequence_gaps
+            );
+        }
+
+        #[cfg(feature = "metrics")]
+        health::record_batch(&self.device_label, frames.len(), crc_errors, sequence_gaps);
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            span.record("frames", frames.len());
+            span.record("crc_errors", crc_errors);
+            span.record("sequence_gaps", sequence_gaps);
+            span.record("partial", partial);
+            if let (Some(first), Some(last)) = (frames.first(), frames.last()) {
+                span.record("first_seq", first.seq);
+                span.record("last_seq", last.seq);
+            }
+        }
+        self.check_throughput(frames.iter().filter(|frame| !frame.filled).count());
+        let link_quality = self.sample_link_quality();
+
+        if partial && !options.allow_partial {
+            return Err(DriverError::Timeout(format!(
+                "Timeout reading frames: got {} of {} requested",
+                frames.len() + crc_errors,
+                n_frames
+            ))
+            .into());
+        }
+
+        let batch = FrameBatch {
+            first_sample_index: frames
+                .first()
+                .map_or(self.samples_received, |frame| frame.sample_index),
+            frames,
+            channels: self.active_channels.clone(),
+            sampling_rate: self.sampling_rate,
+            timestamp_us,
+            crc_errors,
+            sequence_gaps,
+            partial,
+            link_quality,
+            gap_fill: options.gap_fill,
+        };
+        if !batch.frames.is_empty() {
+            self.subscribers.publish(&batch);
+        }
+        self.check_battery();
+        Ok(batch)
+    }
+
+    /// Read only the whole frames already buffered by the transport.
+    ///
+    /// Queries how many bytes can be read without blocking and decodes at most
+    /// `max_frames` complete frames from them, returning immediately. Suitable
+    /// for GUI or game-loop integrations that poll every tick; the returned batch
+    /// is empty when no complete frame has arrived yet. Incomplete trailing bytes
+    /// stay buffered for the next call.
+    ///
+    /// # Errors
+    /// - Returns an error if acquisition is not started.
+    /// - Returns an `Unsupported` I/O error if the transport cannot report its
+    ///   buffered byte count.
+    #[allow(dead_code)]
+    pub fn read_available(&mut self, max_frames: usize) -> Result<FrameBatch> {
+        if self.frame_size == 0 {
+            return Err(DriverError::NotAcquiring(
+                "Acquisition not started. Call start() first.".into(),
+            )
+            .into());
+        }
+
+        let available = self.transport.bytes_available()?;
+        let n_frames = (available / self.frame_size).min(max_frames);
+        if n_frames == 0 {
+            return Ok(FrameBatch {
+                frames: Vec::new(),
+                first_sample_index: self.samples_received,
+                channels: self.active_channels.clone(),
+                sampling_rate: self.sampling_rate,
+                timestamp_us: self.elapsed_us().unwrap_or(0),
+                crc_errors: 0,
+                sequence_gaps: 0,
+                partial: false,
+                link_quality: self.sample_link_quality(),
+                gap_fill: GapFill::Off,
+            });
+        }
+        self.read_frames_timed(n_frames)
+    }
+
+    /// Open a window plotting analog `channel` (0-5) of the running
+    /// acquisition, five seconds at a time, and block until it is closed
+    /// (`plot` feature).
+    ///
+    /// The plot reads the frames, so nothing else receives them meanwhile.
+    /// Call it from the main thread: most platforms only open windows there.
+    ///
+    /// # Errors
+    /// Returns an error if acquisition is not started, `channel` is not being
+    /// acquired, the window cannot be opened, or a read fails.
+    #[cfg(feature = "plot")]
+    #[allow(dead_code)]
+    pub fn live_plot(&mut self, channel: u8) -> Result<()> {
+        if self.frame_size == 0 {
+            return Err(DriverError::NotAcquiring(
+                "Acquisition not started. Call start() first.".into(),
+            )
+            .into());
+        }
+        anyhow::ensure!(
+            self.active_channels.contains(&channel),
+            "Channel {channel} is not being acquired"
+        );
+        let title = format!("BITalino A{}", channel + 1);
+        crate::plot::run(self, &title, &[channel], crate::plot::DEFAULT_WINDOW)
+    }
+
+    /// Receive a copy of every non-empty batch this driver reads.
+    ///
+    /// Whoever drives the read loop (your own `read_frames_timed()` calls or a
+    /// [`BackgroundReader`](crate::streaming::BackgroundReader)) feeds all
+    /// subscribers, so several consumers can share one acquisition. Batches
+    /// arrive as read: under a background reader they may be shorter than its
+    /// batch size at low sampling rates. Channels are unbounded; a consumer
+    /// that falls behind buffers in memory. Dropping the receiver unsubscribes.
+    #[allow(dead_code)]
+    pub fn subscribe(&self) -> Receiver<FrameBatch> {
+        self.subscribers.subscribe(None)
+    }
+
+    /// Like [`subscribe`](Self::subscribe), but each batch first passes
+    /// through `stage` (for example a [`Decimator`](crate::dsp::Decimator)),
+    /// so this consumer can get a reduced or filtered stream while others
+    /// still see the raw one. Batches the stage empties are not delivered.
+    #[allow(dead_code)]
+    pub fn subscribe_through<S: Stage + 'static>(&self, stage: S) -> Receiver<FrameBatch> {
+        self.subscribers.subscribe(Some(Box::new(stage)))
+    }
+
+    pub(crate) fn subscribers(&self) -> &Fanout {
+        &self.subscribers
+    }
+
+    /// Read a single frame from the device.
+    #[allow(dead_code)]
+    pub fn read_frame(&mut self) -> Result<Option<Frame>> {
+        let batch = self.read_frames_timed(1)?;
+        Ok(batch.frames.into_iter().next())
+    }
+
+    // ------------------------------------------------------------------------
+    // Internal Methods
+    // ------------------------------------------------------------------------
+
+    /// Send a command byte to the device.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "command",
+            level = "debug",
+            skip_all,
+            fields(device = %self.device_label, cmd = format_args!("{cmd:#04x}")),
+            err
+        )
+    )]
+    fn send_command(&mut self, cmd: u8) -> Result<()> {
+        // Never split a PWM command sent by a running schedule.
+        let _guard = self
+            .command_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.transport.write_all(&[cmd])?;
+        self.transport.flush()?;
+        std::thread::sleep(COMMAND_DELAY);
+        Ok(())
+    }
+
+    /// Flush any pending input data.
+    fn flush_input(&mut self) -> Result<()> {
+        let mut buf = [0u8; 256];
+        let start = Instant::now();
+        let max_flush = Duration::from_millis(200);
+        let mut iterations = 0usize;
+        loop {
+            iterations += 1;
+            match self.transport.read(&mut buf) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => break,
+                Err(e) => return Err(e.into()),
+            }
+
+            if iterations > 64 || start.elapsed() > max_flush {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calculate the frame size in bytes based on active channels.
+    ///
+    /// BITalino frame structure:
+    /// - 4 digital inputs (4 bits)
+    /// - Sequence number (4 bits)
+    /// - Analog channels: first 4 are 10-bit, remaining are 6-bit
+    fn calculate_frame_size(&self) -> usize {
+        decode::frame_size(self.active_channels.len())
+    }
+
+    /// Verify the CRC of a frame.
+    ///
+    /// BITalino uses a 4-bit CRC stored in the lower nibble of the last byte.
+    fn verify_crc(&self, data: &[u8]) -> bool {
+        decode::check_crc(data)
+    }
+
+    /// Decode a raw frame buffer into a Frame struct.
+    fn decode_frame(&self, data: &[u8]) -> Frame {
+        decode::decode_frame(data, self.active_channels.len())
+    }
+}
+
+impl Drop for Bitalino {
+    fn drop(&mut self) {
+        self.cancel_pwm_schedule();
+        self.finish_pulse();
+        // Best-effort stop so a panic or forgotten `stop()` does not leave the
+        // device streaming. Errors are logged and otherwise ignored.
+        if !self.stop_on_drop || self.frame_size == 0 {
+            return;
+        }
+        device_log!(
+            debug,
+            self.device_label,
+            "Dropping Bitalino during acquisition; sending stop command"
+        );
+        if let Err(e) = self.stop() {
+            device_log!(warn, self.device_label, "stop() on drop failed: {}", e);
+            return;
+        }
+        let _ = self.flush_input();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    impl Transport for Cursor<Vec<u8>> {
+        fn bytes_available(&mut self) -> std::io::Result<usize> {
+            Ok(self.get_ref().len() - self.position() as usize)
+        }
+    }
+
+    /// Fill in the CRC nibble of a raw frame so `verify_crc` accepts it.
+    fn with_crc(mut frame: Vec<u8>) -> Vec<u8> {
+        let last = frame.len() - 1;
+        frame[last] &= 0xF0;
+        let mut crc = 0u8;
+        for &byte in &frame {
+            for bit in (0..8).rev() {
+                crc <<= 1;
+                if (crc & 0x10) != 0 {
+                    crc ^= 0x03;
+                }
+                crc ^= (byte >> bit) & 0x01;
+            }
+        }
+        frame[last] |= crc & 0x0F;
+        frame
+    }
+
+    /// Transport that always reports `WouldBlock`, exercising the deadline path
+    /// without ever closing or returning data.
+    struct AlwaysBlock;
+
+    impl Read for AlwaysBlock {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            std::thread::sleep(Duration::from_millis(5));
+            Err(std::io::Error::from(ErrorKind::WouldBlock))
+        }
+    }
+
+    impl Write for AlwaysBlock {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for AlwaysBlock {}
+
+    /// Transport that serves a fixed byte script, then behaves like `AlwaysBlock`.
+    struct Trickle(Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => AlwaysBlock.read(buf),
+                n => Ok(n),
+            }
+        }
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for Trickle {}
+
+    /// Transport that records every written byte into a shared buffer so tests
+    /// can inspect commands after the driver has been dropped.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        written: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+    }
+
+    impl Read for Recorder {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Ok(0)
+        }
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for Recorder {
+        fn try_clone_writer(&self) -> std::io::Result<Box<dyn Write + Send>> {
+            Ok(Box::new(self.clone()))
+        }
+    }
+
+    /// Cursor transport that also reports link quality, counting queries.
+    struct Radio {
+        data: Cursor<Vec<u8>>,
+        queries: u32,
+    }
+
+    impl Read for Radio {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.data.read(buf)
+        }
+    }
+
+    impl Write for Radio {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for Radio {
+        fn link_quality(&mut self) -> std::io::Result<LinkQuality> {
+            self.queries += 1;
+            Ok(LinkQuality {
+                rssi: Some(-(self.queries as i8)),
+                link_quality: Some(255),
+            })
+        }
+    }
+
+    #[test]
+    fn driver_can_move_to_worker_threads() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Bitalino>();
+        assert_send::<RfcommStream>();
+    }
+
+    #[test]
+    fn validate_config_reports_what_start_would_change() {
+        assert!(Bitalino::validate_config(1, &[0, 1]).unwrap().is_empty());
+        assert!(Bitalino::validate_config(1000, &[0, 1, 2, 3])
+            .unwrap()
+            .is_empty());
+        assert!(Bitalino::validate_config(500, &[0]).is_err());
+        assert!(Bitalino::validate_config(100, &[6, 7]).is_err());
+
+        let warnings = Bitalino::validate_config(100, &[2, 9, 2, 0, 1, 3, 5]).unwrap();
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+        assert!(warnings[0].contains("Channel 9 is out of range"));
+        assert!(warnings[1].contains("Channel 2 is listed more than once"));
+        assert!(warnings[2].contains("A6 is sent with 6-bit"));
+
+        let warnings = Bitalino::validate_config(1000, &[0, 1, 2, 3, 4, 5]).unwrap();
+        assert!(warnings[0].contains("A5 and A6 are sent with 6-bit"));
+        assert!(warnings[1].starts_with("6 channels at 1000 Hz is near RFCOMM limits"));
+    }
+
+    fn build_idle_device<T: Transport + 'static>(transport: T) -> Bitalino {
+        Bitalino::from_transport(Box::new(transport))
+    }
+
+    #[test]
+    fn wait_until_streaming_errors_when_not_started() {
+        let mut dev = build_idle_device(Cursor::new(Vec::new()));
+        let err = dev
+            .wait_until_streaming(Duration::from_millis(50))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("not started"),
+            "expected 'not started' in error: {err}"
+        );
+    }
+
+    #[test]
+    fn wait_until_streaming_times_out_on_silent_peer() {
+        let mut dev = build_idle_device(AlwaysBlock);
+        // Pretend a 1-channel acquisition has started so the precondition passes.
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+
+        let err = dev
+            .wait_until_streaming(Duration::from_millis(50))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Timeout"), "expected timeout error: {err}");
+    }
+
+    #[test]
+    fn drop_during_acquisition_sends_stop() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        drop(dev);
+        assert_eq!(*recorder.written.lock().unwrap(), vec![CMD_STOP]);
+    }
+
+    #[test]
+    fn start_at_waits_for_the_scheduled_time() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        let past = SystemTime::now() - Duration::from_secs(1);
+        assert!(dev.start_at(past, 1000, vec![0]).is_err());
+
+        let at = SystemTime::now() + Duration::from_millis(400);
+        dev.start_at(at, 100, vec![0, 2]).unwrap();
+        assert!(dev.acquisition_start_utc().unwrap() >= at);
+        let offset = dev.start_offset().unwrap();
+        assert!(offset < Duration::from_millis(100), "{offset:?}");
+        // Only the start command waits for the schedule.
+        assert!(recorder.written.lock().unwrap().ends_with(&[0x83, 0x15]));
+
+        dev.start(100, vec![0]).unwrap();
+        assert_eq!(dev.start_offset(), None);
+    }
+
+    #[test]
+    fn force_idle_sends_the_idle_command_to_bitalino2_only() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        dev.set_firmware("BITalino_v5.2");
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        dev.force_idle().unwrap();
+        assert_eq!(*recorder.written.lock().unwrap(), vec![CMD_IDLE, CMD_STOP]);
+        assert!(dev.active_channels.is_empty());
+
+        recorder.written.lock().unwrap().clear();
+        dev.set_firmware("BITalino_v3.1");
+        dev.force_idle().unwrap();
+        assert_eq!(*recorder.written.lock().unwrap(), vec![CMD_STOP]);
+    }
+
+    #[test]
+    fn leak_skips_stop_on_drop() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        dev.leak();
+        assert!(recorder.written.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pwm_pattern_writes_each_value_from_its_own_thread() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        assert!(dev.pwm_pattern(&[(10, Duration::ZERO)]).is_err());
+
+        dev.set_firmware("BITalino_v4.2");
+        let schedule = dev
+            .pwm_pattern(&[(10, Duration::ZERO), (20, Duration::ZERO)])
+            .unwrap();
+        schedule.wait().unwrap();
+        assert_eq!(
+            *recorder.written.lock().unwrap(),
+            [CMD_PWM_PREFIX, 10, CMD_PWM_PREFIX, 20]
+        );
+    }
 
-        for _ in 0..n_frames {
-            self.fill_buffer(&mut buffer, None)?;
+    #[test]
+    fn trigger_pulses_end_before_the_stop_command() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        // BITalino 1.0 only accepts triggers while acquiring.
+        assert!(dev.trigger_pulse(&[1], Duration::ZERO).is_err());
 
-            if self.verify_crc(&buffer) {
-                let frame = self.decode_frame(&buffer);
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        dev.trigger_pulse(&[0, 1], Duration::from_secs(60)).unwrap();
+        dev.stop().unwrap();
+        assert_eq!(
+            *recorder.written.lock().unwrap(),
+            [CMD_TRIGGER_1 | 0x08, CMD_TRIGGER_1, CMD_STOP]
+        );
 
-                // Check for sequence gaps
-                if let Some(last) = self.last_seq {
-                    let expected = (last + 1) & 0x0F;
-                    if frame.seq != expected {
-                        let gap = ((frame.seq as i16 - expected as i16 + 16) % 16) as usize;
-                        if gap > 0 && gap < 8 {
-                            // Likely dropped frames (not a wrap-around confusion)
-                            sequence_gaps += gap;
-                        }
-                    }
-                }
-                self.last_seq = Some(frame.seq);
+        // Outputs left high by trigger() stay high after the pulse.
+        recorder.written.lock().unwrap().clear();
+        dev.set_firmware("BITalino_v4.2");
+        dev.trigger(&[1]).unwrap();
+        dev.trigger_pulse(&[0, 1], Duration::ZERO).unwrap();
+        dev.finish_pulse();
+        assert_eq!(
+            *recorder.written.lock().unwrap(),
+            [
+                CMD_TRIGGER_2 | 0x04,
+                CMD_TRIGGER_2 | 0x0C,
+                CMD_TRIGGER_2 | 0x04
+            ]
+        );
+    }
 
-                frames.push(frame);
-            } else {
-                crc_errors += 1;
-            }
-        }
+    /// Reference frames for every layout, with the values the official BITalino
+    /// API decodes from them: A1..A6 = 1023, 512, 300, 5, 45, 21 and
+    /// I1, I2, O1, O2 = 1, 0, 1, 1.
+    const REFERENCE_FRAMES: [(&[u8], u8); 6] = [
+        (&[0xFC, 0xBF, 0x3F], 3),
+        (&[0x00, 0xFE, 0xBF, 0x64], 6),
+        (&[0x00, 0x4B, 0x00, 0xFE, 0xBF, 0x99], 9),
+        (&[0x50, 0x00, 0x4B, 0x00, 0xFE, 0xBF, 0xC7], 12),
+        (&[0x40, 0x5B, 0x00, 0x4B, 0x00, 0xFE, 0xBF, 0xF1], 15),
+        (&[0x55, 0x5B, 0x00, 0x4B, 0x00, 0xFE, 0xBF, 0x27], 2),
+    ];
 
-        if crc_errors > 0 {
-            warn!(
-                "CRC errors in batch: {} (suppressing per-frame logs)",
-                crc_errors
+    #[test]
+    fn decodes_reference_frames_for_every_channel_count() {
+        let expected_analog = [1023, 512, 300, 5, 45, 21];
+        let mut dev = build_idle_device(Cursor::new(Vec::new()));
+        for (i, (bytes, seq)) in REFERENCE_FRAMES.into_iter().enumerate() {
+            let n_channels = i + 1;
+            dev.active_channels = (0..n_channels as u8).collect();
+            assert_eq!(
+                dev.calculate_frame_size(),
+                bytes.len(),
+                "{n_channels} channels"
             );
-        }
-        if sequence_gaps > 0 {
-            warn!(
-                "Sequence gaps detected in batch: {} (suppressing per-frame logs)",
-                sequence_gaps
+            assert!(dev.verify_crc(bytes), "{n_channels} channels");
+
+            let frame = dev.decode_frame(bytes);
+            assert_eq!(frame.seq, seq);
+            assert_eq!(frame.digital, [1, 0, 1, 1]);
+            assert_eq!(frame.analog_values(), &expected_analog[..n_channels]);
+            assert_eq!(
+                frame.resolution_bits(),
+                &[10, 10, 10, 10, 6, 6][..n_channels]
             );
         }
 
-        Ok(FrameBatch {
-            frames,
-            timestamp_us,
-            crc_errors,
-            sequence_gaps,
-        })
-    }
-
-    /// Read a single frame from the device.
-    #[allow(dead_code)]
-    pub fn read_frame(&mut self) -> Result<Option<Frame>> {
-        let batch = self.read_frames_timed(1)?;
-        Ok(batch.frames.into_iter().next())
+        // Transfer functions use each value's own resolution: A6 = 21 of 63.
+        let frame = dev.decode_frame(REFERENCE_FRAMES[5].0);
+        assert_eq!(frame.physical(5, Sensor::Lux), Some(21.0 / 64.0 * 100.0));
+        assert_eq!(frame.physical(6, Sensor::Lux), None);
     }
 
-    // ------------------------------------------------------------------------
-    // Internal Methods
-    // ------------------------------------------------------------------------
+    #[test]
+    fn to_columns_transposes_frames() {
+        let batch = FrameBatch {
+            frames: vec![
+                Frame::new(1, [1, 0, 0, 1], &[10, 20]),
+                Frame::new(2, [0, 1, 0, 0], &[11, 21]),
+            ],
+            first_sample_index: 0,
+            channels: vec![0, 1],
+            sampling_rate: SamplingRate::Hz1000,
+            timestamp_us: 0,
+            crc_errors: 0,
+            sequence_gaps: 0,
+            partial: false,
+            link_quality: None,
+            gap_fill: GapFill::Off,
+        };
 
-    /// Send a command byte to the device.
-    fn send_command(&mut self, cmd: u8) -> Result<()> {
-        self.transport.write_all(&[cmd])?;
-        self.transport.flush()?;
-        std::thread::sleep(COMMAND_DELAY);
-        Ok(())
+        let columns = batch.to_columns();
+        assert_eq!(columns.analog, vec![vec![10, 11], vec![20, 21]]);
+        assert_eq!(columns.digital, vec![0b1001, 0b0010]);
+        assert_eq!(columns.sequence, vec![1, 2]);
     }
 
-    /// Flush any pending input data.
-    fn flush_input(&mut self) -> Result<()> {
-        let mut buf = [0u8; 256];
-        let start = Instant::now();
-        let max_flush = Duration::from_millis(200);
-        let mut iterations = 0usize;
-        loop {
-            iterations += 1;
-            match self.transport.read(&mut buf) {
-                Ok(0) => break,
-                Ok(_) => {}
-                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
-                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => break,
-                Err(e) => return Err(e.into()),
+    #[test]
+    fn io_timeout_is_the_baseline_restored_after_timed_reads() {
+        /// Silent transport recording every read timeout the driver applies.
+        #[derive(Clone, Default)]
+        struct Timeouts(std::sync::Arc<std::sync::Mutex<Vec<Duration>>>);
+
+        impl Read for Timeouts {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::from(ErrorKind::WouldBlock))
             }
+        }
 
-            if iterations > 64 || start.elapsed() > max_flush {
-                break;
+        impl Write for Timeouts {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
             }
         }
-        Ok(())
-    }
 
-    /// Calculate the frame size in bytes based on active channels.
-    ///
-    /// BITalino frame structure:
-    /// - 4 digital inputs (4 bits)
-    /// - Sequence number (4 bits)
-    /// - Analog channels: first 4 are 10-bit, remaining are 6-bit
-    fn calculate_frame_size(&self) -> usize {
-        let n = self.active_channels.len();
-        if n == 0 {
-            return 0;
+        impl Transport for Timeouts {
+            fn set_read_timeout(&mut self, timeout: Duration) -> std::io::Result<()> {
+                self.0.lock().unwrap().push(timeout);
+                Ok(())
+            }
         }
 
-        // Formula from BITalino documentation
-        let bits = if n <= 4 {
-            12 + 10 * n // 4 digital + 4 seq + n*10-bit analog
-        } else {
-            52 + 6 * (n - 4) // First 4 channels are 10-bit, rest are 6-bit
+        let timeouts = Timeouts::default();
+        let mut dev = build_idle_device(timeouts.clone());
+        assert!(dev.set_io_timeout(Duration::ZERO).is_err());
+        dev.set_io_timeout(Duration::from_millis(300)).unwrap();
+        assert_eq!(dev.io_timeout(), Duration::from_millis(300));
+
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        let options = ReadOptions {
+            timeout: Some(Duration::from_millis(20)),
+            allow_partial: true,
+            ..ReadOptions::default()
         };
+        dev.read_frames_timed_with(1, &options).unwrap();
+        assert_eq!(
+            *timeouts.0.lock().unwrap(),
+            [300, 20, 300].map(Duration::from_millis)
+        );
+    }
 
-        bits.div_ceil(8) // Round up to bytes
+    #[test]
+    fn batches_carry_rate_limited_link_quality() {
+        let mut bytes = with_crc(vec![0x00, 0x00, 0x00]);
+        bytes.extend(with_crc(vec![0x00, 0x01, 0x10]));
+
+        let mut dev = build_idle_device(Radio {
+            data: Cursor::new(bytes.clone()),
+            queries: 0,
+        });
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        let expected = Some(LinkQuality {
+            rssi: Some(-1),
+            link_quality: Some(255),
+        });
+        assert_eq!(dev.read_frames_timed(1).unwrap().link_quality, expected);
+        assert_eq!(dev.read_frames_timed(1).unwrap().link_quality, expected);
+        assert_eq!(dev.link_quality().unwrap().rssi, Some(-2));
+
+        let mut dev = build_idle_device(Cursor::new(bytes));
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        assert_eq!(dev.read_frames_timed(1).unwrap().link_quality, None);
+        assert!(dev.link_sampler.is_none());
+        assert!(dev.link_quality().is_err());
     }
 
-    /// Verify the CRC of a frame.
-    ///
-    /// BITalino uses a 4-bit CRC stored in the lower nibble of the last byte.
-    fn verify_crc(&self, data: &[u8]) -> bool {
-        let len = data.len();
-        if len == 0 {
-            return false;
-        }
+    #[test]
+    fn raw_capture_replays_to_the_same_frames() {
+        let mut bytes = with_crc(vec![0x00, 0x00, 0x00]);
+        bytes.extend(with_crc(vec![0x00, 0x01, 0x10]));
+        let path = std::env::temp_dir().join(format!("bitalino_raw_{}.bin", std::process::id()));
 
-        let received_crc = data[len - 1] & 0x0F;
+        let mut dev = build_idle_device(Cursor::new(bytes.clone()));
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        dev.enable_raw_capture(&path).unwrap();
+        let original = dev.read_frames_timed(2).unwrap();
+        dev.disable_raw_capture().unwrap();
+
+        let captured = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(captured, bytes);
+
+        let replay = crate::replay::ReplayTransport::from_bytes(captured);
+        let mut dev = build_idle_device(replay);
+        dev.start(1000, vec![0]).unwrap();
+        assert_eq!(dev.read_frames_timed(2).unwrap().frames, original.frames);
+    }
 
-        let mut crc = 0u8;
-        for (i, &byte) in data.iter().enumerate() {
-            let byte = if i == len - 1 { byte & 0xF0 } else { byte };
+    #[test]
+    fn annotate_records_markers_only_while_acquiring() {
+        let mut dev = build_idle_device(Cursor::new(Vec::new()));
+        assert!(dev.annotate("too early").is_err());
 
-            for bit in (0..8).rev() {
-                crc <<= 1;
-                if (crc & 0x10) != 0 {
-                    crc ^= 0x03;
-                }
-                crc ^= (byte >> bit) & 0x01;
-            }
-        }
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        dev.start_time = Some(Instant::now() - Duration::from_secs(2));
+        let marker = dev.annotate("stimulus").unwrap();
+        assert_eq!(marker.label, "stimulus");
+        assert!(marker.sample_index >= 2000, "got {}", marker.sample_index);
 
-        received_crc == (crc & 0x0F)
+        assert_eq!(dev.take_annotations(), vec![marker]);
+        assert!(dev.annotations().is_empty());
     }
 
-    /// Decode a raw frame buffer into a Frame struct.
-    fn decode_frame(&self, data: &[u8]) -> Frame {
-        let last = data.len() - 1;
-        let n_channels = self.active_channels.len();
+    #[test]
+    fn sessions_collect_what_happened_during_acquisition() {
+        let mut dev = build_idle_device(Cursor::new(Vec::new()));
+        assert!(dev.begin_session().is_err());
+        assert!(dev.end_session().is_err());
 
-        // Sequence number (upper 4 bits of last byte)
-        let seq = data[last] >> 4;
+        dev.frame_size = 3;
+        dev.active_channels = vec![0, 2];
+        dev.start_time = Some(Instant::now() - Duration::from_secs(1));
+        dev.start_utc = Some(SystemTime::now() - Duration::from_secs(1));
+        let session = dev.begin_session().unwrap();
+        assert_eq!(session.info.channels, [0, 2]);
+        assert_eq!(session.info.sensors, ["RAW", "RAW"]);
+        assert_eq!(session.info.mac, DEFAULT_DEVICE_LABEL);
+        assert!(session.is_open());
+
+        dev.annotate("stimulus").unwrap();
+        let session = dev.end_session().unwrap();
+        assert_eq!(session.annotations.len(), 1);
+        assert!(session.stats.is_some());
+        assert!(session.ended.unwrap() >= session.info.started);
+        assert!(dev.session().is_none());
+    }
 
-        // Digital inputs (bits 4-7 of second-to-last byte)
-        let digital = [
-            (data[last - 1] >> 7) & 0x01,
-            (data[last - 1] >> 6) & 0x01,
-            (data[last - 1] >> 5) & 0x01,
-            (data[last - 1] >> 4) & 0x01,
-        ];
+    #[test]
+    fn unsupported_features_name_the_firmware() {
+        let mut dev = build_idle_device(Recorder::default());
+        let err = dev.pwm(10).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pwm() is not supported on unidentified firmware (call version() first) \
+             (needs firmware 4.2+)"
+        );
 
-        // Analog channels (10-bit values, packed)
-        let mut analog = Vec::with_capacity(n_channels);
+        dev.set_firmware("BITalino_v3.1");
+        let err = dev.state().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<DriverError>(),
+            Some(DriverError::Unsupported(_))
+        ));
+        assert_eq!(
+            err.to_string(),
+            "state() is not supported on firmware BITalino_v3.1 (needs firmware 4.2+)"
+        );
 
-        // Decoding follows BITalino frame format specification
-        if n_channels > 0 {
-            let val = ((data[last - 1] as u16 & 0x0F) << 6) | (data[last - 2] as u16 >> 2);
-            analog.push(val);
-        }
-        if n_channels > 1 {
-            let val = ((data[last - 2] as u16 & 0x03) << 8) | (data[last - 3] as u16);
-            analog.push(val);
-        }
-        if n_channels > 2 {
-            let val = ((data[last - 4] as u16) << 2) | (data[last - 5] as u16 >> 6);
-            analog.push(val);
-        }
-        if n_channels > 3 {
-            let val = ((data[last - 5] as u16 & 0x3F) << 4) | (data[last - 6] as u16 >> 4);
-            analog.push(val);
-        }
-        if n_channels > 4 {
-            let val = ((data[last - 6] as u16 & 0x0F) << 2) | (data[last - 7] as u16 >> 6);
-            analog.push(val);
-        }
-        if n_channels > 5 {
-            let val = data[last - 7] as u16 & 0x3F;
-            analog.push(val);
-        }
+        dev.set_firmware("BITalino_v5.2");
+        assert_eq!(dev.capabilities().state_reply_len, 17);
+        let err = dev.trigger(&[0, 0, 1]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "trigger() cannot raise O3: firmware BITalino_v5.2 has 2 digital outputs"
+        );
+        dev.trigger(&[1, 0, 0, 0]).unwrap();
+    }
+
+    #[test]
+    fn output_changes_are_logged_on_the_sample_axis() {
+        let mut dev = build_idle_device(Recorder::default());
+        dev.set_firmware("BITalino_v4.2");
+        dev.trigger(&[1]).unwrap();
+        assert!(
+            dev.output_events().is_empty(),
+            "idle commands are not logged"
+        );
 
-        Frame::new(seq, digital, analog)
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        dev.start_time = Some(Instant::now() - Duration::from_secs(2));
+        dev.pwm(128).unwrap();
+        dev.trigger_pulse(&[0, 1], Duration::ZERO).unwrap();
+        dev.finish_pulse();
+
+        let events = dev.take_output_events();
+        let commands: Vec<_> = events.iter().map(|e| e.command).collect();
+        assert_eq!(
+            commands,
+            [
+                OutputCommand::Pwm(128),
+                OutputCommand::Digital([1, 1, 0, 0]),
+                OutputCommand::Digital([1, 0, 0, 0]),
+            ]
+        );
+        assert!(events[0].sample_index >= 2000, "got {:?}", events[0]);
+        assert!(events
+            .windows(2)
+            .all(|w| w[0].timestamp_us <= w[1].timestamp_us));
+        assert!(dev.output_events().is_empty());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Cursor;
+    #[test]
+    fn read_available_returns_only_whole_buffered_frames() {
+        let mut bytes = with_crc(vec![0x00, 0x00, 0x00]);
+        bytes.extend(with_crc(vec![0x00, 0x00, 0x10]));
+        bytes.extend([0x00, 0x00]); // half of a third frame
+        let mut dev = build_idle_device(Cursor::new(bytes));
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
 
-    impl Transport for Cursor<Vec<u8>> {}
+        let batch = dev.read_available(10).unwrap();
+        assert_eq!(batch.frames.len(), 2);
+        assert_eq!(batch.frames[1].seq, 1);
 
-    /// Transport that always reports `WouldBlock`, exercising the deadline path
-    /// without ever closing or returning data.
-    struct AlwaysBlock;
+        let batch = dev.read_available(10).unwrap();
+        assert!(batch.frames.is_empty());
+    }
 
-    impl Read for AlwaysBlock {
-        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
-            std::thread::sleep(Duration::from_millis(5));
-            Err(std::io::Error::from(ErrorKind::WouldBlock))
+    /// Transport answering each state command with the next scripted reply.
+    #[derive(Default)]
+    struct StateReplies {
+        replies: std::collections::VecDeque<Vec<u8>>,
+        pending: Cursor<Vec<u8>>,
+        requests: usize,
+    }
+
+    impl Read for StateReplies {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.pending.read(buf)
         }
     }
 
-    impl Write for AlwaysBlock {
+    impl Write for StateReplies {
         fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.contains(&CMD_STATE) {
+                self.requests += 1;
+                let reply = self.replies.pop_front().unwrap_or_default();
+                self.pending = Cursor::new(reply);
+            }
             Ok(buf.len())
         }
 
@@ -1118,45 +4231,300 @@ mod tests {
         }
     }
 
-    impl Transport for AlwaysBlock {}
+    impl Transport for StateReplies {}
 
-    fn build_idle_device<T: Transport + 'static>(transport: T) -> Bitalino {
-        Bitalino {
-            transport: Box::new(transport),
-            active_channels: Vec::new(),
-            frame_size: 0,
-            sampling_rate: SamplingRate::Hz1000,
-            start_time: None,
-            last_seq: None,
-            is_bitalino2: false,
-            is_bitalino52: false,
-        }
+    #[test]
+    fn crc_policy_applies_to_state_and_frames() {
+        let good = with_crc(vec![0x10; 16]);
+        let mut bad = good.clone();
+        bad[15] ^= 0x01;
+        let device = |policy, replies: &[&Vec<u8>]| {
+            let mut dev = build_idle_device(StateReplies {
+                replies: replies.iter().map(|r| r.to_vec()).collect(),
+                ..StateReplies::default()
+            });
+            dev.set_firmware("BITalino_v4.2");
+            dev.set_crc_policy(policy);
+            dev
+        };
+
+        let mut dev = device(CrcPolicy::Lenient, &[&bad]);
+        assert_eq!(dev.state().unwrap().analog[0], 0x1010);
+        let mut dev = device(CrcPolicy::Strict, &[&bad, &good]);
+        let err = dev.state().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<DriverError>(),
+            Some(DriverError::Crc(_))
+        ));
+        let mut dev = device(CrcPolicy::Retry(2), &[&bad, &bad, &good]);
+        assert!(dev.state().is_ok());
+        let mut dev = device(CrcPolicy::Retry(1), &[&bad, &bad, &good]);
+        assert!(dev.state().is_err());
+
+        let frames = || {
+            let mut bytes = with_crc(vec![0x00, 0x00, 0x00]);
+            bytes.extend([0x00, 0x00, 0x1F]); // bad CRC
+            bytes.extend(with_crc(vec![0x00, 0x00, 0x20]));
+            let mut dev = build_idle_device(Cursor::new(bytes));
+            dev.frame_size = 3;
+            dev.active_channels = vec![0];
+            dev
+        };
+        let mut dev = frames();
+        dev.set_crc_policy(CrcPolicy::Strict);
+        assert!(dev.read_frames_timed(2).is_err());
+        let mut dev = frames();
+        dev.set_crc_policy(CrcPolicy::Retry(1));
+        assert_eq!(dev.read_frames_timed(3).unwrap().crc_errors, 1);
     }
 
     #[test]
-    fn wait_until_streaming_errors_when_not_started() {
-        let mut dev = build_idle_device(Cursor::new(Vec::new()));
-        let err = dev
-            .wait_until_streaming(Duration::from_millis(50))
-            .unwrap_err()
-            .to_string();
-        assert!(
-            err.contains("not started"),
-            "expected 'not started' in error: {err}"
-        );
+    fn raw_commands_bypass_the_protocol() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        dev.send_raw(&[0x07, 0xA3, 0x80]).unwrap();
+        assert_eq!(*recorder.written.lock().unwrap(), [0x07, 0xA3, 0x80]);
+        assert!(!dev.is_acquiring());
+
+        let mut dev = build_idle_device(Cursor::new(vec![1, 2, 3, 4, 5]));
+        dev.read_buffer
+            .fill_from(&mut Cursor::new(vec![9]), 1)
+            .unwrap();
+        let bytes = dev.read_raw(3, Duration::from_millis(50)).unwrap();
+        assert_eq!(bytes, [9, 1, 2]);
+        let bytes = dev.read_raw(10, Duration::from_millis(50)).unwrap();
+        assert_eq!(bytes, [3, 4, 5]);
     }
 
     #[test]
-    fn wait_until_streaming_times_out_on_silent_peer() {
-        let mut dev = build_idle_device(AlwaysBlock);
-        // Pretend a 1-channel acquisition has started so the precondition passes.
+    fn read_for_makes_up_for_trailing_crc_errors() {
+        let mut bytes = with_crc(vec![0x00, 0x00, 0x00]);
+        bytes.extend(with_crc(vec![0x00, 0x00, 0x10]));
+        bytes.extend([0x00, 0x00, 0x2F]); // bad CRC
+        bytes.extend(with_crc(vec![0x00, 0x00, 0x30]));
+        let mut dev = build_idle_device(Cursor::new(bytes));
         dev.frame_size = 3;
         dev.active_channels = vec![0];
+        dev.start_time = Some(Instant::now());
 
+        assert_eq!(SamplingRate::Hz100.frames_in(Duration::from_millis(25)), 3);
+        let batch = dev.read_for(Duration::from_millis(3)).unwrap();
+        let seqs: Vec<u8> = batch.frames.iter().map(|f| f.seq).collect();
+        assert_eq!(seqs, [0, 1, 3]);
+        assert_eq!(batch.crc_errors, 1);
+    }
+
+    #[test]
+    fn stats_count_frames_of_batches_that_fail() {
+        let mut bytes = with_crc(vec![0x00, 0x00, 0x00]);
+        bytes.extend([0x00, 0x00, 0x2F]); // bad CRC
+        bytes.extend(with_crc(vec![0x00, 0x00, 0x30])); // seq 3: gap of 2
+        let mut dev = build_idle_device(Cursor::new(bytes));
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+        dev.start_time = Some(Instant::now());
+
+        // The fourth frame never arrives, so the batch itself is lost.
+        assert!(dev.read_frames_timed(4).is_err());
+        let stats = dev.stats();
+        assert_eq!(stats.frames, 2);
+        assert_eq!(stats.bytes_read, 9);
+        assert_eq!(stats.crc_errors, 1);
+        assert_eq!(stats.sequence_gaps, 2);
+        assert_eq!(stats.dropped_samples, 2);
+
+        dev.stop().unwrap();
+        assert_eq!(dev.stats().frames, 2);
+    }
+
+    #[test]
+    fn gap_fill_inserts_one_placeholder_per_lost_frame() {
+        let mut bytes = with_crc(vec![0xFC, 0xBF, 0x00]); // seq 0, A1 = 1023
+        bytes.extend(with_crc(vec![0x00, 0x00, 0x30])); // seq 3: gap of 2
+        let mut dev = build_idle_device(Cursor::new(bytes.repeat(2)));
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+
+        let options = ReadOptions {
+            gap_fill: GapFill::HoldLast,
+            ..ReadOptions::default()
+        };
+        let batch = dev.read_frames_timed_with(2, &options).unwrap();
+        let seqs: Vec<u8> = batch.frames.iter().map(|f| f.seq).collect();
+        assert_eq!(seqs, [0, 1, 2, 3]);
+        let filled: Vec<bool> = batch.frames.iter().map(|f| f.filled).collect();
+        assert_eq!(filled, [false, true, true, false]);
+        assert_eq!(batch.frames[2].analog_values(), [1023]);
+        assert_eq!(batch.sequence_gaps, 2);
+        let indices: Vec<u64> = batch.frames.iter().map(|f| f.sample_index).collect();
+        assert_eq!(indices, [0, 1, 2, 3]);
+
+        // Seq 3 -> 0 loses 12 frames, then 0 -> 3 loses 2 more.
+        let options = ReadOptions {
+            gap_fill: GapFill::Nan,
+            ..ReadOptions::default()
+        };
+        let batch = dev.read_frames_timed_with(2, &options).unwrap();
+        assert_eq!(batch.frames.len(), 16);
+        assert_eq!(batch.sequence_gaps, 14);
+        assert_eq!(batch.frames[1].analog_values(), [0]);
+        let filled = batch.to_columns().filled;
+        assert_eq!(filled.iter().filter(|&&f| f).count(), 14);
+        assert!(!filled[12] && !filled[15]);
+        assert_eq!(batch.frames[12].seq, 0);
+        // The second batch carries on from the first.
+        assert_eq!(batch.first_sample_index, 4);
+        assert_eq!(batch.frames[15].sample_index, 19);
+    }
+
+    #[test]
+    fn frames_are_decoded_from_large_reads() {
+        struct CountingReads(Cursor<Vec<u8>>, Arc<Mutex<usize>>);
+
+        impl Read for CountingReads {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                *self.1.lock().unwrap() += 1;
+                self.0.read(buf)
+            }
+        }
+
+        impl Write for CountingReads {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Transport for CountingReads {}
+
+        let bytes: Vec<u8> = (0..200u8)
+            .flat_map(|i| with_crc(vec![0x00, 0x00, (i % 16) << 4]))
+            .collect();
+        let reads = Arc::new(Mutex::new(0));
+        let mut dev = build_idle_device(CountingReads(Cursor::new(bytes), Arc::clone(&reads)));
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+
+        let batch = dev.read_frames_timed(150).unwrap();
+        assert_eq!((batch.frames.len(), batch.sequence_gaps), (150, 0));
+        assert_eq!(*reads.lock().unwrap(), 1);
+        let batch = dev.read_frames_timed(50).unwrap();
+        assert_eq!(batch.frames[49].seq, 7);
+        assert_eq!(*reads.lock().unwrap(), 1);
+        assert_eq!(dev.stats().bytes_read, 600);
+    }
+
+    #[test]
+    fn read_with_timeout_returns_partial_batch_and_keeps_alignment() {
+        let mut bytes = with_crc(vec![0x00, 0x00, 0x00]);
+        bytes.extend([0xAA, 0xBB]); // first two bytes of the next frame
+        let mut dev = build_idle_device(Trickle(Cursor::new(bytes)));
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+
+        let options = ReadOptions {
+            timeout: Some(Duration::from_millis(50)),
+            ..ReadOptions::default()
+        };
+        let batch = dev.read_frames_timed_with(3, &options).unwrap();
+        assert!(batch.partial);
+        assert_eq!(batch.frames.len(), 1);
+        assert_eq!(dev.read_buffer.as_slice(), [0xAA, 0xBB]);
+
+        let strict = ReadOptions {
+            allow_partial: false,
+            ..options
+        };
         let err = dev
-            .wait_until_streaming(Duration::from_millis(50))
+            .read_frames_timed_with(1, &strict)
             .unwrap_err()
             .to_string();
         assert!(err.starts_with("Timeout"), "expected timeout error: {err}");
     }
+
+    #[test]
+    fn state_during_acquisition_rejects_legacy_devices_before_pausing() {
+        let recorder = Recorder::default();
+        let mut dev = build_idle_device(recorder.clone());
+        dev.frame_size = 3;
+        dev.active_channels = vec![0];
+
+        assert!(dev.state_during_acquisition().is_err());
+        assert!(dev.is_acquiring());
+        assert!(recorder.written.lock().unwrap().is_empty());
+    }
+
+    /// Transport streaming `frames` after every start command and answering
+    /// state commands with `state`, like a BITalino 2.0.
+    struct Device {
+        frames: Vec<u8>,
+        state: Vec<u8>,
+        pending: Cursor<Vec<u8>>,
+    }
+
+    impl Read for Device {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.pending.read(buf)
+        }
+    }
+
+    impl Write for Device {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            for &cmd in buf {
+                let reply = match cmd {
+                    CMD_STOP => Vec::new(),
+                    CMD_STATE => self.state.clone(),
+                    cmd if cmd & 0x03 == 0x01 => self.frames.clone(),
+                    _ => continue,
+                };
+                self.pending = Cursor::new(reply);
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for Device {}
+
+    #[test]
+    fn battery_monitor_reads_the_battery_mid_acquisition() {
+        // Battery ADC value 500: about 3.23 V.
+        let mut state = vec![0u8; 16];
+        state[12] = 0xF4;
+        state[13] = 0x01;
+        let frames = (0..10u8)
+            .flat_map(|seq| with_crc(vec![0x00, 0x00, seq << 4]))
+            .collect();
+        let mut dev = build_idle_device(Device {
+            frames,
+            state: with_crc(state),
+            pending: Cursor::new(Vec::new()),
+        });
+        dev.set_firmware("BITalino_v4.2");
+        dev.start(1000, vec![0]).unwrap();
+
+        let readings = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&readings);
+        dev.monitor_battery(Duration::from_secs(3600), 3.5, move |reading| {
+            sink.lock().unwrap().push(reading)
+        });
+        assert_eq!(dev.read_frames_timed(4).unwrap().frames.len(), 4);
+
+        let readings = readings.lock().unwrap().clone();
+        assert_eq!(readings.len(), 1);
+        assert!((readings[0].voltage - 3.226).abs() < 1e-3);
+        assert!(!readings[0].estimated);
+        // Acquisition resumed, keeping its statistics, and the next check waits
+        // for the interval.
+        assert!(dev.is_acquiring());
+        assert_eq!(dev.stats().frames, 4);
+        assert_eq!(dev.read_frames_timed(4).unwrap().frames[0].seq, 0);
+        assert_eq!(dev.poll_battery().unwrap(), None);
+    }
 }
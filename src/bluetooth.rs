@@ -1,26 +1,347 @@
-use std::fs::File;
-use std::io::{Read, Write};
-use std::mem;
-use std::os::fd::{AsRawFd, FromRawFd};
+//! RFCOMM connection management for BITalino devices.
+//!
+//! The retry/backoff logic in [`BluetoothConnector`] is platform-agnostic; the
+//! socket itself comes from a per-OS backend:
+//! - Linux (`bluez` feature, on by default): kernel `AF_BLUETOOTH` sockets via libc.
+//! - Windows: Winsock Bluetooth sockets (`AF_BTH`).
+//! - macOS: the `/dev/cu.BITalino-*` serial node macOS creates for paired devices.
+//!
+//! Every backend exposes the same [`RfcommStream`] API. On other platforms the
+//! connector returns [`BluetoothError::Unsupported`].
+//!
+//! [`BluetoothConnector::discover`] runs an inquiry scan so a device can be
+//! found without knowing its MAC up front (Linux and Windows only).
+//!
+//! With the `sync-bluetooth` feature, the Linux backend also pairs devices
+//! that are not bonded yet, through BlueZ's D-Bus API on a blocking libdbus
+//! connection (no tokio or other async runtime). The PIN is answered by a
+//! [`PairingAgent`], which can be replaced to use another PIN or to ask the
+//! user.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use log::{debug, info, warn};
 
 use crate::errors::{BluetoothError, DriverError, Result};
 
-const AF_BLUETOOTH: libc::c_ushort = 31;
-const BTPROTO_RFCOMM: libc::c_int = 3;
+#[cfg(all(target_os = "linux", feature = "bluez"))]
+mod linux;
+#[cfg(all(target_os = "linux", feature = "bluez"))]
+use linux as backend;
+#[cfg(all(target_os = "linux", feature = "sync-bluetooth"))]
+mod pairing;
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+use windows as backend;
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+use macos as backend;
+
+#[cfg(not(any(
+    all(target_os = "linux", feature = "bluez"),
+    windows,
+    target_os = "macos"
+)))]
+mod unsupported;
+#[cfg(not(any(
+    all(target_os = "linux", feature = "bluez"),
+    windows,
+    target_os = "macos"
+)))]
+use unsupported as backend;
+
+pub use backend::RfcommStream;
 
 const DEFAULT_IO_TIMEOUT_SECS: u64 = 5;
 const MAX_CONNECT_RETRIES: u32 = 3;
 const RETRY_DELAY_MS: u64 = 500;
+const DEFAULT_SCAN_TIMEOUT_SECS: u64 = 10;
+/// Longest a blocking step waits before checking its [`CancelToken`].
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+/// Name prefix BITalinos advertise (e.g. `BITalino-3D-4F`).
+const BITALINO_NAME_PREFIX: &str = "bitalino";
+
+/// A device answering an inquiry scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredDevice {
+    /// MAC address, `XX:XX:XX:XX:XX:XX`.
+    pub mac: String,
+    /// Friendly name, if the device answered the name request.
+    pub name: Option<String>,
+    /// Received signal strength in dBm, if the adapter reports it.
+    pub rssi: Option<i8>,
+}
+
+/// Radio conditions of an open connection, as reported by the local adapter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkQuality {
+    /// Received signal strength in dB relative to the controller's golden
+    /// receive power range (0 = inside it, negative = too weak).
+    pub rssi: Option<i8>,
+    /// Vendor-specific link quality, 0 (worst) to 255 (best).
+    pub link_quality: Option<u8>,
+}
+
+/// A question BlueZ asks while pairing a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum PairingRequest {
+    /// PIN code for legacy pairing; BITalinos ship with `1234`.
+    PinCode,
+    /// Numeric passkey to enter for the device.
+    Passkey,
+    /// Whether `passkey` matches the one the device shows.
+    Confirmation {
+        /// Six-digit passkey shown by BlueZ.
+        passkey: u32,
+    },
+    /// Whether to pair a device that asks for no code.
+    Authorization,
+}
+
+/// Answer to a [`PairingRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum PairingResponse {
+    /// PIN code, for [`PinCode`](PairingRequest::PinCode); a numeric PIN
+    /// also answers [`Passkey`](PairingRequest::Passkey).
+    Pin(String),
+    /// Passkey, for [`Passkey`](PairingRequest::Passkey).
+    Passkey(u32),
+    /// Yes to a confirmation or authorization.
+    Accept,
+    /// Refuse; pairing fails.
+    Reject,
+}
+
+/// Answers the requests BlueZ makes while pairing a device (Linux with the
+/// `sync-bluetooth` feature; other platforms pair outside the driver).
+///
+/// The default agent of [`BluetoothConnector::pair_and_connect`] answers
+/// PIN and passkey requests with its `pin` and rejects anything needing a
+/// user. Supply one with [`BluetoothConnectorBuilder::pairing_agent`] for
+/// devices given another PIN or to prompt the user:
+///
+/// ```no_run
+/// # use bitalino_rs::{BluetoothConnector, PairingRequest, PairingResponse};
+/// let connector = BluetoothConnector::builder()
+///     .pairing_agent(|mac: &str, request: PairingRequest| match request {
+///         // One device was given its own PIN; the others keep the default.
+///         PairingRequest::PinCode if mac == "20:16:10:00:3D:4F" => {
+///             PairingResponse::Pin("9876".into())
+///         }
+///         PairingRequest::PinCode => PairingResponse::Pin("1234".into()),
+///         _ => PairingResponse::Reject,
+///     })
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct PairingAgent {
+    answer: Arc<AnswerFn>,
+    /// Whether the agent can show a passkey to a user, so BlueZ may ask it
+    /// for confirmations.
+    interactive: bool,
+}
+
+#[allow(dead_code)]
+impl PairingAgent {
+    /// Agent calling `answer` with the device MAC for every request. It is
+    /// registered as able to display and enter codes.
+    pub fn new(
+        answer: impl Fn(&str, PairingRequest) -> PairingResponse + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            answer: Arc::new(answer),
+            interactive: true,
+        }
+    }
+
+    /// The default agent: `pin` for PIN and passkey requests, nothing else.
+    pub fn pin(pin: impl Into<String>) -> Self {
+        let pin = pin.into();
+        Self {
+            answer: Arc::new(move |_: &str, request| match request {
+                PairingRequest::PinCode | PairingRequest::Passkey => {
+                    PairingResponse::Pin(pin.clone())
+                }
+                _ => PairingResponse::Reject,
+            }),
+            interactive: false,
+        }
+    }
+
+    /// Answer `request` for the device `mac`.
+    pub fn answer(&self, mac: &str, request: PairingRequest) -> PairingResponse {
+        (self.answer)(mac, request)
+    }
+
+    /// Whether the agent handles confirmations as well as codes.
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+}
+
+/// Aborts a [`BluetoothConnector::pair_and_connect_cancellable`] from
+/// another thread.
+///
+/// Clones share the same flag. The connector checks it between steps and
+/// during retry delays, then fails with [`BluetoothError::Cancelled`]. On
+/// Linux the scan, BlueZ pairing and each connection attempt are interrupted
+/// too, so cancelling takes effect within a fraction of a second; other
+/// backends finish the step in progress first.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+#[allow(dead_code)]
+impl CancelToken {
+    /// A token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every operation watching this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Fail with [`BluetoothError::Cancelled`] once cancelled.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(DriverError::Bluetooth(BluetoothError::Cancelled))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sleep for `duration`, waking early (with an error) when cancelled.
+    pub(crate) fn sleep(&self, duration: Duration) -> Result<()> {
+        let deadline = Instant::now() + duration;
+        loop {
+            self.check()?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            thread::sleep(remaining.min(CANCEL_POLL));
+        }
+    }
+}
+
+/// A step of [`BluetoothConnector::pair_and_connect`], reported to the
+/// [`on_progress`](BluetoothConnectorBuilder::on_progress) callback so a
+/// front-end can show what the connection is waiting for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum ConnectProgress {
+    /// Scanning for the device (before connecting, or after a failed attempt).
+    Scanning,
+    /// The device answered the scan.
+    Found,
+    /// Pairing through BlueZ (`sync-bluetooth` feature, unpaired devices only).
+    Pairing,
+    /// Pairing succeeded and the device is trusted.
+    Paired,
+    /// Opening the RFCOMM connection, `attempt` (from 1) of `max_attempts`.
+    Connecting { attempt: u32, max_attempts: u32 },
+    /// The connection is established and answered the link check.
+    Verified,
+}
+
+impl fmt::Display for ConnectProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scanning => f.write_str("scanning for the device"),
+            Self::Found => f.write_str("device found"),
+            Self::Pairing => f.write_str("pairing"),
+            Self::Paired => f.write_str("paired"),
+            Self::Connecting {
+                attempt,
+                max_attempts,
+            } => write!(f, "connecting (attempt {attempt} of {max_attempts})"),
+            Self::Verified => f.write_str("connected"),
+        }
+    }
+}
+
+/// Callback receiving the [`ConnectProgress`] of a connection, with the
+/// device MAC.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<ProgressFn>);
+
+#[allow(dead_code)]
+impl ProgressCallback {
+    /// Wrap `callback`; it runs on the connecting thread and should return
+    /// quickly (e.g. by sending the event to a UI thread).
+    pub fn new(callback: impl Fn(&str, ConnectProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    /// Report `progress` for the device `mac`.
+    pub fn report(&self, mac: &str, progress: ConnectProgress) {
+        (self.0)(mac, progress)
+    }
+}
+
+type ProgressFn = dyn Fn(&str, ConnectProgress) + Send + Sync;
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressCallback").finish_non_exhaustive()
+    }
+}
+
+/// Callback behind a [`PairingAgent`], given the device MAC.
+type AnswerFn = dyn Fn(&str, PairingRequest) -> PairingResponse + Send + Sync;
+
+impl fmt::Debug for PairingAgent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PairingAgent")
+            .field("interactive", &self.interactive)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DiscoveredDevice {
+    /// Whether the advertised name looks like a BITalino.
+    pub fn is_bitalino(&self) -> bool {
+        self.name.as_deref().is_some_and(|name| {
+            name.get(..BITALINO_NAME_PREFIX.len())
+                .is_some_and(|p| p.eq_ignore_ascii_case(BITALINO_NAME_PREFIX))
+        })
+    }
+}
 
 /// High level connector that opens an RFCOMM socket without needing root.
 ///
 /// The connector expects the device to already be paired/trusted (e.g., via
-/// `bluetoothctl`); you provide the MAC address and the optional PIN argument is
-/// ignored. Only a minimal libc-based stack is used.
+/// `bluetoothctl` on Linux or the Bluetooth settings on Windows/macOS); you provide the
+/// MAC address and the optional PIN argument is ignored.
+///
+/// Build one with [`BluetoothConnector::builder`] to override the defaults:
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use bitalino_rs::BluetoothConnector;
+/// let connector = BluetoothConnector::builder()
+///     .channel(2)
+///     .retries(5)
+///     .io_timeout(Duration::from_secs(10))
+///     .build();
+/// ```
 #[derive(Debug, Clone)]
 pub struct BluetoothConnector {
     /// RFCOMM channel to connect to (BITalino default: 1).
@@ -31,6 +352,20 @@ pub struct BluetoothConnector {
     pub max_retries: u32,
     /// Delay between retries (exponential backoff uses this as the base).
     pub retry_delay: Duration,
+    /// Local adapter to use, by name (`"hci1"`) or address; `None` lets the
+    /// OS choose. Only honoured by the Linux backend.
+    pub adapter: Option<String>,
+    /// Connect straight away (the default) instead of first scanning for the
+    /// device. A known device is only scanned for if the direct connection
+    /// fails, to tell "out of range" apart from other failures.
+    pub known_device: bool,
+    /// How long a scan for the device may take.
+    pub scan_timeout: Duration,
+    /// Answers pairing requests instead of the `pin` given to
+    /// [`pair_and_connect`](Self::pair_and_connect).
+    pub pairing_agent: Option<PairingAgent>,
+    /// Told about each step of [`pair_and_connect`](Self::pair_and_connect).
+    pub on_progress: Option<ProgressCallback>,
 }
 
 impl Default for BluetoothConnector {
@@ -40,28 +375,274 @@ impl Default for BluetoothConnector {
             io_timeout: Duration::from_secs(DEFAULT_IO_TIMEOUT_SECS),
             max_retries: MAX_CONNECT_RETRIES,
             retry_delay: Duration::from_millis(RETRY_DELAY_MS),
+            adapter: None,
+            known_device: true,
+            scan_timeout: Duration::from_secs(DEFAULT_SCAN_TIMEOUT_SECS),
+            pairing_agent: None,
+            on_progress: None,
         }
     }
 }
 
+/// Step-by-step construction of a [`BluetoothConnector`]; unset options keep
+/// their defaults.
+#[derive(Debug, Clone, Default)]
+pub struct BluetoothConnectorBuilder {
+    connector: BluetoothConnector,
+}
+
+#[allow(dead_code)]
+impl BluetoothConnectorBuilder {
+    /// RFCOMM channel to connect to.
+    pub fn channel(mut self, channel: u8) -> Self {
+        self.connector.channel = channel;
+        self
+    }
+
+    /// Per-operation I/O timeout of the socket.
+    pub fn io_timeout(mut self, timeout: Duration) -> Self {
+        self.connector.io_timeout = timeout;
+        self
+    }
+
+    /// Maximum connection attempts (at least one is always made).
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.connector.max_retries = retries.max(1);
+        self
+    }
+
+    /// Base delay between attempts, doubled on each retry.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.connector.retry_delay = delay;
+        self
+    }
+
+    /// Local adapter by name (`"hci1"`) or address, for hosts with several
+    /// dongles (e.g. one per BITalino).
+    pub fn adapter(mut self, adapter: impl Into<String>) -> Self {
+        self.connector.adapter = Some(adapter.into());
+        self
+    }
+
+    /// Whether the device is known to be paired and reachable (skip the scan
+    /// before connecting).
+    pub fn known_device(mut self, known: bool) -> Self {
+        self.connector.known_device = known;
+        self
+    }
+
+    /// Upper bound on scans for the device before or after connecting.
+    pub fn scan_timeout(mut self, timeout: Duration) -> Self {
+        self.connector.scan_timeout = timeout;
+        self
+    }
+
+    /// Answer pairing requests with `answer` (called with the device MAC)
+    /// instead of the fixed PIN; see [`PairingAgent`].
+    pub fn pairing_agent(
+        mut self,
+        answer: impl Fn(&str, PairingRequest) -> PairingResponse + Send + Sync + 'static,
+    ) -> Self {
+        self.connector.pairing_agent = Some(PairingAgent::new(answer));
+        self
+    }
+
+    /// Call `callback` with the device MAC at each step of
+    /// [`pair_and_connect`](BluetoothConnector::pair_and_connect): scanning,
+    /// pairing, every connection attempt and the verified link.
+    ///
+    /// ```no_run
+    /// # use bitalino_rs::{BluetoothConnector, ConnectProgress};
+    /// let (tx, rx) = std::sync::mpsc::channel::<ConnectProgress>();
+    /// let connector = BluetoothConnector::builder()
+    ///     .on_progress(move |_mac: &str, progress| {
+    ///         let _ = tx.send(progress);
+    ///     })
+    ///     .build();
+    /// // A UI thread shows `rx.recv()` while this one connects.
+    /// let stream = connector.pair_and_connect("7E:91:2B:C4:AF:08", "1234")?;
+    /// # Ok::<(), bitalino_rs::DriverError>(())
+    /// ```
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(&str, ConnectProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.connector.on_progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// Finish building.
+    pub fn build(self) -> BluetoothConnector {
+        self.connector
+    }
+}
+
 impl BluetoothConnector {
-    /// Connect to an already-paired BITalino via RFCOMM using only libc sockets.
-    /// Caller must have paired and trusted the device ahead of time (e.g., via `bluetoothctl`).
-    pub fn pair_and_connect(&self, mac: &str, _pin: &str) -> Result<RfcommStream> {
+    /// Start building a connector from the defaults.
+    #[allow(dead_code)]
+    pub fn builder() -> BluetoothConnectorBuilder {
+        BluetoothConnectorBuilder::default()
+    }
+
+    /// Connect to an already-paired BITalino via RFCOMM using the platform socket API.
+    /// Caller must have paired and trusted the device ahead of time (e.g., via `bluetoothctl`),
+    /// except on Linux with the `sync-bluetooth` feature, where BlueZ pairs it with `pin`
+    /// (or the [`pairing_agent`](Self::pairing_agent), if set) first if needed.
+    ///
+    /// Unless [`known_device`](Self::known_device) is cleared, this connects
+    /// directly and only scans if every attempt fails: a device that does not
+    /// answer the scan yields [`BluetoothError::NotFound`], otherwise the
+    /// connection is retried once more.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(mac = mac, channel = self.channel, adapter = self.adapter.as_deref()),
+            err
+        )
+    )]
+    pub fn pair_and_connect(&self, mac: &str, pin: &str) -> Result<RfcommStream> {
+        self.pair_and_connect_cancellable(mac, pin, &CancelToken::new())
+    }
+
+    /// [`pair_and_connect`](Self::pair_and_connect) that gives up with
+    /// [`BluetoothError::Cancelled`] as soon as `cancel` is cancelled, instead
+    /// of running the scan, pairing and retries to their deadlines.
+    ///
+    /// ```no_run
+    /// # use bitalino_rs::{BluetoothConnector, CancelToken};
+    /// let cancel = CancelToken::new();
+    /// let abort = cancel.clone();
+    /// std::thread::spawn(move || {
+    ///     std::io::stdin().read_line(&mut String::new()).ok();
+    ///     abort.cancel();
+    /// });
+    /// let stream = BluetoothConnector::default().pair_and_connect_cancellable(
+    ///     "7E:91:2B:C4:AF:08",
+    ///     "1234",
+    ///     &cancel,
+    /// )?;
+    /// # Ok::<(), bitalino_rs::DriverError>(())
+    /// ```
+    #[allow(dead_code)]
+    pub fn pair_and_connect_cancellable(
+        &self,
+        mac: &str,
+        pin: &str,
+        cancel: &CancelToken,
+    ) -> Result<RfcommStream> {
         let bdaddr = parse_bdaddr(mac)?;
+        cancel.check()?;
+        #[cfg(all(target_os = "linux", feature = "sync-bluetooth"))]
+        {
+            let agent = self
+                .pairing_agent
+                .clone()
+                .unwrap_or_else(|| PairingAgent::pin(pin));
+            pairing::ensure_paired(
+                &format_bdaddr(bdaddr),
+                &agent,
+                self.adapter.as_deref(),
+                cancel,
+                |progress| self.report(mac, progress),
+            )?;
+        }
+        #[cfg(not(all(target_os = "linux", feature = "sync-bluetooth")))]
+        let _ = pin;
 
+        if !self.known_device {
+            self.wait_for_device(bdaddr, mac, cancel)?;
+            return self.connect_with_retries(bdaddr, mac, cancel);
+        }
+        match self.connect_with_retries(bdaddr, mac, cancel) {
+            Err(cancelled @ DriverError::Bluetooth(BluetoothError::Cancelled)) => Err(cancelled),
+            Err(e) => {
+                warn!("direct connection failed, scanning for {}: {}", mac, e);
+                match self.wait_for_device(bdaddr, mac, cancel) {
+                    Ok(()) => self.connect_with_retries(bdaddr, mac, cancel),
+                    Err(
+                        stop @ DriverError::Bluetooth(
+                            BluetoothError::NotFound { .. } | BluetoothError::Cancelled,
+                        ),
+                    ) => Err(stop),
+                    Err(scan_error) => {
+                        debug!("fallback scan unavailable: {}", scan_error);
+                        Err(e)
+                    }
+                }
+            }
+            connected => connected,
+        }
+    }
+
+    fn report(&self, mac: &str, progress: ConnectProgress) {
+        debug!("{}: {}", mac, progress);
+        if let Some(callback) = &self.on_progress {
+            callback.report(mac, progress);
+        }
+    }
+
+    /// Scan until `bdaddr` answers or `scan_timeout` elapses.
+    ///
+    /// Platforms without discovery skip the check.
+    fn wait_for_device(&self, bdaddr: [u8; 6], mac: &str, cancel: &CancelToken) -> Result<()> {
+        info!("scanning for {} (up to {:?})", mac, self.scan_timeout);
+        self.report(mac, ConnectProgress::Scanning);
+        let found = match backend::discover(
+            self.scan_timeout,
+            self.adapter.as_deref(),
+            Some(bdaddr),
+            cancel,
+        ) {
+            Err(DriverError::Bluetooth(BluetoothError::Unsupported(reason))) => {
+                warn!("cannot scan before connecting: {}", reason);
+                return Ok(());
+            }
+            result => result?,
+        };
+        let wanted = format_bdaddr(bdaddr);
+        if found.iter().any(|d| d.mac == wanted) {
+            self.report(mac, ConnectProgress::Found);
+            Ok(())
+        } else {
+            Err(DriverError::Bluetooth(BluetoothError::NotFound {
+                mac: mac.to_string(),
+            }))
+        }
+    }
+
+    fn connect_with_retries(
+        &self,
+        bdaddr: [u8; 6],
+        mac: &str,
+        cancel: &CancelToken,
+    ) -> Result<RfcommStream> {
         let mut last_error = None;
         for attempt in 0..self.max_retries {
+            cancel.check()?;
             if attempt > 0 {
                 let delay = self.retry_delay * (1 << (attempt - 1).min(3));
                 warn!(
                     "retrying RFCOMM connection after {:?} (mac={}, attempt={})",
                     delay, mac, attempt
                 );
-                thread::sleep(delay);
+                cancel.sleep(delay)?;
             }
-
-            match open_rfcomm_raw(bdaddr, self.channel, self.io_timeout) {
+            self.report(
+                mac,
+                ConnectProgress::Connecting {
+                    attempt: attempt + 1,
+                    max_attempts: self.max_retries,
+                },
+            );
+
+            match backend::open_rfcomm(
+                bdaddr,
+                self.channel,
+                self.io_timeout,
+                self.adapter.as_deref(),
+                cancel,
+            ) {
                 Ok(stream) => {
                     if let Err(e) = stream.verify_connected() {
                         warn!("connection verification failed: mac={}, error={}", mac, e);
@@ -69,8 +650,10 @@ impl BluetoothConnector {
                         continue;
                     }
                     info!("RFCOMM connection established: mac={}", mac);
+                    self.report(mac, ConnectProgress::Verified);
                     return Ok(stream);
                 }
+                Err(e @ DriverError::Bluetooth(BluetoothError::Cancelled)) => return Err(e),
                 Err(e) => {
                     warn!(
                         "RFCOMM connection attempt failed: mac={}, attempt={}, error={}",
@@ -87,101 +670,33 @@ impl BluetoothConnector {
     }
 }
 
-/// Simple RFCOMM stream that behaves like a Read/Write object.
-pub struct RfcommStream {
-    file: File,
-}
-
-impl RfcommStream {
-    /// Set the socket receive timeout (`SO_RCVTIMEO`).
+impl BluetoothConnector {
+    /// Scan for nearby Bluetooth Classic devices for roughly `duration`.
     ///
-    /// Used by the driver to enforce short user-supplied timeouts (e.g. in
-    /// `wait_until_streaming`) without waiting for the connector's default 5 s
-    /// kernel timeout to fire.
-    pub fn set_read_timeout(&self, timeout: Duration) -> std::io::Result<()> {
-        let tv = libc::timeval {
-            tv_sec: timeout.as_secs() as libc::time_t,
-            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
-        };
-        let ret = unsafe {
-            libc::setsockopt(
-                self.file.as_raw_fd(),
-                libc::SOL_SOCKET,
-                libc::SO_RCVTIMEO,
-                &tv as *const _ as *const libc::c_void,
-                mem::size_of::<libc::timeval>() as libc::socklen_t,
-            )
-        };
-        if ret < 0 {
-            Err(std::io::Error::last_os_error())
-        } else {
-            Ok(())
-        }
-    }
-
-    /// Verify the connection is actually established and usable.
-    pub fn verify_connected(&self) -> Result<()> {
-        // Check socket error status
-        let mut err: libc::c_int = 0;
-        let mut len: libc::socklen_t = mem::size_of::<libc::c_int>() as libc::socklen_t;
-
-        let ret = unsafe {
-            libc::getsockopt(
-                self.file.as_raw_fd(),
-                libc::SOL_SOCKET,
-                libc::SO_ERROR,
-                &mut err as *mut _ as *mut libc::c_void,
-                &mut len,
-            )
-        };
-
-        if ret < 0 {
-            return Err(DriverError::Io(std::io::Error::last_os_error()));
-        }
-
-        if err != 0 {
-            return Err(DriverError::Bluetooth(BluetoothError::NotConnected(
-                std::io::Error::from_raw_os_error(err).to_string(),
-            )));
-        }
-
-        Ok(())
+    /// Results are sorted with likely BITalinos first, then by signal strength.
+    /// Only discoverable devices answer; a paired BITalino that is already
+    /// connected elsewhere will not show up.
+    #[allow(dead_code)]
+    pub fn discover(&self, duration: Duration) -> Result<Vec<DiscoveredDevice>> {
+        let mut devices =
+            backend::discover(duration, self.adapter.as_deref(), None, &CancelToken::new())?;
+        devices.sort_by(|a, b| {
+            b.is_bitalino()
+                .cmp(&a.is_bitalino())
+                .then(b.rssi.cmp(&a.rssi))
+                .then(a.mac.cmp(&b.mac))
+        });
+        info!("inquiry finished: {} device(s) found", devices.len());
+        Ok(devices)
     }
 }
 
-impl Read for RfcommStream {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.file.read(buf)
-    }
-}
-
-impl Write for RfcommStream {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.file.write(buf)
-    }
-
-    fn flush(&mut self) -> std::io::Result<()> {
-        self.file.flush()
-    }
-}
-
-// Allow Send for RfcommStream (File is Send)
-unsafe impl Send for RfcommStream {}
-
-#[repr(C)]
-#[derive(Copy, Clone)]
-struct BdAddr {
-    b: [u8; 6],
-}
-
-#[repr(C)]
-struct SockAddrRc {
-    rc_family: libc::sa_family_t,
-    rc_bdaddr: BdAddr,
-    rc_channel: u8,
+/// Format a MAC-ordered address as `XX:XX:XX:XX:XX:XX`.
+fn format_bdaddr(bytes: [u8; 6]) -> String {
+    bytes.map(|b| format!("{b:02X}")).join(":")
 }
 
-fn parse_bdaddr(mac: &str) -> Result<BdAddr> {
+fn parse_bdaddr(mac: &str) -> Result<[u8; 6]> {
     let parts: Vec<&str> = mac.split(':').collect();
     if parts.len() != 6 {
         return Err(DriverError::Bluetooth(BluetoothError::Connection(
@@ -196,93 +711,151 @@ fn parse_bdaddr(mac: &str) -> Result<BdAddr> {
         })?;
         bytes[i] = byte;
     }
-
-    // bdaddr_t stores bytes in reverse order compared to the usual MAC string
-    let mut addr = BdAddr { b: [0; 6] };
-    for i in 0..6 {
-        addr.b[i] = bytes[5 - i];
-    }
-    Ok(addr)
+    Ok(bytes)
 }
 
-fn open_rfcomm_raw(address: BdAddr, channel: u8, timeout: Duration) -> Result<RfcommStream> {
-    debug!(
-        "opening RFCOMM socket: channel={}, addr_bytes={:02X?}",
-        channel, address.b
-    );
-
-    let fd = unsafe {
-        libc::socket(
-            AF_BLUETOOTH as libc::c_int,
-            libc::SOCK_STREAM,
-            BTPROTO_RFCOMM,
-        )
-    };
-    if fd < 0 {
-        return Err(DriverError::Bluetooth(BluetoothError::Connection(
-            std::io::Error::last_os_error().to_string(),
-        )));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_overrides_only_what_is_set() {
+        let connector = BluetoothConnector::builder()
+            .channel(2)
+            .retries(0)
+            .adapter("hci1")
+            .known_device(false)
+            .build();
+        let defaults = BluetoothConnector::default();
+        assert_eq!(connector.channel, 2);
+        assert_eq!(connector.max_retries, 1);
+        assert_eq!(connector.adapter.as_deref(), Some("hci1"));
+        assert!(!connector.known_device);
+        assert_eq!(connector.io_timeout, defaults.io_timeout);
+        assert_eq!(connector.retry_delay, defaults.retry_delay);
+        assert!(connector.pairing_agent.is_none());
     }
 
-    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
-        let err = std::io::Error::last_os_error();
-        unsafe {
-            libc::close(fd);
-        }
-        return Err(DriverError::Bluetooth(BluetoothError::Connection(
-            err.to_string(),
-        )));
+    #[test]
+    fn pairing_agents_answer_requests() {
+        let default = PairingAgent::pin("4321");
+        assert!(!default.is_interactive());
+        assert_eq!(
+            default.answer("20:16:10:00:3D:4F", PairingRequest::Passkey),
+            PairingResponse::Pin("4321".into())
+        );
+        assert_eq!(
+            default.answer(
+                "20:16:10:00:3D:4F",
+                PairingRequest::Confirmation { passkey: 123_456 }
+            ),
+            PairingResponse::Reject
+        );
+
+        let connector = BluetoothConnector::builder()
+            .pairing_agent(|mac: &str, request| match request {
+                PairingRequest::Confirmation { passkey } if mac.ends_with("4F") => {
+                    assert_eq!(passkey, 123_456);
+                    PairingResponse::Accept
+                }
+                _ => PairingResponse::Reject,
+            })
+            .build();
+        let agent = connector.pairing_agent.unwrap();
+        assert!(agent.is_interactive());
+        assert_eq!(
+            agent.answer(
+                "20:16:10:00:3D:4F",
+                PairingRequest::Confirmation { passkey: 123_456 }
+            ),
+            PairingResponse::Accept
+        );
+        assert_eq!(
+            agent.answer("20:16:10:00:3D:4F", PairingRequest::PinCode),
+            PairingResponse::Reject
+        );
     }
 
-    let mut addr = SockAddrRc {
-        rc_family: AF_BLUETOOTH as libc::sa_family_t,
-        rc_bdaddr: address,
-        rc_channel: channel,
-    };
-
-    let ret = unsafe {
-        libc::connect(
-            fd,
-            &mut addr as *mut _ as *const libc::sockaddr,
-            mem::size_of::<SockAddrRc>() as libc::socklen_t,
-        )
-    };
-    if ret < 0 {
-        let err = std::io::Error::last_os_error();
-        unsafe {
-            libc::close(fd);
-        }
-        return Err(DriverError::Bluetooth(BluetoothError::Connection(
-            err.to_string(),
-        )));
+    #[test]
+    fn cancelling_wakes_retry_delays_and_skips_attempts() {
+        let cancel = CancelToken::new();
+        let canceller = cancel.clone();
+        let started = Instant::now();
+        let waker = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            canceller.cancel();
+        });
+        let slept = cancel.sleep(Duration::from_secs(10));
+        waker.join().unwrap();
+        assert!(matches!(
+            slept,
+            Err(DriverError::Bluetooth(BluetoothError::Cancelled))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Already cancelled: fails without scanning or opening a socket.
+        let connector = BluetoothConnector::builder().known_device(false).build();
+        let Err(err) = connector.pair_and_connect_cancellable("20:16:10:00:3D:4F", "1234", &cancel)
+        else {
+            panic!("connected despite the cancellation");
+        };
+        assert_eq!(err.code(), "bluetooth.cancelled");
     }
 
-    // Set IO timeouts to avoid hanging reads/writes.
-    let tv = libc::timeval {
-        tv_sec: timeout.as_secs() as libc::time_t,
-        tv_usec: timeout.subsec_micros() as libc::suseconds_t,
-    };
-    for opt in [libc::SO_RCVTIMEO, libc::SO_SNDTIMEO] {
-        let ret = unsafe {
-            libc::setsockopt(
-                fd,
-                libc::SOL_SOCKET,
-                opt,
-                &tv as *const _ as *const libc::c_void,
-                mem::size_of::<libc::timeval>() as libc::socklen_t,
-            )
+    #[test]
+    fn progress_is_reported_for_each_attempt() {
+        let cancel = CancelToken::new();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let connector = {
+            let (cancel, seen) = (cancel.clone(), seen.clone());
+            BluetoothConnector::builder()
+                .retry_delay(Duration::ZERO)
+                .on_progress(move |mac: &str, progress| {
+                    seen.lock().unwrap().push((mac.to_string(), progress));
+                    // Stop before a real device could be paged.
+                    cancel.cancel();
+                })
+                .build()
         };
-        if ret < 0 {
-            let err = std::io::Error::last_os_error();
-            unsafe {
-                libc::close(fd);
+        let result = connector.pair_and_connect_cancellable("20:16:10:00:3D:4F", "1234", &cancel);
+        assert!(matches!(
+            result,
+            Err(DriverError::Bluetooth(BluetoothError::Cancelled))
+        ));
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [(
+                "20:16:10:00:3D:4F".to_string(),
+                ConnectProgress::Connecting {
+                    attempt: 1,
+                    max_attempts: MAX_CONNECT_RETRIES
+                }
+            )]
+        );
+        assert_eq!(
+            ConnectProgress::Connecting {
+                attempt: 2,
+                max_attempts: 3
             }
-            return Err(DriverError::Bluetooth(BluetoothError::Connection(
-                err.to_string(),
-            )));
-        }
+            .to_string(),
+            "connecting (attempt 2 of 3)"
+        );
     }
 
-    let file = unsafe { File::from_raw_fd(fd) };
-    Ok(RfcommStream { file })
+    #[test]
+    fn recognizes_bitalino_names() {
+        let device = |name: Option<&str>| DiscoveredDevice {
+            mac: "20:16:10:00:3D:4F".into(),
+            name: name.map(String::from),
+            rssi: None,
+        };
+        assert!(device(Some("BITalino-3D-4F")).is_bitalino());
+        assert!(device(Some("bitalino")).is_bitalino());
+        assert!(!device(Some("Headset")).is_bitalino());
+        assert!(!device(None).is_bitalino());
+        assert_eq!(
+            format_bdaddr(parse_bdaddr("20:16:10:00:3d:4f").unwrap()),
+            "20:16:10:00:3D:4F"
+        );
+    }
 }
@@ -0,0 +1,963 @@
+//! Linux RFCOMM backend using the kernel BlueZ socket API (`AF_BLUETOOTH`).
+//!
+//! Only libc is needed: no BlueZ daemon bindings, dbus, or tokio. The device
+//! must already be paired/trusted (e.g. via `bluetoothctl`).
+//!
+//! Discovery sends HCI inquiry and remote-name commands over a raw HCI socket
+//! (on `hci0` unless another adapter is selected). Both commands are on the
+//! kernel's unprivileged allow-list, so scanning does not need `CAP_NET_RAW`.
+//! Selecting an adapter binds the RFCOMM socket to that adapter's address.
+//!
+//! [`RfcommStream::link_quality`] asks the adapter carrying the connection for
+//! its RSSI and link quality (HCI Read RSSI / Get Link Quality) on the same
+//! kind of raw HCI socket.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use super::{format_bdaddr, CancelToken, DiscoveredDevice, LinkQuality, CANCEL_POLL};
+use crate::errors::{BluetoothError, DriverError, Result};
+
+const AF_BLUETOOTH: libc::c_ushort = 31;
+const BTPROTO_HCI: libc::c_int = 1;
+const BTPROTO_RFCOMM: libc::c_int = 3;
+
+const SOL_RFCOMM: libc::c_int = 18;
+const RFCOMM_CONNINFO: libc::c_int = 0x02;
+
+const SOL_HCI: libc::c_int = 0;
+const HCI_FILTER: libc::c_int = 2;
+const HCI_CHANNEL_RAW: u16 = 0;
+/// Adapter used for discovery when none is selected.
+const HCI_DEV: u16 = 0;
+/// Highest adapter index probed when looking an adapter up by address.
+const HCI_MAX_DEV: u16 = 16;
+/// `_IOR('H', 211, int)`: fills a `struct hci_dev_info`.
+const HCIGETDEVINFO: libc::Ioctl = 0x8004_48D3 as libc::Ioctl;
+
+const HCI_COMMAND_PKT: u8 = 0x01;
+const HCI_EVENT_PKT: u8 = 0x04;
+
+const OPCODE_INQUIRY: u16 = 0x0401;
+const OPCODE_INQUIRY_CANCEL: u16 = 0x0402;
+const OPCODE_REMOTE_NAME_REQ: u16 = 0x0419;
+const OPCODE_GET_LINK_QUALITY: u16 = 0x1403;
+const OPCODE_READ_RSSI: u16 = 0x1405;
+
+const EVT_INQUIRY_COMPLETE: u8 = 0x01;
+const EVT_INQUIRY_RESULT: u8 = 0x02;
+const EVT_REMOTE_NAME_REQ_COMPLETE: u8 = 0x07;
+const EVT_CMD_COMPLETE: u8 = 0x0E;
+const EVT_CMD_STATUS: u8 = 0x0F;
+const EVT_INQUIRY_RESULT_WITH_RSSI: u8 = 0x22;
+const EVT_EXTENDED_INQUIRY_RESULT: u8 = 0x2F;
+
+/// General Inquiry Access Code (0x9E8B33), little-endian.
+const GIAC: [u8; 3] = [0x33, 0x8B, 0x9E];
+/// Unit of the inquiry length parameter.
+const INQUIRY_UNIT: Duration = Duration::from_millis(1280);
+/// Largest inquiry length the controller accepts (61.44 s).
+const MAX_INQUIRY_UNITS: u8 = 0x30;
+/// Slack on top of the inquiry length before giving up on the complete event.
+const INQUIRY_SLACK: Duration = Duration::from_secs(2);
+/// How long to wait for each remote name.
+const NAME_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait for a status parameter (RSSI, link quality) reply.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Apply a socket send/receive timeout. A zero `timeval` means "wait forever",
+/// so zero durations are rounded up to 1 µs.
+fn set_timeout(fd: libc::c_int, opt: libc::c_int, timeout: Duration) -> std::io::Result<()> {
+    let timeout = timeout.max(Duration::from_micros(1));
+    let tv = libc::timeval {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+    };
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            opt,
+            &tv as *const _ as *const libc::c_void,
+            mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Simple RFCOMM stream that behaves like a Read/Write object.
+pub struct RfcommStream {
+    file: File,
+}
+
+impl RfcommStream {
+    /// Set the socket receive timeout (`SO_RCVTIMEO`).
+    ///
+    /// Used by the driver to enforce short user-supplied timeouts (e.g. in
+    /// `wait_until_streaming`) without waiting for the connector's default 5 s
+    /// kernel timeout to fire.
+    pub fn set_read_timeout(&self, timeout: Duration) -> std::io::Result<()> {
+        set_timeout(self.file.as_raw_fd(), libc::SO_RCVTIMEO, timeout)
+    }
+
+    /// Set the socket send timeout (`SO_SNDTIMEO`).
+    ///
+    /// Bounds how long a command write may block when the link is congested or
+    /// the device stopped reading.
+    pub fn set_write_timeout(&self, timeout: Duration) -> std::io::Result<()> {
+        set_timeout(self.file.as_raw_fd(), libc::SO_SNDTIMEO, timeout)
+    }
+
+    /// Second handle to the same socket (`dup`), for writing commands from
+    /// another thread while this one reads.
+    pub fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(Self {
+            file: self.file.try_clone()?,
+        })
+    }
+
+    /// Number of bytes buffered in the socket and readable without blocking
+    /// (`FIONREAD`).
+    pub fn bytes_available(&self) -> std::io::Result<usize> {
+        let mut n: libc::c_int = 0;
+        let ret = unsafe { libc::ioctl(self.file.as_raw_fd(), libc::FIONREAD, &mut n) };
+        if ret < 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(n.max(0) as usize)
+        }
+    }
+
+    /// RSSI and link quality of this connection, read from the local adapter.
+    ///
+    /// Opens a raw HCI socket on the adapter carrying the link for each call,
+    /// so poll it at most every few hundred milliseconds. A field is `None` when
+    /// the controller rejects that query.
+    pub fn link_quality(&self) -> std::io::Result<LinkQuality> {
+        let fd = self.file.as_raw_fd();
+
+        let mut conninfo = RfcommConnInfo {
+            hci_handle: 0,
+            dev_class: [0; 3],
+        };
+        let mut len = mem::size_of::<RfcommConnInfo>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                fd,
+                SOL_RFCOMM,
+                RFCOMM_CONNINFO,
+                &mut conninfo as *mut _ as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut local: SockAddrRc = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<SockAddrRc>() as libc::socklen_t;
+        let ret =
+            unsafe { libc::getsockname(fd, &mut local as *mut _ as *mut libc::sockaddr, &mut len) };
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let adapter = format_bdaddr(bdaddr_from_le(&local.rc_bdaddr.b));
+        let (dev, _) = resolve_adapter(&adapter).map_err(std::io::Error::other)?;
+
+        let mut socket = HciSocket::open(dev, CancelToken::new()).map_err(std::io::Error::other)?;
+        let handle = conninfo.hci_handle.to_le_bytes();
+        let mut query = |opcode| {
+            socket
+                .status_parameter(opcode, handle)
+                .map_err(std::io::Error::other)
+        };
+        Ok(LinkQuality {
+            rssi: query(OPCODE_READ_RSSI)?.map(|v| v as i8),
+            link_quality: query(OPCODE_GET_LINK_QUALITY)?,
+        })
+    }
+
+    /// Verify the connection is actually established and usable.
+    pub fn verify_connected(&self) -> Result<()> {
+        // Check socket error status
+        let mut err: libc::c_int = 0;
+        let mut len: libc::socklen_t = mem::size_of::<libc::c_int>() as libc::socklen_t;
+
+        let ret = unsafe {
+            libc::getsockopt(
+                self.file.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_ERROR,
+                &mut err as *mut _ as *mut libc::c_void,
+                &mut len,
+            )
+        };
+
+        if ret < 0 {
+            return Err(DriverError::Io(std::io::Error::last_os_error()));
+        }
+
+        if err != 0 {
+            return Err(DriverError::Bluetooth(BluetoothError::NotConnected(
+                std::io::Error::from_raw_os_error(err).to_string(),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Read for RfcommStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for RfcommStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct BdAddr {
+    b: [u8; 6],
+}
+
+#[repr(C)]
+struct SockAddrRc {
+    rc_family: libc::sa_family_t,
+    rc_bdaddr: BdAddr,
+    rc_channel: u8,
+}
+
+/// `struct rfcomm_conninfo`: ACL handle of the connection.
+#[repr(C)]
+struct RfcommConnInfo {
+    hci_handle: u16,
+    dev_class: [u8; 3],
+}
+
+/// Prefix of `struct hci_dev_info`; the tail is only sized, not read.
+#[repr(C)]
+struct HciDevInfo {
+    dev_id: u16,
+    name: [u8; 8],
+    bdaddr: BdAddr,
+    _rest: [u8; 76],
+}
+
+/// Resolve `adapter` (`"hci1"` or the adapter's MAC address) to its device
+/// index and address (in MAC string order).
+pub(super) fn resolve_adapter(adapter: &str) -> Result<(u16, [u8; 6])> {
+    let by_index = adapter
+        .strip_prefix("hci")
+        .and_then(|n| n.parse::<u16>().ok());
+    let by_address = match by_index {
+        Some(_) => None,
+        None => Some(super::parse_bdaddr(adapter).map_err(|_| {
+            DriverError::Bluetooth(BluetoothError::AdapterNotFound(format!(
+                "{adapter} (expected hciN or an adapter MAC address)"
+            )))
+        })?),
+    };
+
+    let fd = unsafe {
+        libc::socket(
+            AF_BLUETOOTH as libc::c_int,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            BTPROTO_HCI,
+        )
+    };
+    if fd < 0 {
+        return Err(DriverError::Bluetooth(BluetoothError::Unsupported(
+            std::io::Error::last_os_error().to_string(),
+        )));
+    }
+    let socket = unsafe { File::from_raw_fd(fd) };
+
+    let candidates = match by_index {
+        Some(id) => id..id + 1,
+        None => 0..HCI_MAX_DEV,
+    };
+    for dev_id in candidates {
+        let mut info: HciDevInfo = unsafe { mem::zeroed() };
+        info.dev_id = dev_id;
+        if unsafe { libc::ioctl(socket.as_raw_fd(), HCIGETDEVINFO, &mut info) } < 0 {
+            continue;
+        }
+        let mut address = info.bdaddr.b;
+        address.reverse();
+        if by_address.map_or(true, |wanted| wanted == address) {
+            debug!(
+                "using adapter hci{} ({})",
+                info.dev_id,
+                super::format_bdaddr(address)
+            );
+            return Ok((info.dev_id, address));
+        }
+    }
+    Err(DriverError::Bluetooth(BluetoothError::AdapterNotFound(
+        adapter.to_string(),
+    )))
+}
+
+pub(super) fn open_rfcomm(
+    mac: [u8; 6],
+    channel: u8,
+    timeout: Duration,
+    adapter: Option<&str>,
+    cancel: &CancelToken,
+) -> Result<RfcommStream> {
+    // bdaddr_t stores bytes in reverse order compared to the usual MAC string
+    let mut address = BdAddr { b: mac };
+    address.b.reverse();
+
+    debug!(
+        "opening RFCOMM socket: channel={}, addr_bytes={:02X?}",
+        channel, address.b
+    );
+
+    let fd = unsafe {
+        libc::socket(
+            AF_BLUETOOTH as libc::c_int,
+            libc::SOCK_STREAM,
+            BTPROTO_RFCOMM,
+        )
+    };
+    if fd < 0 {
+        return Err(DriverError::Bluetooth(BluetoothError::Connection(
+            std::io::Error::last_os_error().to_string(),
+        )));
+    }
+
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+        let err = std::io::Error::last_os_error();
+        unsafe {
+            libc::close(fd);
+        }
+        return Err(DriverError::Bluetooth(BluetoothError::Connection(
+            err.to_string(),
+        )));
+    }
+
+    if let Some(adapter) = adapter {
+        let local = match resolve_adapter(adapter) {
+            Ok((_, mut local)) => {
+                local.reverse();
+                local
+            }
+            Err(e) => {
+                unsafe {
+                    libc::close(fd);
+                }
+                return Err(e);
+            }
+        };
+        // Channel 0 lets the kernel pick; only the local address matters.
+        let local = SockAddrRc {
+            rc_family: AF_BLUETOOTH as libc::sa_family_t,
+            rc_bdaddr: BdAddr { b: local },
+            rc_channel: 0,
+        };
+        let ret = unsafe {
+            libc::bind(
+                fd,
+                &local as *const _ as *const libc::sockaddr,
+                mem::size_of::<SockAddrRc>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            let err = std::io::Error::last_os_error();
+            unsafe {
+                libc::close(fd);
+            }
+            return Err(DriverError::Bluetooth(BluetoothError::Connection(format!(
+                "bind to adapter {adapter}: {err}"
+            ))));
+        }
+    }
+
+    let addr = SockAddrRc {
+        rc_family: AF_BLUETOOTH as libc::sa_family_t,
+        rc_bdaddr: address,
+        rc_channel: channel,
+    };
+
+    // Connect without blocking so a cancellation is noticed while the
+    // controller pages the device.
+    if let Err(err) = set_nonblocking(fd, true)
+        .and_then(|()| connect_rc(fd, &addr, cancel))
+        .and_then(|()| set_nonblocking(fd, false).map_err(connection_error))
+    {
+        unsafe {
+            libc::close(fd);
+        }
+        return Err(err);
+    }
+
+    // Set IO timeouts to avoid hanging reads/writes.
+    for opt in [libc::SO_RCVTIMEO, libc::SO_SNDTIMEO] {
+        if let Err(err) = set_timeout(fd, opt, timeout) {
+            unsafe {
+                libc::close(fd);
+            }
+            return Err(DriverError::Bluetooth(BluetoothError::Connection(
+                err.to_string(),
+            )));
+        }
+    }
+
+    let file = unsafe { File::from_raw_fd(fd) };
+    Ok(RfcommStream { file })
+}
+
+fn connection_error(err: impl ToString) -> DriverError {
+    DriverError::Bluetooth(BluetoothError::Connection(err.to_string()))
+}
+
+fn set_nonblocking(fd: libc::c_int, nonblocking: bool) -> Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 {
+        return Err(connection_error(std::io::Error::last_os_error()));
+    }
+    let flags = if nonblocking {
+        flags | libc::O_NONBLOCK
+    } else {
+        flags & !libc::O_NONBLOCK
+    };
+    if unsafe { libc::fcntl(fd, libc::F_SETFL, flags) } < 0 {
+        return Err(connection_error(std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// Connect the non-blocking socket `fd` to `addr`, waiting in
+/// [`CANCEL_POLL`] slices until the kernel reports the outcome.
+fn connect_rc(fd: libc::c_int, addr: &SockAddrRc, cancel: &CancelToken) -> Result<()> {
+    let ret = unsafe {
+        libc::connect(
+            fd,
+            addr as *const _ as *const libc::sockaddr,
+            mem::size_of::<SockAddrRc>() as libc::socklen_t,
+        )
+    };
+    if ret == 0 {
+        return Ok(());
+    }
+    let err = std::io::Error::last_os_error();
+    if err.raw_os_error() != Some(libc::EINPROGRESS) {
+        return Err(connection_error(err));
+    }
+
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLOUT,
+        revents: 0,
+    };
+    loop {
+        cancel.check()?;
+        let ret = unsafe { libc::poll(&mut pollfd, 1, CANCEL_POLL.as_millis() as libc::c_int) };
+        if ret < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(connection_error(err));
+        }
+        if ret > 0 {
+            break;
+        }
+    }
+
+    let mut error: libc::c_int = 0;
+    let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_ERROR,
+            &mut error as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret < 0 {
+        return Err(connection_error(std::io::Error::last_os_error()));
+    }
+    if error != 0 {
+        return Err(connection_error(std::io::Error::from_raw_os_error(error)));
+    }
+    Ok(())
+}
+
+#[repr(C)]
+struct SockAddrHci {
+    hci_family: libc::sa_family_t,
+    hci_dev: u16,
+    hci_channel: u16,
+}
+
+/// `struct hci_ufilter`: which packet types and events the socket receives.
+#[repr(C)]
+struct HciFilter {
+    type_mask: u32,
+    event_mask: [u32; 2],
+    opcode: u16,
+}
+
+/// One inquiry response, with the fields needed for a follow-up name request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InquiryResponse {
+    /// Address in MAC string order.
+    bdaddr: [u8; 6],
+    pscan_rep_mode: u8,
+    clock_offset: u16,
+    rssi: Option<i8>,
+    name: Option<String>,
+}
+
+/// The HCI events discovery cares about.
+#[derive(Debug, PartialEq, Eq)]
+enum HciEvent {
+    Inquiry(Vec<InquiryResponse>),
+    InquiryComplete,
+    RemoteName {
+        bdaddr: [u8; 6],
+        name: Option<String>,
+    },
+    CommandStatus {
+        status: u8,
+        opcode: u16,
+    },
+    CommandComplete {
+        opcode: u16,
+        params: Vec<u8>,
+    },
+    Other,
+}
+
+/// Convert a little-endian `bdaddr_t` into MAC string order.
+fn bdaddr_from_le(bytes: &[u8]) -> [u8; 6] {
+    let mut addr = [0u8; 6];
+    addr.copy_from_slice(&bytes[..6]);
+    addr.reverse();
+    addr
+}
+
+/// Decode a NUL-padded UTF-8 name; empty names count as missing.
+fn decode_name(bytes: &[u8]) -> Option<String> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let name = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Extract the local name from extended inquiry response data, preferring the
+/// complete name (type 0x09) over the shortened one (0x08).
+fn eir_name(mut data: &[u8]) -> Option<String> {
+    let mut short = None;
+    while let [len, rest @ ..] = data {
+        let len = *len as usize;
+        if len == 0 || len > rest.len() {
+            break;
+        }
+        let (field, tail) = rest.split_at(len);
+        match field[0] {
+            0x09 => return decode_name(&field[1..]),
+            0x08 => short = decode_name(&field[1..]),
+            _ => {}
+        }
+        data = tail;
+    }
+    short
+}
+
+/// Parse one packet read from a raw HCI socket.
+fn parse_event(packet: &[u8]) -> HciEvent {
+    let [HCI_EVENT_PKT, evt, plen, params @ ..] = packet else {
+        return HciEvent::Other;
+    };
+    let params = &params[..(*plen as usize).min(params.len())];
+
+    // (record size, RSSI offset) for the three inquiry result flavours; the
+    // address, page scan repetition mode and clock offset are common.
+    let inquiry_layout = match *evt {
+        EVT_INQUIRY_RESULT => Some((14, None)),
+        EVT_INQUIRY_RESULT_WITH_RSSI => Some((14, Some(13))),
+        EVT_EXTENDED_INQUIRY_RESULT => Some((254, Some(13))),
+        _ => None,
+    };
+    if let Some((size, rssi_at)) = inquiry_layout {
+        let Some((&count, records)) = params.split_first() else {
+            return HciEvent::Other;
+        };
+        let clock_at = if *evt == EVT_INQUIRY_RESULT { 12 } else { 11 };
+        let responses = records
+            .chunks_exact(size)
+            .take(count as usize)
+            .map(|r| InquiryResponse {
+                bdaddr: bdaddr_from_le(r),
+                pscan_rep_mode: r[6],
+                clock_offset: u16::from_le_bytes([r[clock_at], r[clock_at + 1]]),
+                rssi: rssi_at.map(|i| r[i] as i8),
+                name: if *evt == EVT_EXTENDED_INQUIRY_RESULT {
+                    eir_name(&r[14..])
+                } else {
+                    None
+                },
+            })
+            .collect();
+        return HciEvent::Inquiry(responses);
+    }
+
+    match (*evt, params) {
+        (EVT_INQUIRY_COMPLETE, _) => HciEvent::InquiryComplete,
+        (EVT_REMOTE_NAME_REQ_COMPLETE, [status, rest @ ..]) if rest.len() >= 6 => {
+            HciEvent::RemoteName {
+                bdaddr: bdaddr_from_le(rest),
+                name: if *status == 0 {
+                    decode_name(&rest[6..])
+                } else {
+                    None
+                },
+            }
+        }
+        (EVT_CMD_COMPLETE, [_ncmd, lo, hi, rest @ ..]) => HciEvent::CommandComplete {
+            opcode: u16::from_le_bytes([*lo, *hi]),
+            params: rest.to_vec(),
+        },
+        (EVT_CMD_STATUS, [status, _ncmd, lo, hi, ..]) => HciEvent::CommandStatus {
+            status: *status,
+            opcode: u16::from_le_bytes([*lo, *hi]),
+        },
+        _ => HciEvent::Other,
+    }
+}
+
+fn discovery_error(err: impl ToString) -> DriverError {
+    DriverError::Bluetooth(BluetoothError::Discovery(err.to_string()))
+}
+
+/// Raw HCI socket bound to one adapter.
+struct HciSocket {
+    file: File,
+    /// Checked between reads while waiting for events.
+    cancel: CancelToken,
+}
+
+impl HciSocket {
+    fn open(dev: u16, cancel: CancelToken) -> Result<Self> {
+        let fd = unsafe {
+            libc::socket(
+                AF_BLUETOOTH as libc::c_int,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                BTPROTO_HCI,
+            )
+        };
+        if fd < 0 {
+            return Err(discovery_error(std::io::Error::last_os_error()));
+        }
+        // Owning the fd first closes it on every error path below.
+        let socket = Self {
+            file: unsafe { File::from_raw_fd(fd) },
+            cancel,
+        };
+
+        let addr = SockAddrHci {
+            hci_family: AF_BLUETOOTH as libc::sa_family_t,
+            hci_dev: dev,
+            hci_channel: HCI_CHANNEL_RAW,
+        };
+        let ret = unsafe {
+            libc::bind(
+                fd,
+                &addr as *const _ as *const libc::sockaddr,
+                mem::size_of::<SockAddrHci>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(discovery_error(format!(
+                "hci{}: {}",
+                dev,
+                std::io::Error::last_os_error()
+            )));
+        }
+
+        // Receive every event; the kernel narrows this for unprivileged users.
+        let filter = HciFilter {
+            type_mask: 1 << HCI_EVENT_PKT,
+            event_mask: [u32::MAX; 2],
+            opcode: 0,
+        };
+        let ret = unsafe {
+            libc::setsockopt(
+                fd,
+                SOL_HCI,
+                HCI_FILTER,
+                &filter as *const _ as *const libc::c_void,
+                mem::size_of::<HciFilter>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(discovery_error(std::io::Error::last_os_error()));
+        }
+        Ok(socket)
+    }
+
+    fn send_command(&mut self, opcode: u16, params: &[u8]) -> Result<()> {
+        let mut packet = vec![HCI_COMMAND_PKT];
+        packet.extend_from_slice(&opcode.to_le_bytes());
+        packet.push(params.len() as u8);
+        packet.extend_from_slice(params);
+        self.file.write_all(&packet).map_err(discovery_error)
+    }
+
+    /// Read the next event, or `None` once `deadline` has passed.
+    fn next_event(&mut self, deadline: Instant) -> Result<Option<HciEvent>> {
+        let mut buf = [0u8; 260];
+        loop {
+            self.cancel.check()?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let remaining = remaining.min(CANCEL_POLL);
+            let tv = libc::timeval {
+                tv_sec: remaining.as_secs() as libc::time_t,
+                tv_usec: remaining.subsec_micros().max(1) as libc::suseconds_t,
+            };
+            unsafe {
+                libc::setsockopt(
+                    self.file.as_raw_fd(),
+                    libc::SOL_SOCKET,
+                    libc::SO_RCVTIMEO,
+                    &tv as *const _ as *const libc::c_void,
+                    mem::size_of::<libc::timeval>() as libc::socklen_t,
+                );
+            }
+            match self.file.read(&mut buf) {
+                Ok(n) => return Ok(Some(parse_event(&buf[..n]))),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock
+                            | std::io::ErrorKind::TimedOut
+                            | std::io::ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return Err(discovery_error(e)),
+            }
+        }
+    }
+
+    /// Fail if the controller rejected `opcode` with a command status event.
+    fn check_status(event: &HciEvent, opcode: u16) -> Result<()> {
+        match *event {
+            HciEvent::CommandStatus { status, opcode: op } if op == opcode && status != 0 => {
+                Err(discovery_error(format!(
+                    "controller rejected command {opcode:#06x}: status {status:#04x}"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Run a status parameter command taking a connection handle and return
+    /// its one-byte value (`status, handle, value`), or `None` if the
+    /// controller rejected it or did not answer in time.
+    fn status_parameter(&mut self, opcode: u16, handle: [u8; 2]) -> Result<Option<u8>> {
+        self.send_command(opcode, &handle)?;
+        let deadline = Instant::now() + STATUS_TIMEOUT;
+        while let Some(event) = self.next_event(deadline)? {
+            match event {
+                HciEvent::CommandStatus { opcode: op, .. } if op == opcode => return Ok(None),
+                HciEvent::CommandComplete { opcode: op, params } if op == opcode => {
+                    return Ok(match params[..] {
+                        [0, lo, hi, value, ..] if [lo, hi] == handle => Some(value),
+                        _ => None,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    fn remote_name(&mut self, response: &InquiryResponse) -> Result<Option<String>> {
+        let mut params = [0u8; 10];
+        params[..6].copy_from_slice(&response.bdaddr);
+        params[..6].reverse();
+        params[6] = response.pscan_rep_mode;
+        // Bit 15 marks the clock offset as valid.
+        params[8..].copy_from_slice(&(response.clock_offset | 0x8000).to_le_bytes());
+        self.send_command(OPCODE_REMOTE_NAME_REQ, &params)?;
+
+        let deadline = Instant::now() + NAME_TIMEOUT;
+        while let Some(event) = self.next_event(deadline)? {
+            if Self::check_status(&event, OPCODE_REMOTE_NAME_REQ).is_err() {
+                return Ok(None);
+            }
+            if let HciEvent::RemoteName { bdaddr, name } = event {
+                if bdaddr == response.bdaddr {
+                    return Ok(name);
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Run an inquiry; with a `target`, stop as soon as it answers and skip name
+/// requests.
+pub(super) fn discover(
+    duration: Duration,
+    adapter: Option<&str>,
+    target: Option<[u8; 6]>,
+    cancel: &CancelToken,
+) -> Result<Vec<DiscoveredDevice>> {
+    let dev = match adapter {
+        Some(adapter) => resolve_adapter(adapter)?.0,
+        None => HCI_DEV,
+    };
+    let mut socket = HciSocket::open(dev, cancel.clone())?;
+
+    let units =
+        (duration.as_millis() / INQUIRY_UNIT.as_millis()).clamp(1, MAX_INQUIRY_UNITS as u128) as u8;
+    debug!("starting inquiry: hci{}, length={} x 1.28 s", dev, units);
+    // LAP, inquiry length, unlimited responses.
+    socket.send_command(OPCODE_INQUIRY, &[GIAC[0], GIAC[1], GIAC[2], units, 0])?;
+
+    let mut found: Vec<InquiryResponse> = Vec::new();
+    let deadline = Instant::now() + INQUIRY_UNIT * units as u32 + INQUIRY_SLACK;
+    loop {
+        let event = match socket.next_event(deadline) {
+            Ok(Some(event)) => event,
+            Ok(None) => break,
+            Err(e) => {
+                if cancel.is_cancelled() {
+                    // Otherwise the controller keeps scanning to the end.
+                    let _ = socket.send_command(OPCODE_INQUIRY_CANCEL, &[]);
+                }
+                return Err(e);
+            }
+        };
+        HciSocket::check_status(&event, OPCODE_INQUIRY)?;
+        match event {
+            HciEvent::Inquiry(responses) => {
+                for response in responses {
+                    match found.iter_mut().find(|r| r.bdaddr == response.bdaddr) {
+                        Some(known) => {
+                            known.rssi = response.rssi.or(known.rssi);
+                            known.name = response.name.or(known.name.take());
+                        }
+                        None => found.push(response),
+                    }
+                }
+                if target.is_some_and(|t| found.iter().any(|r| r.bdaddr == t)) {
+                    socket.send_command(OPCODE_INQUIRY_CANCEL, &[])?;
+                    break;
+                }
+            }
+            HciEvent::InquiryComplete => break,
+            _ => {}
+        }
+    }
+
+    let resolve_names = target.is_none();
+    for response in found
+        .iter_mut()
+        .filter(|r| resolve_names && r.name.is_none())
+    {
+        response.name = socket.remote_name(response)?;
+    }
+
+    Ok(found
+        .into_iter()
+        .map(|r| DiscoveredDevice {
+            mac: format_bdaddr(r.bdaddr),
+            name: r.name,
+            rssi: r.rssi,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inquiry_and_name_events() {
+        // Inquiry Result with RSSI for 20:16:10:00:3D:4F at -62 dBm.
+        let mut packet = vec![HCI_EVENT_PKT, EVT_INQUIRY_RESULT_WITH_RSSI, 15, 1];
+        packet.extend_from_slice(&[0x4F, 0x3D, 0x00, 0x10, 0x16, 0x20]);
+        packet.extend_from_slice(&[0x01, 0x00, 0x00, 0x1F, 0x00, 0x34, 0x12, 0xC2]);
+        let HciEvent::Inquiry(responses) = parse_event(&packet) else {
+            panic!("not an inquiry result");
+        };
+        assert_eq!(
+            responses,
+            vec![InquiryResponse {
+                bdaddr: [0x20, 0x16, 0x10, 0x00, 0x3D, 0x4F],
+                pscan_rep_mode: 1,
+                clock_offset: 0x1234,
+                rssi: Some(-62),
+                name: None,
+            }]
+        );
+
+        let mut packet = vec![HCI_EVENT_PKT, EVT_REMOTE_NAME_REQ_COMPLETE, 255, 0];
+        packet.extend_from_slice(&[0x4F, 0x3D, 0x00, 0x10, 0x16, 0x20]);
+        packet.extend_from_slice(b"BITalino-3D-4F");
+        packet.resize(3 + 255, 0);
+        assert_eq!(
+            parse_event(&packet),
+            HciEvent::RemoteName {
+                bdaddr: [0x20, 0x16, 0x10, 0x00, 0x3D, 0x4F],
+                name: Some("BITalino-3D-4F".into()),
+            }
+        );
+
+        // Read RSSI complete: status 0, handle 0x000B, RSSI -7.
+        let packet = [
+            HCI_EVENT_PKT,
+            EVT_CMD_COMPLETE,
+            7,
+            1,
+            0x05,
+            0x14,
+            0,
+            0x0B,
+            0,
+            0xF9,
+        ];
+        assert_eq!(
+            parse_event(&packet),
+            HciEvent::CommandComplete {
+                opcode: OPCODE_READ_RSSI,
+                params: vec![0, 0x0B, 0, 0xF9],
+            }
+        );
+    }
+
+    #[test]
+    fn eir_prefers_complete_name() {
+        let mut eir = vec![4, 0x08, b'B', b'I', b'T'];
+        eir.extend_from_slice(&[9, 0x09]);
+        eir.extend_from_slice(b"BITalino");
+        eir.push(0);
+        assert_eq!(eir_name(&eir).as_deref(), Some("BITalino"));
+        assert_eq!(eir_name(&eir[..5]).as_deref(), Some("BIT"));
+    }
+}
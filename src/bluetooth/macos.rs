@@ -0,0 +1,204 @@
+//! macOS backend: connect through the serial device that macOS creates for a
+//! paired BITalino (`/dev/cu.BITalino-XX-XX`).
+//!
+//! macOS exposes paired Serial Port Profile devices as tty nodes, so no
+//! IOBluetooth bindings are needed. The device must already be paired in the
+//! Bluetooth settings; the MAC selects which node to open.
+
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use log::debug;
+use serialport::SerialPort;
+
+use super::{CancelToken, DiscoveredDevice, LinkQuality};
+use crate::errors::{BluetoothError, DriverError, Result};
+
+/// Serial baud rate of the BITalino SPP link.
+const BAUD_RATE: u32 = 115200;
+
+/// Directory scanned for macOS serial device nodes.
+const DEV_DIR: &str = "/dev";
+
+/// Prefix of the callout devices macOS creates for paired BITalinos.
+const DEVICE_PREFIX: &str = "cu.BITalino";
+
+/// RFCOMM stream backed by the macOS serial device of a paired BITalino.
+pub struct RfcommStream {
+    /// `RefCell` lets the shared-reference timeout setter reach
+    /// `SerialPort::set_timeout`, which needs `&mut`.
+    port: RefCell<Box<dyn SerialPort>>,
+}
+
+impl RfcommStream {
+    /// Set the serial read timeout.
+    ///
+    /// Used by the driver to enforce short user-supplied timeouts (e.g. in
+    /// `wait_until_streaming`) without waiting for the connector's default 5 s
+    /// timeout to fire.
+    pub fn set_read_timeout(&self, timeout: Duration) -> io::Result<()> {
+        self.port
+            .borrow_mut()
+            .set_timeout(timeout)
+            .map_err(io::Error::other)
+    }
+
+    /// Set the write timeout.
+    ///
+    /// Serial ports share one timeout between reads and writes, and writes to
+    /// the Bluetooth serial node complete into the driver's buffer, so this is
+    /// a no-op that keeps the read timeout intact.
+    pub fn set_write_timeout(&self, _timeout: Duration) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Second handle to the same serial device, for writing commands from
+    /// another thread while this one reads.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let port = self.port.borrow().try_clone().map_err(io::Error::other)?;
+        Ok(Self {
+            port: RefCell::new(port),
+        })
+    }
+
+    /// Number of bytes buffered by the serial driver and readable without blocking.
+    pub fn bytes_available(&self) -> io::Result<usize> {
+        self.port
+            .borrow()
+            .bytes_to_read()
+            .map(|n| n as usize)
+            .map_err(io::Error::other)
+    }
+
+    /// RSSI and link quality of this connection.
+    ///
+    /// The serial node hides the underlying Bluetooth link, so this always
+    /// fails with `Unsupported`.
+    pub fn link_quality(&self) -> io::Result<LinkQuality> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Verify the connection is actually established and usable.
+    ///
+    /// Opening the serial node already establishes the Bluetooth link, so this
+    /// only checks that the driver still answers.
+    pub fn verify_connected(&self) -> Result<()> {
+        self.port
+            .borrow()
+            .bytes_to_read()
+            .map(|_| ())
+            .map_err(|e| DriverError::Bluetooth(BluetoothError::NotConnected(e.to_string())))
+    }
+}
+
+impl Read for RfcommStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.get_mut().read(buf)
+    }
+}
+
+impl Write for RfcommStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.get_mut().flush()
+    }
+}
+
+/// Pick the serial node for `mac` among `/dev` entry names.
+///
+/// BITalinos advertise as `BITalino-XX-XX`, where `XX-XX` are the last two MAC
+/// bytes. Nodes of other BITalinos are never used in place of the requested
+/// one.
+fn select_device(names: &[String], mac: [u8; 6]) -> Option<String> {
+    let suffix = format!("{:02X}-{:02X}", mac[4], mac[5]);
+    names
+        .iter()
+        .find(|n| n.starts_with(DEVICE_PREFIX) && n.to_uppercase().ends_with(&suffix))
+        .cloned()
+}
+
+pub(super) fn open_rfcomm(
+    mac: [u8; 6],
+    _channel: u8,
+    timeout: Duration,
+    adapter: Option<&str>,
+    _cancel: &CancelToken,
+) -> Result<RfcommStream> {
+    if let Some(adapter) = adapter {
+        return Err(DriverError::Bluetooth(BluetoothError::Unsupported(
+            format!("selecting adapter {adapter} is only supported on Linux"),
+        )));
+    }
+    let names: Vec<String> = std::fs::read_dir(DEV_DIR)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+
+    let mac_str = mac.map(|b| format!("{b:02X}")).join(":");
+    let name = select_device(&names, mac).ok_or_else(|| {
+        DriverError::Bluetooth(BluetoothError::NotFound {
+            mac: mac_str.clone(),
+        })
+    })?;
+    let path = format!("{DEV_DIR}/{name}");
+    debug!(
+        "opening BITalino serial node: path={}, mac={}",
+        path, mac_str
+    );
+
+    let port = serialport::new(&path, BAUD_RATE)
+        .timeout(timeout)
+        .open()
+        .map_err(|e| DriverError::Bluetooth(BluetoothError::Connection(e.to_string())))?;
+
+    Ok(RfcommStream {
+        port: RefCell::new(port),
+    })
+}
+
+pub(super) fn discover(
+    _duration: Duration,
+    _adapter: Option<&str>,
+    _target: Option<[u8; 6]>,
+    _cancel: &CancelToken,
+) -> Result<Vec<DiscoveredDevice>> {
+    Err(DriverError::Bluetooth(BluetoothError::Unsupported(
+        "inquiry scans need IOBluetooth; pair the device in the Bluetooth settings and \
+         look for /dev/cu.BITalino-* instead"
+            .into(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x20, 0x16, 0x10, 0x00, 0x3D, 0x4F];
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn selects_the_node_matching_the_mac_suffix() {
+        let names = names(&[
+            "cu.Bluetooth-Incoming-Port",
+            "cu.BITalino-12-34",
+            "cu.BITalino-3D-4F",
+        ]);
+        assert_eq!(
+            select_device(&names, MAC).as_deref(),
+            Some("cu.BITalino-3D-4F")
+        );
+    }
+
+    #[test]
+    fn does_not_fall_back_to_another_device() {
+        let names = names(&["cu.Bluetooth-Incoming-Port", "cu.BITalino-12-34"]);
+        assert_eq!(select_device(&names, MAC), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Pairing through the BlueZ daemon over D-Bus (`sync-bluetooth` feature).
+//!
+//! The RFCOMM socket itself never needs the daemon, but bonding a new device
+//! does. Before connecting, [`ensure_paired`] asks `bluetoothd` whether the
+//! device is paired and, if not, runs `org.bluez.Device1.Pair` with a
+//! short-lived agent passing BlueZ's requests to a [`PairingAgent`] (by
+//! default, the configured PIN), then marks the device trusted. Everything
+//! runs on one blocking libdbus connection pumped from the calling thread: no
+//! async runtime is started.
+
+use std::ffi::CString;
+use std::time::{Duration, Instant};
+
+use dbus::arg::Variant;
+use dbus::channel::{BusType, Channel};
+use dbus::message::MessageType;
+use dbus::strings::ErrorName;
+use dbus::Message;
+use log::{debug, info};
+
+use super::linux::resolve_adapter;
+use super::{CancelToken, ConnectProgress, PairingAgent, PairingRequest, PairingResponse};
+use crate::errors::{BluetoothError, DriverError, Result};
+
+const BLUEZ: &str = "org.bluez";
+const DEVICE: &str = "org.bluez.Device1";
+const ADAPTER: &str = "org.bluez.Adapter1";
+const AGENT: &str = "org.bluez.Agent1";
+const AGENT_MANAGER: &str = "org.bluez.AgentManager1";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+/// Errors answering a call on a device object BlueZ has not created.
+const UNKNOWN_OBJECT: &str = "org.freedesktop.DBus.Error.UnknownObject";
+const UNKNOWN_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+
+/// Object path the agent is registered at.
+const AGENT_PATH: &str = "/io/github/bitalino_rs/agent";
+
+/// Timeout for calls that do not wait on the device.
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time allowed for the whole pairing handshake.
+const PAIR_TIMEOUT: Duration = Duration::from_secs(30);
+/// Interval between checks for the device while BlueZ discovers it.
+const DISCOVERY_POLL: Duration = Duration::from_millis(250);
+
+fn pairing_error(err: impl ToString) -> DriverError {
+    DriverError::Bluetooth(BluetoothError::Pairing(err.to_string()))
+}
+
+fn method_call(path: &str, iface: &str, method: &str) -> Result<Message> {
+    Message::new_method_call(BLUEZ, path, iface, method).map_err(pairing_error)
+}
+
+/// Blocking call to `bluetoothd`, turning D-Bus errors into pairing errors.
+fn call(bus: &Channel, msg: Message, timeout: Duration) -> Result<Message> {
+    bus.send_with_reply_and_block(msg, timeout)
+        .map_err(|e| pairing_error(e.message().unwrap_or("D-Bus call failed")))
+}
+
+/// A boolean property of the device, or `None` if BlueZ does not know it.
+fn device_flag(bus: &Channel, path: &str, name: &str) -> Result<Option<bool>> {
+    let msg = method_call(path, PROPERTIES, "Get")?.append2(DEVICE, name);
+    match bus.send_with_reply_and_block(msg, CALL_TIMEOUT) {
+        Ok(reply) => reply
+            .read1::<Variant<bool>>()
+            .map(|v| Some(v.0))
+            .map_err(pairing_error),
+        Err(e) if matches!(e.name(), Some(UNKNOWN_OBJECT | UNKNOWN_METHOD)) => Ok(None),
+        Err(e) => Err(pairing_error(e.message().unwrap_or("D-Bus call failed"))),
+    }
+}
+
+/// Run discovery on the adapter until BlueZ creates the device object.
+fn discover_device(
+    bus: &Channel,
+    adapter: &str,
+    device: &str,
+    mac: &str,
+    cancel: &CancelToken,
+) -> Result<()> {
+    info!("{} unknown to BlueZ, discovering it", mac);
+    call(
+        bus,
+        method_call(adapter, ADAPTER, "StartDiscovery")?,
+        CALL_TIMEOUT,
+    )?;
+    let deadline = Instant::now() + PAIR_TIMEOUT;
+    let found = loop {
+        if device_flag(bus, device, "Paired")?.is_some() {
+            break true;
+        }
+        if Instant::now() >= deadline || cancel.is_cancelled() {
+            break false;
+        }
+        std::thread::sleep(DISCOVERY_POLL);
+    };
+    let _ = call(
+        bus,
+        method_call(adapter, ADAPTER, "StopDiscovery")?,
+        CALL_TIMEOUT,
+    );
+    cancel.check()?;
+    if found {
+        Ok(())
+    } else {
+        Err(DriverError::Bluetooth(BluetoothError::NotFound {
+            mac: mac.to_string(),
+        }))
+    }
+}
+
+/// Answer a call made to the agent with what `agent` says for device `mac`:
+/// a PIN or passkey, or whether to confirm or authorize. Anything else is
+/// rejected.
+fn agent_reply(call: &Message, mac: &str, agent: &PairingAgent) -> Message {
+    let reject = |reason: &str| {
+        let name = ErrorName::new("org.bluez.Error.Rejected").expect("valid error name");
+        let text = CString::new(reason).unwrap_or_default();
+        call.error(&name, &text)
+    };
+    let request = match call.member().as_deref() {
+        Some("RequestPinCode") => PairingRequest::PinCode,
+        Some("RequestPasskey") => PairingRequest::Passkey,
+        Some("RequestConfirmation") => match call.read2::<dbus::Path, u32>() {
+            Ok((_, passkey)) => PairingRequest::Confirmation { passkey },
+            Err(_) => return reject("malformed confirmation request"),
+        },
+        Some("RequestAuthorization") => PairingRequest::Authorization,
+        Some("Release" | "Cancel" | "DisplayPinCode" | "DisplayPasskey") => {
+            return call.method_return()
+        }
+        Some(other) => return reject(&format!("{other} is not supported")),
+        None => return reject("no method"),
+    };
+    match (request, agent.answer(mac, request)) {
+        (PairingRequest::PinCode, PairingResponse::Pin(pin)) => call.method_return().append1(pin),
+        (PairingRequest::PinCode, PairingResponse::Passkey(passkey)) => {
+            call.method_return().append1(passkey.to_string())
+        }
+        (PairingRequest::Passkey, PairingResponse::Passkey(passkey)) => {
+            call.method_return().append1(passkey)
+        }
+        (PairingRequest::Passkey, PairingResponse::Pin(pin)) => match pin.parse::<u32>() {
+            Ok(passkey) => call.method_return().append1(passkey),
+            Err(_) => reject("PIN is not a numeric passkey"),
+        },
+        (
+            PairingRequest::Confirmation { .. } | PairingRequest::Authorization,
+            PairingResponse::Accept,
+        ) => call.method_return(),
+        (_, PairingResponse::Reject) => reject("rejected by the pairing agent"),
+        (request, response) => reject(&format!("{response:?} does not answer {request:?}")),
+    }
+}
+
+/// Call `Device1.Pair` and serve agent requests until it returns, or call
+/// `Device1.CancelPairing` once `cancel` is cancelled.
+fn pair(
+    bus: &Channel,
+    device: &str,
+    mac: &str,
+    agent: &PairingAgent,
+    cancel: &CancelToken,
+) -> Result<()> {
+    let serial = bus
+        .send(method_call(device, DEVICE, "Pair")?)
+        .map_err(|()| pairing_error("failed to send Pair"))?;
+    let deadline = Instant::now() + PAIR_TIMEOUT;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(pairing_error("timed out waiting for the device"));
+        }
+        if cancel.is_cancelled() {
+            let _ = call(
+                bus,
+                method_call(device, DEVICE, "CancelPairing")?,
+                CALL_TIMEOUT,
+            );
+            return cancel.check();
+        }
+        bus.read_write(Some(left.min(DISCOVERY_POLL)))
+            .map_err(|()| pairing_error("D-Bus connection lost"))?;
+        while let Some(mut msg) = bus.pop_message() {
+            match msg.msg_type() {
+                MessageType::MethodCall
+                    if msg.path().as_deref() == Some(AGENT_PATH)
+                        && msg.interface().as_deref() == Some(AGENT) =>
+                {
+                    debug!("agent request: {:?}", msg.member());
+                    let reply = agent_reply(&msg, mac, agent);
+                    let _ = bus.send(reply);
+                }
+                MessageType::MethodReturn | MessageType::Error
+                    if msg.get_reply_serial() == Some(serial) =>
+                {
+                    return match msg.as_result() {
+                        Ok(_) => Ok(()),
+                        Err(e) if e.name() == Some("org.bluez.Error.AlreadyExists") => Ok(()),
+                        Err(e) => Err(pairing_error(e.message().unwrap_or("Pair failed"))),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Make sure BlueZ has `mac` paired and trusted, pairing through `agent` if
+/// needed.
+///
+/// `adapter` selects the local adapter like
+/// [`BluetoothConnectorBuilder::adapter`](super::BluetoothConnectorBuilder::adapter).
+/// Discovery and pairing stop early with [`BluetoothError::Cancelled`] once
+/// `cancel` is cancelled. `report` is told when discovery and pairing start
+/// and succeed.
+pub(super) fn ensure_paired(
+    mac: &str,
+    agent: &PairingAgent,
+    adapter: Option<&str>,
+    cancel: &CancelToken,
+    report: impl Fn(ConnectProgress),
+) -> Result<()> {
+    let adapter = match adapter {
+        Some(adapter) => format!("/org/bluez/hci{}", resolve_adapter(adapter)?.0),
+        None => "/org/bluez/hci0".to_string(),
+    };
+    let device = format!(
+        "{}/dev_{}",
+        adapter,
+        mac.to_ascii_uppercase().replace(':', "_")
+    );
+
+    let bus = Channel::get_private(BusType::System).map_err(|e| {
+        pairing_error(format!(
+            "cannot reach bluetoothd on the system bus: {}",
+            e.message().unwrap_or("unknown error")
+        ))
+    })?;
+
+    let paired = match device_flag(&bus, &device, "Paired")? {
+        Some(paired) => paired,
+        None => {
+            report(ConnectProgress::Scanning);
+            discover_device(&bus, &adapter, &device, mac, cancel)?;
+            report(ConnectProgress::Found);
+            false
+        }
+    };
+    if !paired {
+        info!("pairing with {} through BlueZ", mac);
+        report(ConnectProgress::Pairing);
+        let capability = if agent.is_interactive() {
+            "KeyboardDisplay"
+        } else {
+            "KeyboardOnly"
+        };
+        let register = method_call("/org/bluez", AGENT_MANAGER, "RegisterAgent")?
+            .append2(dbus::Path::from(AGENT_PATH), capability);
+        call(&bus, register, CALL_TIMEOUT)?;
+        let result = pair(&bus, &device, mac, agent, cancel);
+        let unregister = method_call("/org/bluez", AGENT_MANAGER, "UnregisterAgent")?
+            .append1(dbus::Path::from(AGENT_PATH));
+        let _ = call(&bus, unregister, CALL_TIMEOUT);
+        result?;
+        report(ConnectProgress::Paired);
+    }
+    if device_flag(&bus, &device, "Trusted")? != Some(true) {
+        let trust =
+            method_call(&device, PROPERTIES, "Set")?.append3(DEVICE, "Trusted", Variant(true));
+        call(&bus, trust, CALL_TIMEOUT)?;
+    }
+    debug!("{} is paired and trusted", mac);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: &str = "20:16:10:00:3D:4F";
+
+    fn request(member: &str) -> Message {
+        let mut call = Message::new_method_call(":1.5", AGENT_PATH, AGENT, member)
+            .unwrap()
+            .append1(dbus::Path::from("/org/bluez/hci0/dev_20_16_10_00_3D_4F"));
+        call.set_serial(7);
+        call
+    }
+
+    #[test]
+    fn agent_answers_pin_requests_only() {
+        let pin = |pin: &str| PairingAgent::pin(pin);
+        let reply = agent_reply(&request("RequestPinCode"), MAC, &pin("1234"));
+        assert_eq!(reply.msg_type(), MessageType::MethodReturn);
+        assert_eq!(reply.read1::<&str>().unwrap(), "1234");
+
+        let reply = agent_reply(&request("RequestPasskey"), MAC, &pin("1234"));
+        assert_eq!(reply.read1::<u32>().unwrap(), 1234);
+        let reply = agent_reply(&request("RequestPasskey"), MAC, &pin("abcd"));
+        assert_eq!(reply.msg_type(), MessageType::Error);
+
+        let confirmation = request("RequestConfirmation").append1(123_456u32);
+        let reply = agent_reply(&confirmation, MAC, &pin("1234"));
+        assert_eq!(reply.msg_type(), MessageType::Error);
+    }
+
+    #[test]
+    fn custom_agent_sees_the_passkey_to_confirm() {
+        let agent = PairingAgent::new(|mac, request| match request {
+            PairingRequest::Confirmation { passkey: 123_456 } if mac == MAC => {
+                PairingResponse::Accept
+            }
+            PairingRequest::Passkey => PairingResponse::Passkey(987_654),
+            _ => PairingResponse::Reject,
+        });
+        let confirmation = request("RequestConfirmation").append1(123_456u32);
+        let reply = agent_reply(&confirmation, MAC, &agent);
+        assert_eq!(reply.msg_type(), MessageType::MethodReturn);
+
+        let reply = agent_reply(&request("RequestPasskey"), MAC, &agent);
+        assert_eq!(reply.read1::<u32>().unwrap(), 987_654);
+        let reply = agent_reply(&request("RequestAuthorization"), MAC, &agent);
+        assert_eq!(reply.msg_type(), MessageType::Error);
+    }
+}
@@ -0,0 +1,88 @@
+//! Placeholder backend for platforms without native RFCOMM support.
+//!
+//! Keeps the public API compiling everywhere; connecting always fails with
+//! [`BluetoothError::Unsupported`]. Use a serial port path instead where the OS
+//! exposes the device as one.
+
+use std::convert::Infallible;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use super::{CancelToken, DiscoveredDevice, LinkQuality};
+use crate::errors::{BluetoothError, DriverError, Result};
+
+/// Uninhabited RFCOMM stream: no value can exist on this platform.
+pub struct RfcommStream {
+    never: Infallible,
+}
+
+impl RfcommStream {
+    /// Set the socket receive timeout (`SO_RCVTIMEO`).
+    pub fn set_read_timeout(&self, _timeout: Duration) -> io::Result<()> {
+        match self.never {}
+    }
+
+    /// Set the socket send timeout (`SO_SNDTIMEO`).
+    pub fn set_write_timeout(&self, _timeout: Duration) -> io::Result<()> {
+        match self.never {}
+    }
+
+    /// Second handle to the same socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match self.never {}
+    }
+
+    /// Number of bytes readable without blocking.
+    pub fn bytes_available(&self) -> io::Result<usize> {
+        match self.never {}
+    }
+
+    /// RSSI and link quality of this connection.
+    pub fn link_quality(&self) -> io::Result<LinkQuality> {
+        match self.never {}
+    }
+
+    /// Verify the connection is actually established and usable.
+    pub fn verify_connected(&self) -> Result<()> {
+        match self.never {}
+    }
+}
+
+impl Read for RfcommStream {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        match self.never {}
+    }
+}
+
+impl Write for RfcommStream {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        match self.never {}
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.never {}
+    }
+}
+
+pub(super) fn open_rfcomm(
+    _mac: [u8; 6],
+    _channel: u8,
+    _timeout: Duration,
+    _adapter: Option<&str>,
+    _cancel: &CancelToken,
+) -> Result<RfcommStream> {
+    Err(DriverError::Bluetooth(BluetoothError::Unsupported(
+        "no RFCOMM backend for this platform (Linux requires the `bluez` feature)".into(),
+    )))
+}
+
+pub(super) fn discover(
+    _duration: Duration,
+    _adapter: Option<&str>,
+    _target: Option<[u8; 6]>,
+    _cancel: &CancelToken,
+) -> Result<Vec<DiscoveredDevice>> {
+    Err(DriverError::Bluetooth(BluetoothError::Unsupported(
+        "no discovery backend for this platform (Linux requires the `bluez` feature)".into(),
+    )))
+}
@@ -0,0 +1,313 @@
+//! Windows RFCOMM backend using Winsock Bluetooth sockets (`AF_BTH`).
+//!
+//! The device must already be paired in the Windows Bluetooth settings. Winsock
+//! is initialized lazily on the first connection attempt. Discovery uses the
+//! Bluetooth API's device search, which does not report RSSI.
+
+use std::io::{self, Read, Write};
+use std::mem;
+use std::sync::Once;
+use std::time::Duration;
+
+use log::debug;
+use windows_sys::Win32::Devices::Bluetooth::{
+    BluetoothFindDeviceClose, BluetoothFindFirstDevice, BluetoothFindNextDevice, AF_BTH,
+    BLUETOOTH_DEVICE_INFO, BLUETOOTH_DEVICE_SEARCH_PARAMS, BTHPROTO_RFCOMM, SOCKADDR_BTH,
+};
+use windows_sys::Win32::Networking::WinSock::{
+    closesocket, connect, getsockopt, ioctlsocket, recv, send, setsockopt, socket, WSAGetLastError,
+    WSAStartup, FIONREAD, INVALID_SOCKET, SOCKADDR, SOCKET, SOCKET_ERROR, SOCK_STREAM, SOL_SOCKET,
+    SO_ERROR, SO_RCVTIMEO, SO_SNDTIMEO, WSADATA,
+};
+
+use super::{format_bdaddr, CancelToken, DiscoveredDevice, LinkQuality};
+use crate::errors::{BluetoothError, DriverError, Result};
+
+/// Unit of the search timeout multiplier.
+const INQUIRY_UNIT: Duration = Duration::from_millis(1280);
+/// Largest timeout multiplier the API accepts (61.44 s).
+const MAX_INQUIRY_UNITS: u8 = 48;
+/// `ERROR_NO_MORE_ITEMS`: the search finished without (further) results.
+const ERROR_NO_MORE_ITEMS: i32 = 259;
+
+static WINSOCK_INIT: Once = Once::new();
+
+/// Initialize Winsock 2.2 once per process. A failure here surfaces as an error
+/// from the subsequent `socket()` call, so the return code is not checked.
+fn ensure_winsock() {
+    WINSOCK_INIT.call_once(|| {
+        let mut data: WSADATA = unsafe { mem::zeroed() };
+        unsafe {
+            WSAStartup(0x0202, &mut data);
+        }
+    });
+}
+
+fn last_error() -> io::Error {
+    io::Error::from_raw_os_error(unsafe { WSAGetLastError() })
+}
+
+/// Apply a Winsock send/receive timeout (a `DWORD` in milliseconds; 0 means
+/// "wait forever", so sub-millisecond timeouts are rounded up to 1 ms).
+fn set_timeout(socket: SOCKET, opt: i32, timeout: Duration) -> io::Result<()> {
+    let ms = timeout.as_millis().clamp(1, u32::MAX as u128) as u32;
+    let ret = unsafe {
+        setsockopt(
+            socket,
+            SOL_SOCKET,
+            opt,
+            &ms as *const u32 as *const u8,
+            mem::size_of::<u32>() as i32,
+        )
+    };
+    if ret == SOCKET_ERROR {
+        Err(last_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Simple RFCOMM stream that behaves like a Read/Write object.
+pub struct RfcommStream {
+    socket: SOCKET,
+}
+
+impl RfcommStream {
+    /// Set the socket receive timeout (`SO_RCVTIMEO`).
+    ///
+    /// Used by the driver to enforce short user-supplied timeouts (e.g. in
+    /// `wait_until_streaming`) without waiting for the connector's default 5 s
+    /// timeout to fire.
+    pub fn set_read_timeout(&self, timeout: Duration) -> io::Result<()> {
+        set_timeout(self.socket, SO_RCVTIMEO, timeout)
+    }
+
+    /// Set the socket send timeout (`SO_SNDTIMEO`).
+    ///
+    /// Bounds how long a command write may block when the link is congested or
+    /// the device stopped reading.
+    pub fn set_write_timeout(&self, timeout: Duration) -> io::Result<()> {
+        set_timeout(self.socket, SO_SNDTIMEO, timeout)
+    }
+
+    /// Second handle to the same socket.
+    ///
+    /// Not supported: Winsock sockets cannot be shared between handles
+    /// without `WSADuplicateSocket`, which this backend does not use.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Number of bytes buffered in the socket and readable without blocking
+    /// (`FIONREAD`).
+    pub fn bytes_available(&self) -> io::Result<usize> {
+        let mut n: u32 = 0;
+        if unsafe { ioctlsocket(self.socket, FIONREAD, &mut n) } == SOCKET_ERROR {
+            Err(last_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    /// RSSI and link quality of this connection.
+    ///
+    /// Winsock does not expose per-connection radio metrics, so this always
+    /// fails with `Unsupported`.
+    pub fn link_quality(&self) -> io::Result<LinkQuality> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Verify the connection is actually established and usable.
+    pub fn verify_connected(&self) -> Result<()> {
+        let mut err: i32 = 0;
+        let mut len = mem::size_of::<i32>() as i32;
+        let ret = unsafe {
+            getsockopt(
+                self.socket,
+                SOL_SOCKET,
+                SO_ERROR,
+                &mut err as *mut i32 as *mut u8,
+                &mut len,
+            )
+        };
+
+        if ret == SOCKET_ERROR {
+            return Err(DriverError::Io(last_error()));
+        }
+
+        if err != 0 {
+            return Err(DriverError::Bluetooth(BluetoothError::NotConnected(
+                io::Error::from_raw_os_error(err).to_string(),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Read for RfcommStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(i32::MAX as usize) as i32;
+        let n = unsafe { recv(self.socket, buf.as_mut_ptr(), len, 0) };
+        if n == SOCKET_ERROR {
+            Err(last_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+}
+
+impl Write for RfcommStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(i32::MAX as usize) as i32;
+        let n = unsafe { send(self.socket, buf.as_ptr(), len, 0) };
+        if n == SOCKET_ERROR {
+            Err(last_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RfcommStream {
+    fn drop(&mut self) {
+        unsafe {
+            closesocket(self.socket);
+        }
+    }
+}
+
+pub(super) fn open_rfcomm(
+    mac: [u8; 6],
+    channel: u8,
+    timeout: Duration,
+    adapter: Option<&str>,
+    _cancel: &CancelToken,
+) -> Result<RfcommStream> {
+    reject_adapter(adapter)?;
+    // BTH_ADDR packs the MAC most-significant byte first into the low 48 bits.
+    let bt_addr = mac.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    debug!(
+        "opening RFCOMM socket: channel={}, bth_addr={:012X}",
+        channel, bt_addr
+    );
+
+    ensure_winsock();
+
+    let sock = unsafe { socket(AF_BTH as i32, SOCK_STREAM, BTHPROTO_RFCOMM as i32) };
+    if sock == INVALID_SOCKET {
+        return Err(DriverError::Bluetooth(BluetoothError::Connection(
+            last_error().to_string(),
+        )));
+    }
+    // Owning the socket from here on closes it on every early return.
+    let stream = RfcommStream { socket: sock };
+
+    let mut addr: SOCKADDR_BTH = unsafe { mem::zeroed() };
+    addr.addressFamily = AF_BTH;
+    addr.btAddr = bt_addr;
+    addr.port = channel as u32;
+
+    let ret = unsafe {
+        connect(
+            stream.socket,
+            &addr as *const SOCKADDR_BTH as *const SOCKADDR,
+            mem::size_of::<SOCKADDR_BTH>() as i32,
+        )
+    };
+    if ret == SOCKET_ERROR {
+        return Err(DriverError::Bluetooth(BluetoothError::Connection(
+            last_error().to_string(),
+        )));
+    }
+
+    // Set IO timeouts to avoid hanging reads/writes.
+    for opt in [SO_RCVTIMEO, SO_SNDTIMEO] {
+        set_timeout(stream.socket, opt, timeout)
+            .map_err(|e| DriverError::Bluetooth(BluetoothError::Connection(e.to_string())))?;
+    }
+
+    Ok(stream)
+}
+
+/// Adapter selection is only implemented by the Linux backend.
+fn reject_adapter(adapter: Option<&str>) -> Result<()> {
+    match adapter {
+        Some(adapter) => Err(DriverError::Bluetooth(BluetoothError::Unsupported(
+            format!("selecting adapter {adapter} is only supported on Linux"),
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn to_discovered(info: &BLUETOOTH_DEVICE_INFO) -> DiscoveredDevice {
+    // BLUETOOTH_ADDRESS stores the MAC least-significant byte first.
+    let mut mac = unsafe { info.Address.Anonymous.rgBytes };
+    mac.reverse();
+    let len = info
+        .szName
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(info.szName.len());
+    let name = String::from_utf16_lossy(&info.szName[..len]);
+    DiscoveredDevice {
+        mac: format_bdaddr(mac),
+        name: (!name.is_empty()).then_some(name),
+        rssi: None,
+    }
+}
+
+/// Run an inquiry; with a `target`, stop listing results once it shows up.
+pub(super) fn discover(
+    duration: Duration,
+    adapter: Option<&str>,
+    target: Option<[u8; 6]>,
+    _cancel: &CancelToken,
+) -> Result<Vec<DiscoveredDevice>> {
+    reject_adapter(adapter)?;
+    let units =
+        (duration.as_millis() / INQUIRY_UNIT.as_millis()).clamp(1, MAX_INQUIRY_UNITS as u128) as u8;
+    let params = BLUETOOTH_DEVICE_SEARCH_PARAMS {
+        dwSize: mem::size_of::<BLUETOOTH_DEVICE_SEARCH_PARAMS>() as u32,
+        fReturnAuthenticated: 1,
+        fReturnRemembered: 0,
+        fReturnUnknown: 1,
+        fReturnConnected: 1,
+        fIssueInquiry: 1,
+        cTimeoutMultiplier: units,
+        // All local radios.
+        hRadio: std::ptr::null_mut(),
+    };
+    let mut info = BLUETOOTH_DEVICE_INFO {
+        dwSize: mem::size_of::<BLUETOOTH_DEVICE_INFO>() as u32,
+        ..Default::default()
+    };
+    debug!("starting inquiry: length={} x 1.28 s", units);
+
+    let find = unsafe { BluetoothFindFirstDevice(&params, &mut info) };
+    if find.is_null() {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(ERROR_NO_MORE_ITEMS) {
+            return Ok(Vec::new());
+        }
+        return Err(DriverError::Bluetooth(BluetoothError::Discovery(
+            err.to_string(),
+        )));
+    }
+
+    let target = target.map(format_bdaddr);
+    let mut devices = vec![to_discovered(&info)];
+    while target.as_ref() != devices.last().map(|d| &d.mac)
+        && unsafe { BluetoothFindNextDevice(find, &mut info) } != 0
+    {
+        devices.push(to_discovered(&info));
+    }
+    unsafe {
+        BluetoothFindDeviceClose(find);
+    }
+    Ok(devices)
+}
@@ -0,0 +1,671 @@
+//! Compact binary recording format (`.brec`).
+//!
+//! Text formats spend 20 to 40 bytes on a frame the device sends in at most 8.
+//! A `.brec` file stores frames in blocks. Each frame takes one byte for its
+//! sequence number and digital lines, a varint for the samples lost before it
+//! (zero unless the link dropped frames), and per channel a zigzag varint of
+//! the difference from the previous frame, which for biosignals mostly fits in
+//! one byte. With the `zstd` feature, blocks are also compressed, for 5 to 10
+//! times less storage than CSV.
+//!
+//! Layout, integers little-endian:
+//! - `BITREC`, the format version (1) and flags (bit 0: zstd blocks);
+//! - the header length (u32) and the [`RecordingInfo`] as JSON;
+//! - blocks: frame count (u32), payload length (u32), sample index of the
+//!   first frame (u64) and the payload;
+//! - once closed: a frame count and payload length of zero, then the total
+//!   frame count (u64).
+//!
+//! A block is written when full and on every flush, so a crash loses at most
+//! the frames since the last one, and [`recover`](crate::recover) drops a
+//! partial block. [`CompactReader`] reads files back; [`convert`] turns them
+//! into any other [`RecordFormat`].
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::bitalino::{ConnectionKind, Frame, MAX_ANALOG_CHANNELS};
+use crate::recording::{create_sink, ChannelPort, FrameSink, RecordFormat, RecordingInfo};
+use crate::recovery::Recovery;
+
+const MAGIC: &[u8; 6] = b"BITREC";
+const VERSION: u8 = 1;
+/// Header flag: block payloads are zstd frames.
+const FLAG_ZSTD: u8 = 1;
+/// Frames per block, unless a flush ends it sooner.
+const BLOCK_FRAMES: u32 = 4096;
+/// Frame count, payload length and first sample index.
+const BLOCK_HEADER_LEN: u64 = 16;
+
+/// How [`CompactWriter`] stores block payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Delta encoding only.
+    None,
+    /// Delta encoding, then zstd at this level (1-22); needs the `zstd`
+    /// feature.
+    Zstd(i32),
+}
+
+impl Default for Compression {
+    /// zstd level 3 when built with the `zstd` feature, otherwise none.
+    fn default() -> Self {
+        if cfg!(feature = "zstd") {
+            Self::Zstd(3)
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Writes frames in the compact binary format.
+pub struct CompactWriter<W: Write> {
+    out: W,
+    /// The file behind `out`, for [`FrameSink::sync`].
+    file: Option<File>,
+    compression: Compression,
+    n_channels: usize,
+    /// Encoded frames of the block being filled.
+    block: Vec<u8>,
+    block_frames: u32,
+    block_first: u64,
+    prev_sample: u64,
+    prev_values: [i32; MAX_ANALOG_CHANNELS],
+    frames_written: u64,
+}
+
+impl CompactWriter<BufWriter<File>> {
+    /// Create (or truncate) `path` and write the header.
+    pub fn create(
+        path: impl AsRef<Path>,
+        info: &RecordingInfo,
+        compression: Compression,
+    ) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let mut writer = Self::new(BufWriter::new(file.try_clone()?), info, compression)?;
+        writer.file = Some(file);
+        Ok(writer)
+    }
+}
+
+impl<W: Write> CompactWriter<W> {
+    /// Write the header for the acquisition described by `info` into `out`.
+    ///
+    /// Fails with [`io::ErrorKind::Unsupported`] for zstd compression in a
+    /// build without the `zstd` feature.
+    pub fn new(mut out: W, info: &RecordingInfo, compression: Compression) -> io::Result<Self> {
+        let flags = match compression {
+            Compression::None => 0,
+            Compression::Zstd(_) if cfg!(feature = "zstd") => FLAG_ZSTD,
+            Compression::Zstd(_) => return Err(no_zstd()),
+        };
+        let header = serde_json::to_vec(&info_to_json(info))?;
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION, flags])?;
+        out.write_all(&(header.len() as u32).to_le_bytes())?;
+        out.write_all(&header)?;
+        Ok(Self {
+            out,
+            file: None,
+            compression,
+            n_channels: info.channels.len().min(MAX_ANALOG_CHANNELS),
+            block: Vec::new(),
+            block_frames: 0,
+            block_first: 0,
+            prev_sample: 0,
+            prev_values: [0; MAX_ANALOG_CHANNELS],
+            frames_written: 0,
+        })
+    }
+
+    /// Encode `frames`, writing out blocks as they fill up.
+    pub fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        for frame in frames {
+            if self.block_frames == 0 {
+                self.block_first = frame.sample_index;
+                self.prev_sample = frame.sample_index.wrapping_sub(1);
+                self.prev_values = [0; MAX_ANALOG_CHANNELS];
+            }
+            let digital = frame
+                .digital
+                .iter()
+                .enumerate()
+                .fold(0u8, |bits, (i, &level)| bits | (level & 1) << (4 + i));
+            self.block.push(frame.seq & 0x0F | digital);
+            let skipped = frame
+                .sample_index
+                .wrapping_sub(self.prev_sample)
+                .wrapping_sub(1) as i64;
+            put_varint(
+                &mut self.block,
+                u128::from(zigzag(skipped)) << 1 | u128::from(frame.filled),
+            );
+            self.prev_sample = frame.sample_index;
+            for (prev, &value) in self.prev_values[..self.n_channels]
+                .iter_mut()
+                .zip(&frame.analog)
+            {
+                put_varint(
+                    &mut self.block,
+                    zigzag(i64::from(value) - i64::from(*prev)).into(),
+                );
+                *prev = i32::from(value);
+            }
+            self.block_frames += 1;
+            if self.block_frames == BLOCK_FRAMES {
+                self.write_block()?;
+            }
+        }
+        self.frames_written += frames.len() as u64;
+        Ok(())
+    }
+
+    /// Write out the block being filled, if it holds any frames.
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.block_frames == 0 {
+            return Ok(());
+        }
+        let payload = match self.compression {
+            Compression::None => std::mem::take(&mut self.block),
+            Compression::Zstd(level) => {
+                let compressed = zstd_compress(&self.block, level)?;
+                self.block.clear();
+                compressed
+            }
+        };
+        self.out.write_all(&self.block_frames.to_le_bytes())?;
+        self.out.write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.out.write_all(&self.block_first.to_le_bytes())?;
+        self.out.write_all(&payload)?;
+        self.block_frames = 0;
+        Ok(())
+    }
+
+    /// Write out the current block and flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.out.flush()
+    }
+
+    /// Write the end marker and return the underlying writer.
+    #[allow(dead_code)]
+    pub fn finish(mut self) -> io::Result<W> {
+        FrameSink::close(&mut self)?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> FrameSink for CompactWriter<W> {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        CompactWriter::write_frames(self, frames)
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        CompactWriter::flush(self)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        CompactWriter::flush(self)?;
+        self.file.as_ref().map_or(Ok(()), File::sync_data)
+    }
+
+    /// The file ends with the end marker and the total frame count.
+    fn close(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.out.write_all(&[0; 8])?;
+        self.out.write_all(&self.frames_written.to_le_bytes())?;
+        self.sync()
+    }
+}
+
+/// Reads frames back from the compact binary format.
+pub struct CompactReader<R: Read> {
+    input: R,
+    info: RecordingInfo,
+    zstd: bool,
+    /// Total frames from the end marker, once reached.
+    total: Option<u64>,
+}
+
+impl CompactReader<BufReader<File>> {
+    /// Open `path` and read its header.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CompactReader<R> {
+    /// Read the header from `input`.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let (flags, info, _) = read_header(&mut input)?;
+        Ok(Self {
+            input,
+            info,
+            zstd: flags & FLAG_ZSTD != 0,
+            total: None,
+        })
+    }
+
+    /// Acquisition settings recorded in the header.
+    pub fn info(&self) -> &RecordingInfo {
+        &self.info
+    }
+
+    /// Whether the end marker of a closed file has been reached.
+    #[allow(dead_code)]
+    pub fn is_closed(&self) -> bool {
+        self.total.is_some()
+    }
+
+    /// Frames of the next block; `None` at the end marker or at the end of a
+    /// file that was never closed.
+    ///
+    /// # Errors
+    /// Fails on a truncated block or, without the `zstd` feature, on a
+    /// compressed file.
+    pub fn next_block(&mut self) -> io::Result<Option<Vec<Frame>>> {
+        if self.total.is_some() {
+            return Ok(None);
+        }
+        let mut header = [0u8; BLOCK_HEADER_LEN as usize];
+        match self.input.read(&mut header[..1])? {
+            0 => return Ok(None),
+            _ => self.input.read_exact(&mut header[1..])?,
+        }
+        let count = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let len = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let first = u64::from_le_bytes(header[8..].try_into().unwrap());
+        if count == 0 {
+            self.total = Some(first);
+            return Ok(None);
+        }
+        let mut payload = vec![0; len as usize];
+        self.input.read_exact(&mut payload)?;
+        if self.zstd {
+            // Each frame encodes to at most 1 + 19 + 3 bytes per channel.
+            let capacity = count as usize * (20 + 3 * MAX_ANALOG_CHANNELS);
+            payload = zstd_decompress(&payload, capacity)?;
+        }
+        decode_block(&payload, count, first, &self.info.channels).map(Some)
+    }
+}
+
+/// Decode the `count` frames of a block payload.
+fn decode_block(payload: &[u8], count: u32, first: u64, channels: &[u8]) -> io::Result<Vec<Frame>> {
+    let n_channels = channels.len().min(MAX_ANALOG_CHANNELS);
+    let mut bytes = payload.iter().copied();
+    let mut frames = Vec::with_capacity(count as usize);
+    let mut prev_sample = first.wrapping_sub(1);
+    let mut values = [0u16; MAX_ANALOG_CHANNELS];
+    for _ in 0..count {
+        let head = bytes.next().ok_or_else(|| truncated("block"))?;
+        let gap = get_varint(&mut bytes)?;
+        prev_sample = prev_sample
+            .wrapping_add(unzigzag((gap >> 1) as u64) as u64)
+            .wrapping_add(1);
+        for value in &mut values[..n_channels] {
+            let delta = unzigzag(get_varint(&mut bytes)? as u64);
+            *value = (i64::from(*value) + delta) as u16;
+        }
+        let mut frame = Frame::new(
+            head & 0x0F,
+            [0, 1, 2, 3].map(|i| head >> (4 + i) & 1),
+            &values[..n_channels],
+        );
+        frame.sample_index = prev_sample;
+        frame.filled = gap & 1 == 1;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Convert the compact recording `input` into `output` in `format`, closing
+/// it as a complete file. Returns the frames converted.
+///
+/// # Errors
+/// Fails if either file cannot be opened, or `input` is damaged before its
+/// last complete block.
+pub fn convert(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    format: RecordFormat,
+) -> io::Result<u64> {
+    let mut reader = CompactReader::open(input)?;
+    let mut sink = create_sink(output, format, reader.info())?;
+    while let Some(frames) = reader.next_block()? {
+        sink.write_frames(&frames)?;
+    }
+    sink.close()?;
+    Ok(sink.frames_written())
+}
+
+/// [`recover`](crate::recover) for a compact recording: drop a partial block
+/// and add the end marker.
+pub(crate) fn recover(path: &Path) -> io::Result<Recovery> {
+    let mut input = BufReader::new(File::open(path)?);
+    let (_, _, header_len) = read_header(&mut input)?;
+    let len = input.get_ref().metadata()?.len();
+    let mut valid_len = header_len;
+    let mut frames = 0;
+    let mut ended = false;
+    let mut header = [0u8; BLOCK_HEADER_LEN as usize];
+    while valid_len + BLOCK_HEADER_LEN <= len {
+        input.read_exact(&mut header)?;
+        let count = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let payload = u64::from(u32::from_le_bytes(header[4..8].try_into().unwrap()));
+        if count == 0 {
+            ended = payload == 0 && u64::from_le_bytes(header[8..].try_into().unwrap()) == frames;
+            if ended {
+                valid_len += BLOCK_HEADER_LEN;
+            }
+            break;
+        }
+        if valid_len + BLOCK_HEADER_LEN + payload > len {
+            break;
+        }
+        input.seek(SeekFrom::Current(payload as i64))?;
+        valid_len += BLOCK_HEADER_LEN + payload;
+        frames += u64::from(count);
+    }
+
+    let removed_bytes = len - valid_len;
+    let closed = ended && removed_bytes == 0;
+    if !closed {
+        let mut file = OpenOptions::new().append(true).open(path)?;
+        file.set_len(valid_len)?;
+        if !ended {
+            file.write_all(&[0; 8])?;
+            file.write_all(&frames.to_le_bytes())?;
+        }
+        file.sync_all()?;
+    }
+    Ok(Recovery {
+        closed,
+        frames,
+        removed_bytes,
+    })
+}
+
+/// Read the magic, flags and JSON header; returns the flags, the recording
+/// info and the header's length in bytes.
+fn read_header(input: &mut impl Read) -> io::Result<(u8, RecordingInfo, u64)> {
+    let mut start = [0u8; 12];
+    input.read_exact(&mut start)?;
+    if &start[..6] != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a compact BITalino recording",
+        ));
+    }
+    if start[6] != VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported compact recording version {}", start[6]),
+        ));
+    }
+    let flags = start[7];
+    if flags & FLAG_ZSTD != 0 && !cfg!(feature = "zstd") {
+        return Err(no_zstd());
+    }
+    let len = u32::from_le_bytes(start[8..].try_into().unwrap());
+    let mut json = vec![0; len as usize];
+    input.read_exact(&mut json)?;
+    let info = serde_json::from_slice::<Value>(&json)
+        .ok()
+        .and_then(|header| info_from_json(&header))
+        .ok_or_else(|| truncated("header"))?;
+    Ok((flags, info, start.len() as u64 + u64::from(len)))
+}
+
+fn info_to_json(info: &RecordingInfo) -> Value {
+    json!({
+        "mac": info.mac,
+        "sampling_rate": info.sampling_rate,
+        "channels": info.channels,
+        "sensors": info.sensors,
+        "ports": info.ports.iter().map(|port| port.as_ref().map(|port| json!({
+            "label": port.label,
+            "description": port.description,
+        }))).collect::<Vec<_>>(),
+        "is_bitalino2": info.is_bitalino2,
+        "firmware_version": info.firmware_version,
+        "connection": info.connection.as_str(),
+        "started_ns": info.started.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64,
+    })
+}
+
+fn info_from_json(header: &Value) -> Option<RecordingInfo> {
+    let strings = |key: &str| -> Option<Vec<String>> {
+        header[key]
+            .as_array()?
+            .iter()
+            .map(|s| s.as_str().map(String::from))
+            .collect()
+    };
+    Some(RecordingInfo {
+        mac: header["mac"].as_str()?.to_string(),
+        sampling_rate: header["sampling_rate"].as_u64()? as u16,
+        channels: header["channels"]
+            .as_array()?
+            .iter()
+            .map(|c| c.as_u64().map(|c| c as u8))
+            .collect::<Option<_>>()?,
+        sensors: strings("sensors")?,
+        ports: header["ports"]
+            .as_array()?
+            .iter()
+            .map(|port| {
+                Some(ChannelPort {
+                    label: port["label"].as_str()?.to_string(),
+                    description: port["description"].as_str().map(String::from),
+                })
+            })
+            .collect(),
+        is_bitalino2: header["is_bitalino2"].as_bool()?,
+        firmware_version: header["firmware_version"].as_str().map(String::from),
+        connection: match header["connection"].as_str()? {
+            "bluetooth" => ConnectionKind::Bluetooth,
+            "serial" => ConnectionKind::Serial,
+            "tcp" => ConnectionKind::Tcp,
+            "replay" => ConnectionKind::Replay,
+            _ => ConnectionKind::Custom,
+        },
+        started: UNIX_EPOCH + Duration::from_nanos(header["started_ns"].as_u64()?),
+    })
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// LEB128: seven bits per byte, least significant first.
+fn put_varint(out: &mut Vec<u8>, mut value: u128) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn get_varint(bytes: &mut impl Iterator<Item = u8>) -> io::Result<u128> {
+    let mut value = 0u128;
+    for shift in (0..128).step_by(7) {
+        let byte = bytes.next().ok_or_else(|| truncated("block"))?;
+        value |= u128::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(truncated("varint"))
+}
+
+fn truncated(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt compact recording {what}"),
+    )
+}
+
+fn no_zstd() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "zstd-compressed recordings need a build with the `zstd` feature",
+    )
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_compress(data: &[u8], level: i32) -> io::Result<Vec<u8>> {
+    zstd::bulk::compress(data, level)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_compress(_data: &[u8], _level: i32) -> io::Result<Vec<u8>> {
+    Err(no_zstd())
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decompress(data: &[u8], capacity: usize) -> io::Result<Vec<u8>> {
+    zstd::bulk::decompress(data, capacity)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decompress(_data: &[u8], _capacity: usize) -> io::Result<Vec<u8>> {
+    Err(no_zstd())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::FrameWriter;
+
+    fn info() -> RecordingInfo {
+        RecordingInfo {
+            mac: "20:16:10:00:3D:4F".into(),
+            sampling_rate: 1000,
+            channels: vec![0, 1, 4],
+            sensors: vec!["ECG".into(), "EDA".into()],
+            ports: vec![
+                None,
+                Some(ChannelPort {
+                    label: "P2".into(),
+                    description: None,
+                }),
+            ],
+            is_bitalino2: true,
+            firmware_version: Some("BITalino_v5.2".into()),
+            connection: ConnectionKind::Serial,
+            started: UNIX_EPOCH + Duration::from_millis(1_709_211_909_250),
+        }
+    }
+
+    /// Ten seconds of slowly varying signals with a few lost frames.
+    fn frames() -> Vec<Frame> {
+        (0..10_000u64)
+            .filter(|n| !(5_000..5_003).contains(n))
+            .map(|n| {
+                let t = n as f64 / 1000.0;
+                let ecg = 512.0 + 200.0 * (t * 7.5).sin() + (n * 7919 % 5) as f64;
+                let eda = 300.0 + 20.0 * (t * 0.3).sin();
+                let mut frame = Frame::new(
+                    (n % 16) as u8,
+                    [u8::from(n % 1000 < 500), 0, 1, 0],
+                    &[ecg as u16, eda as u16, (n / 100 % 64) as u16],
+                );
+                frame.sample_index = n;
+                frame.filled = n == 42;
+                frame
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_frames_and_header() {
+        let frames = frames();
+        let mut writer = CompactWriter::new(Vec::new(), &info(), Compression::None).unwrap();
+        for batch in frames.chunks(100) {
+            writer.write_frames(batch).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let mut reader = CompactReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.info().sensors, info().sensors);
+        assert_eq!(reader.info().ports, info().ports);
+        assert_eq!(reader.info().started, info().started);
+        assert_eq!(reader.info().connection, ConnectionKind::Serial);
+        let mut decoded = Vec::new();
+        while let Some(block) = reader.next_block().unwrap() {
+            decoded.extend(block);
+        }
+        assert!(reader.is_closed());
+        assert_eq!(decoded, frames);
+
+        let mut csv = FrameWriter::new(Vec::new(), RecordFormat::Csv, &info()).unwrap();
+        csv.write_frames(&frames).unwrap();
+        let csv = csv.finish().unwrap();
+        assert!(
+            bytes.len() * 3 < csv.len(),
+            "{} bytes vs {} of CSV",
+            bytes.len(),
+            csv.len()
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_blocks_shrink_further() {
+        let frames = frames();
+        let encode = |compression| {
+            let mut writer = CompactWriter::new(Vec::new(), &info(), compression).unwrap();
+            writer.write_frames(&frames).unwrap();
+            writer.finish().unwrap()
+        };
+        let plain = encode(Compression::None);
+        let zstd = encode(Compression::Zstd(3));
+        assert!(zstd.len() < plain.len());
+        let mut reader = CompactReader::new(zstd.as_slice()).unwrap();
+        let mut decoded = Vec::new();
+        while let Some(block) = reader.next_block().unwrap() {
+            decoded.extend(block);
+        }
+        assert_eq!(decoded, frames);
+    }
+
+    #[test]
+    fn recovers_and_converts_a_cut_file() {
+        let dir = std::env::temp_dir().join(format!("bitalino_compact_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rec.brec");
+        let frames = frames();
+        let mut writer = CompactWriter::create(&path, &info(), Compression::None).unwrap();
+        writer.write_frames(&frames[..5000]).unwrap();
+        writer.flush().unwrap();
+        // Still in the block being filled when the writer goes away.
+        writer.write_frames(&frames[5000..6000]).unwrap();
+        drop(writer);
+        // A crash in the middle of the next block.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[7, 0, 0, 0, 99, 0]).unwrap();
+
+        let recovery = crate::recovery::recover(&path).unwrap();
+        assert_eq!((recovery.closed, recovery.frames), (false, 5000));
+        assert!(crate::recovery::recover(&path).unwrap().closed);
+
+        let csv = dir.join("rec.csv");
+        assert_eq!(convert(&path, &csv, RecordFormat::Csv).unwrap(), 5000);
+        let text = std::fs::read_to_string(&csv).unwrap();
+        assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count(), 5001);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+//! Connection and acquisition profiles loaded from TOML or JSON files.
+//!
+//! A [`Profile`] keeps everything needed to reach a device and run a session
+//! in one place, so lab setups can be shared as files instead of long command
+//! lines:
+//!
+//! ```toml
+//! mac = "20:16:10:00:3D:4F"
+//! rate = 1000
+//! channels = [0, 1, 2]
+//!
+//! [sensors]
+//! A1 = "ECG"
+//! A2 = "EDA"
+//!
+//! [retry]
+//! max_retries = 5
+//! ```
+//!
+//! BITalino Core and Plugged assemblies wire sensor blocks to labelled ports
+//! rather than fixed inputs. A `[ports]` table names each port, the analog
+//! input it reaches and the block plugged into it; the port labels and
+//! descriptions are carried into recordings:
+//!
+//! ```toml
+//! channels = [0, 1]
+//!
+//! [ports.P1]
+//! channel = 0
+//! sensor = "EMG"
+//! description = "left forearm"
+//!
+//! [ports.P2]
+//! channel = 1
+//! sensor = "ECG"
+//! ```
+//!
+//! Every field is optional; missing ones take the driver defaults.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::bluetooth::BluetoothConnector;
+use crate::presets::Preset;
+
+/// Device connection and acquisition settings.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Bluetooth MAC address of the device.
+    pub mac: Option<String>,
+    /// Pairing PIN (ignored by the RFCOMM backends; kept for pairing tools).
+    pub pin: String,
+    /// RFCOMM channel; `None` takes the one the registry remembers for the
+    /// device, else the BITalino default (1).
+    pub channel: Option<u8>,
+    /// Local Bluetooth adapter (`"hci1"` or its address); `None` for the default.
+    pub adapter: Option<String>,
+    /// Connect without scanning first; set to `false` for devices that may be
+    /// out of range or not yet seen by the adapter.
+    pub known_device: bool,
+    /// Upper bound on scans for the device, in milliseconds.
+    pub scan_timeout_ms: u64,
+    /// Record connections in the [`DeviceRegistry`](crate::DeviceRegistry)
+    /// and skip the scan for devices it already knows. Off by default, so
+    /// library connections leave the registry file alone; the CLI turns it
+    /// on unless `--no-registry` is passed.
+    pub remember: bool,
+    /// Sampling rate in Hz (1, 10, 100 or 1000).
+    pub rate: u16,
+    /// Analog channels to acquire (0-5).
+    pub channels: Vec<u8>,
+    /// Sensor attached to each analog input, keyed by label (`"A1"`..`"A6"`).
+    pub sensors: BTreeMap<String, String>,
+    /// Ports of a Core or Plugged assembly, keyed by their physical label.
+    pub ports: BTreeMap<String, Port>,
+    /// Connection retry policy.
+    pub retry: RetryPolicy,
+}
+
+/// A port of a BITalino Core or Plugged assembly and the block plugged into it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Port {
+    /// Analog input the port is wired to (0-5).
+    pub channel: u8,
+    /// Sensor block plugged into the port (e.g. `"ECG"`); `None` for raw values.
+    #[serde(default)]
+    pub sensor: Option<String>,
+    /// Free-form notes such as electrode placement.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// How hard the connector tries to open the RFCOMM link.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Maximum connection attempts.
+    pub max_retries: u32,
+    /// Base delay between attempts in milliseconds (doubled on each retry).
+    pub retry_delay_ms: u64,
+    /// Socket I/O timeout in milliseconds.
+    pub io_timeout_ms: u64,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            mac: None,
+            pin: "1234".into(),
+            channel: None,
+            adapter: None,
+            known_device: true,
+            scan_timeout_ms: BluetoothConnector::default().scan_timeout.as_millis() as u64,
+            remember: false,
+            rate: 1000,
+            channels: (0..6).collect(),
+            sensors: BTreeMap::new(),
+            ports: BTreeMap::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        let connector = BluetoothConnector::default();
+        Self {
+            max_retries: connector.max_retries,
+            retry_delay_ms: connector.retry_delay.as_millis() as u64,
+            io_timeout_ms: connector.io_timeout.as_millis() as u64,
+        }
+    }
+}
+
+impl Profile {
+    /// Load a profile; `.json` files are parsed as JSON, anything else as TOML.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read profile {}", path.display()))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if is_json {
+            Self::from_json_str(&text)
+        } else {
+            Self::from_toml_str(&text)
+        }
+        .with_context(|| format!("Invalid profile {}", path.display()))
+    }
+
+    /// Parse a TOML profile.
+    pub fn from_toml_str(text: &str) -> Result<Self> {
+        toml::from_str::<Self>(text)?.validated()
+    }
+
+    /// Parse a JSON profile.
+    pub fn from_json_str(text: &str) -> Result<Self> {
+        serde_json::from_str::<Self>(text)?.validated()
+    }
+
+    /// Reject port maps wiring a port to a missing input or two ports to
+    /// the same input.
+    fn validated(self) -> Result<Self> {
+        let mut wired: BTreeMap<u8, &str> = BTreeMap::new();
+        for (label, port) in &self.ports {
+            if port.channel > 5 {
+                anyhow::bail!(
+                    "Port {label} is wired to channel {}; channels are 0-5",
+                    port.channel
+                );
+            }
+            if let Some(other) = wired.insert(port.channel, label) {
+                anyhow::bail!(
+                    "Ports {other} and {label} are both wired to A{}",
+                    port.channel + 1
+                );
+            }
+        }
+        Ok(self)
+    }
+
+    /// Connector configured with this profile's channel (1 when unset),
+    /// adapter and retry policy.
+    pub fn connector(&self) -> BluetoothConnector {
+        let builder = BluetoothConnector::builder()
+            .channel(self.channel.unwrap_or(1))
+            .io_timeout(Duration::from_millis(self.retry.io_timeout_ms))
+            .retries(self.retry.max_retries)
+            .retry_delay(Duration::from_millis(self.retry.retry_delay_ms))
+            .known_device(self.known_device)
+            .scan_timeout(Duration::from_millis(self.scan_timeout_ms));
+        match &self.adapter {
+            Some(adapter) => builder.adapter(adapter.clone()),
+            None => builder,
+        }
+        .build()
+    }
+
+    /// Take the channels, rate and sensor names of `preset`, replacing the
+    /// profile's.
+    pub fn apply_preset(&mut self, preset: &Preset) {
+        self.rate = preset.rate as u16;
+        self.channels = preset.channels();
+        self.sensors = preset
+            .inputs
+            .iter()
+            .map(|&(channel, sensor)| (format!("A{}", channel + 1), sensor.name().to_string()))
+            .collect();
+    }
+
+    /// Sensor name for analog channel `channel` (0-5), from `[sensors]` or
+    /// else from the port wired to it, if mapped.
+    pub fn sensor(&self, channel: u8) -> Option<&str> {
+        self.sensors
+            .get(&format!("A{}", channel + 1))
+            .or_else(|| self.port_on(channel)?.1.sensor.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Analog channel (0-5) the port labelled `label` is wired to.
+    #[allow(dead_code)]
+    pub fn port_channel(&self, label: &str) -> Option<u8> {
+        self.ports.get(label).map(|port| port.channel)
+    }
+
+    /// Label and settings of the port wired to analog channel `channel`.
+    pub fn port_on(&self, channel: u8) -> Option<(&str, &Port)> {
+        self.ports
+            .iter()
+            .find(|(_, port)| port.channel == channel)
+            .map(|(label, port)| (label.as_str(), port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_and_json_profiles_fill_defaults() {
+        let toml = Profile::from_toml_str(
+            r#"
+            mac = "20:16:10:00:3D:4F"
+            channels = [0, 1]
+
+            [sensors]
+            A2 = "EDA"
+
+            [retry]
+            max_retries = 5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(toml.mac.as_deref(), Some("20:16:10:00:3D:4F"));
+        assert_eq!(toml.rate, 1000);
+        assert_eq!(toml.channels, vec![0, 1]);
+        assert_eq!(toml.sensor(1), Some("EDA"));
+        assert_eq!(toml.sensor(0), None);
+        assert_eq!(toml.connector().max_retries, 5);
+        // Unset: the registry's channel (or 1), and the registry left alone.
+        assert_eq!(toml.channel, None);
+        assert_eq!(toml.connector().channel, 1);
+        assert!(!toml.remember);
+        assert_eq!(
+            toml.retry.io_timeout_ms,
+            RetryPolicy::default().io_timeout_ms
+        );
+
+        let json = Profile::from_json_str(
+            r#"{"mac": "20:16:10:00:3D:4F", "channels": [0, 1], "sensors": {"A2": "EDA"},
+                "retry": {"max_retries": 5}}"#,
+        )
+        .unwrap();
+        assert_eq!(json, toml);
+
+        assert!(Profile::from_toml_str("rate = 1000\nchanels = [0]").is_err());
+    }
+
+    #[test]
+    fn ports_map_labels_to_channels() {
+        let profile = Profile::from_toml_str(
+            r#"
+            [sensors]
+            A1 = "EMG"
+
+            [ports.P1]
+            channel = 0
+            sensor = "ECG"
+
+            [ports.P2]
+            channel = 3
+            sensor = "EEG"
+            description = "Fp1"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(profile.port_channel("P2"), Some(3));
+        assert_eq!(profile.port_channel("P3"), None);
+        let (label, port) = profile.port_on(3).unwrap();
+        assert_eq!((label, port.description.as_deref()), ("P2", Some("Fp1")));
+        // [sensors] entries take precedence over the port's block.
+        assert_eq!(profile.sensor(0), Some("EMG"));
+        assert_eq!(profile.sensor(3), Some("EEG"));
+
+        let clash = "[ports.P1]\nchannel = 1\n[ports.P2]\nchannel = 1";
+        assert!(Profile::from_toml_str(clash).is_err());
+        assert!(Profile::from_json_str(r#"{"ports": {"P1": {"channel": 6}}}"#).is_err());
+    }
+}
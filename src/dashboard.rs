@@ -0,0 +1,149 @@
+//! Terminal dashboard for `bitalino monitor`.
+//!
+//! Plain ANSI escape sequences, no TUI framework: each refresh moves the
+//! cursor home and redraws every line, which is enough for a few updates per
+//! second in any modern terminal.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+
+use crate::bitalino::Frame;
+
+/// Sparkline glyphs from lowest to highest.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Enter the dashboard: clear the screen and hide the cursor.
+pub const ENTER: &str = "\x1b[2J\x1b[?25l";
+/// Leave the dashboard: show the cursor again.
+pub const LEAVE: &str = "\x1b[?25h\n";
+
+/// Link and device counters shown under the sparklines.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinkStats {
+    pub frames: u64,
+    pub lost: u64,
+    pub crc_errors: usize,
+    pub sequence_gaps: usize,
+    pub frames_per_sec: f64,
+    pub bytes_per_sec: f64,
+    /// Battery voltage and whether it is extrapolated.
+    pub battery: Option<(f32, bool)>,
+}
+
+/// Rolling per-channel history rendered as sparklines.
+pub struct Dashboard {
+    title: String,
+    channels: Vec<u8>,
+    history: Vec<VecDeque<u16>>,
+    capacity: usize,
+    width: usize,
+}
+
+impl Dashboard {
+    /// Keep `capacity` samples per channel, drawn `width` glyphs wide.
+    pub fn new(title: String, channels: Vec<u8>, capacity: usize, width: usize) -> Self {
+        Self {
+            title,
+            history: vec![VecDeque::with_capacity(capacity); channels.len()],
+            channels,
+            capacity: capacity.max(1),
+            width: width.max(1),
+        }
+    }
+
+    /// Append frames to the channel histories, dropping the oldest samples.
+    pub fn push(&mut self, frames: &[Frame]) {
+        for frame in frames {
+            for (history, &value) in self.history.iter_mut().zip(frame.analog_values()) {
+                if history.len() == self.capacity {
+                    history.pop_front();
+                }
+                history.push_back(value);
+            }
+        }
+    }
+
+    /// Render the whole screen, starting with a cursor-home sequence.
+    pub fn render(&self, stats: &LinkStats) -> String {
+        let mut out = String::from("\x1b[H");
+        let mut line = |text: String| {
+            let _ = writeln!(out, "{text}\x1b[K");
+        };
+
+        line(format!("{}  (Ctrl-C to quit)", self.title));
+        line(String::new());
+        for (channel, history) in self.channels.iter().zip(&self.history) {
+            let samples: Vec<u16> = history.iter().copied().collect();
+            let (min, max) = samples
+                .iter()
+                .fold((u16::MAX, 0), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+            line(format!(
+                "A{}  {:<width$}  now {:>4}  min {:>4}  max {:>4}",
+                channel + 1,
+                sparkline(&samples, self.width),
+                samples.last().copied().unwrap_or(0),
+                if samples.is_empty() { 0 } else { min },
+                max,
+                width = self.width
+            ));
+        }
+        line(String::new());
+        line(format!(
+            "battery   {}",
+            match stats.battery {
+                Some((volts, true)) => format!("~{volts:.2} V (estimated)"),
+                Some((volts, false)) => format!("{volts:.2} V"),
+                None => "n/a".into(),
+            }
+        ));
+        line(format!(
+            "frames    {}  lost {}  seq gaps {}  crc errors {}",
+            stats.frames, stats.lost, stats.sequence_gaps, stats.crc_errors
+        ));
+        line(format!(
+            "rate      {:.0} frames/s  {:.1} kB/s",
+            stats.frames_per_sec,
+            stats.bytes_per_sec / 1000.0
+        ));
+        out
+    }
+}
+
+/// Downsample `samples` into `width` buckets (bucket means) scaled between the
+/// series' min and max. Fewer samples than `width` give a shorter line.
+fn sparkline(samples: &[u16], width: usize) -> String {
+    if samples.is_empty() {
+        return String::new();
+    }
+    let buckets = width.min(samples.len());
+    let means: Vec<f64> = (0..buckets)
+        .map(|i| {
+            let chunk = &samples[i * samples.len() / buckets..(i + 1) * samples.len() / buckets];
+            chunk.iter().map(|&v| v as f64).sum::<f64>() / chunk.len() as f64
+        })
+        .collect();
+    let lo = means.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = means.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    means
+        .iter()
+        .map(|&m| {
+            if hi > lo {
+                BARS[(((m - lo) / (hi - lo)) * (BARS.len() - 1) as f64).round() as usize]
+            } else {
+                BARS[0]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_scales_bucket_means() {
+        assert_eq!(sparkline(&[0, 0, 10, 10, 20, 20], 3), "▁▅█");
+        assert_eq!(sparkline(&[5, 5], 8), "▁▁");
+        assert_eq!(sparkline(&[], 8), "");
+    }
+}
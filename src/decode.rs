@@ -0,0 +1,319 @@
+//! Frame decoding: CRC check and field unpacking.
+//!
+//! [`decode_frame`] unpacks one frame at a time and is the reference for the
+//! wire format. The read path takes whole runs of frames out of its read
+//! buffer and hands them to [`decode_frames`] instead, which decodes a run in
+//! one tight loop: each frame is loaded as a single word, fields come out
+//! with shifts and masks computed once per run rather than per field, and the
+//! CRC comes from independent table lookups. The loop body has no branches
+//! that depend on the data, which lets the compiler unroll it.
+//!
+//! [`encode_frame`] packs frames the way the firmware does; the tests use it to
+//! check both decoders against every layout. `benches/decode.rs` compares the
+//! two decoders for every channel count.
+
+use crate::bitalino::{Frame, MAX_ANALOG_CHANNELS};
+
+/// Size in bytes of a frame carrying `n_channels` analog channels.
+///
+/// Frames hold 4 sequence bits, 4 CRC bits, 4 digital bits, and 10 bits per
+/// analog channel; with 5 or 6 channels the last two are sent with 6 bits only.
+pub fn frame_size(n_channels: usize) -> usize {
+    if n_channels == 0 {
+        return 0;
+    }
+    let bits = if n_channels <= 4 {
+        12 + 10 * n_channels
+    } else {
+        52 + 6 * (n_channels - 4)
+    };
+    bits.div_ceil(8)
+}
+
+/// Longest frame: all 6 analog channels active.
+const MAX_FRAME_SIZE: usize = 8;
+
+/// Feed `byte` to the 4-bit CRC (polynomial x^4 + x + 1) closing each frame,
+/// most significant bit first, as the firmware does.
+const fn crc_byte(mut crc: u8, byte: u8) -> u8 {
+    let mut bit = 8;
+    while bit > 0 {
+        bit -= 1;
+        crc <<= 1;
+        if crc & 0x10 != 0 {
+            crc ^= 0x03;
+        }
+        crc = (crc ^ ((byte >> bit) & 1)) & 0x0F;
+    }
+    crc
+}
+
+/// `CRC_TABLE[k][byte]`: CRC of `byte` followed by `k` zero bytes.
+///
+/// The CRC starts from zero, so it is linear: the CRC of a frame is the XOR
+/// of each byte's entry for the number of bytes after it. Unlike feeding the
+/// bytes one after the other, the lookups do not depend on each other.
+const CRC_TABLE: [[u8; 256]; MAX_FRAME_SIZE] = {
+    let mut table = [[0u8; 256]; MAX_FRAME_SIZE];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = crc_byte(0, byte as u8);
+        let mut k = 0;
+        while k < MAX_FRAME_SIZE {
+            table[k][byte] = crc;
+            crc = crc_byte(crc, 0);
+            k += 1;
+        }
+        byte += 1;
+    }
+    table
+};
+
+/// CRC of a frame with its own nibble zeroed, for frames of at most
+/// `MAX_FRAME_SIZE` bytes.
+fn crc(frame: &[u8]) -> u8 {
+    let tail = frame.len() - 1;
+    frame[..tail].iter().enumerate().fold(
+        CRC_TABLE[0][(frame[tail] & 0xF0) as usize],
+        |crc, (i, &byte)| crc ^ CRC_TABLE[tail - i][byte as usize],
+    )
+}
+
+/// Whether the CRC in the low nibble of the last byte matches the frame.
+///
+/// The CRC covers the whole frame with its own nibble zeroed. Slices longer
+/// than a frame never match.
+pub fn check_crc(frame: &[u8]) -> bool {
+    match frame.last() {
+        Some(&last) if frame.len() <= MAX_FRAME_SIZE => crc(frame) == last & 0x0F,
+        _ => false,
+    }
+}
+
+/// Pack a frame into the bytes a device sends for it, CRC included.
+///
+/// The inverse of [`decode_frame`], for tests and simulated devices. Fields
+/// are truncated to their width on the wire; `sample_index` and `filled` are
+/// not transmitted.
+#[allow(dead_code)]
+pub fn encode_frame(frame: &Frame) -> Vec<u8> {
+    let n_channels = (frame.n_channels as usize).clamp(1, MAX_ANALOG_CHANNELS);
+    let layout = Layout::new(n_channels);
+    let mut word = (frame.seq as u64 & 0x0F) << 60;
+    for (i, &bit) in frame.digital.iter().enumerate() {
+        word |= (bit as u64 & 1) << (55 - i);
+    }
+    for (&value, &(shift, mask)) in frame.analog.iter().zip(&layout.analog) {
+        word |= ((value & mask) as u64) << shift;
+    }
+    let mut bytes = (word >> layout.align).to_le_bytes()[..layout.frame_size].to_vec();
+    let last = layout.frame_size - 1;
+    bytes[last] |= crc(&bytes);
+    bytes
+}
+
+/// Unpack one frame of `frame_size(n_channels)` bytes.
+///
+/// The firmware packs fields MSB-first starting from the *last* byte: sequence
+/// (4 bits), CRC (4), I1, I2, O1, O2 (1 each), then A1..A4 (10 bits each) and,
+/// for 5-6 channel layouts, A5/A6 (6 bits each). Unused low bits of the first
+/// byte are padding. Reading the bytes in reverse therefore yields one
+/// contiguous bit stream for every layout, matching the offsets used by the
+/// official BITalino APIs. The CRC is not checked.
+pub fn decode_frame(data: &[u8], n_channels: usize) -> Frame {
+    debug_assert!((1..=MAX_FRAME_SIZE).contains(&data.len()));
+    debug_assert_eq!(data.len(), frame_size(n_channels));
+
+    // Frames are at most 8 bytes, so the whole stream fits in one word,
+    // left-aligned so the next field always sits in the top bits.
+    let mut word = data
+        .iter()
+        .rev()
+        .fold(0u64, |acc, &byte| (acc << 8) | byte as u64)
+        << (64 - 8 * data.len());
+    let mut take = |width: u32| -> u16 {
+        let value = (word >> (64 - width)) as u16;
+        word <<= width;
+        value
+    };
+
+    let seq = take(4) as u8;
+    let _crc = take(4);
+    let digital = [take(1) as u8, take(1) as u8, take(1) as u8, take(1) as u8];
+    let mut analog = [0u16; MAX_ANALOG_CHANNELS];
+    for (i, value) in analog.iter_mut().take(n_channels).enumerate() {
+        *value = take(if i < 4 { 10 } else { 6 });
+    }
+
+    Frame {
+        seq,
+        digital,
+        analog,
+        n_channels: n_channels as u8,
+        sample_index: 0,
+        filled: false,
+    }
+}
+
+/// Where each analog value of one frame layout sits in the loaded word.
+struct Layout {
+    frame_size: usize,
+    /// Shift left-aligning the frame's bytes in the word
+    align: u32,
+    /// Shift and mask of each analog value; zero masks for absent channels
+    analog: [(u32, u16); MAX_ANALOG_CHANNELS],
+}
+
+impl Layout {
+    fn new(n_channels: usize) -> Self {
+        let frame_size = frame_size(n_channels);
+        let mut analog = [(0, 0); MAX_ANALOG_CHANNELS];
+        // Sequence, CRC and digital bits take the top 12 bits.
+        let mut shift = 64 - 12;
+        for (i, field) in analog.iter_mut().take(n_channels).enumerate() {
+            let width = if i < 4 { 10 } else { 6 };
+            shift -= width;
+            *field = (shift, (1 << width) - 1);
+        }
+        Self {
+            frame_size,
+            align: 64 - 8 * frame_size as u32,
+            analog,
+        }
+    }
+}
+
+/// Decode every complete frame of `bytes`, laid out back to back, appending
+/// each with the result of its CRC check to `out`.
+///
+/// Trailing bytes short of a whole frame are ignored. Frames are decoded as
+/// by [`decode_frame`].
+pub fn decode_frames(bytes: &[u8], n_channels: usize, out: &mut Vec<(Frame, bool)>) {
+    if n_channels == 0 || n_channels > MAX_ANALOG_CHANNELS {
+        return;
+    }
+    let layout = Layout::new(n_channels);
+    let chunks = bytes.chunks_exact(layout.frame_size);
+    out.reserve(chunks.len());
+    for chunk in chunks {
+        // Little-endian puts the last byte on top, like reading it in reverse.
+        let mut padded = [0u8; MAX_FRAME_SIZE];
+        padded[..layout.frame_size].copy_from_slice(chunk);
+        let word = u64::from_le_bytes(padded) << layout.align;
+
+        let mut analog = [0u16; MAX_ANALOG_CHANNELS];
+        for (value, &(shift, mask)) in analog.iter_mut().zip(&layout.analog) {
+            *value = (word >> shift) as u16 & mask;
+        }
+        let bit = |n: u32| ((word >> n) & 1) as u8;
+        let frame = Frame {
+            seq: (word >> 60) as u8,
+            digital: [bit(55), bit(54), bit(53), bit(52)],
+            analog,
+            n_channels: n_channels as u8,
+            sample_index: 0,
+            filled: false,
+        };
+        out.push((frame, check_crc(chunk)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn batch_decoder_matches_the_per_frame_decoder() {
+        // Pseudo-random bytes: about one frame in sixteen passes its CRC.
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let bytes: Vec<u8> = (0..8 * 256)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+
+        for n_channels in 1..=MAX_ANALOG_CHANNELS {
+            let size = frame_size(n_channels);
+            let mut batch = Vec::new();
+            decode_frames(&bytes[..bytes.len() - 1], n_channels, &mut batch);
+            assert_eq!(batch.len(), (bytes.len() - 1) / size);
+            for (chunk, &(frame, crc_ok)) in bytes.chunks_exact(size).zip(&batch) {
+                assert_eq!(frame, decode_frame(chunk, n_channels));
+                assert_eq!(crc_ok, bitwise_crc_ok(chunk));
+            }
+            assert!(batch.iter().any(|&(_, ok)| ok));
+        }
+    }
+
+    fn arb_frame() -> impl Strategy<Value = Frame> {
+        (
+            1..=MAX_ANALOG_CHANNELS,
+            0..16u8,
+            prop::array::uniform4(0..=1u8),
+            prop::array::uniform6(0..1024u16),
+        )
+            .prop_map(|(n_channels, seq, digital, mut analog)| {
+                for value in analog.iter_mut().skip(4) {
+                    *value &= 0x3F;
+                }
+                Frame::new(seq, digital, &analog[..n_channels])
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn encoded_frames_decode_to_themselves(frame in arb_frame()) {
+            let n_channels = frame.n_channels as usize;
+            let bytes = encode_frame(&frame);
+            prop_assert_eq!(bytes.len(), frame_size(n_channels));
+            prop_assert!(check_crc(&bytes));
+            prop_assert!(bitwise_crc_ok(&bytes));
+            prop_assert_eq!(decode_frame(&bytes, n_channels), frame);
+        }
+
+        #[test]
+        fn streams_of_encoded_frames_batch_decode(
+            n_channels in 1..=MAX_ANALOG_CHANNELS,
+            frames in prop::collection::vec(arb_frame(), 0..64),
+        ) {
+            // Same layout for the whole stream, as during one acquisition.
+            let frames: Vec<Frame> = frames
+                .into_iter()
+                .map(|f| Frame::new(f.seq, f.digital, &f.analog[..n_channels]))
+                .collect();
+            let bytes: Vec<u8> = frames.iter().flat_map(encode_frame).collect();
+            let mut decoded = Vec::new();
+            decode_frames(&bytes, n_channels, &mut decoded);
+            prop_assert_eq!(decoded, frames.into_iter().map(|f| (f, true)).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn single_bit_errors_fail_the_crc(frame in arb_frame(), bit in 0..64usize) {
+            let mut bytes = encode_frame(&frame);
+            let bit = bit % (8 * bytes.len());
+            bytes[bit / 8] ^= 1 << (bit % 8);
+            prop_assert!(!check_crc(&bytes));
+        }
+    }
+
+    /// The CRC computed bit by bit, as the firmware does.
+    fn bitwise_crc_ok(frame: &[u8]) -> bool {
+        let last = frame.len() - 1;
+        let mut crc = 0u8;
+        for (i, &byte) in frame.iter().enumerate() {
+            let byte = if i == last { byte & 0xF0 } else { byte };
+            for bit in (0..8).rev() {
+                crc <<= 1;
+                if crc & 0x10 != 0 {
+                    crc ^= 0x03;
+                }
+                crc ^= (byte >> bit) & 0x01;
+            }
+        }
+        frame[last] & 0x0F == crc & 0x0F
+    }
+}
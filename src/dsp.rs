@@ -0,0 +1,30 @@
+//! Signal processing stages for the acquisition pipeline.
+//!
+//! A [`Stage`] transforms every batch a subscriber receives, so consumers that
+//! only need a reduced or conditioned stream (a 100 Hz live plot, a network
+//! sink) can subscribe through one while the raw stream is still recorded
+//! untouched. [`Decimator`] reduces the rate and [`FilterBank`] applies notch
+//! and Butterworth filters per channel:
+//!
+//! ```no_run
+//! # use bitalino_rs::{Bitalino, SamplingRate};
+//! # use bitalino_rs::dsp::Decimator;
+//! # fn run(device: &mut Bitalino) -> anyhow::Result<()> {
+//! device.start(1000, vec![0, 1])?;
+//! let plot = device.subscribe_through(Decimator::new(10).with_anti_alias(SamplingRate::Hz1000));
+//! # Ok(())
+//! # }
+//! ```
+
+mod decimate;
+pub mod eda;
+pub mod filters;
+pub mod hr;
+
+pub use crate::streaming::Stage;
+#[allow(unused_imports)]
+pub use decimate::Decimator;
+pub use eda::{EdaDecomposer, EdaLevel, Scr};
+#[allow(unused_imports)]
+pub use filters::{Biquad, Filter, FilterBank};
+pub use hr::{Beat, QrsDetector};
@@ -0,0 +1,128 @@
+//! Rate reduction for subscribers that do not need every sample.
+
+use std::f64::consts::FRAC_1_SQRT_2;
+
+use super::{Biquad, Stage};
+use crate::bitalino::{Frame, FrameBatch, SamplingRate};
+use crate::sensors::resolution_bits;
+
+/// Fraction of the output Nyquist frequency kept by the anti-alias filter.
+#[allow(dead_code)]
+const PASSBAND: f64 = 0.8;
+
+/// Keeps every `factor`-th frame, optionally low-passing the analog channels
+/// first so content above the new Nyquist frequency does not fold back.
+///
+/// Digital lines and sequence numbers are taken from the kept frame. The phase
+/// carries across batches, so the output is evenly spaced however the input is
+/// chunked; frames lost upstream are not compensated.
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    factor: usize,
+    phase: usize,
+    /// Anti-alias filter design, and one filter per channel once frames arrive.
+    anti_alias: Option<Biquad>,
+    filters: Vec<Biquad>,
+}
+
+#[allow(dead_code)]
+impl Decimator {
+    /// Keep one frame out of `factor` (at least 1).
+    pub fn new(factor: usize) -> Self {
+        Self {
+            factor: factor.max(1),
+            phase: 0,
+            anti_alias: None,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Low-pass the input (acquired at `rate`) below the output Nyquist
+    /// frequency before decimating.
+    pub fn with_anti_alias(mut self, rate: SamplingRate) -> Self {
+        let input_hz = rate as u16 as f64;
+        let cutoff = PASSBAND * input_hz / (2.0 * self.factor as f64);
+        self.anti_alias =
+            (self.factor > 1).then(|| Biquad::lowpass(input_hz, cutoff, FRAC_1_SQRT_2));
+        self
+    }
+
+    /// Decimation factor.
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Decimate a run of consecutive frames.
+    pub fn process_frames(&mut self, frames: &[Frame]) -> Vec<Frame> {
+        let mut out = Vec::with_capacity(frames.len() / self.factor + 1);
+        for frame in frames {
+            let mut frame = *frame;
+            if let Some(design) = self.anti_alias {
+                if self.filters.is_empty() {
+                    self.filters = frame
+                        .analog_values()
+                        .iter()
+                        .map(|&v| {
+                            let mut filter = design;
+                            filter.prime(v as f64);
+                            filter
+                        })
+                        .collect();
+                }
+                let n = frame.n_channels as usize;
+                for (position, (value, filter)) in frame.analog[..n]
+                    .iter_mut()
+                    .zip(&mut self.filters)
+                    .enumerate()
+                {
+                    let max = ((1u32 << resolution_bits(position)) - 1) as f64;
+                    *value = filter.process(*value as f64).round().clamp(0.0, max) as u16;
+                }
+            }
+            if self.phase == 0 {
+                out.push(frame);
+            }
+            self.phase = (self.phase + 1) % self.factor;
+        }
+        out
+    }
+}
+
+impl Stage for Decimator {
+    fn process(&mut self, batch: FrameBatch) -> FrameBatch {
+        let frames = self.process_frames(&batch.frames);
+        FrameBatch {
+            first_sample_index: frames
+                .first()
+                .map_or(batch.first_sample_index, |frame| frame.sample_index),
+            frames,
+            ..batch
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_every_nth_frame_across_batches() {
+        let frames: Vec<Frame> = (0..10u16)
+            .map(|i| Frame::new(i as u8, [0; 4], &[i * 100]))
+            .collect();
+        let mut plain = Decimator::new(3);
+        let mut kept = plain.process_frames(&frames[..4]);
+        kept.extend(plain.process_frames(&frames[4..]));
+        let values: Vec<u16> = kept.iter().map(|f| f.analog[0]).collect();
+        assert_eq!(values, [0, 300, 600, 900]);
+
+        // A 250 Hz square wave at 1000 Hz averages out after decimating by 10.
+        let square: Vec<Frame> = (0..400)
+            .map(|i| Frame::new(0, [0; 4], &[if i % 4 < 2 { 700 } else { 300 }]))
+            .collect();
+        let mut smoothed = Decimator::new(10).with_anti_alias(SamplingRate::Hz1000);
+        let out = smoothed.process_frames(&square);
+        assert_eq!(out.len(), 40);
+        assert!(out[20..].iter().all(|f| f.analog[0].abs_diff(500) < 25));
+    }
+}
@@ -0,0 +1,168 @@
+//! Online electrodermal activity (EDA) decomposition.
+//!
+//! [`EdaDecomposer`] converts raw EDA samples to microsiemens with the sensor
+//! transfer function, smooths them below 1 Hz, and splits the result into a
+//! slowly varying tonic level and the phasic remainder. Skin-conductance
+//! responses (SCRs) are detected on the smoothed signal as rises of at least
+//! 0.01 uS from a trough to the following peak.
+
+use super::Biquad;
+use crate::bitalino::SamplingRate;
+use crate::sensors::Sensor;
+
+/// Cutoff of the smoothing low-pass, in Hz.
+const SMOOTHING_HZ: f64 = 1.0;
+
+/// Cutoff of the tonic low-pass, in Hz.
+const TONIC_HZ: f64 = 0.05;
+
+/// Smallest trough-to-peak rise reported as an SCR, in microsiemens.
+const MIN_SCR_AMPLITUDE_US: f64 = 0.01;
+
+/// Reversal needed to confirm a trough or a peak, in microsiemens, so sensor
+/// noise does not split one response in two.
+const HYSTERESIS_US: f64 = 0.005;
+
+/// Decomposed skin conductance at one sample, in microsiemens.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EdaLevel {
+    /// Absolute sample index (since `start()`).
+    pub sample_index: u64,
+    /// Smoothed skin conductance.
+    pub conductance_us: f64,
+    /// Slowly varying baseline (skin conductance level).
+    pub tonic_us: f64,
+    /// Fast component, `conductance_us - tonic_us`.
+    pub phasic_us: f64,
+}
+
+/// A skin-conductance response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scr {
+    /// Sample index of the trough where the rise started.
+    pub onset_index: u64,
+    /// Sample index of the peak.
+    pub peak_index: u64,
+    /// Rise from onset to peak, in microsiemens.
+    pub amplitude_us: f64,
+    /// Time from onset to peak, in seconds.
+    pub latency_secs: f64,
+}
+
+/// Streaming EDA processor for one channel.
+///
+/// SCRs are reported once the signal has fallen [`HYSTERESIS_US`] below the
+/// peak, i.e. shortly after the peak rather than at the onset.
+#[derive(Debug, Clone)]
+pub struct EdaDecomposer {
+    rate: f64,
+    bits: u8,
+    smoothing: Biquad,
+    tonic: Biquad,
+    primed: bool,
+    /// Latest lowest point since the last peak: onset candidate. Ties move it
+    /// forward, so a flat stretch before a rise does not count as rise time.
+    trough: (u64, f64),
+    /// Highest point of the rise in progress.
+    peak: Option<(u64, f64)>,
+}
+
+impl EdaDecomposer {
+    /// Processor for an EDA sensor sampled at `rate` with a `bits`-bit ADC.
+    pub fn new(rate: SamplingRate, bits: u8) -> Self {
+        let hz = rate as u16 as f64;
+        // Keep the cutoffs below Nyquist at the 1 Hz and 10 Hz rates.
+        let low_pass = |cutoff: f64| {
+            Biquad::lowpass(hz, cutoff.min(0.4 * hz), std::f64::consts::FRAC_1_SQRT_2)
+        };
+        Self {
+            rate: hz,
+            bits,
+            smoothing: low_pass(SMOOTHING_HZ),
+            tonic: low_pass(TONIC_HZ),
+            primed: false,
+            trough: (0, 0.0),
+            peak: None,
+        }
+    }
+
+    /// Feed one raw EDA sample, calling `emit` when it confirms an SCR, and
+    /// return the decomposed level at this sample.
+    pub fn feed(&mut self, raw: u16, sample_index: u64, mut emit: impl FnMut(Scr)) -> EdaLevel {
+        let us = Sensor::Eda.to_physical(raw, self.bits);
+        let (conductance, tonic) = if self.primed {
+            let conductance = self.smoothing.process(us);
+            (conductance, self.tonic.process(conductance))
+        } else {
+            // Start at the current level instead of rising from zero.
+            self.primed = true;
+            self.trough = (sample_index, us);
+            (self.smoothing.prime(us), self.tonic.prime(us))
+        };
+
+        match self.peak {
+            None if conductance <= self.trough.1 => self.trough = (sample_index, conductance),
+            None if conductance - self.trough.1 > HYSTERESIS_US => {
+                self.peak = Some((sample_index, conductance));
+            }
+            None => {}
+            Some((_, top)) if conductance > top => self.peak = Some((sample_index, conductance)),
+            Some((peak_index, top)) if top - conductance > HYSTERESIS_US => {
+                let (onset_index, bottom) = self.trough;
+                if top - bottom >= MIN_SCR_AMPLITUDE_US {
+                    emit(Scr {
+                        onset_index,
+                        peak_index,
+                        amplitude_us: top - bottom,
+                        latency_secs: (peak_index - onset_index) as f64 / self.rate,
+                    });
+                }
+                self.peak = None;
+                self.trough = (sample_index, conductance);
+            }
+            Some(_) => {}
+        }
+
+        EdaLevel {
+            sample_index,
+            conductance_us: conductance,
+            tonic_us: tonic,
+            phasic_us: conductance - tonic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_responses_from_the_tonic_level() {
+        // 10 s at 100 Hz: flat 5 uS with one 0.5 uS response rising from 3 s
+        // to 5 s and decaying afterwards.
+        let counts_per_us = 1024.0 * 0.132 / 3.3;
+        let mut eda = EdaDecomposer::new(SamplingRate::Hz100, 10);
+        let mut scrs = Vec::new();
+        let mut last = EdaLevel::default();
+        for n in 0..1000u64 {
+            let t = n as f64 / 100.0;
+            let us = 5.0
+                + match t {
+                    t if t < 3.0 => 0.0,
+                    t if t < 5.0 => 0.25 * (1.0 - (std::f64::consts::PI * (t - 3.0) / 2.0).cos()),
+                    t => 0.5 * (-(t - 5.0) / 2.0).exp(),
+                };
+            let raw = (us * counts_per_us).round() as u16;
+            last = eda.feed(raw, n, |scr| scrs.push(scr));
+            if n == 0 {
+                assert!((last.tonic_us - 5.0).abs() < 0.05 && last.phasic_us.abs() < 1e-9);
+            }
+        }
+
+        assert_eq!(scrs.len(), 1, "{scrs:?}");
+        let scr = scrs[0];
+        assert!((scr.amplitude_us - 0.5).abs() < 0.05, "{scr:?}");
+        assert!((scr.latency_secs - 2.0).abs() < 0.3, "{scr:?}");
+        assert!(last.tonic_us > 5.0 && last.tonic_us < 5.3, "{last:?}");
+    }
+}
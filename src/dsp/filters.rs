@@ -0,0 +1,395 @@
+//! IIR filters for conditioning analog channels while streaming.
+//!
+//! [`Filter`] describes a response (mains notch or Butterworth low/high/band
+//! pass) independently of the sampling rate; [`FilterBank`] designs a cascade
+//! of [`Biquad`] sections for the acquisition rate and runs it on every analog
+//! channel, with separate filter state per channel.
+
+use std::f64::consts::PI;
+
+use anyhow::{bail, Result};
+
+use super::Stage;
+use crate::bitalino::{Frame, FrameBatch, SamplingRate};
+use crate::sensors::resolution_bits;
+
+/// Quality factor of the mains notch: about 1.7 Hz wide at 50 Hz.
+const NOTCH_Q: f64 = 30.0;
+
+/// Highest Butterworth order accepted.
+const MAX_ORDER: u8 = 8;
+
+/// Second-order IIR section in transposed direct form II.
+///
+/// Coefficients follow the RBJ audio EQ cookbook and are normalised so that
+/// `a0 == 1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    /// Low-pass at `cutoff_hz` with quality factor `q` (`FRAC_1_SQRT_2` gives a
+    /// Butterworth response).
+    pub fn lowpass(sample_rate: f64, cutoff_hz: f64, q: f64) -> Self {
+        let (cos, alpha) = Self::angular(sample_rate, cutoff_hz, q);
+        let b1 = 1.0 - cos;
+        Self::normalised(b1 / 2.0, b1, b1 / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// High-pass at `cutoff_hz` with quality factor `q`.
+    pub fn highpass(sample_rate: f64, cutoff_hz: f64, q: f64) -> Self {
+        let (cos, alpha) = Self::angular(sample_rate, cutoff_hz, q);
+        let b0 = (1.0 + cos) / 2.0;
+        Self::normalised(b0, -2.0 * b0, b0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// Band-reject centred on `freq_hz`; higher `q` gives a narrower notch.
+    pub fn notch(sample_rate: f64, freq_hz: f64, q: f64) -> Self {
+        let (cos, alpha) = Self::angular(sample_rate, freq_hz, q);
+        Self::normalised(1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// First-order low-pass (bilinear transform), for odd filter orders.
+    fn lowpass_first_order(sample_rate: f64, cutoff_hz: f64) -> Self {
+        let k = (PI * cutoff_hz / sample_rate).tan();
+        Self::normalised(k, k, 0.0, k + 1.0, k - 1.0, 0.0)
+    }
+
+    /// First-order high-pass (bilinear transform), for odd filter orders.
+    fn highpass_first_order(sample_rate: f64, cutoff_hz: f64) -> Self {
+        let k = (PI * cutoff_hz / sample_rate).tan();
+        Self::normalised(1.0, -1.0, 0.0, k + 1.0, k - 1.0, 0.0)
+    }
+
+    /// `cos(w0)` and `alpha` for a centre/cutoff frequency.
+    fn angular(sample_rate: f64, freq_hz: f64, q: f64) -> (f64, f64) {
+        let w0 = 2.0 * PI * freq_hz / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * q))
+    }
+
+    fn normalised(b0: f64, b1: f64, b2: f64, a0: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Filter one sample.
+    #[inline]
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+
+    /// Gain at 0 Hz.
+    fn dc_gain(&self) -> f64 {
+        (self.b0 + self.b1 + self.b2) / (1.0 + self.a1 + self.a2)
+    }
+
+    /// Set the internal state as if `x` had been the input forever, so a
+    /// signal sitting at an ADC offset does not start with a step response.
+    /// Returns the matching steady-state output.
+    pub fn prime(&mut self, x: f64) -> f64 {
+        let y = x * self.dc_gain();
+        self.z2 = self.b2 * x - self.a2 * y;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        y
+    }
+
+    /// Clear the filter memory.
+    #[allow(dead_code)]
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// A filter response, designed for the sampling rate by [`FilterBank`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Narrow notch for mains interference, at 50 or 60 Hz.
+    #[allow(dead_code)]
+    Notch { freq_hz: f64 },
+    /// Butterworth low-pass of the given order.
+    LowPass { cutoff_hz: f64, order: u8 },
+    /// Butterworth high-pass of the given order, e.g. to remove baseline wander.
+    HighPass { cutoff_hz: f64, order: u8 },
+    /// Butterworth high-pass at `low_hz` followed by a low-pass at `high_hz`,
+    /// each of the given order.
+    BandPass {
+        low_hz: f64,
+        high_hz: f64,
+        order: u8,
+    },
+}
+
+impl Filter {
+    /// Biquad cascade implementing this filter at `sample_rate` Hz.
+    ///
+    /// # Errors
+    /// Fails if a frequency is not strictly between 0 Hz and the Nyquist
+    /// frequency, the band is empty, or the order is not in `1..=8`.
+    pub fn sections(&self, sample_rate: f64) -> Result<Vec<Biquad>> {
+        let check_freq = |hz: f64| -> Result<()> {
+            if !(hz > 0.0 && hz < sample_rate / 2.0) {
+                bail!(
+                    "Filter frequency {} Hz must be between 0 and {} Hz at {} Hz sampling",
+                    hz,
+                    sample_rate / 2.0,
+                    sample_rate
+                );
+            }
+            Ok(())
+        };
+        let check_order = |order: u8| -> Result<()> {
+            if !(1..=MAX_ORDER).contains(&order) {
+                bail!("Filter order {} must be between 1 and {}", order, MAX_ORDER);
+            }
+            Ok(())
+        };
+
+        match *self {
+            Filter::Notch { freq_hz } => {
+                check_freq(freq_hz)?;
+                Ok(vec![Biquad::notch(sample_rate, freq_hz, NOTCH_Q)])
+            }
+            Filter::LowPass { cutoff_hz, order } => {
+                check_freq(cutoff_hz)?;
+                check_order(order)?;
+                Ok(butterworth(
+                    order,
+                    |q| Biquad::lowpass(sample_rate, cutoff_hz, q),
+                    || Biquad::lowpass_first_order(sample_rate, cutoff_hz),
+                ))
+            }
+            Filter::HighPass { cutoff_hz, order } => {
+                check_freq(cutoff_hz)?;
+                check_order(order)?;
+                Ok(butterworth(
+                    order,
+                    |q| Biquad::highpass(sample_rate, cutoff_hz, q),
+                    || Biquad::highpass_first_order(sample_rate, cutoff_hz),
+                ))
+            }
+            Filter::BandPass {
+                low_hz,
+                high_hz,
+                order,
+            } => {
+                if low_hz >= high_hz {
+                    bail!(
+                        "Band-pass low edge {} Hz must be below the high edge {} Hz",
+                        low_hz,
+                        high_hz
+                    );
+                }
+                let mut sections = Filter::HighPass {
+                    cutoff_hz: low_hz,
+                    order,
+                }
+                .sections(sample_rate)?;
+                sections.extend(
+                    Filter::LowPass {
+                        cutoff_hz: high_hz,
+                        order,
+                    }
+                    .sections(sample_rate)?,
+                );
+                Ok(sections)
+            }
+        }
+    }
+}
+
+/// Split an `order`-pole Butterworth response into second-order sections
+/// (plus one first-order section for odd orders).
+fn butterworth(
+    order: u8,
+    second_order: impl Fn(f64) -> Biquad,
+    first_order: impl Fn() -> Biquad,
+) -> Vec<Biquad> {
+    let n = order as f64;
+    let odd = order % 2;
+    let mut sections: Vec<Biquad> = (0..order / 2)
+        .map(|k| {
+            // Angle of the k-th conjugate pole pair from the negative real axis.
+            let theta = PI * (2 * k + 1 + odd) as f64 / (2.0 * n);
+            second_order(1.0 / (2.0 * theta.cos()))
+        })
+        .collect();
+    if odd == 1 {
+        sections.push(first_order());
+    }
+    sections
+}
+
+/// Runs a cascade of filters on every analog channel of a stream.
+///
+/// Each channel is filtered around its ADC mid-scale and the result
+/// re-centred there, so high-pass and band-pass output stays in the unsigned
+/// range of the frame (512 for 10-bit channels, 32 for 6-bit ones) and is
+/// rounded and clamped to the channel's resolution. Filter state is primed
+/// from the first frame, so the signal does not start with a step response.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct FilterBank {
+    design: Vec<Biquad>,
+    /// One cascade per analog channel, created from the first frame.
+    channels: Vec<Vec<Biquad>>,
+}
+
+#[allow(dead_code)]
+impl FilterBank {
+    /// Design `filters`, applied in order, for acquisition at `rate`.
+    ///
+    /// # Errors
+    /// Fails if any filter is invalid at this rate (see [`Filter::sections`]).
+    pub fn new(rate: SamplingRate, filters: &[Filter]) -> Result<Self> {
+        let sample_rate = rate as u16 as f64;
+        let mut design = Vec::new();
+        for filter in filters {
+            design.extend(filter.sections(sample_rate)?);
+        }
+        Ok(Self {
+            design,
+            channels: Vec::new(),
+        })
+    }
+
+    /// Filter a run of consecutive frames in place.
+    pub fn process_frames(&mut self, frames: &mut [Frame]) {
+        if self.design.is_empty() {
+            return;
+        }
+        for frame in frames {
+            let n = frame.n_channels as usize;
+            if self.channels.is_empty() {
+                self.channels = frame.analog[..n]
+                    .iter()
+                    .enumerate()
+                    .map(|(position, &value)| {
+                        let mut x = value as f64 - mid_scale(position);
+                        let mut cascade = self.design.clone();
+                        for section in &mut cascade {
+                            x = section.prime(x);
+                        }
+                        cascade
+                    })
+                    .collect();
+            }
+            for (position, (value, cascade)) in frame.analog[..n]
+                .iter_mut()
+                .zip(&mut self.channels)
+                .enumerate()
+            {
+                let mid = mid_scale(position);
+                let y = cascade
+                    .iter_mut()
+                    .fold(*value as f64 - mid, |x, section| section.process(x));
+                *value = (y + mid).round().clamp(0.0, 2.0 * mid - 1.0) as u16;
+            }
+        }
+    }
+}
+
+impl Stage for FilterBank {
+    fn process(&mut self, mut batch: FrameBatch) -> FrameBatch {
+        self.process_frames(&mut batch.frames);
+        batch
+    }
+}
+
+/// Half the ADC range of the analog value at `position`.
+#[allow(dead_code)]
+fn mid_scale(position: usize) -> f64 {
+    (1u32 << (resolution_bits(position) - 1)) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    /// Peak output amplitude for a unit sine at `freq_hz`, after settling.
+    fn gain(mut cascade: Vec<Biquad>, rate: f64, freq_hz: f64) -> f64 {
+        (0..8000)
+            .map(|n| {
+                let x = (2.0 * PI * freq_hz * n as f64 / rate).sin();
+                cascade.iter_mut().fold(x, |x, s| s.process(x))
+            })
+            .skip(6000)
+            .fold(0.0, |peak: f64, y| peak.max(y.abs()))
+    }
+
+    #[test]
+    fn lowpass_passes_dc_and_attenuates_above_cutoff() {
+        let filter = Biquad::lowpass(1000.0, 40.0, FRAC_1_SQRT_2);
+        assert!((gain(vec![filter], 1000.0, 40.0) - FRAC_1_SQRT_2).abs() < 0.01);
+        assert!(gain(vec![filter], 1000.0, 400.0) < 0.02);
+
+        let mut primed = filter;
+        primed.prime(512.0);
+        assert!((primed.process(512.0) - 512.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn designs_match_their_responses() {
+        let notch = Filter::Notch { freq_hz: 50.0 }.sections(1000.0).unwrap();
+        assert!(gain(notch.clone(), 1000.0, 50.0) < 0.01);
+        assert!(gain(notch, 1000.0, 10.0) > 0.99);
+
+        // Butterworth: -3 dB at the cutoff whatever the order.
+        for order in [1, 2, 3, 4] {
+            let hp = Filter::HighPass {
+                cutoff_hz: 20.0,
+                order,
+            };
+            let hp = hp.sections(1000.0).unwrap();
+            assert_eq!(hp.len(), (order as usize).div_ceil(2));
+            assert!((gain(hp, 1000.0, 20.0) - FRAC_1_SQRT_2).abs() < 0.01);
+        }
+
+        let band = Filter::BandPass {
+            low_hz: 5.0,
+            high_hz: 40.0,
+            order: 4,
+        };
+        let band = band.sections(1000.0).unwrap();
+        assert!(gain(band.clone(), 1000.0, 15.0) > 0.95);
+        assert!(gain(band.clone(), 1000.0, 1.0) < 0.01);
+        assert!(gain(band, 1000.0, 200.0) < 0.01);
+
+        assert!(Filter::Notch { freq_hz: 60.0 }.sections(100.0).is_err());
+        let flat = Filter::LowPass {
+            cutoff_hz: 10.0,
+            order: 0,
+        };
+        assert!(flat.sections(1000.0).is_err());
+    }
+
+    #[test]
+    fn bank_recentres_high_passed_channels() {
+        let filters = [Filter::HighPass {
+            cutoff_hz: 0.5,
+            order: 2,
+        }];
+        let mut bank = FilterBank::new(SamplingRate::Hz1000, &filters).unwrap();
+        let mut frames = vec![Frame::new(0, [0; 4], &[800, 100, 0, 0, 50, 10]); 3];
+        bank.process_frames(&mut frames);
+        assert_eq!(frames[2].analog_values(), [512, 512, 512, 512, 32, 32]);
+    }
+}
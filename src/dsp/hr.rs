@@ -0,0 +1,234 @@
+//! Heart-rate detection on a live ECG channel.
+//!
+//! [`QrsDetector`] follows Pan and Tompkins (1985): the ECG is band-passed to
+//! 5-15 Hz, differentiated, squared and integrated over a 150 ms window, and
+//! peaks of the integrated signal are classified as QRS complexes or noise
+//! against adaptive thresholds. The search-back for missed beats and the
+//! T-wave slope test are left out, which keeps the detector causal with a
+//! fixed latency of about one integration window.
+
+use std::collections::VecDeque;
+
+use super::{Biquad, Filter};
+use crate::bitalino::SamplingRate;
+
+/// Pass band of the QRS energy, in Hz.
+const QRS_BAND_HZ: (f64, f64) = (5.0, 15.0);
+
+/// Length of the moving-window integrator, in seconds.
+const INTEGRATION_SECS: f64 = 0.150;
+
+/// Minimum spacing between two beats, in seconds (300 BPM).
+const REFRACTORY_SECS: f64 = 0.200;
+
+/// Signal used to initialise the thresholds before beats are reported.
+const LEARNING_SECS: f64 = 2.0;
+
+/// Lowest sampling rate the band-pass can be designed for.
+pub const MIN_QRS_RATE: SamplingRate = SamplingRate::Hz100;
+
+/// One detected heartbeat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beat {
+    /// Absolute sample index (since `start()`) of the R peak.
+    pub sample_index: u64,
+    /// Interval since the previous beat in seconds; `None` for the first beat.
+    pub rr_secs: Option<f64>,
+    /// Instantaneous heart rate, `60 / rr_secs`.
+    pub bpm: Option<f64>,
+}
+
+/// Streaming Pan-Tompkins QRS detector for one ECG channel.
+///
+/// Feed every sample of the channel in order with its absolute sample index;
+/// beats are reported once the integrated QRS energy peaks, about 150 ms after
+/// the R wave. The first two seconds only train the thresholds.
+#[derive(Debug, Clone)]
+pub struct QrsDetector {
+    #[allow(dead_code)]
+    sampling_rate: SamplingRate,
+    rate: f64,
+    /// Band-pass cascade; `None` below [`MIN_QRS_RATE`].
+    bandpass: Option<Vec<Biquad>>,
+    /// Last four band-passed samples, newest first, for the derivative.
+    history: [f64; 4],
+    /// Integration window of (sample index, squared slope, |band-passed|).
+    window: VecDeque<(u64, f64, f64)>,
+    window_len: usize,
+    energy: f64,
+    /// The two previous integrator outputs, newest first.
+    previous: [f64; 2],
+    /// Samples fed since `reset()`.
+    seen: u64,
+    learning_samples: u64,
+    learning_peak: f64,
+    learning_sum: f64,
+    /// Running estimates of QRS and noise peak levels.
+    signal_level: f64,
+    noise_level: f64,
+    refractory: u64,
+    /// R peak of the last reported beat.
+    last_beat: Option<u64>,
+}
+
+impl QrsDetector {
+    /// Detector for an ECG acquired at `rate`. Below 100 Hz the QRS band
+    /// cannot be resolved and no beats are reported.
+    pub fn new(rate: SamplingRate) -> Self {
+        let hz = rate as u16 as f64;
+        let bandpass = (rate as u16 >= MIN_QRS_RATE as u16).then(|| {
+            Filter::BandPass {
+                low_hz: QRS_BAND_HZ.0,
+                high_hz: QRS_BAND_HZ.1,
+                order: 1,
+            }
+            .sections(hz)
+            .expect("QRS band is below the Nyquist frequency")
+        });
+        let window_len = ((INTEGRATION_SECS * hz).round() as usize).max(1);
+        Self {
+            sampling_rate: rate,
+            rate: hz,
+            bandpass,
+            history: [0.0; 4],
+            window: VecDeque::with_capacity(window_len + 1),
+            window_len,
+            energy: 0.0,
+            previous: [0.0; 2],
+            seen: 0,
+            learning_samples: (LEARNING_SECS * hz) as u64,
+            learning_peak: 0.0,
+            learning_sum: 0.0,
+            signal_level: 0.0,
+            noise_level: 0.0,
+            refractory: (REFRACTORY_SECS * hz) as u64,
+            last_beat: None,
+        }
+    }
+
+    /// Forget the signal seen so far, including the learned thresholds.
+    #[allow(dead_code)]
+    pub fn reset(&mut self) {
+        *self = Self::new(self.sampling_rate);
+    }
+
+    /// Whether the acquisition rate allows detection.
+    pub fn is_active(&self) -> bool {
+        self.bandpass.is_some()
+    }
+
+    /// Feed one raw ECG sample, calling `emit` when it completes a beat.
+    pub fn feed(&mut self, value: u16, sample_index: u64, mut emit: impl FnMut(Beat)) {
+        let Some(bandpass) = self.bandpass.as_mut() else {
+            return;
+        };
+        let x = value as f64;
+        let filtered = if self.seen == 0 {
+            // Start from the ADC offset instead of a step from zero.
+            let mut y = x;
+            for section in bandpass.iter_mut() {
+                y = section.prime(y);
+            }
+            self.history = [y; 4];
+            y
+        } else {
+            bandpass.iter_mut().fold(x, |y, s| s.process(y))
+        };
+        self.seen += 1;
+
+        // Five-point derivative, then squaring.
+        let [x1, _, x3, x4] = self.history;
+        let slope = (2.0 * filtered + x1 - x3 - 2.0 * x4) / 8.0;
+        self.history = [filtered, x1, self.history[1], x3];
+        let squared = slope * slope;
+
+        self.window
+            .push_back((sample_index, squared, filtered.abs()));
+        self.energy += squared;
+        if self.window.len() > self.window_len {
+            if let Some((_, old, _)) = self.window.pop_front() {
+                self.energy -= old;
+            }
+        }
+        let integrated = (self.energy / self.window_len as f64).max(0.0);
+
+        let [prev1, prev2] = self.previous;
+        self.previous = [integrated, prev1];
+        if self.seen <= self.learning_samples {
+            self.learning_peak = self.learning_peak.max(integrated);
+            self.learning_sum += integrated;
+            if self.seen == self.learning_samples {
+                self.signal_level = self.learning_peak;
+                self.noise_level = self.learning_sum / self.learning_samples as f64;
+            }
+            return;
+        }
+
+        // Local maximum of the integrated signal at the previous sample.
+        if !(prev1 > prev2 && prev1 >= integrated) {
+            return;
+        }
+        let threshold = self.noise_level + 0.25 * (self.signal_level - self.noise_level);
+        let r_peak = self
+            .window
+            .iter()
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .map_or(sample_index, |&(index, _, _)| index);
+        let refractory = self
+            .last_beat
+            .is_some_and(|last| r_peak.saturating_sub(last) < self.refractory);
+        if prev1 > threshold && !refractory {
+            self.signal_level = 0.125 * prev1 + 0.875 * self.signal_level;
+            let rr_secs = self
+                .last_beat
+                .map(|last| (r_peak - last) as f64 / self.rate);
+            self.last_beat = Some(r_peak);
+            emit(Beat {
+                sample_index: r_peak,
+                rr_secs,
+                bpm: rr_secs.map(|rr| 60.0 / rr),
+            });
+        } else {
+            self.noise_level = 0.125 * prev1 + 0.875 * self.noise_level;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Synthetic ECG at 1000 Hz: narrow R waves every `rr_ms`, broad T waves.
+    fn ecg(seconds: u64, rr_ms: u64) -> Vec<u16> {
+        (0..seconds * 1000)
+            .map(|n| {
+                let t = (n % rr_ms) as f64;
+                let r = 350.0 * (-((t - 100.0) / 8.0).powi(2)).exp();
+                let tw = 80.0 * (-((t - 380.0) / 50.0).powi(2)).exp();
+                (512.0 + r + tw) as u16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_r_waves_and_heart_rate() {
+        let mut detector = QrsDetector::new(SamplingRate::Hz1000);
+        let mut beats = Vec::new();
+        for (n, &value) in ecg(10, 800).iter().enumerate() {
+            detector.feed(value, n as u64, |beat| beats.push(beat));
+        }
+        // 12.5 beats in 10 s, minus the 2 s learning period.
+        assert_eq!(beats.len(), 10);
+        assert_eq!(beats[0].bpm, None);
+        for beat in &beats {
+            assert!((beat.sample_index % 800).abs_diff(100) <= 15, "{beat:?}");
+        }
+        for beat in &beats[1..] {
+            assert!((beat.bpm.unwrap() - 75.0).abs() < 2.0, "{beat:?}");
+        }
+
+        let mut slow = QrsDetector::new(SamplingRate::Hz10);
+        assert!(!slow.is_active());
+        slow.feed(700, 0, |_| panic!("no detection at 10 Hz"));
+    }
+}
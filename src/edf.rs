@@ -0,0 +1,585 @@
+//! European Data Format (EDF+) writer.
+//!
+//! [`EdfWriter`] writes the analog channels as EDF signals in one-second data
+//! records, read by EDFbrowser, MNE (`mne.io.read_raw_edf`), pyEDFlib and
+//! most sleep and EEG tools. The digital range is the ADC range of each
+//! channel and the physical range comes from the sensor transfer function, so
+//! those tools show ECG in mV, EDA in uS and so on.
+//!
+//! Files are continuous EDF+ (`EDF+C`): every record also carries an
+//! `EDF Annotations` signal. It holds the record's start time, to the
+//! millisecond of the recording start, and the session's `annotate()` markers
+//! once [`FrameSink::write_session`] has filled them in.
+//!
+//! EDF has no missing-sample marker: frames lost to sequence gaps repeat the
+//! previous values, and the last data record is padded the same way. The
+//! record count in the header is updated on every flush, so the file stays
+//! readable up to its last complete record.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use log::warn;
+
+use crate::bitalino::Frame;
+use crate::recording::{utc_fields, FrameSink, RecordingInfo};
+use crate::recovery::Recovery;
+use crate::sensors::{resolution_bits, Sensor};
+use crate::session::Session;
+
+/// Offset of the record count in the fixed header.
+const RECORDS_OFFSET: u64 = 236;
+
+/// Two-byte samples of the `EDF Annotations` signal in each data record.
+const ANNOTATION_SAMPLES: usize = 64;
+
+/// Bytes of annotations each data record can hold.
+const ANNOTATION_BYTES: usize = 2 * ANNOTATION_SAMPLES;
+
+/// Longest annotation label, leaving room in a record for its onset and the
+/// record's time-keeping entry.
+const MAX_LABEL: usize = ANNOTATION_BYTES - 40;
+
+/// Streams frames into an EDF file.
+pub struct EdfWriter<W: Write + Seek> {
+    out: W,
+    /// The file behind `out`, for [`FrameSink::sync`].
+    file: Option<File>,
+    /// Samples per channel in a data record: the sampling rate.
+    record_len: usize,
+    /// Samples of the record being filled, by channel.
+    record: Vec<Vec<i16>>,
+    last_values: Vec<i16>,
+    last_seq: Option<u8>,
+    records: u64,
+    frames_written: u64,
+    /// Bytes of the header, where the first data record starts.
+    header_len: u64,
+    /// Seconds from the header's whole-second start time to the first sample.
+    start_offset: f64,
+    /// Sample index of the first frame, the first sample in the file.
+    first_sample: Option<u64>,
+    /// Annotations not yet written, with the record they fall in.
+    annotations: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl EdfWriter<BufWriter<File>> {
+    /// Create (or truncate) `path` and write the header.
+    pub fn create(path: impl AsRef<Path>, info: &RecordingInfo) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let mut writer = Self::new(BufWriter::new(file.try_clone()?), info)?;
+        writer.file = Some(file);
+        Ok(writer)
+    }
+}
+
+impl<W: Write + Seek> EdfWriter<W> {
+    /// Write the header for the acquisition described by `info` into `out`.
+    pub fn new(mut out: W, info: &RecordingInfo) -> io::Result<Self> {
+        let header = header(info);
+        out.write_all(&header)?;
+        let n = info.channels.len();
+        let record_len = usize::from(info.sampling_rate.max(1));
+        Ok(Self {
+            out,
+            file: None,
+            record_len,
+            record: vec![Vec::with_capacity(record_len); n],
+            last_values: vec![0; n],
+            last_seq: None,
+            records: 0,
+            frames_written: 0,
+            header_len: header.len() as u64,
+            start_offset: f64::from(utc_fields(info.started).4) / 1e3,
+            first_sample: None,
+            annotations: VecDeque::new(),
+        })
+    }
+
+    /// Append one sample per channel for each frame, repeating the previous
+    /// values for frames missing from the sequence.
+    pub fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        for frame in frames {
+            self.first_sample.get_or_insert(frame.sample_index);
+            if let Some(last) = self.last_seq {
+                // Same 4-bit sequence arithmetic as the driver's gap detection.
+                let skipped = (frame.seq as u64 + 15 - last as u64) % 16;
+                for _ in 0..skipped {
+                    self.push_sample(None)?;
+                }
+            }
+            self.last_seq = Some(frame.seq);
+            self.push_sample(Some(frame.analog_values()))?;
+        }
+        self.frames_written += frames.len() as u64;
+        Ok(())
+    }
+
+    /// Add one sample per channel, `None` repeating the last ones, and write
+    /// the data record once full.
+    fn push_sample(&mut self, values: Option<&[u16]>) -> io::Result<()> {
+        if let Some(values) = values {
+            for (last, &value) in self.last_values.iter_mut().zip(values) {
+                *last = value as i16;
+            }
+        }
+        for (samples, &value) in self.record.iter_mut().zip(&self.last_values) {
+            samples.push(value);
+        }
+        if self.record.first().map_or(0, Vec::len) == self.record_len {
+            self.write_record()?;
+        }
+        Ok(())
+    }
+
+    fn write_record(&mut self) -> io::Result<()> {
+        for samples in &mut self.record {
+            for value in samples.drain(..) {
+                self.out.write_all(&value.to_le_bytes())?;
+            }
+        }
+        let annotations = self.annotation_record(self.records);
+        self.out.write_all(&annotations)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// `EDF Annotations` bytes of data record `record`: its time-keeping
+    /// entry, then as many pending annotations falling in it or earlier as
+    /// fit. Onsets are relative to the file start, so an annotation moved to
+    /// a later record keeps its time.
+    fn annotation_record(&mut self, record: u64) -> Vec<u8> {
+        let mut bytes = tal(self.start_offset + record as f64, None);
+        while let Some((at, tal)) = self.annotations.front() {
+            if *at > record || bytes.len() + tal.len() > ANNOTATION_BYTES {
+                break;
+            }
+            bytes.extend_from_slice(tal);
+            self.annotations.pop_front();
+        }
+        bytes.resize(ANNOTATION_BYTES, 0);
+        bytes
+    }
+
+    /// Bytes of one data record: every channel's samples and the annotations.
+    fn record_bytes(&self) -> u64 {
+        (2 * self.record_len * self.record.len() + ANNOTATION_BYTES) as u64
+    }
+
+    /// Flush and set the header's record count to the records written.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(RECORDS_OFFSET))?;
+        self.out
+            .write_all(field(&self.records.to_string(), 8).as_bytes())?;
+        self.out.seek(SeekFrom::Start(end))?;
+        self.out.flush()
+    }
+
+    /// Pad and write the last record, then return the underlying writer.
+    #[allow(dead_code)]
+    pub fn finish(mut self) -> io::Result<W> {
+        FrameSink::close(&mut self)?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write + Seek> FrameSink for EdfWriter<W> {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        EdfWriter::write_frames(self, frames)
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        EdfWriter::flush(self)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        EdfWriter::flush(self)?;
+        self.file.as_ref().map_or(Ok(()), File::sync_data)
+    }
+
+    /// The last, partial data record is padded with the last values.
+    fn close(&mut self) -> io::Result<()> {
+        while self.record.first().is_some_and(|r| !r.is_empty()) {
+            self.push_sample(None)?;
+        }
+        if !self.annotations.is_empty() {
+            warn!(
+                "{} annotations did not fit in the EDF annotation signal",
+                self.annotations.len()
+            );
+        }
+        self.sync()
+    }
+
+    /// Write the session's annotations into the `EDF Annotations` signal of
+    /// the records they fall in, rewriting records already on disk; those of
+    /// the unfinished record follow when it is written.
+    fn write_session(&mut self, session: &Session) -> io::Result<()> {
+        let Some(first) = self.first_sample else {
+            return Ok(());
+        };
+        let rate = self.record_len as u64;
+        let mut annotations: Vec<_> = session
+            .annotations
+            .iter()
+            .filter(|a| a.sample_index >= first)
+            .map(|a| {
+                let sample = a.sample_index - first;
+                let onset = self.start_offset + sample as f64 / rate as f64;
+                (sample / rate, tal(onset, Some(&a.label)))
+            })
+            .collect();
+        annotations.sort_by_key(|&(record, _)| record);
+        self.annotations.extend(annotations);
+
+        let end = self.out.stream_position()?;
+        let signal_bytes = self.record_bytes() - ANNOTATION_BYTES as u64;
+        for record in 0..self.records {
+            if !matches!(self.annotations.front(), Some(&(at, _)) if at <= record) {
+                continue;
+            }
+            let bytes = self.annotation_record(record);
+            let offset = self.header_len + record * self.record_bytes() + signal_bytes;
+            self.out.seek(SeekFrom::Start(offset))?;
+            self.out.write_all(&bytes)?;
+        }
+        self.out.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+}
+
+/// One time-stamped annotation list (TAL): `+onset`, then the label, or an
+/// empty one for a record's time-keeping entry.
+fn tal(onset: f64, label: Option<&str>) -> Vec<u8> {
+    let onset = format!("{onset:.6}");
+    let onset = onset.trim_end_matches('0').trim_end_matches('.');
+    let mut bytes = format!("+{onset}\x14").into_bytes();
+    if let Some(label) = label {
+        // Printable characters only: 0x14 and 0x00 delimit the entries.
+        let label: String = label
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        let mut end = label.len().min(MAX_LABEL);
+        while !label.is_char_boundary(end) {
+            end -= 1;
+        }
+        bytes.extend_from_slice(&label.as_bytes()[..end]);
+    }
+    bytes.extend_from_slice(b"\x14\0");
+    bytes
+}
+
+/// Fixed header and signal headers for `info`.
+fn header(info: &RecordingInfo) -> Vec<u8> {
+    let n = info.channels.len();
+    let (year, month, day, secs_of_day, _) = utc_fields(info.started);
+    let mut header = String::new();
+    header += &field("0", 8);
+    // EDF+ patient (code, sex, birthdate, name) and recording (start date,
+    // admin code, technician, equipment) subfields, `X` where unknown.
+    header += &field("X X X X", 80);
+    let startdate = format!("{day:02}-{}-{year}", MONTHS[month as usize - 1]);
+    header += &field(
+        &format!("Startdate {startdate} X X {}", equipment(info)),
+        80,
+    );
+    header += &format!("{:02}.{:02}.{:02}", day, month, year % 100);
+    header += &format!(
+        "{:02}.{:02}.{:02}",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    );
+    header += &field(&(256 * (n + 2)).to_string(), 8);
+    header += &field("EDF+C", 44);
+    // Unknown until the first flush.
+    header += &field("-1", 8);
+    header += &field("1", 8);
+    header += &field(&(n + 1).to_string(), 4);
+
+    let sensors: Vec<Sensor> = (0..n)
+        .map(|i| {
+            info.sensors
+                .get(i)
+                .map_or(Sensor::Raw, |s| Sensor::from_name(s))
+        })
+        .collect();
+    let max = |i: usize| (1u16 << resolution_bits(i)) - 1;
+    let signal_fields: [(usize, &dyn Fn(usize) -> String); 10] = [
+        (16, &|i| {
+            format!("A{} {}", info.channels[i] + 1, sensors[i].name())
+        }),
+        (80, &|i| {
+            info.ports
+                .get(i)
+                .and_then(Option::as_ref)
+                .map_or_else(String::new, |port| format!("port {}", port.label))
+        }),
+        (8, &|i| sensors[i].unit().to_string()),
+        (8, &|i| {
+            number(sensors[i].to_physical(0, resolution_bits(i)))
+        }),
+        (8, &|i| {
+            number(sensors[i].to_physical(max(i), resolution_bits(i)))
+        }),
+        (8, &|_| "0".to_string()),
+        (8, &|i| max(i).to_string()),
+        (80, &|_| String::new()),
+        (8, &|_| info.sampling_rate.max(1).to_string()),
+        (32, &|_| String::new()),
+    ];
+    let samples = ANNOTATION_SAMPLES.to_string();
+    let annotation_fields = [
+        "EDF Annotations",
+        "",
+        "",
+        "-32768",
+        "32767",
+        "-32768",
+        "32767",
+        "",
+        &samples,
+        "",
+    ];
+    for ((width, value), annotation) in signal_fields.into_iter().zip(annotation_fields) {
+        for i in 0..n {
+            header += &field(&value(i), width);
+        }
+        header += &field(annotation, width);
+    }
+    header.into_bytes()
+}
+
+/// Equipment subfield and the details after it: the model, MAC address,
+/// firmware and connection, each one space-free word.
+fn equipment(info: &RecordingInfo) -> String {
+    let model = if info.is_bitalino2 {
+        "BITalino_2.0"
+    } else {
+        "BITalino"
+    };
+    let mut words = vec![model.to_string(), info.mac.clone()];
+    words.extend(info.firmware_version.as_ref().map(|f| f.replace(' ', "_")));
+    words.push(info.connection.as_str().to_string());
+    words.join(" ")
+}
+
+/// Month abbreviations of the EDF+ `Startdate` subfield.
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// `text` as an EDF header field: printable ASCII, space-padded to `width`.
+fn field(text: &str, width: usize) -> String {
+    let ascii: String = text
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(width)
+        .collect();
+    format!("{ascii:<width$}")
+}
+
+/// `value` in at most the 8 characters of a physical range field.
+fn number(value: f64) -> String {
+    let text = (0..=6)
+        .rev()
+        .map(|decimals| format!("{value:.decimals$}"))
+        .find(|text| text.len() <= 8)
+        .unwrap_or_else(|| format!("{value:.0}"));
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+/// [`recover`](crate::recover) for an EDF file: drop a partial data record
+/// and set the record count to the complete ones.
+pub(crate) fn recover(path: &Path) -> io::Result<Recovery> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut fixed = [0u8; 256];
+    io::Read::read_exact(&mut file, &mut fixed)?;
+    let number_at = |range: std::ops::Range<usize>| -> io::Result<i64> {
+        std::str::from_utf8(&fixed[range])
+            .ok()
+            .and_then(|text| text.trim().parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an EDF header"))
+    };
+    let header_len = number_at(184..192)? as u64;
+    let records = number_at(236..244)?;
+    let n = number_at(252..256)? as u64;
+    // Samples per record of each signal, the ninth of the signal fields.
+    let mut samples = vec![0u8; 8 * n as usize];
+    file.seek(SeekFrom::Start(256 + n * (16 + 80 + 8 * 5 + 80)))?;
+    io::Read::read_exact(&mut file, &mut samples)?;
+    let samples: Vec<u64> = samples
+        .chunks(8)
+        .map(|s| {
+            std::str::from_utf8(s)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(0)
+        })
+        .collect();
+    let record_bytes = 2 * samples.iter().sum::<u64>();
+    // Frames are the samples of a channel, not of the annotation signal.
+    let first_samples = samples.first().copied().unwrap_or(0);
+    if record_bytes == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "EDF header without samples",
+        ));
+    }
+
+    let len = file.metadata()?.len();
+    let complete = len.saturating_sub(header_len) / record_bytes;
+    let removed_bytes = len - header_len - complete * record_bytes;
+    let closed = removed_bytes == 0 && records == complete as i64;
+    if !closed {
+        file.set_len(header_len + complete * record_bytes)?;
+        file.seek(SeekFrom::Start(RECORDS_OFFSET))?;
+        file.write_all(field(&complete.to_string(), 8).as_bytes())?;
+        file.sync_all()?;
+    }
+    Ok(Recovery {
+        closed,
+        frames: complete * first_samples,
+        removed_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::ConnectionKind;
+    use crate::events::Annotation;
+    use std::io::Cursor;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn info() -> RecordingInfo {
+        RecordingInfo {
+            mac: "20:16:10:00:3D:4F".into(),
+            sampling_rate: 10,
+            channels: vec![0, 5],
+            sensors: vec!["ECG".into()],
+            ports: Vec::new(),
+            is_bitalino2: true,
+            firmware_version: Some("BITalino_v5.2".into()),
+            connection: ConnectionKind::Bluetooth,
+            // 2024-02-29 13:05:09.250 UTC
+            started: UNIX_EPOCH + Duration::from_millis(1_709_211_909_250),
+        }
+    }
+
+    #[test]
+    fn writes_edf_header_and_padded_records() {
+        let mut writer = EdfWriter::new(Cursor::new(Vec::new()), &info()).unwrap();
+        let frames: Vec<Frame> = (0..14u16)
+            .filter(|&n| n != 3)
+            .map(|n| Frame::new(n as u8, [0; 4], &[500 + n, n]))
+            .collect();
+        writer.write_frames(&frames).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        let header = std::str::from_utf8(&bytes[..1024]).unwrap();
+
+        assert_eq!(header[8..88].trim_end(), "X X X X");
+        assert_eq!(
+            header[88..168].trim_end(),
+            "Startdate 29-FEB-2024 X X BITalino_2.0 20:16:10:00:3D:4F BITalino_v5.2 bluetooth"
+        );
+        assert_eq!(&header[168..184], "29.02.2413.05.09");
+        assert_eq!(header[184..192].trim(), "1024");
+        assert_eq!(&header[192..197], "EDF+C");
+        assert_eq!(header[236..244].trim(), "2");
+        assert_eq!(header[252..256].trim(), "3");
+        assert_eq!(
+            header[256..304].trim_end(),
+            "A1 ECG          A6 RAW          EDF Annotations"
+        );
+        // ECG spans +-1.5 mV over the 10-bit range; A6 is raw ADC counts.
+        assert_eq!(header[568..576].trim(), "-1.5");
+        assert_eq!(header[592..600].trim(), "1.49707");
+        assert_eq!(header[600..608].trim(), "1023");
+        assert_eq!(header[648..656].trim(), "1023");
+        assert_eq!(header[920..928].trim(), "64");
+
+        // Each record holds 10 samples per channel, then 128 annotation bytes.
+        let records: Vec<&[u8]> = bytes[1024..].chunks(168).collect();
+        assert_eq!(records.len(), 2);
+        let samples = |record: &[u8]| -> Vec<i16> {
+            record[..40]
+                .chunks(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                .collect()
+        };
+        // Frame 3 was lost and repeats frame 2; the second record is padded.
+        assert_eq!(&samples(records[0])[..5], [500, 501, 502, 502, 504]);
+        assert_eq!(&samples(records[1])[..6], [510, 511, 512, 513, 513, 513]);
+        assert_eq!(&samples(records[1])[10..14], [10, 11, 12, 13]);
+        // Record start times, 250 ms after the whole second in the header.
+        assert_eq!(tals(records[0]), [vec!["+0.25", ""]]);
+        assert_eq!(tals(records[1]), [vec!["+1.25", ""]]);
+    }
+
+    #[test]
+    fn writes_session_annotations_into_their_records() {
+        let mut writer = EdfWriter::new(Cursor::new(Vec::new()), &info()).unwrap();
+        let frames: Vec<Frame> = (0..13u16)
+            .map(|n| {
+                let mut frame = Frame::new(n as u8, [0; 4], &[n, n]);
+                frame.sample_index = 100 + u64::from(n);
+                frame
+            })
+            .collect();
+        writer.write_frames(&frames).unwrap();
+
+        let mut session = Session::new(info());
+        for (label, sample_index) in [("rest", 103), ("task\nstart", 115), ("early", 20)] {
+            session.annotations.push(Annotation {
+                label: label.into(),
+                sample_index,
+                timestamp_us: 0,
+            });
+        }
+        // The first record is already on disk and is rewritten in place.
+        writer.write_session(&session).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let records: Vec<&[u8]> = bytes[1024..].chunks(168).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(tals(records[0]), [vec!["+0.25", ""], vec!["+0.55", "rest"]]);
+        assert_eq!(
+            tals(records[1]),
+            [vec!["+1.25", ""], vec!["+1.75", "task start"]]
+        );
+        // The samples around the rewritten annotations are untouched.
+        assert_eq!(records[0][..2], [0, 0]);
+        assert_eq!(records[0][18..22], [9, 0, 0, 0]);
+    }
+
+    /// The TALs of a record's annotation bytes, as their onset and labels.
+    fn tals(record: &[u8]) -> Vec<Vec<&str>> {
+        record[record.len() - ANNOTATION_BYTES..]
+            .split(|&b| b == 0)
+            .filter(|tal| !tal.is_empty())
+            .map(|tal| {
+                let text = std::str::from_utf8(tal).unwrap();
+                text.strip_suffix('\x14').unwrap().split('\x14').collect()
+            })
+            .collect()
+    }
+}
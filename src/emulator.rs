@@ -0,0 +1,991 @@
+//! Software BITalino speaking the device protocol over any byte stream.
+//!
+//! [`Emulator`] answers the same commands as the firmware (version, state,
+//! sampling rate, start and stop, triggers, PWM, battery threshold) and
+//! streams frames in real time at the requested rate, with each analog
+//! channel driven by a synthetic [`Signal`]. Serve it on a TCP port and
+//! connect with [`Bitalino::connect_tcp`](crate::Bitalino::connect_tcp), or on
+//! a pseudo-terminal ([`EmulatorPty`], Unix only) and connect with
+//! [`Bitalino::connect_serial`](crate::Bitalino::connect_serial), to develop
+//! acquisition code on machines without Bluetooth. The `bitalino-emulator`
+//! binary does both from the command line.
+//!
+//! For tests, [`Emulator::transport`] connects a driver in-process and
+//! generates frames as fast as they are read. Signal parameters (heart rate,
+//! skin conductance responses, digital square waves) are known exactly, so
+//! algorithms can be checked against them.
+
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+
+use crate::bitalino::{Frame, Transport, MAX_ANALOG_CHANNELS};
+use crate::decode::{encode_frame, frame_size};
+use crate::firmware::{Capabilities, FirmwareVersion};
+use crate::presets::Preset;
+use crate::sensors::Sensor;
+
+/// Firmware version the emulator reports unless told otherwise.
+const DEFAULT_VERSION: &str = "BITalino_v5.2";
+
+/// Default read timeout of [`EmulatorTransport`], matching the RFCOMM connector.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the emulator checks for commands and sends the frames due.
+const TICK: Duration = Duration::from_millis(5);
+
+/// Mid-scale of the 10-bit ADC, around which signals swing.
+const MID_SCALE: f64 = 512.0;
+
+/// Synthetic waveform driving one analog channel, in ADC counts.
+///
+/// Every signal is a pure function of time (see [`value_at`](Self::value_at)),
+/// so tests can compare what the driver decodes, and what algorithms derive
+/// from it, with the exact ground truth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Signal {
+    /// Sine wave around mid-scale.
+    Sine { frequency: f64, amplitude: f64 },
+    /// ECG-like PQRST complexes at `bpm` beats per minute; `amplitude` is the
+    /// height of the R wave above mid-scale.
+    Ecg { bpm: f64, amplitude: f64 },
+    /// Skin conductance as read by the EDA sensor: a tonic level of
+    /// `level_us` microsiemens drifting by `drift_us_per_min`, plus a skin
+    /// conductance response of `scr_amplitude_us` every `scr_interval`
+    /// seconds, the first one at `scr_interval`. Responses rise in about a
+    /// second and decay over several; no responses if `scr_interval` is 0.
+    Eda {
+        level_us: f64,
+        drift_us_per_min: f64,
+        scr_interval: f64,
+        scr_amplitude_us: f64,
+    },
+    /// Gaussian-like noise around mid-scale.
+    Noise { amplitude: f64 },
+    /// Fixed value.
+    Constant(u16),
+}
+
+/// P, Q, R, S and T waves of the ECG template: centre and width as fractions
+/// of the beat, and height relative to the R wave.
+const ECG_WAVES: [(f64, f64, f64); 5] = [
+    (0.20, 0.025, 0.12),
+    (0.34, 0.010, -0.15),
+    (0.37, 0.012, 1.00),
+    (0.40, 0.010, -0.25),
+    (0.60, 0.040, 0.30),
+];
+
+/// Rise and decay time constants of a skin conductance response, in seconds.
+const SCR_RISE: f64 = 0.75;
+const SCR_DECAY: f64 = 2.0;
+
+/// Responses older than this contribute nothing measurable and are skipped.
+const SCR_SPAN: f64 = 30.0;
+
+impl Signal {
+    /// Typical signal for `sensor`: ECG at 72 bpm, tonic EDA with a response
+    /// every 10 s, a 10 Hz alpha rhythm for EEG, a slow sine for EOG, noise
+    /// for EMG, a constant for the light sensor and a 1 Hz sine otherwise.
+    pub fn for_sensor(sensor: Sensor) -> Self {
+        match sensor {
+            Sensor::Raw => Signal::Sine {
+                frequency: 1.0,
+                amplitude: 400.0,
+            },
+            Sensor::Ecg => Signal::Ecg {
+                bpm: 72.0,
+                amplitude: 300.0,
+            },
+            Sensor::Emg => Signal::Noise { amplitude: 80.0 },
+            Sensor::Eda => Signal::Eda {
+                level_us: 5.0,
+                drift_us_per_min: 0.1,
+                scr_interval: 10.0,
+                scr_amplitude_us: 0.5,
+            },
+            Sensor::Eeg => Signal::Sine {
+                frequency: 10.0,
+                amplitude: 50.0,
+            },
+            Sensor::Eog => Signal::Sine {
+                frequency: 0.5,
+                amplitude: 200.0,
+            },
+            Sensor::Lux => Signal::Constant(600),
+        }
+    }
+
+    /// Value at `t` seconds into the acquisition, clamped to 10 bits.
+    pub fn value_at(&self, t: f64) -> u16 {
+        let value = match *self {
+            Signal::Sine {
+                frequency,
+                amplitude,
+            } => MID_SCALE + amplitude * (TAU * frequency * t).sin(),
+            Signal::Ecg { bpm, amplitude } => {
+                let phase = (t * bpm / 60.0).fract();
+                let beat: f64 = ECG_WAVES
+                    .iter()
+                    .map(|&(centre, width, height)| {
+                        height * (-((phase - centre) / width).powi(2) / 2.0).exp()
+                    })
+                    .sum();
+                MID_SCALE + amplitude * beat
+            }
+            Signal::Eda {
+                level_us,
+                drift_us_per_min,
+                scr_interval,
+                scr_amplitude_us,
+            } => {
+                let mut us = level_us + drift_us_per_min * t / 60.0;
+                if scr_interval > 0.0 {
+                    let first = (((t - SCR_SPAN) / scr_interval).ceil() as u64).max(1);
+                    let last = (t / scr_interval).floor() as u64;
+                    us += (first..=last)
+                        .map(|k| scr_amplitude_us * scr_shape(t - k as f64 * scr_interval))
+                        .sum::<f64>();
+                }
+                // Inverse of the sensor's transfer function.
+                us / Sensor::Eda.to_physical(1 << 10, 10) * 1024.0
+            }
+            Signal::Noise { amplitude } => {
+                // Sum of four uniform draws: zero mean, roughly Gaussian. The
+                // draws are seeded by `t` so the noise is reproducible.
+                let mut rng = t.to_bits() ^ 0x9E37_79B9_7F4A_7C15;
+                let sum: f64 = (0..4).map(|_| uniform(&mut rng) - 0.5).sum();
+                MID_SCALE + amplitude * sum
+            }
+            Signal::Constant(value) => value as f64,
+        };
+        value.round().clamp(0.0, 1023.0) as u16
+    }
+}
+
+/// Bi-exponential response `age` seconds after its onset, peaking at 1.
+fn scr_shape(age: f64) -> f64 {
+    let bump = |age: f64| (-age / SCR_DECAY).exp() - (-age / SCR_RISE).exp();
+    let peak_age = (SCR_DECAY / SCR_RISE).ln() * SCR_RISE * SCR_DECAY / (SCR_DECAY - SCR_RISE);
+    bump(age) / bump(peak_age)
+}
+
+/// Uniform draw in `[0, 1)` from a splitmix64 generator.
+fn uniform(state: &mut u64) -> f64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)) as f64 / (u64::MAX as f64 + 1.0)
+}
+
+impl FromStr for Signal {
+    type Err = String;
+
+    /// Parse `sine[:HZ[:AMPLITUDE]]`, `ecg[:BPM[:AMPLITUDE]]`,
+    /// `eda[:LEVEL_US[:SCR_INTERVAL[:SCR_AMPLITUDE_US[:DRIFT_US_PER_MIN]]]]`,
+    /// `noise[:AMPLITUDE]` or `const:VALUE`.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (kind, args) = parse_spec(spec)?;
+        let arg = |i: usize, default: f64| args.get(i).copied().unwrap_or(default);
+        let max_args = match kind {
+            "sine" | "ecg" => 2,
+            "eda" => 4,
+            "noise" | "const" => 1,
+            _ => {
+                return Err(format!(
+                    "unknown signal {kind:?}; expected sine, ecg, eda, noise or const"
+                ))
+            }
+        };
+        if args.len() > max_args {
+            return Err(format!("too many parameters in signal {spec:?}"));
+        }
+        Ok(match kind {
+            "sine" => Signal::Sine {
+                frequency: arg(0, 1.0),
+                amplitude: arg(1, 400.0),
+            },
+            "ecg" => Signal::Ecg {
+                bpm: arg(0, 60.0),
+                amplitude: arg(1, 400.0),
+            },
+            "eda" => Signal::Eda {
+                level_us: arg(0, 5.0),
+                scr_interval: arg(1, 10.0),
+                scr_amplitude_us: arg(2, 0.5),
+                drift_us_per_min: arg(3, 0.0),
+            },
+            "noise" => Signal::Noise {
+                amplitude: arg(0, 50.0),
+            },
+            _ => match args.first() {
+                Some(&value) if (0.0..=1023.0).contains(&value) => Signal::Constant(value as u16),
+                _ => return Err(format!("signal {spec:?} needs a value from 0 to 1023")),
+            },
+        })
+    }
+}
+
+/// Split `kind:N:N...` into the kind and its numeric parameters.
+fn parse_spec(spec: &str) -> Result<(&str, Vec<f64>), String> {
+    let mut parts = spec.split(':');
+    let kind = parts.next().unwrap_or_default();
+    let args = parts
+        .map(|p| {
+            p.parse::<f64>()
+                .map_err(|_| format!("invalid number {p:?} in signal {spec:?}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((kind, args))
+}
+
+/// Level of a digital input over time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigitalSignal {
+    Low,
+    High,
+    /// Square wave starting high, spending `duty` (0-1) of each period high.
+    Square {
+        frequency: f64,
+        duty: f64,
+    },
+}
+
+impl DigitalSignal {
+    /// Level (0 or 1) at `t` seconds into the acquisition.
+    pub fn level_at(&self, t: f64) -> u8 {
+        match *self {
+            DigitalSignal::Low => 0,
+            DigitalSignal::High => 1,
+            DigitalSignal::Square { frequency, duty } => ((t * frequency).fract() < duty) as u8,
+        }
+    }
+}
+
+impl FromStr for DigitalSignal {
+    type Err = String;
+
+    /// Parse `low`, `high` or `square[:HZ[:DUTY]]`.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (kind, args) = parse_spec(spec)?;
+        match (kind, args.as_slice()) {
+            ("low", []) => Ok(DigitalSignal::Low),
+            ("high", []) => Ok(DigitalSignal::High),
+            ("square", [..]) if args.len() <= 2 => {
+                let duty = args.get(1).copied().unwrap_or(0.5);
+                if !(0.0..=1.0).contains(&duty) {
+                    return Err(format!("duty cycle in {spec:?} must be from 0 to 1"));
+                }
+                Ok(DigitalSignal::Square {
+                    frequency: args.first().copied().unwrap_or(1.0),
+                    duty,
+                })
+            }
+            ("low" | "high" | "square", _) => {
+                Err(format!("too many parameters in digital signal {spec:?}"))
+            }
+            _ => Err(format!(
+                "unknown digital signal {kind:?}; expected low, high or square"
+            )),
+        }
+    }
+}
+
+/// A software BITalino.
+///
+/// By default it reports firmware 5.2 and drives A1..A6 with a 1 Hz sine, an
+/// ECG at 60 bpm, noise, a 10 Hz sine and two constants. I1 toggles every
+/// second and I2 stays low; O1 and O2 follow the trigger commands.
+#[derive(Debug, Clone)]
+pub struct Emulator {
+    version: String,
+    signals: [Signal; MAX_ANALOG_CHANNELS],
+    inputs: [DigitalSignal; 2],
+    battery: u16,
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self {
+            version: DEFAULT_VERSION.to_string(),
+            signals: [
+                Signal::Sine {
+                    frequency: 1.0,
+                    amplitude: 400.0,
+                },
+                Signal::Ecg {
+                    bpm: 60.0,
+                    amplitude: 400.0,
+                },
+                Signal::Noise { amplitude: 50.0 },
+                Signal::Sine {
+                    frequency: 10.0,
+                    amplitude: 200.0,
+                },
+                Signal::Constant(256),
+                Signal::Constant(768),
+            ],
+            inputs: [
+                DigitalSignal::Square {
+                    frequency: 0.5,
+                    duty: 0.5,
+                },
+                DigitalSignal::Low,
+            ],
+            // About 3.8 V (see `DeviceState::battery_voltage`).
+            battery: 589,
+        }
+    }
+}
+
+impl Emulator {
+    /// An emulator with the default signals and firmware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report `version` (e.g. `"BITalino_v4.2"`) and behave like that
+    /// firmware: older versions have no state or PWM commands and only accept
+    /// triggers during acquisition.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Drive analog channel `channel` (0-5) with `signal`.
+    ///
+    /// # Panics
+    /// If `channel` is not below [`MAX_ANALOG_CHANNELS`].
+    pub fn signal(mut self, channel: usize, signal: Signal) -> Self {
+        self.signals[channel] = signal;
+        self
+    }
+
+    /// Drive each input of `preset` with the typical signal of its sensor
+    /// (see [`Signal::for_sensor`]); other channels keep their signals.
+    pub fn preset(mut self, preset: &Preset) -> Self {
+        for &(channel, sensor) in preset.inputs {
+            self.signals[channel as usize] = Signal::for_sensor(sensor);
+        }
+        self
+    }
+
+    /// Drive digital input `input` (0 for I1, 1 for I2) with `signal`.
+    ///
+    /// # Panics
+    /// If `input` is not 0 or 1.
+    pub fn input(mut self, input: usize, signal: DigitalSignal) -> Self {
+        self.inputs[input] = signal;
+        self
+    }
+
+    /// Raw battery reading reported by the state command.
+    pub fn battery(mut self, raw: u16) -> Self {
+        self.battery = raw;
+        self
+    }
+
+    /// Emulate a device on one connection until the host hangs up.
+    ///
+    /// Commands are read from `reader` on a separate thread; replies and
+    /// frames go to `writer`. Returns `Ok` when `reader` reaches its end or
+    /// `writer` finds the peer gone.
+    pub fn serve<R, W>(&self, reader: R, mut writer: W) -> io::Result<()>
+    where
+        R: Read + Send + 'static,
+        W: Write,
+    {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut reader = reader;
+            let mut buf = [0u8; 64];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if tx.send(buf[..n].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let mut device = EmulatedDevice::new(self.clone());
+        let result = loop {
+            let mut out = match rx.recv_timeout(TICK) {
+                Ok(commands) => commands
+                    .into_iter()
+                    .flat_map(|cmd| device.command(cmd))
+                    .collect(),
+                Err(RecvTimeoutError::Timeout) => Vec::new(),
+                Err(RecvTimeoutError::Disconnected) => break Ok(()),
+            };
+            device.frames_due(Instant::now(), &mut out);
+            if !out.is_empty() {
+                if let Err(e) = writer.write_all(&out).and_then(|_| writer.flush()) {
+                    break Err(e);
+                }
+            }
+        };
+        match result {
+            Err(e) if is_hang_up(e.kind()) => Ok(()),
+            other => other,
+        }
+    }
+
+    /// Listen on `addr` (e.g. `"127.0.0.1:8500"`) and emulate a device for
+    /// each connection in turn. Only returns on a listener error.
+    pub fn serve_tcp(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        info!("BITalino emulator listening on {}", listener.local_addr()?);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let peer = stream.peer_addr()?;
+            info!("Emulator connection from {}", peer);
+            stream.set_nodelay(true)?;
+            match self.serve(stream.try_clone()?, stream) {
+                Ok(()) => info!("Emulator connection from {} closed", peer),
+                Err(e) => info!("Emulator connection from {} failed: {}", peer, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Emulate a device on the host side of `pty`. Only returns on an I/O
+    /// error: the pseudo-terminal stays open for successive connections.
+    #[cfg(unix)]
+    pub fn serve_pty(&self, pty: &EmulatorPty) -> io::Result<()> {
+        info!("BITalino emulator on {}", pty.path().display());
+        self.serve(pty.master.try_clone()?, pty.master.try_clone()?)
+    }
+
+    /// An in-process [`Transport`] to this emulator, for
+    /// [`Bitalino::from_transport`](crate::Bitalino::from_transport).
+    pub fn transport(&self) -> EmulatorTransport {
+        EmulatorTransport {
+            device: EmulatedDevice::new(self.clone()),
+            pending: VecDeque::new(),
+            realtime: false,
+            read_timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+fn is_hang_up(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::BrokenPipe
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+    )
+}
+
+/// State of one emulated connection.
+struct EmulatedDevice {
+    emulator: Emulator,
+    capabilities: Capabilities,
+    created: Instant,
+    rate_hz: u16,
+    /// Acquired channels, start time and frames sent; `None` while idle.
+    acquisition: Option<(Vec<u8>, Instant, u64)>,
+    /// O1..O4 as last set by a trigger command.
+    outputs: [u8; 4],
+    battery_threshold: u8,
+    /// The next byte is a PWM duty cycle, not a command.
+    pwm_value_next: bool,
+}
+
+impl EmulatedDevice {
+    fn new(emulator: Emulator) -> Self {
+        Self {
+            capabilities: Capabilities::for_firmware(FirmwareVersion::parse(&emulator.version)),
+            created: Instant::now(),
+            rate_hz: 1000,
+            acquisition: None,
+            outputs: [0; 4],
+            battery_threshold: 0,
+            pwm_value_next: false,
+            emulator,
+        }
+    }
+
+    /// Act on one command byte and return the reply, if any.
+    fn command(&mut self, cmd: u8) -> Vec<u8> {
+        if std::mem::take(&mut self.pwm_value_next) {
+            debug!("Emulator PWM output: {}", cmd);
+            return Vec::new();
+        }
+        let acquiring = self.acquisition.is_some();
+        match cmd {
+            0x00 | 0xFF if acquiring => self.acquisition = None,
+            0xFF => {}
+            0xA3 if self.capabilities.pwm => self.pwm_value_next = true,
+            // BITalino 2.0 trigger: 1 0 1 1 O2 O1 1 1
+            c if self.capabilities.idle_trigger && c & 0xF3 == 0xB3 => {
+                self.outputs = [(c >> 2) & 1, (c >> 3) & 1, 0, 0];
+            }
+            // BITalino 1.0 trigger, during acquisition: 1 0 O4 O3 O2 O1 1 1
+            c if !self.capabilities.idle_trigger && acquiring && c & 0xC3 == 0x83 => {
+                self.outputs = [2, 3, 4, 5].map(|bit| (c >> bit) & 1);
+            }
+            _ if acquiring => {}
+            0x07 => return format!("{}\n", self.emulator.version).into_bytes(),
+            0x0B if self.capabilities.state => return self.state_reply(),
+            // Sampling rate: R R 0 0 0 0 1 1
+            c if c & 0x3F == 0x03 => self.rate_hz = [1, 10, 100, 1000][(c >> 6) as usize],
+            // Start, live (0 1) or simulated (1 0) mode: channel mask in the top bits
+            c if matches!(c & 0x03, 0x01 | 0x02) && c >> 2 != 0 => {
+                let channels = (0..MAX_ANALOG_CHANNELS as u8)
+                    .filter(|ch| c & (1 << (ch + 2)) != 0)
+                    .collect();
+                self.acquisition = Some((channels, Instant::now(), 0));
+            }
+            // Battery threshold: T T T T T T 0 0
+            c if c & 0x03 == 0x00 => self.battery_threshold = c >> 2,
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    /// Append the frames due by `now` to `out`.
+    fn frames_due(&mut self, now: Instant, out: &mut Vec<u8>) {
+        let Some((_, started, sent)) = &self.acquisition else {
+            return;
+        };
+        let due =
+            (now.saturating_duration_since(*started).as_secs_f64() * self.rate_hz as f64) as u64;
+        self.next_frames(due.saturating_sub(*sent), out);
+    }
+
+    /// Append the next `count` frames of the acquisition to `out`, regardless
+    /// of the time.
+    fn next_frames(&mut self, count: u64, out: &mut Vec<u8>) {
+        let Some((channels, _, sent)) = &self.acquisition else {
+            return;
+        };
+        let rate = self.rate_hz as f64;
+        let six_bit = channels.len() > 4;
+        for n in *sent..*sent + count {
+            let t = n as f64 / rate;
+            let analog: Vec<u16> = channels
+                .iter()
+                .enumerate()
+                .map(|(i, &ch)| {
+                    let value = self.emulator.signals[ch as usize].value_at(t);
+                    // A5 and A6 go out with their top 6 bits in 5- and 6-channel frames.
+                    if six_bit && i >= 4 {
+                        value >> 4
+                    } else {
+                        value
+                    }
+                })
+                .collect();
+            let frame = Frame::new((n % 16) as u8, self.digital(t), &analog);
+            out.extend(encode_frame(&frame));
+        }
+        if let Some((_, _, sent)) = self.acquisition.as_mut() {
+            *sent += count;
+        }
+    }
+
+    /// Reply to the state command: A1..A6 and the battery as little-endian
+    /// pairs, the battery threshold, a reserved byte from firmware 5.2, then
+    /// the digital channels and the CRC.
+    fn state_reply(&mut self) -> Vec<u8> {
+        let t = self.created.elapsed().as_secs_f64();
+        let mut reply: Vec<u8> = self
+            .emulator
+            .signals
+            .iter()
+            .flat_map(|signal| signal.value_at(t).to_le_bytes())
+            .collect();
+        reply.extend(self.emulator.battery.to_le_bytes());
+        reply.push(self.battery_threshold);
+        if self.capabilities.state_reply_len == 17 {
+            reply.push(0);
+        }
+        let [i1, i2, o1, o2] = self.digital(t);
+        reply.push(i1 << 7 | i2 << 6 | o1 << 5 | o2 << 4);
+        let crc = crc4(&reply);
+        *reply.last_mut().expect("reply is not empty") |= crc;
+        reply
+    }
+
+    /// I1, I2, O1, O2 at `t` seconds.
+    fn digital(&self, t: f64) -> [u8; 4] {
+        let [i1, i2] = self.emulator.inputs.map(|input| input.level_at(t));
+        [i1, i2, self.outputs[0], self.outputs[1]]
+    }
+
+    /// Sampling rate and frame size while acquiring, `None` while idle.
+    fn stream_format(&self) -> Option<(u16, usize)> {
+        let (channels, _, _) = self.acquisition.as_ref()?;
+        Some((self.rate_hz, frame_size(channels.len())))
+    }
+}
+
+/// In-process [`Transport`] to an [`Emulator`], returned by
+/// [`Emulator::transport`].
+///
+/// Frames are generated as fast as they are read, on the acquisition's own
+/// clock: at 1000 Hz, reading 10 000 frames returns the first ten seconds of
+/// the signals at once. [`realtime`](Self::realtime) paces them at the
+/// sampling rate instead.
+pub struct EmulatorTransport {
+    device: EmulatedDevice,
+    /// Replies and frames not yet read.
+    pending: VecDeque<u8>,
+    realtime: bool,
+    read_timeout: Duration,
+}
+
+impl EmulatorTransport {
+    /// Stream frames at the sampling rate instead of as fast as they are read.
+    pub fn realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    /// Generate frames into `pending` if it is empty: those due now in real
+    /// time, otherwise up to `bytes` worth, at least one frame and at most
+    /// one second.
+    fn generate(&mut self, bytes: usize) {
+        let Some((rate, frame_size)) = self.device.stream_format() else {
+            return;
+        };
+        if !self.pending.is_empty() {
+            return;
+        }
+        let mut out = Vec::new();
+        if self.realtime {
+            self.device.frames_due(Instant::now(), &mut out);
+        } else {
+            let frames = (bytes / frame_size).clamp(1, rate as usize);
+            self.device.next_frames(frames as u64, &mut out);
+        }
+        self.pending.extend(out);
+    }
+}
+
+impl Read for EmulatorTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.generate(buf.len());
+        if self.pending.is_empty() {
+            let Some((rate, _)) = self.device.stream_format() else {
+                // Nothing arrives while the device is idle.
+                return Err(io::Error::from(ErrorKind::WouldBlock));
+            };
+            // Real time: wait for the next frame, like a socket would.
+            let deadline = Instant::now() + self.read_timeout;
+            let period = Duration::from_secs_f64(1.0 / rate as f64);
+            while self.pending.is_empty() {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(io::Error::from(ErrorKind::TimedOut));
+                }
+                std::thread::sleep(period.min(deadline - now));
+                self.generate(buf.len());
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        for (dst, src) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for EmulatorTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &cmd in buf {
+            let reply = self.device.command(cmd);
+            self.pending.extend(reply);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for EmulatorTransport {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.read_timeout = timeout;
+        Ok(())
+    }
+
+    fn bytes_available(&mut self) -> io::Result<usize> {
+        // Ten milliseconds of frames when generating as fast as they are read.
+        if let Some((rate, frame_size)) = self.device.stream_format() {
+            self.generate(rate as usize / 100 * frame_size);
+        }
+        Ok(self.pending.len())
+    }
+}
+
+/// The firmware's 4-bit CRC over `data`, whose last nibble is zero.
+fn crc4(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        for bit in (0..8).rev() {
+            crc <<= 1;
+            if crc & 0x10 != 0 {
+                crc ^= 0x03;
+            }
+            crc ^= (byte >> bit) & 1;
+        }
+    }
+    crc & 0x0F
+}
+
+/// A pseudo-terminal for [`Emulator::serve_pty`]; open its [`path`](Self::path)
+/// as a serial port.
+#[cfg(unix)]
+pub struct EmulatorPty {
+    master: std::fs::File,
+    /// Kept open so the pseudo-terminal outlives each client.
+    _slave: std::fs::File,
+    path: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl EmulatorPty {
+    /// Allocate a pseudo-terminal in raw mode.
+    pub fn open() -> io::Result<Self> {
+        use std::ffi::CStr;
+        use std::os::fd::FromRawFd;
+
+        let (mut master, mut slave) = (-1, -1);
+        // SAFETY: openpty writes two descriptors on success; the name,
+        // termios and window size arguments may be null.
+        let rc = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: both descriptors were just opened and are owned by nobody else.
+        let (master_file, slave_file) = unsafe {
+            (
+                std::fs::File::from_raw_fd(master),
+                std::fs::File::from_raw_fd(slave),
+            )
+        };
+
+        // SAFETY: termios is plain data filled by tcgetattr before use.
+        unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(slave, &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            libc::cfmakeraw(&mut termios);
+            if libc::tcsetattr(slave, libc::TCSANOW, &termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: the buffer length is passed along with the buffer.
+        let rc = unsafe { libc::ttyname_r(slave, name.as_mut_ptr(), name.len()) };
+        if rc != 0 {
+            return Err(io::Error::from_raw_os_error(rc));
+        }
+        // SAFETY: ttyname_r succeeded, so `name` holds a NUL-terminated path.
+        let path = unsafe { CStr::from_ptr(name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+            .into();
+        Ok(Self {
+            master: master_file,
+            _slave: slave_file,
+            path,
+        })
+    }
+
+    /// Device node of the client side, e.g. `/dev/pts/3`.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::Bitalino;
+    use crate::tcp::TcpTransport;
+    use std::net::TcpListener;
+
+    #[test]
+    fn driver_acquires_from_the_emulator() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let emulator = Emulator::new().signal(0, Signal::Constant(700));
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            emulator.serve(stream.try_clone().unwrap(), stream)
+        });
+
+        let transport = TcpTransport::connect(&addr.to_string()).unwrap();
+        let mut dev = Bitalino::from_transport(Box::new(transport));
+        dev.set_io_timeout(Duration::from_millis(100)).unwrap();
+        assert_eq!(dev.version().unwrap(), DEFAULT_VERSION);
+        let state = dev.state().unwrap();
+        assert_eq!(state.analog[0], 700);
+        assert_eq!(state.battery, 589);
+
+        dev.trigger(&[1, 0]).unwrap();
+        dev.start(100, vec![0, 1, 2, 3, 4]).unwrap();
+        let batch = dev.read_frames_timed(20).unwrap();
+        assert_eq!((batch.crc_errors, batch.sequence_gaps), (0, 0));
+        assert_eq!(batch.frames.len(), 20);
+        assert!(batch.frames.iter().all(|f| f.analog[0] == 700));
+        assert!(batch.frames.iter().all(|f| f.digital[2..] == [1, 0]));
+        // A5 is sent with 6 bits in a 5-channel frame: 256 >> 4.
+        assert!(batch.frames.iter().all(|f| f.analog[4] == 16));
+        dev.stop().unwrap();
+
+        drop(dev);
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn first_version_stops_an_acquisition_left_running() {
+        let emulator = Emulator::new();
+        let mut idle = Bitalino::from_transport(Box::new(emulator.transport()));
+        assert_eq!(idle.version().unwrap(), DEFAULT_VERSION);
+        assert!(!idle.device_info().was_streaming);
+
+        // A previous process started A1 at 100 Hz and never stopped it.
+        let mut transport = emulator.transport();
+        transport.write_all(&[0x83, 0x05]).unwrap();
+        let mut dev = Bitalino::from_transport(Box::new(transport));
+        assert_eq!(dev.version().unwrap(), DEFAULT_VERSION);
+        assert!(dev.device_info().was_streaming);
+        // Later handshakes keep the answer from the first one.
+        dev.version().unwrap();
+        assert!(dev.device_info().was_streaming);
+    }
+
+    #[test]
+    fn signals_parse_from_specs() {
+        assert_eq!(
+            "sine:10:300".parse(),
+            Ok(Signal::Sine {
+                frequency: 10.0,
+                amplitude: 300.0
+            })
+        );
+        assert_eq!(
+            "ecg".parse(),
+            Ok(Signal::Ecg {
+                bpm: 60.0,
+                amplitude: 400.0
+            })
+        );
+        assert_eq!("const:5".parse(), Ok(Signal::Constant(5)));
+        assert!("const".parse::<Signal>().is_err());
+        assert!("noise:1:2".parse::<Signal>().is_err());
+        assert!("square".parse::<Signal>().is_err());
+        assert_eq!(
+            "eda:3:5".parse(),
+            Ok(Signal::Eda {
+                level_us: 3.0,
+                drift_us_per_min: 0.0,
+                scr_interval: 5.0,
+                scr_amplitude_us: 0.5
+            })
+        );
+        assert_eq!(
+            "square:2".parse(),
+            Ok(DigitalSignal::Square {
+                frequency: 2.0,
+                duty: 0.5
+            })
+        );
+        assert_eq!("high".parse(), Ok(DigitalSignal::High));
+        assert!("square:1:2".parse::<DigitalSignal>().is_err());
+        assert!("low:1".parse::<DigitalSignal>().is_err());
+    }
+
+    #[test]
+    fn detectors_recover_the_generated_ground_truth() {
+        use std::sync::{Arc, Mutex};
+
+        let emulator = Emulator::new()
+            .preset(&Preset::EDA_ECG)
+            .input(0, "square:4:0.25".parse().unwrap());
+        let mut dev = Bitalino::from_transport(Box::new(emulator.transport()));
+        assert_eq!(dev.version().unwrap(), DEFAULT_VERSION);
+
+        let beats = Arc::new(Mutex::new(Vec::new()));
+        let scrs = Arc::new(Mutex::new(Vec::new()));
+        let sink = beats.clone();
+        dev.on_heartbeat(1, move |beat| sink.lock().unwrap().push(beat))
+            .unwrap();
+        let sink = scrs.clone();
+        dev.on_scr(2, move |scr| sink.lock().unwrap().push(scr))
+            .unwrap();
+
+        // 35 s at 1000 Hz, generated as fast as it is read.
+        dev.start(1000, vec![1, 2]).unwrap();
+        let batch = dev.read_frames_timed(35_000).unwrap();
+        dev.stop().unwrap();
+        assert_eq!((batch.crc_errors, batch.sequence_gaps), (0, 0));
+
+        // I1 is high for the first quarter of every 250 ms.
+        for frame in &batch.frames {
+            let t = frame.sample_index as f64 / 1000.0;
+            assert_eq!(frame.digital[0], u8::from((t * 4.0).fract() < 0.25));
+        }
+
+        // ECG at 72 bpm.
+        let beats = beats.lock().unwrap();
+        assert!(beats.len() > 30, "{} beats", beats.len());
+        for bpm in beats.iter().filter_map(|beat| beat.bpm) {
+            assert!((bpm - 72.0).abs() < 2.0, "{bpm} bpm");
+        }
+
+        // One 0.5 uS response every 10 s from 10 s on, peaking 1.18 s later.
+        let scrs = scrs.lock().unwrap();
+        assert_eq!(scrs.len(), 3, "{scrs:?}");
+        for (k, scr) in scrs.iter().enumerate() {
+            let peak = scr.peak_index as f64 / 1000.0;
+            assert!((peak - 10.0 * (k + 1) as f64 - 1.18).abs() < 0.5, "{scr:?}");
+            assert!((scr.amplitude_us - 0.5).abs() < 0.15, "{scr:?}");
+        }
+    }
+
+    #[test]
+    fn eda_signal_follows_the_sensor_transfer_function() {
+        let eda = Signal::Eda {
+            level_us: 5.0,
+            drift_us_per_min: 1.0,
+            scr_interval: 0.0,
+            scr_amplitude_us: 0.0,
+        };
+        for (t, expected_us) in [(0.0, 5.0), (60.0, 6.0)] {
+            let us = Sensor::Eda.to_physical(eda.value_at(t), 10);
+            assert!((us - expected_us).abs() < 0.03, "{us} uS at {t} s");
+        }
+        let noise = Signal::Noise { amplitude: 50.0 };
+        assert_eq!(noise.value_at(1.5), noise.value_at(1.5));
+    }
+}
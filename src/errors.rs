@@ -22,8 +22,7 @@ pub enum DriverError {
     Protocol(String),
 
     /// Operations that exceeded their allotted time budget.
-    #[error("timeout: {0}")]
-    #[allow(dead_code)]
+    #[error("{0}")]
     Timeout(String),
 
     /// Device commands that returned an error.
@@ -32,14 +31,26 @@ pub enum DriverError {
     Command(String),
 
     /// CRC check did not validate frame contents.
-    #[error("CRC validation failed")]
-    #[allow(dead_code)]
-    Crc,
+    #[error("CRC validation failed: {0}")]
+    Crc(String),
+
+    /// The operation requires a running acquisition.
+    #[error("{0}")]
+    NotAcquiring(String),
 
     /// Device reported it is not ready for the requested action.
     #[error("device not ready: {0}")]
     #[allow(dead_code)]
     NotReady(String),
+
+    /// The device's firmware lacks the requested feature.
+    #[error("{0}")]
+    Unsupported(String),
+
+    /// A consumer fell behind a queue with [`OverflowPolicy::Error`](crate::OverflowPolicy::Error).
+    #[error("consumer overflow: {0}")]
+    #[allow(dead_code)]
+    Overflow(String),
 }
 
 #[derive(Debug, Error)]
@@ -61,7 +72,141 @@ pub enum BluetoothError {
     /// Low-level RFCOMM socket errors.
     #[error("rfcomm connection failed: {0}")]
     Connection(String),
+
+    /// The requested local Bluetooth adapter does not exist.
+    #[error("bluetooth adapter not found: {0}")]
+    #[allow(dead_code)]
+    AdapterNotFound(String),
+
+    /// Inquiry (device discovery) could not be run on the adapter.
+    #[error("device discovery failed: {0}")]
+    #[allow(dead_code)]
+    Discovery(String),
+
+    /// No RFCOMM backend is compiled in for this platform.
+    #[error("bluetooth backend unavailable: {0}")]
+    #[allow(dead_code)]
+    Unsupported(String),
+
+    /// The operation was aborted through a [`CancelToken`](crate::CancelToken).
+    #[error("bluetooth operation cancelled")]
+    #[allow(dead_code)]
+    Cancelled,
+}
+
+/// Coarse category of a [`DriverError`], stable across releases.
+///
+/// Lets callers (and the Python bindings) branch on the failure type, e.g.
+/// retrying on timeouts but not on CRC problems, without parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(dead_code)]
+pub enum DriverErrorKind {
+    Io,
+    Serial,
+    Bluetooth,
+    Protocol,
+    Timeout,
+    Command,
+    Crc,
+    NotReady,
+    NotAcquiring,
+    Unsupported,
+    Overflow,
+}
+
+#[allow(dead_code)]
+impl DriverErrorKind {
+    /// Lowercase identifier, e.g. `"timeout"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DriverErrorKind::Io => "io",
+            DriverErrorKind::Serial => "serial",
+            DriverErrorKind::Bluetooth => "bluetooth",
+            DriverErrorKind::Protocol => "protocol",
+            DriverErrorKind::Timeout => "timeout",
+            DriverErrorKind::Command => "command",
+            DriverErrorKind::Crc => "crc",
+            DriverErrorKind::NotReady => "not_ready",
+            DriverErrorKind::NotAcquiring => "not_acquiring",
+            DriverErrorKind::Unsupported => "unsupported",
+            DriverErrorKind::Overflow => "overflow",
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl DriverError {
+    /// Category of this error. I/O timeouts count as [`DriverErrorKind::Timeout`].
+    pub fn kind(&self) -> DriverErrorKind {
+        match self {
+            DriverError::Io(e) if is_io_timeout(e) => DriverErrorKind::Timeout,
+            DriverError::Io(_) => DriverErrorKind::Io,
+            DriverError::Serial(_) => DriverErrorKind::Serial,
+            DriverError::Bluetooth(_) => DriverErrorKind::Bluetooth,
+            DriverError::Protocol(_) => DriverErrorKind::Protocol,
+            DriverError::Timeout(_) => DriverErrorKind::Timeout,
+            DriverError::Command(_) => DriverErrorKind::Command,
+            DriverError::Crc(_) => DriverErrorKind::Crc,
+            DriverError::NotReady(_) => DriverErrorKind::NotReady,
+            DriverError::NotAcquiring(_) => DriverErrorKind::NotAcquiring,
+            DriverError::Unsupported(_) => DriverErrorKind::Unsupported,
+            DriverError::Overflow(_) => DriverErrorKind::Overflow,
+        }
+    }
+
+    /// Fine-grained machine-readable code, `"<kind>"` or `"<kind>.<detail>"`
+    /// (e.g. `"bluetooth.pairing"`, `"io.timed_out"`).
+    pub fn code(&self) -> &'static str {
+        match self {
+            DriverError::Io(e) => match e.kind() {
+                io::ErrorKind::TimedOut => "io.timed_out",
+                io::ErrorKind::WouldBlock => "io.would_block",
+                io::ErrorKind::NotConnected => "io.not_connected",
+                io::ErrorKind::BrokenPipe => "io.broken_pipe",
+                io::ErrorKind::ConnectionReset => "io.connection_reset",
+                io::ErrorKind::PermissionDenied => "io.permission_denied",
+                io::ErrorKind::NotFound => "io.not_found",
+                _ => "io",
+            },
+            DriverError::Bluetooth(e) => match e {
+                BluetoothError::NotFound { .. } => "bluetooth.not_found",
+                BluetoothError::Pairing(_) => "bluetooth.pairing",
+                BluetoothError::NotConnected(_) => "bluetooth.not_connected",
+                BluetoothError::Connection(_) => "bluetooth.connection",
+                BluetoothError::AdapterNotFound(_) => "bluetooth.adapter_not_found",
+                BluetoothError::Discovery(_) => "bluetooth.discovery",
+                BluetoothError::Unsupported(_) => "bluetooth.unsupported",
+                BluetoothError::Cancelled => "bluetooth.cancelled",
+            },
+            other => other.kind().as_str(),
+        }
+    }
+}
+
+/// Whether an I/O error is a read/write timeout rather than a failure.
+#[allow(dead_code)]
+pub(crate) fn is_io_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
 }
 
 /// Convenience result alias for driver operations.
 pub type Result<T> = std::result::Result<T, DriverError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_timeouts_are_classified_as_timeouts() {
+        let err = DriverError::Io(io::Error::from(io::ErrorKind::TimedOut));
+        assert_eq!(err.kind(), DriverErrorKind::Timeout);
+        assert_eq!(err.code(), "io.timed_out");
+
+        let err = DriverError::Bluetooth(BluetoothError::Pairing("rejected".into()));
+        assert_eq!(err.kind().as_str(), "bluetooth");
+        assert_eq!(err.code(), "bluetooth.pairing");
+    }
+}
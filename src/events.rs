@@ -0,0 +1,113 @@
+//! Acquisition events derived from the incoming frame stream.
+//!
+//! Hardware sync pulses on the digital inputs are the usual way to align a
+//! BITalino recording with external equipment. [`EdgeDetector`] turns the
+//! per-frame digital states into [`DigitalEdge`] events tagged with the absolute
+//! sample index, so callers no longer have to scan every frame by hand.
+//! [`Annotation`]s are the software counterpart: markers placed by the host
+//! (e.g. a stimulus-presentation script) on the same sample axis, and
+//! [`OutputEvent`]s record when the host drove the outputs, so stimulus timing
+//! can be reconstructed offline.
+
+/// A transition on one of the digital channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigitalEdge {
+    /// Index into `Frame::digital` (0 = I1, 1 = I2, 2 = O1, 3 = O2).
+    pub channel: u8,
+    /// `true` for a 0 → 1 transition, `false` for 1 → 0.
+    pub rising: bool,
+    /// Absolute sample index (since `start()`) of the first frame with the new level.
+    pub sample_index: u64,
+}
+
+/// A host-side event marker recorded by `Bitalino::annotate()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Free-form label (e.g. the stimulus name).
+    pub label: String,
+    /// Absolute sample index (since `start()`) the device was acquiring when the
+    /// marker was placed, estimated from the host clock.
+    pub sample_index: u64,
+    /// Host time of the marker, in microseconds since acquisition start (same
+    /// clock as `FrameBatch::timestamp_us`).
+    pub timestamp_us: u64,
+}
+
+/// An output change requested by the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputCommand {
+    /// Digital outputs set to `[O1, O2, O3, O4]`, with `trigger()` or by either
+    /// edge of `trigger_pulse()` (BITalino 2.0 only has O1 and O2).
+    Digital([u8; 4]),
+    /// PWM duty cycle set with `pwm()`.
+    Pwm(u8),
+}
+
+/// An output change recorded by `Bitalino` during acquisition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputEvent {
+    /// What was sent to the device.
+    pub command: OutputCommand,
+    /// Absolute sample index (since `start()`) the device was acquiring when the
+    /// command was written, estimated from the host clock.
+    pub sample_index: u64,
+    /// Host time of the write, in microseconds since acquisition start (same
+    /// clock as `FrameBatch::timestamp_us`).
+    pub timestamp_us: u64,
+}
+
+/// Stateful detector comparing each frame's digital bits with the previous frame.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeDetector {
+    last: Option<[u8; 4]>,
+}
+
+impl EdgeDetector {
+    /// Forget the previous state; the next frame only establishes a baseline.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Feed one frame's digital bits, calling `emit` for every channel that changed.
+    pub fn feed(&mut self, digital: [u8; 4], sample_index: u64, mut emit: impl FnMut(DigitalEdge)) {
+        if let Some(last) = self.last {
+            for (channel, (&old, &new)) in last.iter().zip(digital.iter()).enumerate() {
+                if old != new {
+                    emit(DigitalEdge {
+                        channel: channel as u8,
+                        rising: new > old,
+                        sample_index,
+                    });
+                }
+            }
+        }
+        self.last = Some(digital);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_rising_and_falling_edges() {
+        let mut detector = EdgeDetector::default();
+        let mut edges = Vec::new();
+        for (i, digital) in [[0, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0], [0, 1, 0, 0]]
+            .into_iter()
+            .enumerate()
+        {
+            detector.feed(digital, i as u64, |e| edges.push(e));
+        }
+
+        let edge = |channel, rising, sample_index| DigitalEdge {
+            channel,
+            rising,
+            sample_index,
+        };
+        assert_eq!(
+            edges,
+            vec![edge(0, true, 1), edge(0, false, 3), edge(1, true, 3)]
+        );
+    }
+}
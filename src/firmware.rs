@@ -0,0 +1,152 @@
+//! Firmware versions and the features each one supports.
+//!
+//! `version()` answers with a string such as `BITalino_v5.2`. The number
+//! decides which commands the device understands, so the driver keeps it as a
+//! [`FirmwareVersion`] and looks the features up in [`Capabilities`] instead
+//! of guessing from a single "is this a BITalino 2.0" flag:
+//!
+//! | Firmware | Device          | State | PWM | Trigger when idle | Outputs | State reply |
+//! |----------|-----------------|-------|-----|-------------------|---------|-------------|
+//! | < 4.2    | BITalino 1.0    | no    | no  | no                | 4       | -           |
+//! | 4.2-5.1  | BITalino 2.0    | yes   | yes | yes               | 2       | 16 bytes    |
+//! | >= 5.2   | BITalino 2.0    | yes   | yes | yes               | 2       | 17 bytes    |
+//!
+//! A device whose version was never read (or did not parse) gets the
+//! BITalino 1.0 row, which only uses commands every firmware accepts.
+
+use std::fmt;
+
+/// Firmware release number, `major.minor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl FirmwareVersion {
+    /// First BITalino 2.0 ((r)evolution) firmware.
+    pub const BITALINO_2: FirmwareVersion = FirmwareVersion::new(4, 2);
+    /// First firmware answering the state command with 17 bytes.
+    pub const STATE_17_BYTES: FirmwareVersion = FirmwareVersion::new(5, 2);
+
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parse the number out of a `version()` reply such as `BITalino_v5.2`
+    /// or `BITalino V4.2`.
+    pub fn parse(reply: &str) -> Option<Self> {
+        let lower = reply.to_ascii_lowercase();
+        let start = lower
+            .find("_v")
+            .map(|pos| pos + 2)
+            .or_else(|| lower.find('v').map(|pos| pos + 1))?;
+        let mut parts = lower[start..].split(|c: char| !c.is_ascii_digit());
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().filter(|p| !p.is_empty())?.parse().ok()?;
+        Some(Self::new(major, minor))
+    }
+}
+
+impl fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Features of one firmware generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The state command (`state()`, `ping()`).
+    pub state: bool,
+    /// Length of the state reply in bytes, when `state` is supported.
+    pub state_reply_len: usize,
+    /// The PWM output (`pwm()` and its schedules).
+    pub pwm: bool,
+    /// Digital outputs can be set while idle, not only during acquisition.
+    pub idle_trigger: bool,
+    /// Number of digital outputs the trigger command drives.
+    pub digital_outputs: u8,
+}
+
+/// Capabilities by first firmware version, oldest first.
+const CAPABILITY_TABLE: [(FirmwareVersion, Capabilities); 3] = [
+    (
+        FirmwareVersion::new(0, 0),
+        Capabilities {
+            state: false,
+            state_reply_len: 0,
+            pwm: false,
+            idle_trigger: false,
+            digital_outputs: 4,
+        },
+    ),
+    (
+        FirmwareVersion::BITALINO_2,
+        Capabilities {
+            state: true,
+            state_reply_len: 16,
+            pwm: true,
+            idle_trigger: true,
+            digital_outputs: 2,
+        },
+    ),
+    (
+        FirmwareVersion::STATE_17_BYTES,
+        Capabilities {
+            state: true,
+            state_reply_len: 17,
+            pwm: true,
+            idle_trigger: true,
+            digital_outputs: 2,
+        },
+    ),
+];
+
+impl Capabilities {
+    /// Capabilities of `firmware`; an unknown version gets the oldest row.
+    pub fn for_firmware(firmware: Option<FirmwareVersion>) -> Self {
+        CAPABILITY_TABLE
+            .iter()
+            .rev()
+            .find(|(since, _)| firmware.is_some_and(|v| v >= *since))
+            .map_or(CAPABILITY_TABLE[0].1, |&(_, caps)| caps)
+    }
+
+    /// First firmware version with a feature, for error messages.
+    pub fn first_with(feature: impl Fn(&Capabilities) -> bool) -> Option<FirmwareVersion> {
+        CAPABILITY_TABLE
+            .iter()
+            .find(|(_, caps)| feature(caps))
+            .map(|&(since, _)| since)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_follow_the_firmware_version() {
+        let parse = FirmwareVersion::parse;
+        assert_eq!(parse("BITalino_v5.2"), Some(FirmwareVersion::new(5, 2)));
+        assert_eq!(parse("BITalino V4.2"), Some(FirmwareVersion::new(4, 2)));
+        assert_eq!(parse("BITalino_v10.1b"), Some(FirmwareVersion::new(10, 1)));
+        assert_eq!(parse("BITalino_v3"), None);
+        assert_eq!(parse("garbage"), None);
+
+        let caps = |reply| Capabilities::for_firmware(parse(reply));
+        assert!(!caps("BITalino_v3.1").state);
+        assert_eq!(caps("BITalino_v3.1").digital_outputs, 4);
+        assert!(caps("BITalino_v4.2").pwm);
+        assert_eq!(caps("BITalino_v5.1").state_reply_len, 16);
+        assert_eq!(caps("BITalino_v5.2").state_reply_len, 17);
+        assert_eq!(Capabilities::for_firmware(None), caps("BITalino_v1.0"));
+
+        assert_eq!(
+            Capabilities::first_with(|c| c.idle_trigger),
+            Some(FirmwareVersion::BITALINO_2)
+        );
+        assert_eq!(Capabilities::first_with(|c| c.digital_outputs > 4), None);
+    }
+}
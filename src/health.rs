@@ -0,0 +1,66 @@
+//! Driver health metrics published through the [`metrics`] facade.
+//!
+//! Enabled by the `metrics` cargo feature. The driver only emits values; install
+//! any `metrics` recorder (for example `metrics-exporter-prometheus`) in the host
+//! application to scrape them:
+//!
+//! | name                              | kind    | meaning                                 |
+//! |-----------------------------------|---------|-----------------------------------------|
+//! | `bitalino_frames_total`           | counter | CRC-valid frames decoded                |
+//! | `bitalino_crc_errors_total`       | counter | frames rejected by the CRC check        |
+//! | `bitalino_sequence_gaps_total`    | counter | frames missing from the sequence counter|
+//! | `bitalino_battery_volts`          | gauge   | last measured or estimated battery level|
+//! | `bitalino_reconnects_total`       | counter | links re-established after a drop       |
+//!
+//! Every series carries a `device` label taken from
+//! [`Bitalino::device_label`](crate::bitalino::Bitalino::device_label).
+
+use metrics::{counter, describe_counter, describe_gauge, gauge, Unit};
+
+const FRAMES: &str = "bitalino_frames_total";
+const CRC_ERRORS: &str = "bitalino_crc_errors_total";
+const SEQUENCE_GAPS: &str = "bitalino_sequence_gaps_total";
+const BATTERY: &str = "bitalino_battery_volts";
+const RECONNECTS: &str = "bitalino_reconnects_total";
+
+/// Register descriptions and units for every driver metric.
+///
+/// Optional; call once after installing the recorder so exporters can emit
+/// `# HELP` lines.
+#[allow(dead_code)]
+pub fn describe_metrics() {
+    describe_counter!(FRAMES, Unit::Count, "CRC-valid frames decoded");
+    describe_counter!(CRC_ERRORS, Unit::Count, "Frames rejected by the CRC check");
+    describe_counter!(
+        SEQUENCE_GAPS,
+        Unit::Count,
+        "Frames missing from the sequence counter"
+    );
+    describe_gauge!(BATTERY, "Last measured or estimated battery voltage");
+    describe_counter!(
+        RECONNECTS,
+        Unit::Count,
+        "Links re-established after the connection dropped"
+    );
+}
+
+/// Count the outcome of one read batch.
+pub(crate) fn record_batch(device: &str, frames: usize, crc_errors: usize, sequence_gaps: usize) {
+    counter!(FRAMES, "device" => device.to_string()).increment(frames as u64);
+    if crc_errors > 0 {
+        counter!(CRC_ERRORS, "device" => device.to_string()).increment(crc_errors as u64);
+    }
+    if sequence_gaps > 0 {
+        counter!(SEQUENCE_GAPS, "device" => device.to_string()).increment(sequence_gaps as u64);
+    }
+}
+
+/// Publish a battery voltage reading.
+pub(crate) fn record_battery(device: &str, volts: f32) {
+    gauge!(BATTERY, "device" => device.to_string()).set(volts as f64);
+}
+
+/// Count a re-established link.
+pub(crate) fn record_reconnect(device: &str) {
+    counter!(RECONNECTS, "device" => device.to_string()).increment(1);
+}
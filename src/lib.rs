@@ -2,8 +2,17 @@
 //!
 //! This crate provides a robust interface to BITalino biosignal acquisition devices
 //! via Bluetooth RFCOMM using a minimal libc-based stack. It uses a raw RFCOMM
-//! socket and expects the device to be pre-paired/trusted (you provide the MAC);
-//! no BlueZ/tokio/dbus dependencies or privileged operations are required.
+//! socket and expects the device to be pre-paired/trusted (you provide the MAC).
+//! The default build has no BlueZ daemon, dbus or tokio dependency and needs no
+//! privileged operations. The optional `sync-bluetooth` feature adds pairing
+//! through BlueZ's D-Bus API on a blocking libdbus connection (no tokio or
+//! other async runtime), linking against the system `libdbus-1`.
+//!
+//! The driver itself is plain Rust. The PyO3 bindings (the `_bitalino_core`
+//! extension module behind the `bitalino_rs` Python package) are compiled with
+//! the default `python` feature; embedded applications can depend on the crate
+//! with `default-features = false` (adding back `bluez` for Bluetooth on Linux)
+//! to build without them and without linking against Python.
 //!
 //! # Timing and Synchronization
 //!
@@ -14,573 +23,87 @@
 //! 2. Use sequence numbers to detect dropped frames
 //! 3. Calculate sample times as: `start_time + sample_index / sampling_rate`
 
-use log::warn;
-use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
-
 mod logging;
+#[cfg(feature = "python")]
+mod python;
 
+mod battery;
 mod bitalino;
 mod bluetooth;
+mod compact;
+mod config;
+pub mod decode;
+pub mod dsp;
+mod edf;
+pub mod emulator;
 mod errors;
-
-pub use bitalino::{Bitalino, DeviceState, Frame, FrameBatch, SamplingRate};
-pub use bluetooth::{BluetoothConnector, RfcommStream};
+mod events;
+mod firmware;
+#[cfg(feature = "metrics")]
+mod health;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod pipeline;
+#[cfg(feature = "plot")]
+mod plot;
+mod presets;
+mod pwm;
+mod recording;
+mod recovery;
+mod registry;
+mod replay;
+mod rotation;
+mod sensors;
+mod serial;
+mod session;
+mod shared;
+mod stats;
+mod streaming;
+mod sync;
+mod tcp;
+mod throughput;
+mod timing;
+
+pub use battery::{BatteryMonitor, BatteryReading};
+pub use bitalino::{
+    Bitalino, ConnectionKind, CrcPolicy, DeviceInfo, DeviceState, Frame, FrameBatch, FrameColumns,
+    GapFill, ReadOptions, SamplingRate, Transport, MAX_ANALOG_CHANNELS,
+};
+pub use bluetooth::{
+    BluetoothConnector, BluetoothConnectorBuilder, CancelToken, ConnectProgress, DiscoveredDevice,
+    LinkQuality, PairingAgent, PairingRequest, PairingResponse, ProgressCallback, RfcommStream,
+};
+pub use compact::{convert, CompactReader, CompactWriter, Compression};
+pub use config::{Port, Profile, RetryPolicy};
+pub use edf::EdfWriter;
 pub use errors::*;
-pub use logging::{init_python_logging, init_rust_logging, reset_python_logging_cache};
-
-// ============================================================================
-// Python Bindings
-// ============================================================================
-
-/// A single BITalino data frame (dataclass-like).
-///
-/// Attributes:
-///     sequence: Frame sequence number (0-15, wrapping). Use to detect dropped frames.
-///     digital: Digital input values [I1, I2, O1, O2] as list of 0/1.
-///     analog: Analog channel values (10-bit, 0-1023) for configured channels.
-#[pyclass(name = "Frame", frozen, eq, from_py_object)]
-#[derive(Clone, PartialEq, Eq)]
-struct PyFrame {
-    #[pyo3(get)]
-    sequence: u8,
-    // Store raw digital bits; expose as list via custom getter to avoid bytes()
-    digital: Vec<u8>,
-    #[pyo3(get)]
-    analog: Vec<u16>,
-}
-
-#[pymethods]
-impl PyFrame {
-    #[new]
-    fn new(sequence: u8, digital: Vec<u8>, analog: Vec<u16>) -> Self {
-        PyFrame {
-            sequence,
-            digital,
-            analog,
-        }
-    }
-
-    fn __repr__(&self) -> String {
-        format!(
-            "Frame(sequence={}, digital={:?}, analog={:?})",
-            self.sequence, self.digital, self.analog
-        )
-    }
-
-    fn __str__(&self) -> String {
-        format!(
-            "Frame(seq={}, d={:?}, a={:?})",
-            self.sequence, self.digital, self.analog
-        )
-    }
-
-    /// Digital channel states as a Python list [I1, I2, O1, O2].
-    #[getter]
-    fn digital(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
-        Ok(PyList::new(py, &self.digital)?.into())
-    }
-
-    fn __hash__(&self) -> u64 {
-        use std::hash::{Hash, Hasher};
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        self.sequence.hash(&mut hasher);
-        self.digital.hash(&mut hasher);
-        self.analog.hash(&mut hasher);
-        hasher.finish()
-    }
-
-    /// Convert to dictionary for easy serialization.
-    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
-        let dict = PyDict::new(py);
-        dict.set_item("sequence", self.sequence)?;
-        dict.set_item("digital", self.digital.clone())?;
-        dict.set_item("analog", self.analog.clone())?;
-        Ok(dict)
-    }
-
-    /// Number of analog channels in this frame.
-    #[getter]
-    fn n_channels(&self) -> usize {
-        self.analog.len()
-    }
-}
-
-impl From<Frame> for PyFrame {
-    fn from(f: Frame) -> Self {
-        PyFrame {
-            sequence: f.seq,
-            digital: f.digital.to_vec(),
-            analog: f.analog,
-        }
-    }
-}
-
-/// Result from reading a batch of frames, includes timing info.
-///
-/// Attributes:
-///     frames: List of Frame objects.
-///     timestamp_us: Microseconds since acquisition started when batch was read.
-///     crc_errors: Number of frames discarded due to CRC errors.
-///     sequence_gaps: Number of detected dropped frames (from sequence discontinuities).
-#[pyclass(name = "FrameBatch", frozen, from_py_object)]
-#[derive(Clone)]
-struct PyFrameBatch {
-    #[pyo3(get)]
-    frames: Vec<PyFrame>,
-    #[pyo3(get)]
-    timestamp_us: u64,
-    #[pyo3(get)]
-    crc_errors: usize,
-    #[pyo3(get)]
-    sequence_gaps: usize,
-}
-
-#[pymethods]
-impl PyFrameBatch {
-    fn __repr__(&self) -> String {
-        format!(
-            "FrameBatch(frames={}, timestamp_us={}, crc_errors={}, sequence_gaps={})",
-            self.frames.len(),
-            self.timestamp_us,
-            self.crc_errors,
-            self.sequence_gaps
-        )
-    }
-
-    fn __len__(&self) -> usize {
-        self.frames.len()
-    }
-
-    /// Check if any errors occurred during reading.
-    #[getter]
-    fn has_errors(&self) -> bool {
-        self.crc_errors > 0 || self.sequence_gaps > 0
-    }
-}
-
-impl From<FrameBatch> for PyFrameBatch {
-    fn from(b: FrameBatch) -> Self {
-        PyFrameBatch {
-            frames: b.frames.into_iter().map(PyFrame::from).collect(),
-            timestamp_us: b.timestamp_us,
-            crc_errors: b.crc_errors,
-            sequence_gaps: b.sequence_gaps,
-        }
-    }
-}
-
-/// Device state information (BITalino 2.0+ only).
-///
-/// Contains current values of all analog/digital channels and battery status.
-/// Obtained by calling Bitalino.state() when not in acquisition mode.
-///
-/// Attributes:
-///     analog: All 6 analog channel values (10-bit, 0-1023).
-///     battery: Battery ADC value (10-bit, 0-1023).
-///     battery_threshold: Current battery threshold setting (0-63).
-///     digital: Digital channel states [I1, I2, O1, O2].
-#[pyclass(name = "DeviceState", frozen, from_py_object)]
-#[derive(Clone)]
-struct PyDeviceState {
-    #[pyo3(get)]
-    analog: Vec<u16>,
-    #[pyo3(get)]
-    battery: u16,
-    #[pyo3(get)]
-    battery_threshold: u8,
-    // store raw digital bits; expose as list via custom getter to avoid bytes()
-    digital: Vec<u8>,
-}
-
-#[pymethods]
-impl PyDeviceState {
-    fn __repr__(&self) -> String {
-        format!(
-            "DeviceState(battery={}, threshold={}, analog={:?}, digital={:?})",
-            self.battery, self.battery_threshold, self.analog, self.digital
-        )
-    }
-
-    /// Get the approximate battery voltage.
-    ///
-    /// Returns:
-    ///     Approximate battery voltage in Volts (typically 3.2V - 4.2V).
-    #[getter]
-    fn battery_voltage(&self) -> f32 {
-        (self.battery as f32 / 1023.0) * 3.3 * 2.0
-    }
-
-    /// Check if battery is low based on threshold setting.
-    ///
-    /// Returns:
-    ///     True if battery voltage is below the threshold.
-    #[getter]
-    fn is_battery_low(&self) -> bool {
-        let threshold_voltage = 3.4 + (self.battery_threshold as f32 / 63.0) * 0.4;
-        self.battery_voltage() < threshold_voltage
-    }
-
-    /// Digital channel states as a Python list [I1, I2, O1, O2].
-    #[getter]
-    fn digital(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
-        Ok(PyList::new(py, &self.digital)?.into())
-    }
-
-    /// Convert to dictionary for easy serialization.
-    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
-        let dict = PyDict::new(py);
-        dict.set_item("analog", self.analog.clone())?;
-        dict.set_item("battery", self.battery)?;
-        dict.set_item("battery_threshold", self.battery_threshold)?;
-        dict.set_item("digital", PyList::new(py, &self.digital)?)?;
-        dict.set_item("battery_voltage", self.battery_voltage())?;
-        dict.set_item("is_battery_low", self.is_battery_low())?;
-        Ok(dict)
-    }
-}
-
-impl From<DeviceState> for PyDeviceState {
-    fn from(s: DeviceState) -> Self {
-        PyDeviceState {
-            analog: s.analog.to_vec(),
-            battery: s.battery,
-            battery_threshold: s.battery_threshold,
-            digital: s.digital.to_vec(),
-        }
-    }
-}
-
-/// BITalino device driver.
-/// Python-facing BITalino driver wrapper for connection and acquisition.
-/// Provides methods to connect, configure, and read biosignal data from
-/// BITalino devices via Bluetooth. No root privileges required. The backend
-/// uses a raw RFCOMM socket and assumes the device is already paired/trusted
-/// (e.g., via `bluetoothctl`).
-///
-/// Example:
-///     >>> device = Bitalino.connect("7E:91:2B:C4:AF:08")
-///     >>> print(f"Firmware: {device.version()}")
-///     >>> device.start(rate=1000, channels=[0, 1, 2])
-///     >>> frames = device.read(100)
-///     >>> device.stop()
-#[pyclass(name = "Bitalino", unsendable)]
-struct PyBitalino {
-    inner: Bitalino,
-    sampling_rate: u16,
-}
-
-/// Upper bound on `wait_until_streaming` timeouts (seconds).
-/// Keeps `Duration::from_secs_f64` and `Instant::checked_add` well within range.
-const MAX_WAIT_TIMEOUT_SECS: f64 = 3600.0;
-
-#[pyfunction]
-/// Enable Rust-to-Python logging bridge at the given level (or env default).
-fn enable_rust_logs(py: Python<'_>, level: Option<&str>) -> PyResult<()> {
-    logging::set_python_log_level_str(py, level)
-}
-
-#[pyfunction]
-/// Clear cached Python logger handles; call after reconfiguring Python logging.
-fn reset_log_cache() -> PyResult<()> {
-    logging::reset_python_logging_cache();
-    Ok(())
-}
-
-#[pymethods]
-impl PyBitalino {
-    /// Connect to a BITalino device via serial port path (e.g., `/dev/rfcomm0`).
-    ///
-    /// Use this if you've already paired and bound the device manually.
-    #[new]
-    fn new(path: &str) -> PyResult<Self> {
-        Bitalino::connect_serial(path)
-            .map(|dev| PyBitalino {
-                inner: dev,
-                sampling_rate: 1000,
-            })
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
-    }
-
-    /// Connect to a BITalino device via Bluetooth.
-    ///
-    /// Uses a raw RFCOMM socket and expects the device to already be
-    /// paired/trusted; just pass the MAC. The `pin` argument is accepted for API
-    /// compatibility but is ignored by the minimal backend.
-    ///
-    /// Args:
-    ///     mac: The MAC address of the device (e.g., "7E:91:2B:C4:AF:08")
-    ///     pin: The PIN code (ignored; keep default "1234")
-    ///
-    /// Returns:
-    ///     A connected Bitalino instance
-    ///
-    /// Raises:
-    ///     ConnectionError: If pairing or connection fails after retries
-    #[staticmethod]
-    #[pyo3(signature = (mac, pin="1234"))]
-    fn connect(mac: &str, pin: &str) -> PyResult<Self> {
-        let connector = BluetoothConnector::default();
-        let stream = connector
-            .pair_and_connect(mac, pin)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyConnectionError, _>(e.to_string()))?;
-
-        let mut inner = Bitalino::from_rfcomm(stream);
-
-        // Perform an initial handshake to bring the device to a known idle state
-        // and verify the RFCOMM link.
-        if let Err(e) = inner.version() {
-            warn!("Initial version() handshake failed after connect: {}", e);
-        }
-
-        Ok(PyBitalino {
-            inner,
-            sampling_rate: 1000,
-        })
-    }
-
-    /// Get the device firmware version.
-    ///
-    /// Returns:
-    ///     Firmware version string (e.g., "BITalino_v5.2")
-    fn version(&mut self) -> PyResult<String> {
-        self.inner
-            .version()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
-    }
-
-    /// Start data acquisition.
-    ///
-    /// Args:
-    ///     rate: Sampling rate in Hz. Must be 1, 10, 100, or 1000. Default: 1000.
-    ///     channels: List of analog channels to acquire (0-5). Default: all channels.
-    ///
-    /// Raises:
-    ///     RuntimeError: If starting acquisition fails
-    #[pyo3(signature = (rate=1000, channels=None))]
-    fn start(&mut self, rate: u16, channels: Option<Vec<u8>>) -> PyResult<()> {
-        let channels = channels.unwrap_or_else(|| vec![0, 1, 2, 3, 4, 5]);
-        self.sampling_rate = rate;
-        self.inner
-            .start(rate, channels)
-            .map(|_| ())
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-    }
-
-    /// Stop data acquisition.
-    fn stop(&mut self) -> PyResult<()> {
-        self.inner
-            .stop()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-    }
-
-    /// Read frames from the device.
-    ///
-    /// Convenience wrapper that discards the batch's timing and integrity counters.
-    /// Use ``read_timed()`` if you need to detect CRC errors or dropped frames.
-    ///
-    /// Args:
-    ///     n_frames: Number of frames to read. Default: 100.
-    ///
-    /// Returns:
-    ///     List of Frame objects with sequence, digital, and analog attributes.
-    ///
-    /// Raises:
-    ///     IOError: If reading fails
-    #[pyo3(signature = (n_frames=100))]
-    fn read(&mut self, n_frames: usize) -> PyResult<Vec<PyFrame>> {
-        self.inner
-            .read_frames(n_frames)
-            .map(|frames| frames.into_iter().map(PyFrame::from).collect())
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
-    }
-
-    /// Read frames with timing and error information.
-    ///
-    /// This is the recommended method for high-quality acquisition as it provides
-    /// timing information for sample reconstruction and error statistics.
-    ///
-    /// Args:
-    ///     n_frames: Number of frames to read.
-    ///
-    /// Returns:
-    ///     FrameBatch with frames, timestamp_us, crc_errors, and sequence_gaps.
-    #[pyo3(signature = (n_frames=100))]
-    fn read_timed(&mut self, n_frames: usize) -> PyResult<PyFrameBatch> {
-        self.inner
-            .read_frames_timed(n_frames)
-            .map(PyFrameBatch::from)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
-    }
-
-    /// Block until the device is reliably streaming valid frames.
-    ///
-    /// After ``start()`` returns, the Bluetooth link may still be warming up: the
-    /// device is emitting frames but early bytes can be lost or corrupted. This
-    /// method discards warm-up frames and returns as soon as one CRC-valid frame
-    /// arrives. The valid frame's sequence number is stashed so the next
-    /// ``read_timed()`` call does not flag a spurious sequence gap.
-    ///
-    /// Args:
-    ///     timeout: Maximum time to wait, in seconds. Default: 2.0.
-    ///         Must be finite and in (0, 3600].
-    ///
-    /// Raises:
-    ///     ValueError: If timeout is not a finite number in (0, 3600] seconds.
-    ///     TimeoutError: If no CRC-valid frame arrives before the deadline.
-    ///     IOError: If the underlying transport fails (e.g. link dropped).
-    ///     RuntimeError: If acquisition is not started.
-    #[pyo3(signature = (timeout=2.0))]
-    fn wait_until_streaming(&mut self, py: Python<'_>, timeout: f64) -> PyResult<()> {
-        if !timeout.is_finite() || timeout <= 0.0 || timeout > MAX_WAIT_TIMEOUT_SECS {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                "timeout must be finite and in (0, {}] seconds; got {}",
-                MAX_WAIT_TIMEOUT_SECS, timeout
-            )));
-        }
-        let duration = std::time::Duration::from_secs_f64(timeout);
-        py.detach(|| self.inner.wait_until_streaming(duration))
-            .map_err(|e| {
-                let msg = e.to_string();
-                if let Some(io_err) = e.downcast_ref::<std::io::Error>() {
-                    PyErr::new::<pyo3::exceptions::PyIOError, _>(io_err.to_string())
-                } else if msg.starts_with("Timeout") {
-                    PyErr::new::<pyo3::exceptions::PyTimeoutError, _>(msg)
-                } else {
-                    PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(msg)
-                }
-            })
-    }
-
-    /// Get the current sampling rate.
-    #[getter]
-    fn sampling_rate(&self) -> u16 {
-        self.sampling_rate
-    }
-
-    /// Get microseconds elapsed since acquisition started.
-    #[getter]
-    fn elapsed_us(&self) -> Option<u64> {
-        self.inner.elapsed_us()
-    }
-
-    /// Check if this is a BITalino 2.0+ device.
-    ///
-    /// BITalino 2.0+ supports additional features like state(), pwm(), and
-    /// trigger() in idle mode. Call version() first to detect device type.
-    ///
-    /// Returns:
-    ///     True if device is BITalino 2.0+
-    #[getter]
-    fn is_bitalino2(&self) -> bool {
-        self.inner.is_bitalino2()
-    }
-
-    /// Set the battery threshold level.
-    ///
-    /// When battery voltage drops below this threshold, the device LED will blink.
-    /// Must be called when not in acquisition mode.
-    ///
-    /// Args:
-    ///     threshold: Threshold value (0-63).
-    ///         0 = 3.4V (minimum), 63 = 3.8V (maximum)
-    ///
-    /// Raises:
-    ///     RuntimeError: If device is currently in acquisition mode
-    #[pyo3(signature = (threshold=30))]
-    fn set_battery_threshold(&mut self, threshold: u8) -> PyResult<()> {
-        self.inner
-            .set_battery_threshold(threshold)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-    }
-
-    /// Alias for set_battery_threshold for compatibility with official library.
-    #[pyo3(signature = (value=30))]
-    fn battery(&mut self, value: u8) -> PyResult<()> {
-        self.set_battery_threshold(value)
-    }
-
-    /// Get the current device state (BITalino 2.0+ only).
-    ///
-    /// Returns the current values of all analog channels, digital channels,
-    /// battery level, and battery threshold. Must be called when not in acquisition.
-    ///
-    /// Returns:
-    ///     DeviceState object with analog, battery, battery_threshold, digital,
-    ///     battery_voltage, and is_battery_low properties.
-    ///
-    /// Raises:
-    ///     RuntimeError: If device is not BITalino 2.0+ or in acquisition mode
-    ///     IOError: If communication fails or CRC error
-    fn state(&mut self) -> PyResult<PyDeviceState> {
-        self.inner
-            .state()
-            .map(PyDeviceState::from)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-    }
-
-    /// Set digital output pins.
-    ///
-    /// Controls the digital output pins for external circuits or LED control.
-    ///
-    /// Args:
-    ///     outputs: List of output values (0 or 1).
-    ///         BITalino 2.0: [O1, O2] - works in both idle and acquisition modes
-    ///         BITalino 1.0: [O1, O2, O3, O4] - requires acquisition mode
-    ///
-    /// Raises:
-    ///     RuntimeError: If BITalino 1.0 and not in acquisition mode
-    #[pyo3(signature = (outputs=None))]
-    fn trigger(&mut self, outputs: Option<Vec<u8>>) -> PyResult<()> {
-        let outputs = outputs.unwrap_or_else(|| vec![0, 0]);
-        self.inner
-            .trigger(&outputs)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-    }
-
-    /// Set PWM output value (BITalino 2.0+ only).
-    ///
-    /// Controls the pulse-width modulation output for LED brightness
-    /// or other PWM-driven devices.
-    ///
-    /// Args:
-    ///     value: PWM duty cycle (0-255).
-    ///         0 = 0% duty cycle (always off)
-    ///         255 = 100% duty cycle (always on)
-    ///
-    /// Raises:
-    ///     RuntimeError: If device is not BITalino 2.0+
-    #[pyo3(signature = (value=100))]
-    fn pwm(&mut self, value: u8) -> PyResult<()> {
-        self.inner
-            .pwm(value)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-    }
-
-    fn __repr__(&self) -> String {
-        format!("Bitalino(rate={}Hz)", self.sampling_rate)
-    }
-}
-
-/// The Python module definition
-#[pymodule]
-fn _bitalino_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    // Initialize Python logging bridge (no-op if already done)
-    logging::init_python_logging(m.py())?;
-
-    // Add classes
-    m.add_class::<PyBitalino>()?;
-    m.add_class::<PyFrame>()?;
-    m.add_class::<PyFrameBatch>()?;
-    m.add_class::<PyDeviceState>()?;
-
-    // Add module-level constants
-    m.add("DEFAULT_SAMPLING_RATE", 1000u16)?;
-    m.add("VALID_SAMPLING_RATES", vec![1u16, 10, 100, 1000])?;
-
-    // Logging helpers for Python
-    m.add_function(wrap_pyfunction!(enable_rust_logs, m)?)?;
-    m.add_function(wrap_pyfunction!(reset_log_cache, m)?)?;
-
-    Ok(())
-}
+pub use events::{Annotation, DigitalEdge, EdgeDetector, OutputCommand, OutputEvent};
+pub use firmware::{Capabilities, FirmwareVersion};
+#[cfg(feature = "metrics")]
+pub use health::describe_metrics;
+pub use logging::init_rust_logging;
+#[cfg(feature = "mqtt")]
+pub use mqtt::{MqttConfig, MqttEncoding, MqttSink};
+pub use pipeline::{OutputReport, Pipeline, PipelineReport, RunningPipeline};
+pub use presets::Preset;
+pub use pwm::PwmSchedule;
+#[cfg(feature = "python")]
+pub use python::logging::{init_python_logging, reset_python_logging_cache};
+pub use recording::{
+    create_sink, ChannelPort, FrameSink, FrameWriter, JsonlSink, RecordFormat, RecordingInfo,
+    WfdbWriter,
+};
+pub use recovery::{recover, PeriodicSync, Recovery};
+pub use registry::{DeviceRegistry, KnownDevice};
+pub use replay::ReplayTransport;
+pub use rotation::{ManifestPart, RecordingManifest, RotatingSink, Rotation};
+pub use sensors::Sensor;
+pub use serial::{FlowControl, PortKind, SerialConfig, SerialPortEntry, PROBE_BAUD_RATES};
+pub use session::Session;
+pub use shared::SharedBitalino;
+pub use stats::{AcquisitionStats, ChannelStats, DEFAULT_STATS_WINDOW};
+pub use streaming::{BackgroundReader, OverflowPolicy};
+pub use sync::{OffsetEstimator, StartOffset, TriggerAlignment, TriggerSync};
+pub use tcp::TcpTransport;
+pub use throughput::{ThroughputMonitor, ThroughputWarning};
+pub use timing::TimingModel;
@@ -1,21 +1,362 @@
+use std::io::Write;
+use std::path::PathBuf;
 use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
 
+mod battery;
 mod bitalino;
 mod bluetooth;
+mod compact;
+mod config;
+mod dashboard;
+mod decode;
+mod dsp;
+mod edf;
 mod errors;
+mod events;
+mod firmware;
+#[cfg(feature = "metrics")]
+mod health;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "plot")]
+mod plot;
+mod presets;
+mod pwm;
+mod recording;
+mod recovery;
+mod registry;
+mod replay;
+mod rotation;
+mod sensors;
+mod serial;
+mod session;
+mod stats;
+mod streaming;
+mod sync;
+mod tcp;
+mod throughput;
+mod timing;
 
 #[derive(Parser, Debug)]
-#[command(name = "bitalino-demo", about = "Connect to BITalino and read frames")]
-struct Args {
-    /// Bluetooth MAC address (e.g., 20:16:10:XX:XX:XX)
-    mac: String,
-    /// Pairing PIN code (e.g., 1234)
-    pin: String,
+#[command(name = "bitalino", about = "Command-line tools for BITalino devices")]
+struct Cli {
+    /// Connection/acquisition profile (TOML, or JSON with a .json extension);
+    /// command-line options override its values
+    #[arg(long, global = true)]
+    profile: Option<PathBuf>,
+    /// Bluetooth adapter to use, by name (hci1) or address (Linux only)
+    #[arg(long, global = true)]
+    adapter: Option<String>,
+    /// Neither consult nor update the registry of known devices
+    #[arg(long, global = true)]
+    no_registry: bool,
+    /// Channels, rate and sensors of a sensor kit (ecg, emg, eda, eeg,
+    /// eda_ecg, psychophysiology); --rate and --channels still override it
+    #[arg(long, global = true)]
+    preset: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List nearby Bluetooth devices, marking likely BITalinos
+    Scan {
+        /// Inquiry duration in seconds
+        #[arg(short, long, default_value_t = 10)]
+        duration: u64,
+        /// Only list devices that look like BITalinos
+        #[arg(long)]
+        bitalino_only: bool,
+    },
+    /// List serial ports, marking likely BITalinos (bound RFCOMM nodes, known
+    /// USB-UART adapters)
+    Ports {
+        /// Only list ports that look like BITalinos
+        #[arg(long)]
+        bitalino_only: bool,
+    },
+    /// Acquire from a BITalino and write the frames to a file or MQTT broker
+    Record {
+        /// Bluetooth MAC address (e.g., 20:16:10:XX:XX:XX)
+        #[arg(long)]
+        mac: Option<String>,
+        /// Pairing PIN code [default: 1234]
+        #[arg(long)]
+        pin: Option<String>,
+        /// Sampling rate in Hz (1, 10, 100 or 1000) [default: 1000]
+        #[arg(long)]
+        rate: Option<u16>,
+        /// Analog channels to acquire (0-5), comma-separated [default: all]
+        #[arg(long, value_delimiter = ',')]
+        channels: Option<Vec<u8>>,
+        /// Recording length in seconds (default: until Ctrl-C)
+        #[arg(long)]
+        duration: Option<u64>,
+        /// Start acquiring at this UTC time (e.g. 2026-10-16T14:30:00Z), to
+        /// line up with equipment scheduled by clock
+        #[arg(long, value_name = "TIME", value_parser = parse_start_time)]
+        start_at: Option<SystemTime>,
+        /// Output file (optional when publishing with --mqtt)
+        #[arg(long, required_unless_present = "mqtt")]
+        out: Option<PathBuf>,
+        /// Output format (default: OpenSignals for .txt, JSON Lines for .jsonl,
+        /// WFDB for .hea/.dat, compact binary for .brec, EDF for .edf, CSV
+        /// otherwise)
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+        /// Commit the output file to disk at least every SECS, so a crash or
+        /// power loss loses no more (0: after every batch)
+        #[arg(long, value_name = "SECS", default_value_t = 1)]
+        sync_every: u64,
+        #[command(flatten)]
+        rotate: RotateArgs,
+        #[command(flatten)]
+        mqtt: MqttArgs,
+    },
+    /// Repair a recording cut short by a crash, keeping every complete frame
+    Recover {
+        /// Recording file (.csv, .txt, .jsonl, .brec, .edf, or the .hea or
+        /// .dat of a WFDB record)
+        path: PathBuf,
+    },
+    /// Convert a compact binary recording (.brec) to another format
+    Convert {
+        /// Compact recording to read
+        input: PathBuf,
+        /// File to write
+        output: PathBuf,
+        /// Output format (default: from the output extension, as for record)
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+    },
+    /// Show a live dashboard of signals and link health
+    Monitor {
+        /// Bluetooth MAC address (e.g., 20:16:10:XX:XX:XX)
+        mac: Option<String>,
+        /// Pairing PIN code [default: 1234]
+        #[arg(long)]
+        pin: Option<String>,
+        /// Sampling rate in Hz (1, 10, 100 or 1000) [default: 1000]
+        #[arg(long)]
+        rate: Option<u16>,
+        /// Analog channels to show (0-5), comma-separated [default: all]
+        #[arg(long, value_delimiter = ',')]
+        channels: Option<Vec<u8>>,
+        /// Seconds of signal shown in each sparkline
+        #[arg(long, default_value_t = 5)]
+        window: u64,
+        /// Plot the channels in a window instead of the terminal; needs a
+        /// build with the `plot` feature
+        #[arg(long)]
+        plot: bool,
+    },
+    /// Print the device state: analog snapshot, battery and digital I/O
+    State {
+        /// Bluetooth MAC address (e.g., 20:16:10:XX:XX:XX)
+        mac: Option<String>,
+        /// Pairing PIN code [default: 1234]
+        #[arg(long)]
+        pin: Option<String>,
+    },
+    /// Read the battery voltage, or set the low-battery threshold
+    Battery {
+        /// Bluetooth MAC address (e.g., 20:16:10:XX:XX:XX)
+        mac: Option<String>,
+        /// Pairing PIN code [default: 1234]
+        #[arg(long)]
+        pin: Option<String>,
+        /// New threshold, 0 (3.4 V) to 63 (3.8 V)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=63))]
+        set: Option<u8>,
+    },
+    /// Connect to a BITalino and print a few frames
+    Demo {
+        /// Bluetooth MAC address (e.g., 20:16:10:XX:XX:XX)
+        mac: Option<String>,
+        /// Pairing PIN code [default: 1234]
+        pin: Option<String>,
+    },
+    /// Manage the registry of devices connected to before
+    Devices {
+        #[command(subcommand)]
+        action: DevicesCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DevicesCommand {
+    /// List known devices with their channel, firmware and last connection
+    List,
+    /// Remove a device from the registry
+    Forget {
+        /// Bluetooth MAC address (e.g., 20:16:10:XX:XX:XX)
+        mac: String,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FormatArg {
+    Csv,
+    Opensignals,
+    Jsonl,
+    Wfdb,
+    Compact,
+    Edf,
+}
+
+impl From<FormatArg> for recording::RecordFormat {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Csv => Self::Csv,
+            FormatArg::Opensignals => Self::OpenSignals,
+            FormatArg::Jsonl => Self::Jsonl,
+            FormatArg::Wfdb => Self::Wfdb,
+            FormatArg::Compact => Self::Compact,
+            FormatArg::Edf => Self::Edf,
+        }
+    }
+}
+
+/// `record` options for splitting long recordings into numbered files.
+#[derive(clap::Args, Debug)]
+struct RotateArgs {
+    /// Start a new numbered file (out_0001.csv, ...) every SECS of signal,
+    /// listed in out.manifest.json
+    #[arg(long, value_name = "SECS", requires = "out",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    rotate_every: Option<u64>,
+    /// Start a new numbered file once the current one reaches MB megabytes
+    #[arg(long, value_name = "MB", requires = "out",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    rotate_size: Option<u64>,
+    /// Append numbered files to the recording in an existing manifest instead
+    /// of replacing it
+    #[arg(long, requires = "out")]
+    resume: bool,
+}
+
+impl RotateArgs {
+    /// Open the output at `out`: numbered files if any rotation option was
+    /// given, otherwise a single file.
+    fn create_sink(
+        &self,
+        out: &std::path::Path,
+        format: recording::RecordFormat,
+        info: &recording::RecordingInfo,
+    ) -> std::io::Result<Box<dyn recording::FrameSink>> {
+        if self.rotate_every.is_none() && self.rotate_size.is_none() && !self.resume {
+            return Ok(recording::create_sink(out, format, info)?);
+        }
+        let policy = rotation::Rotation {
+            every: self.rotate_every.map(Duration::from_secs),
+            max_bytes: self.rotate_size.map(|mb| mb * 1_000_000),
+        };
+        Ok(Box::new(if self.resume {
+            rotation::RotatingSink::resume(out, format, info, policy)?
+        } else {
+            rotation::RotatingSink::create(out, format, info, policy)?
+        }))
+    }
+}
+
+/// `record` options for publishing to an MQTT broker.
+#[derive(clap::Args, Debug)]
+struct MqttArgs {
+    /// Also publish every batch to this MQTT broker (host[:port]); needs a
+    /// build with the `mqtt` feature
+    #[arg(long)]
+    mqtt: Option<String>,
+    /// MQTT topic [default: bitalino/<mac without colons>]
+    #[arg(long, requires = "mqtt")]
+    mqtt_topic: Option<String>,
+    /// MQTT delivery guarantee (0, 1 or 2)
+    #[arg(long, default_value_t = 0, requires = "mqtt",
+          value_parser = clap::value_parser!(u8).range(0..=2))]
+    mqtt_qos: u8,
+    /// MQTT payload encoding
+    #[arg(long, value_enum, default_value_t = MqttFormatArg::Json, requires = "mqtt")]
+    mqtt_format: MqttFormatArg,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MqttFormatArg {
+    Json,
+    Cbor,
+}
+
+impl MqttArgs {
+    /// Connect the MQTT sink, if `--mqtt` was given.
+    #[cfg(feature = "mqtt")]
+    fn connect(
+        &self,
+        info: &recording::RecordingInfo,
+    ) -> Result<Option<(String, Box<dyn recording::FrameSink>)>> {
+        let Some(broker) = &self.mqtt else {
+            return Ok(None);
+        };
+        let mut config = mqtt::MqttConfig::new(broker.clone(), &info.mac);
+        if let Some(topic) = &self.mqtt_topic {
+            config.topic = topic.clone();
+        }
+        config.qos = self.mqtt_qos;
+        config.encoding = match self.mqtt_format {
+            MqttFormatArg::Json => mqtt::MqttEncoding::Json,
+            MqttFormatArg::Cbor => mqtt::MqttEncoding::Cbor,
+        };
+        let sink = mqtt::MqttSink::connect(&config, info)
+            .with_context(|| format!("Failed to set up MQTT publishing to {broker}"))?;
+        Ok(Some((
+            format!("mqtt://{broker}/{}", config.topic),
+            Box::new(sink),
+        )))
+    }
+
+    #[cfg(not(feature = "mqtt"))]
+    fn connect(
+        &self,
+        _info: &recording::RecordingInfo,
+    ) -> Result<Option<(String, Box<dyn recording::FrameSink>)>> {
+        if self.mqtt.is_some() {
+            anyhow::bail!(
+                "--mqtt needs a build with the `mqtt` feature (cargo build --features mqtt)"
+            );
+        }
+        Ok(None)
+    }
+}
+
+/// Set by the SIGINT/SIGTERM handler; acquisition loops check it between reads.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(_signal: libc::c_int) {
+    // A second signal means the graceful path is stuck (e.g. a read blocked on
+    // a dead link): give up immediately.
+    if INTERRUPTED.swap(true, Ordering::SeqCst) {
+        unsafe { libc::_exit(130) };
+    }
+}
+
+/// Route Ctrl-C and SIGTERM to [`INTERRUPTED`] so acquisition loops stop the
+/// device, flush their output and print statistics before exiting.
+fn install_signal_handlers() {
+    let handler: extern "C" fn(libc::c_int) = on_signal;
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        unsafe {
+            libc::signal(signal, handler as libc::sighandler_t);
+        }
+    }
+}
+
+/// Whether a shutdown signal arrived.
+fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
 }
 
 fn main() {
@@ -26,15 +367,560 @@ fn main() {
 }
 
 fn run() -> Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
+    let mut base = match &cli.profile {
+        Some(path) => config::Profile::load(path)?,
+        None => config::Profile::default(),
+    };
+    base.adapter = cli.adapter.or(base.adapter);
+    // The CLI keeps the registry up to date; library callers opt in.
+    base.remember = !cli.no_registry;
+    if let Some(name) = &cli.preset {
+        base.apply_preset(&presets::Preset::from_name(name)?);
+    }
+    // Command-line values take precedence over the profile.
+    let profile =
+        |mac: Option<String>, pin: Option<String>, rate: Option<u16>, channels: Option<Vec<u8>>| {
+            let mut profile = base.clone();
+            profile.mac = mac.or(profile.mac);
+            profile.pin = pin.unwrap_or(profile.pin);
+            profile.rate = rate.unwrap_or(profile.rate);
+            profile.channels = channels.unwrap_or(profile.channels);
+            profile
+        };
 
-    println!("Using MAC: {}, PIN: {}", args.mac, args.pin);
-    println!("--- Initializing Bluetooth Sensor (Rust) ---");
-    let connector = bluetooth::BluetoothConnector::default();
-    let stream = connector.pair_and_connect(&args.mac, &args.pin)?;
+    match cli.command {
+        Command::Scan {
+            duration,
+            bitalino_only,
+        } => scan(&base, Duration::from_secs(duration), bitalino_only),
+        Command::Ports { bitalino_only } => ports(bitalino_only),
+        Command::Record {
+            mac,
+            pin,
+            rate,
+            channels,
+            duration,
+            start_at,
+            out,
+            format,
+            sync_every,
+            rotate,
+            mqtt,
+        } => {
+            let format = format.map_or_else(
+                || {
+                    out.as_ref().map_or(
+                        recording::RecordFormat::Csv,
+                        recording::RecordFormat::from_path,
+                    )
+                },
+                Into::into,
+            );
+            record(
+                &profile(mac, pin, rate, channels),
+                duration.map(Duration::from_secs),
+                start_at,
+                out.as_ref(),
+                format,
+                Duration::from_secs(sync_every),
+                &rotate,
+                &mqtt,
+            )
+        }
+        Command::Monitor {
+            mac,
+            pin,
+            rate,
+            channels,
+            window,
+            plot,
+        } => monitor(
+            &profile(mac, pin, rate, channels),
+            Duration::from_secs(window),
+            plot,
+        ),
+        Command::State { mac, pin } => state(&profile(mac, pin, None, None)),
+        Command::Battery { mac, pin, set } => battery(&profile(mac, pin, None, None), set),
+        Command::Recover { path } => recover(&path),
+        Command::Convert {
+            input,
+            output,
+            format,
+        } => {
+            let format =
+                format.map_or_else(|| recording::RecordFormat::from_path(&output), Into::into);
+            let frames = compact::convert(&input, &output, format)
+                .with_context(|| format!("Failed to convert {}", input.display()))?;
+            println!("Wrote {frames} frames to {}", output.display());
+            Ok(())
+        }
+        Command::Demo { mac, pin } => demo(&profile(mac, pin, None, None)),
+        Command::Devices { action } => devices(action),
+    }
+}
+
+fn connect(profile: &config::Profile) -> Result<bitalino::Bitalino> {
+    if profile.mac.is_none() {
+        anyhow::bail!("No MAC address: pass one or set `mac` in the --profile file");
+    }
+    bitalino::Bitalino::from_profile(profile)
+}
+
+fn scan(profile: &config::Profile, duration: Duration, bitalino_only: bool) -> Result<()> {
+    println!("Scanning for {}s...", duration.as_secs());
+    let devices: Vec<_> = profile
+        .connector()
+        .discover(duration)?
+        .into_iter()
+        .filter(|d| !bitalino_only || d.is_bitalino())
+        .collect();
+
+    if devices.is_empty() {
+        println!("No devices found. Is the BITalino switched on and not connected elsewhere?");
+        return Ok(());
+    }
+    println!("  {:<17}  {:>5}  NAME", "MAC", "RSSI");
+    for device in &devices {
+        let rssi = device
+            .rssi
+            .map_or_else(|| "-".to_string(), |r| r.to_string());
+        println!(
+            "{} {:<17}  {:>5}  {}",
+            if device.is_bitalino() { "*" } else { " " },
+            device.mac,
+            rssi,
+            device.name.as_deref().unwrap_or("(unknown)")
+        );
+    }
+    if devices.iter().any(|d| d.is_bitalino()) {
+        println!("\n* likely BITalino");
+    }
+
+    // Name known devices, which the registry cannot learn from a connection.
+    if let Some(mut registry) = profile
+        .remember
+        .then(registry::DeviceRegistry::load_or_warn)
+        .flatten()
+    {
+        let mut renamed = false;
+        for device in &devices {
+            if let Some(name) = &device.name {
+                renamed |= registry.set_name(&device.mac, name);
+            }
+        }
+        if renamed {
+            registry.save_or_warn();
+        }
+    }
+    Ok(())
+}
+
+fn ports(bitalino_only: bool) -> Result<()> {
+    let ports: Vec<_> = bitalino::Bitalino::list_serial_ports()?
+        .into_iter()
+        .filter(|p| !bitalino_only || p.is_bitalino())
+        .collect();
+
+    if ports.is_empty() {
+        println!("No serial ports found. Bind a paired BITalino with `rfcomm bind` or plug in the adapter.");
+        return Ok(());
+    }
+    println!("  {:<24}  {:<9}  {:<9}  PRODUCT", "PATH", "KIND", "VID:PID");
+    for port in &ports {
+        let ids = port.vid.zip(port.pid).map_or_else(
+            || "-".to_string(),
+            |(vid, pid)| format!("{:04x}:{:04x}", vid, pid),
+        );
+        let product = [&port.manufacturer, &port.product]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!(
+            "{} {:<24}  {:<9}  {:<9}  {}",
+            if port.is_bitalino() { "*" } else { " " },
+            port.path,
+            port.kind.as_str(),
+            ids,
+            if product.is_empty() { "-" } else { &product }
+        );
+    }
+    if ports.iter().any(|p| p.is_bitalino()) {
+        println!("\n* likely BITalino");
+    }
+    Ok(())
+}
+
+fn devices(action: DevicesCommand) -> Result<()> {
+    let mut registry = registry::DeviceRegistry::load_default()?;
+    match action {
+        DevicesCommand::List => {
+            if registry.devices().is_empty() {
+                println!("No known devices in {}", registry.path().display());
+                return Ok(());
+            }
+            println!(
+                "{:<17}  {:<16}  {:>2}  {:<14}  LAST CONNECTED (UTC)",
+                "MAC", "NAME", "CH", "FIRMWARE"
+            );
+            for device in registry.devices() {
+                let last = device.last_connected.map_or_else(
+                    || "-".to_string(),
+                    |secs| {
+                        recording::utc_iso8601(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+                    },
+                );
+                println!(
+                    "{:<17}  {:<16}  {:>2}  {:<14}  {}",
+                    device.mac,
+                    device.name.as_deref().unwrap_or("-"),
+                    device.channel,
+                    device.firmware.as_deref().unwrap_or("-"),
+                    last
+                );
+            }
+        }
+        DevicesCommand::Forget { mac } => {
+            if !registry.forget(&mac) {
+                anyhow::bail!("{mac} is not in {}", registry.path().display());
+            }
+            registry.save()?;
+            println!("Forgot {mac}");
+        }
+    }
+    Ok(())
+}
+
+fn recover(path: &std::path::Path) -> Result<()> {
+    let recovery =
+        recovery::recover(path).with_context(|| format!("Failed to recover {}", path.display()))?;
+    if recovery.closed {
+        println!(
+            "{} is complete: {} frames, nothing to repair",
+            path.display(),
+            recovery.frames
+        );
+    } else {
+        println!(
+            "Repaired {}: {} frames kept, {} bytes of partial data removed",
+            path.display(),
+            recovery.frames,
+            recovery.removed_bytes
+        );
+    }
+    Ok(())
+}
+
+/// Parse `--start-at`, which must still be ahead.
+fn parse_start_time(text: &str) -> Result<SystemTime, String> {
+    let at = recording::parse_utc_iso8601(text)
+        .ok_or("expected a UTC time such as 2026-10-16T14:30:00Z")?;
+    if at <= SystemTime::now() {
+        return Err("this time has already passed".into());
+    }
+    Ok(at)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record(
+    profile: &config::Profile,
+    duration: Option<Duration>,
+    start_at: Option<SystemTime>,
+    out: Option<&PathBuf>,
+    format: recording::RecordFormat,
+    sync_every: Duration,
+    rotate: &RotateArgs,
+    mqtt: &MqttArgs,
+) -> Result<()> {
+    let mac = profile.mac.as_deref().unwrap_or_default();
+    println!("Connecting to {mac}...");
+    let mut device = connect(profile)?;
+    if let Ok(version) = device.version() {
+        println!("Firmware: {}", version.trim());
+    }
+
+    let rate = match start_at {
+        Some(at) => {
+            println!("Waiting to start at {}...", recording::utc_iso8601(at));
+            let rate = device.start_at(at, profile.rate, profile.channels.clone())?;
+            if let Some(offset) = device.start_offset() {
+                println!(
+                    "Started {:.3} ms after the scheduled time",
+                    offset.as_secs_f64() * 1e3
+                );
+            }
+            rate
+        }
+        None => device.start(profile.rate, profile.channels.clone())?,
+    };
+    // After the start, so Ctrl-C still ends a scheduled wait right away.
+    install_signal_handlers();
+    let mut info = device.begin_session()?.apply_profile(profile).info.clone();
+    info.mac = mac.to_string();
+
+    // Every batch goes to each destination, labelled for progress and errors.
+    let mut sinks: Vec<(String, Box<dyn recording::FrameSink>)> = Vec::new();
+    if let Some(out) = out {
+        let sink = rotate
+            .create_sink(out, format, &info)
+            .with_context(|| format!("Failed to create {}", out.display()))?;
+        let sink = Box::new(recovery::PeriodicSync::new(sink, sync_every));
+        let kind = match format {
+            recording::RecordFormat::Csv => "CSV",
+            recording::RecordFormat::OpenSignals => "OpenSignals",
+            recording::RecordFormat::Jsonl => "JSON Lines",
+            recording::RecordFormat::Wfdb => "WFDB",
+            recording::RecordFormat::Compact => "compact",
+            recording::RecordFormat::Edf => "EDF",
+        };
+        sinks.push((format!("{} ({kind})", out.display()), sink));
+    }
+    if let Some(sink) = mqtt.connect(&info)? {
+        sinks.push(sink);
+    }
+    let destinations = sinks
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(" and ");
+    println!(
+        "Recording {} Hz to {destinations}, Ctrl-C to stop",
+        rate as u16
+    );
+
+    // Read ~100 ms at a time so progress and Ctrl-C stay responsive.
+    let batch_size = (rate as usize / 10).max(1);
+    let started = Instant::now();
+    let mut last_progress = started;
+    let mut written = 0u64;
+    let result = loop {
+        if interrupted() {
+            eprintln!("\nInterrupted, stopping acquisition...");
+            break Ok(());
+        }
+        if duration.is_some_and(|limit| started.elapsed() >= limit) {
+            break Ok(());
+        }
+        let batch = match device.read_frames_timed(batch_size) {
+            Ok(batch) => batch,
+            Err(e) => break Err(e),
+        };
+        let write_error = sinks.iter_mut().find_map(|(name, sink)| {
+            sink.write_frames(&batch.frames)
+                .with_context(|| format!("Failed to write to {name}"))
+                .err()
+        });
+        if let Some(e) = write_error {
+            break Err(e);
+        }
+        written += batch.frames.len() as u64;
+
+        if last_progress.elapsed() >= Duration::from_secs(1) {
+            last_progress = Instant::now();
+            let total = duration.map_or_else(String::new, |d| format!("/{}s", d.as_secs()));
+            let stats = device.stats();
+            eprint!(
+                "\r[{:.0}s{}] frames={} lost={} crc={} gaps={}   ",
+                started.elapsed().as_secs_f64(),
+                total,
+                written,
+                stats.dropped_samples,
+                stats.crc_errors,
+                stats.sequence_gaps
+            );
+            let _ = std::io::stderr().flush();
+        }
+    };
+
+    let stopped = device.stop();
+    let session = device.end_session()?;
+    for (name, sink) in &mut sinks {
+        sink.write_session(&session)
+            .and_then(|()| sink.close())
+            .with_context(|| format!("Failed to write to {name}"))?;
+    }
+
+    let stats = session.stats.unwrap_or_else(|| device.stats());
+    let lost = stats.dropped_samples;
+    println!(
+        "\nWrote {} frames in {:.1}s to {}",
+        written,
+        started.elapsed().as_secs_f64(),
+        destinations
+    );
+    println!(
+        "Lost frames: {} ({:.2}%), CRC errors: {}, sequence gaps: {}",
+        lost,
+        100.0 * lost as f64 / (written + lost).max(1) as f64,
+        stats.crc_errors,
+        stats.sequence_gaps
+    );
+    result?;
+    stopped
+}
 
-    // 2. Connection
-    let mut device = bitalino::Bitalino::from_rfcomm(stream);
+/// Connect and identify the firmware, which `state()` needs to parse replies.
+fn connect_identified(profile: &config::Profile) -> Result<bitalino::Bitalino> {
+    let mut device = connect(profile)?;
+    let version = device.version()?;
+    println!("Firmware:   {}", version.trim());
+    Ok(device)
+}
+
+/// Voltage the LED starts blinking at for a threshold setting.
+fn threshold_volts(threshold: u8) -> f32 {
+    3.4 + (threshold as f32 / 63.0) * 0.4
+}
+
+fn print_battery(state: &bitalino::DeviceState) {
+    println!(
+        "Battery:    {:.2} V (raw {}){}",
+        state.battery_voltage(),
+        state.battery,
+        if state.is_battery_low() { "  LOW" } else { "" }
+    );
+    println!(
+        "Threshold:  {} (≈{:.2} V)",
+        state.battery_threshold,
+        threshold_volts(state.battery_threshold)
+    );
+}
+
+fn state(profile: &config::Profile) -> Result<()> {
+    let mut device = connect_identified(profile)?;
+    let state = device.state()?;
+
+    for (i, value) in state.analog.iter().enumerate() {
+        println!("A{}:         {}", i + 1, value);
+    }
+    print_battery(&state);
+    let [i1, i2, o1, o2] = state.digital;
+    println!("Digital:    I1={i1} I2={i2} O1={o1} O2={o2}");
+    Ok(())
+}
+
+fn battery(profile: &config::Profile, set: Option<u8>) -> Result<()> {
+    let mut device = connect_identified(profile)?;
+    if let Some(threshold) = set {
+        device.set_battery_threshold(threshold)?;
+        println!(
+            "Threshold set to {} (≈{:.2} V)",
+            threshold,
+            threshold_volts(threshold)
+        );
+    }
+    if device.is_bitalino2() {
+        print_battery(&device.state()?);
+    } else {
+        println!("Battery voltage is only reported by BITalino 2.0+ devices.");
+    }
+    Ok(())
+}
+
+/// Dashboard refresh period.
+const MONITOR_REFRESH: Duration = Duration::from_millis(250);
+/// Period of the dashboard's battery readings, each pausing the stream briefly.
+const BATTERY_CHECK: Duration = Duration::from_secs(60);
+/// Sparkline width in terminal columns.
+const SPARKLINE_WIDTH: usize = 60;
+
+fn monitor(profile: &config::Profile, window: Duration, plot: bool) -> Result<()> {
+    if plot && !cfg!(feature = "plot") {
+        anyhow::bail!("--plot needs a build with the `plot` feature (cargo build --features plot)");
+    }
+    let mac = profile.mac.as_deref().unwrap_or_default();
+    println!("Connecting to {mac}...");
+    let mut device = connect(profile)?;
+    let version = device
+        .version()
+        .unwrap_or_else(|_| "unknown firmware".into());
+
+    // With no threshold every reading reaches the callback: one now, then one
+    // from the read path every BATTERY_CHECK.
+    let (readings_tx, readings) = std::sync::mpsc::channel();
+    device.monitor_battery(BATTERY_CHECK, f32::INFINITY, move |reading| {
+        let _ = readings_tx.send((reading.voltage, reading.estimated));
+    });
+    device.poll_battery()?;
+    let mut battery = readings.try_iter().last();
+
+    install_signal_handlers();
+    let rate = device.start(profile.rate, profile.channels.clone())?;
+    // `start` sorts and deduplicates the channels; label what is streamed.
+    let channels = device.active_channels().to_vec();
+    let rate_hz = rate as u16 as usize;
+    let title = format!("BITalino {mac} ({}) @ {rate_hz} Hz", version.trim());
+
+    #[cfg(feature = "plot")]
+    if plot {
+        let result = plot::run(&mut device, &title, &channels, window);
+        let stopped = device.stop();
+        result?;
+        return stopped;
+    }
+
+    let frame_size = decode::frame_size(channels.len());
+    let mut dashboard = dashboard::Dashboard::new(
+        title,
+        channels,
+        rate_hz * window.as_secs().max(1) as usize,
+        SPARKLINE_WIDTH,
+    );
+    let mut stats = dashboard::LinkStats::default();
+
+    print!("{}", dashboard::ENTER);
+    let batch_size = (rate_hz / 20).max(1);
+    let mut last_refresh = Instant::now();
+    let mut frames_at_refresh = 0;
+    let result = loop {
+        if interrupted() {
+            break Ok(());
+        }
+        let batch = match device.read_frames_timed(batch_size) {
+            Ok(batch) => batch,
+            Err(e) => break Err(e),
+        };
+        dashboard.push(&batch.frames);
+        stats.frames += batch.frames.len() as u64;
+        stats.crc_errors += batch.crc_errors;
+        stats.sequence_gaps += batch.sequence_gaps;
+
+        let since_refresh = last_refresh.elapsed();
+        if since_refresh >= MONITOR_REFRESH {
+            battery = readings.try_iter().last().or(battery);
+            stats.battery = battery;
+            stats.lost = device.samples_received().saturating_sub(stats.frames);
+            stats.frames_per_sec =
+                (stats.frames - frames_at_refresh) as f64 / since_refresh.as_secs_f64();
+            stats.bytes_per_sec = stats.frames_per_sec * frame_size as f64;
+            frames_at_refresh = stats.frames;
+            last_refresh = Instant::now();
+
+            print!("{}", dashboard.render(&stats));
+            let _ = std::io::stdout().flush();
+        }
+    };
+
+    print!("{}", dashboard::LEAVE);
+    let stopped = device.stop();
+    println!(
+        "Received {} frames, lost {}, seq gaps {}, CRC errors {}",
+        stats.frames, stats.lost, stats.sequence_gaps, stats.crc_errors
+    );
+    result?;
+    stopped
+}
+
+fn demo(profile: &config::Profile) -> Result<()> {
+    println!(
+        "Using MAC: {}, PIN: {}",
+        profile.mac.as_deref().unwrap_or_default(),
+        profile.pin
+    );
+    println!("--- Initializing Bluetooth Sensor (Rust) ---");
+    let mut device = connect(profile)?;
 
     println!("Connected! Getting Version...");
     match device.version() {
@@ -44,6 +930,7 @@ fn run() -> Result<()> {
 
     // 3. Acquisition
     println!("Starting Acquisition (1000Hz)...");
+    install_signal_handlers();
     device.start(1000, vec![0, 1, 2, 3, 4, 5])?;
 
     if let Err(e) = device.wait_until_streaming(Duration::from_secs(2)) {
@@ -52,12 +939,18 @@ fn run() -> Result<()> {
 
     println!("Reading 10 batches of 100 samples...");
     for i in 0..10 {
+        if interrupted() {
+            println!("Interrupted.");
+            break;
+        }
         match device.read_frames(100) {
             Ok(frames) => {
                 if let Some(first) = frames.first() {
                     println!(
                         "[Batch {}] Seq: {:02} | Analog: {:?}",
-                        i, first.seq, first.analog
+                        i,
+                        first.seq,
+                        first.analog_values()
                     );
                 }
             }
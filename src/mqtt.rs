@@ -0,0 +1,303 @@
+//! Publish recorded batches to an MQTT broker (feature `mqtt`).
+//!
+//! [`MqttSink`] is a [`FrameSink`]: every `write_frames` call becomes one
+//! message on the configured topic, encoded as JSON or CBOR. The client runs
+//! its network loop on a background thread and never blocks acquisition: when
+//! the broker cannot keep up, messages are dropped with a warning.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, warn};
+use rumqttc::{Client, ConnectionError, Event, MqttOptions, Outgoing, QoS};
+use serde::Serialize;
+
+use crate::bitalino::Frame;
+use crate::recording::{FrameSink, RecordingInfo};
+
+/// Default MQTT port.
+const DEFAULT_PORT: u16 = 1883;
+
+/// Messages queued for the network thread before new ones are dropped.
+const QUEUE_CAPACITY: usize = 64;
+
+/// Pause before the network loop retries after losing the broker.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How long dropping the sink waits for queued messages to go out.
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Message payload encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MqttEncoding {
+    /// UTF-8 JSON object.
+    #[default]
+    Json,
+    /// CBOR (RFC 8949), the same structure as the JSON payload.
+    Cbor,
+}
+
+/// Broker, topic and delivery settings for [`MqttSink`].
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    /// Broker as `host` or `host:port` (port 1883 by default).
+    pub broker: String,
+    /// Topic every batch is published to.
+    pub topic: String,
+    /// Delivery guarantee, 0-2.
+    pub qos: u8,
+    /// Payload encoding.
+    pub encoding: MqttEncoding,
+    /// MQTT client identifier; must be unique per broker.
+    pub client_id: String,
+}
+
+impl MqttConfig {
+    /// JSON at QoS 0 on `bitalino/<mac>`, identified as `bitalino-<mac>`.
+    pub fn new(broker: impl Into<String>, mac: &str) -> Self {
+        let id = mac.replace(':', "").to_ascii_lowercase();
+        Self {
+            broker: broker.into(),
+            topic: format!("bitalino/{id}"),
+            qos: 0,
+            encoding: MqttEncoding::Json,
+            client_id: format!("bitalino-{id}"),
+        }
+    }
+}
+
+/// One published message.
+#[derive(Debug, Serialize)]
+struct BatchMessage<'a> {
+    device: &'a str,
+    sampling_rate: u16,
+    channels: &'a [String],
+    /// Number of frames published before this batch.
+    offset: u64,
+    frames: Vec<FrameMessage<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct FrameMessage<'a> {
+    seq: u8,
+    digital: [u8; 4],
+    analog: &'a [u16],
+}
+
+/// Serializes batches for one acquisition.
+struct Encoder {
+    encoding: MqttEncoding,
+    device: String,
+    sampling_rate: u16,
+    channels: Vec<String>,
+}
+
+impl Encoder {
+    fn new(encoding: MqttEncoding, info: &RecordingInfo) -> Self {
+        Self {
+            encoding,
+            device: info.mac.clone(),
+            sampling_rate: info.sampling_rate,
+            channels: info
+                .channels
+                .iter()
+                .map(|c| format!("A{}", c + 1))
+                .collect(),
+        }
+    }
+
+    fn encode(&self, offset: u64, frames: &[Frame]) -> io::Result<Vec<u8>> {
+        let message = BatchMessage {
+            device: &self.device,
+            sampling_rate: self.sampling_rate,
+            channels: &self.channels,
+            offset,
+            frames: frames
+                .iter()
+                .map(|f| FrameMessage {
+                    seq: f.seq,
+                    digital: f.digital,
+                    analog: f.analog_values(),
+                })
+                .collect(),
+        };
+        match self.encoding {
+            MqttEncoding::Json => Ok(serde_json::to_vec(&message)?),
+            MqttEncoding::Cbor => {
+                let mut payload = Vec::new();
+                ciborium::into_writer(&message, &mut payload)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+                Ok(payload)
+            }
+        }
+    }
+}
+
+/// Publishes each batch of frames as one MQTT message.
+pub struct MqttSink {
+    client: Client,
+    /// Tells the network thread to stop retrying an unreachable broker.
+    closing: Arc<AtomicBool>,
+    /// Signalled when the network thread exits.
+    finished: Receiver<()>,
+    topic: String,
+    qos: QoS,
+    encoder: Encoder,
+    frames_written: u64,
+}
+
+impl MqttSink {
+    /// Connect to the broker in `config` for the acquisition described by `info`.
+    ///
+    /// The connection is established in the background; batches written before
+    /// it is up are queued.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed broker address or QoS above 2,
+    /// and any error from spawning the network thread.
+    pub fn connect(config: &MqttConfig, info: &RecordingInfo) -> io::Result<Self> {
+        let (host, port) = match config.broker.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>().map_err(|_| {
+                    invalid_input(format!("invalid MQTT broker port in {:?}", config.broker))
+                })?,
+            ),
+            None => (config.broker.as_str(), DEFAULT_PORT),
+        };
+        let qos = match config.qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            other => return Err(invalid_input(format!("invalid MQTT QoS {other} (0-2)"))),
+        };
+
+        let mut options = MqttOptions::new(config.client_id.clone(), host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        let (client, mut connection) = Client::new(options, QUEUE_CAPACITY);
+        let broker = config.broker.clone();
+        let closing = Arc::new(AtomicBool::new(false));
+        let closing_flag = Arc::clone(&closing);
+        let (done, finished) = mpsc::channel();
+        thread::Builder::new()
+            .name("bitalino-mqtt".into())
+            .spawn(move || {
+                for event in connection.iter() {
+                    match event {
+                        Ok(Event::Outgoing(Outgoing::Disconnect))
+                        | Err(ConnectionError::RequestsDone) => break,
+                        Ok(event) => debug!("mqtt: {:?}", event),
+                        Err(_) if closing_flag.load(Ordering::Relaxed) => break,
+                        Err(e) => {
+                            warn!("MQTT broker {} unavailable: {}", broker, e);
+                            thread::sleep(RETRY_DELAY);
+                        }
+                    }
+                }
+                let _ = done.send(());
+            })?;
+
+        Ok(Self {
+            client,
+            closing,
+            finished,
+            topic: config.topic.clone(),
+            qos,
+            encoder: Encoder::new(config.encoding, info),
+            frames_written: 0,
+        })
+    }
+}
+
+impl FrameSink for MqttSink {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        if frames.is_empty() {
+            return Ok(());
+        }
+        let payload = self.encoder.encode(self.frames_written, frames)?;
+        if let Err(e) = self
+            .client
+            .try_publish(self.topic.as_str(), self.qos, false, payload)
+        {
+            warn!(
+                "Dropping {} frames, MQTT queue is full: {}",
+                frames.len(),
+                e
+            );
+        }
+        self.frames_written += frames.len() as u64;
+        Ok(())
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Messages are handed to the network thread as they are written.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for MqttSink {
+    fn drop(&mut self) {
+        // Queued after the pending publishes, so those go out first. An
+        // unreachable broker never sees it; give up after a short wait.
+        self.closing.store(true, Ordering::Relaxed);
+        if self.client.disconnect().is_ok() {
+            let _ = self.finished.recv_timeout(DISCONNECT_TIMEOUT);
+        }
+    }
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::ConnectionKind;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn encodes_batches_as_json_and_cbor() {
+        let info = RecordingInfo {
+            mac: "20:16:10:00:3D:4F".into(),
+            sampling_rate: 100,
+            channels: vec![0],
+            sensors: Vec::new(),
+            ports: Vec::new(),
+            is_bitalino2: true,
+            firmware_version: None,
+            connection: ConnectionKind::Bluetooth,
+            started: UNIX_EPOCH,
+        };
+        let mut config = MqttConfig::new("localhost", &info.mac);
+        assert_eq!(config.topic, "bitalino/201610003d4f");
+        let frames = [Frame::new(5, [0, 1, 0, 0], &[700])];
+
+        let json = Encoder::new(MqttEncoding::Json, &info)
+            .encode(10, &frames)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["offset"], 10);
+        assert_eq!(json["channels"], serde_json::json!(["A1"]));
+        assert_eq!(json["frames"][0]["analog"], serde_json::json!([700]));
+
+        let cbor = Encoder::new(MqttEncoding::Cbor, &info)
+            .encode(10, &frames)
+            .unwrap();
+        let cbor: serde_json::Value = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(cbor, json);
+
+        config.qos = 3;
+        assert!(MqttSink::connect(&config, &info).is_err());
+        config.broker = "localhost:port".into();
+        assert!(MqttSink::connect(&config, &info).is_err());
+    }
+}
@@ -0,0 +1,505 @@
+//! Record, process and display one acquisition at the same time.
+//!
+//! A [`Pipeline`] wires a [`BackgroundReader`] to any number of outputs. Each
+//! output runs on its own thread behind a bounded queue, so a slow disk or
+//! network sink does not stall the others. The queue's [`OverflowPolicy`]
+//! decides what happens when an output falls behind, and dropped batches are
+//! counted so a consumer can tell its stream has holes. [`Stage`]s can be
+//! placed between outputs. Every output receives the stream as transformed by
+//! the stages added before it, so raw data can be recorded while a filtered
+//! copy is plotted:
+//!
+//! ```no_run
+//! # use bitalino_rs::{create_sink, Bitalino, Pipeline, RecordFormat, RecordingInfo};
+//! # use bitalino_rs::dsp::{Filter, FilterBank};
+//! # use bitalino_rs::SamplingRate;
+//! # fn run(device: Bitalino, info: &RecordingInfo) -> anyhow::Result<()> {
+//! let pipeline = Pipeline::new(device)
+//!     .sink(create_sink("session.csv", RecordFormat::Csv, info)?)
+//!     .stage(FilterBank::new(SamplingRate::Hz1000, &[Filter::Notch { freq_hz: 50.0 }])?)
+//!     .live(|batch| println!("{} filtered frames", batch.frames.len()))
+//!     .start()
+//!     .map_err(|(_, e)| e)?;
+//! std::thread::sleep(std::time::Duration::from_secs(60));
+//! let (device, report) = pipeline.stop();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use anyhow::Result;
+use log::{debug, warn};
+
+use crate::bitalino::{Bitalino, FrameBatch};
+use crate::errors::DriverError;
+use crate::recording::FrameSink;
+use crate::streaming::{BackgroundReader, OverflowPolicy, Stage};
+
+/// Batches read per iteration when not set with [`Pipeline::batch_size`].
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Batches each output may fall behind by before its [`OverflowPolicy`]
+/// applies.
+const DEFAULT_QUEUE_LEN: usize = 32;
+
+/// Consumer behind one output queue.
+enum Consumer {
+    Sink(Box<dyn FrameSink + Send>),
+    Callback(Box<dyn FnMut(&FrameBatch) + Send>),
+}
+
+enum Step {
+    Stage(Box<dyn Stage>),
+    Output(Consumer, OverflowPolicy),
+}
+
+/// Builder wiring a device to sinks, stages and live callbacks.
+pub struct Pipeline {
+    device: Bitalino,
+    batch_size: usize,
+    queue_len: usize,
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    /// Pipeline reading from `device`, which must be acquiring by the time
+    /// [`start`](Self::start) is called.
+    pub fn new(device: Bitalino) -> Self {
+        Self {
+            device,
+            batch_size: DEFAULT_BATCH_SIZE,
+            queue_len: DEFAULT_QUEUE_LEN,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Frames per batch handed to the outputs (default: 100).
+    pub fn batch_size(mut self, frames: usize) -> Self {
+        self.batch_size = frames.max(1);
+        self
+    }
+
+    /// Batches each output may queue before its [`OverflowPolicy`] applies
+    /// (default: 32).
+    pub fn queue_len(mut self, batches: usize) -> Self {
+        self.queue_len = batches.max(1);
+        self
+    }
+
+    /// Transform the stream seen by the outputs added after this call.
+    pub fn stage<S: Stage + 'static>(mut self, stage: S) -> Self {
+        self.steps.push(Step::Stage(Box::new(stage)));
+        self
+    }
+
+    /// Write every batch to `sink`, waiting for it when it falls behind.
+    pub fn sink<S: FrameSink + Send + 'static>(self, sink: S) -> Self {
+        self.sink_with(sink, OverflowPolicy::Block)
+    }
+
+    /// Write every batch to `sink` with the given overflow policy.
+    pub fn sink_with<S: FrameSink + Send + 'static>(
+        mut self,
+        sink: S,
+        overflow: OverflowPolicy,
+    ) -> Self {
+        self.steps
+            .push(Step::Output(Consumer::Sink(Box::new(sink)), overflow));
+        self
+    }
+
+    /// Call `callback` with every batch on its own thread, discarding the
+    /// oldest queued batches while it falls behind
+    /// ([`OverflowPolicy::DropOldest`]).
+    pub fn live<F: FnMut(&FrameBatch) + Send + 'static>(self, callback: F) -> Self {
+        self.live_with(callback, OverflowPolicy::DropOldest)
+    }
+
+    /// Call `callback` with every batch on its own thread with the given
+    /// overflow policy.
+    pub fn live_with<F: FnMut(&FrameBatch) + Send + 'static>(
+        mut self,
+        callback: F,
+        overflow: OverflowPolicy,
+    ) -> Self {
+        self.steps.push(Step::Output(
+            Consumer::Callback(Box::new(callback)),
+            overflow,
+        ));
+        self
+    }
+
+    /// Spawn the output threads and the reader.
+    ///
+    /// An output with [`OverflowPolicy::Error`] that falls behind stops the
+    /// reader, which reports a [`DriverError::Overflow`] as
+    /// [`PipelineReport::read_error`].
+    ///
+    /// # Errors
+    /// Fails if acquisition is not running or a thread cannot be spawned; the
+    /// driver is returned with the error.
+    pub fn start(self) -> std::result::Result<RunningPipeline, (Box<Bitalino>, anyhow::Error)> {
+        let mut chain = Vec::new();
+        let mut outputs = Vec::new();
+        for (index, step) in self.steps.into_iter().enumerate() {
+            match step {
+                Step::Stage(stage) => chain.push(Link::Stage(stage)),
+                Step::Output(consumer, overflow) => {
+                    let queue = Arc::new(BatchQueue::new(self.queue_len, overflow));
+                    let rx = CloseOnDrop(Arc::clone(&queue));
+                    let handle = thread::Builder::new()
+                        .name(format!("bitalino-output-{index}"))
+                        .spawn(move || drain(consumer, rx));
+                    match handle {
+                        Ok(handle) => outputs.push(Output {
+                            handle,
+                            queue: Arc::clone(&queue),
+                        }),
+                        Err(e) => return Err((Box::new(self.device), e.into())),
+                    }
+                    chain.push(Link::Output(CloseOnDrop(queue)));
+                }
+            }
+        }
+
+        let reader =
+            BackgroundReader::spawn_fallible(self.device, self.batch_size, move |mut batch| {
+                for link in chain.iter_mut() {
+                    match link {
+                        Link::Stage(stage) => batch = stage.process(batch),
+                        Link::Output(_) if batch.frames.is_empty() => {}
+                        Link::Output(queue) => queue.0.push(batch.clone())?,
+                    }
+                }
+                Ok(())
+            })?;
+        Ok(RunningPipeline { reader, outputs })
+    }
+}
+
+/// A step as run on the reader thread.
+enum Link {
+    Stage(Box<dyn Stage>),
+    Output(CloseOnDrop),
+}
+
+struct Output {
+    handle: JoinHandle<(u64, Option<io::Error>)>,
+    queue: Arc<BatchQueue>,
+}
+
+/// Bounded FIFO of batches between the read loop and one consumer thread.
+///
+/// Closing it (from either side) wakes both: a blocked producer discards its
+/// batch and the consumer drains what is left, then sees the end.
+struct BatchQueue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+}
+
+#[derive(Default)]
+struct QueueState {
+    batches: VecDeque<FrameBatch>,
+    closed: bool,
+}
+
+impl BatchQueue {
+    /// Queue holding at most `capacity` (at least 1) batches.
+    fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState::default()),
+            changed: Condvar::new(),
+            capacity: capacity.max(1),
+            policy,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Enqueue `batch`, applying the overflow policy if the queue is full.
+    ///
+    /// # Errors
+    /// With [`OverflowPolicy::Error`], fails when the queue is full.
+    fn push(&self, batch: FrameBatch) -> Result<()> {
+        let mut state = self.lock();
+        if state.batches.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::Block => {
+                    state = self
+                        .changed
+                        .wait_while(state, |s| s.batches.len() >= self.capacity && !s.closed)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+                OverflowPolicy::DropOldest => {
+                    state.batches.pop_front();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                OverflowPolicy::Error => {
+                    return Err(DriverError::Overflow(format!(
+                        "consumer fell {} batches behind",
+                        self.capacity
+                    ))
+                    .into());
+                }
+            }
+        }
+        if !state.closed {
+            state.batches.push_back(batch);
+            self.changed.notify_all();
+        }
+        Ok(())
+    }
+
+    /// Next batch, waiting for one; `None` once closed and drained.
+    fn pop(&self) -> Option<FrameBatch> {
+        let mut state = self
+            .changed
+            .wait_while(self.lock(), |s| s.batches.is_empty() && !s.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        let batch = state.batches.pop_front();
+        self.changed.notify_all();
+        batch
+    }
+
+    /// Stop accepting batches; queued ones can still be popped.
+    fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+
+    /// Batches discarded by [`OverflowPolicy::DropOldest`] or
+    /// [`OverflowPolicy::DropNewest`].
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Closes a [`BatchQueue`] when dropped, so the other side never waits on a
+/// thread that has exited (or panicked).
+struct CloseOnDrop(Arc<BatchQueue>);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Feed queued batches to `consumer` until the reader hangs up, then close
+/// the sink.
+///
+/// After a write error the queue is still drained, so a failed sink never
+/// blocks the reader.
+fn drain(mut consumer: Consumer, rx: CloseOnDrop) -> (u64, Option<io::Error>) {
+    let mut error = None;
+    let mut frames = 0;
+    while let Some(batch) = rx.0.pop() {
+        if error.is_some() {
+            continue;
+        }
+        match &mut consumer {
+            Consumer::Sink(sink) => match sink.write_frames(&batch.frames) {
+                Ok(()) => frames += batch.frames.len() as u64,
+                Err(e) => {
+                    warn!("pipeline sink failed, discarding its batches: {e}");
+                    error = Some(e);
+                }
+            },
+            Consumer::Callback(callback) => {
+                callback(&batch);
+                frames += batch.frames.len() as u64;
+            }
+        }
+    }
+    if let (Consumer::Sink(sink), None) = (&mut consumer, &error) {
+        error = sink.close().err();
+    }
+    debug!("pipeline output finished after {frames} frames");
+    (frames, error)
+}
+
+/// What one output received, in the order outputs were added.
+#[derive(Debug)]
+pub struct OutputReport {
+    /// Frames written to the sink or passed to the callback.
+    pub frames: u64,
+    /// Batches discarded because the queue was full
+    /// ([`OverflowPolicy::DropOldest`] or [`OverflowPolicy::DropNewest`]).
+    pub dropped_batches: u64,
+    /// Write or close error that stopped a sink.
+    pub error: Option<io::Error>,
+}
+
+/// Outcome of a pipeline run, returned by [`RunningPipeline::stop`].
+#[derive(Debug)]
+pub struct PipelineReport {
+    /// Error that ended the read loop early, if any.
+    pub read_error: Option<anyhow::Error>,
+    /// One entry per sink or callback.
+    pub outputs: Vec<OutputReport>,
+}
+
+/// Handle to a started [`Pipeline`].
+///
+/// Dropping it stops the reader without waiting; outputs still drain what
+/// was queued.
+pub struct RunningPipeline {
+    reader: BackgroundReader,
+    outputs: Vec<Output>,
+}
+
+impl RunningPipeline {
+    /// Whether the reader is still running (it exits on a read error).
+    pub fn is_running(&self) -> bool {
+        self.reader.is_running()
+    }
+
+    /// Batches discarded so far by each output, in the order outputs were
+    /// added.
+    pub fn dropped_batches(&self) -> Vec<u64> {
+        self.outputs.iter().map(|o| o.queue.dropped()).collect()
+    }
+
+    /// Stop reading, let every output finish its queue and return the driver.
+    /// Acquisition keeps running on the device.
+    pub fn stop(self) -> (Bitalino, PipelineReport) {
+        let (device, result) = self.reader.stop();
+        // The reader thread has closed every queue, so every output ends.
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|output| {
+                let (frames, error) = output
+                    .handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                OutputReport {
+                    frames,
+                    dropped_batches: output.queue.dropped(),
+                    error,
+                }
+            })
+            .collect();
+        (
+            device,
+            PipelineReport {
+                read_error: result.err(),
+                outputs,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::{Frame, GapFill, SamplingRate};
+    use crate::streaming::fixtures::{acquiring_replay, KeepFirst};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Records batch lengths into a shared list.
+    struct Lengths(Arc<Mutex<Vec<usize>>>);
+
+    impl FrameSink for Lengths {
+        fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+            self.0.lock().unwrap().push(frames.len());
+            Ok(())
+        }
+
+        fn frames_written(&self) -> u64 {
+            self.0.lock().unwrap().iter().sum::<usize>() as u64
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn outputs_see_the_stages_added_before_them() {
+        let device = acquiring_replay(20);
+
+        let raw = Arc::new(Mutex::new(Vec::new()));
+        let reduced = Arc::new(Mutex::new(Vec::new()));
+        let live = Arc::clone(&reduced);
+        let pipeline = Pipeline::new(device)
+            .batch_size(5)
+            .sink(Lengths(Arc::clone(&raw)))
+            .stage(KeepFirst)
+            .live(move |batch| live.lock().unwrap().push(batch.frames.len()))
+            .start()
+            .map_err(|(_, e)| e)
+            .unwrap();
+        while pipeline.is_running() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let (device, report) = pipeline.stop();
+
+        assert!(device.is_acquiring());
+        assert!(
+            report.read_error.is_some(),
+            "capture ends like a closed link"
+        );
+        assert_eq!(*raw.lock().unwrap(), [5, 5, 5, 5]);
+        assert_eq!(*reduced.lock().unwrap(), [1, 1, 1, 1]);
+        assert_eq!(report.outputs[0].frames, 20);
+        assert_eq!(report.outputs[1].frames, 4);
+        assert!(report.outputs.iter().all(|o| o.error.is_none()));
+    }
+
+    #[test]
+    fn full_queues_follow_their_overflow_policy() {
+        let batch = |n: usize| FrameBatch {
+            frames: vec![Frame::new(0, [0; 4], &[0]); n],
+            first_sample_index: 0,
+            channels: vec![0],
+            sampling_rate: SamplingRate::Hz1000,
+            timestamp_us: 0,
+            crc_errors: 0,
+            sequence_gaps: 0,
+            partial: false,
+            link_quality: None,
+            gap_fill: GapFill::Off,
+        };
+        let lengths = |queue: &BatchQueue| {
+            queue.close();
+            std::iter::from_fn(|| queue.pop())
+                .map(|b| b.frames.len())
+                .collect::<Vec<_>>()
+        };
+
+        let oldest = BatchQueue::new(2, OverflowPolicy::DropOldest);
+        let newest = BatchQueue::new(2, OverflowPolicy::DropNewest);
+        let error = BatchQueue::new(2, OverflowPolicy::Error);
+        for n in 1..=3 {
+            oldest.push(batch(n)).unwrap();
+            newest.push(batch(n)).unwrap();
+        }
+        error.push(batch(1)).unwrap();
+        error.push(batch(2)).unwrap();
+        let err = error.push(batch(3)).unwrap_err();
+
+        assert_eq!((lengths(&oldest), oldest.dropped()), (vec![2, 3], 1));
+        assert_eq!((lengths(&newest), newest.dropped()), (vec![1, 2], 1));
+        assert!(matches!(
+            err.downcast_ref::<DriverError>(),
+            Some(DriverError::Overflow(_))
+        ));
+        assert_eq!(lengths(&error), [1, 2]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Real-time plot window for quick signal checks (`plot` feature).
+//!
+//! [`Bitalino::live_plot`] and `bitalino monitor --plot` open a window with
+//! the last few seconds of each plotted channel, so signal quality can be
+//! checked in the field without writing a plotting loop. Frames are read on
+//! a helper thread while the window runs on the caller's thread, which must
+//! be the main thread on most platforms.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use eframe::egui;
+use egui_plot::{Line, Plot};
+
+use crate::bitalino::Bitalino;
+
+/// How often the window redraws.
+const REPAINT: Duration = Duration::from_millis(33);
+
+/// Seconds of signal shown by [`Bitalino::live_plot`].
+pub(crate) const DEFAULT_WINDOW: Duration = Duration::from_secs(5);
+
+/// Samples and link counters shared by the reader thread and the window.
+#[derive(Default)]
+struct Shared {
+    /// Per plotted channel: time in seconds and value of the visible samples.
+    history: Vec<VecDeque<[f64; 2]>>,
+    frames: u64,
+    crc_errors: usize,
+    sequence_gaps: usize,
+    /// Why reading stopped, shown until the window is closed.
+    error: Option<String>,
+}
+
+/// Plot analog `channels` (0-5, all acquired) of the running acquisition on
+/// `device`, `window` of signal at a time, until the window is closed.
+///
+/// # Errors
+/// Fails if the window cannot be opened, or with the read error that stopped
+/// the plot.
+pub(crate) fn run(
+    device: &mut Bitalino,
+    title: &str,
+    channels: &[u8],
+    window: Duration,
+) -> Result<()> {
+    let rate_hz = device.sampling_rate() as u16 as f64;
+    let capacity = ((rate_hz * window.as_secs_f64()) as usize).max(1);
+    let batch_size = (rate_hz as usize / 20).max(1);
+    let shared = Arc::new(Mutex::new(Shared {
+        history: vec![VecDeque::with_capacity(capacity); channels.len()],
+        ..Shared::default()
+    }));
+    let closed = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        let reader = scope.spawn(|| -> Result<()> {
+            while !closed.load(Ordering::Relaxed) {
+                let batch = match device.read_frames_timed(batch_size) {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        shared.lock().unwrap().error = Some(format!("{e:#}"));
+                        return Err(e);
+                    }
+                };
+                let columns: Vec<Option<usize>> = channels
+                    .iter()
+                    .map(|ch| batch.channels.iter().position(|c| c == ch))
+                    .collect();
+                let mut shared = shared.lock().unwrap();
+                for frame in batch.frames.iter().filter(|f| !f.filled) {
+                    let t = frame.sample_index as f64 / rate_hz;
+                    for (history, column) in shared.history.iter_mut().zip(&columns) {
+                        let Some(column) = *column else { continue };
+                        if history.len() == capacity {
+                            history.pop_front();
+                        }
+                        history.push_back([t, frame.analog[column] as f64]);
+                    }
+                }
+                shared.frames += batch.frames.len() as u64;
+                shared.crc_errors += batch.crc_errors;
+                shared.sequence_gaps += batch.sequence_gaps;
+            }
+            Ok(())
+        });
+
+        let app = LivePlot {
+            channels: channels.to_vec(),
+            shared: shared.clone(),
+        };
+        // Stop the reader however the window ends: winit panics when it is
+        // not on the main thread, and the scope would wait for the reader.
+        let stop_reader = StopOnDrop(&closed);
+        let opened = eframe::run_native(
+            title,
+            eframe::NativeOptions::default(),
+            Box::new(|_| Box::new(app)),
+        );
+        drop(stop_reader);
+        let read = reader.join().expect("plot reader thread panicked");
+        opened.map_err(|e| anyhow!("Failed to open the plot window: {e}"))?;
+        read
+    })
+}
+
+/// Sets its flag when dropped, including during a panic.
+struct StopOnDrop<'a>(&'a AtomicBool);
+
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The window: one plot per channel, x axes linked, and a status line.
+struct LivePlot {
+    channels: Vec<u8>,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl eframe::App for LivePlot {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let shared = self.shared.lock().unwrap();
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} frames, {} CRC errors, {} sequence gaps",
+                    shared.frames, shared.crc_errors, shared.sequence_gaps
+                ));
+                if let Some(error) = &shared.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let height = ui.available_height() / self.channels.len().max(1) as f32;
+            for (ch, history) in self.channels.iter().zip(&shared.history) {
+                let points: Vec<[f64; 2]> = history.iter().copied().collect();
+                Plot::new(ch)
+                    .height(height)
+                    .link_axis(egui::Id::new("time"), true, false)
+                    .y_axis_label(format!("A{}", ch + 1))
+                    .allow_drag(false)
+                    .allow_zoom(false)
+                    .allow_scroll(false)
+                    .show(ui, |plot| plot.line(Line::new(points)));
+            }
+        });
+        ctx.request_repaint_after(REPAINT);
+    }
+}
@@ -0,0 +1,184 @@
+//! Ready-made acquisition setups for common sensor kits.
+//!
+//! A [`Preset`] bundles the analog inputs a kit is wired to, the sensor on
+//! each, a recommended sampling rate and default filters, so a first session
+//! needs no knowledge of the board layout:
+//!
+//! ```no_run
+//! # use bitalino_rs::{Bitalino, Preset};
+//! # fn run(device: &mut Bitalino) -> anyhow::Result<()> {
+//! device.start_preset(Preset::EDA_ECG)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Inputs follow the BITalino (r)evolution board: EMG on A1, ECG on A2, EDA on
+//! A3 and EEG on A4. The default filters are applied to every channel of the
+//! preset, so presets mixing sensors only low-pass, leaving the EDA level
+//! intact. None includes a mains notch, which depends on the local 50 or 60 Hz
+//! supply.
+
+use anyhow::Result;
+
+use crate::bitalino::SamplingRate;
+use crate::dsp::{Filter, FilterBank};
+use crate::sensors::Sensor;
+
+/// Channels, sensors, rate and filters for one sensor kit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preset {
+    /// Short name, as accepted by [`from_name`](Self::from_name).
+    pub name: &'static str,
+    /// One-line description of the setup.
+    pub description: &'static str,
+    /// Recommended sampling rate.
+    pub rate: SamplingRate,
+    /// Analog inputs (0-5) and the sensor wired to each.
+    pub inputs: &'static [(u8, Sensor)],
+    /// Filters suggested for live views, applied to every channel.
+    pub filters: &'static [Filter],
+}
+
+impl Preset {
+    /// ECG on A2, band-passed to 0.5-40 Hz.
+    pub const ECG_SINGLE: Preset = Preset {
+        name: "ecg",
+        description: "ECG on A2",
+        rate: SamplingRate::Hz1000,
+        inputs: &[(1, Sensor::Ecg)],
+        filters: &[Filter::BandPass {
+            low_hz: 0.5,
+            high_hz: 40.0,
+            order: 2,
+        }],
+    };
+
+    /// EMG on A1, band-passed to 20-450 Hz.
+    pub const EMG_SINGLE: Preset = Preset {
+        name: "emg",
+        description: "EMG on A1",
+        rate: SamplingRate::Hz1000,
+        inputs: &[(0, Sensor::Emg)],
+        filters: &[Filter::BandPass {
+            low_hz: 20.0,
+            high_hz: 450.0,
+            order: 2,
+        }],
+    };
+
+    /// EDA on A3 at 100 Hz, low-passed at 5 Hz.
+    pub const EDA_SINGLE: Preset = Preset {
+        name: "eda",
+        description: "EDA on A3",
+        rate: SamplingRate::Hz100,
+        inputs: &[(2, Sensor::Eda)],
+        filters: &[Filter::LowPass {
+            cutoff_hz: 5.0,
+            order: 2,
+        }],
+    };
+
+    /// EEG on A4, band-passed to 0.5-40 Hz.
+    pub const EEG_SINGLE: Preset = Preset {
+        name: "eeg",
+        description: "EEG on A4",
+        rate: SamplingRate::Hz1000,
+        inputs: &[(3, Sensor::Eeg)],
+        filters: &[Filter::BandPass {
+            low_hz: 0.5,
+            high_hz: 40.0,
+            order: 2,
+        }],
+    };
+
+    /// ECG on A2 and EDA on A3, low-passed at 40 Hz.
+    pub const EDA_ECG: Preset = Preset {
+        name: "eda_ecg",
+        description: "ECG on A2 and EDA on A3",
+        rate: SamplingRate::Hz1000,
+        inputs: &[(1, Sensor::Ecg), (2, Sensor::Eda)],
+        filters: &[Filter::LowPass {
+            cutoff_hz: 40.0,
+            order: 2,
+        }],
+    };
+
+    /// EMG on A1, ECG on A2 and EDA on A3, unfiltered: the EMG band and the
+    /// EDA level have no filter in common.
+    pub const PSYCHO_PHYSIOLOGY: Preset = Preset {
+        name: "psychophysiology",
+        description: "EMG on A1, ECG on A2 and EDA on A3",
+        rate: SamplingRate::Hz1000,
+        inputs: &[(0, Sensor::Emg), (1, Sensor::Ecg), (2, Sensor::Eda)],
+        filters: &[],
+    };
+
+    /// Every preset, in the order listed by help texts.
+    pub const ALL: [Preset; 6] = [
+        Preset::ECG_SINGLE,
+        Preset::EMG_SINGLE,
+        Preset::EDA_SINGLE,
+        Preset::EEG_SINGLE,
+        Preset::EDA_ECG,
+        Preset::PSYCHO_PHYSIOLOGY,
+    ];
+
+    /// Look a preset up by name, ignoring case.
+    ///
+    /// # Errors
+    /// Fails with the list of known names if `name` is not one of them.
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|p| p.name).collect();
+                anyhow::anyhow!("Unknown preset {name:?}. Known: {}", names.join(", "))
+            })
+    }
+
+    /// Analog channels to acquire, in frame order.
+    pub fn channels(&self) -> Vec<u8> {
+        self.inputs.iter().map(|&(channel, _)| channel).collect()
+    }
+
+    /// Sensor on analog `channel`, or [`Sensor::Raw`] if the preset does not
+    /// use it.
+    #[allow(dead_code)]
+    pub fn sensor(&self, channel: u8) -> Sensor {
+        self.inputs
+            .iter()
+            .find(|&&(c, _)| c == channel)
+            .map_or(Sensor::Raw, |&(_, sensor)| sensor)
+    }
+
+    /// The default filters, ready to run at `rate` (normally [`Self::rate`]).
+    ///
+    /// # Errors
+    /// Fails if a filter frequency is above the Nyquist frequency of `rate`.
+    #[allow(dead_code)]
+    pub fn filter_bank(&self, rate: SamplingRate) -> Result<FilterBank> {
+        FilterBank::new(rate, self.filters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_resolve_by_name_and_filter_at_their_rate() {
+        assert_eq!(Preset::from_name("EDA_ECG").unwrap(), Preset::EDA_ECG);
+        assert!(Preset::from_name("ekg")
+            .unwrap_err()
+            .to_string()
+            .contains("ecg, emg"));
+        for preset in Preset::ALL {
+            preset.filter_bank(preset.rate).unwrap();
+            assert!(preset.channels().windows(2).all(|w| w[0] < w[1]));
+        }
+        assert_eq!(Preset::PSYCHO_PHYSIOLOGY.channels(), [0, 1, 2]);
+        assert_eq!(Preset::EDA_ECG.sensor(2), Sensor::Eda);
+        assert_eq!(Preset::EDA_ECG.sensor(0), Sensor::Raw);
+    }
+}
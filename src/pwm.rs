@@ -0,0 +1,161 @@
+//! Timed PWM output: ramps and patterns played by a scheduler thread.
+//!
+//! [`Bitalino::pwm_pattern`](crate::Bitalino::pwm_pattern) and
+//! [`Bitalino::pwm_ramp`](crate::Bitalino::pwm_ramp) hand a list of
+//! `(value, duration)` steps to a thread that writes each value on its own
+//! handle to the link, so an LED or haptic driver follows a schedule while
+//! the caller keeps reading frames. Steps are timed from the start of the
+//! schedule, so slow writes delay one step without shifting the rest.
+//!
+//! Each value takes [`MIN_STEP`] to send, which bounds how fast the output
+//! can change: a ramp uses at most one step per [`MIN_STEP`], and shorter
+//! pattern steps are stretched to it.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::bitalino::{device_log, CMD_PWM_PREFIX, COMMAND_DELAY};
+
+/// Time taken to send one PWM value: the command byte and the value, each
+/// followed by the command delay.
+pub const MIN_STEP: Duration = COMMAND_DELAY.saturating_mul(2);
+
+/// Longest sleep between checks for cancellation.
+const CANCEL_POLL: Duration = Duration::from_millis(20);
+
+/// Send the two-byte PWM command for `value`.
+pub(crate) fn write_pwm<W: Write + ?Sized>(writer: &mut W, value: u8) -> io::Result<()> {
+    writer.write_all(&[CMD_PWM_PREFIX])?;
+    writer.flush()?;
+    thread::sleep(COMMAND_DELAY);
+    writer.write_all(&[value])?;
+    writer.flush()?;
+    thread::sleep(COMMAND_DELAY);
+    Ok(())
+}
+
+/// Steps moving linearly from `from` to `to` over `duration`, at most one per
+/// [`MIN_STEP`]. The last step holds `to` with no duration.
+pub(crate) fn ramp_steps(from: u8, to: u8, duration: Duration) -> Vec<(u8, Duration)> {
+    let delta = to as i32 - from as i32;
+    let n = (duration.as_nanos() / MIN_STEP.as_nanos()).min(delta.unsigned_abs() as u128) as u32;
+    if n == 0 {
+        return vec![(to, Duration::ZERO)];
+    }
+    let hold = duration / n;
+    (0..n)
+        .map(|i| {
+            let value = from as i32 + (delta * i as i32 + n as i32 / 2 * delta.signum()) / n as i32;
+            (value as u8, hold)
+        })
+        .chain(std::iter::once((to, Duration::ZERO)))
+        .collect()
+}
+
+/// Handle to a running PWM schedule.
+///
+/// Dropping the handle leaves the schedule running; it ends after its last
+/// step, on [`cancel`](Self::cancel), or when the driver sets the output
+/// again with `pwm()` or starts another schedule.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct PwmSchedule {
+    cancelled: Arc<AtomicBool>,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+impl PwmSchedule {
+    /// Play `steps` on `writer`, holding `lock` while each value is sent so
+    /// the driver's own commands are never interleaved with it.
+    pub(crate) fn spawn(
+        mut writer: Box<dyn Write + Send>,
+        lock: Arc<Mutex<()>>,
+        cancelled: Arc<AtomicBool>,
+        steps: Vec<(u8, Duration)>,
+        label: String,
+    ) -> Result<Self> {
+        let flag = Arc::clone(&cancelled);
+        let handle = thread::Builder::new()
+            .name("bitalino-pwm".into())
+            .spawn(move || {
+                let mut due = Instant::now();
+                for (value, hold) in steps {
+                    if flag.load(Ordering::Relaxed) {
+                        device_log!(debug, label, "PWM schedule cancelled");
+                        return Ok(());
+                    }
+                    {
+                        let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+                        write_pwm(writer.as_mut(), value)?;
+                    }
+                    due += hold;
+                    while !flag.load(Ordering::Relaxed) {
+                        let left = due.saturating_duration_since(Instant::now());
+                        if left.is_zero() {
+                            break;
+                        }
+                        thread::sleep(left.min(CANCEL_POLL));
+                    }
+                }
+                Ok(())
+            })?;
+        Ok(Self { cancelled, handle })
+    }
+
+    /// Whether steps are still being played.
+    #[allow(dead_code)]
+    pub fn is_running(&self) -> bool {
+        !self.handle.is_finished()
+    }
+
+    /// Wait for the last step to be sent.
+    ///
+    /// # Errors
+    /// Fails if a write to the device failed, which ends the schedule.
+    #[allow(dead_code)]
+    pub fn wait(self) -> Result<()> {
+        self.handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            .map_err(Into::into)
+    }
+
+    /// Stop after the value being sent, leaving the output at that value.
+    ///
+    /// # Errors
+    /// Fails if a write to the device had already failed.
+    #[allow(dead_code)]
+    pub fn cancel(self) -> Result<()> {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.wait()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramps_step_at_most_once_per_write() {
+        let steps = ramp_steps(0, 255, Duration::from_secs(1));
+        assert_eq!(steps.len(), 11);
+        assert_eq!(steps[0], (0, Duration::from_millis(100)));
+        assert_eq!(steps[5].0, 128);
+        assert_eq!(steps[10], (255, Duration::ZERO));
+
+        // Downwards, and limited by the number of distinct values.
+        let steps = ramp_steps(10, 7, Duration::from_secs(10));
+        let values: Vec<u8> = steps.iter().map(|&(v, _)| v).collect();
+        assert_eq!(values, [10, 9, 8, 7]);
+        assert!(steps[..3]
+            .iter()
+            .all(|&(_, d)| d == Duration::from_secs(10) / 3));
+
+        assert_eq!(ramp_steps(0, 200, Duration::ZERO), [(200, Duration::ZERO)]);
+    }
+}
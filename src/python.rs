@@ -0,0 +1,3696 @@
+//! PyO3 bindings: the `_bitalino_core` extension module.
+//!
+//! Compiled with the `python` feature (on by default). The classes here wrap
+//! the driver types one to one, release the GIL around blocking device calls
+//! and map driver errors onto the exception hierarchy in [`exceptions`].
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDict, PyIterator, PyList, PySlice, PyTuple, PyType,
+};
+
+mod aio;
+mod exceptions;
+pub(crate) mod logging;
+
+use exceptions::{to_py_err, NotInAcquisitionError};
+
+use crate::bitalino::device_log;
+use crate::bluetooth::{
+    BluetoothConnector, CancelToken, ConnectProgress, LinkQuality, PairingRequest, PairingResponse,
+    RfcommStream,
+};
+use crate::dsp;
+use crate::sensors::RESOLUTION_BITS;
+use crate::{
+    AcquisitionStats, Annotation, Bitalino, ChannelStats, CrcPolicy, DeviceInfo, DeviceRegistry,
+    DeviceState, FlowControl, Frame, FrameBatch, GapFill, OutputCommand, OutputEvent,
+    OverflowPolicy, Pipeline, Preset, PwmSchedule, ReadOptions, ReplayTransport, RunningPipeline,
+    SamplingRate, Sensor, SerialConfig, SerialPortEntry, Session, MAX_ANALOG_CHANNELS,
+};
+
+/// A single BITalino data frame (dataclass-like).
+///
+/// Attributes:
+///     sequence: Frame sequence number (0-15, wrapping). Use to detect dropped frames.
+///     digital (list[int]): Digital input values [I1, I2, O1, O2] as list of 0/1.
+///     analog: Analog channel values for configured channels: 10-bit (0-1023),
+///         except the fifth and sixth values of 5- and 6-channel frames, which
+///         are 6-bit (0-63). See ``resolution_bits``.
+///     sample_index: Absolute sample index since ``start()``, counting frames
+///         lost to CRC errors or sequence gaps. 0 for frames built by hand.
+///     filled: True for a placeholder inserted by ``read_timed(gap_fill=...)``
+///         where a frame was lost.
+#[pyclass(
+    name = "Frame",
+    module = "bitalino_rs._bitalino_core",
+    frozen,
+    eq,
+    from_py_object
+)]
+#[derive(Clone, PartialEq, Eq)]
+struct PyFrame {
+    #[pyo3(get)]
+    sequence: u8,
+    // Store raw digital bits; expose as list via custom getter to avoid bytes()
+    digital: Vec<u8>,
+    #[pyo3(get)]
+    analog: Vec<u16>,
+    #[pyo3(get)]
+    sample_index: u64,
+    #[pyo3(get)]
+    filled: bool,
+}
+
+#[pymethods]
+impl PyFrame {
+    /// Raises:
+    ///     ValueError: If ``digital`` does not hold 4 values or ``analog`` more
+    ///         than 6.
+    #[new]
+    #[pyo3(signature = (sequence, digital, analog, sample_index=0, filled=false))]
+    fn new(
+        sequence: u8,
+        digital: Vec<u8>,
+        analog: Vec<u16>,
+        sample_index: u64,
+        filled: bool,
+    ) -> PyResult<Self> {
+        if digital.len() != 4 || analog.len() > MAX_ANALOG_CHANNELS {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Frame needs 4 digital values and at most {} analog values; got {} and {}",
+                MAX_ANALOG_CHANNELS,
+                digital.len(),
+                analog.len()
+            )));
+        }
+        Ok(PyFrame {
+            sequence,
+            digital,
+            analog,
+            sample_index,
+            filled,
+        })
+    }
+
+    /// Pickle support: rebuild through the constructor.
+    fn __reduce__<'py>(
+        slf: &Bound<'py, Self>,
+    ) -> PyResult<(Bound<'py, PyType>, Bound<'py, PyTuple>)> {
+        let f = slf.get();
+        let args = (
+            f.sequence,
+            PyList::new(slf.py(), &f.digital)?,
+            f.analog.clone(),
+            f.sample_index,
+            f.filled,
+        )
+            .into_pyobject(slf.py())?;
+        Ok((slf.get_type(), args))
+    }
+
+    /// Frames are immutable, so copies share the original.
+    fn __copy__(slf: Bound<'_, Self>) -> Bound<'_, Self> {
+        slf
+    }
+
+    fn __deepcopy__<'py>(slf: Bound<'py, Self>, _memo: &Bound<'py, PyAny>) -> Bound<'py, Self> {
+        slf
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Frame(sequence={}, digital={:?}, analog={:?})",
+            self.sequence, self.digital, self.analog
+        )
+    }
+
+    fn __str__(&self) -> String {
+        format!(
+            "Frame(seq={}, d={:?}, a={:?})",
+            self.sequence, self.digital, self.analog
+        )
+    }
+
+    /// Digital channel states as a Python list [I1, I2, O1, O2].
+    #[getter]
+    fn digital(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
+        Ok(PyList::new(py, &self.digital)?.into())
+    }
+
+    fn __hash__(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.sequence.hash(&mut hasher);
+        self.digital.hash(&mut hasher);
+        self.analog.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Convert to dictionary for easy serialization.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("sequence", self.sequence)?;
+        dict.set_item("digital", self.digital.clone())?;
+        dict.set_item("analog", self.analog.clone())?;
+        dict.set_item("sample_index", self.sample_index)?;
+        dict.set_item("filled", self.filled)?;
+        Ok(dict)
+    }
+
+    /// Number of analog channels in this frame.
+    #[getter]
+    fn n_channels(&self) -> usize {
+        self.analog.len()
+    }
+
+    /// list[int]: ADC resolution in bits of each analog value, e.g.
+    /// ``[10, 10, 10, 10, 6]``.
+    #[getter]
+    fn resolution_bits(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
+        Ok(PyList::new(py, &RESOLUTION_BITS[..self.analog.len()])?.into())
+    }
+}
+
+impl From<&PyFrame> for Frame {
+    fn from(f: &PyFrame) -> Self {
+        let mut digital = [0u8; 4];
+        digital.copy_from_slice(&f.digital);
+        let mut frame = Frame::new(f.sequence, digital, &f.analog);
+        frame.sample_index = f.sample_index;
+        frame.filled = f.filled;
+        frame
+    }
+}
+
+impl From<Frame> for PyFrame {
+    fn from(f: Frame) -> Self {
+        PyFrame {
+            sequence: f.seq,
+            digital: f.digital.to_vec(),
+            analog: f.analog_values().to_vec(),
+            sample_index: f.sample_index,
+            filled: f.filled,
+        }
+    }
+}
+
+/// Result from reading a batch of frames, includes timing info.
+///
+/// Behaves as a sequence of Frame objects: ``len(batch)``, ``batch[5]``,
+/// ``for frame in batch``. Slicing returns a new FrameBatch whose counters and
+/// timestamps still describe the read it came from.
+///
+/// Attributes:
+///     frames: List of Frame objects.
+///     first_sample_index: ``sample_index`` of the first frame, or of the next
+///         frame to be read when the batch is empty.
+///     timestamp_us: Microseconds since acquisition started when batch was read.
+///     crc_errors: Number of frames discarded due to CRC errors.
+///     sequence_gaps: Number of detected dropped frames (from sequence discontinuities).
+///     partial: True if a read timeout elapsed before all requested frames arrived.
+///     rssi: RSSI of the Bluetooth link around this batch (dB relative to the
+///         adapter's golden receive range), or None if unavailable.
+///     link_quality: Adapter-reported link quality (0-255), or None if unavailable.
+///     channels (list[int]): Acquired analog channels (0-5), in frame order.
+///     sampling_rate: Acquisition rate in Hz, at which ``sample_index`` advances.
+#[pyclass(
+    name = "FrameBatch",
+    module = "bitalino_rs._bitalino_core",
+    frozen,
+    from_py_object
+)]
+#[derive(Clone)]
+struct PyFrameBatch {
+    /// Decoded batch kept for the columnar `arrays()` view.
+    raw: FrameBatch,
+    #[pyo3(get)]
+    frames: Vec<PyFrame>,
+    #[pyo3(get)]
+    first_sample_index: u64,
+    #[pyo3(get)]
+    timestamp_us: u64,
+    #[pyo3(get)]
+    crc_errors: usize,
+    #[pyo3(get)]
+    sequence_gaps: usize,
+    #[pyo3(get)]
+    partial: bool,
+    #[pyo3(get)]
+    rssi: Option<i8>,
+    #[pyo3(get)]
+    link_quality: Option<u8>,
+    // Exposed as a list via a custom getter, like Frame.digital.
+    channels: Vec<u8>,
+    #[pyo3(get)]
+    sampling_rate: u16,
+}
+
+#[pymethods]
+impl PyFrameBatch {
+    /// Build a batch from frames, e.g. to replay or test processing code.
+    ///
+    /// ``first_sample_index`` defaults to the first frame's ``sample_index``
+    /// and ``channels`` to the first ``n_channels`` channels.
+    ///
+    /// Raises:
+    ///     ValueError: If gap_fill is not "off", "hold" or "nan", or the
+    ///         sampling rate is not 1, 10, 100 or 1000.
+    #[new]
+    #[pyo3(signature = (
+        frames,
+        first_sample_index=None,
+        timestamp_us=0,
+        crc_errors=0,
+        sequence_gaps=0,
+        partial=false,
+        rssi=None,
+        link_quality=None,
+        gap_fill="off",
+        channels=None,
+        sampling_rate=1000,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        frames: Vec<PyFrame>,
+        first_sample_index: Option<u64>,
+        timestamp_us: u64,
+        crc_errors: usize,
+        sequence_gaps: usize,
+        partial: bool,
+        rssi: Option<i8>,
+        link_quality: Option<u8>,
+        gap_fill: &str,
+        channels: Option<Vec<u8>>,
+        sampling_rate: u16,
+    ) -> PyResult<Self> {
+        let frames: Vec<Frame> = frames.iter().map(Frame::from).collect();
+        let sampling_rate = SamplingRate::from_u16_checked(sampling_rate)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let channels =
+            channels.unwrap_or_else(|| (0..frames.first().map_or(0, |f| f.n_channels)).collect());
+        Ok(PyFrameBatch::from(FrameBatch {
+            channels,
+            sampling_rate,
+            first_sample_index: first_sample_index
+                .or_else(|| frames.first().map(|f| f.sample_index))
+                .unwrap_or(0),
+            frames,
+            timestamp_us,
+            crc_errors,
+            sequence_gaps,
+            partial,
+            link_quality: (rssi.is_some() || link_quality.is_some())
+                .then_some(LinkQuality { rssi, link_quality }),
+            gap_fill: gap_fill_mode(gap_fill)?,
+        }))
+    }
+
+    /// Pickle support: rebuild through the constructor.
+    fn __reduce__<'py>(
+        slf: &Bound<'py, Self>,
+    ) -> PyResult<(Bound<'py, PyType>, Bound<'py, PyTuple>)> {
+        let b = slf.get();
+        let args = (
+            b.frames.clone(),
+            b.first_sample_index,
+            b.timestamp_us,
+            b.crc_errors,
+            b.sequence_gaps,
+            b.partial,
+            b.rssi,
+            b.link_quality,
+            gap_fill_name(b.raw.gap_fill),
+            PyList::new(slf.py(), &b.channels)?,
+            b.sampling_rate,
+        )
+            .into_pyobject(slf.py())?;
+        Ok((slf.get_type(), args))
+    }
+
+    /// Batches are immutable, so copies share the original.
+    fn __copy__(slf: Bound<'_, Self>) -> Bound<'_, Self> {
+        slf
+    }
+
+    fn __deepcopy__<'py>(slf: Bound<'py, Self>, _memo: &Bound<'py, PyAny>) -> Bound<'py, Self> {
+        slf
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FrameBatch(frames={}, timestamp_us={}, crc_errors={}, sequence_gaps={}, partial={})",
+            self.frames.len(),
+            self.timestamp_us,
+            self.crc_errors,
+            self.sequence_gaps,
+            if self.partial { "True" } else { "False" }
+        )
+    }
+
+    fn __len__(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Frame at an index (negative counts from the end), or a new batch for a
+    /// slice.
+    ///
+    /// Overloads:
+    ///     (index: int) -> Frame
+    ///     (index: slice) -> FrameBatch
+    fn __getitem__<'py>(
+        &self,
+        py: Python<'py>,
+        index: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let len = self.frames.len();
+        if let Ok(slice) = index.cast::<PySlice>() {
+            let indices = slice.indices(len as isize)?;
+            let frames: Vec<Frame> = (0..indices.slicelength)
+                .map(|i| self.raw.frames[(indices.start + i as isize * indices.step) as usize])
+                .collect();
+            let batch = FrameBatch {
+                first_sample_index: frames
+                    .first()
+                    .map_or(self.raw.first_sample_index, |f| f.sample_index),
+                frames,
+                ..self.raw.clone()
+            };
+            return Ok(Bound::new(py, PyFrameBatch::from(batch))?.into_any());
+        }
+        let i: isize = index.extract()?;
+        let position = if i < 0 { i + len as isize } else { i };
+        let frame = usize::try_from(position)
+            .ok()
+            .and_then(|p| self.frames.get(p))
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyIndexError, _>("FrameBatch index out of range")
+            })?;
+        Ok(Bound::new(py, frame.clone())?.into_any())
+    }
+
+    /// Returns:
+    ///     Iterator[Frame]: The frames in order.
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyList::new(py, self.frames.clone())?.try_iter()
+    }
+
+    /// Values of one analog channel across the batch.
+    ///
+    /// Args:
+    ///     index: Position of the channel among the acquired channels, e.g. 1
+    ///         for A3 when acquiring ``channels=[0, 2]``.
+    ///
+    /// Returns:
+    ///     List with one value per frame.
+    ///
+    /// Raises:
+    ///     IndexError: If the frames carry fewer channels.
+    fn analog_channel(&self, index: usize) -> PyResult<Vec<u16>> {
+        self.frames
+            .iter()
+            .map(|f| {
+                f.analog.get(index).copied().ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyIndexError, _>(format!(
+                        "channel index {} out of range for {} channels",
+                        index,
+                        f.analog.len()
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Acquired analog channels (0-5) as a Python list, in frame order.
+    #[getter]
+    fn channels(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
+        Ok(PyList::new(py, &self.channels)?.into())
+    }
+
+    /// list[int]: ADC resolution in bits of each acquired channel, in frame
+    /// order: 6 for the fifth and sixth channels of 5- and 6-channel
+    /// acquisitions, else 10.
+    #[getter]
+    fn resolution_bits(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
+        Ok(PyList::new(py, self.raw.resolution_bits())?.into())
+    }
+
+    /// Check if any errors occurred during reading.
+    #[getter]
+    fn has_errors(&self) -> bool {
+        self.crc_errors > 0 || self.sequence_gaps > 0
+    }
+
+    /// Get the batch as column-major NumPy arrays (requires ``numpy``).
+    ///
+    /// Returns:
+    ///     Dict with ``analog`` (uint16, shape ``(n_channels, n_frames)``, one
+    ///     contiguous row per channel), ``digital`` (uint8, one packed byte per
+    ///     frame: bit 0 = I1, 1 = I2, 2 = O1, 3 = O2), ``sequence`` (uint8),
+    ///     ``sample_index`` (uint64) and ``filled`` (bool, gap-fill
+    ///     placeholders). For batches read with
+    ///     ``gap_fill="nan"``, ``analog`` is float64 with NaN at placeholders.
+    ///
+    /// Raises:
+    ///     ImportError: If NumPy is not installed.
+    fn arrays<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let numpy = py.import("numpy")?;
+        let columns = self.raw.to_columns();
+        let n_frames = columns.sequence.len();
+
+        // frombuffer over a bytearray yields a writable array without a second copy.
+        let analog_bytes: Vec<u8> = columns
+            .analog
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let analog = numpy
+            .call_method1(
+                "frombuffer",
+                (
+                    PyByteArray::new(py, &analog_bytes),
+                    numpy.getattr("uint16")?,
+                ),
+            )?
+            .call_method1("reshape", (columns.analog.len(), n_frames))?;
+        let uint8 = numpy.getattr("uint8")?;
+        let digital = numpy.call_method1(
+            "frombuffer",
+            (PyByteArray::new(py, &columns.digital), &uint8),
+        )?;
+        let sequence = numpy.call_method1(
+            "frombuffer",
+            (PyByteArray::new(py, &columns.sequence), &uint8),
+        )?;
+        let sample_index_bytes: Vec<u8> = columns
+            .sample_index
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let sample_index = numpy.call_method1(
+            "frombuffer",
+            (
+                PyByteArray::new(py, &sample_index_bytes),
+                numpy.getattr("uint64")?,
+            ),
+        )?;
+
+        let filled =
+            numpy.call_method1("array", (columns.filled.clone(), numpy.getattr("bool_")?))?;
+        let analog = if self.raw.gap_fill == GapFill::Nan && columns.filled.contains(&true) {
+            let analog = analog.call_method1("astype", (numpy.getattr("float64")?,))?;
+            analog.set_item((py.Ellipsis(), &filled), numpy.getattr("nan")?)?;
+            analog
+        } else {
+            analog
+        };
+
+        let dict = PyDict::new(py);
+        dict.set_item("analog", analog)?;
+        dict.set_item("digital", digital)?;
+        dict.set_item("sequence", sequence)?;
+        dict.set_item("sample_index", sample_index)?;
+        dict.set_item("filled", filled)?;
+        Ok(dict)
+    }
+
+    /// Get the batch as a pandas DataFrame (requires ``pandas``).
+    ///
+    /// Built from the ``arrays()`` columns: one column per analog channel named
+    /// after the acquired channel (``A1`` to ``A6``), the digital lines ``I1``,
+    /// ``I2``, ``O1`` and ``O2`` as 0/1, then ``sequence`` and ``filled``. The
+    /// ``time`` index is each frame's ``sample_index`` over the sampling rate,
+    /// as a ``TimedeltaIndex`` since ``start()``.
+    ///
+    /// Args:
+    ///     start (datetime | None): Acquisition start, e.g.
+    ///         ``dev.acquisition_start_utc()``, to
+    ///         index by wall-clock timestamps instead.
+    ///
+    /// Raises:
+    ///     ImportError: If pandas or NumPy is not installed.
+    #[pyo3(signature = (start=None))]
+    fn to_pandas<'py>(
+        &self,
+        py: Python<'py>,
+        start: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let pandas = py.import("pandas")?;
+        let arrays = self.arrays(py)?.into_any();
+
+        let columns = PyDict::new(py);
+        let analog = arrays.get_item("analog")?;
+        for row in 0..analog.len()? {
+            let channel = self.channels.get(row).copied().unwrap_or(row as u8);
+            columns.set_item(format!("A{}", channel + 1), analog.get_item(row)?)?;
+        }
+        let digital = arrays.get_item("digital")?;
+        for (bit, name) in ["I1", "I2", "O1", "O2"].into_iter().enumerate() {
+            columns.set_item(name, digital.rshift(bit)?.bitand(1)?)?;
+        }
+        columns.set_item("sequence", arrays.get_item("sequence")?)?;
+        columns.set_item("filled", arrays.get_item("filled")?)?;
+
+        let seconds = arrays
+            .get_item("sample_index")?
+            .div(self.sampling_rate as f64)?;
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("unit", "s")?;
+        let mut index = pandas.call_method("to_timedelta", (seconds,), Some(&kwargs))?;
+        if let Some(start) = start {
+            index = pandas.call_method1("Timestamp", (start,))?.add(index)?;
+        }
+        index.setattr("name", "time")?;
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("index", index)?;
+        pandas.call_method("DataFrame", (columns,), Some(&kwargs))
+    }
+
+    /// Get the batch as an MNE-Python ``RawArray`` (requires ``mne``).
+    ///
+    /// Analog channels are named after the acquired channel (``A1`` to
+    /// ``A6``) and converted with the transfer function of their sensor to
+    /// the SI units MNE expects: volts for ECG, EMG, EEG and EOG (channel
+    /// types ``ecg``, ``emg``, ``eeg``, ``eog``) and siemens for EDA
+    /// (``gsr``). Light and unlabelled channels become unitless ``misc``
+    /// channels in percent and ADC counts. The digital lines ``I1``, ``I2``,
+    /// ``O1`` and ``O2`` follow as ``stim`` channels, so ``mne.find_events``
+    /// sees trigger edges. ``first_samp`` is the first frame's
+    /// ``sample_index``; gap-fill placeholders of ``gap_fill="nan"`` batches
+    /// are NaN.
+    ///
+    /// Args:
+    ///     sensors (list[str] | None): Sensor per acquired channel (``"ECG"``,
+    ///         ``"EDA"``, ...), as in ``Session.sensors``; all ``"RAW"`` by
+    ///         default.
+    ///     start (datetime | None): Acquisition start as a timezone-aware
+    ///         ``datetime``, e.g. ``dev.acquisition_start_utc()``, stored as
+    ///         the measurement date.
+    ///
+    /// Raises:
+    ///     ValueError: If ``sensors`` does not name one sensor per channel.
+    ///     ImportError: If MNE or NumPy is not installed.
+    #[pyo3(signature = (sensors=None, start=None))]
+    fn to_mne<'py>(
+        &self,
+        py: Python<'py>,
+        sensors: Option<Vec<String>>,
+        start: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let sensors: Vec<Sensor> = match sensors {
+            Some(names) if names.len() != self.channels.len() => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "sensors names {} channels, the batch has {}",
+                    names.len(),
+                    self.channels.len()
+                )))
+            }
+            Some(names) => names.iter().map(|name| Sensor::from_name(name)).collect(),
+            None => vec![Sensor::Raw; self.channels.len()],
+        };
+        let mne = py.import("mne")?;
+        let numpy = py.import("numpy")?;
+
+        let frames = &self.raw.frames;
+        let nan_fill = self.raw.gap_fill == GapFill::Nan;
+        let mut names = Vec::new();
+        let mut types = Vec::new();
+        let mut data: Vec<f64> = Vec::with_capacity((sensors.len() + 4) * frames.len());
+        for (position, (&channel, &sensor)) in self.channels.iter().zip(&sensors).enumerate() {
+            let (kind, scale) = mne_channel(sensor);
+            names.push(format!("A{}", channel + 1));
+            types.push(kind);
+            data.extend(
+                frames
+                    .iter()
+                    .map(|frame| match frame.physical(position, sensor) {
+                        Some(_) if frame.filled && nan_fill => f64::NAN,
+                        Some(value) => value * scale.unwrap_or(1.0),
+                        None => f64::NAN,
+                    }),
+            );
+        }
+        for (bit, name) in ["I1", "I2", "O1", "O2"].into_iter().enumerate() {
+            names.push(name.to_string());
+            types.push("stim");
+            data.extend(frames.iter().map(|frame| frame.digital[bit] as f64));
+        }
+
+        let data_bytes: Vec<u8> = data.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let data = numpy
+            .call_method1(
+                "frombuffer",
+                (PyByteArray::new(py, &data_bytes), numpy.getattr("float64")?),
+            )?
+            .call_method1("reshape", (names.len(), frames.len()))?;
+        let info = mne.call_method1(
+            "create_info",
+            (names, self.sampling_rate as f64, types.clone()),
+        )?;
+        // Channels without an SI unit: ADC counts and percent of full scale.
+        let chs = info.get_item("chs")?;
+        let no_unit = mne
+            .getattr("io")?
+            .getattr("constants")?
+            .getattr("FIFF")?
+            .getattr("FIFF_UNIT_NONE")?;
+        for (ch, &sensor) in sensors.iter().enumerate() {
+            if mne_channel(sensor).1.is_none() {
+                chs.get_item(ch)?.set_item("unit", &no_unit)?;
+            }
+        }
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("first_samp", frames.first().map_or(0, |f| f.sample_index))?;
+        kwargs.set_item("verbose", false)?;
+        let raw = mne
+            .getattr("io")?
+            .call_method("RawArray", (data, info), Some(&kwargs))?;
+        if let Some(start) = start {
+            raw.call_method1("set_meas_date", (start,))?;
+        }
+        Ok(raw)
+    }
+}
+
+/// MNE channel type for `sensor`, and the factor from its
+/// [`Sensor::to_physical`] unit to the SI unit of that type; `None` for
+/// channels MNE gets without a unit.
+fn mne_channel(sensor: Sensor) -> (&'static str, Option<f64>) {
+    match sensor {
+        Sensor::Ecg => ("ecg", Some(1e-3)),
+        Sensor::Emg => ("emg", Some(1e-3)),
+        Sensor::Eog => ("eog", Some(1e-3)),
+        Sensor::Eeg => ("eeg", Some(1e-6)),
+        Sensor::Eda => ("gsr", Some(1e-6)),
+        Sensor::Lux | Sensor::Raw => ("misc", None),
+    }
+}
+
+impl From<FrameBatch> for PyFrameBatch {
+    fn from(b: FrameBatch) -> Self {
+        PyFrameBatch {
+            frames: b.frames.iter().copied().map(PyFrame::from).collect(),
+            first_sample_index: b.first_sample_index,
+            timestamp_us: b.timestamp_us,
+            crc_errors: b.crc_errors,
+            sequence_gaps: b.sequence_gaps,
+            partial: b.partial,
+            rssi: b.link_quality.and_then(|q| q.rssi),
+            link_quality: b.link_quality.and_then(|q| q.link_quality),
+            channels: b.channels.clone(),
+            sampling_rate: b.sampling_rate as u16,
+            raw: b,
+        }
+    }
+}
+
+/// Event marker recorded with Bitalino.annotate().
+///
+/// Attributes:
+///     label: Free-form marker label.
+///     sample_index: Sample (counted since start()) being acquired when the
+///         marker was placed.
+///     timestamp_us: Host time of the marker in microseconds since start(), on
+///         the same clock as FrameBatch.timestamp_us.
+#[pyclass(name = "Annotation", frozen, from_py_object)]
+#[derive(Clone)]
+struct PyAnnotation {
+    #[pyo3(get)]
+    label: String,
+    #[pyo3(get)]
+    sample_index: u64,
+    #[pyo3(get)]
+    timestamp_us: u64,
+}
+
+#[pymethods]
+impl PyAnnotation {
+    fn __repr__(&self) -> String {
+        format!(
+            "Annotation(label={:?}, sample_index={}, timestamp_us={})",
+            self.label, self.sample_index, self.timestamp_us
+        )
+    }
+}
+
+impl From<Annotation> for PyAnnotation {
+    fn from(a: Annotation) -> Self {
+        PyAnnotation {
+            label: a.label,
+            sample_index: a.sample_index,
+            timestamp_us: a.timestamp_us,
+        }
+    }
+}
+
+/// Output change recorded during acquisition with Bitalino.trigger(),
+/// trigger_pulse() or pwm().
+///
+/// Attributes:
+///     kind (Literal["digital", "pwm"]): ``"digital"`` or ``"pwm"``.
+///     outputs: Digital output levels [O1, O2, O3, O4] set, for ``"digital"``.
+///     pwm: Duty cycle (0-255) set, for ``"pwm"``.
+///     sample_index: Sample (counted since start()) being acquired when the
+///         command was written.
+///     timestamp_us: Host time of the write in microseconds since start(), on
+///         the same clock as FrameBatch.timestamp_us.
+#[pyclass(name = "OutputEvent", frozen, from_py_object)]
+#[derive(Clone)]
+struct PyOutputEvent {
+    #[pyo3(get)]
+    kind: &'static str,
+    #[pyo3(get)]
+    outputs: Option<[u8; 4]>,
+    #[pyo3(get)]
+    pwm: Option<u8>,
+    #[pyo3(get)]
+    sample_index: u64,
+    #[pyo3(get)]
+    timestamp_us: u64,
+}
+
+#[pymethods]
+impl PyOutputEvent {
+    fn __repr__(&self) -> String {
+        let command = match (self.outputs, self.pwm) {
+            (Some(outputs), _) => format!("outputs={:?}", outputs),
+            (_, Some(pwm)) => format!("pwm={}", pwm),
+            _ => String::new(),
+        };
+        format!(
+            "OutputEvent(kind={:?}, {}, sample_index={}, timestamp_us={})",
+            self.kind, command, self.sample_index, self.timestamp_us
+        )
+    }
+}
+
+impl From<OutputEvent> for PyOutputEvent {
+    fn from(e: OutputEvent) -> Self {
+        let (kind, outputs, pwm) = match e.command {
+            OutputCommand::Digital(levels) => ("digital", Some(levels), None),
+            OutputCommand::Pwm(value) => ("pwm", None, Some(value)),
+        };
+        PyOutputEvent {
+            kind,
+            outputs,
+            pwm,
+            sample_index: e.sample_index,
+            timestamp_us: e.timestamp_us,
+        }
+    }
+}
+
+/// Serial port present on the host, as listed by Bitalino.list_serial_ports().
+///
+/// Attributes:
+///     path: Path to pass to ``Bitalino(path)``, e.g. ``"/dev/rfcomm0"``.
+///     kind (Literal["usb", "bluetooth", "pci", "other"]): ``"usb"``,
+///         ``"bluetooth"``, ``"pci"`` or ``"other"``.
+///     vid: USB vendor ID, for USB ports.
+///     pid: USB product ID, for USB ports.
+///     manufacturer: USB manufacturer string, if reported.
+///     product: USB product string, if reported.
+///     serial_number: USB serial number, if reported.
+///     is_bitalino: Whether the port likely leads to a BITalino: a Bluetooth
+///         port, a name or USB descriptor mentioning BITalino or PLUX, or a
+///         USB-UART bridge usually wired to its UART.
+#[pyclass(name = "SerialPort", frozen, from_py_object)]
+#[derive(Clone)]
+struct PySerialPort {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    kind: &'static str,
+    #[pyo3(get)]
+    vid: Option<u16>,
+    #[pyo3(get)]
+    pid: Option<u16>,
+    #[pyo3(get)]
+    manufacturer: Option<String>,
+    #[pyo3(get)]
+    product: Option<String>,
+    #[pyo3(get)]
+    serial_number: Option<String>,
+    #[pyo3(get)]
+    is_bitalino: bool,
+}
+
+#[pymethods]
+impl PySerialPort {
+    fn __repr__(&self) -> String {
+        format!(
+            "SerialPort(path={:?}, kind={:?}, is_bitalino={})",
+            self.path,
+            self.kind,
+            if self.is_bitalino { "True" } else { "False" }
+        )
+    }
+}
+
+impl From<SerialPortEntry> for PySerialPort {
+    fn from(port: SerialPortEntry) -> Self {
+        PySerialPort {
+            is_bitalino: port.is_bitalino(),
+            kind: port.kind.as_str(),
+            path: port.path,
+            vid: port.vid,
+            pid: port.pid,
+            manufacturer: port.manufacturer,
+            product: port.product,
+            serial_number: port.serial_number,
+        }
+    }
+}
+
+/// One recorded acquisition, from Bitalino.begin_session() to end_session().
+///
+/// Attributes:
+///     mac: Device MAC address (or bridge address / label on other links).
+///     sampling_rate: Sampling rate in Hz.
+///     channels: Acquired analog channels (0-5), in frame order.
+///     sensors: Sensor name per acquired channel (``"RAW"`` when unlabelled).
+///     firmware_version: Firmware string, or None if never queried.
+///     is_bitalino2: Whether the device is a BITalino 2.0+.
+///     connection_kind (Literal["bluetooth", "serial", "tcp", "replay", "custom"]):
+///         How the device is connected.
+///     started (datetime): Acquisition start as a timezone-aware UTC
+///         ``datetime``.
+///     ended (datetime | None): When the session ended, as a UTC ``datetime``.
+///     annotations: Markers placed with annotate(), oldest first.
+///     output_events: Output changes made during the acquisition.
+///     stats: Totals for the acquisition as of end_session().
+#[pyclass(name = "Session", frozen, from_py_object)]
+#[derive(Clone)]
+struct PySession {
+    session: Session,
+}
+
+#[pymethods]
+impl PySession {
+    #[getter]
+    fn mac(&self) -> &str {
+        &self.session.info.mac
+    }
+
+    #[getter]
+    fn sampling_rate(&self) -> u16 {
+        self.session.info.sampling_rate
+    }
+
+    #[getter]
+    fn channels(&self) -> Vec<u8> {
+        self.session.info.channels.clone()
+    }
+
+    #[getter]
+    fn sensors(&self) -> Vec<String> {
+        self.session.info.sensors.clone()
+    }
+
+    #[getter]
+    fn firmware_version(&self) -> Option<&str> {
+        self.session.info.firmware_version.as_deref()
+    }
+
+    #[getter]
+    fn is_bitalino2(&self) -> bool {
+        self.session.info.is_bitalino2
+    }
+
+    #[getter]
+    fn connection_kind(&self) -> &'static str {
+        self.session.info.connection.as_str()
+    }
+
+    #[getter]
+    fn started<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        utc_datetime(py, self.session.info.started)
+    }
+
+    #[getter]
+    fn ended<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.session.ended.map(|t| utc_datetime(py, t)).transpose()
+    }
+
+    #[getter]
+    fn annotations(&self) -> Vec<PyAnnotation> {
+        self.session
+            .annotations
+            .iter()
+            .cloned()
+            .map(PyAnnotation::from)
+            .collect()
+    }
+
+    #[getter]
+    fn output_events(&self) -> Vec<PyOutputEvent> {
+        self.session
+            .output_events
+            .iter()
+            .cloned()
+            .map(PyOutputEvent::from)
+            .collect()
+    }
+
+    #[getter]
+    fn stats(&self) -> Option<PyAcquisitionStats> {
+        self.session.stats.map(PyAcquisitionStats::from)
+    }
+
+    /// The whole session as a JSON string, as recordings append it.
+    fn to_json(&self) -> String {
+        self.session.to_json().to_string()
+    }
+
+    /// Convert ``batch``, read during this session, to an MNE-Python
+    /// ``RawArray`` (requires ``mne``).
+    ///
+    /// Like ``FrameBatch.to_mne()`` with the session's sensors and start time,
+    /// plus the annotations placed within the batch as zero-length MNE
+    /// annotations.
+    ///
+    /// Raises:
+    ///     ValueError: If the batch's channels or sampling rate differ from the
+    ///         session's.
+    ///     ImportError: If MNE or NumPy is not installed.
+    fn to_mne<'py>(&self, py: Python<'py>, batch: &PyFrameBatch) -> PyResult<Bound<'py, PyAny>> {
+        let info = &self.session.info;
+        if batch.channels != info.channels || batch.sampling_rate != info.sampling_rate {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "batch acquired channels {:?} at {} Hz, the session {:?} at {} Hz",
+                batch.channels, batch.sampling_rate, info.channels, info.sampling_rate
+            )));
+        }
+        let start = utc_datetime(py, info.started)?;
+        let raw = batch.to_mne(py, Some(info.sensors.clone()), Some(start.clone()))?;
+
+        let (first, last) = match (batch.raw.frames.first(), batch.raw.frames.last()) {
+            (Some(first), Some(last)) => (first.sample_index, last.sample_index),
+            _ => return Ok(raw),
+        };
+        let rate = info.sampling_rate as f64;
+        let (mut onsets, mut labels) = (Vec::new(), Vec::new());
+        for annotation in &self.session.annotations {
+            if (first..=last).contains(&annotation.sample_index) {
+                onsets.push(annotation.sample_index as f64 / rate);
+                labels.push(annotation.label.clone());
+            }
+        }
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("orig_time", start)?;
+        let durations = vec![0.0; onsets.len()];
+        let annotations = py.import("mne")?.call_method(
+            "Annotations",
+            (onsets, durations, labels),
+            Some(&kwargs),
+        )?;
+        raw.call_method1("set_annotations", (annotations,))?;
+        Ok(raw)
+    }
+
+    fn __repr__(&self) -> String {
+        let info = &self.session.info;
+        format!(
+            "Session(mac={:?}, sampling_rate={}, channels={:?}, annotations={}, output_events={})",
+            info.mac,
+            info.sampling_rate,
+            info.channels,
+            self.session.annotations.len(),
+            self.session.output_events.len()
+        )
+    }
+}
+
+impl From<Session> for PySession {
+    fn from(session: Session) -> Self {
+        PySession { session }
+    }
+}
+
+/// `time` as a timezone-aware UTC `datetime.datetime`.
+fn utc_datetime(py: Python<'_>, time: std::time::SystemTime) -> PyResult<Bound<'_, PyAny>> {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let datetime = py.import("datetime")?;
+    let utc = datetime.getattr("timezone")?.getattr("utc")?;
+    datetime
+        .getattr("datetime")?
+        .call_method1("fromtimestamp", (secs, utc))
+}
+
+/// `at` (a `datetime.datetime`; naive values are local time) as a
+/// `SystemTime`.
+pub(crate) fn system_time(at: &Bound<'_, PyAny>) -> PyResult<std::time::SystemTime> {
+    let secs: f64 = at.call_method0("timestamp")?.extract()?;
+    let offset = std::time::Duration::try_from_secs_f64(secs.abs())
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    Ok(if secs >= 0.0 {
+        std::time::UNIX_EPOCH + offset
+    } else {
+        std::time::UNIX_EPOCH - offset
+    })
+}
+
+/// Device state information (BITalino 2.0+ only).
+///
+/// Contains current values of all analog/digital channels and battery status.
+/// Obtained by calling Bitalino.state() when not in acquisition mode.
+///
+/// Attributes:
+///     analog: All 6 analog channel values (10-bit, 0-1023).
+///     battery: Battery ADC value (10-bit, 0-1023).
+///     battery_threshold: Current battery threshold setting (0-63).
+///     digital (list[int]): Digital channel states [I1, I2, O1, O2].
+#[pyclass(
+    name = "DeviceState",
+    module = "bitalino_rs._bitalino_core",
+    frozen,
+    from_py_object
+)]
+#[derive(Clone)]
+struct PyDeviceState {
+    #[pyo3(get)]
+    analog: Vec<u16>,
+    #[pyo3(get)]
+    battery: u16,
+    #[pyo3(get)]
+    battery_threshold: u8,
+    // store raw digital bits; expose as list via custom getter to avoid bytes()
+    digital: Vec<u8>,
+}
+
+#[pymethods]
+impl PyDeviceState {
+    #[new]
+    fn new(analog: Vec<u16>, battery: u16, battery_threshold: u8, digital: Vec<u8>) -> Self {
+        PyDeviceState {
+            analog,
+            battery,
+            battery_threshold,
+            digital,
+        }
+    }
+
+    /// Pickle support: rebuild through the constructor.
+    fn __reduce__<'py>(
+        slf: &Bound<'py, Self>,
+    ) -> PyResult<(Bound<'py, PyType>, Bound<'py, PyTuple>)> {
+        let s = slf.get();
+        let args = (
+            s.analog.clone(),
+            s.battery,
+            s.battery_threshold,
+            PyList::new(slf.py(), &s.digital)?,
+        )
+            .into_pyobject(slf.py())?;
+        Ok((slf.get_type(), args))
+    }
+
+    /// States are immutable, so copies share the original.
+    fn __copy__(slf: Bound<'_, Self>) -> Bound<'_, Self> {
+        slf
+    }
+
+    fn __deepcopy__<'py>(slf: Bound<'py, Self>, _memo: &Bound<'py, PyAny>) -> Bound<'py, Self> {
+        slf
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DeviceState(battery={}, threshold={}, analog={:?}, digital={:?})",
+            self.battery, self.battery_threshold, self.analog, self.digital
+        )
+    }
+
+    /// Get the approximate battery voltage.
+    ///
+    /// Returns:
+    ///     Approximate battery voltage in Volts (typically 3.2V - 4.2V).
+    #[getter]
+    fn battery_voltage(&self) -> f32 {
+        (self.battery as f32 / 1023.0) * 3.3 * 2.0
+    }
+
+    /// Check if battery is low based on threshold setting.
+    ///
+    /// Returns:
+    ///     True if battery voltage is below the threshold.
+    #[getter]
+    fn is_battery_low(&self) -> bool {
+        let threshold_voltage = 3.4 + (self.battery_threshold as f32 / 63.0) * 0.4;
+        self.battery_voltage() < threshold_voltage
+    }
+
+    /// Digital channel states as a Python list [I1, I2, O1, O2].
+    #[getter]
+    fn digital(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
+        Ok(PyList::new(py, &self.digital)?.into())
+    }
+
+    /// Convert to dictionary for easy serialization.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("analog", self.analog.clone())?;
+        dict.set_item("battery", self.battery)?;
+        dict.set_item("battery_threshold", self.battery_threshold)?;
+        dict.set_item("digital", PyList::new(py, &self.digital)?)?;
+        dict.set_item("battery_voltage", self.battery_voltage())?;
+        dict.set_item("is_battery_low", self.is_battery_low())?;
+        Ok(dict)
+    }
+}
+
+impl From<DeviceState> for PyDeviceState {
+    fn from(s: DeviceState) -> Self {
+        PyDeviceState {
+            analog: s.analog.to_vec(),
+            battery: s.battery,
+            battery_threshold: s.battery_threshold,
+            digital: s.digital.to_vec(),
+        }
+    }
+}
+
+/// Identity and capabilities of a connected device.
+///
+/// Returned by ``Bitalino.device_info()``. Firmware fields are only known
+/// once ``version()`` has succeeded; the connect helpers call it for you.
+///
+/// Attributes:
+///     mac: Bluetooth address, or None when not connected by MAC.
+///     firmware_version: Firmware string (e.g. "BITalino_v5.2"), or None.
+///     is_bitalino2: Whether the device is a BITalino 2.0+.
+///     supported_rates: Accepted sampling rates in Hz, slowest first.
+///     n_analog_channels: Number of analog inputs.
+///     connection_kind (Literal["bluetooth", "serial", "tcp", "replay", "custom"]):
+///         How the device is connected.
+///     was_streaming: Whether the device was already acquiring when first
+///         contacted (e.g. left running by a crashed process); the handshake
+///         stopped it.
+#[pyclass(
+    name = "DeviceInfo",
+    module = "bitalino_rs._bitalino_core",
+    frozen,
+    eq,
+    from_py_object
+)]
+#[derive(Clone, PartialEq)]
+struct PyDeviceInfo {
+    #[pyo3(get)]
+    mac: Option<String>,
+    #[pyo3(get)]
+    firmware_version: Option<String>,
+    #[pyo3(get)]
+    is_bitalino2: bool,
+    #[pyo3(get)]
+    supported_rates: Vec<u16>,
+    #[pyo3(get)]
+    n_analog_channels: usize,
+    #[pyo3(get)]
+    connection_kind: String,
+    #[pyo3(get)]
+    was_streaming: bool,
+}
+
+#[pymethods]
+impl PyDeviceInfo {
+    #[new]
+    #[pyo3(signature = (
+        mac, firmware_version, is_bitalino2, supported_rates, n_analog_channels, connection_kind,
+        was_streaming=false
+    ))]
+    fn new(
+        mac: Option<String>,
+        firmware_version: Option<String>,
+        is_bitalino2: bool,
+        supported_rates: Vec<u16>,
+        n_analog_channels: usize,
+        connection_kind: String,
+        was_streaming: bool,
+    ) -> Self {
+        PyDeviceInfo {
+            mac,
+            firmware_version,
+            is_bitalino2,
+            supported_rates,
+            n_analog_channels,
+            connection_kind,
+            was_streaming,
+        }
+    }
+
+    /// Pickle support: rebuild through the constructor.
+    fn __reduce__<'py>(
+        slf: &Bound<'py, Self>,
+    ) -> PyResult<(Bound<'py, PyType>, Bound<'py, PyTuple>)> {
+        let s = slf.get();
+        let args = (
+            s.mac.clone(),
+            s.firmware_version.clone(),
+            s.is_bitalino2,
+            s.supported_rates.clone(),
+            s.n_analog_channels,
+            s.connection_kind.clone(),
+            s.was_streaming,
+        )
+            .into_pyobject(slf.py())?;
+        Ok((slf.get_type(), args))
+    }
+
+    /// Device info is immutable, so copies share the original.
+    fn __copy__(slf: Bound<'_, Self>) -> Bound<'_, Self> {
+        slf
+    }
+
+    fn __deepcopy__<'py>(slf: Bound<'py, Self>, _memo: &Bound<'py, PyAny>) -> Bound<'py, Self> {
+        slf
+    }
+
+    fn __repr__(&self) -> String {
+        let text = |v: &Option<String>| v.as_ref().map_or("None".into(), |v| format!("{v:?}"));
+        format!(
+            "DeviceInfo(mac={}, firmware_version={}, is_bitalino2={}, supported_rates={:?}, \
+             n_analog_channels={}, connection_kind={:?}, was_streaming={})",
+            text(&self.mac),
+            text(&self.firmware_version),
+            if self.is_bitalino2 { "True" } else { "False" },
+            self.supported_rates,
+            self.n_analog_channels,
+            self.connection_kind,
+            if self.was_streaming { "True" } else { "False" }
+        )
+    }
+
+    /// Convert to dictionary for easy serialization.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("mac", self.mac.clone())?;
+        dict.set_item("firmware_version", self.firmware_version.clone())?;
+        dict.set_item("is_bitalino2", self.is_bitalino2)?;
+        dict.set_item("supported_rates", self.supported_rates.clone())?;
+        dict.set_item("n_analog_channels", self.n_analog_channels)?;
+        dict.set_item("connection_kind", self.connection_kind.clone())?;
+        dict.set_item("was_streaming", self.was_streaming)?;
+        Ok(dict)
+    }
+}
+
+impl From<DeviceInfo> for PyDeviceInfo {
+    fn from(info: DeviceInfo) -> Self {
+        PyDeviceInfo {
+            mac: info.mac,
+            firmware_version: info.firmware_version,
+            is_bitalino2: info.is_bitalino2,
+            supported_rates: info.supported_rates.iter().map(|&r| r as u16).collect(),
+            n_analog_channels: info.n_analog_channels,
+            connection_kind: info.connection_kind.as_str().to_string(),
+            was_streaming: info.was_streaming,
+        }
+    }
+}
+
+/// Cumulative acquisition statistics since ``start()``.
+///
+/// Returned by the ``Bitalino.stats`` property. Counters include frames from
+/// reads that raised, so they stay accurate when batches are lost to errors.
+///
+/// Attributes:
+///     frames: CRC-valid frames received.
+///     bytes_read: Raw bytes read from the link, including discarded frames.
+///     crc_errors: Frames rejected because their CRC did not match.
+///     sequence_gaps: Frames missing from the sequence counter.
+///     dropped_samples: Frames the device produced that never reached the
+///         caller (lost on the link or rejected by CRC).
+///     elapsed: Wall-clock seconds since ``start()``.
+///     throughput_hz: Valid frames per second of wall-clock time.
+///     clock_skew: Wall-clock time minus the time implied by the sample count
+///         at the nominal rate, in seconds.
+#[pyclass(name = "AcquisitionStats", frozen, from_py_object)]
+#[derive(Clone)]
+struct PyAcquisitionStats {
+    #[pyo3(get)]
+    frames: u64,
+    #[pyo3(get)]
+    bytes_read: u64,
+    #[pyo3(get)]
+    crc_errors: u64,
+    #[pyo3(get)]
+    sequence_gaps: u64,
+    #[pyo3(get)]
+    dropped_samples: u64,
+    #[pyo3(get)]
+    elapsed: f64,
+    #[pyo3(get)]
+    throughput_hz: f64,
+    #[pyo3(get)]
+    clock_skew: f64,
+}
+
+#[pymethods]
+impl PyAcquisitionStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "AcquisitionStats(frames={}, dropped={}, crc_errors={}, throughput={:.1} Hz, elapsed={:.1}s)",
+            self.frames, self.dropped_samples, self.crc_errors, self.throughput_hz, self.elapsed
+        )
+    }
+
+    /// Convert to dictionary for easy serialization.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("frames", self.frames)?;
+        dict.set_item("bytes_read", self.bytes_read)?;
+        dict.set_item("crc_errors", self.crc_errors)?;
+        dict.set_item("sequence_gaps", self.sequence_gaps)?;
+        dict.set_item("dropped_samples", self.dropped_samples)?;
+        dict.set_item("elapsed", self.elapsed)?;
+        dict.set_item("throughput_hz", self.throughput_hz)?;
+        dict.set_item("clock_skew", self.clock_skew)?;
+        Ok(dict)
+    }
+}
+
+/// Windowed statistics of one analog channel, in ADC counts.
+///
+/// Attributes:
+///     channel: Analog channel (0-5 for A1-A6).
+///     samples: Samples currently in the window.
+///     mean: Mean over the window.
+///     rms: Root mean square around the window mean (AC amplitude).
+///     min: Smallest value in the window.
+///     max: Largest value in the window.
+///     envelope: Rectified signal low-passed at 5 Hz; an EMG activation level.
+#[pyclass(name = "ChannelStats", frozen, from_py_object)]
+#[derive(Clone)]
+struct PyChannelStats {
+    #[pyo3(get)]
+    channel: u8,
+    #[pyo3(get)]
+    samples: usize,
+    #[pyo3(get)]
+    mean: f64,
+    #[pyo3(get)]
+    rms: f64,
+    #[pyo3(get)]
+    min: u16,
+    #[pyo3(get)]
+    max: u16,
+    #[pyo3(get)]
+    envelope: f64,
+}
+
+#[pymethods]
+impl PyChannelStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "ChannelStats(channel={}, mean={:.1}, rms={:.1}, min={}, max={}, envelope={:.1})",
+            self.channel, self.mean, self.rms, self.min, self.max, self.envelope
+        )
+    }
+
+    /// Convert to dictionary for easy serialization.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("channel", self.channel)?;
+        dict.set_item("samples", self.samples)?;
+        dict.set_item("mean", self.mean)?;
+        dict.set_item("rms", self.rms)?;
+        dict.set_item("min", self.min)?;
+        dict.set_item("max", self.max)?;
+        dict.set_item("envelope", self.envelope)?;
+        Ok(dict)
+    }
+}
+
+impl From<ChannelStats> for PyChannelStats {
+    fn from(s: ChannelStats) -> Self {
+        PyChannelStats {
+            channel: s.channel,
+            samples: s.samples,
+            mean: s.mean,
+            rms: s.rms,
+            min: s.min,
+            max: s.max,
+            envelope: s.envelope,
+        }
+    }
+}
+
+/// A filter to apply to analog channels while streaming.
+///
+/// Build one with the static constructors and pass a list of them to
+/// ``Bitalino.start_streaming(filters=...)``. Filters are designed for the
+/// acquisition rate and applied in order to every channel, with the output
+/// re-centred on the ADC mid-scale (512 for A1-A4).
+///
+/// Example:
+///     >>> device.start_streaming(
+///     ...     on_batch,
+///     ...     filters=[Filter.notch(50), Filter.bandpass(0.5, 40)],
+///     ... )
+#[pyclass(name = "Filter", frozen, from_py_object)]
+#[derive(Clone)]
+struct PyFilter(dsp::Filter);
+
+#[pymethods]
+impl PyFilter {
+    /// Narrow notch removing mains interference at ``freq`` Hz (50 or 60).
+    #[staticmethod]
+    #[pyo3(signature = (freq=50.0))]
+    fn notch(freq: f64) -> Self {
+        PyFilter(dsp::Filter::Notch { freq_hz: freq })
+    }
+
+    /// Butterworth low-pass at ``cutoff`` Hz.
+    #[staticmethod]
+    #[pyo3(signature = (cutoff, order=2))]
+    fn lowpass(cutoff: f64, order: u8) -> Self {
+        PyFilter(dsp::Filter::LowPass {
+            cutoff_hz: cutoff,
+            order,
+        })
+    }
+
+    /// Butterworth high-pass at ``cutoff`` Hz, e.g. to remove baseline wander.
+    #[staticmethod]
+    #[pyo3(signature = (cutoff, order=2))]
+    fn highpass(cutoff: f64, order: u8) -> Self {
+        PyFilter(dsp::Filter::HighPass {
+            cutoff_hz: cutoff,
+            order,
+        })
+    }
+
+    /// Butterworth band-pass keeping ``low`` to ``high`` Hz.
+    #[staticmethod]
+    #[pyo3(signature = (low, high, order=2))]
+    fn bandpass(low: f64, high: f64, order: u8) -> Self {
+        PyFilter(dsp::Filter::BandPass {
+            low_hz: low,
+            high_hz: high,
+            order,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        match self.0 {
+            dsp::Filter::Notch { freq_hz } => format!("Filter.notch({freq_hz})"),
+            dsp::Filter::LowPass { cutoff_hz, order } => {
+                format!("Filter.lowpass({cutoff_hz}, order={order})")
+            }
+            dsp::Filter::HighPass { cutoff_hz, order } => {
+                format!("Filter.highpass({cutoff_hz}, order={order})")
+            }
+            dsp::Filter::BandPass {
+                low_hz,
+                high_hz,
+                order,
+            } => format!("Filter.bandpass({low_hz}, {high_hz}, order={order})"),
+        }
+    }
+}
+
+impl From<AcquisitionStats> for PyAcquisitionStats {
+    fn from(s: AcquisitionStats) -> Self {
+        PyAcquisitionStats {
+            frames: s.frames,
+            bytes_read: s.bytes_read,
+            crc_errors: s.crc_errors,
+            sequence_gaps: s.sequence_gaps,
+            dropped_samples: s.dropped_samples,
+            elapsed: s.elapsed.as_secs_f64(),
+            throughput_hz: s.throughput_hz,
+            clock_skew: s.clock_skew_secs,
+        }
+    }
+}
+
+/// BITalino device driver.
+/// Python-facing BITalino driver wrapper for connection and acquisition.
+/// Provides methods to connect, configure, and read biosignal data from
+/// BITalino devices via Bluetooth. No root privileges required. The backend
+/// uses a raw RFCOMM socket and assumes the device is already paired/trusted
+/// (e.g., via `bluetoothctl`).
+///
+/// Use it as a context manager (or call ``close()``) to stop acquisition and
+/// release the Bluetooth connection deterministically. If the object is garbage
+/// collected while acquiring, the device is stopped automatically; call
+/// ``leak()`` to opt out.
+///
+/// Instances may be handed to other threads (e.g. a ``ThreadPoolExecutor``);
+/// reads release the GIL while waiting for frames. Calls on one instance must
+/// not overlap: a concurrent call raises ``RuntimeError``.
+///
+/// Example:
+///     >>> with Bitalino.connect("7E:91:2B:C4:AF:08") as device:
+///     ...     print(f"Firmware: {device.version()}")
+///     ...     device.start(rate=1000, channels=[0, 1, 2])
+///     ...     frames = device.read(100)
+#[pyclass(name = "Bitalino")]
+struct PyBitalino {
+    /// `None` once `close()` has released the transport. The mutex only makes
+    /// the driver `Sync`: PyO3's borrow checking already serializes access, so
+    /// it is never contended (`&mut self` methods bypass it with `get_mut`).
+    inner: Mutex<Option<Bitalino>>,
+    sampling_rate: u16,
+    /// Set by `start_streaming()`; holds the driver until `stop_streaming()`.
+    reader: Option<RunningPipeline>,
+    /// Batches the last streaming session dropped, once it has stopped.
+    dropped_batches: u64,
+    /// Preset of the last `start()`, whose filters `start_streaming()` uses.
+    preset: Option<Preset>,
+}
+
+impl PyBitalino {
+    fn from_device(device: Bitalino) -> Self {
+        PyBitalino {
+            inner: Mutex::new(Some(device)),
+            sampling_rate: 1000,
+            reader: None,
+            dropped_batches: 0,
+            preset: None,
+        }
+    }
+
+    /// The driver slot, for `&mut self` methods.
+    fn slot(&mut self) -> &mut Option<Bitalino> {
+        self.inner.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The driver slot, for read-only getters.
+    fn peek(&self) -> MutexGuard<'_, Option<Bitalino>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Borrow the open driver, or raise if it is closed or streaming.
+    fn device(&mut self) -> PyResult<&mut Bitalino> {
+        let unavailable = self.unavailable();
+        self.slot().as_mut().ok_or(unavailable)
+    }
+
+    /// Move the open driver out, leaving this object closed.
+    fn take_device(&mut self) -> PyResult<Bitalino> {
+        let unavailable = self.unavailable();
+        self.slot().take().ok_or(unavailable)
+    }
+
+    /// Error for calls made while the driver is not available.
+    fn unavailable(&self) -> PyErr {
+        let msg = if self.reader.is_some() {
+            "Device is streaming; call stop_streaming() first"
+        } else {
+            "Device is closed"
+        };
+        PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(msg)
+    }
+
+    /// Join the streaming thread (without holding the GIL) and take the driver
+    /// back, returning the error that ended streaming early, if any.
+    fn join_reader(&mut self, py: Python<'_>) -> Option<anyhow::Error> {
+        let reader = self.reader.take()?;
+        let (device, report) = py.detach(|| reader.stop());
+        *self.slot() = Some(device);
+        self.dropped_batches = report.outputs.iter().map(|o| o.dropped_batches).sum();
+        report.read_error
+    }
+
+    /// Stop any acquisition and release the connection; see
+    /// [`stop_before_release`] for `unwinding`.
+    fn release(&mut self, py: Python<'_>, unwinding: bool) -> PyResult<()> {
+        let read_error = self.join_reader(py);
+        let Some(mut device) = self.slot().take() else {
+            return Ok(());
+        };
+        if let Some(e) = read_error {
+            device_log!(
+                warn,
+                device.device_label(),
+                "Streaming had stopped with an error: {:#}",
+                e
+            );
+        }
+        stop_before_release(&mut device, unwinding).map_err(to_py_err)
+    }
+}
+
+/// Stop `device` if it is acquiring. While `unwinding`, a failure is logged
+/// and swallowed so the exception that ended the ``with`` block propagates.
+fn stop_before_release(device: &mut Bitalino, unwinding: bool) -> anyhow::Result<()> {
+    if !device.is_acquiring() {
+        return Ok(());
+    }
+    match device.stop() {
+        Err(e) if unwinding => {
+            device_log!(
+                warn,
+                device.device_label(),
+                "Failed to stop acquisition while leaving a failed with block: {:#}",
+                e
+            );
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Iterator over timed batches, returned by ``Bitalino.frames()``.
+///
+/// Each step reads one ``FrameBatch`` of ``batch_size`` frames. Iteration ends
+/// (``StopIteration``) once the iterator is closed, the device is closed, or
+/// acquisition is stopped.
+#[pyclass(name = "FrameIterator")]
+struct PyFrameIterator {
+    /// `None` once the iterator has been closed or exhausted.
+    device: Option<Py<PyBitalino>>,
+    batch_size: usize,
+}
+
+#[pymethods]
+impl PyFrameIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyFrameBatch>> {
+        let Some(device) = self.device.as_ref() else {
+            return Ok(None);
+        };
+        let mut device = device.borrow_mut(py);
+        if !device.peek().as_ref().is_some_and(Bitalino::is_acquiring) {
+            drop(device);
+            self.device = None;
+            return Ok(None);
+        }
+        device
+            .read_timed(py, self.batch_size, None, true, "off")
+            .map(Some)
+    }
+
+    /// Stop iterating; the next ``__next__`` raises ``StopIteration``.
+    ///
+    /// The device itself is left untouched (acquisition keeps running).
+    fn close(&mut self) {
+        self.device = None;
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FrameIterator(batch_size={}, closed={})",
+            self.batch_size,
+            self.device.is_none()
+        )
+    }
+}
+
+/// PWM schedule running on a background thread, returned by
+/// ``Bitalino.pwm_ramp()`` and ``Bitalino.pwm_pattern()``.
+///
+/// Dropping it leaves the schedule running until its last step.
+#[pyclass(name = "PwmSchedule")]
+struct PyPwmSchedule {
+    /// `None` once waited for or cancelled.
+    schedule: Option<PwmSchedule>,
+}
+
+impl PyPwmSchedule {
+    /// Join the schedule without holding the GIL, after cancelling it if asked.
+    fn finish(&mut self, py: Python<'_>, cancel: bool) -> PyResult<()> {
+        let Some(schedule) = self.schedule.take() else {
+            return Ok(());
+        };
+        py.detach(|| {
+            if cancel {
+                schedule.cancel()
+            } else {
+                schedule.wait()
+            }
+        })
+        .map_err(to_py_err)
+    }
+}
+
+#[pymethods]
+impl PyPwmSchedule {
+    /// Whether steps are still being sent.
+    #[getter]
+    fn running(&self) -> bool {
+        self.schedule.as_ref().is_some_and(PwmSchedule::is_running)
+    }
+
+    /// Block until the last step has been sent.
+    ///
+    /// Raises:
+    ///     BitalinoError: If writing to the device failed.
+    fn wait(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.finish(py, false)
+    }
+
+    /// Stop after the value being sent, leaving the output at that value.
+    ///
+    /// Raises:
+    ///     BitalinoError: If writing to the device had failed.
+    fn cancel(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.finish(py, true)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PwmSchedule(running={})", self.running())
+    }
+}
+
+/// Ask the Python `pairing_agent` of `Bitalino.connect` to answer `request`.
+fn py_pairing_answer(agent: &Py<PyAny>, mac: &str, request: PairingRequest) -> PairingResponse {
+    let (name, passkey) = match request {
+        PairingRequest::PinCode => ("pin_code", None),
+        PairingRequest::Passkey => ("passkey", None),
+        PairingRequest::Confirmation { passkey } => ("confirmation", Some(passkey)),
+        PairingRequest::Authorization => ("authorization", None),
+    };
+    Python::attach(|py| {
+        let answer = agent.call1(py, (mac, name, passkey))?;
+        let answer = answer.bind(py);
+        Ok::<_, PyErr>(if let Ok(accept) = answer.cast::<PyBool>() {
+            if accept.is_true() {
+                PairingResponse::Accept
+            } else {
+                PairingResponse::Reject
+            }
+        } else if let Ok(pin) = answer.extract::<String>() {
+            PairingResponse::Pin(pin)
+        } else if let Ok(passkey) = answer.extract::<u32>() {
+            PairingResponse::Passkey(passkey)
+        } else {
+            PairingResponse::Reject
+        })
+    })
+    .unwrap_or_else(|e| {
+        log::warn!("pairing_agent failed, rejecting {name}: {e}");
+        PairingResponse::Reject
+    })
+}
+
+/// Pass a connection step to the Python `on_progress` of `Bitalino.connect`.
+fn py_progress(callback: &Py<PyAny>, mac: &str, progress: ConnectProgress) {
+    let (stage, attempt) = match progress {
+        ConnectProgress::Scanning => ("scanning", None),
+        ConnectProgress::Found => ("found", None),
+        ConnectProgress::Pairing => ("pairing", None),
+        ConnectProgress::Paired => ("paired", None),
+        ConnectProgress::Connecting { attempt, .. } => ("connecting", Some(attempt)),
+        ConnectProgress::Verified => ("verified", None),
+    };
+    Python::attach(|py| {
+        if let Err(e) = callback.call1(py, (mac, stage, attempt)) {
+            log::warn!("on_progress failed for {stage}: {e}");
+        }
+    });
+}
+
+/// How often `Bitalino.connect` checks for Ctrl-C while connecting.
+const SIGNAL_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Run `connector.pair_and_connect` on a helper thread while this one handles
+/// signals, so Ctrl-C cancels the scan, pairing and retries and raises
+/// `KeyboardInterrupt` instead of waiting for their deadlines.
+fn pair_and_connect_interruptibly(
+    py: Python<'_>,
+    connector: &BluetoothConnector,
+    mac: &str,
+    pin: &str,
+) -> PyResult<RfcommStream> {
+    let cancel = CancelToken::new();
+    let (tx, mut rx) = std::sync::mpsc::channel();
+    let worker = {
+        let (connector, mac, pin, cancel) = (
+            connector.clone(),
+            mac.to_string(),
+            pin.to_string(),
+            cancel.clone(),
+        );
+        std::thread::spawn(move || {
+            let _ = tx.send(connector.pair_and_connect_cancellable(&mac, &pin, &cancel));
+        })
+    };
+    loop {
+        let (received, waited) = py.detach(move || {
+            let received = rx.recv_timeout(SIGNAL_POLL);
+            (received, rx)
+        });
+        rx = waited;
+        match received {
+            Ok(result) => {
+                let _ = worker.join();
+                return Ok(result?);
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                    "connection thread panicked",
+                ));
+            }
+        }
+        if let Err(interrupted) = py.check_signals() {
+            cancel.cancel();
+            // The socket is closed once the helper returns.
+            let _ = py.detach(move || worker.join());
+            return Err(interrupted);
+        }
+    }
+}
+
+/// Upper bound on `wait_until_streaming` timeouts (seconds).
+/// Keeps `Duration::from_secs_f64` and `Instant::checked_add` well within range.
+const MAX_WAIT_TIMEOUT_SECS: f64 = 3600.0;
+
+/// Convert a Python duration in seconds, rejecting negative or non-finite values.
+fn seconds(name: &str, value: f64) -> PyResult<std::time::Duration> {
+    std::time::Duration::try_from_secs_f64(value).map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "{} must be a finite non-negative number of seconds; got {}",
+            name, value
+        ))
+    })
+}
+
+/// Parse a ``gap_fill`` argument.
+fn gap_fill_mode(name: &str) -> PyResult<GapFill> {
+    match name {
+        "off" => Ok(GapFill::Off),
+        "hold" => Ok(GapFill::HoldLast),
+        "nan" => Ok(GapFill::Nan),
+        other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "gap_fill must be \"off\", \"hold\" or \"nan\"; got {:?}",
+            other
+        ))),
+    }
+}
+
+/// The ``gap_fill`` argument naming `mode`.
+fn gap_fill_name(mode: GapFill) -> &'static str {
+    match mode {
+        GapFill::Off => "off",
+        GapFill::HoldLast => "hold",
+        GapFill::Nan => "nan",
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (level=None))]
+/// Enable Rust-to-Python logging bridge at the given level (or env default).
+fn enable_rust_logs(py: Python<'_>, level: Option<&str>) -> PyResult<()> {
+    logging::set_python_log_level_str(py, level)
+}
+
+#[pyfunction]
+/// Clear cached Python logger handles; call after reconfiguring Python logging.
+fn reset_log_cache() -> PyResult<()> {
+    logging::reset_python_logging_cache();
+    Ok(())
+}
+
+/// Install the bridge carrying Rust logs into Python's ``logging``.
+///
+/// Done automatically on import, so this only matters to check the result.
+/// Records go to loggers named after the Rust module (``bitalino_rs.bitalino``,
+/// ...), at the level set by ``BITALINO_LOG`` or ``RUST_LOG`` (default info).
+///
+/// Returns:
+///     True if Rust logs reach Python, False if another Rust logger was
+///     installed in this process first.
+#[pyfunction]
+fn init_logging(py: Python<'_>) -> PyResult<bool> {
+    logging::init_python_logging(py)?;
+    Ok(logging::python_logging_active())
+}
+
+/// Set the most verbose Rust level forwarded to Python's ``logging``.
+///
+/// Python loggers still filter what they receive, so also lower their level
+/// (e.g. ``logging.getLogger("bitalino_rs").setLevel("DEBUG")``) to see
+/// debug records.
+///
+/// Args:
+///     level (Literal["off", "critical", "error", "warning", "warn", "info", "debug", "trace"]):
+///         Level name, case-insensitive. ``"critical"`` means error, as Rust
+///         has no more severe level.
+///
+/// Raises:
+///     ValueError: If the level name is unknown.
+#[pyfunction]
+fn set_log_level(py: Python<'_>, level: &str) -> PyResult<()> {
+    let filter = logging::parse_python_level(level).ok_or_else(|| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Unknown log level {level:?}; expected off, error, warn, info, debug or trace"
+        ))
+    })?;
+    logging::set_python_log_level(py, filter)
+}
+
+/// Clear cached Python logger levels; call after reconfiguring Python logging.
+#[pyfunction]
+fn reset_logging_cache() {
+    logging::reset_python_logging_cache();
+}
+
+#[pymethods]
+impl PyBitalino {
+    /// Connect to a BITalino device via serial port path (e.g., `/dev/rfcomm0`).
+    ///
+    /// Use this if you've already paired and bound the device manually, or for
+    /// USB-serial adapters. The defaults (115200 baud, no flow control) suit
+    /// the BITalino UART; some adapters need other settings.
+    ///
+    /// Example:
+    ///     >>> dev = Bitalino("/dev/ttyUSB0", dtr=True, reset_pulse=0.1,
+    ///     ...                probe_baud_rates=True)
+    ///
+    /// Args:
+    ///     path: Serial device path.
+    ///     baud_rate: Baud rate, tried first when probing. Default: 115200.
+    ///     flow_control (Literal["none", "software", "hardware"]): "none",
+    ///         "software" (XON/XOFF) or "hardware" (RTS/CTS).
+    ///     dtr: Level to drive DTR to after opening. Default: leave as is.
+    ///     rts: Level to drive RTS to after opening. Default: leave as is.
+    ///     reset_pulse: Seconds to hold DTR and RTS low after opening before
+    ///         raising them, for adapters that reset the module on the edge.
+    ///     probe_baud_rates: Query version() and, until the reply names a
+    ///         BITalino, retry at 230400, 57600, 38400, 19200 and 9600 baud.
+    ///         This stops an acquisition left running on the link.
+    ///
+    /// Raises:
+    ///     ValueError: If flow_control is unknown or reset_pulse is negative.
+    ///     BitalinoError: If the port cannot be opened.
+    ///     TimeoutError: If probing finds no rate the device answers at.
+    #[new]
+    #[pyo3(signature = (
+        path,
+        *,
+        baud_rate=115_200,
+        flow_control="none",
+        dtr=None,
+        rts=None,
+        reset_pulse=None,
+        probe_baud_rates=false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        py: Python<'_>,
+        path: &str,
+        baud_rate: u32,
+        flow_control: &str,
+        dtr: Option<bool>,
+        rts: Option<bool>,
+        reset_pulse: Option<f64>,
+        probe_baud_rates: bool,
+    ) -> PyResult<Self> {
+        let config = SerialConfig {
+            baud_rate,
+            flow_control: FlowControl::from_name(flow_control).ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "flow_control must be 'none', 'software' or 'hardware'; got {:?}",
+                    flow_control
+                ))
+            })?,
+            dtr,
+            rts,
+            reset_pulse: reset_pulse
+                .map(|secs| seconds("reset_pulse", secs))
+                .transpose()?,
+            probe_baud_rates,
+            ..SerialConfig::default()
+        };
+        py.detach(|| Bitalino::connect_serial_with(path, &config))
+            .map(PyBitalino::from_device)
+            .map_err(to_py_err)
+    }
+
+    /// Connect to a BITalino device via Bluetooth.
+    ///
+    /// Uses a raw RFCOMM socket and expects the device to already be
+    /// paired/trusted; just pass the MAC. Builds with the `sync-bluetooth`
+    /// feature pair an unpaired device through BlueZ first, answering with
+    /// ``pin`` or, if given, ``pairing_agent``; elsewhere both are ignored.
+    ///
+    /// A ``pairing_agent`` is called as ``pairing_agent(mac, request,
+    /// passkey)`` where ``request`` is ``"pin_code"``, ``"passkey"``,
+    /// ``"confirmation"`` (``passkey`` is the code to compare) or
+    /// ``"authorization"``. It returns the PIN as a ``str``, the passkey as an
+    /// ``int``, or ``True``/``False`` to accept or reject; exceptions reject.
+    ///
+    /// ``on_progress(mac, stage, attempt)`` is called at each step, with
+    /// ``stage`` one of ``"scanning"``, ``"found"``, ``"pairing"``,
+    /// ``"paired"``, ``"connecting"`` (``attempt`` counts from 1; ``None``
+    /// otherwise) and ``"verified"``, so a UI can show what it is waiting
+    /// for. It runs on a helper thread; exceptions are logged and ignored.
+    ///
+    /// Ctrl-C aborts the scan, pairing and retries within a fraction of a
+    /// second and raises ``KeyboardInterrupt``.
+    ///
+    /// Example:
+    ///     >>> def agent(mac, request, passkey):
+    ///     ...     if request == "confirmation":
+    ///     ...         return input(f"Does {mac} show {passkey:06}? ") == "y"
+    ///     ...     return "9876"
+    ///     >>> device = Bitalino.connect("7E:91:2B:C4:AF:08", pairing_agent=agent)
+    ///
+    /// Args:
+    ///     mac: The MAC address of the device (e.g., "7E:91:2B:C4:AF:08")
+    ///     pin: The PIN code used when pairing (default "1234")
+    ///     channel: RFCOMM channel (default: the registry's, else 1)
+    ///     timeout: Socket I/O timeout in seconds
+    ///     retries: Maximum connection attempts
+    ///     retry_delay: Base delay between attempts in seconds (doubled per retry)
+    ///     adapter: Local adapter by name ("hci1") or address; Linux only
+    ///     known_device: Connect without scanning first (scan only if that fails)
+    ///     scan_timeout: Upper bound on scans for the device, in seconds
+    ///     registry: Use the on-disk registry of known devices: a device
+    ///         connected to before is reached without scanning on its last
+    ///         channel, a changed firmware version is logged as a warning, and
+    ///         the connection is recorded. Managed with ``bitalino devices``.
+    ///     pairing_agent (Callable[[str, str, int | None], str | int | bool] | None):
+    ///         Answers pairing requests instead of ``pin``.
+    ///     on_progress (Callable[[str, str, int | None], None] | None):
+    ///         Called with each connection step.
+    ///
+    /// Returns:
+    ///     A connected Bitalino instance
+    ///
+    /// Raises:
+    ///     BluetoothError: If pairing or connection fails after retries
+    ///     ValueError: If a timeout or delay is negative or not finite
+    ///     KeyboardInterrupt: If interrupted while connecting
+    #[staticmethod]
+    #[pyo3(signature = (
+        mac, pin="1234", *, channel=None, timeout=5.0, retries=3, retry_delay=0.5, adapter=None,
+        known_device=true, scan_timeout=10.0, registry=false, pairing_agent=None,
+        on_progress=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn connect(
+        py: Python<'_>,
+        mac: &str,
+        pin: &str,
+        channel: Option<u8>,
+        timeout: f64,
+        retries: u32,
+        retry_delay: f64,
+        adapter: Option<String>,
+        known_device: bool,
+        scan_timeout: f64,
+        registry: bool,
+        pairing_agent: Option<Py<PyAny>>,
+        on_progress: Option<Py<PyAny>>,
+    ) -> PyResult<Self> {
+        let mut registry = registry.then(DeviceRegistry::load_or_warn).flatten();
+        let known = registry.as_ref().and_then(|r| r.get(mac));
+        let channel = channel.or(known.map(|k| k.channel)).unwrap_or(1);
+        let mut builder = BluetoothConnector::builder()
+            .channel(channel)
+            .io_timeout(seconds("timeout", timeout)?)
+            .retries(retries)
+            .retry_delay(seconds("retry_delay", retry_delay)?)
+            .known_device(known_device || known.is_some())
+            .scan_timeout(seconds("scan_timeout", scan_timeout)?);
+        if let Some(adapter) = adapter {
+            builder = builder.adapter(adapter);
+        }
+        if let Some(agent) = pairing_agent {
+            builder =
+                builder.pairing_agent(move |mac, request| py_pairing_answer(&agent, mac, request));
+        }
+        if let Some(callback) = on_progress {
+            builder =
+                builder.on_progress(move |mac, progress| py_progress(&callback, mac, progress));
+        }
+        let connector = builder.build();
+        let stream = pair_and_connect_interruptibly(py, &connector, mac, pin)?;
+
+        let mut inner = Bitalino::from_rfcomm(stream);
+        inner.set_mac(mac);
+        if let Some(firmware) = known.and_then(|k| k.firmware.as_deref()) {
+            // Lets the handshake force a BITalino 2.0 out of any state.
+            inner.set_firmware(firmware);
+        }
+        inner
+            .set_io_timeout(connector.io_timeout)
+            .map_err(to_py_err)?;
+
+        // Perform an initial handshake to bring the device to a known idle state
+        // and verify the RFCOMM link.
+        if let Err(e) = inner.version() {
+            device_log!(
+                warn,
+                inner.device_label(),
+                "Initial version() handshake failed after connect: {}",
+                e
+            );
+        }
+        if let Some(registry) = registry.as_mut() {
+            registry.note_connection(&inner, channel);
+            registry.save_or_warn();
+        }
+
+        Ok(PyBitalino::from_device(inner))
+    }
+
+    /// Connect to a BITalino proxied over TCP.
+    ///
+    /// Use this when the device is attached to another machine and exposed as a
+    /// raw TCP byte stream (e.g. ``ser2net`` on a Raspberry Pi). Dropped
+    /// connections are re-established automatically.
+    ///
+    /// Args:
+    ///     address: Bridge address as ``"host:port"``.
+    ///
+    /// Returns:
+    ///     A connected Bitalino instance
+    ///
+    /// Raises:
+    ///     BitalinoError: If the bridge cannot be reached
+    #[staticmethod]
+    fn connect_tcp(address: &str) -> PyResult<Self> {
+        let mut inner = Bitalino::connect_tcp(address).map_err(to_py_err)?;
+
+        if let Err(e) = inner.version() {
+            device_log!(
+                warn,
+                inner.device_label(),
+                "Initial version() handshake failed after connect: {}",
+                e
+            );
+        }
+
+        Ok(PyBitalino::from_device(inner))
+    }
+
+    /// Open a recorded raw capture for offline playback.
+    ///
+    /// The returned device behaves like a connected one: call ``start()`` with
+    /// the rate and channels used during the recording, then read as usual.
+    /// Reading past the end of the capture raises IOError.
+    ///
+    /// Args:
+    ///     path: File holding the raw bytes received during acquisition.
+    ///     realtime: Pace playback at the sampling rate instead of replaying as
+    ///         fast as possible. Default: False.
+    ///
+    /// Returns:
+    ///     A Bitalino instance backed by the recording
+    ///
+    /// Raises:
+    ///     IOError: If the file cannot be read
+    #[staticmethod]
+    #[pyo3(signature = (path, realtime=false))]
+    fn replay(path: &str, realtime: bool) -> PyResult<Self> {
+        let transport = ReplayTransport::from_file(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?
+            .realtime(realtime);
+
+        Ok(PyBitalino::from_device(Bitalino::from_transport(Box::new(
+            transport,
+        ))))
+    }
+
+    /// List the serial ports present on this host, likely BITalinos first.
+    ///
+    /// Example:
+    ///     >>> ports = [p for p in Bitalino.list_serial_ports() if p.is_bitalino]
+    ///     >>> device = Bitalino(ports[0].path)
+    ///
+    /// Returns:
+    ///     A list of SerialPort
+    ///
+    /// Raises:
+    ///     BitalinoError: If the ports cannot be enumerated
+    #[staticmethod]
+    fn list_serial_ports() -> PyResult<Vec<PySerialPort>> {
+        Ok(Bitalino::list_serial_ports()
+            .map_err(to_py_err)?
+            .into_iter()
+            .map(PySerialPort::from)
+            .collect())
+    }
+
+    /// Get the device firmware version.
+    ///
+    /// Returns:
+    ///     Firmware version string (e.g., "BITalino_v5.2")
+    fn version(&mut self) -> PyResult<String> {
+        self.device()?.version().map_err(to_py_err)
+    }
+
+    /// Start data acquisition.
+    ///
+    /// A ``preset`` names a sensor kit and supplies the channels, the
+    /// recommended rate and the default filters of ``start_streaming()``:
+    /// ``"ecg"`` (A2), ``"emg"`` (A1), ``"eda"`` (A3, 100 Hz), ``"eeg"`` (A4),
+    /// ``"eda_ecg"`` (A2, A3) or ``"psychophysiology"`` (A1-A3). Explicit
+    /// ``rate`` and ``channels`` take precedence over it.
+    ///
+    /// Example:
+    ///     >>> device.start(preset="ecg")
+    ///
+    /// Args:
+    ///     rate: Sampling rate in Hz. Must be 1, 10, 100, or 1000. Default:
+    ///         the preset's, or 1000.
+    ///     channels: List of analog channels to acquire (0-5). Default: the
+    ///         preset's, or all channels.
+    ///     preset (Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None):
+    ///         Name of a sensor kit preset. Default: none.
+    ///
+    /// Raises:
+    ///     ValueError: If the preset is unknown.
+    ///     BitalinoError: If starting acquisition fails
+    #[pyo3(signature = (rate=None, channels=None, preset=None))]
+    fn start(
+        &mut self,
+        rate: Option<u16>,
+        channels: Option<Vec<u8>>,
+        preset: Option<&str>,
+    ) -> PyResult<()> {
+        let preset = preset
+            .map(Preset::from_name)
+            .transpose()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let rate = rate.unwrap_or(preset.map_or(1000, |p| p.rate as u16));
+        let channels = channels
+            .unwrap_or_else(|| preset.map_or_else(|| vec![0, 1, 2, 3, 4, 5], |p| p.channels()));
+        self.device()?.start(rate, channels).map_err(to_py_err)?;
+        self.sampling_rate = rate;
+        self.preset = preset;
+        Ok(())
+    }
+
+    /// Start data acquisition at a scheduled wall-clock time.
+    ///
+    /// Sets up the acquisition right away, then blocks (without holding the
+    /// GIL) until ``at`` and sends only the start command, for coordinating
+    /// with other equipment scheduled by clock. ``start_offset`` then tells
+    /// how late the command went out.
+    ///
+    /// Example:
+    ///     >>> at = datetime.now(timezone.utc).replace(second=0, microsecond=0) + timedelta(minutes=1)
+    ///     >>> device.start_at(at, rate=1000, channels=[0, 1])
+    ///
+    /// Args:
+    ///     at (datetime): When to start; a naive ``datetime`` is local time.
+    ///     rate: Sampling rate in Hz. Must be 1, 10, 100, or 1000. Default: 1000.
+    ///     channels: List of analog channels to acquire (0-5). Default: all.
+    ///
+    /// Raises:
+    ///     BitalinoError: If ``at`` has already passed or starting acquisition
+    ///         fails.
+    #[pyo3(signature = (at, rate=1000, channels=None))]
+    fn start_at(
+        &mut self,
+        py: Python<'_>,
+        at: &Bound<'_, PyAny>,
+        rate: u16,
+        channels: Option<Vec<u8>>,
+    ) -> PyResult<()> {
+        let at = system_time(at)?;
+        let channels = channels.unwrap_or_else(|| vec![0, 1, 2, 3, 4, 5]);
+        let device = self.device()?;
+        py.detach(|| device.start_at(at, rate, channels))
+            .map_err(to_py_err)?;
+        self.sampling_rate = rate;
+        self.preset = None;
+        Ok(())
+    }
+
+    /// Check a ``start()`` configuration without touching a device.
+    ///
+    /// Raises where ``start()`` would, and otherwise lists what it would
+    /// quietly change or what is likely to cost data: out-of-range or repeated
+    /// channels, the 6-bit values of a fifth and sixth channel, and data rates
+    /// close to what the Bluetooth link carries.
+    ///
+    /// Example:
+    ///     >>> Bitalino.validate_config(1000, [0, 1, 2, 3, 4, 5])
+    ///     ['With 6 channels, A5 and A6 are sent with 6-bit resolution (0-63)',
+    ///      '6 channels at 1000 Hz is near RFCOMM limits (8000 of 11520 bytes/s); ...']
+    ///
+    /// Args:
+    ///     rate: Sampling rate in Hz.
+    ///     channels: Analog channels to acquire (0-5).
+    ///
+    /// Returns:
+    ///     Warnings, empty if the configuration is fine.
+    ///
+    /// Raises:
+    ///     ValueError: If the rate is not 1, 10, 100 or 1000, or no channel is in 0-5.
+    #[classmethod]
+    fn validate_config(
+        _cls: &Bound<'_, PyType>,
+        rate: u16,
+        channels: Vec<u8>,
+    ) -> PyResult<Vec<String>> {
+        Bitalino::validate_config(rate, &channels)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None:
+    /// Name of the preset passed to the last ``start()``, or None.
+    #[getter]
+    fn preset(&self) -> Option<&'static str> {
+        self.preset.map(|p| p.name)
+    }
+
+    /// Stop data acquisition.
+    fn stop(&mut self) -> PyResult<()> {
+        self.device()?.stop().map_err(to_py_err)
+    }
+
+    /// Bring the device back to idle from any state, e.g. after a crashed
+    /// process left it streaming. BITalino 2.0 devices (and devices not yet
+    /// identified by ``version()``) also get the go-to-idle command; pending
+    /// input is discarded.
+    fn force_idle(&mut self, py: Python<'_>) -> PyResult<()> {
+        let device = self.device()?;
+        py.detach(|| device.force_idle()).map_err(to_py_err)
+    }
+
+    /// Read frames from the device.
+    ///
+    /// Convenience wrapper that discards the batch's timing and integrity counters.
+    /// Use ``read_timed()`` if you need to detect CRC errors or dropped frames.
+    ///
+    /// Args:
+    ///     n_frames: Number of frames to read. Default: 100.
+    ///     timeout_ms: Overall time budget in milliseconds. If it elapses, the
+    ///         frames read so far are returned. Default: wait for all frames.
+    ///
+    /// Returns:
+    ///     List of Frame objects with sequence, digital, and analog attributes.
+    ///
+    /// Raises:
+    ///     BitalinoError: If reading fails
+    #[pyo3(signature = (n_frames=100, timeout_ms=None))]
+    fn read(
+        &mut self,
+        py: Python<'_>,
+        n_frames: usize,
+        timeout_ms: Option<u64>,
+    ) -> PyResult<Vec<PyFrame>> {
+        self.read_timed(py, n_frames, timeout_ms, true, "off")
+            .map(|batch| batch.frames)
+    }
+
+    /// Read frames with timing and error information.
+    ///
+    /// This is the recommended method for high-quality acquisition as it provides
+    /// timing information for sample reconstruction and error statistics.
+    ///
+    /// Args:
+    ///     n_frames: Number of frames to read.
+    ///     timeout_ms: Overall time budget in milliseconds. Default: wait for all frames.
+    ///     allow_partial: On timeout, return the frames read so far with
+    ///         ``partial=True`` instead of raising. Default: True.
+    ///     gap_fill (Literal["off", "hold", "nan"]): Insert placeholder frames
+    ///         (``Frame.filled``) where sequence
+    ///         gaps show lost frames, so the batch has one frame per sample
+    ///         period: ``"hold"`` repeats the last analog values, ``"nan"``
+    ///         marks them NaN in ``arrays()``. Default: ``"off"``.
+    ///
+    /// Returns:
+    ///     FrameBatch with frames, timestamp_us, crc_errors, sequence_gaps, and partial.
+    ///
+    /// Raises:
+    ///     ValueError: If gap_fill is not "off", "hold" or "nan".
+    ///     TimeoutError: If the timeout elapses and allow_partial is False.
+    ///     BitalinoError: If reading fails
+    #[pyo3(signature = (n_frames=100, timeout_ms=None, allow_partial=true, gap_fill="off"))]
+    fn read_timed(
+        &mut self,
+        py: Python<'_>,
+        n_frames: usize,
+        timeout_ms: Option<u64>,
+        allow_partial: bool,
+        gap_fill: &str,
+    ) -> PyResult<PyFrameBatch> {
+        let gap_fill = gap_fill_mode(gap_fill)?;
+        let options = ReadOptions {
+            timeout: timeout_ms.map(std::time::Duration::from_millis),
+            allow_partial,
+            gap_fill,
+            ..ReadOptions::default()
+        };
+        let device = self.device()?;
+        // Other Python threads keep running while this one waits for frames.
+        py.detach(|| device.read_frames_timed_with(n_frames, &options))
+            .map(PyFrameBatch::from)
+            .map_err(to_py_err)
+    }
+
+    /// Read ``seconds`` of data at the acquisition rate as one batch.
+    ///
+    /// Computes the frame count from the sampling rate and keeps reading until
+    /// the batch spans that many samples, so frames discarded for CRC errors
+    /// are made up for. Frames lost on the link count towards the duration and
+    /// show in ``sequence_gaps``.
+    ///
+    /// Example:
+    ///     >>> device.start(rate=1000, channels=[0, 1])
+    ///     >>> batch = device.read_seconds(5.0)  # about 5000 frames
+    ///
+    /// Args:
+    ///     seconds: Duration to cover, in seconds.
+    ///
+    /// Returns:
+    ///     FrameBatch with frames, timestamp_us, crc_errors, and sequence_gaps.
+    ///
+    /// Raises:
+    ///     ValueError: If seconds is negative or not finite.
+    ///     NotInAcquisitionError: If acquisition is not started.
+    ///     BitalinoError: If reading fails
+    fn read_seconds(&mut self, py: Python<'_>, seconds: f64) -> PyResult<PyFrameBatch> {
+        let duration = std::time::Duration::try_from_secs_f64(seconds).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "seconds must be a non-negative finite number, got {seconds}"
+            ))
+        })?;
+        let device = self.device()?;
+        py.detach(|| device.read_for(duration))
+            .map(PyFrameBatch::from)
+            .map_err(to_py_err)
+    }
+
+    /// Read only the frames already buffered, without blocking.
+    ///
+    /// Returns immediately with whatever whole frames have arrived (up to
+    /// ``max_frames``), so it is safe to poll from a GUI or game loop. The batch
+    /// is empty if no complete frame is buffered yet.
+    ///
+    /// Args:
+    ///     max_frames: Upper bound on frames returned. Default: 1000.
+    ///
+    /// Returns:
+    ///     FrameBatch with frames, timestamp_us, crc_errors, and sequence_gaps.
+    ///
+    /// Raises:
+    ///     BitalinoError: If the transport cannot report buffered bytes or reading fails
+    #[pyo3(signature = (max_frames=1000))]
+    fn read_available(&mut self, max_frames: usize) -> PyResult<PyFrameBatch> {
+        self.device()?
+            .read_available(max_frames)
+            .map(PyFrameBatch::from)
+            .map_err(to_py_err)
+    }
+
+    /// Iterate over timed batches until acquisition stops.
+    ///
+    /// Replaces a manual ``read_timed()`` loop. Iteration ends cleanly when
+    /// ``stop()`` or ``close()`` is called on the device, or when the iterator's
+    /// own ``close()`` is called.
+    ///
+    /// Example:
+    ///     >>> for batch in device.frames(batch_size=100):
+    ///     ...     process(batch)
+    ///
+    /// Args:
+    ///     batch_size: Number of frames per yielded batch. Default: 100.
+    ///
+    /// Returns:
+    ///     FrameIterator yielding FrameBatch objects.
+    ///
+    /// Raises:
+    ///     ValueError: If batch_size is 0.
+    ///     NotInAcquisitionError: If acquisition is not started.
+    #[pyo3(signature = (batch_size=100))]
+    fn frames(slf: Bound<'_, Self>, batch_size: usize) -> PyResult<PyFrameIterator> {
+        if batch_size == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "batch_size must be at least 1",
+            ));
+        }
+        if !slf.borrow_mut().device()?.is_acquiring() {
+            return Err(NotInAcquisitionError::new_err(
+                "Acquisition not started. Call start() first.",
+            ));
+        }
+        Ok(PyFrameIterator {
+            device: Some(slf.unbind()),
+            batch_size,
+        })
+    }
+
+    /// Stream batches to ``callback`` from a background thread.
+    ///
+    /// A Rust thread reads continuously and queues each ``FrameBatch`` of
+    /// ``batch_size`` frames for a delivery thread, which calls
+    /// ``callback(batch)`` holding the GIL only for the call. Until
+    /// ``stop_streaming()``, other device methods raise ``RuntimeError``.
+    /// Exceptions raised by the callback are reported as unraisable and
+    /// streaming continues.
+    ///
+    /// When the callback falls ``queue_size`` batches behind, ``overflow``
+    /// decides what gives:
+    ///
+    /// - ``"block"``: pause reading until it catches up. No batch is skipped,
+    ///   but a long pause overflows the device's buffer and frames are lost on
+    ///   the link (visible as ``sequence_gaps``).
+    /// - ``"drop_oldest"``: discard the oldest queued batch, staying close to
+    ///   live.
+    /// - ``"drop_newest"``: discard the batch just read.
+    /// - ``"error"``: end streaming; ``stop_streaming()`` raises a
+    ///   ``BitalinoError`` with ``kind == "overflow"``.
+    ///
+    /// Discarded batches are counted in ``dropped_batches``, and each batch
+    /// keeps its ``first_sample_index``, so gaps in time stay detectable.
+    ///
+    /// ``filters`` are applied on the reader thread before the callback sees
+    /// each batch, so no per-batch round trip through scipy is needed.
+    ///
+    /// Example:
+    ///     >>> device.start(rate=1000, channels=[0, 1])
+    ///     >>> device.start_streaming(lambda batch: plot(batch.arrays()))
+    ///     >>> ...
+    ///     >>> device.stop_streaming()
+    ///
+    /// Args:
+    ///     callback (Callable[[FrameBatch], object]): Called with each
+    ///         FrameBatch, on the delivery thread.
+    ///     batch_size: Frames per batch. Default: 100.
+    ///     filters: Filters applied in order to every analog channel.
+    ///         Default: the preset's filters if started with one, else none.
+    ///     queue_size: Batches the callback may fall behind by. Default: 16.
+    ///     overflow (Literal["block", "drop_oldest", "drop_newest", "error"]):
+    ///         "block", "drop_oldest", "drop_newest" or "error".
+    ///         Default: "block".
+    ///
+    /// Raises:
+    ///     ValueError: If batch_size or queue_size is 0, overflow is unknown,
+    ///         or a filter is invalid at the sampling rate.
+    ///     NotInAcquisitionError: If acquisition is not started.
+    #[pyo3(signature = (callback, batch_size=100, filters=None, queue_size=16, overflow="block"))]
+    fn start_streaming(
+        &mut self,
+        callback: Py<PyAny>,
+        batch_size: usize,
+        filters: Option<Vec<PyFilter>>,
+        queue_size: usize,
+        overflow: &str,
+    ) -> PyResult<()> {
+        if batch_size == 0 || queue_size == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "batch_size and queue_size must be at least 1",
+            ));
+        }
+        let overflow = OverflowPolicy::from_name(overflow).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Unknown overflow policy {overflow:?}; expected \"block\", \"drop_oldest\", \"drop_newest\" or \"error\""
+            ))
+        })?;
+        let filters: Vec<dsp::Filter> = match filters {
+            Some(filters) => filters.iter().map(|f| f.0).collect(),
+            None => self.preset.map_or_else(Vec::new, |p| p.filters.to_vec()),
+        };
+        let rate = self.device()?.sampling_rate();
+        let bank = dsp::FilterBank::new(rate, &filters)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let device = self.take_device()?;
+        let on_batch = move |batch: &FrameBatch| {
+            Python::attach(|py| {
+                if let Err(e) = callback.call1(py, (PyFrameBatch::from(batch.clone()),)) {
+                    e.write_unraisable(py, None);
+                }
+            });
+        };
+        let pipeline = Pipeline::new(device)
+            .batch_size(batch_size)
+            .queue_len(queue_size)
+            .stage(bank)
+            .live_with(on_batch, overflow)
+            .start();
+        match pipeline {
+            Ok(reader) => {
+                self.reader = Some(reader);
+                self.dropped_batches = 0;
+                Ok(())
+            }
+            Err((device, e)) => {
+                *self.slot() = Some(*device);
+                Err(to_py_err(e))
+            }
+        }
+    }
+
+    /// Stop the background reader started by ``start_streaming()``.
+    ///
+    /// Waits for the reader thread, delivering the frames collected so far as a
+    /// last (shorter) batch. Acquisition keeps running; call ``stop()`` to end
+    /// it. No-op if not streaming.
+    ///
+    /// Raises:
+    ///     BitalinoError: If streaming ended early because a read failed (e.g.
+    ///         the link dropped).
+    fn stop_streaming(&mut self, py: Python<'_>) -> PyResult<()> {
+        match self.join_reader(py) {
+            Some(e) => Err(to_py_err(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the background reader is running.
+    ///
+    /// False after ``stop_streaming()``, and also once a read error has ended
+    /// streaming early (``stop_streaming()`` then raises that error).
+    #[getter]
+    fn streaming(&self) -> bool {
+        self.reader
+            .as_ref()
+            .is_some_and(RunningPipeline::is_running)
+    }
+
+    /// Batches discarded because the streaming callback fell behind.
+    ///
+    /// Counts the current ``start_streaming()`` session, or the last one once
+    /// stopped. Always 0 with ``overflow="block"``.
+    #[getter]
+    fn dropped_batches(&self) -> u64 {
+        self.reader
+            .as_ref()
+            .map_or(self.dropped_batches, |r| r.dropped_batches().iter().sum())
+    }
+
+    /// Block until the device is reliably streaming valid frames.
+    ///
+    /// After ``start()`` returns, the Bluetooth link may still be warming up: the
+    /// device is emitting frames but early bytes can be lost or corrupted. This
+    /// method discards warm-up frames and returns as soon as one CRC-valid frame
+    /// arrives. The valid frame's sequence number is stashed so the next
+    /// ``read_timed()`` call does not flag a spurious sequence gap.
+    ///
+    /// Args:
+    ///     timeout: Maximum time to wait, in seconds. Default: 2.0.
+    ///         Must be finite and in (0, 3600].
+    ///
+    /// Raises:
+    ///     ValueError: If timeout is not a finite number in (0, 3600] seconds.
+    ///     TimeoutError: If no CRC-valid frame arrives before the deadline.
+    ///     CrcError: If frames arrive but none passes the CRC check.
+    ///     BitalinoError: If the underlying transport fails (e.g. link dropped).
+    ///     NotInAcquisitionError: If acquisition is not started.
+    #[pyo3(signature = (timeout=2.0))]
+    fn wait_until_streaming(&mut self, py: Python<'_>, timeout: f64) -> PyResult<()> {
+        if !timeout.is_finite() || timeout <= 0.0 || timeout > MAX_WAIT_TIMEOUT_SECS {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "timeout must be finite and in (0, {}] seconds; got {}",
+                MAX_WAIT_TIMEOUT_SECS, timeout
+            )));
+        }
+        let duration = std::time::Duration::from_secs_f64(timeout);
+        let device = self.device()?;
+        py.detach(|| device.wait_until_streaming(duration))
+            .map_err(to_py_err)
+    }
+
+    /// Get the current sampling rate.
+    #[getter]
+    fn sampling_rate(&self) -> u16 {
+        self.sampling_rate
+    }
+
+    /// Read/write timeout of the connection in seconds (5.0 by default).
+    ///
+    /// Lower it during acquisition to detect a stalled link quickly; raise it
+    /// for slow command exchanges. Reads given their own timeout still use the
+    /// shorter of the two.
+    ///
+    /// Raises:
+    ///     ValueError: If set to zero, a negative or a non-finite value.
+    #[getter]
+    fn io_timeout(&self) -> Option<f64> {
+        self.peek()
+            .as_ref()
+            .map(|device| device.io_timeout().as_secs_f64())
+    }
+
+    #[setter]
+    fn set_io_timeout(&mut self, timeout: f64) -> PyResult<()> {
+        let timeout = seconds("io_timeout", timeout)?;
+        if timeout.is_zero() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "io_timeout must be greater than zero",
+            ));
+        }
+        self.device()?.set_io_timeout(timeout).map_err(to_py_err)
+    }
+
+    /// Get microseconds elapsed since acquisition started.
+    #[getter]
+    fn elapsed_us(&self) -> Option<u64> {
+        self.peek().as_ref().and_then(Bitalino::elapsed_us)
+    }
+
+    /// Wall-clock start of the current (or most recent) acquisition.
+    ///
+    /// Recorded at ``start()`` alongside the monotonic clock behind
+    /// ``elapsed_us``, for aligning recordings with external logs or video.
+    ///
+    /// Returns:
+    ///     datetime | None: Timezone-aware UTC ``datetime``, or None before the
+    ///     first ``start()``.
+    ///
+    /// Raises:
+    ///     RuntimeError: If the device is closed or streaming.
+    fn acquisition_start_utc<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let started = self
+            .peek()
+            .as_ref()
+            .map(Bitalino::acquisition_start_utc)
+            .ok_or_else(|| self.unavailable())?;
+        started.map(|t| utc_datetime(py, t)).transpose()
+    }
+
+    /// How late the start command of the last ``start_at()`` went out.
+    ///
+    /// Returns:
+    ///     float | None: Seconds between the scheduled and the actual start
+    ///     command, or None if acquisition was started without a schedule.
+    ///
+    /// Raises:
+    ///     RuntimeError: If the device is closed or streaming.
+    #[getter]
+    fn start_offset(&self) -> PyResult<Option<f64>> {
+        self.peek()
+            .as_ref()
+            .map(|device| device.start_offset().map(|offset| offset.as_secs_f64()))
+            .ok_or_else(|| self.unavailable())
+    }
+
+    /// Cumulative statistics for the current (or most recent) acquisition.
+    ///
+    /// Replaces summing ``FrameBatch`` counters by hand: totals reset on
+    /// ``start()``, include reads that raised, and are kept after ``stop()``.
+    ///
+    /// Raises:
+    ///     RuntimeError: If the device is closed or streaming.
+    #[getter]
+    fn stats(&self) -> PyResult<PyAcquisitionStats> {
+        let stats = self.peek().as_ref().map(Bitalino::stats);
+        stats
+            .map(PyAcquisitionStats::from)
+            .ok_or_else(|| self.unavailable())
+    }
+
+    /// Windowed statistics of each acquired channel.
+    ///
+    /// Computed from the most recent ``stats_window`` seconds of frames as they
+    /// are read, so biofeedback code can poll a smoothed level instead of
+    /// processing raw frames. Empty until the first frame after ``start()``.
+    ///
+    /// Returns:
+    ///     List of ChannelStats, in acquisition channel order.
+    ///
+    /// Raises:
+    ///     RuntimeError: If the device is closed or streaming.
+    fn channel_stats(&self) -> PyResult<Vec<PyChannelStats>> {
+        let stats = self.peek().as_ref().map(Bitalino::channel_stats);
+        stats
+            .map(|stats| stats.into_iter().map(PyChannelStats::from).collect())
+            .ok_or_else(|| self.unavailable())
+    }
+
+    /// Literal["lenient", "strict"] | int | None: What to do with data failing
+    /// its CRC check, for ``state()`` and reads.
+    ///
+    /// ``"lenient"`` (default) logs and carries on: ``state()`` returns the
+    /// reply as decoded and reads drop corrupt frames. ``"strict"`` raises
+    /// ``CrcError`` on the first mismatch. An integer ``n`` re-issues the
+    /// state command up to ``n`` times and lets a read drop up to ``n``
+    /// corrupt frames before raising.
+    ///
+    /// Raises:
+    ///     ValueError: If set to another string.
+    #[getter]
+    fn crc_policy<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let Some(policy) = self.peek().as_ref().map(Bitalino::crc_policy) else {
+            return Ok(None);
+        };
+        Ok(Some(match policy {
+            CrcPolicy::Lenient => "lenient".into_pyobject(py)?.into_any(),
+            CrcPolicy::Strict => "strict".into_pyobject(py)?.into_any(),
+            CrcPolicy::Retry(retries) => retries.into_pyobject(py)?.into_any(),
+        }))
+    }
+
+    #[setter]
+    fn set_crc_policy(&mut self, policy: &Bound<'_, PyAny>) -> PyResult<()> {
+        let policy = if let Ok(retries) = policy.extract::<u32>() {
+            CrcPolicy::Retry(retries)
+        } else {
+            let name: String = policy.extract()?;
+            CrcPolicy::from_name(&name).ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "crc_policy must be \"lenient\", \"strict\" or a retry count; got {name:?}"
+                ))
+            })?
+        };
+        self.device()?.set_crc_policy(policy);
+        Ok(())
+    }
+
+    /// Window behind ``channel_stats()``, in seconds (1.0 by default).
+    ///
+    /// Raises:
+    ///     ValueError: If set to zero, a negative or a non-finite value.
+    #[getter]
+    fn stats_window(&self) -> Option<f64> {
+        self.peek()
+            .as_ref()
+            .map(|device| device.stats_window().as_secs_f64())
+    }
+
+    #[setter]
+    fn set_stats_window(&mut self, window: f64) -> PyResult<()> {
+        let window = seconds("stats_window", window)?;
+        if window.is_zero() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "stats_window must be greater than zero",
+            ));
+        }
+        self.device()?.set_stats_window(window);
+        Ok(())
+    }
+
+    /// Estimated drift of the device crystal in parts per million.
+    ///
+    /// Compares the number of frames produced against host monotonic time.
+    /// Positive values mean the device samples faster than nominal. ``None``
+    /// until roughly 10 seconds of data have been read with ``read_timed()``.
+    #[getter]
+    fn drift_ppm(&self) -> Option<f64> {
+        self.peek().as_ref().and_then(Bitalino::drift_ppm)
+    }
+
+    /// Drift-corrected time of a sample, in microseconds since acquisition start.
+    ///
+    /// Uses the estimated true sampling rate once available (see ``drift_ppm``),
+    /// and the nominal rate before that.
+    ///
+    /// Args:
+    ///     sample_index: Zero-based index of the sample since ``start()``.
+    ///
+    /// Returns:
+    ///     Timestamp in microseconds as a float.
+    fn sample_time_us(&mut self, sample_index: u64) -> PyResult<f64> {
+        Ok(self.device()?.timing().sample_time_us(sample_index))
+    }
+
+    /// Query the adapter for the radio conditions of the active connection.
+    ///
+    /// Batches already carry a sample taken at most once per second
+    /// (``FrameBatch.rssi`` / ``FrameBatch.link_quality``); use this for an
+    /// immediate reading, e.g. while repositioning the device.
+    ///
+    /// Returns:
+    ///     ``(rssi, link_quality)``; either may be None if the adapter rejects
+    ///     that query.
+    ///
+    /// Raises:
+    ///     BitalinoError: If the link cannot report radio metrics (serial, TCP,
+    ///         replay, or a platform other than Linux).
+    fn link_quality(&mut self) -> PyResult<(Option<i8>, Option<u8>)> {
+        self.device()?
+            .link_quality()
+            .map(|q| (q.rssi, q.link_quality))
+            .map_err(to_py_err)
+    }
+
+    /// Check if this is a BITalino 2.0+ device.
+    ///
+    /// BITalino 2.0+ supports additional features like state(), pwm(), and
+    /// trigger() in idle mode. Call version() first to detect device type.
+    ///
+    /// Returns:
+    ///     True if device is BITalino 2.0+
+    #[getter]
+    fn is_bitalino2(&self) -> bool {
+        self.peek().as_ref().is_some_and(Bitalino::is_bitalino2)
+    }
+
+    /// Identity and capabilities of the connected device.
+    ///
+    /// Returns:
+    ///     DeviceInfo with the MAC, firmware version, revision, supported
+    ///     sampling rates, analog channel count and connection kind.
+    ///
+    /// Raises:
+    ///     RuntimeError: If the device is closed or streaming.
+    fn device_info(&self) -> PyResult<PyDeviceInfo> {
+        self.peek()
+            .as_ref()
+            .map(|device| PyDeviceInfo::from(device.device_info()))
+            .ok_or_else(|| self.unavailable())
+    }
+
+    /// Set the battery threshold level.
+    ///
+    /// When battery voltage drops below this threshold, the device LED will blink.
+    /// Must be called when not in acquisition mode.
+    ///
+    /// Args:
+    ///     threshold: Threshold value (0-63).
+    ///         0 = 3.4V (minimum), 63 = 3.8V (maximum)
+    ///
+    /// Raises:
+    ///     BitalinoError: If device is currently in acquisition mode
+    #[pyo3(signature = (threshold=30))]
+    fn set_battery_threshold(&mut self, threshold: u8) -> PyResult<()> {
+        self.device()?
+            .set_battery_threshold(threshold)
+            .map_err(to_py_err)
+    }
+
+    /// Alias for set_battery_threshold for compatibility with official library.
+    #[pyo3(signature = (value=30))]
+    fn battery(&mut self, value: u8) -> PyResult<()> {
+        self.set_battery_threshold(value)
+    }
+
+    /// Get the current device state (BITalino 2.0+ only).
+    ///
+    /// Returns the current values of all analog channels, digital channels,
+    /// battery level, and battery threshold. Must be called when not in acquisition.
+    ///
+    /// Returns:
+    ///     DeviceState object with analog, battery, battery_threshold, digital,
+    ///     battery_voltage, and is_battery_low properties.
+    ///
+    /// Raises:
+    ///     BitalinoError: If device is not BITalino 2.0+, is in acquisition mode,
+    ///         or communication fails
+    fn state(&mut self) -> PyResult<PyDeviceState> {
+        self.device()?
+            .state()
+            .map(PyDeviceState::from)
+            .map_err(to_py_err)
+    }
+
+    /// Get the device state without ending an ongoing acquisition (BITalino 2.0+ only).
+    ///
+    /// Pauses streaming, queries ``state()``, and restarts with the same rate and
+    /// channels. ``elapsed_us`` and drift estimation continue across the pause.
+    /// Equivalent to ``state()`` when not acquiring.
+    ///
+    /// Returns:
+    ///     Tuple of (DeviceState, samples_lost), where samples_lost estimates how
+    ///     many samples were not recorded during the pause.
+    ///
+    /// Raises:
+    ///     BitalinoError: If device is not BITalino 2.0+ or the query/restart fails
+    fn state_during_acquisition(&mut self) -> PyResult<(PyDeviceState, u64)> {
+        self.device()?
+            .state_during_acquisition()
+            .map(|(state, lost)| (PyDeviceState::from(state), lost))
+            .map_err(to_py_err)
+    }
+
+    /// Install a low-battery hook: ``callback`` runs when a check finds the
+    /// battery below ``threshold`` Volts.
+    ///
+    /// Checks run inside calls made on the device, not on a background task.
+    /// While acquiring, the first read after each ``interval`` takes a real
+    /// reading with ``state_during_acquisition()`` (BITalino 2.0+), pausing the
+    /// stream for a few hundred milliseconds; if that query fails, the voltage
+    /// is extrapolated from earlier readings instead. While idle, call
+    /// ``poll_battery()``, which queries the device at most every ``interval``
+    /// seconds. Prefer intervals of minutes to keep the pauses rare.
+    ///
+    /// Args:
+    ///     callback (Callable[[float, bool], object]): Called as
+    ///         ``callback(voltage, estimated)`` on every check
+    ///         below the threshold. Exceptions are reported as unraisable.
+    ///     interval: Minimum seconds between checks. Default: 60.0.
+    ///     threshold: Voltage below which the callback fires. Default: 3.5.
+    ///
+    /// Raises:
+    ///     ValueError: If interval is not a finite positive number.
+    #[pyo3(signature = (callback, interval=60.0, threshold=3.5))]
+    fn monitor_battery(
+        &mut self,
+        callback: Py<PyAny>,
+        interval: f64,
+        threshold: f32,
+    ) -> PyResult<()> {
+        let interval = std::time::Duration::try_from_secs_f64(interval)
+            .ok()
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "interval must be a finite positive number of seconds; got {}",
+                    interval
+                ))
+            })?;
+        self.device()?
+            .monitor_battery(interval, threshold, move |reading| {
+                Python::attach(|py| {
+                    if let Err(e) = callback.call1(py, (reading.voltage, reading.estimated)) {
+                        e.write_unraisable(py, None);
+                    }
+                });
+            });
+        Ok(())
+    }
+
+    /// Remove the battery monitor installed by ``monitor_battery()``.
+    fn stop_battery_monitor(&mut self) -> PyResult<()> {
+        self.device()?.stop_battery_monitor();
+        Ok(())
+    }
+
+    /// Run the battery monitor now if its interval has elapsed. Reads already
+    /// do this while acquiring; see ``monitor_battery()``.
+    ///
+    /// Returns:
+    ///     ``(voltage, estimated)`` if a reading was taken, else None.
+    ///
+    /// Raises:
+    ///     BitalinoError: If the state query fails
+    fn poll_battery(&mut self) -> PyResult<Option<(f32, bool)>> {
+        self.device()?
+            .poll_battery()
+            .map(|reading| reading.map(|r| (r.voltage, r.estimated)))
+            .map_err(to_py_err)
+    }
+
+    /// Call ``callback`` when the frame rate falls short of the sampling rate.
+    ///
+    /// Each timed read counts the valid frames it returned; when more than
+    /// ``max_deficit`` of the nominal frames are missing over the last
+    /// ``window`` seconds, the callback runs on the reading thread and a
+    /// warning is logged, at most once per window while the shortfall lasts.
+    /// The window restarts with every ``start()``. Replaces any previous
+    /// monitor; pass None to stop monitoring.
+    ///
+    /// Example:
+    ///     >>> device.monitor_throughput(lambda got, hz, deficit: print(f"{got:.0f}/{hz:.0f} Hz"))
+    ///
+    /// Args:
+    ///     callback (Callable[[float, float, float], object] | None): Called as
+    ///         ``callback(received_hz, expected_hz, deficit)``, where deficit is
+    ///         the missing fraction ``1 - received_hz / expected_hz``.
+    ///         Exceptions are reported as unraisable.
+    ///     window: Seconds the frame rate is measured over. Default: 5.0.
+    ///     max_deficit: Missing fraction tolerated before warning. Default: 0.1.
+    ///
+    /// Raises:
+    ///     ValueError: If window is not a finite positive number.
+    #[pyo3(signature = (callback, window=5.0, max_deficit=0.1))]
+    fn monitor_throughput(
+        &mut self,
+        callback: Option<Py<PyAny>>,
+        window: f64,
+        max_deficit: f64,
+    ) -> PyResult<()> {
+        let window = std::time::Duration::try_from_secs_f64(window)
+            .ok()
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "window must be a finite positive number of seconds; got {}",
+                    window
+                ))
+            })?;
+        let device = self.device()?;
+        match callback {
+            Some(callback) => device.monitor_throughput(window, max_deficit, move |warning| {
+                Python::attach(|py| {
+                    let args = (warning.received_hz, warning.expected_hz, warning.deficit);
+                    if let Err(e) = callback.call1(py, args) {
+                        e.write_unraisable(py, None);
+                    }
+                });
+            }),
+            None => device.stop_throughput_monitor(),
+        }
+        Ok(())
+    }
+
+    /// Call ``callback`` for every digital-channel transition in incoming frames.
+    ///
+    /// Edges are detected while reading (``read()``, ``read_timed()``,
+    /// ``frames()``, ...), so the callback runs on the reading thread. Replaces
+    /// any previous handler; pass None to stop reporting edges.
+    ///
+    /// Args:
+    ///     callback (Callable[[int, bool, int], object] | None): Called as
+    ///         ``callback(channel, rising, sample_index)``, where
+    ///         channel indexes ``Frame.digital`` (0 = I1, 1 = I2, 2 = O1, 3 = O2)
+    ///         and sample_index counts samples since ``start()``. Exceptions are
+    ///         reported as unraisable.
+    #[pyo3(signature = (callback))]
+    fn on_digital_edge(&mut self, callback: Option<Py<PyAny>>) -> PyResult<()> {
+        let device = self.device()?;
+        match callback {
+            Some(callback) => device.on_digital_edge(move |edge| {
+                Python::attach(|py| {
+                    let args = (edge.channel, edge.rising, edge.sample_index);
+                    if let Err(e) = callback.call1(py, args) {
+                        e.write_unraisable(py, None);
+                    }
+                });
+            }),
+            None => device.clear_digital_edge_handler(),
+        }
+        Ok(())
+    }
+
+    /// Detect heartbeats on an ECG channel and call ``callback`` for each.
+    ///
+    /// A Pan-Tompkins QRS detector runs while reading, like digital-edge
+    /// detection. It restarts with every ``start()``, needs two seconds of
+    /// signal to set its thresholds, and requires a rate of at least 100 Hz.
+    /// Replaces any previous handler; pass None to stop detection.
+    ///
+    /// Example:
+    ///     >>> device.on_heartbeat(1, lambda i, rr, bpm: print(f"{bpm} BPM"))
+    ///
+    /// Args:
+    ///     channel: Analog channel carrying the ECG (0-5).
+    ///     callback (Callable[[int, float | None, float | None], object] | None):
+    ///         Called as ``callback(sample_index, rr, bpm)``, where
+    ///         sample_index is the R peak counted since ``start()`` and rr the
+    ///         interval to the previous beat in seconds (rr and bpm are None for
+    ///         the first beat). Exceptions are reported as unraisable.
+    ///
+    /// Raises:
+    ///     BitalinoError: If channel is not 0-5.
+    #[pyo3(signature = (channel, callback))]
+    fn on_heartbeat(&mut self, channel: u8, callback: Option<Py<PyAny>>) -> PyResult<()> {
+        let device = self.device()?;
+        match callback {
+            Some(callback) => device
+                .on_heartbeat(channel, move |beat| {
+                    Python::attach(|py| {
+                        let args = (beat.sample_index, beat.rr_secs, beat.bpm);
+                        if let Err(e) = callback.call1(py, args) {
+                            e.write_unraisable(py, None);
+                        }
+                    });
+                })
+                .map_err(to_py_err),
+            None => {
+                device.clear_heartbeat_handler();
+                Ok(())
+            }
+        }
+    }
+
+    /// Decompose an EDA channel and call ``callback`` for each skin-conductance
+    /// response (SCR).
+    ///
+    /// Samples are converted to microsiemens, smoothed below 1 Hz and split into
+    /// a tonic level and a phasic component (see ``eda_level``) while reading.
+    /// Responses rising at least 0.01 uS are reported shortly after their peak.
+    /// Replaces any previous handler; pass None to stop decomposition.
+    ///
+    /// Args:
+    ///     channel: Analog channel carrying the EDA sensor (0-5).
+    ///     callback (Callable[[int, int, float, float], object] | None): Called
+    ///         as ``callback(onset_index, peak_index, amplitude, latency)``, with sample indices counted since ``start()``, the
+    ///         amplitude in uS and the onset-to-peak latency in seconds.
+    ///         Exceptions are reported as unraisable.
+    ///
+    /// Raises:
+    ///     BitalinoError: If channel is not 0-5.
+    #[pyo3(signature = (channel, callback))]
+    fn on_scr(&mut self, channel: u8, callback: Option<Py<PyAny>>) -> PyResult<()> {
+        let device = self.device()?;
+        match callback {
+            Some(callback) => device
+                .on_scr(channel, move |scr| {
+                    Python::attach(|py| {
+                        let args = (
+                            scr.onset_index,
+                            scr.peak_index,
+                            scr.amplitude_us,
+                            scr.latency_secs,
+                        );
+                        if let Err(e) = callback.call1(py, args) {
+                            e.write_unraisable(py, None);
+                        }
+                    });
+                })
+                .map_err(to_py_err),
+            None => {
+                device.clear_scr_handler();
+                Ok(())
+            }
+        }
+    }
+
+    /// Latest decomposed skin conductance as ``(conductance, tonic, phasic)``
+    /// in uS, or None without an ``on_scr()`` handler or before its first
+    /// sample.
+    #[getter]
+    fn eda_level(&self) -> Option<(f64, f64, f64)> {
+        let level = self.peek().as_ref().and_then(Bitalino::eda_level)?;
+        Some((level.conductance_us, level.tonic_us, level.phasic_us))
+    }
+
+    /// Record an event marker at the current sample.
+    ///
+    /// Use from stimulus-presentation code to mark events on the signal's sample
+    /// axis. The sample index is estimated from the host clock, so it does not
+    /// lag behind by the Bluetooth buffering delay.
+    ///
+    /// Args:
+    ///     label: Free-form marker label.
+    ///
+    /// Returns:
+    ///     The recorded Annotation.
+    ///
+    /// Raises:
+    ///     NotInAcquisitionError: If acquisition is not running.
+    fn annotate(&mut self, label: String) -> PyResult<PyAnnotation> {
+        self.device()?
+            .annotate(label)
+            .map(PyAnnotation::from)
+            .map_err(to_py_err)
+    }
+
+    /// Markers recorded since start(), oldest first.
+    #[getter]
+    fn annotations(&mut self) -> PyResult<Vec<PyAnnotation>> {
+        Ok(self
+            .device()?
+            .annotations()
+            .iter()
+            .cloned()
+            .map(PyAnnotation::from)
+            .collect())
+    }
+
+    /// Remove and return the recorded markers (e.g. after writing them out).
+    fn take_annotations(&mut self) -> PyResult<Vec<PyAnnotation>> {
+        Ok(self
+            .device()?
+            .take_annotations()
+            .into_iter()
+            .map(PyAnnotation::from)
+            .collect())
+    }
+
+    /// Output changes sent with trigger(), trigger_pulse() and pwm() since
+    /// start(), oldest first.
+    ///
+    /// Commands sent while idle are not recorded, and neither are PWM
+    /// patterns or ramps.
+    #[getter]
+    fn output_events(&mut self) -> PyResult<Vec<PyOutputEvent>> {
+        Ok(self
+            .device()?
+            .output_events()
+            .into_iter()
+            .map(PyOutputEvent::from)
+            .collect())
+    }
+
+    /// Remove and return the recorded output changes (e.g. after writing them
+    /// out with the annotations).
+    fn take_output_events(&mut self) -> PyResult<Vec<PyOutputEvent>> {
+        Ok(self
+            .device()?
+            .take_output_events()
+            .into_iter()
+            .map(PyOutputEvent::from)
+            .collect())
+    }
+
+    /// Start collecting the running acquisition into a Session.
+    ///
+    /// The session records the start time, device identity and channels now,
+    /// and picks up annotations, output events and statistics when ended. An
+    /// open session is replaced.
+    ///
+    /// Raises:
+    ///     NotInAcquisitionError: If acquisition is not running.
+    fn begin_session(&mut self) -> PyResult<PySession> {
+        self.device()?
+            .begin_session()
+            .map(|session| PySession::from(session.clone()))
+            .map_err(to_py_err)
+    }
+
+    /// Close the session opened with begin_session() and return it, filled
+    /// with the annotations, output events and statistics so far. Can be
+    /// called after stop().
+    ///
+    /// Raises:
+    ///     BitalinoError: If no session is open.
+    fn end_session(&mut self) -> PyResult<PySession> {
+        self.device()?
+            .end_session()
+            .map(PySession::from)
+            .map_err(to_py_err)
+    }
+
+    /// Tee the raw acquisition stream into a file for debugging or replay.
+    ///
+    /// Every byte read while decoding frames is written exactly as received,
+    /// including frames that fail CRC checks. The dump can be played back with
+    /// ``Bitalino.replay()``.
+    ///
+    /// Args:
+    ///     path: Output file (created or truncated).
+    ///
+    /// Raises:
+    ///     BitalinoError: If the file cannot be created
+    fn enable_raw_capture(&mut self, path: &str) -> PyResult<()> {
+        self.device()?.enable_raw_capture(path).map_err(to_py_err)
+    }
+
+    /// Stop the raw capture started with ``enable_raw_capture()`` and flush it.
+    fn disable_raw_capture(&mut self) -> PyResult<()> {
+        self.device()?.disable_raw_capture().map_err(to_py_err)
+    }
+
+    /// Set digital output pins.
+    ///
+    /// Controls the digital output pins for external circuits or LED control.
+    ///
+    /// Args:
+    ///     outputs: List of output values (0 or 1).
+    ///         BITalino 2.0: [O1, O2] - works in both idle and acquisition modes
+    ///         BITalino 1.0: [O1, O2, O3, O4] - requires acquisition mode
+    ///
+    /// Raises:
+    ///     NotInAcquisitionError: If BITalino 1.0 and not in acquisition mode
+    #[pyo3(signature = (outputs=None))]
+    fn trigger(&mut self, outputs: Option<Vec<u8>>) -> PyResult<()> {
+        let outputs = outputs.unwrap_or_else(|| vec![0, 0]);
+        self.device()?.trigger(&outputs).map_err(to_py_err)
+    }
+
+    /// Raise digital outputs for ``width`` seconds, then bring them back down.
+    ///
+    /// Outputs set to 1 go high at once; the others keep their level. The
+    /// call returns immediately and the outputs fall ``width`` seconds after
+    /// they rose, so frames can be read during the pulse, e.g. to send sync
+    /// pulses to an EEG amplifier or a camera. Connections that cannot be
+    /// written from another thread (Windows Bluetooth, replay) block for the
+    /// pulse instead. A new pulse, ``trigger()`` or ``stop()`` ends a pending
+    /// pulse early.
+    ///
+    /// Example:
+    ///     >>> device.trigger_pulse([1, 0], 0.01)  # 10 ms on O1
+    ///
+    /// Args:
+    ///     outputs: Outputs to pulse (0 or 1), same layout as ``trigger()``.
+    ///     width: Pulse width in seconds.
+    ///
+    /// Raises:
+    ///     ValueError: If width is negative or not finite.
+    ///     NotInAcquisitionError: If BITalino 1.0 and not in acquisition mode
+    fn trigger_pulse(&mut self, py: Python<'_>, outputs: Vec<u8>, width: f64) -> PyResult<()> {
+        let width = seconds("width", width)?;
+        let device = self.device()?;
+        py.detach(|| device.trigger_pulse(&outputs, width))
+            .map_err(to_py_err)
+    }
+
+    /// Send raw bytes to the device and read back its reply.
+    ///
+    /// An escape hatch for experimenting with undocumented firmware commands:
+    /// the bytes are written as given (one command byte at a time) and the
+    /// driver's own view of the device is not updated, so a raw start or stop
+    /// confuses later reads. Prefer the typed methods for anything they
+    /// cover.
+    ///
+    /// Example:
+    ///     >>> device.raw(b"\x07", n_bytes=64, timeout=0.5)  # version request
+    ///     b'BITalino_v5.2\n'
+    ///
+    /// Args:
+    ///     data (bytes): Bytes to send; may be empty to only read.
+    ///     n_bytes: Most bytes to read back. Default: 0 (send only).
+    ///     timeout: Seconds to wait for ``n_bytes``. Default: 1.0.
+    ///
+    /// Returns:
+    ///     The bytes that arrived within the timeout, possibly fewer than
+    ///     ``n_bytes``.
+    ///
+    /// Raises:
+    ///     ValueError: If timeout is negative or not finite.
+    ///     BitalinoError: If writing or reading fails
+    #[pyo3(signature = (data=b"".to_vec(), n_bytes=0, timeout=1.0))]
+    fn raw<'py>(
+        &mut self,
+        py: Python<'py>,
+        data: Vec<u8>,
+        n_bytes: usize,
+        timeout: f64,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let timeout = seconds("timeout", timeout)?;
+        let device = self.device()?;
+        let reply = py
+            .detach(|| {
+                device.send_raw(&data)?;
+                if n_bytes == 0 {
+                    return Ok(Vec::new());
+                }
+                device.read_raw(n_bytes, timeout)
+            })
+            .map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &reply))
+    }
+
+    /// Set PWM output value (BITalino 2.0+ only).
+    ///
+    /// Controls the pulse-width modulation output for LED brightness
+    /// or other PWM-driven devices.
+    ///
+    /// Args:
+    ///     value: PWM duty cycle (0-255).
+    ///         0 = 0% duty cycle (always off)
+    ///         255 = 100% duty cycle (always on)
+    ///
+    /// Raises:
+    ///     BitalinoError: If device is not BITalino 2.0+
+    #[pyo3(signature = (value=100))]
+    fn pwm(&mut self, value: u8) -> PyResult<()> {
+        self.device()?.pwm(value).map_err(to_py_err)
+    }
+
+    /// Play PWM ``steps`` on a background thread (BITalino 2.0+ only).
+    ///
+    /// Each ``(value, seconds)`` step is held for its duration, timed from the
+    /// start of the pattern, and the last value stays set. Reads and other
+    /// calls go on meanwhile, so an LED can flash for photic stimulation or a
+    /// haptic driver pulse during an acquisition. Sending a value takes about
+    /// 0.1 s, so shorter steps are stretched. A new pattern or ``pwm()``
+    /// replaces a running one.
+    ///
+    /// Example:
+    ///     >>> flash = [(255, 0.5), (0, 0.5)] * 10  # 1 Hz for 10 s
+    ///     >>> device.pwm_pattern(flash)
+    ///
+    /// Args:
+    ///     steps: List of ``(value, seconds)`` pairs, value 0-255.
+    ///
+    /// Returns:
+    ///     PwmSchedule to wait for or cancel.
+    ///
+    /// Raises:
+    ///     ValueError: If a duration is negative or not finite.
+    ///     BitalinoError: If device is not BITalino 2.0+, or the connection
+    ///         cannot be written from another thread (Windows Bluetooth, replay).
+    fn pwm_pattern(&mut self, steps: Vec<(u8, f64)>) -> PyResult<PyPwmSchedule> {
+        let steps = steps
+            .into_iter()
+            .map(|(value, secs)| Ok((value, seconds("step duration", secs)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        let schedule = self.device()?.pwm_pattern(&steps).map_err(to_py_err)?;
+        Ok(PyPwmSchedule {
+            schedule: Some(schedule),
+        })
+    }
+
+    /// Move the PWM output linearly from ``start`` to ``end`` over
+    /// ``seconds`` on a background thread (BITalino 2.0+ only).
+    ///
+    /// The value changes at most every 0.1 s; see ``pwm_pattern()``.
+    ///
+    /// Example:
+    ///     >>> device.pwm_ramp(0, 255, 2.0).wait()  # fade an LED in
+    ///
+    /// Args:
+    ///     start: First value (0-255).
+    ///     end: Final value (0-255), left set when the ramp ends.
+    ///     seconds: Ramp duration.
+    ///
+    /// Returns:
+    ///     PwmSchedule to wait for or cancel.
+    ///
+    /// Raises:
+    ///     ValueError: If seconds is negative or not finite.
+    ///     BitalinoError: Same as ``pwm_pattern()``.
+    fn pwm_ramp(&mut self, start: u8, end: u8, seconds: f64) -> PyResult<PyPwmSchedule> {
+        let duration = self::seconds("seconds", seconds)?;
+        let schedule = self
+            .device()?
+            .pwm_ramp(start, end, duration)
+            .map_err(to_py_err)?;
+        Ok(PyPwmSchedule {
+            schedule: Some(schedule),
+        })
+    }
+
+    /// Keep the device streaming when this object is garbage collected.
+    ///
+    /// By default the driver sends the stop command on cleanup if acquisition is
+    /// still running. After ``leak()`` the connection is released without
+    /// stopping the device.
+    fn leak(&mut self) {
+        if let Some(device) = self.slot().as_mut() {
+            device.set_stop_on_drop(false);
+        }
+    }
+
+    /// Stop acquisition (if running) and close the connection.
+    ///
+    /// Idempotent: calling ``close()`` on an already-closed device is a no-op.
+    /// Any other method called afterwards raises ``RuntimeError``.
+    ///
+    /// Raises:
+    ///     BitalinoError: If the stop command fails (the connection is still released)
+    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.release(py, false)
+    }
+
+    /// Whether ``close()`` has released the connection.
+    #[getter]
+    fn closed(&self) -> bool {
+        self.peek().is_none() && self.reader.is_none()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Close the device. If the block raised, a failure to stop is logged so
+    /// the original exception propagates; on a clean exit it is raised.
+    #[pyo3(signature = (exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        self.release(py, exc_type.is_some_and(|t| !t.is_none()))?;
+        Ok(false)
+    }
+
+    fn __repr__(&self) -> String {
+        if self.reader.is_some() {
+            return format!("Bitalino(rate={}Hz, streaming)", self.sampling_rate);
+        }
+        if self.peek().is_none() {
+            return "Bitalino(closed)".to_string();
+        }
+        format!("Bitalino(rate={}Hz)", self.sampling_rate)
+    }
+}
+
+/// The Python module definition
+#[pymodule]
+fn _bitalino_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // Initialize Python logging bridge (no-op if already done)
+    logging::init_python_logging(m.py())?;
+
+    // Add classes
+    m.add_class::<PyBitalino>()?;
+    m.add_class::<aio::PyAsyncBitalino>()?;
+    m.add_class::<PyFrame>()?;
+    m.add_class::<PyFrameBatch>()?;
+    m.add_class::<PyFrameIterator>()?;
+    m.add_class::<PyPwmSchedule>()?;
+    m.add_class::<PyDeviceState>()?;
+    m.add_class::<PyDeviceInfo>()?;
+    m.add_class::<PyAcquisitionStats>()?;
+    m.add_class::<PyChannelStats>()?;
+    m.add_class::<PyFilter>()?;
+    m.add_class::<PyAnnotation>()?;
+    m.add_class::<PyOutputEvent>()?;
+    m.add_class::<PySerialPort>()?;
+    m.add_class::<PySession>()?;
+
+    exceptions::register(m)?;
+
+    // Add module-level constants
+    m.add("DEFAULT_SAMPLING_RATE", 1000u16)?;
+    m.add("VALID_SAMPLING_RATES", vec![1u16, 10, 100, 1000])?;
+
+    // Logging helpers for Python
+    m.add_function(wrap_pyfunction!(enable_rust_logs, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_log_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(init_logging, m)?)?;
+    m.add_function(wrap_pyfunction!(set_log_level, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_logging_cache, m)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::Transport;
+
+    /// Accepts commands until `broken` is set, then fails every write.
+    struct Breaking(Arc<AtomicBool>);
+
+    impl Read for Breaking {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Ok(0)
+        }
+    }
+
+    impl Write for Breaking {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0.load(Ordering::Relaxed) {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            } else {
+                Ok(buf.len())
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for Breaking {}
+
+    /// An acquiring device whose link has just gone down.
+    fn broken_device() -> Bitalino {
+        let broken = Arc::new(AtomicBool::new(false));
+        let mut device = Bitalino::from_transport(Box::new(Breaking(broken.clone())));
+        device.start(100, vec![0]).unwrap();
+        broken.store(true, Ordering::Relaxed);
+        device
+    }
+
+    #[test]
+    fn failed_stop_does_not_replace_the_with_block_exception() {
+        // The body raised: the stop failure is only logged.
+        stop_before_release(&mut broken_device(), true).unwrap();
+
+        // Clean exit: the stop failure is the error to raise.
+        let err = stop_before_release(&mut broken_device(), false).unwrap_err();
+        assert!(err.to_string().contains("pipe"), "{err:#}");
+    }
+}
@@ -0,0 +1,299 @@
+//! asyncio front end for the Python bindings.
+//!
+//! `AsyncBitalino` moves a connected driver onto a dedicated reader thread and
+//! turns each call into an `asyncio.Future`: the thread runs the blocking
+//! driver call, then resolves the future on the event loop with
+//! `loop.call_soon_threadsafe`. The event loop never blocks and no async
+//! runtime (tokio, pyo3-asyncio) is involved.
+
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use pyo3::prelude::*;
+use pyo3::types::PyCFunction;
+use pyo3::IntoPyObjectExt;
+
+use super::exceptions::to_py_err;
+use super::{system_time, PyBitalino, PyDeviceState, PyFrameBatch};
+use crate::bitalino::{Bitalino, ReadOptions};
+use crate::presets::Preset;
+
+/// Work item run on the reader thread.
+type Job = Box<dyn FnOnce(&mut Bitalino) + Send>;
+
+/// asyncio wrapper around a connected ``Bitalino``.
+///
+/// Every device call runs on a background Rust thread and returns an awaitable,
+/// so acquisition never blocks the event loop. Calls are executed in the order
+/// they are made. Cancelling an awaitable does not interrupt the device call
+/// already queued; frames it reads are discarded.
+///
+/// Example:
+///     >>> device = await asyncio.to_thread(Bitalino.connect, "7E:91:2B:C4:AF:08")
+///     >>> async with AsyncBitalino(device) as dev:
+///     ...     await dev.start(rate=1000, channels=[0, 1])
+///     ...     batch = await dev.read(100)
+#[pyclass(name = "AsyncBitalino")]
+pub(crate) struct PyAsyncBitalino {
+    /// `None` once `close()` has been requested.
+    jobs: Option<Sender<Job>>,
+}
+
+impl PyAsyncBitalino {
+    /// Queue `call` on the reader thread and return a future for its result.
+    fn submit<'py, T, F>(&self, py: Python<'py>, call: F) -> PyResult<Bound<'py, PyAny>>
+    where
+        F: FnOnce(&mut Bitalino) -> anyhow::Result<T> + Send + 'static,
+        T: for<'a> IntoPyObject<'a> + Send + 'static,
+    {
+        let jobs = self
+            .jobs
+            .as_ref()
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Device is closed"))?;
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+
+        let (event_loop_ref, future_ref) = (event_loop.unbind(), future.clone().unbind());
+        let job: Job = Box::new(move |device| {
+            let result = call(device);
+            Python::attach(|py| {
+                let outcome = result
+                    .map_err(to_py_err)
+                    .and_then(|value| value.into_py_any(py));
+                resolve(py, &event_loop_ref, future_ref, outcome);
+            });
+        });
+        jobs.send(job).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Reader thread has stopped")
+        })?;
+        Ok(future)
+    }
+}
+
+/// A future on the running loop that is already resolved to `value`.
+fn ready(value: Bound<'_, PyAny>) -> PyResult<Bound<'_, PyAny>> {
+    let future = value
+        .py()
+        .import("asyncio")?
+        .call_method0("get_running_loop")?
+        .call_method0("create_future")?;
+    future.call_method1("set_result", (value,))?;
+    Ok(future)
+}
+
+/// Complete `future` with `outcome` from the event loop's own thread.
+///
+/// The future may have been cancelled in the meantime, so the callback checks
+/// `done()` first. A closed loop means nobody awaits the result any more.
+fn resolve(
+    py: Python<'_>,
+    event_loop: &Py<PyAny>,
+    future: Py<PyAny>,
+    outcome: PyResult<Py<PyAny>>,
+) {
+    let callback = PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+        let py = args.py();
+        let future = future.bind(py);
+        if future.call_method0("done")?.is_truthy()? {
+            return Ok(());
+        }
+        match &outcome {
+            Ok(value) => future.call_method1("set_result", (value.clone_ref(py),))?,
+            Err(err) => future.call_method1("set_exception", (err.value(py),))?,
+        };
+        Ok(())
+    });
+    let _ = callback.and_then(|callback| {
+        event_loop
+            .bind(py)
+            .call_method1("call_soon_threadsafe", (callback,))
+    });
+}
+
+#[pymethods]
+impl PyAsyncBitalino {
+    /// Take over a connected ``Bitalino``.
+    ///
+    /// The synchronous object is closed by this call; use the returned
+    /// ``AsyncBitalino`` from then on.
+    ///
+    /// Args:
+    ///     device: A connected (not closed) Bitalino instance.
+    ///
+    /// Raises:
+    ///     RuntimeError: If ``device`` is already closed.
+    #[new]
+    fn new(device: &Bound<'_, PyBitalino>) -> PyResult<Self> {
+        let mut device = device.borrow_mut().take_device()?;
+        let (jobs, queue) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("bitalino-async".into())
+            .spawn(move || {
+                for job in queue {
+                    job(&mut device);
+                }
+                // Dropping the driver stops a running acquisition.
+            })
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        Ok(Self { jobs: Some(jobs) })
+    }
+
+    /// Await the firmware version string.
+    ///
+    /// Returns:
+    ///     Awaitable[str]: Resolves to the version, e.g. "BITalino_v5.2".
+    fn version<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.submit(py, |device| device.version())
+    }
+
+    /// Await the start of acquisition; same arguments as ``Bitalino.start()``.
+    ///
+    /// Args:
+    ///     rate: Sampling rate in Hz (1, 10, 100 or 1000).
+    ///     channels: Analog channels to acquire (0-5).
+    ///     preset (Literal["ecg", "emg", "eda", "eeg", "eda_ecg", "psychophysiology"] | None):
+    ///         Name of a sensor kit preset.
+    ///
+    /// Returns:
+    ///     Awaitable[None]: Resolves once the device is acquiring.
+    #[pyo3(signature = (rate=None, channels=None, preset=None))]
+    fn start<'py>(
+        &self,
+        py: Python<'py>,
+        rate: Option<u16>,
+        channels: Option<Vec<u8>>,
+        preset: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let preset = preset
+            .map(Preset::from_name)
+            .transpose()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let rate = rate.unwrap_or(preset.map_or(1000, |p| p.rate as u16));
+        let channels = channels
+            .unwrap_or_else(|| preset.map_or_else(|| vec![0, 1, 2, 3, 4, 5], |p| p.channels()));
+        self.submit(py, move |device| device.start(rate, channels).map(|_| ()))
+    }
+
+    /// Await acquisition started at a scheduled wall-clock time; see
+    /// ``Bitalino.start_at()``. The event loop keeps running while waiting.
+    ///
+    /// Args:
+    ///     at (datetime): When to start; a naive ``datetime`` is local time.
+    ///     rate: Sampling rate in Hz (1, 10, 100 or 1000).
+    ///     channels: Analog channels to acquire (0-5). Default: all.
+    ///
+    /// Returns:
+    ///     Awaitable[None]: Resolves once the device is acquiring, just after
+    ///     ``at``.
+    #[pyo3(signature = (at, rate=1000, channels=None))]
+    fn start_at<'py>(
+        &self,
+        py: Python<'py>,
+        at: &Bound<'py, PyAny>,
+        rate: u16,
+        channels: Option<Vec<u8>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let at = system_time(at)?;
+        let channels = channels.unwrap_or_else(|| vec![0, 1, 2, 3, 4, 5]);
+        self.submit(py, move |device| {
+            device.start_at(at, rate, channels).map(|_| ())
+        })
+    }
+
+    /// Await the end of acquisition.
+    ///
+    /// Returns:
+    ///     Awaitable[None]: Resolves once the device is idle.
+    fn stop<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.submit(py, |device| device.stop())
+    }
+
+    /// Await a batch of frames; same arguments as ``Bitalino.read_timed()``.
+    ///
+    /// Returns:
+    ///     Awaitable[FrameBatch]: Resolves to the batch.
+    #[pyo3(signature = (n_frames=100, timeout_ms=None, allow_partial=true))]
+    fn read<'py>(
+        &self,
+        py: Python<'py>,
+        n_frames: usize,
+        timeout_ms: Option<u64>,
+        allow_partial: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let options = ReadOptions {
+            timeout: timeout_ms.map(std::time::Duration::from_millis),
+            allow_partial,
+            ..ReadOptions::default()
+        };
+        self.submit(py, move |device| {
+            device
+                .read_frames_timed_with(n_frames, &options)
+                .map(PyFrameBatch::from)
+        })
+    }
+
+    /// Await the device state (BITalino 2.0+ only, idle mode).
+    ///
+    /// Returns:
+    ///     Awaitable[DeviceState]: Resolves to the state.
+    fn state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.submit(py, |device| device.state().map(PyDeviceState::from))
+    }
+
+    /// Stop acquisition (if running) and release the connection.
+    ///
+    /// Calls made before ``close()`` still complete. Awaiting ``close()`` on an
+    /// already-closed device returns immediately.
+    ///
+    /// Returns:
+    ///     Awaitable[None]: Resolves once the connection is released.
+    fn close<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        if self.jobs.is_none() {
+            return ready(py.None().into_bound(py));
+        }
+        let future = self.submit(py, |device| {
+            if device.is_acquiring() {
+                device.stop()?;
+            }
+            Ok(())
+        })?;
+        // The reader thread exits once the queue drains.
+        self.jobs = None;
+        Ok(future)
+    }
+
+    /// Whether ``close()`` has been called.
+    #[getter]
+    fn closed(&self) -> bool {
+        self.jobs.is_none()
+    }
+
+    /// Returns:
+    ///     Awaitable[AsyncBitalino]: Resolves to this object.
+    fn __aenter__(slf: Bound<'_, Self>) -> PyResult<Bound<'_, PyAny>> {
+        ready(slf.into_any())
+    }
+
+    /// Close the device; exceptions are not suppressed.
+    ///
+    /// Returns:
+    ///     Awaitable[None]: Resolves like ``close()``.
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __aexit__<'py>(
+        &mut self,
+        py: Python<'py>,
+        _exc_type: Option<&Bound<'py, PyAny>>,
+        _exc_value: Option<&Bound<'py, PyAny>>,
+        _traceback: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.close(py)
+    }
+
+    fn __repr__(&self) -> String {
+        if self.jobs.is_none() {
+            "AsyncBitalino(closed)".to_string()
+        } else {
+            "AsyncBitalino()".to_string()
+        }
+    }
+}
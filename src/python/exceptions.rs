@@ -0,0 +1,132 @@
+//! Python exception hierarchy for driver errors.
+//!
+//! Every error raised by the driver derives from `BitalinoError`, itself an
+//! `OSError`, so existing `except IOError` blocks keep working while callers
+//! can target specific failures:
+//!
+//! ```text
+//! OSError
+//! └── BitalinoError
+//!     ├── BluetoothError
+//!     │   └── PairingError
+//!     ├── CrcError
+//!     ├── TimeoutError
+//!     └── NotInAcquisitionError
+//! ```
+//!
+//! Raised exceptions also carry `kind` (e.g. `"timeout"`) and `code` (e.g.
+//! `"io.timed_out"`) attributes mirroring `DriverError::kind()` and
+//! `DriverError::code()`; errors without a typed cause use `"other"` for both.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyOSError;
+use pyo3::prelude::*;
+
+use crate::errors::{is_io_timeout, BluetoothError as RustBluetoothError, DriverError};
+
+create_exception!(
+    bitalino_rs,
+    BitalinoError,
+    PyOSError,
+    "Base class for all errors raised by the BITalino driver.
+
+Attributes:
+    kind (str): Error category, e.g. \"timeout\" or \"bluetooth\".
+    code (str): Stable error code, e.g. \"io.timed_out\"."
+);
+create_exception!(
+    bitalino_rs,
+    BluetoothError,
+    BitalinoError,
+    "Bluetooth discovery or connection failed."
+);
+create_exception!(
+    bitalino_rs,
+    PairingError,
+    BluetoothError,
+    "Pairing with the device failed."
+);
+create_exception!(
+    bitalino_rs,
+    CrcError,
+    BitalinoError,
+    "Frames arrived but failed CRC validation."
+);
+create_exception!(
+    bitalino_rs,
+    TimeoutError,
+    BitalinoError,
+    "The device did not answer within the allotted time."
+);
+create_exception!(
+    bitalino_rs,
+    NotInAcquisitionError,
+    BitalinoError,
+    "The operation requires a running acquisition; call start() first."
+);
+
+/// Add the exception classes to the extension module.
+pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("BitalinoError", py.get_type::<BitalinoError>())?;
+    m.add("BluetoothError", py.get_type::<BluetoothError>())?;
+    m.add("PairingError", py.get_type::<PairingError>())?;
+    m.add("CrcError", py.get_type::<CrcError>())?;
+    m.add("TimeoutError", py.get_type::<TimeoutError>())?;
+    m.add(
+        "NotInAcquisitionError",
+        py.get_type::<NotInAcquisitionError>(),
+    )?;
+    Ok(())
+}
+
+/// Attach the machine-readable `kind` and `code` attributes to `err`.
+fn with_category(err: PyErr, kind: &str, code: &str) -> PyErr {
+    Python::attach(|py| {
+        let value = err.value(py);
+        // Setting attributes on a fresh exception instance cannot fail in
+        // practice; losing them is preferable to masking the original error.
+        let _ = value.setattr("kind", kind);
+        let _ = value.setattr("code", code);
+    });
+    err
+}
+
+/// Pick the exception class for a driver error, raising it with `msg`.
+fn driver_exception(err: &DriverError, msg: String) -> PyErr {
+    let py_err = match err {
+        DriverError::Bluetooth(RustBluetoothError::Pairing(_)) => PairingError::new_err(msg),
+        DriverError::Bluetooth(_) => BluetoothError::new_err(msg),
+        DriverError::Timeout(_) => TimeoutError::new_err(msg),
+        DriverError::Io(e) if is_io_timeout(e) => TimeoutError::new_err(msg),
+        DriverError::Crc(_) => CrcError::new_err(msg),
+        DriverError::NotAcquiring(_) => NotInAcquisitionError::new_err(msg),
+        _ => BitalinoError::new_err(msg),
+    };
+    with_category(py_err, err.kind().as_str(), err.code())
+}
+
+impl From<DriverError> for PyErr {
+    fn from(err: DriverError) -> Self {
+        driver_exception(&err, err.to_string())
+    }
+}
+
+/// Convert an error from the `Bitalino` API into the matching Python exception.
+///
+/// The first typed cause in the chain (`DriverError` or `io::Error`) decides the
+/// class; the message is the full error text.
+pub(crate) fn to_py_err(err: anyhow::Error) -> PyErr {
+    let msg = err.to_string();
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<DriverError>() {
+            return driver_exception(e, msg);
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            // Classify bare I/O errors exactly like `DriverError::Io`.
+            let e = DriverError::Io(std::io::Error::new(e.kind(), e.to_string()));
+            return driver_exception(&e, msg);
+        }
+    }
+    with_category(BitalinoError::new_err(msg), "other", "other")
+}
@@ -0,0 +1,1151 @@
+//! File writers for recorded acquisitions.
+//!
+//! [`FrameWriter`] appends decoded frames to a plain CSV file or to the
+//! OpenSignals text format (tab-separated values behind a JSON header), which
+//! the PLUX OpenSignals software and its loaders read directly. Both headers
+//! record the UTC acquisition start, so rows can be aligned with other clocks.
+//! [`JsonlSink`] emits one self-describing JSON object per frame instead, for
+//! piping into `jq`, Logstash or scripts. [`WfdbWriter`] writes a PhysioNet
+//! WFDB record that `wfdb-python` and the PhysioToolkit read directly. All
+//! implement [`FrameSink`], as do the compact binary and EDF writers in their
+//! own modules.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+use crate::bitalino::{ConnectionKind, Frame};
+use crate::compact::{CompactWriter, Compression};
+use crate::edf::EdfWriter;
+use crate::sensors::{resolution_bits, Sensor};
+use crate::session::Session;
+
+/// Output file layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Comma-separated values with a single header row, preceded by `# `
+    /// comment lines with the start time and the device identity.
+    Csv,
+    /// OpenSignals text format (`.txt`).
+    OpenSignals,
+    /// JSON Lines, one object per frame (`.jsonl`, `.ndjson`).
+    Jsonl,
+    /// WFDB record: a `.hea` header next to a `.dat` signal file.
+    Wfdb,
+    /// Compact binary format (`.brec`), see [`CompactWriter`](crate::CompactWriter).
+    Compact,
+    /// European Data Format (`.edf`), see [`EdfWriter`](crate::EdfWriter).
+    Edf,
+}
+
+impl RecordFormat {
+    /// Guess the format from a file extension: `.txt` is OpenSignals, `.jsonl`
+    /// and `.ndjson` JSON Lines, `.hea` and `.dat` WFDB, `.brec` compact,
+    /// `.edf` EDF, anything else CSV.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("txt") => Self::OpenSignals,
+            Some(ext) if ext.eq_ignore_ascii_case("hea") || ext.eq_ignore_ascii_case("dat") => {
+                Self::Wfdb
+            }
+            Some(ext)
+                if ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson") =>
+            {
+                Self::Jsonl
+            }
+            Some(ext) if ext.eq_ignore_ascii_case("brec") => Self::Compact,
+            Some(ext) if ext.eq_ignore_ascii_case("edf") => Self::Edf,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// Destination for decoded frames.
+pub trait FrameSink {
+    /// Append `frames`, in acquisition order.
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()>;
+
+    /// Frames written so far.
+    #[allow(dead_code)]
+    fn frames_written(&self) -> u64;
+
+    /// Push buffered output to its destination.
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Flush, then have the operating system commit file output to storage,
+    /// so it survives a power loss. Sinks without files just flush.
+    fn sync(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    /// End the recording: append the end-of-file footer of the format, if it
+    /// has one, and sync. Nothing may be written afterwards.
+    /// [`recover`](crate::recover) tells a closed file from one cut short
+    /// by the footer.
+    fn close(&mut self) -> io::Result<()> {
+        self.sync()
+    }
+
+    /// Append the annotations, output events and statistics of an ended
+    /// session after the last frame; EDF writes only the annotations, into
+    /// its annotation signal. Formats without room for them (OpenSignals,
+    /// WFDB, compact) ignore it.
+    fn write_session(&mut self, _session: &Session) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: FrameSink + ?Sized> FrameSink for Box<S> {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        (**self).write_frames(frames)
+    }
+
+    fn frames_written(&self) -> u64 {
+        (**self).frames_written()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        (**self).sync()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        (**self).close()
+    }
+
+    fn write_session(&mut self, session: &Session) -> io::Result<()> {
+        (**self).write_session(session)
+    }
+}
+
+/// Create (or truncate) `path` and open the sink for `format`.
+pub fn create_sink(
+    path: impl AsRef<Path>,
+    format: RecordFormat,
+    info: &RecordingInfo,
+) -> io::Result<Box<dyn FrameSink + Send>> {
+    Ok(match format {
+        RecordFormat::Jsonl => Box::new(JsonlSink::create(path, info)?),
+        RecordFormat::Wfdb => Box::new(WfdbWriter::create(path, info)?),
+        RecordFormat::Compact => {
+            Box::new(CompactWriter::create(path, info, Compression::default())?)
+        }
+        RecordFormat::Edf => Box::new(EdfWriter::create(path, info)?),
+        _ => Box::new(FrameWriter::create(path, format, info)?),
+    })
+}
+
+/// Acquisition settings written into file headers.
+#[derive(Debug, Clone)]
+pub struct RecordingInfo {
+    /// Device MAC address (or another identifier for non-Bluetooth links).
+    pub mac: String,
+    /// Sampling rate in Hz.
+    pub sampling_rate: u16,
+    /// Acquired analog channels (0-5), in frame order.
+    pub channels: Vec<u8>,
+    /// Sensor attached to each acquired channel (OpenSignals `sensor` field);
+    /// missing entries are written as `RAW`.
+    pub sensors: Vec<String>,
+    /// Port of a Core or Plugged assembly each acquired channel is wired to;
+    /// missing or `None` entries are not wired through a named port.
+    pub ports: Vec<Option<ChannelPort>>,
+    /// Whether the device is a BITalino 2.0 (`bitalino_rev` in OpenSignals).
+    pub is_bitalino2: bool,
+    /// Firmware version string reported by the device, if queried.
+    pub firmware_version: Option<String>,
+    /// Link the device was acquired over.
+    pub connection: ConnectionKind,
+    /// Acquisition start time.
+    pub started: SystemTime,
+}
+
+/// Named port an acquired channel is wired to, from a profile's `[ports]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelPort {
+    /// Physical label of the port.
+    pub label: String,
+    /// Free-form notes such as electrode placement.
+    pub description: Option<String>,
+}
+
+impl RecordingInfo {
+    /// Port the channel at `position` (in frame order) is wired to, if named.
+    fn port(&self, position: usize) -> Option<&ChannelPort> {
+        self.ports.get(position)?.as_ref()
+    }
+}
+
+/// Streams frames into a recording file.
+pub struct FrameWriter<W: Write> {
+    out: W,
+    /// The file behind `out`, for [`FrameSink::sync`].
+    file: Option<File>,
+    format: RecordFormat,
+    frames_written: u64,
+}
+
+impl FrameWriter<BufWriter<File>> {
+    /// Create (or truncate) `path` and write the header.
+    pub fn create(
+        path: impl AsRef<Path>,
+        format: RecordFormat,
+        info: &RecordingInfo,
+    ) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let mut writer = Self::new(BufWriter::new(file.try_clone()?), format, info)?;
+        writer.file = Some(file);
+        Ok(writer)
+    }
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// Wrap `out` and write the header for `format`.
+    pub fn new(mut out: W, format: RecordFormat, info: &RecordingInfo) -> io::Result<Self> {
+        match format {
+            RecordFormat::Csv => {
+                writeln!(
+                    out,
+                    "# acquisition_start_utc: {}",
+                    utc_iso8601(info.started)
+                )?;
+                for line in device_comments(info) {
+                    writeln!(out, "# {line}")?;
+                }
+                writeln!(out, "{}", columns(&info.channels).join(","))?;
+            }
+            RecordFormat::OpenSignals => write_opensignals_header(&mut out, info)?,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "FrameWriter only writes CSV and OpenSignals; see create_sink",
+                ))
+            }
+        }
+        Ok(Self {
+            out,
+            file: None,
+            format,
+            frames_written: 0,
+        })
+    }
+
+    /// Append one row per frame.
+    pub fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        let sep = match self.format {
+            RecordFormat::OpenSignals => "\t",
+            _ => ",",
+        };
+        for frame in frames {
+            let row: Vec<String> = std::iter::once(frame.seq as u16)
+                .chain(frame.digital.iter().map(|&d| d as u16))
+                .chain(frame.analog_values().iter().copied())
+                .map(|v| v.to_string())
+                .collect();
+            writeln!(self.out, "{}", row.join(sep))?;
+        }
+        self.frames_written += frames.len() as u64;
+        Ok(())
+    }
+
+    /// Rows written so far.
+    #[allow(dead_code)]
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Flush buffered rows to the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Flush and return the underlying writer.
+    #[allow(dead_code)]
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> FrameSink for FrameWriter<W> {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        FrameWriter::write_frames(self, frames)
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        FrameWriter::flush(self)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        sync_file(self.file.as_ref())
+    }
+
+    /// CSV ends with a `# end: {"frames": ..., "ended": ...}` comment line;
+    /// OpenSignals files have no footer.
+    fn close(&mut self) -> io::Result<()> {
+        if self.format == RecordFormat::Csv {
+            writeln!(self.out, "# end: {}", footer(self.frames_written))?;
+        }
+        self.sync()
+    }
+
+    /// CSV gets one `# annotation: {"sample": ..., "label": ...}` comment
+    /// line per marker, keyed by its sample index, then a final
+    /// `# session: {...}` line.
+    fn write_session(&mut self, session: &Session) -> io::Result<()> {
+        if self.format == RecordFormat::Csv {
+            for annotation in &session.annotations {
+                let line = json!({
+                    "sample": annotation.sample_index,
+                    "label": annotation.label,
+                });
+                writeln!(self.out, "# annotation: {line}")?;
+            }
+            writeln!(self.out, "# session: {}", session.to_json())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes each frame as one JSON object per line.
+///
+/// Every line carries the reconstructed sample time, the digital lines and,
+/// per analog channel, the raw value plus its conversion through the sensor's
+/// transfer function:
+///
+/// ```json
+/// {"t":0.002,"time":1709211909.252,"sample":2,"seq":2,"I1":0,"I2":0,"O1":0,"O2":0,
+///  "A1":{"raw":512,"value":0.0,"unit":"mV"}}
+/// ```
+///
+/// `t` is seconds since acquisition start at the nominal sampling rate; frames
+/// lost to sequence gaps advance it, so it stays aligned with the device clock.
+/// `sample` counts from the [`Frame::sample_index`] of the first frame, and
+/// `time` is Unix seconds, taking the recording start as the time of that
+/// first frame. The first line is a `{"header": {...}}` object identifying the
+/// device (MAC, generation, firmware, link and port wiring), the sampling rate
+/// and the start time. A recording ended with a
+/// session has one `{"session": {...}}` line after the frames, and a closed
+/// recording ends with an `{"end": {...}}` line.
+pub struct JsonlSink<W: Write> {
+    out: W,
+    /// The file behind `out`, for [`FrameSink::sync`].
+    file: Option<File>,
+    /// First line, until it is written.
+    header: Option<Value>,
+    labels: Vec<String>,
+    sensors: Vec<Sensor>,
+    ports: Vec<Option<String>>,
+    period_secs: f64,
+    started_secs: f64,
+    /// Sample index of the first frame, the one written at `started_secs`.
+    first_sample: u64,
+    next_sample: u64,
+    last_seq: Option<u8>,
+    frames_written: u64,
+}
+
+impl JsonlSink<BufWriter<File>> {
+    /// Create (or truncate) `path`.
+    pub fn create(path: impl AsRef<Path>, info: &RecordingInfo) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let mut sink = Self::new(BufWriter::new(file.try_clone()?), info);
+        sink.file = Some(file);
+        Ok(sink)
+    }
+}
+
+impl<W: Write> JsonlSink<W> {
+    /// Write JSON Lines for the acquisition described by `info` into `out`.
+    pub fn new(out: W, info: &RecordingInfo) -> Self {
+        let mut header = device_json(info);
+        header["sampling_rate"] = json!(info.sampling_rate);
+        header["started"] = json!(utc_iso8601(info.started));
+        Self {
+            out,
+            file: None,
+            header: Some(json!({ "header": header })),
+            labels: info
+                .channels
+                .iter()
+                .map(|c| format!("A{}", c + 1))
+                .collect(),
+            sensors: (0..info.channels.len())
+                .map(|i| {
+                    info.sensors
+                        .get(i)
+                        .map_or(Sensor::Raw, |s| Sensor::from_name(s))
+                })
+                .collect(),
+            ports: (0..info.channels.len())
+                .map(|i| info.port(i).map(|port| port.label.clone()))
+                .collect(),
+            period_secs: 1.0 / f64::from(info.sampling_rate.max(1)),
+            started_secs: info
+                .started
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+            first_sample: 0,
+            next_sample: 0,
+            last_seq: None,
+            frames_written: 0,
+        }
+    }
+
+    /// Append one line per frame.
+    pub fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        self.write_header()?;
+        for frame in frames {
+            match self.last_seq {
+                Some(last) => {
+                    // Same 4-bit sequence arithmetic as the driver's gap detection.
+                    let skipped = (frame.seq as u64 + 15 - last as u64) % 16;
+                    self.next_sample += skipped;
+                }
+                None => {
+                    self.first_sample = frame.sample_index;
+                    self.next_sample = frame.sample_index;
+                }
+            }
+            self.last_seq = Some(frame.seq);
+            let sample = self.next_sample;
+            self.next_sample += 1;
+
+            let t = sample as f64 * self.period_secs;
+            let since_first = (sample - self.first_sample) as f64 * self.period_secs;
+            let mut line = Map::new();
+            line.insert("t".into(), json!(t));
+            line.insert("time".into(), json!(self.started_secs + since_first));
+            line.insert("sample".into(), json!(sample));
+            line.insert("seq".into(), json!(frame.seq));
+            for (name, level) in ["I1", "I2", "O1", "O2"].into_iter().zip(frame.digital) {
+                line.insert(name.into(), json!(level));
+            }
+            for (i, (&raw, (label, sensor))) in frame
+                .analog_values()
+                .iter()
+                .zip(self.labels.iter().zip(&self.sensors))
+                .enumerate()
+            {
+                let mut value = json!({
+                    "raw": raw,
+                    "value": sensor.to_physical(raw, resolution_bits(i)),
+                    "unit": sensor.unit(),
+                });
+                if let Some(port) = &self.ports[i] {
+                    value["port"] = json!(port);
+                }
+                line.insert(label.clone(), value);
+            }
+            serde_json::to_writer(&mut self.out, &Value::Object(line))?;
+            self.out.write_all(b"\n")?;
+        }
+        self.frames_written += frames.len() as u64;
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer.
+    #[allow(dead_code)]
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_header()?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Write the header line if nothing has been written yet.
+    fn write_header(&mut self) -> io::Result<()> {
+        match self.header.take() {
+            Some(header) => {
+                serde_json::to_writer(&mut self.out, &header)?;
+                self.out.write_all(b"\n")
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> FrameSink for JsonlSink<W> {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        JsonlSink::write_frames(self, frames)
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        sync_file(self.file.as_ref())
+    }
+
+    /// The last line is `{"end": {"frames": ..., "ended": ...}}`.
+    fn close(&mut self) -> io::Result<()> {
+        self.write_header()?;
+        serde_json::to_writer(
+            &mut self.out,
+            &json!({ "end": footer(self.frames_written) }),
+        )?;
+        self.out.write_all(b"\n")?;
+        self.sync()
+    }
+
+    /// A final `{"session": {...}}` line follows the frames.
+    fn write_session(&mut self, session: &Session) -> io::Result<()> {
+        self.write_header()?;
+        serde_json::to_writer(&mut self.out, &json!({ "session": session.to_json() }))?;
+        self.out.write_all(b"\n")
+    }
+}
+
+/// WFDB format 16 value marking a sample lost to a sequence gap.
+const WFDB_INVALID: i16 = i16::MIN;
+
+/// Writes a WFDB record in signal format 16 (little-endian 16-bit samples,
+/// interleaved by channel).
+///
+/// Samples are stored as raw ADC counts; each signal's gain and baseline come
+/// from the sensor transfer function, so PhysioNet tools report ECG in mV, EDA
+/// in uS and so on. Frames lost to sequence gaps are written as the WFDB
+/// invalid-sample value, keeping the record uniformly sampled. The header is
+/// rewritten on every flush with the sample count and checksums so far, and
+/// carries the acquisition start as the record's base time and date.
+pub struct WfdbWriter<W: Write> {
+    signals: W,
+    /// The signal file behind `signals`, for [`FrameSink::sync`].
+    file: Option<File>,
+    /// Where [`flush`](Self::flush) writes the header, if anywhere.
+    header_path: Option<PathBuf>,
+    record: String,
+    sampling_rate: u16,
+    started: SystemTime,
+    descriptions: Vec<String>,
+    /// Device identity, written as `#` lines after the signal specifications.
+    comments: Vec<String>,
+    sensors: Vec<Sensor>,
+    first_values: Vec<i16>,
+    checksums: Vec<i16>,
+    samples: u64,
+    last_seq: Option<u8>,
+    frames_written: u64,
+}
+
+impl WfdbWriter<BufWriter<File>> {
+    /// Create (or truncate) the record at `path`: the `.dat` and `.hea` files
+    /// share its stem, whatever its extension.
+    pub fn create(path: impl AsRef<Path>, info: &RecordingInfo) -> io::Result<Self> {
+        let path = path.as_ref();
+        let record = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|s| !s.is_empty() && !s.contains(char::is_whitespace))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "WFDB record names must be non-empty and without spaces",
+                )
+            })?;
+        let file = File::create(path.with_extension("dat"))?;
+        let mut writer = Self::new(BufWriter::new(file.try_clone()?), record, info);
+        writer.file = Some(file);
+        writer.header_path = Some(path.with_extension("hea"));
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> WfdbWriter<W> {
+    /// Write the signal file of record `record` into `out`; the header is only
+    /// available from [`header`](Self::header).
+    pub fn new(out: W, record: &str, info: &RecordingInfo) -> Self {
+        let n = info.channels.len();
+        Self {
+            signals: out,
+            file: None,
+            header_path: None,
+            record: record.to_string(),
+            sampling_rate: info.sampling_rate,
+            started: info.started,
+            descriptions: info
+                .channels
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    let sensor = info.sensors.get(i).map_or("RAW", String::as_str);
+                    match info.port(i) {
+                        Some(port) => format!("A{} {} port {}", c + 1, sensor, port.label),
+                        None => format!("A{} {}", c + 1, sensor),
+                    }
+                })
+                .collect(),
+            comments: device_comments(info),
+            sensors: (0..n)
+                .map(|i| {
+                    info.sensors
+                        .get(i)
+                        .map_or(Sensor::Raw, |s| Sensor::from_name(s))
+                })
+                .collect(),
+            first_values: vec![0; n],
+            checksums: vec![0; n],
+            samples: 0,
+            last_seq: None,
+            frames_written: 0,
+        }
+    }
+
+    /// Append one sample per channel for each frame, plus invalid samples for
+    /// frames missing from the sequence.
+    pub fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        let invalid = vec![WFDB_INVALID; self.sensors.len()];
+        for frame in frames {
+            if let Some(last) = self.last_seq {
+                // Same 4-bit sequence arithmetic as the driver's gap detection.
+                let skipped = (frame.seq as u64 + 15 - last as u64) % 16;
+                for _ in 0..skipped {
+                    self.write_sample(&invalid)?;
+                }
+            }
+            self.last_seq = Some(frame.seq);
+            if frame.filled {
+                self.write_sample(&invalid)?;
+                continue;
+            }
+            let values: Vec<i16> = (0..self.sensors.len())
+                .map(|i| {
+                    frame
+                        .analog_values()
+                        .get(i)
+                        .map_or(WFDB_INVALID, |&v| v as i16)
+                })
+                .collect();
+            self.write_sample(&values)?;
+        }
+        self.frames_written += frames.len() as u64;
+        Ok(())
+    }
+
+    fn write_sample(&mut self, values: &[i16]) -> io::Result<()> {
+        for (i, &value) in values.iter().enumerate() {
+            if self.samples == 0 {
+                self.first_values[i] = value;
+            }
+            self.checksums[i] = self.checksums[i].wrapping_add(value);
+            self.signals.write_all(&value.to_le_bytes())?;
+        }
+        self.samples += 1;
+        Ok(())
+    }
+
+    /// Header (`.hea`) describing the samples written so far.
+    pub fn header(&self) -> String {
+        let (year, month, day, secs_of_day, millis) = utc_fields(self.started);
+        let mut header = format!(
+            "{} {} {} {} {:02}:{:02}:{:02}.{:03} {:02}/{:02}/{:04}\n",
+            self.record,
+            self.sensors.len(),
+            self.sampling_rate,
+            self.samples,
+            secs_of_day / 3_600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+            millis,
+            day,
+            month,
+            year
+        );
+        for (i, sensor) in self.sensors.iter().enumerate() {
+            let bits = resolution_bits(i);
+            // Physical value = (sample - baseline) / gain for every sensor.
+            let zero = sensor.to_physical(0, bits);
+            let gain = 1.0 / (sensor.to_physical(1, bits) - zero);
+            let baseline = (-zero * gain).round() as i64;
+            header.push_str(&format!(
+                "{}.dat 16 {:.6}({})/{} {} 0 {} {} 0 {}\n",
+                self.record,
+                gain,
+                baseline,
+                sensor.unit(),
+                bits,
+                self.first_values[i],
+                self.checksums[i],
+                self.descriptions[i]
+            ));
+        }
+        for comment in &self.comments {
+            header.push_str(&format!("# {comment}\n"));
+        }
+        header
+    }
+
+    /// Flush the signal file and, for records created on disk, replace the
+    /// header.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.signals.flush()?;
+        if let Some(path) = &self.header_path {
+            replace_file(path, self.header().as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Flush and return the underlying signal writer.
+    #[allow(dead_code)]
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.signals)
+    }
+}
+
+impl<W: Write> FrameSink for WfdbWriter<W> {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        WfdbWriter::write_frames(self, frames)
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        WfdbWriter::flush(self)
+    }
+
+    /// The header, rewritten on every flush, is the record's index: it has
+    /// no footer.
+    fn sync(&mut self) -> io::Result<()> {
+        self.signals.flush()?;
+        sync_file(self.file.as_ref())?;
+        WfdbWriter::flush(self)
+    }
+}
+
+/// Content of the end-of-file footer of a closed recording.
+fn footer(frames: u64) -> Value {
+    json!({ "frames": frames, "ended": utc_iso8601(SystemTime::now()) })
+}
+
+/// Commit `file`, if any, to storage.
+fn sync_file(file: Option<&File>) -> io::Result<()> {
+    file.map_or(Ok(()), File::sync_data)
+}
+
+/// Replace `path` with `contents` through a temporary file, so a crash never
+/// leaves it truncated.
+pub(crate) fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Column names: sequence, the four digital lines, then `A1`.. for each channel.
+fn columns(channels: &[u8]) -> Vec<String> {
+    ["nSeq", "I1", "I2", "O1", "O2"]
+        .into_iter()
+        .map(String::from)
+        .chain(channels.iter().map(|c| format!("A{}", c + 1)))
+        .collect()
+}
+
+fn json_list<T: std::fmt::Display>(items: impl IntoIterator<Item = T>, quote: bool) -> String {
+    let items: Vec<String> = items
+        .into_iter()
+        .map(|i| {
+            if quote {
+                format!("\"{i}\"")
+            } else {
+                i.to_string()
+            }
+        })
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn write_opensignals_header(out: &mut impl Write, info: &RecordingInfo) -> io::Result<()> {
+    let (date, time) = utc_date_time(info.started);
+    let labels: Vec<String> = info
+        .channels
+        .iter()
+        .map(|c| format!("A{}", c + 1))
+        .collect();
+    // Sequence is 4 bits, digital lines 1 bit, then the analog values by
+    // position: the fifth and sixth are 6 bits, whichever inputs they carry.
+    let resolution = [4, 1, 1, 1, 1]
+        .into_iter()
+        .chain((0..info.channels.len()).map(resolution_bits));
+
+    writeln!(out, "# OpenSignals Text File Format")?;
+    writeln!(
+        out,
+        "# {{\"{mac}\": {{\"sensor\": {sensor}, \"device name\": \"{mac}\", \
+         \"column\": {column}, \"sync interval\": 2, \"time\": \"{time}\", \
+         \"comments\": {comments}, \"device connection\": \"BTH{mac}\", \"channels\": {channels}, \
+         \"date\": \"{date}\", \"mode\": 0, \"digital IO\": [0, 0, 1, 1], \
+         \"device\": \"{device}\", \"position\": 0, \"sampling rate\": {rate}, \
+         \"label\": {label}, \"resolution\": {resolution}, \"special\": {special}, \
+         \"firmware version\": \"{firmware}\"}}}}",
+        mac = info.mac,
+        comments = Value::from(port_comments(info).join("; ")),
+        sensor = json_list(
+            (0..info.channels.len()).map(|i| info.sensors.get(i).map_or("RAW", String::as_str)),
+            true
+        ),
+        column = json_list(columns(&info.channels), true),
+        channels = json_list(info.channels.iter().map(|c| c + 1), false),
+        device = if info.is_bitalino2 {
+            "bitalino_rev"
+        } else {
+            "bitalino"
+        },
+        rate = info.sampling_rate,
+        label = json_list(&labels, true),
+        resolution = json_list(resolution, false),
+        special = json_list(info.channels.iter().map(|_| "{}"), false),
+        firmware = info.firmware_version.as_deref().unwrap_or_default(),
+    )?;
+    writeln!(out, "# EndOfHeader")
+}
+
+/// `key: value` lines identifying the device and its port wiring, for
+/// formats with free-form header comments.
+fn device_comments(info: &RecordingInfo) -> Vec<String> {
+    let mut lines = vec![format!("device: {}", info.mac)];
+    if let Some(firmware) = &info.firmware_version {
+        lines.push(format!("firmware: {firmware}"));
+    }
+    lines.push(format!("connection: {}", info.connection.as_str()));
+    lines.extend(port_comments(info));
+    lines
+}
+
+/// The [`device_comments`] fields, plus the device generation, as a JSON
+/// object.
+fn device_json(info: &RecordingInfo) -> Value {
+    json!({
+        "device": info.mac,
+        "bitalino2": info.is_bitalino2,
+        "firmware": info.firmware_version,
+        "connection": info.connection.as_str(),
+        "ports": port_comments(info),
+    })
+}
+
+/// One `port <label>: <input> <sensor>[, <description>]` line per channel
+/// wired through a named port.
+fn port_comments(info: &RecordingInfo) -> Vec<String> {
+    info.channels
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            let port = info.port(i)?;
+            let sensor = info.sensors.get(i).map_or("RAW", String::as_str);
+            let mut line = format!("port {}: A{} {}", port.label, c + 1, sensor);
+            if let Some(description) = &port.description {
+                line.push_str(&format!(", {description}"));
+            }
+            Some(line)
+        })
+        .collect()
+}
+
+/// Format `at` as OpenSignals' `Y-M-D` date and `H:M:S.mmm` time, in UTC.
+fn utc_date_time(at: SystemTime) -> (String, String) {
+    let (year, month, day, secs_of_day, millis) = utc_fields(at);
+    (
+        format!("{year}-{month}-{day}"),
+        format!(
+            "{}:{}:{}.{:03}",
+            secs_of_day / 3_600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+            millis
+        ),
+    )
+}
+
+/// Format `at` as an ISO 8601 UTC timestamp with milliseconds.
+pub fn utc_iso8601(at: SystemTime) -> String {
+    let (year, month, day, secs_of_day, millis) = utc_fields(at);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{millis:03}Z",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+    )
+}
+
+/// Parse an ISO 8601 UTC timestamp as written by [`utc_iso8601`]:
+/// `YYYY-MM-DDTHH:MM:SS` with optional fractional seconds and a `Z`.
+#[allow(dead_code)]
+pub(crate) fn parse_utc_iso8601(text: &str) -> Option<SystemTime> {
+    let text = text.strip_suffix('Z')?;
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+    let nanos = if fraction.is_empty() {
+        0
+    } else if fraction.len() <= 9 && fraction.bytes().all(|b| b.is_ascii_digit()) {
+        fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32)
+    } else {
+        return None;
+    };
+
+    // Days-from-civil (H. Hinnant), the inverse of `utc_fields`.
+    let y = year - i64::from(month <= 2);
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146_097 + doe - 719_468).ok()?;
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Calendar date, seconds into the day and milliseconds of `at`, in UTC.
+pub(crate) fn utc_fields(at: SystemTime) -> (i64, i64, i64, u64, u32) {
+    let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days (H. Hinnant), shifted so the era starts on 0000-03-01.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day, secs_of_day, since_epoch.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Annotation;
+
+    fn info() -> RecordingInfo {
+        RecordingInfo {
+            mac: "20:16:10:00:3D:4F".into(),
+            sampling_rate: 1000,
+            channels: vec![0, 5],
+            sensors: vec!["ECG".into()],
+            ports: vec![
+                None,
+                Some(ChannelPort {
+                    label: "P6".into(),
+                    description: Some("wrist".into()),
+                }),
+            ],
+            is_bitalino2: true,
+            firmware_version: Some("BITalino_v5.2".into()),
+            connection: ConnectionKind::Bluetooth,
+            // 2024-02-29 13:05:09.250 UTC
+            started: UNIX_EPOCH + Duration::from_millis(1_709_211_909_250),
+        }
+    }
+
+    #[test]
+    fn writes_csv_rows() {
+        let mut writer = FrameWriter::new(Vec::new(), RecordFormat::Csv, &info()).unwrap();
+        writer
+            .write_frames(&[Frame::new(7, [1, 0, 0, 1], &[512, 21])])
+            .unwrap();
+        assert_eq!(writer.frames_written(), 1);
+        let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            text,
+            "# acquisition_start_utc: 2024-02-29T13:05:09.250Z\n\
+             # device: 20:16:10:00:3D:4F\n\
+             # firmware: BITalino_v5.2\n\
+             # connection: bluetooth\n\
+             # port P6: A6 RAW, wrist\n\
+             nSeq,I1,I2,O1,O2,A1,A6\n7,1,0,0,1,512,21\n"
+        );
+    }
+
+    #[test]
+    fn writes_csv_annotation_lines() {
+        let mut writer = FrameWriter::new(Vec::new(), RecordFormat::Csv, &info()).unwrap();
+        writer
+            .write_frames(&[Frame::new(7, [1, 0, 0, 1], &[512, 21])])
+            .unwrap();
+        let mut session = Session::new(info());
+        for (label, sample_index) in [("rest", 7), ("task, \"b\"", 1200)] {
+            session.annotations.push(Annotation {
+                label: label.into(),
+                sample_index,
+                timestamp_us: sample_index * 1000,
+            });
+        }
+        writer.write_session(&session).unwrap();
+        let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        let annotations: Vec<(u64, String)> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("# annotation: "))
+            .map(|line| {
+                let value: Value = serde_json::from_str(line).unwrap();
+                let label = value["label"].as_str().unwrap().to_string();
+                (value["sample"].as_u64().unwrap(), label)
+            })
+            .collect();
+        assert_eq!(
+            annotations,
+            [(7, "rest".to_string()), (1200, "task, \"b\"".to_string())]
+        );
+        assert!(text.lines().last().unwrap().starts_with("# session: "));
+    }
+
+    #[test]
+    fn parses_the_timestamps_it_writes() {
+        let started = info().started;
+        assert_eq!(parse_utc_iso8601(&utc_iso8601(started)), Some(started));
+        assert_eq!(
+            parse_utc_iso8601("1999-12-31T23:59:60Z"),
+            None,
+            "leap seconds are not representable"
+        );
+        assert_eq!(
+            parse_utc_iso8601("2026-03-01T00:00:00Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_772_323_200))
+        );
+        assert_eq!(
+            parse_utc_iso8601("2026-03-01T00:00:00.5Z"),
+            Some(UNIX_EPOCH + Duration::from_millis(1_772_323_200_500))
+        );
+        assert_eq!(parse_utc_iso8601("2026-03-01T00:00:00"), None);
+        assert_eq!(parse_utc_iso8601("2026-13-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn writes_opensignals_header_and_rows() {
+        let mut writer = FrameWriter::new(Vec::new(), RecordFormat::OpenSignals, &info()).unwrap();
+        writer
+            .write_frames(&[Frame::new(7, [1, 0, 0, 1], &[512, 21])])
+            .unwrap();
+        let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "# OpenSignals Text File Format");
+        assert!(lines[1]
+            .contains("\"column\": [\"nSeq\", \"I1\", \"I2\", \"O1\", \"O2\", \"A1\", \"A6\"]"));
+        assert!(lines[1].contains("\"channels\": [1, 6]"));
+        assert!(lines[1].contains("\"sensor\": [\"ECG\", \"RAW\"]"));
+        assert!(lines[1].contains("\"comments\": \"port P6: A6 RAW, wrist\""));
+        // A6 is only sent with 6 bits in 5- and 6-channel frames.
+        assert!(lines[1].contains("\"resolution\": [4, 1, 1, 1, 1, 10, 10]"));
+        assert!(lines[1].contains("\"date\": \"2024-2-29\", "));
+        assert!(lines[1].contains("\"time\": \"13:5:9.250\", "));
+        assert!(lines[1].contains("\"firmware version\": \"BITalino_v5.2\""));
+        assert_eq!(lines[2], "# EndOfHeader");
+        assert_eq!(lines[3], "7\t1\t0\t0\t1\t512\t21");
+        assert_eq!(
+            RecordFormat::from_path("session.TXT"),
+            RecordFormat::OpenSignals
+        );
+    }
+
+    #[test]
+    fn writes_one_json_object_per_frame() {
+        let mut sink = JsonlSink::new(Vec::new(), &info());
+        sink.write_frames(&[
+            Frame::new(0, [1, 0, 0, 1], &[512, 21]),
+            Frame::new(3, [0, 0, 0, 0], &[1023, 63]),
+        ])
+        .unwrap();
+        let text = String::from_utf8(sink.finish().unwrap()).unwrap();
+        let lines: Vec<Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0]["header"],
+            json!({
+                "device": "20:16:10:00:3D:4F",
+                "bitalino2": true,
+                "firmware": "BITalino_v5.2",
+                "connection": "bluetooth",
+                "ports": ["port P6: A6 RAW, wrist"],
+                "sampling_rate": 1000,
+                "started": "2024-02-29T13:05:09.250Z",
+            })
+        );
+        assert_eq!(lines[1]["I1"], 1);
+        assert_eq!(
+            lines[1]["A1"],
+            json!({"raw": 512, "value": 0.0, "unit": "mV"})
+        );
+        assert_eq!(lines[1]["A6"]["unit"], "ADC");
+        assert_eq!(lines[1]["A6"]["port"], "P6");
+        // Two frames lost between sequence 0 and 3.
+        assert_eq!(lines[2]["sample"], 3);
+        assert_eq!(lines[2]["t"], 0.003);
+        assert!((lines[2]["time"].as_f64().unwrap() - 1_709_211_909.253).abs() < 1e-6);
+        assert_eq!(RecordFormat::from_path("a.ndjson"), RecordFormat::Jsonl);
+    }
+
+    #[test]
+    fn writes_wfdb_record_with_sensor_gains() {
+        let mut writer = WfdbWriter::new(Vec::new(), "rec", &info());
+        writer
+            .write_frames(&[
+                Frame::new(0, [0; 4], &[512, 21]),
+                Frame::new(2, [0; 4], &[600, 1]), // one frame lost
+            ])
+            .unwrap();
+        let header = writer.header();
+        let lines: Vec<&str> = header.lines().collect();
+        assert_eq!(lines[0], "rec 2 1000 3 13:05:09.250 29/02/2024");
+        // ECG: 1024 * 1100 / 3300 counts per mV around mid-scale.
+        assert_eq!(
+            lines[1],
+            "rec.dat 16 341.333333(512)/mV 10 0 512 -31656 0 A1 ECG"
+        );
+        assert_eq!(
+            lines[2],
+            "rec.dat 16 1.000000(0)/ADC 10 0 21 -32746 0 A6 RAW port P6"
+        );
+        assert_eq!(lines[3], "# device: 20:16:10:00:3D:4F");
+        assert_eq!(lines[5], "# connection: bluetooth");
+        assert_eq!(lines[6], "# port P6: A6 RAW, wrist");
+
+        let dat = writer.finish().unwrap();
+        let samples: Vec<i16> = dat
+            .chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(samples, [512, 21, i16::MIN, i16::MIN, 600, 1]);
+        assert_eq!(RecordFormat::from_path("rec.hea"), RecordFormat::Wfdb);
+    }
+}
@@ -0,0 +1,387 @@
+//! Crash safety for recordings.
+//!
+//! Recording files are written through buffers, and the operating system
+//! keeps written data in its cache a while longer, so a crash or power loss
+//! can cut a file off mid-row. [`PeriodicSync`] bounds what is lost by syncing
+//! a sink on an interval, and [`recover`] repairs a file cut short: partial
+//! rows at its end are removed and the footer a closed file ends with is
+//! added, so only the batch being written when it stopped is lost.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::bitalino::Frame;
+use crate::recording::{replace_file, FrameSink, RecordFormat};
+use crate::session::Session;
+
+/// [`FrameSink`] wrapper that syncs the wrapped sink to storage after a
+/// write once `interval` has passed since the last sync.
+pub struct PeriodicSync<S: FrameSink> {
+    sink: S,
+    interval: Duration,
+    last_sync: Instant,
+}
+
+impl<S: FrameSink> PeriodicSync<S> {
+    /// Sync `sink` at most every `interval`; zero syncs after every write.
+    pub fn new(sink: S, interval: Duration) -> Self {
+        Self {
+            sink,
+            interval,
+            last_sync: Instant::now(),
+        }
+    }
+
+    /// The wrapped sink.
+    #[allow(dead_code)]
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S: FrameSink> FrameSink for PeriodicSync<S> {
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        self.sink.write_frames(frames)?;
+        if self.last_sync.elapsed() >= self.interval {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.sink.frames_written()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.last_sync = Instant::now();
+        self.sink.sync()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.sink.close()
+    }
+
+    fn write_session(&mut self, session: &Session) -> io::Result<()> {
+        self.sink.write_session(session)
+    }
+}
+
+/// What [`recover`] found in a recording, and removed from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    /// Whether the file was complete, footer included, and left untouched.
+    /// Files repaired before count as complete.
+    pub closed: bool,
+    /// Complete frames in the file (WFDB: samples, gaps included).
+    pub frames: u64,
+    /// Bytes of partial or unreadable data removed from the end.
+    pub removed_bytes: u64,
+}
+
+/// Repair the recording at `path`, in the format its extension gives (see
+/// [`RecordFormat::from_path`]), after a crash cut it short.
+///
+/// The file is truncated after its last complete frame. CSV and JSON Lines
+/// files then get the footer [`FrameSink::close`] writes, marked
+/// `"recovered": true`; a WFDB record gets a header matching its signal file.
+/// Compact files lose a partial block and get their end marker, EDF files a
+/// partial data record and get the record count in their header.
+/// OpenSignals files have no footer, so their last complete row is all that
+/// is checked. Files that were closed properly are not modified.
+///
+/// # Errors
+/// Fails if the file cannot be read or rewritten.
+pub fn recover(path: impl AsRef<Path>) -> io::Result<Recovery> {
+    let path = path.as_ref();
+    match RecordFormat::from_path(path) {
+        RecordFormat::Wfdb => recover_wfdb(path),
+        RecordFormat::Compact => crate::compact::recover(path),
+        RecordFormat::Edf => crate::edf::recover(path),
+        format => recover_lines(path, format),
+    }
+}
+
+/// [`recover`] for the line-based formats.
+fn recover_lines(path: &Path, format: RecordFormat) -> io::Result<Recovery> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = Vec::new();
+    // End of the last complete, valid line and whether it was the footer.
+    let mut valid_len = 0u64;
+    let mut footer = false;
+    let mut frames = 0;
+    // Fields per row, from the CSV column names or the first row.
+    let mut columns = None;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        let Ok(text) = std::str::from_utf8(&line[..read - 1]) else {
+            break;
+        };
+        let kind = match format {
+            RecordFormat::Jsonl => jsonl_line(text),
+            RecordFormat::OpenSignals => text_line(text, '\t', &mut columns),
+            _ => text_line(text, ',', &mut columns),
+        };
+        match kind {
+            Line::Frame => frames += 1,
+            Line::Footer | Line::Other => {}
+            Line::Invalid => break,
+        }
+        footer = kind == Line::Footer;
+        valid_len += read as u64;
+    }
+
+    let removed_bytes = std::fs::metadata(path)?.len() - valid_len;
+    let closed = removed_bytes == 0 && (footer || format == RecordFormat::OpenSignals);
+    if !closed {
+        // Appends go to the new end of the file.
+        let mut file = OpenOptions::new().append(true).open(path)?;
+        file.set_len(valid_len)?;
+        let end = json!({ "frames": frames, "recovered": true });
+        match format {
+            RecordFormat::Jsonl => writeln!(file, "{}", json!({ "end": end }))?,
+            RecordFormat::Csv => writeln!(file, "# end: {end}")?,
+            _ => {}
+        }
+        file.sync_all()?;
+    }
+    Ok(Recovery {
+        closed,
+        frames,
+        removed_bytes,
+    })
+}
+
+/// A complete line of a recording, as far as [`recover`] is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Frame,
+    Footer,
+    /// Header, column names or session.
+    Other,
+    Invalid,
+}
+
+/// Classify a CSV or OpenSignals line: `#` comments, the CSV column names and
+/// rows of `sep`-separated integers, as many as the first row has.
+fn text_line(text: &str, sep: char, columns: &mut Option<usize>) -> Line {
+    if let Some(comment) = text.strip_prefix('#') {
+        return if comment.trim_start().starts_with("end:") {
+            Line::Footer
+        } else {
+            Line::Other
+        };
+    }
+    if text.starts_with("nSeq") && columns.is_none() {
+        *columns = Some(text.split(sep).count());
+        return Line::Other;
+    }
+    let fields = text.split(sep).map(|f| f.parse::<u16>().ok());
+    let count = fields.clone().count();
+    if fields.clone().any(|f| f.is_none()) || *columns.get_or_insert(count) != count {
+        return Line::Invalid;
+    }
+    Line::Frame
+}
+
+/// Classify a JSON Lines line: frames carry `seq`, the footer is `end`.
+fn jsonl_line(text: &str) -> Line {
+    match serde_json::from_str::<Value>(text) {
+        Ok(Value::Object(line)) if line.contains_key("seq") => Line::Frame,
+        Ok(Value::Object(line)) if line.contains_key("end") => Line::Footer,
+        Ok(Value::Object(_)) => Line::Other,
+        _ => Line::Invalid,
+    }
+}
+
+/// [`recover`] for a WFDB record: drop a partial sample from the signal file
+/// and rewrite the sample count, initial values and checksums in the header.
+fn recover_wfdb(path: &Path) -> io::Result<Recovery> {
+    let header_path = path.with_extension("hea");
+    let signals_path = path.with_extension("dat");
+    let header = std::fs::read_to_string(&header_path)?;
+    let mut lines: Vec<String> = header.lines().map(String::from).collect();
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {what}", header_path.display()),
+        )
+    };
+    let record: Vec<&str> = lines
+        .first()
+        .ok_or_else(|| invalid("empty header"))?
+        .split(' ')
+        .collect();
+    let n_signals: usize = record
+        .get(1)
+        .and_then(|n| n.parse().ok())
+        .filter(|&n| n > 0 && lines.len() > n)
+        .ok_or_else(|| invalid("bad signal count"))?;
+    let header_samples: Option<u64> = record.get(3).and_then(|n| n.parse().ok());
+
+    let sample_bytes = 2 * n_signals as u64;
+    let len = std::fs::metadata(&signals_path)?.len();
+    let removed_bytes = len % sample_bytes;
+    let samples = len / sample_bytes;
+    if removed_bytes == 0 && header_samples == Some(samples) {
+        return Ok(Recovery {
+            closed: true,
+            frames: samples,
+            removed_bytes,
+        });
+    }
+
+    let file = OpenOptions::new().write(true).open(&signals_path)?;
+    file.set_len(len - removed_bytes)?;
+    file.sync_all()?;
+    let mut first_values = vec![0i16; n_signals];
+    let mut checksums = vec![0i16; n_signals];
+    let mut reader = BufReader::new(File::open(&signals_path)?);
+    let mut sample = vec![0u8; sample_bytes as usize];
+    for n in 0..samples {
+        reader.read_exact(&mut sample)?;
+        for (i, bytes) in sample.chunks_exact(2).enumerate() {
+            let value = i16::from_le_bytes([bytes[0], bytes[1]]);
+            if n == 0 {
+                first_values[i] = value;
+            }
+            checksums[i] = checksums[i].wrapping_add(value);
+        }
+    }
+
+    let mut record: Vec<String> = record.iter().map(|f| f.to_string()).collect();
+    record[3] = samples.to_string();
+    lines[0] = record.join(" ");
+    for i in 0..n_signals {
+        let mut fields: Vec<String> = lines[i + 1].splitn(8, ' ').map(String::from).collect();
+        if fields.len() < 8 {
+            return Err(invalid("bad signal specification"));
+        }
+        fields[5] = first_values[i].to_string();
+        fields[6] = checksums[i].to_string();
+        lines[i + 1] = fields.join(" ");
+    }
+    replace_file(&header_path, (lines.join("\n") + "\n").as_bytes())?;
+    Ok(Recovery {
+        closed: false,
+        frames: samples,
+        removed_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::ConnectionKind;
+    use crate::recording::{FrameWriter, JsonlSink, RecordingInfo, WfdbWriter};
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+
+    fn info() -> RecordingInfo {
+        RecordingInfo {
+            mac: "20:16:10:00:3D:4F".into(),
+            sampling_rate: 1000,
+            channels: vec![0, 1],
+            sensors: Vec::new(),
+            ports: Vec::new(),
+            is_bitalino2: true,
+            firmware_version: None,
+            connection: ConnectionKind::Bluetooth,
+            started: UNIX_EPOCH,
+        }
+    }
+
+    /// Frames `range` of a gapless acquisition.
+    fn frames(range: std::ops::Range<u64>) -> Vec<Frame> {
+        range
+            .map(|i| Frame::new((i % 16) as u8, [0; 4], &[i as u16, 7]))
+            .collect()
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bitalino_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Append `bytes` to `path`, as a crash mid-write would leave them.
+    fn append(path: &Path, bytes: &[u8]) {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(bytes).unwrap();
+    }
+
+    #[test]
+    fn recovers_csv_and_jsonl_cut_mid_line() {
+        let dir = scratch("recover_lines");
+        let csv = dir.join("rec.csv");
+        let mut sink = PeriodicSync::new(
+            FrameWriter::create(&csv, RecordFormat::Csv, &info()).unwrap(),
+            Duration::ZERO,
+        );
+        sink.write_frames(&frames(0..20)).unwrap();
+        drop(sink);
+        append(&csv, b"4,0,0,0,0,51");
+        let recovery = recover(&csv).unwrap();
+        assert_eq!((recovery.closed, recovery.frames), (false, 20));
+        assert_eq!(recovery.removed_bytes, 12);
+        let text = std::fs::read_to_string(&csv).unwrap();
+        assert!(text.ends_with("3,0,0,0,0,19,7\n# end: {\"frames\":20,\"recovered\":true}\n"));
+        // Repaired files count as closed.
+        assert!(recover(&csv).unwrap().closed);
+
+        let jsonl = dir.join("rec.jsonl");
+        let mut sink = JsonlSink::create(&jsonl, &info()).unwrap();
+        sink.write_frames(&frames(0..5)).unwrap();
+        sink.sync().unwrap();
+        drop(sink);
+        append(&jsonl, b"{\"t\":0.005,\"ti");
+        assert_eq!(recover(&jsonl).unwrap().frames, 5);
+        let last = std::fs::read_to_string(&jsonl).unwrap();
+        let last: Value = serde_json::from_str(last.lines().last().unwrap()).unwrap();
+        assert_eq!(last["end"]["frames"], 5);
+
+        let mut sink = JsonlSink::create(&jsonl, &info()).unwrap();
+        sink.write_frames(&frames(0..5)).unwrap();
+        sink.close().unwrap();
+        let recovery = recover(&jsonl).unwrap();
+        assert_eq!((recovery.closed, recovery.removed_bytes), (true, 0));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recovers_wfdb_header_from_signal_file() {
+        let dir = scratch("recover_wfdb");
+        let path = dir.join("rec.hea");
+        let mut writer = WfdbWriter::create(&path, &info()).unwrap();
+        writer.write_frames(&frames(0..10)).unwrap();
+        writer.flush().unwrap();
+        let closed = std::fs::read_to_string(&path).unwrap();
+        assert!(recover(&path).unwrap().closed);
+
+        // Samples reach the signal file without the header catching up.
+        writer.write_frames(&frames(10..13)).unwrap();
+        writer.sync().unwrap();
+        drop(writer);
+        std::fs::write(&path, &closed).unwrap();
+        append(&dir.join("rec.dat"), &[1, 2, 3]);
+        let recovery = recover(&path).unwrap();
+        assert_eq!((recovery.frames, recovery.removed_bytes), (13, 3));
+
+        let mut expected = WfdbWriter::new(Vec::new(), "rec", &info());
+        expected.write_frames(&frames(0..13)).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected.header());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,287 @@
+//! On-disk registry of devices this host has connected to.
+//!
+//! Each successful connection records the device's MAC, RFCOMM channel and
+//! firmware version, so later connections to a known device can skip the
+//! Bluetooth scan, reuse its channel and notice when the device answering at
+//! that address is not the one seen before. The registry is a TOML file:
+//!
+//! ```toml
+//! [[device]]
+//! mac = "20:16:10:00:3D:4F"
+//! name = "BITalino-3D-4F"
+//! channel = 1
+//! firmware = "BITalino_v5.2"
+//! last_connected = 1709211909
+//! ```
+//!
+//! It lives at `$BITALINO_REGISTRY` if set, otherwise at
+//! `bitalino/devices.toml` under `$XDG_CONFIG_HOME` (`~/.config`), or under
+//! `%APPDATA%` on Windows.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::bitalino::Bitalino;
+
+/// A device recorded in the registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownDevice {
+    /// MAC address, upper case.
+    pub mac: String,
+    /// Friendly name, once a scan has seen the device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// RFCOMM channel of the last successful connection.
+    pub channel: u8,
+    /// Firmware version reported on the last connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firmware: Option<String>,
+    /// Unix time of the last successful connection, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_connected: Option<u64>,
+}
+
+/// Layout of the registry file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegistryFile {
+    #[serde(default, rename = "device", skip_serializing_if = "Vec::is_empty")]
+    devices: Vec<KnownDevice>,
+}
+
+/// Devices remembered across sessions, backed by a TOML file.
+///
+/// Changes are kept in memory until [`save`](Self::save).
+#[derive(Debug, Clone)]
+pub struct DeviceRegistry {
+    path: PathBuf,
+    devices: Vec<KnownDevice>,
+}
+
+impl DeviceRegistry {
+    /// Where the registry is kept on this host; `None` if no configuration
+    /// directory can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        registry_path(|name| std::env::var_os(name))
+    }
+
+    /// Load the registry at [`default_path`](Self::default_path).
+    ///
+    /// # Errors
+    /// Fails if there is no configuration directory or the file is unreadable.
+    pub fn load_default() -> Result<Self> {
+        let path = Self::default_path()
+            .context("No configuration directory: set BITALINO_REGISTRY or HOME")?;
+        Self::load(path)
+    }
+
+    /// [`load_default`](Self::load_default) for connection paths: failures
+    /// are logged and give `None`, so a broken registry never blocks a
+    /// connection (and is not overwritten).
+    pub fn load_or_warn() -> Option<Self> {
+        Self::load_default()
+            .map_err(|e| warn!("Device registry unavailable: {:#}", e))
+            .ok()
+    }
+
+    /// Load the registry at `path`; a missing file is an empty registry.
+    ///
+    /// # Errors
+    /// Fails if the file exists but cannot be read or parsed.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str::<RegistryFile>(&text)
+                .with_context(|| format!("Invalid device registry {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => RegistryFile::default(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read device registry {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path,
+            devices: file.devices,
+        })
+    }
+
+    /// File the registry is loaded from and saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Remembered devices, in the order they were first seen.
+    pub fn devices(&self) -> &[KnownDevice] {
+        &self.devices
+    }
+
+    /// Entry for `mac`, compared case-insensitively.
+    pub fn get(&self, mac: &str) -> Option<&KnownDevice> {
+        self.devices
+            .iter()
+            .find(|d| d.mac.eq_ignore_ascii_case(mac))
+    }
+
+    fn get_mut(&mut self, mac: &str) -> Option<&mut KnownDevice> {
+        self.devices
+            .iter_mut()
+            .find(|d| d.mac.eq_ignore_ascii_case(mac))
+    }
+
+    /// Record a successful connection to `device` over `channel`, warning if
+    /// its firmware differs from the one seen before at the same address.
+    ///
+    /// Devices without a MAC (serial, TCP or replay links) are not recorded.
+    pub fn note_connection(&mut self, device: &Bitalino, channel: u8) {
+        let info = device.device_info();
+        let Some(mac) = info.mac else {
+            return;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .ok();
+        match self.get_mut(&mac) {
+            Some(known) => {
+                if let (Some(before), Some(now)) = (&known.firmware, &info.firmware_version) {
+                    if before != now {
+                        warn!(
+                            "{} reports firmware {} but was {} on the last connection",
+                            known.mac, now, before
+                        );
+                    }
+                }
+                known.channel = channel;
+                known.firmware = info.firmware_version.or(known.firmware.take());
+                known.last_connected = now;
+            }
+            None => self.devices.push(KnownDevice {
+                mac: mac.to_ascii_uppercase(),
+                name: None,
+                channel,
+                firmware: info.firmware_version,
+                last_connected: now,
+            }),
+        }
+    }
+
+    /// Set the friendly name of a remembered device; unknown MACs are ignored.
+    pub fn set_name(&mut self, mac: &str, name: &str) -> bool {
+        match self.get_mut(mac) {
+            Some(known) if known.name.as_deref() != Some(name) => {
+                known.name = Some(name.to_string());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Remove `mac` from the registry, returning whether it was present.
+    pub fn forget(&mut self, mac: &str) -> bool {
+        let before = self.devices.len();
+        self.devices.retain(|d| !d.mac.eq_ignore_ascii_case(mac));
+        self.devices.len() != before
+    }
+
+    /// Write the registry back to its file, creating the directory if needed.
+    ///
+    /// The file is replaced atomically, so a crash mid-write never leaves a
+    /// truncated registry behind.
+    ///
+    /// # Errors
+    /// Fails if the directory or file cannot be written.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let text = toml::to_string(&RegistryFile {
+            devices: self.devices.clone(),
+        })?;
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, text)
+            .and_then(|()| std::fs::rename(&tmp, &self.path))
+            .with_context(|| format!("Failed to write device registry {}", self.path.display()))
+    }
+
+    /// [`save`](Self::save), logging failures instead of returning them.
+    pub fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            warn!("{:#}", e);
+        }
+    }
+}
+
+/// Registry location from the environment variables looked up by `var`.
+fn registry_path(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let set = |name: &str| var(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    if let Some(path) = set("BITALINO_REGISTRY") {
+        return Some(path);
+    }
+    let config = set("XDG_CONFIG_HOME")
+        .or_else(|| set("HOME").map(|home| home.join(".config")))
+        .or_else(|| set("APPDATA"))?;
+    Some(config.join("bitalino").join("devices.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_xdg_locations() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| OsString::from(v))
+            }
+        };
+        assert_eq!(
+            registry_path(env(&[("HOME", "/home/ana")])),
+            Some(PathBuf::from("/home/ana/.config/bitalino/devices.toml"))
+        );
+        assert_eq!(
+            registry_path(env(&[("HOME", "/home/ana"), ("XDG_CONFIG_HOME", "/cfg")])),
+            Some(PathBuf::from("/cfg/bitalino/devices.toml"))
+        );
+        assert_eq!(
+            registry_path(env(&[("BITALINO_REGISTRY", "/tmp/r.toml"), ("HOME", "/h")])),
+            Some(PathBuf::from("/tmp/r.toml"))
+        );
+        assert_eq!(registry_path(env(&[("HOME", "")])), None);
+    }
+
+    #[test]
+    fn saves_and_forgets_devices() {
+        let path = std::env::temp_dir()
+            .join(format!("bitalino_registry_{}", std::process::id()))
+            .join("devices.toml");
+        let mut registry = DeviceRegistry::load(&path).unwrap();
+        assert!(registry.devices().is_empty());
+        registry.devices.push(KnownDevice {
+            mac: "20:16:10:00:3D:4F".into(),
+            name: None,
+            channel: 1,
+            firmware: Some("BITalino_v5.2".into()),
+            last_connected: Some(1_709_211_909),
+        });
+        assert!(registry.set_name("20:16:10:00:3d:4f", "BITalino-3D-4F"));
+        registry.save().unwrap();
+
+        let mut reloaded = DeviceRegistry::load(&path).unwrap();
+        assert_eq!(reloaded.devices(), registry.devices());
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("[[device]]\nmac = \"20:16:10:00:3D:4F\"\n"));
+        assert!(reloaded.forget("20:16:10:00:3d:4f"));
+        assert!(!reloaded.forget("20:16:10:00:3D:4F"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
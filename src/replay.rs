@@ -0,0 +1,226 @@
+//! Offline playback of recorded BITalino byte streams.
+//!
+//! [`ReplayTransport`] serves a previously captured raw stream (the bytes the
+//! device sent during acquisition) through the normal [`Bitalino`] decoding
+//! path, so analysis code and decoders can be developed without hardware.
+//!
+//! The transport follows the driver's commands: bytes are only served between
+//! a start and a stop command, and the sampling rate and channel mask of the
+//! start command set the real-time pacing. Other commands are accepted and
+//! ignored, so queries such as `version()` time out. The end of the recording
+//! reads as a closed link.
+//!
+//! [`Bitalino`]: crate::bitalino::Bitalino
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::bitalino::{ConnectionKind, Transport};
+use crate::decode::frame_size;
+
+/// Default read timeout, matching the RFCOMM connector.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Command prefix followed by a PWM value byte that must not be parsed.
+const CMD_PWM_PREFIX: u8 = 0xA3;
+
+/// Transport replaying a captured byte stream.
+pub struct ReplayTransport {
+    data: Vec<u8>,
+    pos: usize,
+    realtime: bool,
+    read_timeout: Duration,
+    /// Sampling rate from the last rate command, in Hz.
+    rate_hz: u32,
+    /// Playback state while acquiring: bytes per second and where pacing started.
+    playing: Option<Playback>,
+    /// Set after a PWM prefix: the next written byte is its argument.
+    skip_next_command: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Playback {
+    bytes_per_sec: f64,
+    started: Instant,
+    start_pos: usize,
+}
+
+impl ReplayTransport {
+    /// Replay the raw capture stored at `path`.
+    #[allow(dead_code)]
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_bytes(std::fs::read(path)?))
+    }
+
+    /// Replay an in-memory capture.
+    #[allow(dead_code)]
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            pos: 0,
+            realtime: false,
+            read_timeout: DEFAULT_TIMEOUT,
+            rate_hz: 1000,
+            playing: None,
+            skip_next_command: false,
+        }
+    }
+
+    /// Pace playback at the acquisition's sampling rate instead of serving the
+    /// capture as fast as it is read.
+    #[allow(dead_code)]
+    pub fn realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    /// Bytes not yet replayed.
+    #[allow(dead_code)]
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// Track the driver's acquisition state from a command byte.
+    fn observe_command(&mut self, cmd: u8) {
+        if self.skip_next_command {
+            self.skip_next_command = false;
+            return;
+        }
+        if self.playing.is_some() {
+            if cmd == 0x00 {
+                self.playing = None;
+            }
+            return;
+        }
+        match cmd {
+            CMD_PWM_PREFIX => self.skip_next_command = true,
+            // Rate command: 0bRR000011
+            c if c & 0x3F == 0x03 => self.rate_hz = [1, 10, 100, 1000][(c >> 6) as usize],
+            // Live start command: channel mask in bits 2-7, LSB set
+            c if c & 0x03 == 0x01 => {
+                let frame_size = frame_size((c >> 2).count_ones() as usize);
+                self.playing = Some(Playback {
+                    bytes_per_sec: (self.rate_hz as usize * frame_size) as f64,
+                    started: Instant::now(),
+                    start_pos: self.pos,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Number of bytes that may be served now under real-time pacing.
+    fn paced_available(&self, playback: &Playback) -> usize {
+        let due = (playback.started.elapsed().as_secs_f64() * playback.bytes_per_sec) as usize;
+        (playback.start_pos + due)
+            .min(self.data.len())
+            .saturating_sub(self.pos)
+    }
+}
+
+impl Read for ReplayTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(playback) = self.playing else {
+            // Nothing arrives while the "device" is idle.
+            return Err(io::Error::from(ErrorKind::WouldBlock));
+        };
+        if self.pos >= self.data.len() {
+            return Ok(0);
+        }
+
+        let mut available = self.data.len() - self.pos;
+        if self.realtime {
+            available = self.paced_available(&playback);
+            if available == 0 {
+                // Wait for the next byte to become due, like a socket would.
+                let next_due = (self.pos + 1 - playback.start_pos) as f64 / playback.bytes_per_sec;
+                let wait = Duration::from_secs_f64(next_due)
+                    .saturating_sub(playback.started.elapsed())
+                    .min(self.read_timeout);
+                std::thread::sleep(wait);
+                available = self.paced_available(&playback);
+                if available == 0 {
+                    return Err(io::Error::from(ErrorKind::TimedOut));
+                }
+            }
+        }
+
+        let n = buf.len().min(available);
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for ReplayTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &cmd in buf {
+            self.observe_command(cmd);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.read_timeout = timeout;
+        Ok(())
+    }
+
+    fn bytes_available(&mut self) -> io::Result<usize> {
+        Ok(match self.playing {
+            None => 0,
+            Some(playback) if self.realtime => self.paced_available(&playback),
+            Some(_) => self.data.len() - self.pos,
+        })
+    }
+
+    fn connection_kind(&self) -> ConnectionKind {
+        ConnectionKind::Replay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::Bitalino;
+    use crate::streaming::fixtures::FRAME;
+
+    #[test]
+    fn replays_frames_through_the_driver_after_start() {
+        let capture = FRAME.repeat(3);
+        let mut dev = Bitalino::from_transport(Box::new(ReplayTransport::from_bytes(capture)));
+        dev.start(1000, vec![0]).unwrap();
+
+        let batch = dev.read_frames_timed(3).unwrap();
+        assert_eq!(batch.frames.len(), 3);
+        assert_eq!(batch.frames[0].analog_values(), &[1023]);
+        assert!(
+            dev.read_frames_timed(1).is_err(),
+            "end of capture reads as closed link"
+        );
+    }
+
+    #[test]
+    fn realtime_playback_is_paced_by_sampling_rate() {
+        let mut replay = ReplayTransport::from_bytes(FRAME.repeat(20)).realtime(true);
+        // 100 Hz, one channel: 300 bytes/s, so 20 frames take ~200 ms.
+        replay.write_all(&[0x83, 0x05]).unwrap();
+        let started = Instant::now();
+        let mut buf = [0u8; 60];
+        let mut filled = 0;
+        while filled < buf.len() {
+            match replay.read(&mut buf[filled..]) {
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::TimedOut => {}
+                Err(e) => panic!("{e}"),
+            }
+        }
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+}
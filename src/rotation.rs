@@ -0,0 +1,498 @@
+//! Rotation of long recordings into numbered part files.
+//!
+//! A multi-hour acquisition written to one file ends up as a single fragile
+//! multi-gigabyte file. [`RotatingSink`] closes the current file and opens the
+//! next one every [`Rotation::every`] of signal, or once the file reaches
+//! [`Rotation::max_bytes`]. For `--out rec.csv` the parts are `rec_0001.csv`,
+//! `rec_0002.csv`, ... and `rec.manifest.json` lists them in order.
+//!
+//! Each part is a complete file in the chosen format, and its header carries
+//! the time of its own first frame. Sample indexes continue across parts: the
+//! manifest gives the index of each part's first sample, and JSON Lines
+//! `sample` values keep counting. Parts are closed, footer included, as the
+//! next one opens. The manifest is rewritten whenever a part is opened and on
+//! every flush, so a crash only affects the part being written.
+//! [`RotatingSink::resume`] continues a recording listed in an existing
+//! manifest, for example after the device was reconnected.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bitalino::Frame;
+use crate::recording::{
+    create_sink, replace_file, utc_iso8601, FrameSink, RecordFormat, RecordingInfo,
+};
+use crate::session::Session;
+
+/// When [`RotatingSink`] starts a new part file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rotation {
+    /// Signal length of each part, counted in samples at the nominal rate.
+    pub every: Option<Duration>,
+    /// Size at which a part is closed. The check runs before each write and
+    /// sees what has left the write buffer, so parts end slightly larger.
+    pub max_bytes: Option<u64>,
+}
+
+/// Index of a rotated recording, saved as JSON next to its parts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingManifest {
+    /// Format of the parts: `csv`, `opensignals`, `jsonl`, `wfdb`, `compact`
+    /// or `edf`.
+    pub format: String,
+    /// Sampling rate in Hz.
+    pub sampling_rate: u16,
+    /// Acquired channels, as `A1`..`A6`.
+    pub channels: Vec<String>,
+    /// Device MAC address or other identifier.
+    pub device: String,
+    /// Part files in recording order.
+    pub parts: Vec<ManifestPart>,
+}
+
+/// One part file of a rotated recording.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestPart {
+    /// File name, relative to the manifest's directory. WFDB parts name the
+    /// `.hea` header.
+    pub file: String,
+    /// Recording-wide index of the part's first sample.
+    pub first_sample: u64,
+    /// Samples spanned by the part, frames lost to gaps included.
+    pub samples: u64,
+    /// Frames written to the part.
+    pub frames: u64,
+    /// UTC time of the part's first frame (ISO 8601).
+    pub started: String,
+}
+
+impl RecordingManifest {
+    /// Manifest path of a recording written to `path`: `rec.csv` is indexed by
+    /// `rec.manifest.json`.
+    pub fn path_for(path: impl AsRef<Path>) -> PathBuf {
+        path.as_ref().with_extension("manifest.json")
+    }
+
+    /// Read the manifest at `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Recording-wide index following the last part.
+    pub fn next_sample(&self) -> u64 {
+        self.parts
+            .last()
+            .map_or(0, |part| part.first_sample + part.samples)
+    }
+
+    /// Write to `path` through a temporary file, so a crash never leaves a
+    /// truncated manifest behind.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(self)?;
+        replace_file(path, (text + "\n").as_bytes())
+    }
+}
+
+/// [`FrameSink`] that splits a recording into numbered parts, following a
+/// [`Rotation`] policy. The first part is opened by the first frame.
+pub struct RotatingSink {
+    path: PathBuf,
+    format: RecordFormat,
+    info: RecordingInfo,
+    manifest_path: PathBuf,
+    manifest: RecordingManifest,
+    /// Samples per part, from [`Rotation::every`].
+    part_samples: Option<u64>,
+    max_bytes: Option<u64>,
+    /// Sink of the last part in the manifest, once opened.
+    current: Option<Box<dyn FrameSink + Send>>,
+    /// Added to frame sample indexes: the samples recorded before a resume.
+    offset: u64,
+    frames_written: u64,
+}
+
+impl RotatingSink {
+    /// Start a rotated recording of the acquisition described by `info`, with
+    /// parts numbered after `path` and the manifest at
+    /// [`RecordingManifest::path_for`] `path`, replaced if present.
+    pub fn create(
+        path: impl AsRef<Path>,
+        format: RecordFormat,
+        info: &RecordingInfo,
+        rotation: Rotation,
+    ) -> io::Result<Self> {
+        let manifest = RecordingManifest {
+            format: format_name(format).to_string(),
+            sampling_rate: info.sampling_rate,
+            channels: info
+                .channels
+                .iter()
+                .map(|c| format!("A{}", c + 1))
+                .collect(),
+            device: info.mac.clone(),
+            parts: Vec::new(),
+        };
+        Self::open(path.as_ref(), format, info, rotation, manifest)
+    }
+
+    /// Continue the rotated recording at `path` with a new acquisition: parts
+    /// are numbered after the last one in the manifest, and sample indexes
+    /// carry on from its end. Falls back to [`create`](Self::create) when
+    /// there is no manifest yet.
+    ///
+    /// Fails if the manifest was written with another format, sampling rate
+    /// or channel set.
+    pub fn resume(
+        path: impl AsRef<Path>,
+        format: RecordFormat,
+        info: &RecordingInfo,
+        rotation: Rotation,
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+        let manifest = match RecordingManifest::load(RecordingManifest::path_for(path)) {
+            Ok(manifest) => manifest,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Self::create(path, format, info, rotation)
+            }
+            Err(e) => return Err(e),
+        };
+        let channels: Vec<String> = info
+            .channels
+            .iter()
+            .map(|c| format!("A{}", c + 1))
+            .collect();
+        if manifest.format != format_name(format)
+            || manifest.sampling_rate != info.sampling_rate
+            || manifest.channels != channels
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot resume a {} recording of {} at {} Hz as {} of {} at {} Hz",
+                    manifest.format,
+                    manifest.channels.join(","),
+                    manifest.sampling_rate,
+                    format_name(format),
+                    channels.join(","),
+                    info.sampling_rate
+                ),
+            ));
+        }
+        Self::open(path, format, info, rotation, manifest)
+    }
+
+    fn open(
+        path: &Path,
+        format: RecordFormat,
+        info: &RecordingInfo,
+        rotation: Rotation,
+        manifest: RecordingManifest,
+    ) -> io::Result<Self> {
+        let manifest_path = RecordingManifest::path_for(path);
+        manifest.save(&manifest_path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            info: info.clone(),
+            manifest_path,
+            offset: manifest.next_sample(),
+            manifest,
+            part_samples: rotation
+                .every
+                .map(|every| ((every.as_secs_f64() * f64::from(info.sampling_rate)) as u64).max(1)),
+            max_bytes: rotation.max_bytes,
+            current: None,
+            frames_written: 0,
+        })
+    }
+
+    /// The manifest as last saved or updated.
+    #[allow(dead_code)]
+    pub fn manifest(&self) -> &RecordingManifest {
+        &self.manifest
+    }
+
+    /// Path of part `number` (1-based): `rec.csv` becomes `rec_0001.csv`.
+    fn part_path(&self, number: usize) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut name = format!("{stem}_{number:04}");
+        if let Some(ext) = self.path.extension() {
+            name.push('.');
+            name.push_str(&ext.to_string_lossy());
+        }
+        self.path.with_file_name(name)
+    }
+
+    /// Whether the open part must be closed before recording-wide `sample`.
+    fn part_full(&self, sample: u64) -> io::Result<bool> {
+        let Some(part) = self.manifest.parts.last() else {
+            return Ok(true);
+        };
+        if self
+            .part_samples
+            .is_some_and(|n| sample >= part.first_sample + n)
+        {
+            return Ok(true);
+        }
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(false);
+        };
+        let mut data = self.path.with_file_name(&part.file);
+        if self.format == RecordFormat::Wfdb {
+            data.set_extension("dat");
+        }
+        Ok(std::fs::metadata(data)?.len() >= max_bytes)
+    }
+
+    /// Close the open part, if any, and open the next one at recording-wide
+    /// `sample`.
+    fn next_part(&mut self, sample: u64) -> io::Result<()> {
+        if let Some(mut sink) = self.current.take() {
+            sink.close()?;
+        }
+        let path = self.part_path(self.manifest.parts.len() + 1);
+        let mut info = self.info.clone();
+        // The part header starts at its first frame.
+        info.started += Duration::from_secs_f64(
+            (sample - self.offset) as f64 / f64::from(info.sampling_rate.max(1)),
+        );
+        self.current = Some(create_sink(&path, self.format, &info)?);
+        self.manifest.parts.push(ManifestPart {
+            file: path
+                .file_name()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            first_sample: sample,
+            samples: 0,
+            frames: 0,
+            started: utc_iso8601(info.started),
+        });
+        self.manifest.save(&self.manifest_path)
+    }
+
+    /// Write `frames` to the open part and account for them in the manifest.
+    fn write_part(&mut self, frames: &[Frame]) -> io::Result<()> {
+        let (Some(sink), Some(part), Some(last)) = (
+            self.current.as_mut(),
+            self.manifest.parts.last_mut(),
+            frames.last(),
+        ) else {
+            return Ok(());
+        };
+        if self.offset == 0 {
+            sink.write_frames(frames)?;
+        } else {
+            let shifted: Vec<Frame> = frames
+                .iter()
+                .map(|frame| Frame {
+                    sample_index: frame.sample_index + self.offset,
+                    ..*frame
+                })
+                .collect();
+            sink.write_frames(&shifted)?;
+        }
+        part.frames += frames.len() as u64;
+        part.samples = last.sample_index + self.offset + 1 - part.first_sample;
+        self.frames_written += frames.len() as u64;
+        Ok(())
+    }
+}
+
+impl FrameSink for RotatingSink {
+    /// Frames past the end of a part of [`Rotation::every`] go to the next
+    /// part, so parts hold whole periods of signal.
+    fn write_frames(&mut self, frames: &[Frame]) -> io::Result<()> {
+        let mut rest = frames;
+        while let Some(first) = rest.first() {
+            let sample = first.sample_index + self.offset;
+            if self.current.is_none() || self.part_full(sample)? {
+                self.next_part(sample)?;
+            }
+            let end = match (self.part_samples, self.manifest.parts.last()) {
+                (Some(n), Some(part)) => part.first_sample + n,
+                _ => u64::MAX,
+            };
+            let count = rest
+                .iter()
+                .position(|f| f.sample_index + self.offset >= end)
+                .unwrap_or(rest.len());
+            let (head, tail) = rest.split_at(count);
+            self.write_part(head)?;
+            rest = tail;
+        }
+        Ok(())
+    }
+
+    fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(sink) = &mut self.current {
+            sink.flush()?;
+        }
+        self.manifest.save(&self.manifest_path)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        if let Some(sink) = &mut self.current {
+            sink.sync()?;
+        }
+        self.manifest.save(&self.manifest_path)
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        if let Some(sink) = &mut self.current {
+            sink.close()?;
+        }
+        self.manifest.save(&self.manifest_path)
+    }
+
+    /// The session goes after the frames of the last part.
+    fn write_session(&mut self, session: &Session) -> io::Result<()> {
+        match &mut self.current {
+            Some(sink) => sink.write_session(session),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Name of `format` in manifests, as accepted by `record --format`.
+fn format_name(format: RecordFormat) -> &'static str {
+    match format {
+        RecordFormat::Csv => "csv",
+        RecordFormat::OpenSignals => "opensignals",
+        RecordFormat::Jsonl => "jsonl",
+        RecordFormat::Wfdb => "wfdb",
+        RecordFormat::Compact => "compact",
+        RecordFormat::Edf => "edf",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::ConnectionKind;
+    use std::time::UNIX_EPOCH;
+
+    fn info() -> RecordingInfo {
+        RecordingInfo {
+            mac: "20:16:10:00:3D:4F".into(),
+            sampling_rate: 10,
+            channels: vec![0],
+            sensors: Vec::new(),
+            ports: Vec::new(),
+            is_bitalino2: true,
+            firmware_version: None,
+            connection: ConnectionKind::Bluetooth,
+            started: UNIX_EPOCH + Duration::from_secs(1_709_211_909),
+        }
+    }
+
+    /// Frames `range` of a gapless acquisition, as the driver numbers them.
+    fn frames(range: std::ops::Range<u64>) -> Vec<Frame> {
+        range
+            .map(|n| {
+                let mut frame = Frame::new((n % 16) as u8, [0; 4], &[n as u16]);
+                frame.sample_index = n;
+                frame
+            })
+            .collect()
+    }
+
+    /// Empty directory for one test's files.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bitalino_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// `sample` of each frame line; closed parts end with a footer line.
+    fn jsonl_samples(path: &Path) -> Vec<u64> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .filter_map(|l| {
+                serde_json::from_str::<serde_json::Value>(l).unwrap()["sample"].as_u64()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_duration_with_continuous_samples() {
+        let dir = scratch("rotation_duration");
+        let path = dir.join("rec.jsonl");
+        let rotation = Rotation {
+            every: Some(Duration::from_secs(2)),
+            max_bytes: None,
+        };
+        let mut sink = RotatingSink::create(&path, RecordFormat::Jsonl, &info(), rotation).unwrap();
+        // Batches that straddle part boundaries.
+        sink.write_frames(&frames(0..15)).unwrap();
+        sink.write_frames(&frames(15..45)).unwrap();
+        sink.flush().unwrap();
+
+        let manifest = RecordingManifest::load(dir.join("rec.manifest.json")).unwrap();
+        let files: Vec<&str> = manifest.parts.iter().map(|p| p.file.as_str()).collect();
+        assert_eq!(
+            files,
+            ["rec_0001.jsonl", "rec_0002.jsonl", "rec_0003.jsonl"]
+        );
+        let starts: Vec<u64> = manifest.parts.iter().map(|p| p.first_sample).collect();
+        assert_eq!(starts, [0, 20, 40]);
+        assert_eq!(manifest.parts[2].samples, 5);
+        assert_eq!(manifest.parts[1].started, "2024-02-29T13:05:11.000Z");
+        assert_eq!(
+            jsonl_samples(&dir.join("rec_0002.jsonl")),
+            (20..40).collect::<Vec<_>>()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn splits_on_size_and_resumes_numbering() {
+        let dir = scratch("rotation_size");
+        let path = dir.join("rec.csv");
+        let rotation = Rotation {
+            every: None,
+            max_bytes: Some(1),
+        };
+        let mut sink = RotatingSink::create(&path, RecordFormat::Csv, &info(), rotation).unwrap();
+        for n in 0..3 {
+            sink.write_frames(&frames(n * 10..n * 10 + 10)).unwrap();
+            sink.flush().unwrap();
+        }
+        drop(sink);
+        assert_eq!(
+            RecordingManifest::load(RecordingManifest::path_for(&path))
+                .unwrap()
+                .parts
+                .len(),
+            3
+        );
+
+        // A second acquisition numbers its samples from zero again.
+        let mut sink = RotatingSink::resume(&path, RecordFormat::Csv, &info(), rotation).unwrap();
+        sink.write_frames(&frames(0..10)).unwrap();
+        sink.flush().unwrap();
+        let part = &sink.manifest().parts[3];
+        assert_eq!(
+            (part.file.as_str(), part.first_sample),
+            ("rec_0004.csv", 30)
+        );
+        assert!(dir.join("rec_0004.csv").exists());
+
+        let mut faster = info();
+        faster.sampling_rate = 100;
+        assert!(RotatingSink::resume(&path, RecordFormat::Csv, &faster, rotation).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Sensor transfer functions: raw ADC counts to physical units.
+//!
+//! The formulas follow the PLUX sensor datasheets for BITalino blocks, with the
+//! 3.3 V supply and the channel's ADC resolution (10 bits for A1-A4, 6 bits for
+//! A5-A6 when more than four channels are acquired).
+
+/// BITalino supply voltage used by every transfer function.
+const VCC: f64 = 3.3;
+
+/// Sensor block attached to an analog input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sensor {
+    /// No conversion; values stay in ADC counts.
+    #[default]
+    Raw,
+    /// Electrocardiography, in millivolts.
+    Ecg,
+    /// Electromyography, in millivolts.
+    Emg,
+    /// Electrodermal activity, in microsiemens.
+    Eda,
+    /// Electroencephalography, in microvolts.
+    Eeg,
+    /// Electrooculography, in millivolts.
+    Eog,
+    /// Ambient light, in percent of full scale.
+    Lux,
+}
+
+impl Sensor {
+    /// Parse a sensor name as used in profiles and OpenSignals headers
+    /// (`"ECG"`, `"eda"`, ...). Unknown names map to [`Sensor::Raw`].
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_uppercase().as_str() {
+            "ECG" => Self::Ecg,
+            "EMG" => Self::Emg,
+            "EDA" => Self::Eda,
+            "EEG" => Self::Eeg,
+            "EOG" => Self::Eog,
+            "LUX" => Self::Lux,
+            _ => Self::Raw,
+        }
+    }
+
+    /// Upper-case name, as accepted by [`from_name`](Self::from_name).
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Raw => "RAW",
+            Self::Ecg => "ECG",
+            Self::Emg => "EMG",
+            Self::Eda => "EDA",
+            Self::Eeg => "EEG",
+            Self::Eog => "EOG",
+            Self::Lux => "LUX",
+        }
+    }
+
+    /// Unit of [`to_physical`](Self::to_physical) results.
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Raw => "ADC",
+            Self::Ecg | Self::Emg | Self::Eog => "mV",
+            Self::Eda => "uS",
+            Self::Eeg => "uV",
+            Self::Lux => "%",
+        }
+    }
+
+    /// Convert a raw sample read with a `bits`-bit ADC.
+    pub fn to_physical(self, raw: u16, bits: u8) -> f64 {
+        let ratio = raw as f64 / (1u32 << bits) as f64;
+        match self {
+            Self::Raw => raw as f64,
+            Self::Ecg => (ratio - 0.5) * VCC / 1100.0 * 1e3,
+            Self::Emg => (ratio - 0.5) * VCC / 1009.0 * 1e3,
+            Self::Eda => ratio * VCC / 0.132,
+            Self::Eeg => (ratio - 0.5) * VCC / 41782.0 * 1e6,
+            Self::Eog => (ratio - 0.5) * VCC / 2040.0 * 1e3,
+            Self::Lux => ratio * 100.0,
+        }
+    }
+}
+
+/// ADC resolution of each analog value, by position within a frame: with
+/// five or six channels, the fifth and sixth values are sent with 6 bits.
+pub(crate) const RESOLUTION_BITS: [u8; 6] = [10, 10, 10, 10, 6, 6];
+
+/// ADC resolution of the analog value at `position` within a frame.
+pub fn resolution_bits(position: usize) -> u8 {
+    RESOLUTION_BITS.get(position).copied().unwrap_or(6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_functions_match_datasheet_values() {
+        assert_eq!(Sensor::from_name("ecg"), Sensor::Ecg);
+        assert_eq!(Sensor::from_name("PZT"), Sensor::Raw);
+        assert_eq!(Sensor::Ecg.to_physical(512, 10), 0.0);
+        assert!((Sensor::Ecg.to_physical(1023, 10) - 1.4971).abs() < 1e-4);
+        assert!((Sensor::Eda.to_physical(1023, 10) - 24.976).abs() < 1e-3);
+        assert_eq!(Sensor::Lux.to_physical(32, 6), 50.0);
+        assert_eq!(Sensor::Raw.to_physical(700, 10), 700.0);
+    }
+}
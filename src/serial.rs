@@ -0,0 +1,319 @@
+//! Serial port settings for BITalinos behind USB-serial adapters or bound
+//! RFCOMM nodes.
+//!
+//! The BITalino UART runs at 115200 baud, 8N1, without flow control, which is
+//! all [`Bitalino::connect_serial`](crate::Bitalino::connect_serial) assumes.
+//! Some adapters need more: another rate configured on their Bluetooth
+//! module, hardware flow control, or DTR/RTS driven to a given level (or
+//! pulsed) before the module answers. [`SerialConfig`] carries those settings
+//! to [`Bitalino::connect_serial_with`](crate::Bitalino::connect_serial_with),
+//! which can also probe the common rates until the device answers `version()`
+//! with a BITalino version string rather than garbage.
+//!
+//! [`Bitalino::list_serial_ports`](crate::Bitalino::list_serial_ports) lists
+//! the ports present on the host, marking the ones likely to lead to a
+//! BITalino: bound RFCOMM nodes and Bluetooth ports, ports whose name or USB
+//! descriptors mention BITalino or PLUX, and the USB-UART bridges usually
+//! wired to its UART.
+
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use log::debug;
+use serialport::SerialPort;
+
+use crate::bitalino::{BAUD_RATE, DEFAULT_TIMEOUT};
+
+/// Baud rates tried when probing, most common first.
+pub const PROBE_BAUD_RATES: [u32; 6] = [115_200, 230_400, 57_600, 38_400, 19_200, 9_600];
+
+/// I/O timeout while probing, so a silent rate is given up on quickly.
+pub(crate) const PROBE_IO_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// USB vendor/product IDs of the USB-UART bridges usually wired to the
+/// BITalino UART: FTDI FT232R, Silicon Labs CP210x, WCH CH340 and Prolific
+/// PL2303.
+const BITALINO_USB_IDS: [(u16, u16); 4] = [
+    (0x0403, 0x6001),
+    (0x10c4, 0xea60),
+    (0x1a86, 0x7523),
+    (0x067b, 0x2303),
+];
+
+/// Names that give a BITalino away in a port path or USB descriptor.
+const BITALINO_PORT_NAMES: [&str; 2] = ["bitalino", "plux"];
+
+/// Serial flow control.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlowControl {
+    /// No flow control (BITalino default).
+    #[default]
+    Off,
+    /// XON/XOFF.
+    Software,
+    /// RTS/CTS.
+    Hardware,
+}
+
+impl FlowControl {
+    /// Parse the Python spelling (`"none"`, `"software"`, `"hardware"`).
+    #[allow(dead_code)]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(FlowControl::Off),
+            "software" => Some(FlowControl::Software),
+            "hardware" => Some(FlowControl::Hardware),
+            _ => None,
+        }
+    }
+}
+
+impl From<FlowControl> for serialport::FlowControl {
+    fn from(flow: FlowControl) -> Self {
+        match flow {
+            FlowControl::Off => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        }
+    }
+}
+
+/// How to open a serial link to a BITalino.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialConfig {
+    /// Baud rate, tried first when probing.
+    pub baud_rate: u32,
+    /// Flow control.
+    pub flow_control: FlowControl,
+    /// Level DTR is driven to after opening; `None` leaves the driver's.
+    pub dtr: Option<bool>,
+    /// Level RTS is driven to after opening; `None` leaves the driver's. With
+    /// hardware flow control the driver owns RTS, so leave it unset.
+    pub rts: Option<bool>,
+    /// Hold DTR and RTS low for this long after opening, then raise them (or
+    /// set `dtr`/`rts`), for adapters that reset or wake the module on the
+    /// edge.
+    pub reset_pulse: Option<Duration>,
+    /// Query `version()` and, until the reply names a BITalino, reopen at the
+    /// other [`PROBE_BAUD_RATES`]. Off by default: the query stops an
+    /// acquisition left running on the link.
+    pub probe_baud_rates: bool,
+    /// Read and write timeout.
+    pub timeout: Duration,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            baud_rate: BAUD_RATE,
+            flow_control: FlowControl::Off,
+            dtr: None,
+            rts: None,
+            reset_pulse: None,
+            probe_baud_rates: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl SerialConfig {
+    /// Rates to open the port at, in order: `baud_rate`, then the other
+    /// [`PROBE_BAUD_RATES`] when probing.
+    pub(crate) fn candidate_rates(&self) -> Vec<u32> {
+        let mut rates = vec![self.baud_rate];
+        if self.probe_baud_rates {
+            rates.extend(PROBE_BAUD_RATES.iter().filter(|&&r| r != self.baud_rate));
+        }
+        rates
+    }
+
+    /// Open `path` at `baud_rate` and set up the control lines.
+    pub(crate) fn open(&self, path: &str, baud_rate: u32) -> Result<Box<dyn SerialPort>> {
+        let mut port = serialport::new(path, baud_rate)
+            .timeout(self.timeout)
+            .flow_control(self.flow_control.into())
+            .open()
+            .with_context(|| format!("Failed to open serial port at {}", path))?;
+        if let Some(pulse) = self.reset_pulse {
+            debug!("Pulsing DTR/RTS low for {:?} on {}", pulse, path);
+            port.write_data_terminal_ready(false)?;
+            port.write_request_to_send(false)?;
+            thread::sleep(pulse);
+            port.write_data_terminal_ready(self.dtr.unwrap_or(true))?;
+            port.write_request_to_send(self.rts.unwrap_or(true))?;
+        } else {
+            if let Some(level) = self.dtr {
+                port.write_data_terminal_ready(level)?;
+            }
+            if let Some(level) = self.rts {
+                port.write_request_to_send(level)?;
+            }
+        }
+        Ok(port)
+    }
+}
+
+/// How a serial port is attached to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortKind {
+    /// USB-serial adapter.
+    Usb,
+    /// Bluetooth serial port, such as a bound `/dev/rfcommN` node.
+    Bluetooth,
+    /// PCI serial card.
+    Pci,
+    /// Anything else (on-board UARTs, or not reported by the OS).
+    Other,
+}
+
+impl PortKind {
+    /// Lower-case name, as shown by `bitalino ports` and in Python.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PortKind::Usb => "usb",
+            PortKind::Bluetooth => "bluetooth",
+            PortKind::Pci => "pci",
+            PortKind::Other => "other",
+        }
+    }
+}
+
+/// A serial port present on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialPortEntry {
+    /// Path to open, e.g. `/dev/ttyUSB0`, `/dev/rfcomm0` or `COM3`.
+    pub path: String,
+    /// How the port is attached.
+    pub kind: PortKind,
+    /// USB vendor ID, for USB ports.
+    pub vid: Option<u16>,
+    /// USB product ID, for USB ports.
+    pub pid: Option<u16>,
+    /// USB manufacturer string, if the adapter reports one.
+    pub manufacturer: Option<String>,
+    /// USB product string, if the adapter reports one.
+    pub product: Option<String>,
+    /// USB serial number, if the adapter reports one.
+    pub serial_number: Option<String>,
+}
+
+impl SerialPortEntry {
+    /// Whether the port likely leads to a BITalino: a Bluetooth port, a name
+    /// or USB descriptor mentioning BITalino or PLUX, or a USB-UART bridge
+    /// usually wired to its UART.
+    pub fn is_bitalino(&self) -> bool {
+        let named = |text: &str| {
+            let text = text.to_ascii_lowercase();
+            BITALINO_PORT_NAMES.iter().any(|name| text.contains(name))
+        };
+        self.kind == PortKind::Bluetooth
+            || named(&self.path)
+            || [&self.manufacturer, &self.product]
+                .into_iter()
+                .flatten()
+                .any(|text| named(text))
+            || self
+                .vid
+                .zip(self.pid)
+                .is_some_and(|id| BITALINO_USB_IDS.contains(&id))
+    }
+}
+
+impl From<serialport::SerialPortInfo> for SerialPortEntry {
+    fn from(info: serialport::SerialPortInfo) -> Self {
+        let mut entry = SerialPortEntry {
+            path: info.port_name,
+            kind: PortKind::Other,
+            vid: None,
+            pid: None,
+            manufacturer: None,
+            product: None,
+            serial_number: None,
+        };
+        match info.port_type {
+            serialport::SerialPortType::UsbPort(usb) => {
+                entry.kind = PortKind::Usb;
+                entry.vid = Some(usb.vid);
+                entry.pid = Some(usb.pid);
+                entry.manufacturer = usb.manufacturer;
+                entry.product = usb.product;
+                entry.serial_number = usb.serial_number;
+            }
+            serialport::SerialPortType::BluetoothPort => entry.kind = PortKind::Bluetooth,
+            serialport::SerialPortType::PciPort => entry.kind = PortKind::Pci,
+            serialport::SerialPortType::Unknown => {}
+        }
+        entry
+    }
+}
+
+/// Serial ports present on the host, likely BITalinos first, then by path.
+pub(crate) fn list_ports() -> Result<Vec<SerialPortEntry>> {
+    let mut ports: Vec<SerialPortEntry> = serialport::available_ports()
+        .context("Failed to enumerate serial ports")?
+        .into_iter()
+        .map(SerialPortEntry::from)
+        .collect();
+    ports.sort_by(|a, b| {
+        b.is_bitalino()
+            .cmp(&a.is_bitalino())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(ports)
+}
+
+/// Whether a `version()` reply comes from a BITalino rather than a link
+/// running at the wrong rate.
+pub(crate) fn is_bitalino_version(reply: &str) -> bool {
+    reply.to_ascii_lowercase().contains("bitalino")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probes_the_configured_rate_first() {
+        let config = SerialConfig::default();
+        assert_eq!(config.candidate_rates(), [115_200]);
+
+        let config = SerialConfig {
+            baud_rate: 57_600,
+            probe_baud_rates: true,
+            ..SerialConfig::default()
+        };
+        assert_eq!(
+            config.candidate_rates(),
+            [57_600, 115_200, 230_400, 38_400, 19_200, 9_600]
+        );
+
+        assert!(is_bitalino_version("BITalino_v5.2"));
+        assert!(!is_bitalino_version("\u{fffd}x\u{fffd}v1.0"));
+        assert_eq!(
+            FlowControl::from_name("hardware"),
+            Some(FlowControl::Hardware)
+        );
+        assert_eq!(FlowControl::from_name("rts"), None);
+    }
+
+    #[test]
+    fn flags_ports_that_look_like_a_bitalino() {
+        let port =
+            |path: &str, kind, ids: Option<(u16, u16)>, product: Option<&str>| SerialPortEntry {
+                path: path.into(),
+                kind,
+                vid: ids.map(|(vid, _)| vid),
+                pid: ids.map(|(_, pid)| pid),
+                manufacturer: None,
+                product: product.map(Into::into),
+                serial_number: None,
+            };
+        assert!(port("/dev/rfcomm0", PortKind::Bluetooth, None, None).is_bitalino());
+        assert!(port("/dev/cu.BITalino-3D-4F", PortKind::Other, None, None).is_bitalino());
+        assert!(port("/dev/ttyUSB0", PortKind::Usb, Some((0x0403, 0x6001)), None).is_bitalino());
+        assert!(port("COM4", PortKind::Usb, Some((0x1234, 1)), Some("PLUX hub")).is_bitalino());
+        assert!(!port("/dev/ttyACM0", PortKind::Usb, Some((0x2341, 0x0043)), None).is_bitalino());
+        assert!(!port("/dev/ttyS0", PortKind::Other, None, None).is_bitalino());
+    }
+}
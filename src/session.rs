@@ -0,0 +1,322 @@
+//! Recording sessions: everything known about one acquisition.
+//!
+//! A [`Session`] gathers what a recording needs beyond its frames: the start
+//! time, device identity and channel configuration written into file headers
+//! (its [`RecordingInfo`]), plus the annotations, output changes and
+//! cumulative statistics collected until it ends. Open one with
+//! [`Bitalino::begin_session`](crate::Bitalino::begin_session) once
+//! acquisition has started and close it with
+//! [`Bitalino::end_session`](crate::Bitalino::end_session); exporters write the
+//! header from [`info`](Session::info) and append the rest through
+//! [`FrameSink::write_session`](crate::FrameSink::write_session).
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, ensure, Result};
+use serde_json::{json, Value};
+
+use crate::bitalino::Frame;
+use crate::config::Profile;
+use crate::events::{Annotation, OutputCommand, OutputEvent};
+use crate::recording::{utc_iso8601, ChannelPort, RecordingInfo};
+use crate::stats::AcquisitionStats;
+use crate::sync::{rebase, rising_edge, StartOffset};
+
+/// One acquisition, from `begin_session()` to `end_session()`.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Start time, device identity and channel configuration.
+    pub info: RecordingInfo,
+    /// Markers placed with `annotate()` during the acquisition.
+    pub annotations: Vec<Annotation>,
+    /// Output changes made with `trigger()`, `trigger_pulse()` or `pwm()`.
+    pub output_events: Vec<OutputEvent>,
+    /// Totals for the acquisition as of `end_session()`; `None` while open.
+    pub stats: Option<AcquisitionStats>,
+    /// When the session ended; `None` while open.
+    pub ended: Option<SystemTime>,
+}
+
+impl Session {
+    /// Open session for the acquisition described by `info`.
+    pub fn new(info: RecordingInfo) -> Self {
+        Self {
+            info,
+            annotations: Vec::new(),
+            output_events: Vec::new(),
+            stats: None,
+            ended: None,
+        }
+    }
+
+    /// Whether the session has not ended yet.
+    #[allow(dead_code)]
+    pub fn is_open(&self) -> bool {
+        self.ended.is_none()
+    }
+
+    /// Label the acquired channels with the sensors and ports of `profile`.
+    pub fn apply_profile(&mut self, profile: &Profile) -> &mut Self {
+        let channels = &self.info.channels;
+        self.info.sensors = channels
+            .iter()
+            .map(|&c| profile.sensor(c).unwrap_or("RAW").to_string())
+            .collect();
+        self.info.ports = channels
+            .iter()
+            .map(|&c| {
+                profile.port_on(c).map(|(label, port)| ChannelPort {
+                    label: label.to_string(),
+                    description: port.description.clone(),
+                })
+            })
+            .collect();
+        self
+    }
+
+    /// Line up the recordings of several devices on a pulse they all saw.
+    ///
+    /// Finds the first rising edge on digital `channel` (an index into
+    /// [`Frame::digital`], 0 = I1) in each stream of frames and rebases it so
+    /// that the edge is sample 0 everywhere: earlier frames, annotations and
+    /// output events are dropped, later ones count from the edge, and
+    /// `info.started` moves to the edge. `sessions[i]` describes `streams[i]`.
+    ///
+    /// Returns each device's start offset relative to the first one, as found
+    /// from the edges. Its uncertainty is what remains after aligning: an edge
+    /// is only seen at the next sample, so up to one sample period of the
+    /// slower of the two devices.
+    ///
+    /// # Errors
+    /// Fails if the slices differ in length or a stream has no rising edge on
+    /// `channel`; nothing is changed then.
+    #[allow(dead_code)]
+    pub fn align_on_digital_edge(
+        sessions: &mut [Session],
+        streams: &mut [Vec<Frame>],
+        channel: u8,
+    ) -> Result<Vec<StartOffset>> {
+        ensure!(
+            sessions.len() == streams.len(),
+            "{} sessions for {} streams",
+            sessions.len(),
+            streams.len()
+        );
+        ensure!(
+            usize::from(channel) < 4,
+            "Digital channel {channel} is out of range (0-3)"
+        );
+        let edges = streams
+            .iter()
+            .enumerate()
+            .map(|(i, frames)| {
+                rising_edge(frames, usize::from(channel)).ok_or_else(|| {
+                    anyhow!("No rising edge on digital channel {channel} in stream {i}")
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let period = |session: &Session| 1.0 / f64::from(session.info.sampling_rate.max(1));
+        let edge_secs = |i: usize| edges[i] as f64 * period(&sessions[i]);
+
+        let offsets = (0..sessions.len())
+            .map(|i| StartOffset {
+                // A device that started later reached the edge sooner.
+                offset_secs: edge_secs(0) - edge_secs(i),
+                uncertainty_secs: Some(if i == 0 {
+                    0.0
+                } else {
+                    period(&sessions[0]).max(period(&sessions[i]))
+                }),
+            })
+            .collect();
+
+        for ((session, frames), &edge) in sessions.iter_mut().zip(streams.iter_mut()).zip(&edges) {
+            rebase(frames, edge);
+            let shift = Duration::from_secs_f64(edge as f64 * period(session));
+            let shift_us = shift.as_micros() as u64;
+            session.info.started += shift;
+            session.annotations.retain(|a| a.sample_index >= edge);
+            for annotation in &mut session.annotations {
+                annotation.sample_index -= edge;
+                annotation.timestamp_us = annotation.timestamp_us.saturating_sub(shift_us);
+            }
+            session.output_events.retain(|e| e.sample_index >= edge);
+            for event in &mut session.output_events {
+                event.sample_index -= edge;
+                event.timestamp_us = event.timestamp_us.saturating_sub(shift_us);
+            }
+        }
+        Ok(offsets)
+    }
+
+    /// The whole session as one JSON object, as exporters append it.
+    pub fn to_json(&self) -> Value {
+        let info = &self.info;
+        let annotations: Vec<Value> = self
+            .annotations
+            .iter()
+            .map(|a| {
+                json!({
+                    "label": a.label,
+                    "sample": a.sample_index,
+                    "timestamp_us": a.timestamp_us,
+                })
+            })
+            .collect();
+        let output_events: Vec<Value> = self
+            .output_events
+            .iter()
+            .map(|e| {
+                let mut event = match e.command {
+                    OutputCommand::Digital(levels) => json!({"digital": levels}),
+                    OutputCommand::Pwm(value) => json!({"pwm": value}),
+                };
+                event["sample"] = json!(e.sample_index);
+                event["timestamp_us"] = json!(e.timestamp_us);
+                event
+            })
+            .collect();
+        let stats = self.stats.as_ref().map(|s| {
+            json!({
+                "frames": s.frames,
+                "bytes_read": s.bytes_read,
+                "crc_errors": s.crc_errors,
+                "sequence_gaps": s.sequence_gaps,
+                "dropped_samples": s.dropped_samples,
+                "elapsed_secs": s.elapsed.as_secs_f64(),
+                "throughput_hz": s.throughput_hz,
+                "clock_skew_secs": s.clock_skew_secs,
+            })
+        });
+        json!({
+            "device": info.mac,
+            "firmware": info.firmware_version,
+            "bitalino2": info.is_bitalino2,
+            "connection": info.connection.as_str(),
+            "started": utc_iso8601(info.started),
+            "ended": self.ended.map(utc_iso8601),
+            "sampling_rate": info.sampling_rate,
+            "channels": info.channels.iter().map(|c| format!("A{}", c + 1)).collect::<Vec<_>>(),
+            "sensors": info.sensors,
+            "annotations": annotations,
+            "output_events": output_events,
+            "stats": stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitalino::ConnectionKind;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn serializes_annotations_events_and_stats() {
+        let mut session = Session::new(RecordingInfo {
+            mac: "20:16:10:00:3D:4F".into(),
+            sampling_rate: 1000,
+            channels: vec![0, 1],
+            sensors: Vec::new(),
+            ports: Vec::new(),
+            is_bitalino2: true,
+            firmware_version: Some("BITalino_v5.2".into()),
+            connection: ConnectionKind::Bluetooth,
+            started: UNIX_EPOCH + Duration::from_secs(1_709_211_909),
+        });
+        let profile = Profile::from_toml_str("[sensors]\nA2 = \"ECG\"\n").unwrap();
+        session.apply_profile(&profile);
+        assert_eq!(session.info.sensors, ["RAW", "ECG"]);
+        session.annotations.push(Annotation {
+            label: "stimulus".into(),
+            sample_index: 1500,
+            timestamp_us: 1_500_000,
+        });
+        session.output_events.push(OutputEvent {
+            command: OutputCommand::Pwm(128),
+            sample_index: 2000,
+            timestamp_us: 2_000_000,
+        });
+        assert!(session.is_open());
+        session.ended = Some(session.info.started + Duration::from_secs(60));
+
+        let json = session.to_json();
+        assert_eq!(json["started"], "2024-02-29T13:05:09.000Z");
+        assert_eq!(json["ended"], "2024-02-29T13:06:09.000Z");
+        assert_eq!(json["channels"], json!(["A1", "A2"]));
+        assert_eq!(json["annotations"][0]["sample"], 1500);
+        assert_eq!(
+            json["output_events"][0],
+            json!({"pwm": 128, "sample": 2000, "timestamp_us": 2_000_000})
+        );
+        assert_eq!(json["stats"], Value::Null);
+    }
+
+    #[test]
+    fn aligns_streams_on_their_first_rising_edge() {
+        let session = |rate: u16| {
+            Session::new(RecordingInfo {
+                mac: String::new(),
+                sampling_rate: rate,
+                channels: vec![0],
+                sensors: Vec::new(),
+                ports: Vec::new(),
+                is_bitalino2: true,
+                firmware_version: None,
+                connection: ConnectionKind::Serial,
+                started: UNIX_EPOCH + Duration::from_secs(100),
+            })
+        };
+        // I2 rises at sample 250 of the first device and sample 230 of the
+        // second, which started 20 ms later.
+        let stream = |edge: u64| -> Vec<Frame> {
+            (0..400)
+                .map(|n| {
+                    let mut frame =
+                        Frame::new((n % 16) as u8, [0, u8::from(n >= edge), 0, 0], &[512]);
+                    frame.sample_index = n;
+                    frame
+                })
+                .collect()
+        };
+        let mut sessions = [session(1000), session(1000)];
+        sessions[1].annotations.push(Annotation {
+            label: "before".into(),
+            sample_index: 100,
+            timestamp_us: 100_000,
+        });
+        sessions[1].annotations.push(Annotation {
+            label: "after".into(),
+            sample_index: 300,
+            timestamp_us: 300_000,
+        });
+        sessions[1].output_events.push(OutputEvent {
+            command: OutputCommand::Pwm(128),
+            sample_index: 260,
+            timestamp_us: 260_000,
+        });
+        let mut streams = [stream(250), stream(230)];
+
+        assert!(Session::align_on_digital_edge(&mut sessions, &mut streams, 0).is_err());
+        assert_eq!(streams[0].len(), 400, "a failed search changes nothing");
+
+        let offsets = Session::align_on_digital_edge(&mut sessions, &mut streams, 1).unwrap();
+        assert_eq!(offsets[0].offset_secs, 0.0);
+        assert!((offsets[1].offset_secs - 0.020).abs() < 1e-9);
+        assert_eq!(offsets[1].uncertainty_secs, Some(0.001));
+
+        assert_eq!(streams[0][0].sample_index, 0);
+        assert_eq!(streams[0].len(), 150);
+        assert_eq!(streams[1].len(), 170);
+        assert_eq!(streams[1][0].digital[1], 1);
+        assert_eq!(sessions[1].annotations.len(), 1);
+        assert_eq!(sessions[1].annotations[0].sample_index, 70);
+        assert_eq!(sessions[1].annotations[0].timestamp_us, 70_000);
+        assert_eq!(sessions[1].output_events[0].sample_index, 30);
+        assert_eq!(sessions[1].output_events[0].timestamp_us, 30_000);
+        assert_eq!(
+            sessions[0].info.started,
+            UNIX_EPOCH + Duration::from_millis(100_250)
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! A driver handle shared between threads.
+//!
+//! Services often control a device from one thread (start/stop, triggers,
+//! annotations from a UI or an RPC handler) while another thread reads frames.
+//! [`SharedBitalino`] wraps the driver in an `Arc<Mutex<_>>` so every clone
+//! reaches the same connection and calls never interleave on the link: each
+//! command runs to completion before the next, and a read holds the device
+//! until its batch is complete. Keep reads short (about 100 ms of frames) so
+//! commands from other threads are not held up behind them.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use anyhow::Result;
+
+use crate::bitalino::{Bitalino, DeviceState, FrameBatch, SamplingRate};
+use crate::events::Annotation;
+use crate::stats::AcquisitionStats;
+
+/// Cloneable, thread-safe handle to one [`Bitalino`].
+///
+/// The driver is closed (and acquisition stopped) when the last handle is
+/// dropped. A thread that panics while holding the device does not poison it
+/// for the others.
+#[derive(Clone)]
+pub struct SharedBitalino {
+    device: Arc<Mutex<Bitalino>>,
+}
+
+impl SharedBitalino {
+    /// Share a connected driver.
+    pub fn new(device: Bitalino) -> Self {
+        Self {
+            device: Arc::new(Mutex::new(device)),
+        }
+    }
+
+    /// Exclusive access to the driver until the guard is dropped, for
+    /// sequences of calls that must not be interleaved with other threads.
+    pub fn lock(&self) -> MutexGuard<'_, Bitalino> {
+        self.device.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run `f` with exclusive access to the driver.
+    pub fn with<T>(&self, f: impl FnOnce(&mut Bitalino) -> T) -> T {
+        f(&mut self.lock())
+    }
+
+    /// Take the driver back once this is the last handle; otherwise the
+    /// handle is returned unchanged.
+    pub fn into_inner(self) -> std::result::Result<Bitalino, Self> {
+        match Arc::try_unwrap(self.device) {
+            Ok(device) => Ok(device.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(device) => Err(Self { device }),
+        }
+    }
+
+    /// See [`Bitalino::version`].
+    pub fn version(&self) -> Result<String> {
+        self.lock().version()
+    }
+
+    /// See [`Bitalino::start`].
+    pub fn start(&self, sampling_rate: u16, channels: Vec<u8>) -> Result<SamplingRate> {
+        self.lock().start(sampling_rate, channels)
+    }
+
+    /// See [`Bitalino::stop`].
+    pub fn stop(&self) -> Result<()> {
+        self.lock().stop()
+    }
+
+    /// See [`Bitalino::is_acquiring`].
+    pub fn is_acquiring(&self) -> bool {
+        self.lock().is_acquiring()
+    }
+
+    /// See [`Bitalino::read_frames_timed`].
+    pub fn read_frames_timed(&self, n_frames: usize) -> Result<FrameBatch> {
+        self.lock().read_frames_timed(n_frames)
+    }
+
+    /// See [`Bitalino::state`].
+    pub fn state(&self) -> Result<DeviceState> {
+        self.lock().state()
+    }
+
+    /// See [`Bitalino::trigger`].
+    pub fn trigger(&self, outputs: &[u8]) -> Result<()> {
+        self.lock().trigger(outputs)
+    }
+
+    /// See [`Bitalino::pwm`].
+    pub fn pwm(&self, value: u8) -> Result<()> {
+        self.lock().pwm(value)
+    }
+
+    /// See [`Bitalino::annotate`].
+    pub fn annotate(&self, label: impl Into<String>) -> Result<Annotation> {
+        self.lock().annotate(label)
+    }
+
+    /// See [`Bitalino::stats`].
+    pub fn stats(&self) -> AcquisitionStats {
+        self.lock().stats()
+    }
+}
+
+impl From<Bitalino> for SharedBitalino {
+    fn from(device: Bitalino) -> Self {
+        Self::new(device)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::thread;
+
+    #[test]
+    fn clones_reach_the_same_driver() {
+        let shared =
+            SharedBitalino::new(Bitalino::from_transport(Box::new(Cursor::new(Vec::new()))));
+        let control = shared.clone();
+        let worker = thread::spawn(move || {
+            control.with(|device| device.set_device_label("rig-1"));
+            control.annotate("too early").is_err()
+        });
+        assert!(worker.join().unwrap());
+        assert_eq!(shared.lock().device_label(), "rig-1");
+        assert!(!shared.is_acquiring());
+
+        let other = shared.clone();
+        let Err(shared) = shared.into_inner() else {
+            panic!("another handle is still alive");
+        };
+        drop(other);
+        assert!(shared.into_inner().is_ok());
+    }
+}
@@ -0,0 +1,366 @@
+//! Continuous acquisition on a background thread.
+//!
+//! [`BackgroundReader`] takes ownership of an acquiring [`Bitalino`], reads
+//! fixed-size batches in a loop and hands each one to a callback. Stopping the
+//! reader returns the driver, so the caller can keep using the connection (or
+//! stop the device) afterwards.
+//!
+//! Any number of consumers can also [`subscribe`](Bitalino::subscribe) to the
+//! batches a driver reads: each gets its own channel fed by the single read
+//! loop, so a recorder, a live plot and an event detector never compete for
+//! frames.
+//!
+//! Consumers slower than the device sit behind a bounded queue (see
+//! [`Pipeline`](crate::Pipeline)) whose [`OverflowPolicy`] decides what gives
+//! when it fills up.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::bitalino::{device_log, Bitalino, FrameBatch, ReadOptions};
+use crate::errors::DriverError;
+
+/// Longest a single read blocks, bounding how late a stop request is noticed.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A transformation applied to each batch on its way to a subscriber (see
+/// [`Bitalino::subscribe_through`]).
+///
+/// Stages keep state across batches (filter memories, decimation phase), so
+/// one instance must see a single continuous stream. Implementations live in
+/// [`dsp`](crate::dsp).
+pub trait Stage: Send {
+    /// Transform `batch`. Returning a batch without frames skips delivery.
+    fn process(&mut self, batch: FrameBatch) -> FrameBatch;
+}
+
+struct Subscriber {
+    tx: Sender<FrameBatch>,
+    stage: Option<Box<dyn Stage>>,
+}
+
+/// Channels receiving a copy of every batch a driver reads.
+///
+/// Cloning shares the subscriber list, so a handle taken before the driver
+/// moves to another thread keeps accepting subscriptions.
+#[derive(Clone, Default)]
+pub(crate) struct Fanout(Arc<Mutex<Vec<Subscriber>>>);
+
+impl Fanout {
+    /// Register a new consumer, optionally behind a processing stage.
+    pub(crate) fn subscribe(&self, stage: Option<Box<dyn Stage>>) -> Receiver<FrameBatch> {
+        let (tx, rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Subscriber { tx, stage });
+        rx
+    }
+
+    /// Send `batch` to every consumer, forgetting those that hung up.
+    pub(crate) fn publish(&self, batch: &FrameBatch) {
+        let mut subscribers = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        subscribers.retain_mut(|subscriber| {
+            let batch = match subscriber.stage.as_mut() {
+                Some(stage) => stage.process(batch.clone()),
+                None => batch.clone(),
+            };
+            batch.frames.is_empty() || subscriber.tx.send(batch).is_ok()
+        });
+    }
+}
+
+/// What a bounded batch queue does with a batch that arrives while it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Pause reading until the consumer catches up. The queue loses nothing,
+    /// but a long pause overflows the device's buffer and frames are lost on
+    /// the link instead (reported as sequence gaps).
+    #[default]
+    Block,
+    /// Discard the oldest queued batch, keeping the consumer close to live.
+    DropOldest,
+    /// Discard the arriving batch.
+    DropNewest,
+    /// Stop reading with a [`DriverError::Overflow`].
+    Error,
+}
+
+impl OverflowPolicy {
+    /// Parse the Python spelling (`"block"`, `"drop_oldest"`, `"drop_newest"`,
+    /// `"error"`).
+    #[allow(dead_code)]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "block" => Some(OverflowPolicy::Block),
+            "drop_oldest" => Some(OverflowPolicy::DropOldest),
+            "drop_newest" => Some(OverflowPolicy::DropNewest),
+            "error" => Some(OverflowPolicy::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Handle to a thread streaming batches out of a [`Bitalino`].
+///
+/// Dropping the handle asks the thread to stop without waiting for it; the
+/// driver is then dropped on the reader thread, which stops the acquisition.
+pub struct BackgroundReader {
+    stop: Arc<AtomicBool>,
+    subscribers: Fanout,
+    handle: Option<JoinHandle<(Bitalino, Result<()>)>>,
+}
+
+impl BackgroundReader {
+    /// Read `batch_size`-frame batches from `device` until stopped, passing each
+    /// to `on_batch` on the reader thread.
+    ///
+    /// Batches may be shorter than `batch_size` only when the reader is
+    /// stopped, which flushes the frames collected so far.
+    ///
+    /// # Errors
+    /// Fails if acquisition is not running or the thread cannot be spawned; the
+    /// driver is returned with the error.
+    #[allow(dead_code)]
+    pub fn spawn<F>(
+        device: Bitalino,
+        batch_size: usize,
+        mut on_batch: F,
+    ) -> std::result::Result<Self, (Box<Bitalino>, anyhow::Error)>
+    where
+        F: FnMut(FrameBatch) + Send + 'static,
+    {
+        Self::spawn_fallible(device, batch_size, move |batch| {
+            on_batch(batch);
+            Ok(())
+        })
+    }
+
+    /// [`spawn`](Self::spawn) with a callback that can end the loop by
+    /// returning an error, which `stop()` then reports.
+    pub(crate) fn spawn_fallible<F>(
+        device: Bitalino,
+        batch_size: usize,
+        mut on_batch: F,
+    ) -> std::result::Result<Self, (Box<Bitalino>, anyhow::Error)>
+    where
+        F: FnMut(FrameBatch) -> Result<()> + Send + 'static,
+    {
+        if !device.is_acquiring() {
+            let err =
+                DriverError::NotAcquiring("Acquisition not started. Call start() first.".into());
+            return Err((Box::new(device), err.into()));
+        }
+        let batch_size = batch_size.max(1);
+        let subscribers = device.subscribers().clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = Arc::clone(&stop);
+
+        // The driver comes back through the channel if spawning fails.
+        let (tx, rx) = mpsc::sync_channel(1);
+        let spawned = thread::Builder::new()
+            .name("bitalino-reader".into())
+            .spawn(move || {
+                let mut device = rx.recv().expect("driver is sent right after spawning");
+                let result = read_loop(&mut device, batch_size, &stop_flag, &mut on_batch);
+                (device, result)
+            });
+        match spawned {
+            Ok(handle) => {
+                let _ = tx.send(device);
+                Ok(Self {
+                    stop,
+                    subscribers,
+                    handle: Some(handle),
+                })
+            }
+            Err(e) => Err((Box::new(device), e.into())),
+        }
+    }
+
+    /// Receive every batch the reader thread reads; see [`Bitalino::subscribe`].
+    #[allow(dead_code)]
+    pub fn subscribe(&self) -> Receiver<FrameBatch> {
+        self.subscribers.subscribe(None)
+    }
+
+    /// Receive every batch the reader thread reads, transformed by `stage`;
+    /// see [`Bitalino::subscribe_through`].
+    #[allow(dead_code)]
+    pub fn subscribe_through<S: Stage + 'static>(&self, stage: S) -> Receiver<FrameBatch> {
+        self.subscribers.subscribe(Some(Box::new(stage)))
+    }
+
+    /// Whether the reader thread is still running (it exits on a read error).
+    #[allow(dead_code)]
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_finished())
+    }
+
+    /// Stop reading, wait for the thread and return the driver.
+    ///
+    /// The second element is the error that ended the loop early, if any.
+    /// Acquisition keeps running on the device.
+    #[allow(dead_code)]
+    pub fn stop(mut self) -> (Bitalino, Result<()>) {
+        self.stop.store(true, Ordering::Relaxed);
+        let handle = self.handle.take().expect("reader joined twice");
+        handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+impl Drop for BackgroundReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn read_loop<F: FnMut(FrameBatch) -> Result<()>>(
+    device: &mut Bitalino,
+    batch_size: usize,
+    stop: &AtomicBool,
+    on_batch: &mut F,
+) -> Result<()> {
+    let options = ReadOptions {
+        timeout: Some(POLL_INTERVAL),
+        allow_partial: true,
+        ..ReadOptions::default()
+    };
+    let mut pending: Option<FrameBatch> = None;
+    while !stop.load(Ordering::Relaxed) {
+        let wanted = batch_size - pending.as_ref().map_or(0, |b| b.frames.len());
+        let batch = match device.read_frames_timed_with(wanted, &options) {
+            Ok(batch) => batch,
+            Err(e) => {
+                device_log!(
+                    debug,
+                    device.device_label(),
+                    "background reader stopping: {e:#}"
+                );
+                if let Some(rest) = pending.filter(|b| !b.frames.is_empty()) {
+                    let _ = on_batch(rest);
+                }
+                return Err(e);
+            }
+        };
+        let merged = match pending.take() {
+            Some(mut acc) => {
+                acc.append(batch);
+                acc
+            }
+            None => batch,
+        };
+        if merged.frames.len() >= batch_size {
+            on_batch(FrameBatch {
+                partial: false,
+                ..merged
+            })?;
+        } else {
+            pending = Some(merged);
+        }
+    }
+    match pending.filter(|b| !b.frames.is_empty()) {
+        Some(rest) => on_batch(rest),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::Stage;
+    use crate::bitalino::{Bitalino, FrameBatch};
+    use crate::replay::ReplayTransport;
+
+    /// One-channel frame (A1 = 1023, seq 3) with a valid CRC.
+    pub(crate) const FRAME: [u8; 3] = [0xFC, 0xBF, 0x3F];
+
+    /// Driver acquiring A1 from a capture of `frames` copies of [`FRAME`];
+    /// reading past them fails like a closed link.
+    pub(crate) fn acquiring_replay(frames: usize) -> Bitalino {
+        let capture = FRAME.repeat(frames);
+        let mut device = Bitalino::from_transport(Box::new(ReplayTransport::from_bytes(capture)));
+        device.start(1000, vec![0]).unwrap();
+        device
+    }
+
+    /// Passes on only the first frame of each batch.
+    pub(crate) struct KeepFirst;
+
+    impl Stage for KeepFirst {
+        fn process(&mut self, mut batch: FrameBatch) -> FrameBatch {
+            batch.frames.truncate(1);
+            batch
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayTransport;
+    use crate::streaming::fixtures::{acquiring_replay, KeepFirst};
+    use std::sync::mpsc;
+
+    #[test]
+    fn streams_full_batches_and_returns_the_driver() {
+        assert!(BackgroundReader::spawn(
+            Bitalino::from_transport(Box::new(ReplayTransport::from_bytes(Vec::new()))),
+            10,
+            |_| {}
+        )
+        .is_err());
+        let device = acquiring_replay(25);
+
+        let (tx, rx) = mpsc::channel();
+        let reader = BackgroundReader::spawn(device, 10, move |batch| {
+            let _ = tx.send(batch.frames.len());
+        })
+        .map_err(|(_, e)| e)
+        .unwrap();
+        assert_eq!(rx.recv().unwrap(), 10);
+        assert_eq!(rx.recv().unwrap(), 10);
+
+        // The end of the capture reads as a closed link and ends the loop.
+        while reader.is_running() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let (device, result) = reader.stop();
+        assert!(result.is_err());
+        assert!(device.is_acquiring());
+        assert_eq!(rx.iter().collect::<Vec<_>>(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn subscribers_share_the_read_loop() {
+        let mut device = acquiring_replay(4);
+        let early = device.subscribe();
+        let halved = device.subscribe_through(KeepFirst);
+        let dropped = device.subscribe();
+        drop(dropped);
+
+        device.read_frames_timed(2).unwrap();
+        let reader = BackgroundReader::spawn(device, 2, |_| {})
+            .map_err(|(_, e)| e)
+            .unwrap();
+        let late = reader.subscribe();
+        while reader.is_running() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let (device, _) = reader.stop();
+        // `early`, `halved` and `late`; the dropped receiver was pruned on publish.
+        assert_eq!(device.subscribers().0.lock().unwrap().len(), 3);
+        drop(late);
+
+        let early: Vec<usize> = early.try_iter().map(|b| b.frames.len()).collect();
+        assert_eq!(early, [2, 2]);
+        let halved: Vec<usize> = halved.try_iter().map(|b| b.frames.len()).collect();
+        assert_eq!(halved, [1, 1]);
+    }
+}
@@ -0,0 +1,465 @@
+//! Start-time offsets between devices acquiring together.
+//!
+//! BITalino has no shared clock, so devices started one after another begin
+//! sampling at slightly different times: each start command is written a little
+//! later than the previous one and reaches its device after an unknown
+//! Bluetooth latency. [`OffsetEstimator`] bounds that latency NTP-style. It
+//! pings each idle device with state requests, keeps the shortest round trip,
+//! and assumes the device received its start command halfway through it. The
+//! start offsets are the differences between those arrival estimates, each
+//! accurate to within half of the round trips involved.
+//!
+//! When one device's digital output is wired to the others' inputs,
+//! [`TriggerSync`] does better: it pulses that output once every device is
+//! acquiring and finds the edge in each stream, which gives offsets exact to
+//! a sample and a common sample axis starting at the edge.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, ensure, Result};
+
+use crate::bitalino::{device_log, Bitalino, Frame, SamplingRate};
+
+/// How long [`TriggerSync`] reads each device for the trigger edge by default.
+#[allow(dead_code)]
+const TRIGGER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Estimated start of one device relative to the first device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartOffset {
+    /// Seconds this device started after the first one (negative if before).
+    pub offset_secs: f64,
+    /// Bound on the error of `offset_secs`, in seconds; `None` when the link
+    /// latency of this device or the first one was never measured.
+    pub uncertainty_secs: Option<f64>,
+}
+
+/// Measures link latencies and turns start-command times into offsets.
+///
+/// ```no_run
+/// # use bitalino_rs::{Bitalino, OffsetEstimator};
+/// # fn run(devices: &mut [Bitalino]) -> anyhow::Result<()> {
+/// let mut estimator = OffsetEstimator::new(5);
+/// estimator.calibrate(devices)?;
+/// for (i, offset) in estimator.start_all(devices, 1000, &[0, 1])?.iter().enumerate() {
+///     println!("device {i}: {:+.1} ms", offset.offset_secs * 1e3);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct OffsetEstimator {
+    pings: usize,
+    /// Shortest round trip seen per device, in device order.
+    round_trips: Vec<Option<Duration>>,
+}
+
+#[allow(dead_code)]
+impl OffsetEstimator {
+    /// Estimator sending `pings` state requests (at least 1) per device and
+    /// calibration.
+    pub fn new(pings: usize) -> Self {
+        Self {
+            pings: pings.max(1),
+            round_trips: Vec::new(),
+        }
+    }
+
+    /// Ping every idle device, keeping the shortest round trip seen so far.
+    ///
+    /// Call again between acquisitions to tighten the bounds. Devices without
+    /// the state command (BITalino 1.0, or not yet identified with
+    /// `version()`) are skipped and get no uncertainty bound.
+    ///
+    /// # Errors
+    /// Fails if a device is acquiring or a ping times out.
+    pub fn calibrate(&mut self, devices: &mut [Bitalino]) -> Result<()> {
+        self.round_trips.resize(devices.len(), None);
+        for (i, device) in devices.iter_mut().enumerate() {
+            if !device.is_bitalino2() {
+                device_log!(
+                    debug,
+                    device.device_label(),
+                    "No state command, latency unknown"
+                );
+                continue;
+            }
+            for _ in 0..self.pings {
+                let rtt = device.ping()?;
+                let best = self.round_trips[i].map_or(rtt, |best| best.min(rtt));
+                self.round_trips[i] = Some(best);
+            }
+            device_log!(
+                debug,
+                device.device_label(),
+                "Round trip: {:?}",
+                self.round_trips[i]
+            );
+        }
+        Ok(())
+    }
+
+    /// Shortest round trip measured for each device, in device order.
+    pub fn round_trips(&self) -> &[Option<Duration>] {
+        &self.round_trips
+    }
+
+    /// Start each device in turn and estimate how far apart they started.
+    ///
+    /// # Errors
+    /// Fails if any device fails to start; devices started before it keep
+    /// acquiring.
+    pub fn start_all(
+        &self,
+        devices: &mut [Bitalino],
+        sampling_rate: u16,
+        channels: &[u8],
+    ) -> Result<Vec<StartOffset>> {
+        let rate = SamplingRate::from_u16_checked(sampling_rate)?;
+        for device in devices.iter_mut() {
+            device.start_with_rate(rate, channels.to_vec())?;
+        }
+        self.offsets(devices)
+    }
+
+    /// Offsets of devices that are already acquiring, from when each start
+    /// command was written.
+    ///
+    /// # Errors
+    /// Fails if a device was never started.
+    pub fn offsets(&self, devices: &[Bitalino]) -> Result<Vec<StartOffset>> {
+        let sent = devices
+            .iter()
+            .enumerate()
+            .map(|(i, device)| {
+                device
+                    .start_command_sent()
+                    .ok_or_else(|| anyhow::anyhow!("Device {i} was not started"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let offsets = estimate(&sent, &self.round_trips);
+        for (device, offset) in devices.iter().zip(&offsets) {
+            device_log!(
+                info,
+                device.device_label(),
+                "Start offset: {:+.3} ms (±{})",
+                offset.offset_secs * 1e3,
+                offset
+                    .uncertainty_secs
+                    .map_or_else(|| "?".to_string(), |u| format!("{:.3} ms", u * 1e3))
+            );
+        }
+        Ok(offsets)
+    }
+}
+
+/// Synchronized start of devices sharing a digital trigger line.
+///
+/// One device, the source, has a digital output wired to a digital input of
+/// every other device. [`start_all`](Self::start_all) starts them all, pulses
+/// the output and reads each stream until the rising edge, on the input of
+/// the receivers and on the source's own output channel, which BITalino 2.0
+/// frames carry. The edge happened at the same instant everywhere, so the
+/// samples it falls on line the streams up exactly.
+///
+/// ```no_run
+/// # use bitalino_rs::{Bitalino, TriggerSync};
+/// # fn run(devices: &mut [Bitalino]) -> anyhow::Result<()> {
+/// // O1 of the first device is wired to I1 of the others.
+/// let alignment = TriggerSync::new(0, 0, 0).start_all(devices, 1000, &[0, 1])?;
+/// for (i, device) in devices.iter_mut().enumerate() {
+///     let mut frames = device.read_frames(100)?;
+///     alignment.align(i, &mut frames);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct TriggerSync {
+    source: usize,
+    output: usize,
+    input: usize,
+    timeout: Duration,
+}
+
+#[allow(dead_code)]
+impl TriggerSync {
+    /// Trigger sent on `output` (0 = O1, 1 = O2) of device `source` and
+    /// received on `input` (0 = I1, 1 = I2) of every other device.
+    pub fn new(source: usize, output: u8, input: u8) -> Self {
+        Self {
+            source,
+            output: usize::from(output),
+            input: usize::from(input),
+            timeout: TRIGGER_TIMEOUT,
+        }
+    }
+
+    /// Give up if a device shows no edge within `timeout` of the pulse
+    /// (default: 2 s).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Start each device in turn, fire the trigger and align the streams on
+    /// its rising edge.
+    ///
+    /// Frames read while waiting for the edge are returned in the alignment
+    /// rather than lost.
+    ///
+    /// # Errors
+    /// Fails if the wiring is out of range, the source is not a BITalino 2.0,
+    /// a device fails to start or to read, the trigger line is already high,
+    /// or a device sees no edge within the timeout. Devices keep acquiring.
+    pub fn start_all(
+        &self,
+        devices: &mut [Bitalino],
+        sampling_rate: u16,
+        channels: &[u8],
+    ) -> Result<TriggerAlignment> {
+        let rate = SamplingRate::from_u16_checked(sampling_rate)?;
+        ensure!(
+            self.source < devices.len(),
+            "Trigger source {} is not one of the {} devices",
+            self.source,
+            devices.len()
+        );
+        ensure!(
+            self.output < 2 && self.input < 2,
+            "Trigger output and input must be 0 or 1 (O1/O2, I1/I2)"
+        );
+        ensure!(
+            devices[self.source].is_bitalino2(),
+            "Trigger source {} must be a BITalino 2.0, whose frames carry its output levels",
+            self.source
+        );
+        for device in devices.iter_mut() {
+            device.start_with_rate(rate, channels.to_vec())?;
+        }
+
+        // A few frames from each device establish that the line is low.
+        let baseline = (rate as usize / 20).max(1);
+        let mut frames = Vec::with_capacity(devices.len());
+        for (i, device) in devices.iter_mut().enumerate() {
+            let batch = device.read_frames(baseline)?;
+            if batch.iter().any(|f| f.digital[self.channel(i)] != 0) {
+                bail!("Trigger line is already high on device {i}");
+            }
+            frames.push(batch);
+        }
+
+        // Long enough to be sampled at any rate.
+        let width = Duration::from_micros(2 * rate.period_us()).max(Duration::from_millis(20));
+        let mut pulse = [0, 0];
+        pulse[self.output] = 1;
+        devices[self.source].trigger_pulse(&pulse, width)?;
+        let fired = Instant::now();
+
+        let mut edge_samples = Vec::with_capacity(devices.len());
+        for (i, device) in devices.iter_mut().enumerate() {
+            let channel = self.channel(i);
+            let seen = &mut frames[i];
+            let edge = loop {
+                if let Some(edge) = rising_edge(seen, channel) {
+                    break edge;
+                }
+                if fired.elapsed() > self.timeout {
+                    bail!(
+                        "No trigger edge on device {i} within {:?}; check the wiring",
+                        self.timeout
+                    );
+                }
+                seen.extend(device.read_frames(baseline)?);
+            };
+            device_log!(
+                debug,
+                device.device_label(),
+                "Trigger edge at sample {}",
+                edge
+            );
+            edge_samples.push(edge);
+        }
+
+        let mut alignment = TriggerAlignment {
+            edge_samples,
+            frames: Vec::with_capacity(devices.len()),
+            rate,
+        };
+        for (i, mut batch) in frames.into_iter().enumerate() {
+            alignment.align(i, &mut batch);
+            alignment.frames.push(batch);
+        }
+        for (i, offset) in alignment.start_offsets().iter().enumerate() {
+            device_log!(
+                info,
+                devices[i].device_label(),
+                "Start offset: {:+.3} ms (trigger edge at sample {})",
+                offset.offset_secs * 1e3,
+                alignment.edge_samples[i]
+            );
+        }
+        Ok(alignment)
+    }
+
+    /// Digital channel the trigger shows up on for device `i`.
+    fn channel(&self, i: usize) -> usize {
+        if i == self.source {
+            2 + self.output
+        } else {
+            self.input
+        }
+    }
+}
+
+/// Result of [`TriggerSync::start_all`]: where the trigger edge fell in each
+/// stream.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct TriggerAlignment {
+    /// Sample index (since each device's `start()`) of the first frame with
+    /// the trigger line high, in device order.
+    pub edge_samples: Vec<u64>,
+    /// Frames from the edge on that were read while looking for it, already
+    /// aligned, in device order. Record these before reading further.
+    pub frames: Vec<Vec<Frame>>,
+    rate: SamplingRate,
+}
+
+#[allow(dead_code)]
+impl TriggerAlignment {
+    /// Start offsets relative to the first device, as from
+    /// [`OffsetEstimator`], accurate to one sample period.
+    pub fn start_offsets(&self) -> Vec<StartOffset> {
+        let period = self.rate.period_us() as f64 / 1e6;
+        let first = self.edge_samples.first().copied().unwrap_or_default();
+        self.edge_samples
+            .iter()
+            .enumerate()
+            .map(|(i, &edge)| StartOffset {
+                // A device that started later has fewer samples at the edge.
+                offset_secs: (first as f64 - edge as f64) * period,
+                uncertainty_secs: Some(if i == 0 { 0.0 } else { period }),
+            })
+            .collect()
+    }
+
+    /// Move frames read from `device` onto the common sample axis, which
+    /// starts at the trigger edge: frames before the edge are dropped and
+    /// `sample_index` counts from the edge on every device.
+    ///
+    /// # Panics
+    /// Panics if `device` is not an index into the started devices.
+    pub fn align(&self, device: usize, frames: &mut Vec<Frame>) {
+        rebase(frames, self.edge_samples[device]);
+    }
+}
+
+/// Drop the frames before sample `edge` and count the rest from it.
+pub(crate) fn rebase(frames: &mut Vec<Frame>, edge: u64) {
+    frames.retain(|f| f.sample_index >= edge);
+    for frame in frames.iter_mut() {
+        frame.sample_index -= edge;
+    }
+}
+
+/// Sample index of the first frame with digital `channel` high that follows
+/// one with it low.
+pub(crate) fn rising_edge(frames: &[Frame], channel: usize) -> Option<u64> {
+    frames
+        .windows(2)
+        .find(|pair| pair[0].digital[channel] == 0 && pair[1].digital[channel] != 0)
+        .map(|pair| pair[1].sample_index)
+}
+
+/// Offsets relative to the first device, taking each device to receive its
+/// start command half a round trip after it was sent.
+#[allow(dead_code)]
+fn estimate(sent: &[Instant], round_trips: &[Option<Duration>]) -> Vec<StartOffset> {
+    let rtt = |i: usize| round_trips.get(i).copied().flatten();
+    let Some(&origin) = sent.first() else {
+        return Vec::new();
+    };
+    let arrival = |i: usize| {
+        let latency = rtt(i).map_or(0.0, |rtt| rtt.as_secs_f64() / 2.0);
+        sent[i].saturating_duration_since(origin).as_secs_f64()
+            - origin.saturating_duration_since(sent[i]).as_secs_f64()
+            + latency
+    };
+    (0..sent.len())
+        .map(|i| StartOffset {
+            offset_secs: arrival(i) - arrival(0),
+            uncertainty_secs: match (rtt(0), rtt(i)) {
+                _ if i == 0 => Some(0.0),
+                (Some(first), Some(this)) => Some((first + this).as_secs_f64() / 2.0),
+                _ => None,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_edges_align_the_streams() {
+        // The line goes high at sample 7 of the first device, which started
+        // three samples before the second one.
+        let stream = |first: u64, edge: u64| -> Vec<Frame> {
+            (first..first + 10)
+                .map(|n| {
+                    let mut frame = Frame::new((n % 16) as u8, [u8::from(n >= edge), 0, 0, 0], &[]);
+                    frame.sample_index = n;
+                    frame
+                })
+                .collect()
+        };
+        let (a, mut b) = (stream(0, 7), stream(0, 4));
+        assert_eq!(rising_edge(&a, 0), Some(7));
+        assert_eq!(rising_edge(&a, 1), None);
+        // A line already high at the first frame is not an edge.
+        assert_eq!(rising_edge(&stream(5, 5), 0), None);
+
+        let alignment = TriggerAlignment {
+            edge_samples: vec![7, 4],
+            frames: Vec::new(),
+            rate: SamplingRate::Hz100,
+        };
+        let offsets = alignment.start_offsets();
+        assert_eq!(offsets[0].offset_secs, 0.0);
+        assert!((offsets[1].offset_secs - 0.03).abs() < 1e-9);
+        assert_eq!(offsets[1].uncertainty_secs, Some(0.01));
+
+        alignment.align(1, &mut b);
+        assert_eq!(b.len(), 6);
+        assert_eq!(b[0].sample_index, 0);
+        assert_eq!(b[0].digital[0], 1);
+    }
+
+    #[test]
+    fn offsets_use_half_the_round_trip() {
+        let t0 = Instant::now();
+        let sent = [
+            t0,
+            t0 + Duration::from_millis(10),
+            t0 + Duration::from_millis(15),
+        ];
+        let rtts = [
+            Some(Duration::from_millis(20)),
+            Some(Duration::from_millis(40)),
+            None,
+        ];
+        let offsets = estimate(&sent, &rtts);
+
+        assert_eq!(offsets[0].offset_secs, 0.0);
+        // Arrivals at 10 ms and 30 ms.
+        assert!((offsets[1].offset_secs - 0.020).abs() < 1e-9);
+        assert!((offsets[1].uncertainty_secs.unwrap() - 0.030).abs() < 1e-9);
+        // Unknown latency counts as none, without a bound.
+        assert!((offsets[2].offset_secs - 0.005).abs() < 1e-9);
+        assert_eq!(offsets[2].uncertainty_secs, None);
+    }
+}
@@ -0,0 +1,214 @@
+//! TCP transport for BITalinos proxied over the network.
+//!
+//! A device attached to another machine (e.g. a Raspberry Pi running `ser2net`
+//! in raw mode) appears as a plain byte stream on a TCP port. [`TcpTransport`]
+//! speaks that stream with the same timeout semantics as the RFCOMM path and
+//! transparently reconnects when the bridge drops the connection.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use log::{debug, warn};
+
+use crate::bitalino::{ConnectionKind, Transport};
+
+/// Default connect and I/O timeout, matching the RFCOMM connector.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reconnect attempts after the bridge drops the connection.
+const DEFAULT_RECONNECT_ATTEMPTS: u32 = 3;
+
+/// Base delay between reconnect attempts (multiplied by the attempt number).
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Byte stream to a BITalino behind a TCP serial bridge.
+pub struct TcpTransport {
+    addr: SocketAddr,
+    stream: TcpStream,
+    read_timeout: Duration,
+    io_timeout: Duration,
+    reconnect_attempts: u32,
+}
+
+impl TcpTransport {
+    /// Connect to `addr` (`"host:port"`) with the default 5 s timeout.
+    pub fn connect(addr: &str) -> io::Result<Self> {
+        Self::connect_timeout(addr, DEFAULT_TIMEOUT)
+    }
+
+    /// Connect to `addr` using `timeout` for the connection and for reads/writes.
+    pub fn connect_timeout(addr: &str, timeout: Duration) -> io::Result<Self> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("no address found for {addr}"),
+            )
+        })?;
+        let stream = open(addr, timeout, timeout)?;
+        debug!("TCP bridge connected: {}", addr);
+        Ok(Self {
+            addr,
+            stream,
+            read_timeout: timeout,
+            io_timeout: timeout,
+            reconnect_attempts: DEFAULT_RECONNECT_ATTEMPTS,
+        })
+    }
+
+    /// Set how many times a dropped connection is re-established before I/O
+    /// errors are surfaced (0 disables reconnecting).
+    #[allow(dead_code)]
+    pub fn with_reconnect_attempts(mut self, attempts: u32) -> Self {
+        self.reconnect_attempts = attempts;
+        self
+    }
+
+    /// Remote address of the bridge.
+    #[allow(dead_code)]
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Re-establish the connection after the bridge dropped it.
+    ///
+    /// On success the caller sees `Interrupted`, which the driver retries.
+    /// Bytes in flight when the link dropped are lost; the driver reports them
+    /// as sequence gaps.
+    fn reconnect(&mut self, cause: io::Error) -> io::Error {
+        for attempt in 1..=self.reconnect_attempts {
+            std::thread::sleep(RECONNECT_BACKOFF * attempt);
+            match open(self.addr, self.io_timeout, self.read_timeout) {
+                Ok(stream) => {
+                    warn!(
+                        "TCP bridge {} reconnected after: {} (attempt {})",
+                        self.addr, cause, attempt
+                    );
+                    self.stream = stream;
+                    #[cfg(feature = "metrics")]
+                    crate::health::record_reconnect(&self.addr.to_string());
+                    return io::Error::new(ErrorKind::Interrupted, "TCP bridge reconnected");
+                }
+                Err(e) => debug!("TCP reconnect attempt {} failed: {}", attempt, e),
+            }
+        }
+        cause
+    }
+}
+
+/// Open a stream with Nagle disabled so single-byte commands go out at once.
+fn open(addr: SocketAddr, io_timeout: Duration, read_timeout: Duration) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect_timeout(&addr, io_timeout)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(read_timeout))?;
+    stream.set_write_timeout(Some(io_timeout))?;
+    Ok(stream)
+}
+
+/// Whether `kind` means the peer is gone (as opposed to a timeout).
+fn is_disconnect(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+            | ErrorKind::NotConnected
+            | ErrorKind::UnexpectedEof
+    )
+}
+
+impl Read for TcpTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.stream.read(buf) {
+            Ok(0) if !buf.is_empty() => Err(self.reconnect(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "TCP bridge closed the connection",
+            ))),
+            Err(e) if is_disconnect(e.kind()) => Err(self.reconnect(e)),
+            other => other,
+        }
+    }
+}
+
+impl Write for TcpTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.stream.write(buf) {
+            Err(e) if is_disconnect(e.kind()) => {
+                let e = self.reconnect(e);
+                if e.kind() != ErrorKind::Interrupted {
+                    return Err(e);
+                }
+                self.stream.write(buf)
+            }
+            other => other,
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for TcpTransport {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        // A zero duration means "no timeout" to std; clamp like the RFCOMM path.
+        let timeout = timeout.max(Duration::from_millis(1));
+        self.read_timeout = timeout;
+        self.stream.set_read_timeout(Some(timeout))
+    }
+
+    fn set_write_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        let timeout = timeout.max(Duration::from_millis(1));
+        // Reconnects reuse it for the connect and write timeouts.
+        self.io_timeout = timeout;
+        self.stream.set_write_timeout(Some(timeout))
+    }
+
+    fn bytes_available(&mut self) -> io::Result<usize> {
+        let mut probe = [0u8; 4096];
+        self.stream.set_nonblocking(true)?;
+        let result = self.stream.peek(&mut probe);
+        self.stream.set_nonblocking(false)?;
+        match result {
+            Ok(n) => Ok(n),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn connection_kind(&self) -> ConnectionKind {
+        ConnectionKind::Tcp
+    }
+
+    /// Clone of the current stream; it does not follow reconnects, so a
+    /// schedule writing through it fails once the bridge drops.
+    fn try_clone_writer(&self) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(self.stream.try_clone()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn reconnects_when_bridge_drops_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            // First connection closes immediately; the second serves data.
+            drop(listener.accept().unwrap());
+            let (mut conn, _) = listener.accept().unwrap();
+            conn.write_all(&[0xAB]).unwrap();
+        });
+
+        let mut transport = TcpTransport::connect(&addr).unwrap();
+        let mut buf = [0u8; 1];
+        let err = transport.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        transport.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0xAB]);
+        server.join().unwrap();
+    }
+}
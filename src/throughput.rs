@@ -0,0 +1,140 @@
+//! Live frame-rate monitoring with a shortfall callback.
+//!
+//! A saturated link or radio interference shows up offline as sequence gaps
+//! and a `throughput_hz` below the nominal rate in
+//! [`AcquisitionStats`](crate::stats::AcquisitionStats). [`ThroughputMonitor`]
+//! reports it while streaming instead: the read path feeds it the number of
+//! valid frames in every batch, and it compares the frames received per second
+//! over a sliding window with the nominal sampling rate.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::bitalino::SamplingRate;
+
+/// Frame-rate shortfall delivered to the monitor callback.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThroughputWarning {
+    /// Valid frames received per second over the window.
+    pub received_hz: f64,
+    /// Nominal sampling rate in Hz.
+    pub expected_hz: f64,
+    /// Fraction of the nominal rate missing, `1 - received_hz / expected_hz`.
+    pub deficit: f64,
+    /// Wall-clock span the rate was measured over.
+    pub window: Duration,
+}
+
+/// Frame-rate watchdog driven by the read path.
+///
+/// The callback fires when the deficit over the last `window` exceeds
+/// `max_deficit`, at most once per `window` while the shortfall lasts.
+pub struct ThroughputMonitor {
+    window: Duration,
+    max_deficit: f64,
+    callback: Box<dyn FnMut(ThroughputWarning) + Send>,
+    expected_hz: f64,
+    /// Time and valid frame count of each read, oldest first; the first entry
+    /// is the reference point at or before the start of the window.
+    reads: VecDeque<(Instant, usize)>,
+    last_warning: Option<Instant>,
+}
+
+impl ThroughputMonitor {
+    /// Create a monitor that calls `callback` when fewer than
+    /// `1 - max_deficit` of the nominal frames arrive over `window`.
+    pub fn new<F>(window: Duration, max_deficit: f64, callback: F) -> Self
+    where
+        F: FnMut(ThroughputWarning) + Send + 'static,
+    {
+        Self {
+            window,
+            max_deficit,
+            callback: Box::new(callback),
+            expected_hz: SamplingRate::default() as u16 as f64,
+            reads: VecDeque::new(),
+            last_warning: None,
+        }
+    }
+
+    /// Start over for an acquisition at `rate`.
+    pub(crate) fn reset(&mut self, rate: SamplingRate) {
+        self.expected_hz = rate as u16 as f64;
+        self.reads.clear();
+        self.last_warning = None;
+    }
+
+    /// Record a read of `frames` valid frames finished at `now`, and fire the
+    /// callback if the window is complete and short of frames.
+    pub(crate) fn record(&mut self, now: Instant, frames: usize) -> Option<ThroughputWarning> {
+        self.reads.push_back((now, frames));
+        while self
+            .reads
+            .get(1)
+            .is_some_and(|&(at, _)| now.duration_since(at) >= self.window)
+        {
+            self.reads.pop_front();
+        }
+        let &(since, _) = self.reads.front()?;
+        let span = now.duration_since(since);
+        if span < self.window
+            || self
+                .last_warning
+                .is_some_and(|at| now.duration_since(at) < self.window)
+        {
+            return None;
+        }
+
+        // Frames of the reference read arrived before the window began.
+        let received: usize = self.reads.iter().skip(1).map(|&(_, n)| n).sum();
+        let received_hz = received as f64 / span.as_secs_f64();
+        let deficit = 1.0 - received_hz / self.expected_hz;
+        if deficit <= self.max_deficit {
+            return None;
+        }
+        self.last_warning = Some(now);
+        let warning = ThroughputWarning {
+            received_hz,
+            expected_hz: self.expected_hz,
+            deficit,
+            window: span,
+        };
+        (self.callback)(warning);
+        Some(warning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn warns_once_per_window_while_frames_are_missing() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let mut monitor = ThroughputMonitor::new(Duration::from_secs(1), 0.1, move |w| {
+            sink.lock().unwrap().push(w)
+        });
+        monitor.reset(SamplingRate::Hz100);
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+
+        // 10 frames every 100 ms: the nominal rate.
+        for i in 0..=20 {
+            assert_eq!(monitor.record(at(i * 100), 10), None);
+        }
+        // 8 frames every 100 ms: 20% short once the window is full of them.
+        let warnings: Vec<_> = (21..=40)
+            .filter_map(|i| monitor.record(at(i * 100), 8))
+            .collect();
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!((warnings[1].received_hz - 80.0).abs() < 1e-9);
+        assert!((warnings[1].deficit - 0.2).abs() < 1e-9);
+        assert_eq!(seen.lock().unwrap().len(), 2);
+
+        monitor.reset(SamplingRate::Hz100);
+        assert_eq!(monitor.record(at(5000), 0), None);
+        assert_eq!(monitor.record(at(5500), 0), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! Host-side clock-drift estimation for BITalino acquisitions.
+//!
+//! The device samples on its own crystal (~20 ppm accuracy), and the host only
+//! sees frames arriving over a jittery Bluetooth link. Over a multi-hour
+//! recording, assuming the nominal rate is exact accumulates seconds of error.
+//!
+//! [`TimingModel`] fits a line through `(samples received, host time)`
+//! observations. Bluetooth latency shifts individual points but not the slope,
+//! so the fitted slope converges on the device's true sampling period.
+
+use crate::bitalino::SamplingRate;
+
+/// Minimum observation span, in seconds of nominal device time, before a drift
+/// estimate is reported. Shorter spans are dominated by Bluetooth jitter.
+const MIN_DRIFT_SPAN_SECS: f64 = 10.0;
+
+/// Online estimate of the device crystal drift relative to the host clock.
+///
+/// Feed it with [`observe`](Self::observe) after each read; `Bitalino` does
+/// this automatically in `read_frames_timed()`.
+#[derive(Debug, Clone)]
+pub struct TimingModel {
+    nominal_rate: f64,
+    n: u64,
+    first_samples: u64,
+    last_samples: u64,
+    mean_samples: f64,
+    mean_host_secs: f64,
+    /// Sum of squared deviations of the sample counts.
+    m2_samples: f64,
+    /// Sum of co-deviations of sample counts and host time.
+    cov: f64,
+}
+
+impl TimingModel {
+    /// Create an empty model for the given nominal sampling rate.
+    pub fn new(rate: SamplingRate) -> Self {
+        Self {
+            nominal_rate: rate as u16 as f64,
+            n: 0,
+            first_samples: 0,
+            last_samples: 0,
+            mean_samples: 0.0,
+            mean_host_secs: 0.0,
+            m2_samples: 0.0,
+            cov: 0.0,
+        }
+    }
+
+    /// Record that `samples` frames had been produced by the device when the host
+    /// clock read `host_us` microseconds since acquisition start.
+    pub fn observe(&mut self, samples: u64, host_us: u64) {
+        let x = samples as f64;
+        let y = host_us as f64 / 1_000_000.0;
+
+        if self.n == 0 {
+            self.first_samples = samples;
+        }
+        self.last_samples = samples;
+        self.n += 1;
+
+        // Welford-style running covariance keeps precision over long recordings.
+        let dx = x - self.mean_samples;
+        self.mean_samples += dx / self.n as f64;
+        self.mean_host_secs += (y - self.mean_host_secs) / self.n as f64;
+        self.m2_samples += dx * (x - self.mean_samples);
+        self.cov += dx * (y - self.mean_host_secs);
+    }
+
+    /// Estimated true sampling rate of the device in Hz.
+    ///
+    /// Returns `None` until at least [`MIN_DRIFT_SPAN_SECS`] worth of samples
+    /// have been observed.
+    pub fn effective_rate_hz(&self) -> Option<f64> {
+        let span = self.last_samples.saturating_sub(self.first_samples) as f64;
+        if self.n < 2 || span < self.nominal_rate * MIN_DRIFT_SPAN_SECS {
+            return None;
+        }
+        if self.m2_samples <= 0.0 || self.cov <= 0.0 {
+            return None;
+        }
+        let period_secs = self.cov / self.m2_samples;
+        Some(1.0 / period_secs)
+    }
+
+    /// Estimated drift of the device crystal in parts per million.
+    ///
+    /// Positive values mean the device samples faster than nominal.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        self.effective_rate_hz()
+            .map(|rate| (rate / self.nominal_rate - 1.0) * 1_000_000.0)
+    }
+
+    /// Drift-corrected time of `sample_index`, in microseconds since acquisition
+    /// start.
+    ///
+    /// Falls back to the nominal rate until a drift estimate is available.
+    #[allow(dead_code)]
+    pub fn sample_time_us(&self, sample_index: u64) -> f64 {
+        let rate = self.effective_rate_hz().unwrap_or(self.nominal_rate);
+        sample_index as f64 / rate * 1_000_000.0
+    }
+
+    /// Drift-corrected index of the sample being acquired at `host_us`
+    /// microseconds since acquisition start (inverse of [`sample_time_us`](Self::sample_time_us)).
+    pub fn sample_index_at(&self, host_us: u64) -> u64 {
+        let rate = self.effective_rate_hz().unwrap_or(self.nominal_rate);
+        (host_us as f64 / 1_000_000.0 * rate).floor() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Simulate a device running `ppm` fast, read in 100-frame batches with a
+    /// repeating latency pattern on top.
+    fn simulate(ppm: f64, seconds: u64) -> TimingModel {
+        let mut model = TimingModel::new(SamplingRate::Hz1000);
+        let true_rate = 1000.0 * (1.0 + ppm / 1_000_000.0);
+        let mut samples = 0u64;
+        while samples < seconds * 1000 {
+            samples += 100;
+            let latency_us = [12_000.0, 35_000.0, 20_000.0, 48_000.0][(samples / 100 % 4) as usize];
+            let host_us = samples as f64 / true_rate * 1_000_000.0 + latency_us;
+            model.observe(samples, host_us as u64);
+        }
+        model
+    }
+
+    #[test]
+    fn no_estimate_before_minimum_span() {
+        let model = simulate(50.0, 5);
+        assert!(model.drift_ppm().is_none());
+        assert_eq!(model.sample_time_us(1000), 1_000_000.0);
+    }
+
+    #[test]
+    fn estimates_crystal_drift() {
+        let model = simulate(50.0, 3600);
+        let ppm = model.drift_ppm().unwrap();
+        assert!((ppm - 50.0).abs() < 1.0, "estimated {ppm} ppm");
+        // One hour at +50 ppm is 180 ms shorter than nominal.
+        let corrected = model.sample_time_us(3_600_000);
+        assert!(
+            (corrected - 3_599_820_009.0).abs() < 5_000.0,
+            "got {corrected}"
+        );
+    }
+}
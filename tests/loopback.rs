@@ -0,0 +1,286 @@
+//! End-to-end tests of the driver against a scripted fake BITalino.
+//!
+//! The fake runs on a thread at one end of a Unix socketpair and speaks the
+//! device protocol: it answers version and state queries, streams encoded
+//! frames in real time between start and stop, and can be scripted to send
+//! noise, corrupt frames or fall silent. The real `Bitalino` drives the other
+//! end through a small [`Transport`] wrapper.
+
+#![cfg(unix)]
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use bitalino_rs::decode::encode_frame;
+use bitalino_rs::{Bitalino, DriverError, Frame, ReadOptions, Transport};
+
+const VERSION: &str = "BITalino_v5.2";
+
+/// State reply values: A1..A6, battery, threshold and the digital byte.
+const STATE_ANALOG: [u16; 6] = [1, 20, 300, 1023, 512, 7];
+const STATE_BATTERY: u16 = 870;
+const STATE_THRESHOLD: u8 = 30;
+const STATE_DIGITAL: u8 = 0b1010_0000;
+
+/// How the fake device misbehaves.
+#[derive(Default)]
+struct Script {
+    /// Bytes sent as soon as the link is up, before any command.
+    noise: Vec<u8>,
+    /// Frame numbers sent with one bit flipped.
+    corrupt: Vec<u64>,
+    /// Stop sending frames after this many, as if the radio dropped out.
+    stall_after: Option<u64>,
+}
+
+/// Frame number `n` of an acquisition on `n_channels` channels.
+fn frame(n: u64, n_channels: usize) -> Frame {
+    // A5 and A6 are sent with 6 bits in 5- and 6-channel frames.
+    let analog: Vec<u16> = (0..n_channels)
+        .map(|ch| ((n * 7 + ch as u64 * 100) % if ch < 4 { 1024 } else { 64 }) as u16)
+        .collect();
+    let digital = [0, 1, 2, 3].map(|bit| (n >> bit) as u8 & 1);
+    Frame::new((n % 16) as u8, digital, &analog)
+}
+
+/// The 17-byte state reply of firmware 5.2, CRC included.
+fn state_reply() -> Vec<u8> {
+    let mut reply: Vec<u8> = STATE_ANALOG.iter().flat_map(|v| v.to_le_bytes()).collect();
+    reply.extend(STATE_BATTERY.to_le_bytes());
+    reply.extend([STATE_THRESHOLD, 0, STATE_DIGITAL]);
+    let mut crc = 0u8;
+    for &byte in &reply {
+        for bit in (0..8).rev() {
+            crc <<= 1;
+            if crc & 0x10 != 0 {
+                crc ^= 0x03;
+            }
+            crc ^= (byte >> bit) & 1;
+        }
+    }
+    *reply.last_mut().unwrap() |= crc & 0x0F;
+    reply
+}
+
+/// Run the fake device on `link` until the driver hangs up.
+fn fake_device(mut link: UnixStream, script: Script) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        link.set_read_timeout(Some(Duration::from_millis(2)))
+            .unwrap();
+        if link.write_all(&script.noise).is_err() {
+            return;
+        }
+        let mut rate = 1000u64;
+        // Channel count, start time and frames sent while acquiring.
+        let mut streaming: Option<(usize, Instant, u64)> = None;
+        let mut skip_next = false;
+        loop {
+            let mut cmd = [0u8; 1];
+            match link.read(&mut cmd) {
+                Ok(0) => return,
+                Ok(_) if std::mem::take(&mut skip_next) => {}
+                Ok(_) => {
+                    let reply = match cmd[0] {
+                        0x00 => {
+                            streaming = None;
+                            None
+                        }
+                        _ if streaming.is_some() => None,
+                        0x07 => Some(format!("{VERSION}\n").into_bytes()),
+                        0x0B => Some(state_reply()),
+                        // PWM: the next byte is the duty cycle.
+                        0xA3 => {
+                            skip_next = true;
+                            None
+                        }
+                        c if c & 0x3F == 0x03 => {
+                            rate = [1, 10, 100, 1000][(c >> 6) as usize];
+                            None
+                        }
+                        c if c & 0x03 == 0x01 => {
+                            let n_channels = (c >> 2).count_ones() as usize;
+                            streaming = Some((n_channels, Instant::now(), 0));
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(reply) = reply {
+                        if link.write_all(&reply).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(_) => return,
+            }
+
+            if let Some((n_channels, started, sent)) = streaming.as_mut() {
+                let due = (started.elapsed().as_secs_f64() * rate as f64) as u64;
+                let due = script.stall_after.map_or(due, |limit| due.min(limit));
+                let mut bytes = Vec::new();
+                for n in *sent..due {
+                    let mut encoded = encode_frame(&frame(n, *n_channels));
+                    if script.corrupt.contains(&n) {
+                        encoded[0] ^= 0x80;
+                    }
+                    bytes.extend(encoded);
+                }
+                *sent = due.max(*sent);
+                if link.write_all(&bytes).is_err() {
+                    return;
+                }
+            }
+        }
+    })
+}
+
+/// Driver end of the socketpair.
+struct Loopback(UnixStream);
+
+impl Read for Loopback {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Loopback {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Transport for Loopback {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.0
+            .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))
+    }
+
+    fn set_write_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.0
+            .set_write_timeout(Some(timeout.max(Duration::from_millis(1))))
+    }
+}
+
+/// A driver connected to a fake device following `script`.
+fn connect(script: Script) -> (Bitalino, JoinHandle<()>) {
+    let (host, device) = UnixStream::pair().unwrap();
+    let fake = fake_device(device, script);
+    let mut dev = Bitalino::from_transport(Box::new(Loopback(host)));
+    dev.set_io_timeout(Duration::from_millis(100)).unwrap();
+    (dev, fake)
+}
+
+/// Drop the driver and wait for the fake to see the hang-up.
+fn disconnect(dev: Bitalino, fake: JoinHandle<()>) {
+    drop(dev);
+    fake.join().unwrap();
+}
+
+#[test]
+fn streams_the_frames_the_device_sends() {
+    let (mut dev, fake) = connect(Script::default());
+    assert_eq!(dev.version().unwrap(), VERSION);
+
+    let channels = vec![0, 2, 3, 4, 5];
+    dev.start(1000, channels.clone()).unwrap();
+    let batch = dev.read_frames_timed(200).unwrap();
+    assert_eq!((batch.crc_errors, batch.sequence_gaps), (0, 0));
+    assert_eq!(batch.frames.len(), 200);
+    assert_eq!(batch.channels, channels);
+    for (n, got) in batch.frames.iter().enumerate() {
+        let mut expected = frame(n as u64, channels.len());
+        expected.sample_index = n as u64;
+        assert_eq!(*got, expected);
+    }
+
+    dev.stop().unwrap();
+    disconnect(dev, fake);
+}
+
+#[test]
+fn state_reply_is_decoded() {
+    let (mut dev, fake) = connect(Script::default());
+    dev.version().unwrap();
+
+    let state = dev.state().unwrap();
+    assert_eq!(state.analog, STATE_ANALOG);
+    assert_eq!(state.battery, STATE_BATTERY);
+    assert_eq!(state.battery_threshold, STATE_THRESHOLD);
+    assert_eq!(state.digital, [1, 0, 1, 0]);
+
+    disconnect(dev, fake);
+}
+
+#[test]
+fn noise_before_the_first_command_is_flushed() {
+    let (mut dev, fake) = connect(Script {
+        noise: (0..=255).collect(),
+        ..Script::default()
+    });
+    assert_eq!(dev.version().unwrap(), VERSION);
+
+    dev.start(1000, vec![0]).unwrap();
+    let batch = dev.read_frames_timed(50).unwrap();
+    assert_eq!((batch.frames.len(), batch.crc_errors), (50, 0));
+    assert_eq!(batch.frames[0].analog_values(), frame(0, 1).analog_values());
+
+    disconnect(dev, fake);
+}
+
+#[test]
+fn streaming_recovers_after_a_corrupted_frame() {
+    let (mut dev, fake) = connect(Script {
+        corrupt: vec![10],
+        ..Script::default()
+    });
+    dev.start(1000, vec![0, 1]).unwrap();
+
+    let batch = dev.read_frames_timed(50).unwrap();
+    assert_eq!(batch.crc_errors, 1);
+    assert_eq!(batch.sequence_gaps, 1);
+    assert_eq!(batch.frames.len(), 49);
+    // The frame after the corrupted one keeps its place in the acquisition.
+    assert_eq!(batch.frames[10].sample_index, 11);
+    assert_eq!(
+        batch.frames[10].analog_values(),
+        frame(11, 2).analog_values()
+    );
+
+    disconnect(dev, fake);
+}
+
+#[test]
+fn silent_device_times_out() {
+    let (mut dev, fake) = connect(Script {
+        stall_after: Some(20),
+        ..Script::default()
+    });
+    dev.start(1000, vec![0]).unwrap();
+
+    let partial = ReadOptions {
+        timeout: Some(Duration::from_millis(300)),
+        allow_partial: true,
+        ..ReadOptions::default()
+    };
+    let batch = dev.read_frames_timed_with(50, &partial).unwrap();
+    assert!(batch.partial);
+    assert_eq!(batch.frames.len(), 20);
+
+    let strict = ReadOptions {
+        allow_partial: false,
+        ..partial
+    };
+    let err = dev.read_frames_timed_with(10, &strict).unwrap_err();
+    assert!(
+        matches!(err.downcast_ref(), Some(DriverError::Timeout(_))),
+        "{err:#}"
+    );
+
+    disconnect(dev, fake);
+}
@@ -0,0 +1,15 @@
+//! The committed typing stub matches the one `build.rs` renders from the
+//! PyO3 bindings.
+
+#![cfg(feature = "python")]
+
+#[test]
+fn committed_stub_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/_bitalino_core.pyi"));
+    let committed = include_str!("../bitalino_rs/_bitalino_core.pyi");
+    assert!(
+        generated == committed,
+        "bitalino_rs/_bitalino_core.pyi is out of date; \
+         regenerate it with `BITALINO_REGEN_STUBS=1 cargo build`"
+    );
+}